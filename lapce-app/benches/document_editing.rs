@@ -0,0 +1,272 @@
+//! Latency of typing, scrolling and pasting in the documents of the editor:
+//! the time from an input to the text layouts of the viewport being ready to
+//! paint, on generated Rust files the size of real ones. Unlike the
+//! `core_editing` benchmark of `lapce-core`, it goes through `Document`, with
+//! its caches and styles, and lays the lines out. The painting isn't part of
+//! it.
+//!
+//! Run with `cargo bench -p lapce-app --bench document_editing`, optionally
+//! with a name to only run the scenarios containing it. It fails when a
+//! scenario takes longer than its budget, so that a regression is caught
+//! before it is released.
+
+use std::{
+    cell::Cell,
+    process::ExitCode,
+    rc::Rc,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use floem::reactive::{create_runtime, create_scope, create_signal, Scope};
+use lapce_app::{config::LapceConfig, doc::Document, find::Find};
+use lapce_core::{
+    buffer::rope_text::RopeText,
+    cursor::{Cursor, CursorMode},
+    editor::EditType,
+    language::LapceLanguage,
+    selection::Selection,
+};
+use lapce_rpc::proxy::ProxyRpcHandler;
+use lapce_xi_rope::Rope;
+
+/// How many lines the viewport shows
+const VIEWPORT_LINES: usize = 60;
+
+/// The sizes of the files, in lines
+const FILE_SIZES: [usize; 2] = [2_000, 20_000];
+
+/// What typing inserts, a character at a time
+const TYPED: &str = "let value = compute(&argument, 42); // a comment\n";
+
+/// How many lines are pasted at once
+const PASTED_LINES: usize = 5_000;
+
+/// The 95th percentile a scenario must stay under. A keystroke and a scroll
+/// have to fit in a frame.
+const TYPING_BUDGET: Duration = Duration::from_millis(16);
+const SCROLLING_BUDGET: Duration = Duration::from_millis(16);
+const PASTE_BUDGET: Duration = Duration::from_millis(300);
+
+/// A Rust file with the mix of items, comments and strings of real code
+fn rust_source(lines: usize) -> String {
+    let mut source = String::new();
+    let mut source_lines = 0;
+    let mut i = 0;
+    while source_lines < lines {
+        let item = format!(
+            r#"/// The item number {i}, documented like public items are
+#[derive(Clone, Debug, PartialEq)]
+pub struct Item{i} {{
+    pub name: String,
+    pub values: Vec<usize>,
+}}
+
+impl Item{i} {{
+    pub fn new(name: &str) -> Self {{
+        // Start empty, values are pushed later
+        Self {{
+            name: format!("item {{}} {i}", name),
+            values: Vec::with_capacity({i}),
+        }}
+    }}
+
+    pub fn total(&self) -> usize {{
+        self.values.iter().filter(|v| **v > {i}).sum()
+    }}
+}}
+
+"#
+        );
+        source_lines += item.lines().count();
+        source.push_str(&item);
+        i += 1;
+    }
+    source
+}
+
+/// A Rust document as an editor holds it, with the cursor in the middle
+struct Doc {
+    doc: Document,
+    cursor: Cursor,
+    config: Arc<LapceConfig>,
+}
+
+impl Doc {
+    fn new(cx: Scope, text: &str) -> Self {
+        let config = Arc::new(LapceConfig::default_for_test());
+        let (config_signal, _) = create_signal(cx, config.clone());
+        let mut doc = Document::new_local(
+            cx,
+            Find::new(cx),
+            ProxyRpcHandler::new(),
+            config_signal,
+        );
+        doc.set_language(LapceLanguage::Rust);
+        doc.reload(Rope::from(text), true);
+        let buffer = doc.buffer();
+        let offset = buffer.offset_of_line(buffer.num_lines() / 2);
+        let cursor =
+            Cursor::new(CursorMode::Insert(Selection::caret(offset)), None, None);
+        Self {
+            doc,
+            cursor,
+            config,
+        }
+    }
+
+    /// Lay out the lines of the viewport starting at the line, like painting
+    /// it does, giving how many lines were laid out
+    fn layout_viewport(&self, start: usize) -> usize {
+        let font_size = self.config.editor.font_size();
+        let end = (start + VIEWPORT_LINES).min(self.doc.buffer().num_lines());
+        for line in start..end {
+            self.doc.get_text_layout(line, font_size);
+        }
+        end - start
+    }
+
+    /// The first line of the viewport that keeps the cursor in the middle
+    fn cursor_viewport(&self) -> usize {
+        let line = self.doc.buffer().line_of_offset(self.cursor.offset());
+        line.saturating_sub(VIEWPORT_LINES / 2)
+    }
+}
+
+/// The time each run of a scenario took
+struct Timings {
+    name: String,
+    budget: Duration,
+    samples: Vec<Duration>,
+    /// How many lines were laid out, to show the work was done
+    laid_out: usize,
+}
+
+impl Timings {
+    fn new(name: String, budget: Duration) -> Self {
+        Self {
+            name,
+            budget,
+            samples: Vec::new(),
+            laid_out: 0,
+        }
+    }
+
+    fn time(&mut self, f: impl FnOnce() -> usize) {
+        let start = Instant::now();
+        let laid_out = f();
+        self.samples.push(start.elapsed());
+        self.laid_out += laid_out;
+    }
+
+    fn percentile(&self, percentile: usize) -> Duration {
+        let mut samples = self.samples.clone();
+        samples.sort();
+        let index = (samples.len() * percentile / 100).min(samples.len() - 1);
+        samples[index]
+    }
+
+    /// Print the timings, giving whether they're within the budget
+    fn report(&self) -> bool {
+        let p95 = self.percentile(95);
+        let within_budget = p95 <= self.budget;
+        println!(
+            "{:<20} median {:>10.3?}  p95 {:>10.3?}  max {:>10.3?}  ({} runs, {} lines laid out){}",
+            self.name,
+            self.percentile(50),
+            p95,
+            self.samples.iter().max().copied().unwrap_or_default(),
+            self.samples.len(),
+            self.laid_out,
+            if within_budget {
+                String::new()
+            } else {
+                format!("  OVER BUDGET of {:?}", self.budget)
+            },
+        );
+        within_budget
+    }
+}
+
+/// Type a line character by character in the middle of the file
+fn typing(cx: Scope, lines: usize) -> Timings {
+    let mut timings = Timings::new(format!("typing/{lines}"), TYPING_BUDGET);
+    let mut doc = Doc::new(cx, &rust_source(lines));
+    doc.layout_viewport(doc.cursor_viewport());
+    for c in TYPED.chars() {
+        timings.time(|| {
+            doc.doc
+                .do_insert(&mut doc.cursor, &c.to_string(), &doc.config);
+            doc.layout_viewport(doc.cursor_viewport())
+        });
+    }
+    timings
+}
+
+/// Scroll through the file a page at a time, laying out each page the first
+/// time it's shown
+fn scrolling(cx: Scope, lines: usize) -> Timings {
+    let mut timings = Timings::new(format!("scrolling/{lines}"), SCROLLING_BUDGET);
+    let doc = Doc::new(cx, &rust_source(lines));
+    for start in (0..doc.doc.buffer().num_lines()).step_by(VIEWPORT_LINES) {
+        timings.time(|| doc.layout_viewport(start));
+    }
+    timings
+}
+
+/// Paste a large piece of code in the middle of the file
+fn paste(cx: Scope, lines: usize) -> Timings {
+    let mut timings = Timings::new(format!("paste/{lines}"), PASTE_BUDGET);
+    let source = rust_source(lines);
+    let pasted = rust_source(PASTED_LINES);
+    for _ in 0..10 {
+        let mut doc = Doc::new(cx, &source);
+        doc.layout_viewport(doc.cursor_viewport());
+        timings.time(|| {
+            let selection = doc.cursor.edit_selection(doc.doc.buffer());
+            doc.doc
+                .do_raw_edit(&[(&selection, pasted.as_str())], EditType::Paste);
+            doc.layout_viewport(doc.cursor_viewport())
+        });
+    }
+    timings
+}
+
+fn main() -> ExitCode {
+    // Cargo passes `--bench`, anything else is a filter on the names
+    let filter = std::env::args().skip(1).find(|arg| !arg.starts_with("--"));
+    let scenarios: [(&str, fn(Scope, usize) -> Timings); 3] = [
+        ("typing", typing),
+        ("scrolling", scrolling),
+        ("paste", paste),
+    ];
+
+    let within_budget = Rc::new(Cell::new(true));
+    let runtime = create_runtime();
+    for (name, scenario) in scenarios {
+        for lines in FILE_SIZES {
+            let full_name = format!("{name}/{lines}");
+            if let Some(filter) = filter.as_ref() {
+                if !full_name.contains(filter.as_str()) {
+                    continue;
+                }
+            }
+            // Each scenario gets a scope of its own for the signals of its
+            // documents
+            let within_budget = within_budget.clone();
+            create_scope(runtime, move |cx| {
+                if !scenario(cx, lines).report() {
+                    within_budget.set(false);
+                }
+            })
+            .dispose();
+        }
+    }
+    runtime.dispose();
+
+    if within_budget.get() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
@@ -1,10 +1,13 @@
 #[cfg(target_os = "windows")]
 use std::os::windows::process::CommandExt;
 use std::{
+    collections::HashSet,
     io::{BufReader, Read, Write},
     ops::Range,
+    path::{Path, PathBuf},
     process::Stdio,
     sync::Arc,
+    time::Duration,
 };
 
 use anyhow::{anyhow, Result};
@@ -14,6 +17,8 @@
     cosmic_text::{Style as FontStyle, Weight},
     event::{Event, EventListener},
     ext_event::{create_ext_action, create_signal_from_channel},
+    id::Id,
+    menu::{Menu, MenuItem},
     peniko::{
         kurbo::{Point, Rect, Size},
         Color,
@@ -29,20 +34,31 @@
     },
     view::View,
     views::{
-        container, container_box, empty, label, list, scroll, stack, svg, tab,
-        virtual_list, Decorators, VirtualListDirection, VirtualListItemSize,
+        container, container_box, empty, label, list, rich_text, scroll, stack, svg,
+        tab, virtual_list, Decorators, VirtualListDirection, VirtualListItemSize,
         VirtualListVector,
     },
     window::WindowConfig,
     ViewContext,
 };
-use lapce_core::{directory::Directory, meta, mode::Mode};
+use lapce_core::{
+    directory::Directory,
+    indent::{detect_indent_issue, IndentIssue, IndentStyle},
+    meta,
+    mode::Mode,
+};
 use lapce_rpc::{
+    buffer::EncodingFallback,
     core::{CoreMessage, CoreNotification},
     file::PathObject,
+    plugin::VoltMetadata,
+    proxy::ProxyResponse,
     RpcMessage,
 };
-use lsp_types::{CompletionItemKind, DiagnosticSeverity};
+use lsp_types::{
+    CompletionItem, CompletionItemKind, DiagnosticSeverity, ParameterLabel,
+    SignatureInformation,
+};
 use notify::Watcher;
 use serde::{Deserialize, Serialize};
 use tracing::{error, metadata::LevelFilter, trace};
@@ -50,25 +66,29 @@
 
 use crate::{
     code_action::CodeActionStatus,
-    command::{InternalCommand, WindowCommand},
+    command::{InternalCommand, LapceWorkbenchCommand, WindowCommand},
+    commit_message,
+    completion::{CompletionData, CompletionFreshness, CompletionStatus},
     config::{
         color::LapceColor, icon::LapceIcons, watcher::ConfigWatcher, LapceConfig,
     },
     db::LapceDb,
     debug::RunDebugMode,
-    doc::DocContent,
+    doc::{DocContent, FileGone, MAX_UNMAPPABLE_CHARS},
     editor::{
         diff::{diff_show_more_section_view, DiffEditorData},
         location::{EditorLocation, EditorPosition},
         view::editor_container_view,
-        EditorData,
+        EditorData, EncodingCheck,
     },
     editor_tab::{EditorTabChild, EditorTabData},
+    file_explorer::{data::DeleteNotice, view::path_menu_entries},
     focus_text::focus_text,
     id::{DiffEditorId, EditorId, EditorTabId, SplitId},
     keypress::keymap::KeyMap,
     listener::Listener,
     main_split::{MainSplitData, SplitContent, SplitData, SplitDirection},
+    markdown::{parse_documentation, parse_markdown, MarkdownContent},
     palette::{
         item::{PaletteItem, PaletteItemContent},
         kind::PaletteKind,
@@ -78,12 +98,18 @@
         kind::PanelKind, position::PanelContainerPosition,
         view::panel_container_view,
     },
+    replay::{time_ago, ReplayData},
+    safe_mode::{self, SafeModePart},
     settings::settings_view,
+    shortcuts::ShortcutItem,
+    status_template::{file_variable, StatusTemplate},
     text_input::text_input,
+    timer::exec_after,
     title::title,
-    update::ReleaseInfo,
+    update::{ReleaseInfo, UpdateStatus},
+    welcome::welcome_page,
     window::{TabsInfo, WindowData, WindowInfo},
-    window_tab::{CommonData, Focus, WindowTabData},
+    window_tab::{CommonData, DragContent, Focus, WindowTabData, WorkProgress},
     workspace::{LapceWorkspace, LapceWorkspaceType},
 };
 
@@ -102,6 +128,14 @@ struct Cli {
     /// Manually set log level
     #[clap(short, long)]
     log_level: Option<String>,
+    /// Store config, plugins, sessions and caches next to the executable
+    /// instead of in the user profile
+    #[clap(long, action)]
+    portable: bool,
+    /// Start with the default settings and keymaps, no plugins and without
+    /// restoring the last session
+    #[clap(long, action)]
+    safe_mode: bool,
 
     /// Paths to file(s) and/or folder(s) to open.
     /// When path is a file (that exists or not),
@@ -120,6 +154,8 @@ pub struct AppInfo {
 #[derive(Clone)]
 pub enum AppCommand {
     SaveApp,
+    CheckForUpdates,
+    ReloadConfig,
 }
 
 #[derive(Clone)]
@@ -156,10 +192,97 @@ pub fn run_app_command(&self, cmd: AppCommand) {
                 let db: Arc<LapceDb> = use_context(self.scope).unwrap();
                 let _ = db.save_app(self);
             }
+            AppCommand::CheckForUpdates => {
+                let latest_release = self.latest_release;
+                let send = create_ext_action(
+                    self.scope,
+                    move |release: Option<ReleaseInfo>| {
+                        if let Some(release) = release {
+                            latest_release.set(Arc::new(Some(release)));
+                        }
+                    },
+                );
+                std::thread::spawn(
+                    move || match crate::update::get_latest_release() {
+                        Ok(release) => send(Some(release)),
+                        Err(err) => {
+                            error!("Failed to check for updates: {err}");
+                            send(None);
+                        }
+                    },
+                );
+            }
+            AppCommand::ReloadConfig => {
+                self.reload_config();
+            }
         }
     }
 }
 
+/// The title of the editor tab of the file, in the format of the settings
+fn tab_title(
+    config: &LapceConfig,
+    workspace: &LapceWorkspace,
+    path: &Path,
+    is_pristine: bool,
+) -> String {
+    let template = StatusTemplate::parse(&config.ui.tab_title_format);
+    if template.is_empty() {
+        return path
+            .file_name()
+            .unwrap_or_default()
+            .to_str()
+            .unwrap_or_default()
+            .to_string();
+    }
+    template.render(|name, _| {
+        file_variable(name, Some(path), workspace.path.as_deref(), is_pristine)
+    })
+}
+
+/// The file the child of an editor tab is showing, if any
+fn editor_tab_child_path(
+    child: &EditorTabChild,
+    editors: ReadSignal<im::HashMap<EditorId, RwSignal<EditorData>>>,
+    diff_editors: ReadSignal<im::HashMap<DiffEditorId, DiffEditorData>>,
+) -> Option<PathBuf> {
+    let doc = match child {
+        EditorTabChild::Editor(editor_id) => editors
+            .with_untracked(|editors| editors.get(editor_id).copied())?
+            .with_untracked(|editor| editor.view.doc),
+        EditorTabChild::DiffEditor(diff_editor_id) => diff_editors
+            .with_untracked(|diff_editors| {
+                diff_editors.get(diff_editor_id).map(|d| d.right)
+            })?
+            .with_untracked(|editor| editor.view.doc),
+        EditorTabChild::Settings(_) => return None,
+    };
+    doc.with_untracked(|doc| match &doc.content {
+        DocContent::File(path)
+        | DocContent::Notebook(path)
+        | DocContent::Archive(path) => Some(path.clone()),
+        _ => None,
+    })
+}
+
+fn show_tab_context_menu(
+    path: &Path,
+    internal_command: Listener<InternalCommand>,
+    id: Id,
+) {
+    let menu =
+        Menu::new("").entry(MenuItem::new("Reveal in File Explorer").action({
+            let path = path.to_path_buf();
+            move || {
+                internal_command.send(InternalCommand::RevealInFileExplorer {
+                    path: path.clone(),
+                })
+            }
+        }));
+    let menu = path_menu_entries(menu, path, internal_command);
+    id.show_context_menu(menu, Point::ZERO);
+}
+
 fn editor_tab_header(
     active_editor_tab: ReadSignal<Option<EditorTabId>>,
     editor_tab: RwSignal<EditorTabData>,
@@ -170,6 +293,8 @@ fn editor_tab_header(
     let focus = common.focus;
     let config = common.config;
     let internal_command = common.internal_command;
+    let dragging = common.dragging;
+    let workspace = common.workspace.clone();
 
     let items = move || {
         let editor_tab = editor_tab.get();
@@ -192,8 +317,14 @@ fn editor_tab_header(
     };
 
     let view_fn = move |(i, child): (RwSignal<usize>, EditorTabChild)| {
+        // A pinned tab shrinks to its icon
+        let is_pinned =
+            move || editor_tab.with(|editor_tab| editor_tab.is_pinned(i.get()));
         let local_child = child.clone();
         let child_for_close = child.clone();
+        let child_for_drag = child.clone();
+        let child_for_menu = child.clone();
+        let workspace = workspace.clone();
         let child_view = move || {
             #[derive(PartialEq)]
             struct Info {
@@ -205,6 +336,7 @@ struct Info {
             }
 
             let cx = ViewContext::get_current();
+            let workspace = workspace.clone();
             let info = match child {
                 EditorTabChild::Editor(editor_id) => {
                     create_memo(cx.scope, move |_| {
@@ -212,43 +344,84 @@ struct Info {
                         let editor_data =
                             editors.with(|editors| editors.get(&editor_id).cloned());
                         let path = if let Some(editor_data) = editor_data {
-                            let ((content, is_pristine), confirmed) = editor_data
-                                .with(|editor_data| {
+                            let ((content, is_pristine, symlink, gone), confirmed) =
+                                editor_data.with(|editor_data| {
                                     (
                                         editor_data.view.doc.with(|doc| {
                                             (
                                                 doc.content.clone(),
                                                 doc.buffer().is_pristine(),
+                                                doc.symlink.clone(),
+                                                doc.gone.clone(),
                                             )
                                         }),
                                         editor_data.confirmed,
                                     )
                                 });
                             match content {
-                                DocContent::File(path) => {
-                                    Some((path, confirmed, is_pristine))
-                                }
+                                DocContent::File(path)
+                                | DocContent::Notebook(path)
+                                | DocContent::Archive(path) => Some((
+                                    path,
+                                    confirmed,
+                                    is_pristine,
+                                    symlink,
+                                    gone,
+                                )),
                                 DocContent::Local => None,
                                 DocContent::History(_) => None,
+                                DocContent::SearchResults => {
+                                    return Info {
+                                        icon: config.ui_svg(LapceIcons::SEARCH),
+                                        color: Some(*config.get_color(
+                                            LapceColor::LAPCE_ICON_ACTIVE,
+                                        )),
+                                        path: "Search Results".to_string(),
+                                        confirmed: Some(confirmed),
+                                        is_pristine,
+                                    };
+                                }
                             }
                         } else {
                             None
                         };
                         let (icon, color, path, confirmed, is_pristine) = match path
                         {
-                            Some((path, confirmed, is_pritine)) => {
+                            Some((path, confirmed, is_pritine, symlink, gone)) => {
                                 let (svg, color) = config.file_svg(&path);
-                                (
-                                    svg,
-                                    color.cloned(),
-                                    path.file_name()
-                                        .unwrap_or_default()
-                                        .to_str()
-                                        .unwrap_or_default()
-                                        .to_string(),
-                                    confirmed,
-                                    is_pritine,
-                                )
+                                let mut title = tab_title(
+                                    &config, &workspace, &path, is_pritine,
+                                );
+                                // Show what the file was opened through
+                                if let Some(symlink) = symlink {
+                                    let symlink = workspace
+                                        .path
+                                        .as_deref()
+                                        .and_then(|w| symlink.strip_prefix(w).ok())
+                                        .unwrap_or(&symlink);
+                                    title = format!(
+                                        "{title} ← {}",
+                                        symlink.to_string_lossy()
+                                    );
+                                }
+                                match gone {
+                                    Some(FileGone::Deleted) => {
+                                        title = format!("{title} (deleted)");
+                                    }
+                                    Some(FileGone::Renamed(to)) => {
+                                        let to = workspace
+                                            .path
+                                            .as_deref()
+                                            .and_then(|w| to.strip_prefix(w).ok())
+                                            .unwrap_or(&to);
+                                        title = format!(
+                                            "{title} (renamed to {})",
+                                            to.to_string_lossy()
+                                        );
+                                    }
+                                    None => {}
+                                }
+                                (svg, color.cloned(), title, confirmed, is_pritine)
                             }
                             None => (
                                 config.ui_svg(LapceIcons::FILE),
@@ -292,6 +465,9 @@ struct Info {
                                 DocContent::File(path) => Some((path, is_pristine)),
                                 DocContent::Local => None,
                                 DocContent::History(_) => None,
+                                DocContent::SearchResults => None,
+                                DocContent::Notebook(_) => None,
+                                DocContent::Archive(_) => None,
                             }
                         } else {
                             None
@@ -304,10 +480,9 @@ struct Info {
                                     color.cloned(),
                                     format!(
                                         "{} (Diff)",
-                                        path.file_name()
-                                            .unwrap_or_default()
-                                            .to_str()
-                                            .unwrap_or_default()
+                                        tab_title(
+                                            &config, &workspace, &path, is_pritine,
+                                        )
                                     ),
                                     is_pritine,
                                 )
@@ -360,13 +535,15 @@ struct Info {
                     .style(|| Style::BASE.padding_horiz_px(10.0)),
                     label(move || info.with(|info| info.path.clone())).style(
                         move || {
-                            Style::BASE.apply_if(
-                                !info
-                                    .with(|info| info.confirmed)
-                                    .map(|confirmed| confirmed.get())
-                                    .unwrap_or(true),
-                                |s| s.font_style(FontStyle::Italic),
-                            )
+                            Style::BASE
+                                .apply_if(
+                                    !info
+                                        .with(|info| info.confirmed)
+                                        .map(|confirmed| confirmed.get())
+                                        .unwrap_or(true),
+                                    |s| s.font_style(FontStyle::Italic),
+                                )
+                                .apply_if(is_pinned(), |s| s.hide())
                         },
                     ),
                     clickable_icon(
@@ -392,7 +569,11 @@ struct Info {
                         config,
                     )
                     .on_event(EventListener::PointerDown, |_| true)
-                    .style(|| Style::BASE.margin_horiz_px(6.0)),
+                    .style(move || {
+                        Style::BASE
+                            .margin_horiz_px(6.0)
+                            .apply_if(is_pinned(), |s| s.hide())
+                    }),
                 )
             })
             .style(move || {
@@ -413,6 +594,8 @@ struct Info {
             _ => None,
         };
 
+        let cx = ViewContext::get_current();
+        let dragging_over = create_rw_signal(cx.scope, false);
         stack(|| {
             (
                 container(child_view)
@@ -422,13 +605,44 @@ struct Info {
                         }
                         true
                     })
-                    .on_event(EventListener::PointerDown, move |_| {
+                    .on_event(EventListener::PointerDown, move |event| {
                         editor_tab.update(|editor_tab| {
                             editor_tab.active = i.get_untracked();
                         });
+                        if let Event::PointerDown(pointer_event) = event {
+                            if pointer_event.button.is_right() {
+                                let path = editor_tab_child_path(
+                                    &child_for_menu,
+                                    editors,
+                                    diff_editors,
+                                );
+                                if let Some(path) = path {
+                                    show_tab_context_menu(
+                                        &path,
+                                        internal_command,
+                                        cx.id,
+                                    );
+                                }
+                            }
+                        }
                         false
                     })
                     .draggable()
+                    .on_event(EventListener::DragStart, move |_| {
+                        let editor_tab_id =
+                            editor_tab.with_untracked(|t| t.editor_tab_id);
+                        dragging.set(Some(DragContent::EditorTab(
+                            editor_tab_id,
+                            child_for_drag.clone(),
+                        )));
+                        true
+                    })
+                    // A tab dropped anywhere but on an editor tab or a split
+                    // zone stays where it was
+                    .on_event(EventListener::DragEnd, move |_| {
+                        dragging.set(None);
+                        true
+                    })
                     .dragging_style(move || {
                         let config = config.get();
                         Style::BASE
@@ -472,7 +686,34 @@ struct Info {
                 }),
             )
         })
-        .style(|| Style::BASE.height_pct(100.0))
+        .on_event(EventListener::DragEnter, move |_| {
+            if is_dragging_editor_tab(dragging) {
+                dragging_over.set(true);
+                true
+            } else {
+                false
+            }
+        })
+        .on_event(EventListener::DragLeave, move |_| {
+            dragging_over.set(false);
+            false
+        })
+        .on_event(EventListener::Drop, move |_| {
+            dragging_over.set(false);
+            let zone = EditorTabDropZone::Tabs(Some(i.get_untracked()));
+            drop_editor_tab_child(editor_tab, dragging, internal_command, zone)
+        })
+        .style(move || {
+            Style::BASE
+                .height_pct(100.0)
+                .apply_if(dragging_over.get(), |s| {
+                    s.background(
+                        *config
+                            .get()
+                            .get_color(LapceColor::EDITOR_DRAG_DROP_TAB_BACKGROUND),
+                    )
+                })
+        })
     };
 
     stack(|| {
@@ -506,6 +747,10 @@ struct Info {
                         .max_width_pct(100.0)
                 })
             })
+            .on_event(EventListener::Drop, move |_| {
+                let zone = EditorTabDropZone::Tabs(None);
+                drop_editor_tab_child(editor_tab, dragging, internal_command, zone)
+            })
             .style(|| {
                 Style::BASE
                     .height_pct(100.0)
@@ -552,6 +797,65 @@ struct Info {
     })
 }
 
+/// Where a tab dragged onto an editor tab is dropped
+#[derive(Clone, Copy, PartialEq)]
+enum EditorTabDropZone {
+    /// Among the tabs of the editor tab, before the one at the index or at the
+    /// end
+    Tabs(Option<usize>),
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+fn is_dragging_editor_tab(dragging: RwSignal<Option<DragContent>>) -> bool {
+    dragging
+        .with_untracked(|d| d.as_ref().map(|d| d.is_editor_tab()))
+        .unwrap_or(false)
+}
+
+/// Move the child of the tab being dragged to where it's dropped on the editor
+/// tab, returning whether a tab was being dragged
+fn drop_editor_tab_child(
+    editor_tab: RwSignal<EditorTabData>,
+    dragging: RwSignal<Option<DragContent>>,
+    internal_command: Listener<InternalCommand>,
+    zone: EditorTabDropZone,
+) -> bool {
+    let Some(DragContent::EditorTab(from_editor_tab_id, child)) =
+        dragging.get_untracked()
+    else {
+        return false;
+    };
+    dragging.set(None);
+
+    let to_editor_tab_id = editor_tab.with_untracked(|t| t.editor_tab_id);
+    let (direction, after) = match zone {
+        EditorTabDropZone::Tabs(index) => {
+            internal_command.send(InternalCommand::EditorTabChildMove {
+                from_editor_tab_id,
+                child,
+                to_editor_tab_id,
+                index,
+            });
+            return true;
+        }
+        EditorTabDropZone::Left => (SplitDirection::Vertical, false),
+        EditorTabDropZone::Right => (SplitDirection::Vertical, true),
+        EditorTabDropZone::Top => (SplitDirection::Horizontal, false),
+        EditorTabDropZone::Bottom => (SplitDirection::Horizontal, true),
+    };
+    internal_command.send(InternalCommand::EditorTabChildSplit {
+        from_editor_tab_id,
+        child,
+        to_editor_tab_id,
+        direction,
+        after,
+    });
+    true
+}
+
 fn editor_tab_content(
     main_split: MainSplitData,
     workspace: Arc<LapceWorkspace>,
@@ -771,6 +1075,118 @@ fn editor_tab_content(
     tab(active, items, key, view_fn).style(|| Style::BASE.size_pct(100.0, 100.0))
 }
 
+/// The zones over the content of an editor tab where a dragged tab can be
+/// dropped, with the part of the editor tab it will take highlighted
+fn editor_tab_drop_zones(
+    editor_tab: RwSignal<EditorTabData>,
+    common: CommonData,
+) -> impl View {
+    let config = common.config;
+    let dragging = common.dragging;
+    let internal_command = common.internal_command;
+    let cx = ViewContext::get_current();
+    let drop_zone = create_rw_signal(cx.scope, None);
+
+    let zone_view = move |zone: EditorTabDropZone| {
+        empty()
+            .on_event(EventListener::DragEnter, move |_| {
+                if is_dragging_editor_tab(dragging) {
+                    drop_zone.set(Some(zone));
+                    true
+                } else {
+                    false
+                }
+            })
+            .on_event(EventListener::DragLeave, move |_| {
+                if drop_zone.get_untracked() == Some(zone) {
+                    drop_zone.set(None);
+                }
+                false
+            })
+            .on_event(EventListener::Drop, move |_| {
+                drop_zone.set(None);
+                drop_editor_tab_child(editor_tab, dragging, internal_command, zone)
+            })
+            .style(move || match zone {
+                EditorTabDropZone::Tabs(_) => {
+                    Style::BASE.height_pct(100.0).flex_grow(1.0)
+                }
+                EditorTabDropZone::Left | EditorTabDropZone::Right => {
+                    Style::BASE.size_pct(25.0, 100.0)
+                }
+                EditorTabDropZone::Top | EditorTabDropZone::Bottom => {
+                    Style::BASE.size_pct(100.0, 25.0)
+                }
+            })
+    };
+
+    stack(|| {
+        (
+            container(|| {
+                empty().style(move || {
+                    let (width, height) = match drop_zone.get() {
+                        Some(EditorTabDropZone::Left | EditorTabDropZone::Right) => {
+                            (50.0, 100.0)
+                        }
+                        Some(EditorTabDropZone::Top | EditorTabDropZone::Bottom) => {
+                            (100.0, 50.0)
+                        }
+                        _ => (100.0, 100.0),
+                    };
+                    Style::BASE.size_pct(width, height).apply_if(
+                        drop_zone.get().is_some(),
+                        |s| {
+                            s.background(
+                                *config.get().get_color(
+                                    LapceColor::EDITOR_DRAG_DROP_BACKGROUND,
+                                ),
+                            )
+                        },
+                    )
+                })
+            })
+            .style(move || {
+                let zone = drop_zone.get();
+                Style::BASE
+                    .absolute()
+                    .size_pct(100.0, 100.0)
+                    .apply_if(
+                        matches!(
+                            zone,
+                            Some(EditorTabDropZone::Top | EditorTabDropZone::Bottom)
+                        ),
+                        |s| s.flex_col(),
+                    )
+                    .apply_if(
+                        matches!(
+                            zone,
+                            Some(
+                                EditorTabDropZone::Right | EditorTabDropZone::Bottom
+                            )
+                        ),
+                        |s| s.justify_content(Some(JustifyContent::FlexEnd)),
+                    )
+            }),
+            stack(move || {
+                (
+                    zone_view(EditorTabDropZone::Top),
+                    stack(move || {
+                        (
+                            zone_view(EditorTabDropZone::Left),
+                            zone_view(EditorTabDropZone::Tabs(None)),
+                            zone_view(EditorTabDropZone::Right),
+                        )
+                    })
+                    .style(|| Style::BASE.width_pct(100.0).flex_grow(1.0)),
+                    zone_view(EditorTabDropZone::Bottom),
+                )
+            })
+            .style(|| Style::BASE.absolute().size_pct(100.0, 100.0).flex_col()),
+        )
+    })
+    .style(|| Style::BASE.absolute().size_pct(100.0, 100.0))
+}
+
 fn editor_tab(
     main_split: MainSplitData,
     workspace: Arc<LapceWorkspace>,
@@ -807,14 +1223,20 @@ fn editor_tab(
                 diff_editors,
                 common,
             ),
-            editor_tab_content(
-                main_split.clone(),
-                workspace.clone(),
-                active_editor_tab,
-                editor_tab,
-                editors,
-                diff_editors,
-            ),
+            stack(|| {
+                (
+                    editor_tab_content(
+                        main_split.clone(),
+                        workspace.clone(),
+                        active_editor_tab,
+                        editor_tab,
+                        editors,
+                        diff_editors,
+                    ),
+                    editor_tab_drop_zones(editor_tab, main_split.common.clone()),
+                )
+            })
+            .style(|| Style::BASE.size_pct(100.0, 100.0)),
         )
     })
     .on_event(EventListener::PointerDown, move |_| {
@@ -833,13 +1255,19 @@ fn split_border(
     editor_tabs: ReadSignal<im::HashMap<EditorTabId, RwSignal<EditorTabData>>>,
     split: ReadSignal<SplitData>,
     config: ReadSignal<Arc<LapceConfig>>,
+    main_split: MainSplitData,
 ) -> impl View {
     let direction = move || split.with(|split| split.direction);
+    let maximized_editor_tab = main_split.maximized_editor_tab;
     list(
         move || split.get().children.into_iter().skip(1),
         |content| content.id(),
         move |content| {
-            container(|| {
+            // Where the border was grabbed, when it's being dragged
+            let cx = ViewContext::get_current();
+            let drag_start = create_rw_signal(cx.scope, None);
+            let main_split = main_split.clone();
+            let view = container(|| {
                 empty().style(move || {
                     let direction = direction();
                     Style::BASE
@@ -899,6 +1327,42 @@ fn split_border(
                         SplitDirection::Horizontal => FlexDirection::Column,
                     })
                     .justify_content(Some(JustifyContent::Center))
+                    .cursor(CursorStyle::Pointer)
+                    .apply_if(maximized_editor_tab.get().is_some(), |s| s.hide())
+            });
+            let id = view.id();
+            view.on_event(EventListener::PointerDown, move |event| {
+                if let Event::PointerDown(pointer_event) = event {
+                    id.request_active();
+                    drag_start.set(Some(pointer_event.pos));
+                }
+                true
+            })
+            .on_event(EventListener::PointerMove, move |event| {
+                let Event::PointerMove(pointer_event) = event else {
+                    return false;
+                };
+                let Some(start) = drag_start.get_untracked() else {
+                    return false;
+                };
+                // The border follows the pointer, so the pointer stays where
+                // it was grabbed once the move is applied
+                let delta = match direction() {
+                    SplitDirection::Vertical => pointer_event.pos.x - start.x,
+                    SplitDirection::Horizontal => pointer_event.pos.y - start.y,
+                };
+                let (split_id, index) = split.with_untracked(|split| {
+                    let index = split.children.iter().position(|c| c == &content);
+                    (split.split_id, index)
+                });
+                if let Some(index) = index {
+                    main_split.split_resize(split_id, index, delta);
+                }
+                true
+            })
+            .on_event(EventListener::PointerUp, move |_| {
+                drag_start.set(None);
+                false
             })
         },
     )
@@ -938,6 +1402,10 @@ fn split_list(
     let items = move || split.get().children.into_iter().enumerate();
     let key = |(_index, content): &(usize, SplitContent)| content.id();
     let view_fn = move |(_index, content), main_split: MainSplitData| {
+        let is_hidden = {
+            let main_split = main_split.clone();
+            move || main_split.is_split_content_hidden(&content)
+        };
         let child = match &content {
             SplitContent::EditorTab(editor_tab_id) => {
                 let editor_tab_data = editor_tabs
@@ -994,14 +1462,17 @@ fn split_list(
                 }
             })
             .style(move || {
+                let size = split.with(|split| split.size(&content));
                 Style::BASE
-                    .flex_grow(1.0)
+                    .flex_grow(size as f32)
                     .flex_basis(Dimension::Points(1.0))
+                    .apply_if(is_hidden(), |s| s.hide())
             })
     };
     container_box(move || {
         Box::new(
             stack(move || {
+                let border_main_split = main_split.clone();
                 (
                     list(items, key, move |(index, content)| {
                         view_fn((index, content), main_split.clone())
@@ -1014,7 +1485,13 @@ fn split_list(
                             })
                             .size_pct(100.0, 100.0)
                     }),
-                    split_border(splits, editor_tabs, split, config),
+                    split_border(
+                        splits,
+                        editor_tabs,
+                        split,
+                        config,
+                        border_main_split,
+                    ),
                 )
             })
             .style(|| Style::BASE.size_pct(100.0, 100.0)),
@@ -1034,18 +1511,33 @@ fn main_split(window_tab_data: Arc<WindowTabData>) -> impl View {
     let config = window_tab_data.main_split.common.config;
     let workspace = window_tab_data.workspace.clone();
     let panel = window_tab_data.panel.clone();
-    split_list(workspace, root_split, window_tab_data.main_split.clone()).style(
-        move || {
-            let config = config.get();
-            let is_hidden = panel.panel_bottom_maximized(true)
-                && panel.is_container_shown(&PanelContainerPosition::Bottom, true);
-            Style::BASE
-                .border_color(*config.get_color(LapceColor::LAPCE_BORDER))
-                .background(*config.get_color(LapceColor::EDITOR_BACKGROUND))
-                .apply_if(is_hidden, |s| s.display(Display::None))
-                .flex_grow(1.0)
-        },
-    )
+    let editor_tabs = window_tab_data.main_split.editor_tabs;
+    let is_empty = move || editor_tabs.with(|tabs| tabs.is_empty());
+    stack(move || {
+        (
+            split_list(workspace, root_split, window_tab_data.main_split.clone())
+                .style(move || {
+                    Style::BASE
+                        .size_pct(100.0, 100.0)
+                        .apply_if(is_empty(), |s| s.hide())
+                }),
+            welcome_page(window_tab_data.clone()).style(move || {
+                Style::BASE
+                    .size_pct(100.0, 100.0)
+                    .apply_if(!is_empty(), |s| s.hide())
+            }),
+        )
+    })
+    .style(move || {
+        let config = config.get();
+        let is_hidden = panel.panel_bottom_maximized(true)
+            && panel.is_container_shown(&PanelContainerPosition::Bottom, true);
+        Style::BASE
+            .border_color(*config.get_color(LapceColor::LAPCE_BORDER))
+            .background(*config.get_color(LapceColor::EDITOR_BACKGROUND))
+            .apply_if(is_hidden, |s| s.display(Display::None))
+            .flex_grow(1.0)
+    })
 }
 
 pub fn clickable_icon(
@@ -1135,7 +1627,14 @@ fn status(window_tab_data: Arc<WindowTabData>) -> impl View {
     let diagnostics = window_tab_data.main_split.diagnostics;
     let editor = window_tab_data.main_split.active_editor;
     let panel = window_tab_data.panel.clone();
+    let task_panel = window_tab_data.panel.clone();
+    let terminal = window_tab_data.terminal.clone();
     let palette = window_tab_data.palette.clone();
+    let plugin = window_tab_data.plugin.clone();
+    let progresses = window_tab_data.progresses;
+    let server_status_visible = window_tab_data.server_status_visible;
+    let plugin_status_items = window_tab_data.common.plugin_status_items;
+    let plugin_commands_data = window_tab_data.clone();
     let cx = ViewContext::get_current();
     let diagnostic_count = create_memo(cx.scope, move |_| {
         let mut errors = 0;
@@ -1154,56 +1653,118 @@ fn status(window_tab_data: Arc<WindowTabData>) -> impl View {
         (errors, warnings)
     });
 
+    let branch = window_tab_data.source_control.branch;
+    let workspace = window_tab_data.workspace.clone();
     let mode = create_memo(cx.scope, move |_| window_tab_data.mode());
+    let status_template = create_memo(cx.scope, move |_| {
+        StatusTemplate::parse(&config.get().ui.status_format)
+    });
+    // The status format replaces the mode, diagnostics, cursor and language
+    let has_status_format =
+        move || status_template.with(|template| !template.is_empty());
+
+    let spinner_frame = create_rw_signal(cx.scope, 0);
+    let spinning = create_rw_signal(cx.scope, false);
+    create_effect(cx.scope, move |_| {
+        if progresses.with(|progresses| !progresses.is_empty())
+            && !spinning.get_untracked()
+        {
+            spinning.set(true);
+            spin_progress(cx.scope, spinner_frame, spinning, move || {
+                progresses.with_untracked(|progresses| !progresses.is_empty())
+            });
+        }
+    });
+
+    // Looking for indentation issues goes through the whole document, so it's
+    // done off the UI thread once the document stops changing for a moment
+    let indent_issue = create_rw_signal(cx.scope, None);
+    create_effect(cx.scope, move |_| {
+        let Some(active) = editor.get() else {
+            indent_issue.set(None);
+            return;
+        };
+        let doc = active.with(|editor| editor.view.doc);
+        // The document may override the tab width of the settings
+        config.with(|config| config.editor.tab_width);
+        let (version, text, indent_width) = doc.with(|doc| {
+            let indent_width = match doc.buffer().indent_style() {
+                IndentStyle::Spaces(width) => width as usize,
+                IndentStyle::Tabs => doc.tab_width(),
+            };
+            (
+                (doc.buffer_id, doc.rev()),
+                doc.buffer().text().clone(),
+                indent_width,
+            )
+        });
+        // Whether the active document is still the one looked at
+        let current = move || {
+            editor.get_untracked().map(|editor| {
+                let doc = editor.with_untracked(|editor| editor.view.doc);
+                doc.with_untracked(|doc| (doc.buffer_id, doc.rev()))
+            }) == Some(version)
+        };
+        exec_after(cx.scope, INDENT_ISSUE_DELAY, move || {
+            if !current() {
+                return;
+            }
+            let send = create_ext_action(cx.scope, move |issue| {
+                if current() {
+                    indent_issue.set(issue);
+                }
+            });
+            std::thread::spawn(move || {
+                send(detect_indent_issue(&text, indent_width));
+            });
+        });
+    });
 
     stack(|| {
         (
             stack(|| {
                 (
-                    label(move || match mode.get() {
-                        Mode::Normal => "Normal".to_string(),
-                        Mode::Insert => "Insert".to_string(),
-                        Mode::Visual => "Visual".to_string(),
-                        Mode::Terminal => "Terminal".to_string(),
-                    })
-                    .style(move || {
-                        let config = config.get();
-                        let display = if config.core.modal {
-                            Display::Flex
-                        } else {
-                            Display::None
-                        };
+                    label(move || mode_name(mode.get()).to_string()).style(
+                        move || {
+                            let config = config.get();
+                            let display =
+                                if config.core.modal && !has_status_format() {
+                                    Display::Flex
+                                } else {
+                                    Display::None
+                                };
 
-                        let (bg, fg) = match mode.get() {
-                            Mode::Normal => (
-                                LapceColor::STATUS_MODAL_NORMAL_BACKGROUND,
-                                LapceColor::STATUS_MODAL_NORMAL_FOREGROUND,
-                            ),
-                            Mode::Insert => (
-                                LapceColor::STATUS_MODAL_INSERT_BACKGROUND,
-                                LapceColor::STATUS_MODAL_INSERT_FOREGROUND,
-                            ),
-                            Mode::Visual => (
-                                LapceColor::STATUS_MODAL_VISUAL_BACKGROUND,
-                                LapceColor::STATUS_MODAL_VISUAL_FOREGROUND,
-                            ),
-                            Mode::Terminal => (
-                                LapceColor::STATUS_MODAL_TERMINAL_BACKGROUND,
-                                LapceColor::STATUS_MODAL_TERMINAL_FOREGROUND,
-                            ),
-                        };
+                            let (bg, fg) = match mode.get() {
+                                Mode::Normal => (
+                                    LapceColor::STATUS_MODAL_NORMAL_BACKGROUND,
+                                    LapceColor::STATUS_MODAL_NORMAL_FOREGROUND,
+                                ),
+                                Mode::Insert => (
+                                    LapceColor::STATUS_MODAL_INSERT_BACKGROUND,
+                                    LapceColor::STATUS_MODAL_INSERT_FOREGROUND,
+                                ),
+                                Mode::Visual => (
+                                    LapceColor::STATUS_MODAL_VISUAL_BACKGROUND,
+                                    LapceColor::STATUS_MODAL_VISUAL_FOREGROUND,
+                                ),
+                                Mode::Terminal => (
+                                    LapceColor::STATUS_MODAL_TERMINAL_BACKGROUND,
+                                    LapceColor::STATUS_MODAL_TERMINAL_FOREGROUND,
+                                ),
+                            };
 
-                        let bg = *config.get_color(bg);
-                        let fg = *config.get_color(fg);
+                            let bg = *config.get_color(bg);
+                            let fg = *config.get_color(fg);
 
-                        Style::BASE
-                            .display(display)
-                            .padding_horiz_px(10.0)
-                            .color(fg)
-                            .background(bg)
-                            .height_pct(100.0)
-                            .align_items(Some(AlignItems::Center))
-                    }),
+                            Style::BASE
+                                .display(display)
+                                .padding_horiz_px(10.0)
+                                .color(fg)
+                                .background(bg)
+                                .height_pct(100.0)
+                                .align_items(Some(AlignItems::Center))
+                        },
+                    ),
                     {
                         let panel = panel.clone();
                         stack(|| {
@@ -1241,11 +1802,12 @@ fn status(window_tab_data: Arc<WindowTabData>) -> impl View {
                             panel.show_panel(&PanelKind::Problem);
                             true
                         })
-                        .style(|| {
+                        .style(move || {
                             Style::BASE
                                 .height_pct(100.0)
                                 .padding_horiz_px(10.0)
                                 .items_center()
+                                .apply_if(has_status_format(), |s| s.hide())
                         })
                         .hover_style(move || {
                             Style::BASE.cursor(CursorStyle::Pointer).background(
@@ -1255,21 +1817,103 @@ fn status(window_tab_data: Arc<WindowTabData>) -> impl View {
                             )
                         })
                     },
-                )
-            })
-            .style(|| {
-                Style::BASE
-                    .height_pct(100.0)
-                    .flex_basis_px(0.0)
-                    .flex_grow(1.0)
-                    .items_center()
-            }),
-            stack(move || {
-                (
-                    {
-                        let panel = panel.clone();
-                        let icon = {
-                            let panel = panel.clone();
+                    // Only the signals of the variables in the format are read,
+                    // so it's rendered again only when one of them changes.
+                    label(move || {
+                        status_template.with(|template| {
+                            template.render(|name, arg| match (name, arg) {
+                                ("mode", _) => {
+                                    Some(mode_name(mode.get()).to_string())
+                                }
+                                ("branch", _) => Some(branch.get()),
+                                ("diag", Some("errors")) => {
+                                    Some(diagnostic_count.get().0.to_string())
+                                }
+                                ("diag", Some("warnings")) => {
+                                    Some(diagnostic_count.get().1.to_string())
+                                }
+                                ("language", _) => Some(match editor.get() {
+                                    Some(editor) => {
+                                        let doc =
+                                            editor.with(|editor| editor.view.doc);
+                                        doc.with(|doc| {
+                                            doc.syntax().language.to_string()
+                                        })
+                                    }
+                                    None => "Plain Text".to_string(),
+                                }),
+                                ("line" | "column", _) => {
+                                    let Some(editor) = editor.get() else {
+                                        return Some(String::new());
+                                    };
+                                    let (cursor, doc) = editor.with(|editor| {
+                                        (editor.cursor, editor.view.doc)
+                                    });
+                                    let cursor = cursor.get();
+                                    let position = doc.with(|doc| {
+                                        cursor.get_line_col_char(doc.buffer())
+                                    });
+                                    Some(match position {
+                                        Some((line, _, _)) if name == "line" => {
+                                            (line + 1).to_string()
+                                        }
+                                        Some((_, column, _)) => {
+                                            (column + 1).to_string()
+                                        }
+                                        None => String::new(),
+                                    })
+                                }
+                                (name, _) => {
+                                    let doc = editor.get().map(|editor| {
+                                        editor.with(|editor| editor.view.doc)
+                                    });
+                                    let (path, is_pristine) = doc
+                                        .map(|doc| {
+                                            doc.with(|doc| {
+                                                let path = match &doc.content {
+                                                    DocContent::File(path)
+                                                    | DocContent::Notebook(path)
+                                                    | DocContent::Archive(path) => {
+                                                        Some(path.clone())
+                                                    }
+                                                    _ => None,
+                                                };
+                                                (path, doc.buffer().is_pristine())
+                                            })
+                                        })
+                                        .unwrap_or((None, true));
+                                    file_variable(
+                                        name,
+                                        path.as_deref(),
+                                        workspace.path.as_deref(),
+                                        is_pristine,
+                                    )
+                                }
+                            })
+                        })
+                    })
+                    .style(move || {
+                        Style::BASE
+                            .height_pct(100.0)
+                            .padding_horiz_px(10.0)
+                            .items_center()
+                            .apply_if(!has_status_format(), |s| s.hide())
+                    }),
+                )
+            })
+            .style(|| {
+                Style::BASE
+                    .height_pct(100.0)
+                    .flex_basis_px(0.0)
+                    .flex_grow(1.0)
+                    .items_center()
+            }),
+            stack(move || {
+                (
+                    {
+                        let panel = panel.clone();
+                        let icon = {
+                            let panel = panel.clone();
                             move || {
                                 if panel.is_container_shown(
                                     &PanelContainerPosition::Left,
@@ -1351,31 +1995,208 @@ fn status(window_tab_data: Arc<WindowTabData>) -> impl View {
             })
             .style(|| Style::BASE.height_pct(100.0).items_center()),
             stack(|| {
+                let plugin_items = list(
+                    move || {
+                        plugin_status_items.with(|items| {
+                            items
+                                .iter()
+                                .map(|(key, item)| (key.clone(), item.text.clone()))
+                                .collect::<im::Vector<_>>()
+                        })
+                    },
+                    |item| item.clone(),
+                    move |(key, text)| {
+                        let window_tab_data = plugin_commands_data.clone();
+                        // The command is looked up when clicked, for the one
+                        // the plugin set last
+                        let command = move || {
+                            plugin_status_items.with_untracked(|items| {
+                                items.get(&key).and_then(|item| item.command.clone())
+                            })
+                        };
+                        label(move || text.clone())
+                            .on_click(move |_| {
+                                if let Some(command) = command() {
+                                    window_tab_data.run_command_from_plugin(
+                                        &command.command,
+                                        command.data,
+                                    );
+                                }
+                                true
+                            })
+                            .style(move || {
+                                Style::BASE
+                                    .height_pct(100.0)
+                                    .padding_horiz_px(10.0)
+                                    .items_center()
+                            })
+                            .hover_style(move || {
+                                Style::BASE.cursor(CursorStyle::Pointer).background(
+                                    *config.get().get_color(
+                                        LapceColor::PANEL_HOVERED_BACKGROUND,
+                                    ),
+                                )
+                            })
+                    },
+                )
+                .style(|| Style::BASE.height_pct(100.0).items_center());
+                // The tasks that are running, and the one that ran last once
+                // it's done, so that it's clear what a rerun would do
+                let task_info = {
+                    let terminal = terminal.clone();
+                    label(move || {
+                        let processes = terminal.run_debug_processes();
+                        let running = processes
+                            .iter()
+                            .filter(|(_, process)| !process.stopped)
+                            .map(|(_, process)| format!("▶ {}", process.config.name))
+                            .collect::<Vec<_>>();
+                        if !running.is_empty() {
+                            return running.join("  ");
+                        }
+                        match processes.last() {
+                            Some((_, process)) => {
+                                format!("■ {}", process.config.name)
+                            }
+                            None => String::new(),
+                        }
+                    })
+                }
+                .on_click(move |_| {
+                    let processes = terminal.run_debug_processes();
+                    let last = processes
+                        .iter()
+                        .rev()
+                        .find(|(_, process)| !process.stopped)
+                        .or_else(|| processes.last());
+                    if let Some((term_id, _)) = last {
+                        terminal.focus_terminal(*term_id);
+                        task_panel.show_panel(&PanelKind::Terminal);
+                    }
+                    true
+                })
+                .style(move || {
+                    Style::BASE
+                        .height_pct(100.0)
+                        .padding_horiz_px(10.0)
+                        .items_center()
+                })
+                .hover_style(move || {
+                    Style::BASE.cursor(CursorStyle::Pointer).background(
+                        *config
+                            .get()
+                            .get_color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                    )
+                });
+                let progress_info = label(move || {
+                    let frame = PROGRESS_SPINNER
+                        [spinner_frame.get() % PROGRESS_SPINNER.len()];
+                    let work = progresses
+                        .with(|progresses| progresses.values().last().cloned());
+                    match work {
+                        Some(work) => {
+                            format!(
+                                "{frame} {}: {}",
+                                work.server_name,
+                                progress_text(&work)
+                            )
+                        }
+                        None => {
+                            let servers = plugin.enabled_volts().len();
+                            if servers == 0 {
+                                String::new()
+                            } else {
+                                format!("{servers} Plugins")
+                            }
+                        }
+                    }
+                })
+                .on_click(move |_| {
+                    server_status_visible.update(|visible| *visible = !*visible);
+                    true
+                })
+                .style(move || {
+                    Style::BASE
+                        .height_pct(100.0)
+                        .padding_horiz_px(10.0)
+                        .items_center()
+                })
+                .hover_style(move || {
+                    Style::BASE.cursor(CursorStyle::Pointer).background(
+                        *config
+                            .get()
+                            .get_color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                    )
+                });
+                let indent_info = label(move || match indent_issue.get() {
+                    Some(IndentIssue::MixedTabsAndSpaces) => {
+                        "Mixed Indentation".to_string()
+                    }
+                    Some(IndentIssue::InconsistentWidth) => {
+                        "Inconsistent Indentation".to_string()
+                    }
+                    None => String::new(),
+                })
+                .on_click(move |_| {
+                    // Convert to the indentation style the document was
+                    // detected to use.
+                    if let Some(editor) = editor.get_untracked() {
+                        let editor = editor.get_untracked();
+                        let style = editor
+                            .view
+                            .doc
+                            .with_untracked(|doc| doc.buffer().indent_style());
+                        editor.convert_indentation(style);
+                    }
+                    true
+                })
+                .style(move || {
+                    Style::BASE
+                        .display(if indent_issue.get().is_some() {
+                            Display::Flex
+                        } else {
+                            Display::None
+                        })
+                        .height_pct(100.0)
+                        .padding_horiz_px(10.0)
+                        .items_center()
+                        .color(*config.get().get_color(LapceColor::LAPCE_WARN))
+                })
+                .hover_style(move || {
+                    Style::BASE.cursor(CursorStyle::Pointer).background(
+                        *config
+                            .get()
+                            .get_color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                    )
+                });
                 let palette_clone = palette.clone();
                 let cursor_info = label(move || {
                     if let Some(editor) = editor.get() {
                         let mut status = String::new();
                         let cursor = editor.get().cursor.get();
-                        if let Some((line, column, character)) = cursor
-                            .get_line_col_char(editor.get().view.doc.get().buffer())
+                        let doc = editor.get().view.doc;
+                        if let Some((line, column, character)) =
+                            doc.with(|doc| cursor.get_line_col_char(doc.buffer()))
                         {
                             status = format!(
                                 "Ln {}, Col {}, Char {}",
-                                line, column, character,
+                                line + 1,
+                                column + 1,
+                                character,
                             );
                         }
-                        if let Some(selection) = cursor.get_selection() {
-                            let selection_range = selection.0.abs_diff(selection.1);
-
-                            if selection.0 != selection.1 {
-                                status =
-                                    format!("{status} ({selection_range} selected)");
-                            }
+                        let (chars, lines) =
+                            doc.with(|doc| cursor.get_selection_stats(doc.buffer()));
+                        if chars > 0 {
+                            status = if lines > 1 {
+                                format!("{status} ({chars} selected, {lines} lines)")
+                            } else {
+                                format!("{status} ({chars} selected)")
+                            };
                         }
                         let selection_count = cursor.get_selection_count();
                         if selection_count > 1 {
-                            status =
-                                format!("{status} {selection_count} selections");
+                            status = format!("{status} {selection_count} carets");
                         }
                         return status;
                     }
@@ -1385,11 +2206,12 @@ fn status(window_tab_data: Arc<WindowTabData>) -> impl View {
                     palette_clone.run(cx.scope, PaletteKind::Line);
                     true
                 })
-                .style(|| {
+                .style(move || {
                     Style::BASE
                         .height_pct(100.0)
                         .padding_horiz_px(10.0)
                         .items_center()
+                        .apply_if(has_status_format(), |s| s.hide())
                 })
                 .hover_style(move || {
                     Style::BASE.cursor(CursorStyle::Pointer).background(
@@ -1411,11 +2233,12 @@ fn status(window_tab_data: Arc<WindowTabData>) -> impl View {
                     palette_clone.run(cx.scope, PaletteKind::Language);
                     true
                 })
-                .style(|| {
+                .style(move || {
                     Style::BASE
                         .height_pct(100.0)
                         .padding_horiz_px(10.0)
                         .items_center()
+                        .apply_if(has_status_format(), |s| s.hide())
                 })
                 .hover_style(move || {
                     Style::BASE.cursor(CursorStyle::Pointer).background(
@@ -1424,7 +2247,14 @@ fn status(window_tab_data: Arc<WindowTabData>) -> impl View {
                             .get_color(LapceColor::PANEL_HOVERED_BACKGROUND),
                     )
                 });
-                (cursor_info, language_info)
+                (
+                    plugin_items,
+                    task_info,
+                    progress_info,
+                    indent_info,
+                    cursor_info,
+                    language_info,
+                )
             })
             .style(|| {
                 Style::BASE
@@ -1446,6 +2276,206 @@ fn status(window_tab_data: Arc<WindowTabData>) -> impl View {
     })
 }
 
+fn mode_name(mode: Mode) -> &'static str {
+    match mode {
+        Mode::Normal => "Normal",
+        Mode::Insert => "Insert",
+        Mode::Visual => "Visual",
+        Mode::Terminal => "Terminal",
+    }
+}
+
+/// How long the document has to stay unchanged before it's looked at for
+/// indentation issues
+const INDENT_ISSUE_DELAY: Duration = Duration::from_millis(500);
+
+const PROGRESS_SPINNER: [&str; 10] =
+    ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+/// Advance the spinner `frame` every 100ms for as long as `active` is true,
+/// like the status bar's while the language servers have work in progress
+fn spin_progress(
+    scope: Scope,
+    frame: RwSignal<usize>,
+    spinning: RwSignal<bool>,
+    active: impl Fn() -> bool + Copy + Send + 'static,
+) {
+    let send = create_ext_action(scope, move |_| {
+        if !active() {
+            spinning.set(false);
+            return;
+        }
+        frame.update(|frame| *frame += 1);
+        spin_progress(scope, frame, spinning, active);
+    });
+    std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(100));
+        send(());
+    });
+}
+
+fn progress_text(work: &WorkProgress) -> String {
+    let mut text = work.title.clone();
+    if let Some(message) = work.message.as_ref() {
+        text = format!("{text} {message}");
+    }
+    if let Some(percentage) = work.percentage {
+        text = format!("{text} ({percentage}%)");
+    }
+    text
+}
+
+fn server_status_popup(window_tab_data: Arc<WindowTabData>) -> impl View {
+    const WIDTH: f64 = 360.0;
+    let config = window_tab_data.common.config;
+    let layout_rect = window_tab_data.layout_rect;
+    let progresses = window_tab_data.progresses;
+    let visible = window_tab_data.server_status_visible;
+    let plugin = window_tab_data.plugin.clone();
+    let cx = ViewContext::get_current();
+    let size = create_rw_signal(cx.scope, Size::ZERO);
+
+    let server_view = move |meta: VoltMetadata| {
+        let window_tab_data = window_tab_data.clone();
+        let display_name = meta.display_name.clone();
+        let server_name = meta.display_name.clone();
+        stack(|| {
+            (
+                label(move || display_name.clone()).style(|| {
+                    Style::BASE.flex_grow(1.0).min_width_px(0.0).text_ellipsis()
+                }),
+                label(move || {
+                    progresses.with(|progresses| {
+                        progresses
+                            .values()
+                            .filter(|work| work.server_name == server_name)
+                            .last()
+                            .map(progress_text)
+                            .unwrap_or_else(|| "Running".to_string())
+                    })
+                })
+                .style(move || {
+                    Style::BASE
+                        .margin_left_px(10.0)
+                        .max_width_px(160.0)
+                        .text_ellipsis()
+                        .color(*config.get().get_color(LapceColor::EDITOR_DIM))
+                }),
+                label(|| "Restart".to_string())
+                    .on_click(move |_| {
+                        window_tab_data.restart_server(meta.clone());
+                        true
+                    })
+                    .style(|| {
+                        Style::BASE
+                            .margin_left_px(10.0)
+                            .padding_horiz_px(6.0)
+                            .border_radius(4.0)
+                    })
+                    .hover_style(move || {
+                        Style::BASE.cursor(CursorStyle::Pointer).background(
+                            *config
+                                .get()
+                                .get_color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                        )
+                    }),
+            )
+        })
+        .style(|| {
+            Style::BASE
+                .width_pct(100.0)
+                .items_center()
+                .padding_vert_px(2.0)
+        })
+    };
+
+    stack(|| {
+        (
+            label(|| "Language Servers".to_string()).style(|| {
+                Style::BASE.font_weight(Weight::BOLD).margin_bottom_px(4.0)
+            }),
+            list(
+                move || im::Vector::from(plugin.enabled_volts()),
+                |meta| meta.id(),
+                server_view,
+            )
+            .style(|| Style::BASE.flex_col().width_pct(100.0)),
+        )
+    })
+    .on_resize(move |_, rect| {
+        size.set(rect.size());
+    })
+    .style(move || {
+        let config = config.get();
+        let layout_rect = layout_rect.get();
+        let size = size.get();
+        let status_height = config.ui.status_height() as f64;
+        Style::BASE
+            .position(Position::Absolute)
+            .apply_if(!visible.get(), |s| s.hide())
+            .flex_col()
+            .width_px(WIDTH as f32)
+            .margin_left_px((layout_rect.width() - WIDTH - 10.0).max(0.0) as f32)
+            .margin_top_px(
+                (layout_rect.height() - status_height - size.height - 10.0).max(0.0)
+                    as f32,
+            )
+            .padding_px(10.0)
+            .border(1.0)
+            .border_radius(6.0)
+            .border_color(*config.get_color(LapceColor::LAPCE_BORDER))
+            .background(*config.get_color(LapceColor::COMPLETION_BACKGROUND))
+    })
+}
+
+/// The file name and the folder of a path as the palette shows them, each with
+/// the offsets of its chars that the input matched. A shortened folder doesn't
+/// have the matched offsets of the path.
+pub(crate) fn palette_path_parts(
+    path: &Path,
+    shortened_folder: Option<&str>,
+    indices: &[usize],
+) -> ((String, Vec<usize>), (String, Vec<usize>)) {
+    let file_name = path
+        .file_name()
+        .and_then(|s| s.to_str())
+        .unwrap_or("")
+        .to_string();
+    let folder = path
+        .parent()
+        .and_then(|s| s.to_str())
+        .unwrap_or("")
+        .to_string();
+    let folder_len = folder.len();
+
+    let file_name_indices = indices
+        .iter()
+        .filter_map(|&i| {
+            if folder_len > 0 {
+                if i > folder_len {
+                    Some(i - folder_len - 1)
+                } else {
+                    None
+                }
+            } else {
+                Some(i)
+            }
+        })
+        .collect::<Vec<_>>();
+    let folder_indices = indices
+        .iter()
+        .filter_map(|&i| {
+            if i < folder_len && shortened_folder.is_none() {
+                Some(i)
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+    let folder = shortened_folder.map(str::to_string).unwrap_or(folder);
+    ((file_name, file_name_indices), (folder, folder_indices))
+}
+
 fn palette_item(
     workspace: Arc<LapceWorkspace>,
     i: usize,
@@ -1458,40 +2488,12 @@ fn palette_item(
     match &item.content {
         PaletteItemContent::File { path, .. }
         | PaletteItemContent::Reference { path, .. } => {
-            let file_name = path
-                .file_name()
-                .and_then(|s| s.to_str())
-                .unwrap_or("")
-                .to_string();
-            // let (file_name, _) = create_signal(cx.scope, file_name);
-            let folder = path
-                .parent()
-                .and_then(|s| s.to_str())
-                .unwrap_or("")
-                .to_string();
-            // let (folder, _) = create_signal(cx.scope, folder);
-            let folder_len = folder.len();
-
-            let file_name_indices = item
-                .indices
-                .iter()
-                .filter_map(|&i| {
-                    if folder_len > 0 {
-                        if i > folder_len {
-                            Some(i - folder_len - 1)
-                        } else {
-                            None
-                        }
-                    } else {
-                        Some(i)
-                    }
-                })
-                .collect::<Vec<_>>();
-            let folder_indices = item
-                .indices
-                .iter()
-                .filter_map(|&i| if i < folder_len { Some(i) } else { None })
-                .collect::<Vec<_>>();
+            let ((file_name, file_name_indices), (folder, folder_indices)) =
+                palette_path_parts(
+                    path,
+                    item.decoration.folder.as_deref(),
+                    &item.indices,
+                );
 
             let path = path.to_path_buf();
             let style_path = path.clone();
@@ -1908,18 +2910,50 @@ fn palette_item(
         | PaletteItemContent::Workspace { .. }
         | PaletteItemContent::SshHost { .. }
         | PaletteItemContent::Language { .. }
+        | PaletteItemContent::TabWidth { .. }
+        | PaletteItemContent::IndentWidth { .. }
+        | PaletteItemContent::DiagnosticKind { .. }
         | PaletteItemContent::ColorTheme { .. }
         | PaletteItemContent::SCMReference { .. }
+        | PaletteItemContent::Register { .. }
+        | PaletteItemContent::SearchHistory { .. }
+        | PaletteItemContent::Profile { .. }
+        | PaletteItemContent::PluginPermissions { .. }
+        | PaletteItemContent::KeymapChange { .. }
+        | PaletteItemContent::FileCommit { .. }
         | PaletteItemContent::IconTheme { .. } => {
             let text = item.filter_text;
             let indices = item.indices;
+            let icon = item.decoration.icon;
             container_box(move || {
                 Box::new(
-                    focus_text(
-                        move || text.clone(),
-                        move || indices.clone(),
-                        move || *config.get().get_color(LapceColor::EDITOR_FOCUS),
-                    )
+                    stack(move || {
+                        (
+                            svg(move || {
+                                icon.map(|icon| config.get().ui_svg(icon))
+                                    .unwrap_or_default()
+                            })
+                            .style(move || {
+                                let config = config.get();
+                                let size = config.ui.icon_size() as f32;
+                                let color =
+                                    config.get_color(LapceColor::LAPCE_ICON_ACTIVE);
+                                Style::BASE
+                                    .min_width_px(size)
+                                    .size_px(size, size)
+                                    .margin_right_px(5.0)
+                                    .color(*color)
+                                    .apply_if(icon.is_none(), |s| s.hide())
+                            }),
+                            focus_text(
+                                move || text.clone(),
+                                move || indices.clone(),
+                                move || {
+                                    *config.get().get_color(LapceColor::EDITOR_FOCUS)
+                                },
+                            ),
+                        )
+                    })
                     .style(|| {
                         Style::BASE
                             .align_items(Some(AlignItems::Center))
@@ -1928,6 +2962,16 @@ fn palette_item(
                 )
             })
         }
+        PaletteItemContent::Header { name } => {
+            let name = name.clone();
+            container_box(move || {
+                Box::new(label(move || name.clone()).style(move || {
+                    Style::BASE
+                        .color(*config.get().get_color(LapceColor::EDITOR_DIM))
+                        .max_width_pct(100.0)
+                }))
+            })
+        }
     }
     .style(move || {
         Style::BASE
@@ -1948,12 +2992,39 @@ fn palette_input(window_tab_data: Arc<WindowTabData>) -> impl View {
     let editor = window_tab_data.palette.input_editor.clone();
     let config = window_tab_data.common.config;
     let focus = window_tab_data.common.focus;
+    let loading = window_tab_data.palette.loading;
     let is_focused = move || focus.get() == Focus::Palette;
+
+    let cx = ViewContext::get_current();
+    let spinner_frame = create_rw_signal(cx.scope, 0);
+    let spinning = create_rw_signal(cx.scope, false);
+    create_effect(cx.scope, move |_| {
+        if loading.get() && !spinning.get_untracked() {
+            spinning.set(true);
+            spin_progress(cx.scope, spinner_frame, spinning, move || {
+                loading.get_untracked()
+            });
+        }
+    });
+
     container(move || {
-        container(move || {
-            text_input(editor, is_focused).style(|| Style::BASE.width_pct(100.0))
-        })
-        .style(move || {
+        stack(move || {
+            (
+                text_input(editor, is_focused).style(|| Style::BASE.flex_grow(1.0)),
+                label(move || {
+                    PROGRESS_SPINNER[spinner_frame.get() % PROGRESS_SPINNER.len()]
+                        .to_string()
+                })
+                .style(move || {
+                    Style::BASE
+                        .padding_horiz_px(10.0)
+                        .color(*config.get().get_color(LapceColor::EDITOR_DIM))
+                        .apply_if(!loading.get(), |s| s.hide())
+                }),
+            )
+        })
+        .style(|| Style::BASE.width_pct(100.0).items_center())
+        .style(move || {
             let config = config.get();
             Style::BASE
                 .width_pct(100.0)
@@ -2023,7 +3094,17 @@ fn palette_content(
                         let keymap = if let PaletteItemContent::Command { cmd } =
                             item.clone().content
                         {
-                            keymaps.get(cmd.kind.str()).and_then(|maps| maps.get(0))
+                            // A command with data, like the ones of the
+                            // plugins, is bound as `command:data`
+                            let with_data = cmd
+                                .data
+                                .as_ref()
+                                .and_then(|data| data.as_str())
+                                .map(|data| format!("{}:{data}", cmd.kind.str()));
+                            with_data
+                                .and_then(|name| keymaps.get(&name))
+                                .or_else(|| keymaps.get(cmd.kind.str()))
+                                .and_then(|maps| maps.get(0))
                         } else {
                             None
                         };
@@ -2224,6 +3305,57 @@ fn completion(window_tab_data: Arc<WindowTabData>) -> impl View {
     let active = completion_data.with_untracked(|c| c.active);
     let request_id =
         move || completion_data.with_untracked(|c| (c.request_id, c.input_id));
+    let freshness = move || completion_data.with(|c| c.freshness);
+    stack(|| {
+        (
+            completion_list(completion_data, active, request_id, config),
+            label(move || match freshness() {
+                CompletionFreshness::Fresh => "".to_string(),
+                CompletionFreshness::Refreshing => "Refreshing…".to_string(),
+                CompletionFreshness::Stale => {
+                    "Server busy, showing earlier results".to_string()
+                }
+            })
+            .style(move || {
+                let config = config.get();
+                Style::BASE
+                    .padding_horiz_px(10.0)
+                    .padding_vert_px(4.0)
+                    .color(*config.get_color(LapceColor::EDITOR_DIM))
+                    .apply_if(freshness() == CompletionFreshness::Fresh, |s| {
+                        s.hide()
+                    })
+            }),
+        )
+    })
+    .on_resize(move |_, rect| {
+        completion_data.update(|c| {
+            c.layout_rect = rect;
+        });
+    })
+    .style(move || {
+        let config = config.get();
+        let origin = window_tab_data.completion_origin();
+        Style::BASE
+            .position(Position::Absolute)
+            .flex_col()
+            .width_px(400.0)
+            .max_height_px(400.0)
+            .margin_left_px(origin.x as f32)
+            .margin_top_px(origin.y as f32)
+            .background(*config.get_color(LapceColor::COMPLETION_BACKGROUND))
+            .font_family(config.editor.font_family.clone())
+            .font_size(config.editor.font_size() as f32)
+            .border_radius(10.0)
+    })
+}
+
+fn completion_list(
+    completion_data: RwSignal<CompletionData>,
+    active: RwSignal<usize>,
+    request_id: impl Fn() -> (usize, usize) + Copy + 'static,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
     scroll(move || {
         virtual_list(
             VirtualListDirection::Vertical,
@@ -2313,20 +3445,272 @@ fn completion(window_tab_data: Arc<WindowTabData>) -> impl View {
                 active as f64 * config.editor.line_height() as f64,
             ))
     })
-    .on_resize(move |_, rect| {
-        completion_data.update(|c| {
-            c.layout_rect = rect;
+    .style(|| {
+        Style::BASE
+            .width_pct(100.0)
+            .min_height_px(0.0)
+            .flex_grow(1.0)
+    })
+}
+
+fn completion_documentation(window_tab_data: Arc<WindowTabData>) -> impl View {
+    const WIDTH: f64 = 400.0;
+    const PADDING: f64 = 10.0;
+    let completion = window_tab_data.common.completion;
+    let config = window_tab_data.common.config;
+    let proxy = window_tab_data.common.proxy.clone();
+    let cx = ViewContext::get_current();
+    let scope = cx.scope;
+
+    let active_item = create_memo(cx.scope, move |_| {
+        completion.with(|c| {
+            if c.status == CompletionStatus::Inactive {
+                return None;
+            }
+            let active = c.active.get();
+            c.filtered_items
+                .get(active)
+                .map(|item| (c.request_id, item.clone()))
+        })
+    });
+
+    // Servers can leave the documentation out of the completion response, so
+    // the items are resolved as they get selected.
+    let requested = create_rw_signal(cx.scope, HashSet::new());
+    create_effect(cx.scope, move |_| {
+        let Some((request_id, item)) = active_item.get() else {
+            return;
+        };
+        if item.item.documentation.is_some() || item.item.data.is_none() {
+            return;
+        }
+        let key = (request_id, item.item.label.clone());
+        if requested.with_untracked(|requested| requested.contains(&key)) {
+            return;
+        }
+        requested.update(|requested| {
+            requested.insert(key);
+        });
+        let send = create_ext_action(scope, move |resolved| {
+            completion.update(|c| c.set_resolved_item(request_id, resolved));
+        });
+        proxy.completion_resolve(item.plugin_id, item.item, move |result| {
+            if let Ok(ProxyResponse::CompletionResolveResponse { item }) = result {
+                send(*item);
+            }
+        });
+    });
+
+    let contents = create_rw_signal(cx.scope, (0, Vec::new()));
+    create_effect(cx.scope, move |_| {
+        let config = config.get();
+        let docs = active_item.with(|item| {
+            item.as_ref()
+                .map(|(_, item)| completion_item_docs(&item.item, &config))
+                .unwrap_or_default()
         });
+        set_markdown_contents(contents, docs, WIDTH - PADDING * 2.0);
+    });
+
+    scroll(move || {
+        markdown_contents(contents, config)
+            .style(|| Style::BASE.width_pct(100.0).padding_px(PADDING as f32))
     })
+    .scroll_bar_color(move || *config.get().get_color(LapceColor::LAPCE_SCROLL_BAR))
     .style(move || {
         let config = config.get();
         let origin = window_tab_data.completion_origin();
+        let completion_width = completion.with(|c| c.layout_rect.size().width);
+        let tab_width = window_tab_data.layout_rect.get().width();
+        let x = if origin.x + completion_width + WIDTH <= tab_width {
+            origin.x + completion_width
+        } else {
+            (origin.x - WIDTH).max(0.0)
+        };
+        let hidden = !config.editor.completion_show_documentation
+            || contents.with(|(_, contents)| contents.is_empty());
         Style::BASE
             .position(Position::Absolute)
-            .width_px(400.0)
+            .apply_if(hidden, |s| s.hide())
+            .width_px(WIDTH as f32)
             .max_height_px(400.0)
+            .margin_left_px(x as f32)
+            .margin_top_px(origin.y as f32)
+            .background(*config.get_color(LapceColor::COMPLETION_BACKGROUND))
+            .border_radius(10.0)
+    })
+}
+
+/// Replace the markdown shown by [`markdown_contents`], wrapping its text at
+/// `width`. The contents are keyed by a generation, for the list to be rebuilt
+/// when they change.
+fn set_markdown_contents(
+    contents: RwSignal<(usize, Vec<MarkdownContent>)>,
+    mut new_contents: Vec<MarkdownContent>,
+    width: f64,
+) {
+    for content in new_contents.iter_mut() {
+        if let MarkdownContent::Text(text_layout) = content {
+            text_layout.set_size(width as f32, f32::MAX);
+        }
+    }
+    contents.update(|(generation, contents)| {
+        *generation += 1;
+        *contents = new_contents;
+    });
+}
+
+fn markdown_contents(
+    contents: RwSignal<(usize, Vec<MarkdownContent>)>,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    list(
+        move || {
+            let (generation, contents) = contents.get();
+            contents
+                .into_iter()
+                .enumerate()
+                .map(move |(i, content)| (generation, i, content))
+        },
+        |(generation, i, _)| (*generation, *i),
+        move |(_, _, content)| match content {
+            MarkdownContent::Text(text_layout) => {
+                container_box(|| Box::new(rich_text(move || text_layout.clone())))
+            }
+            MarkdownContent::Image { .. } => container_box(|| Box::new(empty())),
+            MarkdownContent::Separator => container_box(|| {
+                Box::new(empty().style(move || {
+                    Style::BASE
+                        .width_pct(100.0)
+                        .margin_vert_px(5.0)
+                        .height_px(1.0)
+                        .background(
+                            *config.get().get_color(LapceColor::LAPCE_BORDER),
+                        )
+                }))
+            }),
+        },
+    )
+    .style(|| Style::BASE.flex_col())
+}
+
+fn signature(window_tab_data: Arc<WindowTabData>) -> impl View {
+    const WIDTH: f64 = 400.0;
+    const PADDING: f64 = 10.0;
+    let signature = window_tab_data.common.signature;
+    let config = window_tab_data.common.config;
+    let cx = ViewContext::get_current();
+
+    let current = create_memo(cx.scope, move |_| {
+        signature.with(|signature| {
+            if !signature.is_active() {
+                return None;
+            }
+            let info = signature.current()?.clone();
+            Some((
+                info,
+                signature.current_parameter(),
+                signature.current_signature,
+                signature.signatures.len(),
+            ))
+        })
+    });
+    let show_documentation =
+        create_memo(cx.scope, move |_| signature.with(|s| s.show_documentation));
+
+    let documentation = create_rw_signal(cx.scope, (0, Vec::new()));
+    create_effect(cx.scope, move |_| {
+        let config = config.get();
+        let docs = if show_documentation.get() {
+            current.with(|current| {
+                current
+                    .as_ref()
+                    .map(|(info, parameter, _, _)| {
+                        signature_docs(info, *parameter, &config)
+                    })
+                    .unwrap_or_default()
+            })
+        } else {
+            Vec::new()
+        };
+        set_markdown_contents(documentation, docs, WIDTH - PADDING * 2.0);
+    });
+
+    stack(|| {
+        (
+            stack(|| {
+                (
+                    focus_text(
+                        move || {
+                            current.with(|current| {
+                                current
+                                    .as_ref()
+                                    .map(|(info, ..)| info.label.clone())
+                                    .unwrap_or_default()
+                            })
+                        },
+                        move || {
+                            current.with(|current| {
+                                current
+                                    .as_ref()
+                                    .and_then(|(info, parameter, ..)| {
+                                        parameter_label_indices(info, (*parameter)?)
+                                    })
+                                    .unwrap_or_default()
+                            })
+                        },
+                        move || *config.get().get_color(LapceColor::EDITOR_FOCUS),
+                    )
+                    .style(|| Style::BASE.flex_grow(1.0).min_width_px(0.0)),
+                    label(move || {
+                        current.with(|current| match current {
+                            Some((_, _, index, len)) if *len > 1 => {
+                                format!("{}/{len}", index + 1)
+                            }
+                            _ => String::new(),
+                        })
+                    })
+                    .style(move || {
+                        Style::BASE
+                            .margin_left_px(10.0)
+                            .color(*config.get().get_color(LapceColor::EDITOR_DIM))
+                    }),
+                )
+            })
+            .style(|| Style::BASE.width_pct(100.0).items_center()),
+            scroll(move || {
+                markdown_contents(documentation, config)
+                    .style(|| Style::BASE.width_pct(100.0))
+            })
+            .scroll_bar_color(move || {
+                *config.get().get_color(LapceColor::LAPCE_SCROLL_BAR)
+            })
+            .style(move || {
+                Style::BASE
+                    .width_pct(100.0)
+                    .max_height_px(200.0)
+                    .apply_if(documentation.with(|(_, docs)| docs.is_empty()), |s| {
+                        s.hide()
+                    })
+            }),
+        )
+    })
+    .on_resize(move |_, rect| {
+        signature.update(|signature| {
+            signature.layout_rect = rect;
+        });
+    })
+    .style(move || {
+        let config = config.get();
+        let origin = window_tab_data.signature_origin();
+        Style::BASE
+            .position(Position::Absolute)
+            .apply_if(current.with(|current| current.is_none()), |s| s.hide())
+            .flex_col()
+            .width_px(WIDTH as f32)
             .margin_left_px(origin.x as f32)
             .margin_top_px(origin.y as f32)
+            .padding_px(PADDING as f32)
             .background(*config.get_color(LapceColor::COMPLETION_BACKGROUND))
             .font_family(config.editor.font_family.clone())
             .font_size(config.editor.font_size() as f32)
@@ -2334,6 +3718,81 @@ fn completion(window_tab_data: Arc<WindowTabData>) -> impl View {
     })
 }
 
+/// The char indices of a parameter in the label of its signature
+fn parameter_label_indices(
+    info: &SignatureInformation,
+    parameter: usize,
+) -> Option<Vec<usize>> {
+    let parameter = info.parameters.as_ref()?.get(parameter)?;
+    let (start, end) = match &parameter.label {
+        ParameterLabel::Simple(label) => {
+            let start = info.label.find(label.as_str())?;
+            let start = info.label[..start].chars().count();
+            (start, start + label.chars().count())
+        }
+        // The offsets are in UTF-16 code units
+        ParameterLabel::LabelOffsets([start, end]) => {
+            let mut utf16 = 0;
+            let mut start_char = None;
+            let mut end_char = None;
+            for (i, c) in info.label.chars().enumerate() {
+                if utf16 == *start as usize {
+                    start_char = Some(i);
+                }
+                if utf16 == *end as usize {
+                    end_char = Some(i);
+                }
+                utf16 += c.len_utf16();
+            }
+            let end_char = end_char.unwrap_or_else(|| info.label.chars().count());
+            (start_char?, end_char)
+        }
+    };
+    Some((start..end).collect())
+}
+
+/// The documentation of the active parameter, then of the signature
+fn signature_docs(
+    info: &SignatureInformation,
+    parameter: Option<usize>,
+    config: &LapceConfig,
+) -> Vec<MarkdownContent> {
+    let mut docs = Vec::new();
+    let parameter_doc = parameter
+        .and_then(|parameter| info.parameters.as_ref()?.get(parameter))
+        .and_then(|parameter| parameter.documentation.as_ref());
+    if let Some(parameter_doc) = parameter_doc {
+        docs.extend(parse_documentation(parameter_doc, config));
+    }
+    if let Some(documentation) = info.documentation.as_ref() {
+        let documentation = parse_documentation(documentation, config);
+        if !docs.is_empty() && !documentation.is_empty() {
+            docs.push(MarkdownContent::Separator);
+        }
+        docs.extend(documentation);
+    }
+    docs
+}
+
+/// The detail and the documentation of a completion item
+fn completion_item_docs(
+    item: &CompletionItem,
+    config: &LapceConfig,
+) -> Vec<MarkdownContent> {
+    let mut docs = Vec::new();
+    if let Some(detail) = item.detail.as_ref().filter(|d| !d.trim().is_empty()) {
+        docs.extend(parse_markdown(&format!("```\n{detail}\n```"), 1.5, config));
+    }
+    if let Some(documentation) = item.documentation.as_ref() {
+        let documentation = parse_documentation(documentation, config);
+        if !docs.is_empty() && !documentation.is_empty() {
+            docs.push(MarkdownContent::Separator);
+        }
+        docs.extend(documentation);
+    }
+    docs
+}
+
 fn code_action(window_tab_data: Arc<WindowTabData>) -> impl View {
     let config = window_tab_data.common.config;
     let code_action = window_tab_data.code_action;
@@ -2447,75 +3906,1152 @@ fn rename(window_tab_data: Arc<WindowTabData>) -> impl View {
     })
 }
 
-pub fn dispose_on_ui_cleanup(scope: Scope) {
-    on_cleanup(ViewContext::get_current().scope, move || {
-        let send = create_ext_action(scope, move |_| {
-            scope.dispose();
-        });
-        std::thread::spawn(move || {
-            send(());
-        });
-    });
-}
-
-fn window_tab(window_tab_data: Arc<WindowTabData>) -> impl View {
-    dispose_on_ui_cleanup(window_tab_data.scope);
-    let source_control = window_tab_data.source_control.clone();
-    let window_origin = window_tab_data.window_origin;
-    let layout_rect = window_tab_data.layout_rect;
+/// The notification of a newer release than the running one, with its release
+/// notes and the actions to update to it.
+fn update_notification(window_tab_data: Arc<WindowTabData>) -> impl View {
+    const WIDTH: f64 = 360.0;
+    const PADDING: f64 = 10.0;
     let latest_release = window_tab_data.latest_release;
-    let update_in_progress = window_tab_data.update_in_progress;
-    let config = window_tab_data.common.config;
-    let workspace = window_tab_data.workspace.clone();
+    let update_status = window_tab_data.update_status;
+    let update_dismissed = window_tab_data.update_dismissed;
     let workbench_command = window_tab_data.common.workbench_command;
-    let main_split = window_tab_data.main_split.clone();
+    let config = window_tab_data.common.config;
+    let cx = ViewContext::get_current();
 
-    let view = stack(|| {
-        (
-            stack(|| {
-                (
-                    title(
-                        workspace,
-                        main_split,
-                        source_control,
-                        workbench_command,
-                        latest_release,
-                        update_in_progress,
-                        config,
-                    ),
-                    workbench(window_tab_data.clone()),
-                    status(window_tab_data.clone()),
-                )
-            })
-            .on_resize(move |point, rect| {
-                window_origin.set(point);
-                layout_rect.set(rect);
-            })
-            .style(|| Style::BASE.size_pct(100.0, 100.0).flex_col()),
-            completion(window_tab_data.clone()),
-            code_action(window_tab_data.clone()),
-            rename(window_tab_data.clone()),
-            palette(window_tab_data.clone()),
-        )
-    })
-    .style(move || {
+    let version = create_memo(cx.scope, move |_| {
+        latest_release.with(|release| {
+            release
+                .as_ref()
+                .as_ref()
+                .filter(|release| release.is_newer())
+                .map(|release| release.version.clone())
+        })
+    });
+    let is_visible = move || {
+        let version = version.get();
+        version.is_some() && version != update_dismissed.get()
+    };
+
+    let notes = create_rw_signal(cx.scope, (0, Vec::new()));
+    create_effect(cx.scope, move |_| {
         let config = config.get();
-        Style::BASE
-            .size_pct(100.0, 100.0)
-            .color(*config.get_color(LapceColor::EDITOR_FOREGROUND))
-            .background(*config.get_color(LapceColor::EDITOR_BACKGROUND))
-            .font_size(config.ui.font_size() as f32)
-            .apply_if(!config.ui.font_family.is_empty(), |s| {
-                s.font_family(config.ui.font_family.clone())
-            })
+        let body = latest_release.with(|release| {
+            release
+                .as_ref()
+                .as_ref()
+                .and_then(|release| release.body.clone())
+                .unwrap_or_default()
+        });
+        let contents = parse_markdown(&body, 1.5, &config);
+        set_markdown_contents(notes, contents, WIDTH - PADDING * 2.0);
     });
-    let view_id = view.id();
-    window_tab_data.common.view_id.set(view_id);
-    view
-}
 
-fn workspace_title(workspace: &LapceWorkspace) -> Option<String> {
-    let p = workspace.path.as_ref()?;
+    let status_text = move || {
+        match update_status.get() {
+            UpdateStatus::Idle => "",
+            UpdateStatus::Downloading => "Downloading...",
+            UpdateStatus::Ready(_) => "Restart Lapce to finish the update",
+            UpdateStatus::Failed => "The update failed",
+        }
+        .to_string()
+    };
+    // The action offered for the current state of the update, if any
+    let action = move || match update_status.get() {
+        UpdateStatus::Idle => {
+            Some(("Update", LapceWorkbenchCommand::DownloadUpdate))
+        }
+        UpdateStatus::Downloading => None,
+        UpdateStatus::Ready(_) => {
+            Some(("Restart Now", LapceWorkbenchCommand::RestartToUpdate))
+        }
+        UpdateStatus::Failed => {
+            Some(("Retry", LapceWorkbenchCommand::DownloadUpdate))
+        }
+    };
+
+    container(move || {
+        stack(move || {
+            (
+                label(move || {
+                    format!(
+                        "Lapce {} is available",
+                        version.get().unwrap_or_default()
+                    )
+                })
+                .style(|| Style::BASE.font_bold().margin_bottom_px(8.0)),
+                scroll(move || markdown_contents(notes, config))
+                    .scroll_bar_color(move || {
+                        *config.get().get_color(LapceColor::LAPCE_SCROLL_BAR)
+                    })
+                    .style(|| Style::BASE.width_pct(100.0).max_height_px(200.0)),
+                stack(move || {
+                    (
+                        label(status_text).style(move || {
+                            Style::BASE
+                                .flex_grow(1.0)
+                                .min_width_px(0.0)
+                                .text_ellipsis()
+                                .color(
+                                    *config.get().get_color(LapceColor::EDITOR_DIM),
+                                )
+                        }),
+                        notification_button(
+                            move || {
+                                let text = action().map(|(text, _)| text);
+                                text.unwrap_or_default().to_string()
+                            },
+                            move || {
+                                if let Some((_, cmd)) = action() {
+                                    workbench_command.send(cmd);
+                                }
+                            },
+                            move || action().is_none(),
+                            config,
+                        ),
+                        notification_button(
+                            || "Later".to_string(),
+                            move || {
+                                update_dismissed.set(version.get_untracked());
+                            },
+                            || false,
+                            config,
+                        ),
+                    )
+                })
+                .style(|| Style::BASE.items_center().margin_top_px(10.0)),
+            )
+        })
+        .style(move || {
+            let config = config.get();
+            Style::BASE
+                .flex_col()
+                .width_px(WIDTH as f32)
+                .padding_px(PADDING as f32)
+                .margin_right_px(10.0)
+                .margin_bottom_px(40.0)
+                .border(1.0)
+                .border_radius(6.0)
+                .border_color(*config.get_color(LapceColor::LAPCE_BORDER))
+                .background(*config.get_color(LapceColor::PANEL_BACKGROUND))
+        })
+    })
+    .style(move || {
+        Style::BASE
+            .position(Position::Absolute)
+            .size_pct(100.0, 100.0)
+            .justify_end()
+            .items_end()
+            .apply_if(!is_visible(), |s| s.hide())
+    })
+}
+
+/// The progress of the diagnostics burn-down, with the actions to take on the
+/// current diagnostic
+fn burn_down_progress(window_tab_data: Arc<WindowTabData>) -> impl View {
+    let burn_down = window_tab_data.burn_down.clone();
+    let main_split = window_tab_data.main_split.clone();
+    let workbench_command = window_tab_data.common.workbench_command;
+    let config = window_tab_data.common.config;
+    let cx = ViewContext::get_current();
+
+    let pending = {
+        let burn_down = burn_down.clone();
+        create_memo(cx.scope, move |_| {
+            burn_down
+                .pending(&main_split, true)
+                .iter()
+                .map(|(_, diagnostics)| diagnostics.len())
+                .sum::<usize>()
+        })
+    };
+    let info = burn_down.info;
+    let title = move || {
+        info.with(|info| {
+            info.kind
+                .as_ref()
+                .map(|kind| kind.label())
+                .unwrap_or_default()
+        })
+    };
+    let progress = move || {
+        let pending = pending.get();
+        info.with(|info| {
+            if pending == 0 {
+                format!("All done, {} skipped", info.skipped.len())
+            } else {
+                format!(
+                    "{} of {} fixed, {} skipped, {pending} to go",
+                    info.fixed(pending),
+                    info.total,
+                    info.skipped.len(),
+                )
+            }
+        })
+    };
+    // Only stopping is left to do once all the diagnostics are gone through
+    let button = move |text: &'static str, cmd: LapceWorkbenchCommand| {
+        let is_stop = cmd == LapceWorkbenchCommand::BurnDownStop;
+        notification_button(
+            move || text.to_string(),
+            move || workbench_command.send(cmd.clone()),
+            move || !is_stop && pending.get() == 0,
+            config,
+        )
+    };
+
+    container(move || {
+        stack(move || {
+            (
+                label(title).style(|| Style::BASE.font_bold().margin_bottom_px(4.0)),
+                label(progress).style(move || {
+                    Style::BASE
+                        .color(*config.get().get_color(LapceColor::EDITOR_DIM))
+                }),
+                stack(move || {
+                    (
+                        button("Next", LapceWorkbenchCommand::BurnDownNext),
+                        button("Skip", LapceWorkbenchCommand::BurnDownSkip),
+                        button("Quick Fix", LapceWorkbenchCommand::BurnDownQuickFix),
+                        button("Stop", LapceWorkbenchCommand::BurnDownStop),
+                    )
+                })
+                .style(|| Style::BASE.justify_end().margin_top_px(10.0)),
+            )
+        })
+        .style(move || {
+            let config = config.get();
+            Style::BASE
+                .flex_col()
+                .width_px(360.0)
+                .padding_px(10.0)
+                .margin_right_px(10.0)
+                .margin_bottom_px(40.0)
+                .border(1.0)
+                .border_radius(6.0)
+                .border_color(*config.get_color(LapceColor::LAPCE_BORDER))
+                .background(*config.get_color(LapceColor::PANEL_BACKGROUND))
+        })
+    })
+    .style(move || {
+        Style::BASE
+            .position(Position::Absolute)
+            .size_pct(100.0, 100.0)
+            .justify_end()
+            .items_end()
+            .apply_if(!burn_down.is_active(), |s| s.hide())
+    })
+}
+
+/// The notification about the paths last deleted from the file explorer, to
+/// confirm deleting them for good or to put them back from the trash
+fn delete_notification(window_tab_data: Arc<WindowTabData>) -> impl View {
+    let explorer = window_tab_data.file_explorer.clone();
+    let delete_notice = explorer.delete_notice;
+    let config = window_tab_data.common.config;
+
+    fn items(paths: &[PathBuf]) -> String {
+        match paths {
+            [path] => path
+                .file_name()
+                .map(|name| format!("\"{}\"", name.to_string_lossy()))
+                .unwrap_or_default(),
+            paths => format!("{} items", paths.len()),
+        }
+    }
+    let text = move || {
+        delete_notice.with(|notice| match notice {
+            Some(DeleteNotice::Confirm(paths)) => {
+                format!("Delete {} permanently? This can't be undone.", items(paths))
+            }
+            Some(DeleteNotice::Trashed(paths)) => {
+                format!("Moved {} to the trash", items(paths))
+            }
+            Some(DeleteNotice::Deleted(paths)) => {
+                format!("Deleted {} permanently", items(paths))
+            }
+            Some(DeleteNotice::RestoreFailed(err)) => {
+                format!("Couldn't put back from the trash: {err}")
+            }
+            None => String::new(),
+        })
+    };
+    let is_confirm =
+        move || matches!(delete_notice.get(), Some(DeleteNotice::Confirm(_)));
+    let is_trashed =
+        move || matches!(delete_notice.get(), Some(DeleteNotice::Trashed(_)));
+
+    container(move || {
+        stack(move || {
+            (
+                label(text).style(|| {
+                    Style::BASE.flex_grow(1.0).min_width_px(0.0).text_ellipsis()
+                }),
+                notification_button(
+                    || "Delete".to_string(),
+                    {
+                        let explorer = explorer.clone();
+                        move || explorer.confirm_delete()
+                    },
+                    move || !is_confirm(),
+                    config,
+                ),
+                notification_button(
+                    || "Undo".to_string(),
+                    {
+                        let explorer = explorer.clone();
+                        move || explorer.undo_delete()
+                    },
+                    move || !is_trashed(),
+                    config,
+                ),
+                notification_button(
+                    move || {
+                        let text = if is_confirm() { "Cancel" } else { "Dismiss" };
+                        text.to_string()
+                    },
+                    move || explorer.dismiss_delete_notice(),
+                    || false,
+                    config,
+                ),
+            )
+        })
+        .style(move || {
+            let config = config.get();
+            Style::BASE
+                .items_center()
+                .width_px(360.0)
+                .padding_px(10.0)
+                .margin_right_px(10.0)
+                .margin_bottom_px(40.0)
+                .border(1.0)
+                .border_radius(6.0)
+                .border_color(*config.get_color(LapceColor::LAPCE_BORDER))
+                .background(*config.get_color(LapceColor::PANEL_BACKGROUND))
+        })
+    })
+    .style(move || {
+        Style::BASE
+            .position(Position::Absolute)
+            .size_pct(100.0, 100.0)
+            .justify_end()
+            .items_end()
+            .apply_if(delete_notice.with(|notice| notice.is_none()), |s| s.hide())
+    })
+}
+
+/// Tells that Lapce was started in safe mode, with buttons to turn the parts
+/// of the user's setup it left out back on
+fn safe_mode_notification(window_tab_data: Arc<WindowTabData>) -> impl View {
+    let left_out = window_tab_data.safe_mode;
+    let dismissed = window_tab_data.safe_mode_dismissed;
+    let config = window_tab_data.common.config;
+
+    let text = move || {
+        let parts = left_out.with(|parts| {
+            parts
+                .iter()
+                .map(|part| part.to_string().to_lowercase())
+                .collect::<Vec<_>>()
+        });
+        let left_out = match parts.as_slice() {
+            [] => return "Safe mode: the last session wasn't restored".to_string(),
+            [part] => part.clone(),
+            [rest @ .., last] => format!("{} and {last}", rest.join(", ")),
+        };
+        format!(
+            "Safe mode: your {left_out} are left out, and the last session \
+             wasn't restored"
+        )
+    };
+    let turn_on_button = move |part: SafeModePart| {
+        let window_tab_data = window_tab_data.clone();
+        notification_button(
+            move || format!("Turn On {part}"),
+            move || window_tab_data.turn_on_safe_mode_part(part),
+            move || left_out.with(|parts| !parts.contains(&part)),
+            config,
+        )
+    };
+
+    container(move || {
+        stack(move || {
+            (
+                label(text)
+                    .style(|| Style::BASE.width_pct(100.0).margin_bottom_px(8.0)),
+                stack(move || {
+                    (
+                        turn_on_button(SafeModePart::Settings),
+                        turn_on_button(SafeModePart::Keymaps),
+                        turn_on_button(SafeModePart::Plugins),
+                        notification_button(
+                            || "Dismiss".to_string(),
+                            move || dismissed.set(true),
+                            || false,
+                            config,
+                        ),
+                    )
+                })
+                .style(|| Style::BASE.width_pct(100.0).justify_end()),
+            )
+        })
+        .style(move || {
+            let config = config.get();
+            Style::BASE
+                .flex_col()
+                .width_px(480.0)
+                .padding_px(10.0)
+                .margin_right_px(10.0)
+                .margin_bottom_px(40.0)
+                .border(1.0)
+                .border_radius(6.0)
+                .border_color(*config.get_color(LapceColor::LAPCE_BORDER))
+                .background(*config.get_color(LapceColor::PANEL_BACKGROUND))
+        })
+    })
+    .style(move || {
+        Style::BASE
+            .position(Position::Absolute)
+            .size_pct(100.0, 100.0)
+            .justify_end()
+            .items_end()
+            .apply_if(!safe_mode::is_active() || dismissed.get(), |s| s.hide())
+    })
+}
+
+/// How many of the characters the encoding can't represent the check before
+/// saving lists
+const LISTED_UNMAPPABLE_CHARS: usize = 5;
+
+/// Asks what to do with the characters the encoding of a file can't represent
+/// before saving it
+fn encoding_notification(window_tab_data: Arc<WindowTabData>) -> impl View {
+    let encoding_check = window_tab_data.common.encoding_check;
+    let config = window_tab_data.common.config;
+
+    let text = move || {
+        encoding_check.with(|check| {
+            let Some(check) = check else {
+                return String::new();
+            };
+            let (name, encoding) = check.editor.view.doc.with_untracked(|doc| {
+                let name = doc
+                    .content
+                    .path()
+                    .and_then(|path| path.file_name())
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                (name, doc.encoding.name())
+            });
+            let count = if check.chars.len() < MAX_UNMAPPABLE_CHARS {
+                check.chars.len().to_string()
+            } else {
+                format!("{MAX_UNMAPPABLE_CHARS} or more")
+            };
+            let mut text = format!(
+                "\"{name}\" has {count} characters {encoding} can't represent:"
+            );
+            for c in check.chars.iter().take(LISTED_UNMAPPABLE_CHARS) {
+                text.push_str(&format!(
+                    "\nLn {}, Col {}: {} (U+{:04X})",
+                    c.line + 1,
+                    c.col + 1,
+                    c.c,
+                    c.c as u32
+                ));
+            }
+            if check.chars.len() > LISTED_UNMAPPABLE_CHARS {
+                text.push_str(&format!(
+                    "\nand {} more",
+                    check.chars.len() - LISTED_UNMAPPABLE_CHARS
+                ));
+            }
+            text
+        })
+    };
+    let button = move |text: &'static str, on_click: fn(&EncodingCheck)| {
+        notification_button(
+            move || text.to_string(),
+            move || {
+                if let Some(check) = encoding_check.get_untracked() {
+                    on_click(&check);
+                }
+            },
+            || false,
+            config,
+        )
+    };
+
+    container(move || {
+        stack(move || {
+            (
+                label(text).style(|| Style::BASE.width_pct(100.0)),
+                stack(move || {
+                    (
+                        button("Substitute", |check| {
+                            check.save_with(EncodingFallback::Substitute)
+                        }),
+                        button("Escape", |check| {
+                            check.save_with(EncodingFallback::Escape)
+                        }),
+                        button("Save as UTF-8", |check| check.save_as_utf8()),
+                        button("Cancel", |check| check.cancel()),
+                    )
+                })
+                .style(|| {
+                    Style::BASE
+                        .width_pct(100.0)
+                        .justify_end()
+                        .margin_top_px(10.0)
+                }),
+            )
+        })
+        .style(move || {
+            let config = config.get();
+            Style::BASE
+                .flex_col()
+                .width_px(420.0)
+                .padding_px(10.0)
+                .margin_right_px(10.0)
+                .margin_bottom_px(40.0)
+                .border(1.0)
+                .border_radius(6.0)
+                .border_color(*config.get_color(LapceColor::LAPCE_BORDER))
+                .background(*config.get_color(LapceColor::PANEL_BACKGROUND))
+        })
+    })
+    .style(move || {
+        Style::BASE
+            .position(Position::Absolute)
+            .size_pct(100.0, 100.0)
+            .justify_end()
+            .items_end()
+            .apply_if(encoding_check.with(|check| check.is_none()), |s| s.hide())
+    })
+}
+
+fn notification_button(
+    text: impl Fn() -> String + 'static,
+    on_click: impl Fn() + 'static,
+    is_hidden: impl Fn() -> bool + 'static,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    label(text)
+        .on_click(move |_| {
+            on_click();
+            true
+        })
+        .style(move || {
+            let config = config.get();
+            Style::BASE
+                .margin_left_px(8.0)
+                .padding_horiz_px(12.0)
+                .padding_vert_px(4.0)
+                .border(1.0)
+                .border_radius(6.0)
+                .border_color(*config.get_color(LapceColor::LAPCE_BORDER))
+                .apply_if(is_hidden(), |s| s.hide())
+        })
+        .hover_style(move || {
+            Style::BASE.cursor(CursorStyle::Pointer).background(
+                *config.get().get_color(LapceColor::PANEL_HOVERED_BACKGROUND),
+            )
+        })
+}
+
+fn shortcut_cheat_sheet(window_tab_data: Arc<WindowTabData>) -> impl View {
+    let shortcuts = window_tab_data.shortcuts.clone();
+    let editor = shortcuts.editor.clone();
+    let active = shortcuts.active;
+    let layout_rect = window_tab_data.layout_rect.read_only();
+    let config = window_tab_data.common.config;
+
+    let item_view = move |item: ShortcutItem| {
+        let ShortcutItem {
+            desc,
+            keys,
+            modes,
+            when,
+        } = item;
+        let detail = match (modes.is_empty(), when) {
+            (true, None) => String::new(),
+            (true, Some(when)) => when,
+            (false, None) => format!("[{modes}]"),
+            (false, Some(when)) => format!("[{modes}] {when}"),
+        };
+        stack(move || {
+            (
+                label(move || desc.clone()).style(|| {
+                    Style::BASE
+                        .text_ellipsis()
+                        .min_width_px(0.0)
+                        .flex_grow(1.0)
+                        .flex_basis_px(0.0)
+                }),
+                label(move || detail.clone()).style(move || {
+                    Style::BASE
+                        .text_ellipsis()
+                        .max_width_px(150.0)
+                        .margin_left_px(10.0)
+                        .color(*config.get().get_color(LapceColor::EDITOR_DIM))
+                }),
+                label(move || keys.clone()).style(move || {
+                    let config = config.get();
+                    Style::BASE
+                        .margin_left_px(10.0)
+                        .padding_horiz_px(5.0)
+                        .border(1.0)
+                        .border_radius(3.0)
+                        .border_color(*config.get_color(LapceColor::LAPCE_BORDER))
+                }),
+            )
+        })
+        .style(|| {
+            Style::BASE
+                .items_center()
+                .width_pct(100.0)
+                .padding_horiz_px(10.0)
+                .padding_vert_px(2.0)
+        })
+    };
+
+    container(|| {
+        stack(|| {
+            (
+                container(|| {
+                    text_input(editor, move || active.get())
+                        .style(|| Style::BASE.width_pct(100.0))
+                })
+                .style(move || {
+                    let config = config.get();
+                    Style::BASE
+                        .width_pct(100.0)
+                        .padding_px(5.0)
+                        .border_bottom(1.0)
+                        .border_color(*config.get_color(LapceColor::LAPCE_BORDER))
+                }),
+                scroll(move || {
+                    list(
+                        move || shortcuts.groups(),
+                        |(category, items)| (*category, items.clone()),
+                        move |(category, items)| {
+                            stack(move || {
+                                (
+                                    label(move || category.to_string()).style(
+                                        move || {
+                                            Style::BASE
+                                                .font_bold()
+                                                .padding_horiz_px(10.0)
+                                                .padding_vert_px(5.0)
+                                                .color(*config.get().get_color(
+                                                    LapceColor::EDITOR_FOCUS,
+                                                ))
+                                        },
+                                    ),
+                                    list(
+                                        move || items.clone(),
+                                        |item| item.clone(),
+                                        item_view,
+                                    )
+                                    .style(
+                                        || Style::BASE.flex_col().width_pct(100.0),
+                                    ),
+                                )
+                            })
+                            .style(|| Style::BASE.flex_col().width_pct(100.0))
+                        },
+                    )
+                    .style(|| Style::BASE.flex_col().width_pct(100.0))
+                })
+                .scroll_bar_color(move || {
+                    *config.get().get_color(LapceColor::LAPCE_SCROLL_BAR)
+                })
+                .style(|| Style::BASE.width_pct(100.0).min_height_px(0.0)),
+            )
+        })
+        .on_event(EventListener::PointerDown, move |_| true)
+        .style(move || {
+            let config = config.get();
+            Style::BASE
+                .flex_col()
+                .width_px(600.0)
+                .max_width_pct(90.0)
+                .max_height_px((layout_rect.get().height() * 0.8).round() as f32)
+                .margin_top_px(40.0)
+                .border(1.0)
+                .border_radius(6.0)
+                .border_color(*config.get_color(LapceColor::LAPCE_BORDER))
+                .background(*config.get_color(LapceColor::PALETTE_BACKGROUND))
+        })
+    })
+    .on_event(EventListener::PointerDown, move |_| {
+        window_tab_data.shortcuts.close();
+        true
+    })
+    .style(move || {
+        Style::BASE
+            .position(Position::Absolute)
+            .size_pct(100.0, 100.0)
+            .flex_col()
+            .items_center()
+            .apply_if(!active.get(), |s| s.hide())
+    })
+}
+
+/// The registers with content, like vim's `:registers`
+fn registers_view(window_tab_data: Arc<WindowTabData>) -> impl View {
+    let registers = window_tab_data.registers.clone();
+    let active = registers.active;
+    let layout_rect = window_tab_data.layout_rect.read_only();
+    let config = window_tab_data.common.config;
+
+    container(|| {
+        scroll(move || {
+            list(
+                move || registers.entries(),
+                |entry| entry.clone(),
+                move |(name, content)| {
+                    stack(move || {
+                        (
+                            label(move || format!("\"{name}")).style(move || {
+                                Style::BASE.width_px(30.0).color(
+                                    *config
+                                        .get()
+                                        .get_color(LapceColor::EDITOR_FOCUS),
+                                )
+                            }),
+                            label(move || content.clone()).style(|| {
+                                Style::BASE
+                                    .text_ellipsis()
+                                    .min_width_px(0.0)
+                                    .flex_grow(1.0)
+                                    .flex_basis_px(0.0)
+                            }),
+                        )
+                    })
+                    .style(|| {
+                        Style::BASE
+                            .items_center()
+                            .width_pct(100.0)
+                            .padding_horiz_px(10.0)
+                            .padding_vert_px(2.0)
+                    })
+                },
+            )
+            .style(|| Style::BASE.flex_col().width_pct(100.0).padding_vert_px(5.0))
+        })
+        .scroll_bar_color(move || {
+            *config.get().get_color(LapceColor::LAPCE_SCROLL_BAR)
+        })
+        .on_event(EventListener::PointerDown, move |_| true)
+        .style(move || {
+            let config = config.get();
+            Style::BASE
+                .width_px(600.0)
+                .max_width_pct(90.0)
+                .max_height_px((layout_rect.get().height() * 0.8).round() as f32)
+                .margin_top_px(40.0)
+                .border(1.0)
+                .border_radius(6.0)
+                .border_color(*config.get_color(LapceColor::LAPCE_BORDER))
+                .background(*config.get_color(LapceColor::PALETTE_BACKGROUND))
+        })
+    })
+    .on_event(EventListener::PointerDown, move |_| {
+        window_tab_data.registers.close();
+        true
+    })
+    .style(move || {
+        Style::BASE
+            .position(Position::Absolute)
+            .size_pct(100.0, 100.0)
+            .flex_col()
+            .items_center()
+            .apply_if(!active.get(), |s| s.hide())
+    })
+}
+
+fn scratchpad(window_tab_data: Arc<WindowTabData>) -> impl View {
+    let main_split = window_tab_data.main_split.clone();
+    let workspace = window_tab_data.workspace.clone();
+    let editor = window_tab_data.scratchpad.editor;
+    let active = window_tab_data.scratchpad.active;
+    let layout_rect = window_tab_data.layout_rect.read_only();
+    let config = window_tab_data.common.config;
+    let is_active = move |tracked: bool| {
+        if tracked {
+            active.get()
+        } else {
+            active.get_untracked()
+        }
+    };
+
+    container(|| {
+        stack(|| {
+            (
+                label(|| "Scratchpad".to_string()).style(move || {
+                    let config = config.get();
+                    Style::BASE
+                        .width_pct(100.0)
+                        .padding_horiz_px(10.0)
+                        .padding_vert_px(5.0)
+                        .border_bottom(1.0)
+                        .border_color(*config.get_color(LapceColor::LAPCE_BORDER))
+                        .color(*config.get_color(LapceColor::EDITOR_DIM))
+                }),
+                container(|| {
+                    editor_container_view(main_split, workspace, is_active, editor)
+                })
+                .style(|| Style::BASE.size_pct(100.0, 100.0).min_height_px(0.0)),
+            )
+        })
+        .on_event(EventListener::PointerDown, move |_| true)
+        .style(move || {
+            let config = config.get();
+            Style::BASE
+                .flex_col()
+                .width_px(700.0)
+                .max_width_pct(90.0)
+                .height_px((layout_rect.get().height() * 0.6).round() as f32)
+                .margin_top_px(40.0)
+                .border(1.0)
+                .border_radius(6.0)
+                .border_color(*config.get_color(LapceColor::LAPCE_BORDER))
+                .background(*config.get_color(LapceColor::EDITOR_BACKGROUND))
+        })
+    })
+    .on_event(EventListener::PointerDown, move |_| {
+        window_tab_data.scratchpad.close();
+        true
+    })
+    .style(move || {
+        Style::BASE
+            .position(Position::Absolute)
+            .size_pct(100.0, 100.0)
+            .flex_col()
+            .items_center()
+            .apply_if(!active.get(), |s| s.hide())
+    })
+}
+
+/// The replay of the edits recorded of a file, with the text at the current
+/// step and a scrubber to go through the steps
+fn replay(window_tab_data: Arc<WindowTabData>) -> impl View {
+    let main_split = window_tab_data.main_split.clone();
+    let workspace = window_tab_data.workspace.clone();
+    let replay = window_tab_data.replay.clone();
+    let editor = replay.editor;
+    let active = replay.active;
+    let layout_rect = window_tab_data.layout_rect.read_only();
+    let config = window_tab_data.common.config;
+    let is_active = move |tracked: bool| {
+        if tracked {
+            active.get()
+        } else {
+            active.get_untracked()
+        }
+    };
+
+    let header = {
+        let replay = replay.clone();
+        move || {
+            let title = replay.title.get();
+            let step = replay.step.get();
+            replay.recording.with(|recording| match recording {
+                None => format!(
+                    "{title}: no edits recorded, turn on the \"Record Edits\" setting"
+                ),
+                Some(recording) => {
+                    let time = recording
+                        .time_at(step)
+                        .map(|time| format!(", {}", time_ago(time)))
+                        .unwrap_or_default();
+                    format!("{title}: edit {step} of {}{time}", recording.len())
+                }
+            })
+        }
+    };
+    let playing = replay.playing;
+
+    container(|| {
+        stack(|| {
+            (
+                label(header).style(move || {
+                    let config = config.get();
+                    Style::BASE
+                        .width_pct(100.0)
+                        .padding_horiz_px(10.0)
+                        .padding_vert_px(5.0)
+                        .border_bottom(1.0)
+                        .border_color(*config.get_color(LapceColor::LAPCE_BORDER))
+                        .color(*config.get_color(LapceColor::EDITOR_DIM))
+                }),
+                container(|| {
+                    editor_container_view(main_split, workspace, is_active, editor)
+                })
+                .style(|| Style::BASE.size_pct(100.0, 100.0).min_height_px(0.0)),
+                stack(|| {
+                    (
+                        replay_scrubber(replay.clone(), config),
+                        notification_button(
+                            move || {
+                                let text =
+                                    if playing.get() { "Pause" } else { "Play" };
+                                text.to_string()
+                            },
+                            {
+                                let replay = replay.clone();
+                                move || replay.toggle_play()
+                            },
+                            || false,
+                            config,
+                        ),
+                        notification_button(
+                            || "1 Hour Ago".to_string(),
+                            {
+                                let replay = replay.clone();
+                                move || replay.step_to_hour_ago()
+                            },
+                            || false,
+                            config,
+                        ),
+                    )
+                })
+                .style(move || {
+                    let config = config.get();
+                    Style::BASE
+                        .items_center()
+                        .width_pct(100.0)
+                        .padding_px(10.0)
+                        .border_top(1.0)
+                        .border_color(*config.get_color(LapceColor::LAPCE_BORDER))
+                }),
+            )
+        })
+        .on_event(EventListener::PointerDown, move |_| true)
+        .style(move || {
+            let config = config.get();
+            Style::BASE
+                .flex_col()
+                .width_px(800.0)
+                .max_width_pct(90.0)
+                .height_px((layout_rect.get().height() * 0.7).round() as f32)
+                .margin_top_px(40.0)
+                .border(1.0)
+                .border_radius(6.0)
+                .border_color(*config.get_color(LapceColor::LAPCE_BORDER))
+                .background(*config.get_color(LapceColor::EDITOR_BACKGROUND))
+        })
+    })
+    .on_event(EventListener::PointerDown, move |_| {
+        window_tab_data.replay.close();
+        true
+    })
+    .style(move || {
+        Style::BASE
+            .position(Position::Absolute)
+            .size_pct(100.0, 100.0)
+            .flex_col()
+            .items_center()
+            .apply_if(!active.get(), |s| s.hide())
+    })
+}
+
+/// A bar filled up to the current step of the replay, which is clicked or
+/// dragged along to go to a step
+fn replay_scrubber(
+    replay: ReplayData,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    let cx = ViewContext::get_current();
+    let width = create_rw_signal(cx.scope, 0.0);
+    let dragging = create_rw_signal(cx.scope, false);
+    let step = replay.step;
+    let filled = {
+        let replay = replay.clone();
+        move || {
+            let len = replay.len();
+            if len == 0 {
+                0.0
+            } else {
+                width.get() * step.get() as f64 / len as f64
+            }
+        }
+    };
+    let seek = move |x: f64| {
+        let width = width.get_untracked();
+        if width > 0.0 {
+            let fraction = (x / width).clamp(0.0, 1.0);
+            replay.set_step((fraction * replay.len() as f64).round() as usize);
+        }
+    };
+    let seek_on_move = seek.clone();
+
+    let view = container(|| {
+        empty().style(move || {
+            Style::BASE
+                .height_pct(100.0)
+                .width_px(filled() as f32)
+                .background(*config.get().get_color(LapceColor::EDITOR_CARET))
+        })
+    })
+    .on_resize(move |_, rect| width.set(rect.width()))
+    .style(move || {
+        let config = config.get();
+        Style::BASE
+            .flex_grow(1.0)
+            .height_px(8.0)
+            .border_radius(4.0)
+            .cursor(CursorStyle::Pointer)
+            .background(*config.get_color(LapceColor::LAPCE_SCROLL_BAR))
+    });
+    let id = view.id();
+    view.on_event(EventListener::PointerDown, move |event| {
+        if let Event::PointerDown(pointer_event) = event {
+            id.request_active();
+            dragging.set(true);
+            seek(pointer_event.pos.x);
+        }
+        true
+    })
+    .on_event(EventListener::PointerMove, move |event| {
+        if let Event::PointerMove(pointer_event) = event {
+            if dragging.get_untracked() {
+                seek_on_move(pointer_event.pos.x);
+                return true;
+            }
+        }
+        false
+    })
+    .on_event(EventListener::PointerUp, move |_| {
+        dragging.set(false);
+        false
+    })
+}
+
+fn diagnostic_popup(window_tab_data: Arc<WindowTabData>) -> impl View {
+    const WIDTH: f64 = 400.0;
+    let config = window_tab_data.common.config;
+    let popup = window_tab_data.main_split.diagnostic_popup;
+
+    container(|| {
+        label(move || {
+            popup.with(|popup| {
+                popup
+                    .as_ref()
+                    .map(|popup| popup.diagnostic.message.clone())
+                    .unwrap_or_default()
+            })
+        })
+        .style(|| Style::BASE.min_width_px(0.0))
+    })
+    .style(move || {
+        let origin = window_tab_data.diagnostic_popup_origin(WIDTH);
+        let config = config.get();
+        let color = match popup
+            .with(|popup| popup.as_ref().and_then(|p| p.diagnostic.severity))
+        {
+            Some(DiagnosticSeverity::ERROR) => LapceColor::LAPCE_ERROR,
+            _ => LapceColor::LAPCE_WARN,
+        };
+        Style::BASE
+            .position(Position::Absolute)
+            .apply_if(origin.is_none(), |s| s.hide())
+            .max_width_px(WIDTH as f32)
+            .apply_opt(origin, |s, origin| {
+                s.margin_left_px(origin.x as f32)
+                    .margin_top_px(origin.y as f32)
+            })
+            .padding_horiz_px(10.0)
+            .padding_vert_px(4.0)
+            .border(1.0)
+            .border_radius(6.0)
+            .border_color(*config.get_color(color))
+            .background(*config.get_color(LapceColor::COMPLETION_BACKGROUND))
+    })
+}
+
+pub fn dispose_on_ui_cleanup(scope: Scope) {
+    on_cleanup(ViewContext::get_current().scope, move || {
+        let send = create_ext_action(scope, move |_| {
+            scope.dispose();
+        });
+        std::thread::spawn(move || {
+            send(());
+        });
+    });
+}
+
+fn window_tab(window_tab_data: Arc<WindowTabData>) -> impl View {
+    dispose_on_ui_cleanup(window_tab_data.scope);
+    let source_control = window_tab_data.source_control.clone();
+    let window_origin = window_tab_data.window_origin;
+    let layout_rect = window_tab_data.layout_rect;
+    let latest_release = window_tab_data.latest_release;
+    let update_status = window_tab_data.update_status.read_only();
+    let config = window_tab_data.common.config;
+    let workspace = window_tab_data.workspace.clone();
+    let workbench_command = window_tab_data.common.workbench_command;
+    let main_split = window_tab_data.main_split.clone();
+
+    let view = stack(|| {
+        (
+            stack(|| {
+                (
+                    title(
+                        workspace,
+                        main_split,
+                        source_control,
+                        workbench_command,
+                        latest_release,
+                        update_status,
+                        config,
+                    ),
+                    workbench(window_tab_data.clone()),
+                    status(window_tab_data.clone()),
+                )
+            })
+            .on_resize(move |point, rect| {
+                window_origin.set(point);
+                layout_rect.set(rect);
+            })
+            .style(|| Style::BASE.size_pct(100.0, 100.0).flex_col()),
+            completion(window_tab_data.clone()),
+            completion_documentation(window_tab_data.clone()),
+            signature(window_tab_data.clone()),
+            code_action(window_tab_data.clone()),
+            rename(window_tab_data.clone()),
+            diagnostic_popup(window_tab_data.clone()),
+            server_status_popup(window_tab_data.clone()),
+            update_notification(window_tab_data.clone()),
+            burn_down_progress(window_tab_data.clone()),
+            delete_notification(window_tab_data.clone()),
+            safe_mode_notification(window_tab_data.clone()),
+            encoding_notification(window_tab_data.clone()),
+            palette(window_tab_data.clone()),
+            shortcut_cheat_sheet(window_tab_data.clone()),
+            registers_view(window_tab_data.clone()),
+            scratchpad(window_tab_data.clone()),
+            replay(window_tab_data.clone()),
+        )
+    })
+    .style(move || {
+        let config = config.get();
+        Style::BASE
+            .size_pct(100.0, 100.0)
+            .color(*config.get_color(LapceColor::EDITOR_FOREGROUND))
+            .background(*config.get_color(LapceColor::EDITOR_BACKGROUND))
+            .font_size(config.ui.font_size() as f32)
+            .apply_if(!config.ui.font_family.is_empty(), |s| {
+                s.font_family(config.ui.font_family.clone())
+            })
+    });
+    let view_id = view.id();
+    window_tab_data.common.view_id.set(view_id);
+    view
+}
+
+fn workspace_title(workspace: &LapceWorkspace) -> Option<String> {
+    let p = workspace.path.as_ref()?;
     let dir = p.file_name().unwrap_or(p.as_os_str()).to_string_lossy();
     Some(match &workspace.kind {
         LapceWorkspaceType::Local => format!("{dir}"),
@@ -2742,6 +5278,16 @@ fn app_view(window_data: WindowData) -> impl View {
 
 pub fn launch() {
     use tracing_subscriber::{filter, fmt, prelude::*, reload};
+
+    let cli = Cli::parse();
+    // The log directory already depends on it
+    if cli.portable {
+        Directory::set_portable();
+    }
+    if cli.safe_mode {
+        safe_mode::start();
+    }
+
     let file_appender = tracing_appender::rolling::Builder::new()
         .max_log_files(10)
         .rotation(tracing_appender::rolling::Rotation::DAILY)
@@ -2772,8 +5318,6 @@ pub fn launch() {
         load_shell_env();
     }
 
-    let cli = Cli::parse();
-
     if let Some(log_level) = cli.log_level {
         if let Err(e) = reload_handle.modify(|filter| {
             *filter = match log_level.to_lowercase().as_str() {
@@ -2811,7 +5355,14 @@ pub fn launch() {
         return;
     }
 
-    if !cli.new {
+    // A commit message opened by git, such as when Lapce is the git editor,
+    // gets a process of its own so that git waits until it's closed
+    let is_commit_message = cli
+        .paths
+        .iter()
+        .any(|path| commit_message::is_commit_message(&path.path));
+    // The running instance isn't in safe mode, so it's only used otherwise
+    if !cli.new && !is_commit_message && !cli.safe_mode {
         if let Ok(socket) = get_socket() {
             if let Err(e) = try_open_in_existing_process(socket, &cli.paths) {
                 error!("failed to open path(s): {e}");
@@ -2857,9 +5408,15 @@ pub fn launch() {
     if let Some(path) = LapceConfig::keymaps_file() {
         let _ = watcher.watch(&path, notify::RecursiveMode::Recursive);
     }
+    if let Some(path) = LapceConfig::languages_file() {
+        let _ = watcher.watch(&path, notify::RecursiveMode::Recursive);
+    }
     if let Some(path) = Directory::plugins_directory() {
         let _ = watcher.watch(&path, notify::RecursiveMode::Recursive);
     }
+    if let Some(path) = Directory::profiles_directory() {
+        let _ = watcher.watch(&path, notify::RecursiveMode::Recursive);
+    }
 
     let windows = create_rw_signal(scope, windows);
     let app_data = AppData {
@@ -2884,6 +5441,29 @@ pub fn launch() {
 
     #[cfg(feature = "updater")]
     {
+        use std::sync::atomic::{self, AtomicBool};
+
+        // The setting is global, so any of the windows tells whether it's on
+        let check_for_updates = create_memo(scope, move |_| {
+            windows.with(|windows| {
+                windows.iter().any(|window| {
+                    window.config.with(|config| config.core.check_for_updates)
+                })
+            })
+        });
+        let enabled = Arc::new(AtomicBool::new(false));
+        {
+            let app_data = app_data.clone();
+            let enabled = enabled.clone();
+            create_effect(scope, move |_| {
+                let check_for_updates = check_for_updates.get();
+                enabled.store(check_for_updates, atomic::Ordering::Relaxed);
+                if check_for_updates {
+                    app_data.run_app_command(AppCommand::CheckForUpdates);
+                }
+            });
+        }
+
         let (tx, rx) = crossbeam_channel::bounded(1);
         let notification = create_signal_from_channel(scope, rx);
         let latest_release = app_data.latest_release;
@@ -2893,10 +5473,13 @@ pub fn launch() {
             }
         });
         std::thread::spawn(move || loop {
+            std::thread::sleep(Duration::from_secs(60 * 60));
+            if !enabled.load(atomic::Ordering::Relaxed) {
+                continue;
+            }
             if let Ok(release) = crate::update::get_latest_release() {
                 let _ = tx.send(release);
             }
-            std::thread::sleep(std::time::Duration::from_secs(60 * 60));
         });
     }
 
@@ -2997,7 +5580,7 @@ fn create_windows(
             windows.push_back(window_data.clone());
             app = app.window(move || app_view(window_data), Some(config));
         }
-    } else if files.is_empty() {
+    } else if files.is_empty() && !safe_mode::is_active() {
         // There were no dirs and no files specified, so we'll load the last windows
         if let Ok(app_info) = db.get_app() {
             for info in app_info.windows {
@@ -0,0 +1,329 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use floem::reactive::{
+    create_rw_signal, use_context, RwSignal, Scope, SignalGet, SignalGetUntracked,
+    SignalSet, SignalUpdate, SignalWith, SignalWithUntracked,
+};
+use itertools::Itertools;
+use lsp_types::{Diagnostic, DiagnosticTag, NumberOrString, Range};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    db::LapceDb, doc::EditorDiagnostic, main_split::MainSplitData,
+    workspace::LapceWorkspace,
+};
+
+/// A kind of diagnostics to burn down
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DiagnosticKind {
+    Deprecated,
+    Unnecessary,
+    /// The diagnostics with a code, from the source giving it
+    Code {
+        source: String,
+        code: String,
+    },
+}
+
+impl DiagnosticKind {
+    /// The kinds the diagnostic is of
+    pub fn of(diagnostic: &Diagnostic) -> Vec<DiagnosticKind> {
+        let mut kinds = Vec::new();
+        for tag in diagnostic.tags.iter().flatten() {
+            if *tag == DiagnosticTag::DEPRECATED {
+                kinds.push(DiagnosticKind::Deprecated);
+            } else if *tag == DiagnosticTag::UNNECESSARY {
+                kinds.push(DiagnosticKind::Unnecessary);
+            }
+        }
+        if let Some(code) = &diagnostic.code {
+            let code = match code {
+                NumberOrString::Number(code) => code.to_string(),
+                NumberOrString::String(code) => code.clone(),
+            };
+            let source = diagnostic.source.clone().unwrap_or_default();
+            kinds.push(DiagnosticKind::Code { source, code });
+        }
+        kinds
+    }
+
+    pub fn matches(&self, diagnostic: &Diagnostic) -> bool {
+        Self::of(diagnostic).contains(self)
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            DiagnosticKind::Deprecated => "Deprecated".to_string(),
+            DiagnosticKind::Unnecessary => "Unnecessary".to_string(),
+            DiagnosticKind::Code { source, code } if source.is_empty() => {
+                code.clone()
+            }
+            DiagnosticKind::Code { source, code } => format!("{source}: {code}"),
+        }
+    }
+}
+
+/// The kinds of the diagnostics with how many diagnostics are of each, the
+/// most common first
+pub fn kind_counts<'a>(
+    diagnostics: impl Iterator<Item = &'a Diagnostic>,
+) -> Vec<(DiagnosticKind, usize)> {
+    let mut counts = HashMap::new();
+    for kind in diagnostics.flat_map(DiagnosticKind::of) {
+        *counts.entry(kind).or_insert(0) += 1;
+    }
+    counts
+        .into_iter()
+        .sorted_by(|(a, a_count), (b, b_count)| {
+            b_count.cmp(a_count).then_with(|| a.label().cmp(&b.label()))
+        })
+        .collect()
+}
+
+/// A diagnostic skipped during a burn-down, recognized by its file, its
+/// message and its range, so that the other diagnostics with the same message
+/// in the file are still gone through
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SkippedDiagnostic {
+    pub path: PathBuf,
+    pub message: String,
+    #[serde(default)]
+    pub range: Range,
+}
+
+impl SkippedDiagnostic {
+    fn matches(&self, path: &Path, diagnostic: &Diagnostic) -> bool {
+        self.path == path
+            && self.message == diagnostic.message
+            && self.range == diagnostic.range
+    }
+}
+
+/// The progress of the burn-down of a workspace as it is persisted
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct BurnDownInfo {
+    /// The kind of diagnostics being burnt down, if any
+    pub kind: Option<DiagnosticKind>,
+    /// How many diagnostics of the kind there were when it started
+    pub total: usize,
+    pub skipped: Vec<SkippedDiagnostic>,
+}
+
+impl BurnDownInfo {
+    /// Whether the diagnostic is one still to go through
+    pub fn is_pending(&self, path: &Path, diagnostic: &Diagnostic) -> bool {
+        self.kind.as_ref().map(|kind| kind.matches(diagnostic)) == Some(true)
+            && !self
+                .skipped
+                .iter()
+                .any(|skipped| skipped.matches(path, diagnostic))
+    }
+
+    /// How many diagnostics were fixed, given how many are still pending.
+    /// Diagnostics that appeared since the start make up for the fixed ones.
+    pub fn fixed(&self, pending: usize) -> usize {
+        self.total.saturating_sub(pending + self.skipped.len())
+    }
+}
+
+/// A guided walk through the diagnostics of a kind across the workspace, to
+/// fix, skip or apply a quick fix to each of them in turn.
+#[derive(Clone)]
+pub struct BurnDown {
+    pub info: RwSignal<BurnDownInfo>,
+    /// The diagnostic being looked at
+    current: RwSignal<Option<(PathBuf, Diagnostic)>>,
+    workspace: Arc<LapceWorkspace>,
+    scope: Scope,
+}
+
+impl BurnDown {
+    pub fn new(
+        cx: Scope,
+        workspace: Arc<LapceWorkspace>,
+        info: BurnDownInfo,
+    ) -> Self {
+        Self {
+            info: create_rw_signal(cx, info),
+            current: create_rw_signal(cx, None),
+            workspace,
+            scope: cx,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.info.with(|info| info.kind.is_some())
+    }
+
+    /// The diagnostics still to go through, by file
+    pub fn pending(
+        &self,
+        main_split: &MainSplitData,
+        tracked: bool,
+    ) -> Vec<(PathBuf, Vec<EditorDiagnostic>)> {
+        let info = if tracked {
+            self.info.get()
+        } else {
+            self.info.get_untracked()
+        };
+        let diagnostics = if tracked {
+            main_split.diagnostics.get()
+        } else {
+            main_split.diagnostics.get_untracked()
+        };
+        diagnostics
+            .into_iter()
+            .filter_map(|(path, diagnostics)| {
+                let diagnostics = if tracked {
+                    diagnostics.diagnostics.get()
+                } else {
+                    diagnostics.diagnostics.get_untracked()
+                };
+                let diagnostics: Vec<EditorDiagnostic> = diagnostics
+                    .into_iter()
+                    .filter(|d| info.is_pending(&path, &d.diagnostic))
+                    .collect();
+                (!diagnostics.is_empty()).then_some((path, diagnostics))
+            })
+            .collect()
+    }
+
+    /// Start burning down the diagnostics of the kind, or carry on with the
+    /// burn-down of the workspace if it is of the same kind.
+    pub fn start(&self, main_split: &MainSplitData, kind: DiagnosticKind) {
+        if self
+            .info
+            .with_untracked(|info| info.kind.as_ref() != Some(&kind))
+        {
+            let total = main_split
+                .diagnostics
+                .get_untracked()
+                .values()
+                .flat_map(|d| d.diagnostics.get_untracked())
+                .filter(|d| kind.matches(&d.diagnostic))
+                .count();
+            self.info.set(BurnDownInfo {
+                kind: Some(kind),
+                total,
+                skipped: Vec::new(),
+            });
+            self.save();
+        }
+        self.next(main_split);
+    }
+
+    /// Go to the pending diagnostic after the cursor
+    pub fn next(&self, main_split: &MainSplitData) {
+        let pending = self.pending(main_split, false);
+        self.current
+            .set(main_split.jump_to_diagnostic(&pending, false));
+    }
+
+    /// Leave the current diagnostic as it is, and go to the next one.
+    pub fn skip(&self, main_split: &MainSplitData) {
+        if let Some((path, diagnostic)) = self.current.get_untracked() {
+            self.info.update(|info| {
+                info.skipped.push(SkippedDiagnostic {
+                    path,
+                    message: diagnostic.message,
+                    range: diagnostic.range,
+                });
+            });
+            self.save();
+        }
+        self.next(main_split);
+    }
+
+    /// Offer the code actions of the current diagnostic.
+    pub fn quick_fix(&self, main_split: &MainSplitData) {
+        if self.current.with_untracked(|current| current.is_none()) {
+            return;
+        }
+        if let Some(editor) = main_split.active_editor.get_untracked() {
+            editor.get_untracked().show_code_actions(false);
+        }
+    }
+
+    pub fn stop(&self) {
+        self.info.set(BurnDownInfo::default());
+        self.current.set(None);
+        self.save();
+    }
+
+    fn save(&self) {
+        let db: Arc<LapceDb> = use_context(self.scope).unwrap();
+        db.save_burn_down(self.workspace.clone(), self.info.get_untracked());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::Position;
+
+    use super::*;
+
+    fn diagnostic(code: Option<&str>, tags: Vec<DiagnosticTag>) -> Diagnostic {
+        Diagnostic {
+            range: Range::default(),
+            code: code.map(|code| NumberOrString::String(code.to_string())),
+            source: Some("rustc".to_string()),
+            message: "message".to_string(),
+            tags: Some(tags),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_kind_counts() {
+        let diagnostics = [
+            diagnostic(Some("E0599"), vec![]),
+            diagnostic(None, vec![DiagnosticTag::DEPRECATED]),
+            diagnostic(Some("deprecated"), vec![DiagnosticTag::DEPRECATED]),
+        ];
+        let counts = kind_counts(diagnostics.iter());
+        let labels: Vec<(String, usize)> = counts
+            .iter()
+            .map(|(kind, count)| (kind.label(), *count))
+            .collect();
+        assert_eq!(
+            labels,
+            vec![
+                ("Deprecated".to_string(), 2),
+                ("rustc: E0599".to_string(), 1),
+                ("rustc: deprecated".to_string(), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_is_pending() {
+        let path = PathBuf::from("/src/main.rs");
+        let mut info = BurnDownInfo {
+            kind: Some(DiagnosticKind::Deprecated),
+            total: 3,
+            skipped: Vec::new(),
+        };
+        let deprecated = diagnostic(None, vec![DiagnosticTag::DEPRECATED]);
+        assert!(info.is_pending(&path, &deprecated));
+        assert!(!info.is_pending(&path, &diagnostic(Some("E0599"), vec![])));
+
+        info.skipped.push(SkippedDiagnostic {
+            path: path.clone(),
+            message: deprecated.message.clone(),
+            range: deprecated.range,
+        });
+        assert!(!info.is_pending(&path, &deprecated));
+        assert!(info.is_pending(Path::new("/src/lib.rs"), &deprecated));
+        // Another diagnostic with the same message in the file isn't skipped
+        let elsewhere = Diagnostic {
+            range: Range::new(Position::new(3, 0), Position::new(3, 4)),
+            ..deprecated.clone()
+        };
+        assert!(info.is_pending(&path, &elsewhere));
+        assert_eq!(info.fixed(1), 1);
+    }
+}
@@ -14,6 +14,7 @@
 use crate::{
     command::{CommandExecuted, CommandKind, InternalCommand},
     keypress::{condition::Condition, KeyPressFocus},
+    overlay::OverlayKind,
     window_tab::{CommonData, Focus},
 };
 
@@ -187,12 +188,17 @@ pub fn show(
             })
             .collect();
         self.filtered_items = self.items.clone();
+        self.common.overlays.show(OverlayKind::CodeAction);
         self.common.focus.set(Focus::CodeAction);
     }
 
-    fn cancel(&self) {
+    pub fn cancel(&self) {
         self.status.set(CodeActionStatus::Inactive);
-        self.common.focus.set(Focus::Workbench);
+        self.common.overlays.hidden(OverlayKind::CodeAction);
+        // It may be closing because something else took the focus
+        if let Focus::CodeAction = self.common.focus.get_untracked() {
+            self.common.focus.set(Focus::Workbench);
+        }
     }
 
     fn select(&self) {
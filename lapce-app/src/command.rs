@@ -5,18 +5,26 @@
 use lapce_core::command::{
     EditCommand, FocusCommand, MotionModeCommand, MoveCommand, MultiSelectionCommand,
 };
-use lapce_rpc::{dap_types::RunDebugConfig, plugin::PluginId, terminal::TermId};
+use lapce_rpc::{
+    dap_types::RunDebugConfig,
+    plugin::{PluginId, VoltMetadata},
+    source_control::FileCommit,
+    terminal::TermId,
+};
 use lsp_types::{CodeActionOrCommand, Position, WorkspaceEdit};
 use serde_json::Value;
 use strum::{EnumMessage, IntoEnumIterator};
 use strum_macros::{Display, EnumIter, EnumMessage, EnumString, IntoStaticStr};
 
 use crate::{
+    burn_down::DiagnosticKind,
     debug::RunDebugMode,
     editor::location::EditorLocation,
     editor_tab::EditorTabChild,
     id::EditorTabId,
+    keypress::review::KeymapChange,
     main_split::{SplitDirection, SplitMoveDirection},
+    overlay::OverlayKind,
     workspace::LapceWorkspace,
 };
 
@@ -48,6 +56,18 @@ pub fn desc(&self) -> Option<&'static str> {
         }
     }
 
+    /// The name of the group the command is listed under
+    pub fn category(&self) -> &'static str {
+        match &self {
+            CommandKind::Workbench(_) => "Workbench",
+            CommandKind::Edit(_) => "Editing",
+            CommandKind::Move(_) => "Movement",
+            CommandKind::Focus(_) => "Focus",
+            CommandKind::MotionMode(_) => "Motion Mode",
+            CommandKind::MultiSelection(_) => "Multiple Selections",
+        }
+    }
+
     pub fn str(&self) -> &'static str {
         match &self {
             CommandKind::Workbench(cmd) => cmd.into(),
@@ -156,6 +176,26 @@ pub enum LapceWorkbenchCommand {
     #[strum(message = "Reveal Active File in File Explorer")]
     RevealActiveFileInFileExplorer,
 
+    #[strum(serialize = "reveal_active_file_in_file_manager")]
+    #[strum(message = "Reveal Active File in System File Manager")]
+    RevealActiveFileInFileManager,
+
+    #[strum(serialize = "open_active_file_folder_in_terminal")]
+    #[strum(message = "Open Folder of Active File in New Terminal")]
+    OpenActiveFileFolderInTerminal,
+
+    #[strum(serialize = "copy_active_file_path")]
+    #[strum(message = "Copy Path of Active File")]
+    CopyActiveFilePath,
+
+    #[strum(serialize = "copy_active_file_relative_path")]
+    #[strum(message = "Copy Relative Path of Active File")]
+    CopyActiveFileRelativePath,
+
+    #[strum(serialize = "copy_active_file_name")]
+    #[strum(message = "Copy Name of Active File")]
+    CopyActiveFileName,
+
     #[strum(serialize = "change_color_theme")]
     #[strum(message = "Change Color Theme")]
     ChangeColorTheme,
@@ -184,6 +224,74 @@ pub enum LapceWorkbenchCommand {
     #[strum(message = "Open Keyboard Shortcuts File")]
     OpenKeyboardShortcutsFile,
 
+    #[strum(serialize = "show_shortcut_cheat_sheet")]
+    #[strum(message = "Show Keyboard Shortcut Cheat Sheet")]
+    ShowShortcutCheatSheet,
+
+    #[strum(serialize = "registers")]
+    #[strum(message = "Show Registers")]
+    ShowRegisters,
+
+    #[strum(serialize = "dry_run_keys")]
+    #[strum(message = "Dry Run Keys in the Editor")]
+    DryRunKeys,
+
+    #[strum(serialize = "review_keymap_changes")]
+    #[strum(message = "Review Changes to the Default Keybindings")]
+    ReviewKeymapChanges,
+
+    #[strum(serialize = "toggle_scratchpad")]
+    #[strum(message = "Toggle Scratchpad")]
+    ToggleScratchpad,
+
+    #[strum(serialize = "replay_edits")]
+    #[strum(message = "Replay Edits of Active File")]
+    ReplayEdits,
+
+    #[strum(serialize = "summarize_file_changes")]
+    #[strum(message = "Summarize Changes of Active File Since Last Commit")]
+    SummarizeFileChanges,
+
+    #[strum(serialize = "insert_file_changes_in_commit_message")]
+    #[strum(message = "Insert Changes of Active File in Commit Message")]
+    InsertFileChangesInCommitMessage,
+
+    #[strum(serialize = "toggle_focus_mode")]
+    #[strum(message = "Toggle Focus Mode")]
+    ToggleFocusMode,
+
+    #[strum(serialize = "toggle_fit_selection")]
+    #[strum(message = "Toggle Fitting the Selection in the Editor")]
+    ToggleFitSelection,
+
+    #[strum(serialize = "toggle_scroll_lock")]
+    #[strum(message = "Toggle Scroll Lock with the Nearest Split")]
+    ToggleScrollLock,
+
+    #[strum(serialize = "toggle_ignored_files")]
+    #[strum(message = "Toggle Showing Ignored Files")]
+    ToggleIgnoredFiles,
+
+    #[strum(serialize = "burn_down_diagnostics")]
+    #[strum(message = "Burn Down Diagnostics of a Kind")]
+    BurnDownDiagnostics,
+
+    #[strum(serialize = "burn_down_next")]
+    #[strum(message = "Burn Down: Next Diagnostic")]
+    BurnDownNext,
+
+    #[strum(serialize = "burn_down_skip")]
+    #[strum(message = "Burn Down: Skip Diagnostic")]
+    BurnDownSkip,
+
+    #[strum(serialize = "burn_down_quick_fix")]
+    #[strum(message = "Burn Down: Apply a Quick Fix")]
+    BurnDownQuickFix,
+
+    #[strum(serialize = "burn_down_stop")]
+    #[strum(message = "Burn Down: Stop")]
+    BurnDownStop,
+
     #[strum(serialize = "open_log_file")]
     #[strum(message = "Open Log File")]
     OpenLogFile,
@@ -204,6 +312,24 @@ pub enum LapceWorkbenchCommand {
     #[strum(message = "Open Plugins Directory")]
     OpenPluginsDirectory,
 
+    #[strum(serialize = "manage_plugin_permissions")]
+    #[strum(message = "Manage Plugin Permissions")]
+    ManagePluginPermissions,
+
+    /// Run the command a plugin registered, named in the data, such as
+    /// `run_plugin_command:my-plugin.sort-imports` in a keymap. The commands
+    /// are listed in the palette by their own titles.
+    #[strum(serialize = "run_plugin_command")]
+    RunPluginCommand,
+
+    #[strum(serialize = "open_profiles_directory")]
+    #[strum(message = "Open Profiles Directory")]
+    OpenProfilesDirectory,
+
+    #[strum(serialize = "set_workspace_profile")]
+    #[strum(message = "Use Current Profile for This Workspace")]
+    SetWorkspaceProfile,
+
     #[strum(serialize = "zoom_in")]
     #[strum(message = "Zoom In")]
     ZoomIn,
@@ -216,6 +342,18 @@ pub enum LapceWorkbenchCommand {
     #[strum(message = "Reset Zoom")]
     ZoomReset,
 
+    #[strum(serialize = "editor_font_zoom_in")]
+    #[strum(message = "Editor Font: Zoom In")]
+    EditorFontZoomIn,
+
+    #[strum(serialize = "editor_font_zoom_out")]
+    #[strum(message = "Editor Font: Zoom Out")]
+    EditorFontZoomOut,
+
+    #[strum(serialize = "editor_font_zoom_reset")]
+    #[strum(message = "Editor Font: Reset Zoom")]
+    EditorFontZoomReset,
+
     #[strum(serialize = "close_window_tab")]
     #[strum(message = "Close Current Window Tab")]
     CloseWindowTab,
@@ -305,6 +443,34 @@ pub enum LapceWorkbenchCommand {
     #[strum(serialize = "palette.scm_references")]
     PaletteSCMReferences,
 
+    #[strum(message = "Open Recently Closed File")]
+    #[strum(serialize = "palette.recently_closed")]
+    PaletteRecentlyClosed,
+
+    #[strum(message = "Paste from Register")]
+    #[strum(serialize = "palette.registers")]
+    PaletteRegisters,
+
+    #[strum(message = "Search History")]
+    #[strum(serialize = "palette.search_history")]
+    PaletteSearchHistory,
+
+    #[strum(serialize = "open_search_results_in_editor")]
+    #[strum(message = "Open Search Results in Editor")]
+    OpenSearchResultsInEditor,
+
+    #[strum(serialize = "open_notebook_raw_json")]
+    #[strum(message = "Notebook: Open Raw JSON")]
+    OpenNotebookRawJson,
+
+    #[strum(serialize = "export_notebook_to_python")]
+    #[strum(message = "Notebook: Export to .py")]
+    ExportNotebookToPython,
+
+    #[strum(message = "Switch Profile")]
+    #[strum(serialize = "palette.profile")]
+    PaletteProfile,
+
     #[strum(message = "Run and Debug Restart Current Running")]
     #[strum(serialize = "palette.run_and_debug_restart")]
     RunAndDebugRestart,
@@ -313,6 +479,25 @@ pub enum LapceWorkbenchCommand {
     #[strum(serialize = "palette.run_and_debug_stop")]
     RunAndDebugStop,
 
+    #[strum(message = "Run Current File")]
+    #[strum(serialize = "run_current_file")]
+    RunCurrentFile,
+
+    /// Run the task of `.lapce/run.toml` named in the data, such as
+    /// `run_task:Build` in a keymap, or pick one in the palette
+    #[strum(message = "Run Task")]
+    #[strum(serialize = "run_task")]
+    RunTask,
+
+    #[strum(message = "Rerun Last Task")]
+    #[strum(serialize = "rerun_last_task")]
+    RerunLastTask,
+
+    /// Restart the task that was started last and is still running
+    #[strum(message = "Restart Task")]
+    #[strum(serialize = "restart_task")]
+    RestartTask,
+
     #[strum(serialize = "source_control.checkout_reference")]
     CheckoutReference,
 
@@ -369,6 +554,14 @@ pub enum LapceWorkbenchCommand {
     #[strum(serialize = "toggle_search_focus")]
     ToggleSearchFocus,
 
+    #[strum(message = "Toggle Regex Tester Focus")]
+    #[strum(serialize = "toggle_regex_tester_focus")]
+    ToggleRegexTesterFocus,
+
+    #[strum(message = "Toggle Plugin Views Focus")]
+    #[strum(serialize = "toggle_plugin_views_focus")]
+    TogglePluginViewsFocus,
+
     // Visual toggle commands
     #[strum(serialize = "toggle_terminal_visual")]
     ToggleTerminalVisual,
@@ -391,6 +584,12 @@ pub enum LapceWorkbenchCommand {
     #[strum(serialize = "toggle_search_visual")]
     ToggleSearchVisual,
 
+    #[strum(serialize = "toggle_regex_tester_visual")]
+    ToggleRegexTesterVisual,
+
+    #[strum(serialize = "toggle_plugin_views_visual")]
+    TogglePluginViewsVisual,
+
     #[strum(serialize = "focus_editor")]
     FocusEditor,
 
@@ -419,6 +618,10 @@ pub enum LapceWorkbenchCommand {
     #[strum(serialize = "source_control_discard_workspace_changes")]
     SourceControlDiscardWorkspaceChanges,
 
+    #[strum(message = "Source Control: Show File History")]
+    #[strum(serialize = "source_control_show_file_history")]
+    SourceControlShowFileHistory,
+
     #[strum(serialize = "export_current_theme_settings")]
     #[strum(message = "Export current settings to a theme file")]
     ExportCurrentThemeSettings,
@@ -431,6 +634,14 @@ pub enum LapceWorkbenchCommand {
     #[strum(message = "Change current file language")]
     ChangeFileLanguage,
 
+    #[strum(serialize = "set_tab_width")]
+    #[strum(message = "Set Tab Width for Current File")]
+    SetTabWidth,
+
+    #[strum(serialize = "convert_indentation_to_spaces")]
+    #[strum(message = "Convert Indentation to Spaces")]
+    ConvertIndentationToSpaces,
+
     #[strum(serialize = "next_editor_tab")]
     #[strum(message = "Next Editor Tab")]
     NextEditorTab,
@@ -439,13 +650,40 @@ pub enum LapceWorkbenchCommand {
     #[strum(message = "Previous Editor Tab")]
     PreviousEditorTab,
 
+    #[strum(serialize = "toggle_pin_editor_tab")]
+    #[strum(message = "Pin/Unpin Editor Tab")]
+    TogglePinEditorTab,
+
+    #[strum(serialize = "close_other_editor_tabs")]
+    #[strum(message = "Close Other Editor Tabs")]
+    CloseOtherEditorTabs,
+
+    #[strum(serialize = "close_all_editor_tabs")]
+    #[strum(message = "Close All Editor Tabs")]
+    CloseAllEditorTabs,
+
+    #[strum(serialize = "reopen_closed_editor")]
+    #[strum(message = "Reopen Closed Editor")]
+    ReopenClosedEditor,
+
     #[strum(serialize = "toggle_inlay_hints")]
     #[strum(message = "Toggle Inlay Hints")]
     ToggleInlayHints,
 
+    #[strum(message = "Check for Updates")]
+    #[strum(serialize = "check_for_updates")]
+    CheckForUpdates,
+
+    #[strum(serialize = "download_update")]
+    DownloadUpdate,
+
     #[strum(serialize = "restart_to_update")]
     RestartToUpdate,
 
+    #[strum(serialize = "toggle_server_status")]
+    #[strum(message = "Toggle Language Server Status")]
+    ToggleServerStatus,
+
     #[strum(serialize = "show_about")]
     #[strum(message = "About Lapce")]
     ShowAbout,
@@ -484,6 +722,22 @@ pub enum LapceWorkbenchCommand {
     #[strum(serialize = "previous_error")]
     PreviousError,
 
+    #[strum(message = "Next Diagnostic in File")]
+    #[strum(serialize = "next_diagnostic_in_file")]
+    NextDiagnosticInFile,
+
+    #[strum(message = "Previous Diagnostic in File")]
+    #[strum(serialize = "previous_diagnostic_in_file")]
+    PreviousDiagnosticInFile,
+
+    #[strum(message = "Next Change")]
+    #[strum(serialize = "next_change")]
+    NextChange,
+
+    #[strum(message = "Previous Change")]
+    #[strum(serialize = "previous_change")]
+    PreviousChange,
+
     #[strum(serialize = "quit")]
     #[strum(message = "Quit Editor")]
     Quit,
@@ -523,6 +777,18 @@ pub enum InternalCommand {
     SplitExchange {
         editor_tab_id: EditorTabId,
     },
+    SplitRotate {
+        editor_tab_id: EditorTabId,
+    },
+    SplitResize {
+        editor_tab_id: EditorTabId,
+        delta: f64,
+    },
+    /// Show the editor tab over the whole editor area, or restore the splits
+    /// if it already is
+    SplitMaximize {
+        editor_tab_id: EditorTabId,
+    },
     SplitTerminal {
         term_id: TermId,
     },
@@ -542,6 +808,23 @@ pub enum InternalCommand {
         editor_tab_id: EditorTabId,
         child: EditorTabChild,
     },
+    /// Move a child of an editor tab dropped on an editor tab, before the
+    /// child at the index or at the end
+    EditorTabChildMove {
+        from_editor_tab_id: EditorTabId,
+        child: EditorTabChild,
+        to_editor_tab_id: EditorTabId,
+        index: Option<usize>,
+    },
+    /// Move a child of an editor tab dropped on a side of an editor tab into a
+    /// new editor tab on that side
+    EditorTabChildSplit {
+        from_editor_tab_id: EditorTabId,
+        child: EditorTabChild,
+        to_editor_tab_id: EditorTabId,
+        direction: SplitDirection,
+        after: bool,
+    },
     ShowCodeActions {
         offset: usize,
         mouse_click: bool,
@@ -564,6 +847,10 @@ pub enum InternalCommand {
         start: usize,
         position: Position,
     },
+    /// Close the popup over the editors, for the overlay manager
+    CloseOverlay {
+        kind: OverlayKind,
+    },
     Search {
         pattern: Option<String>,
     },
@@ -573,6 +860,12 @@ pub enum InternalCommand {
     ReplaceEditorReceiveChar {
         s: String,
     },
+    /// Replace the content of the find or replace editor with an entry of the
+    /// search history
+    NavigateFindHistory {
+        replace: bool,
+        previous: bool,
+    },
     FindEditorCommand {
         command: LapceCommand,
         count: Option<usize>,
@@ -586,6 +879,25 @@ pub enum InternalCommand {
     FocusEditorTab {
         editor_tab_id: EditorTabId,
     },
+    /// Copy the path to the clipboard, relative to the workspace if asked and
+    /// it's in it
+    CopyPath {
+        path: PathBuf,
+        relative: bool,
+    },
+    CopyFileName {
+        path: PathBuf,
+    },
+    RevealInFileExplorer {
+        path: PathBuf,
+    },
+    RevealInFileManager {
+        path: PathBuf,
+    },
+    /// Open a new terminal in the folder, or in the folder of the file
+    OpenInTerminal {
+        path: PathBuf,
+    },
 
     SetColorTheme {
         name: String,
@@ -597,6 +909,40 @@ pub enum InternalCommand {
         /// Whether to save the theme to the config file
         save: bool,
     },
+    /// Switch to the profile, `None` for the default one
+    SetProfile {
+        name: Option<String>,
+    },
+    /// Start or carry on burning down the diagnostics of the kind
+    StartBurnDown {
+        kind: DiagnosticKind,
+    },
+    /// Write the edited lines of the search results buffer back to their files
+    ApplySearchResults,
+    /// Let the user review the permissions the plugin asks for
+    ReviewPluginPermissions {
+        volt: VoltMetadata,
+    },
+    /// Report what the keys would do in the active editor, without running
+    /// their commands
+    DryRunKeys {
+        keys: String,
+    },
+    /// Search the workspace for the pattern, in the search panel
+    GlobalSearch {
+        pattern: String,
+    },
+    /// Let the user open the file as of the commit, or diff it against the
+    /// working tree
+    OpenFileCommit {
+        path: PathBuf,
+        commit: FileCommit,
+    },
+    /// Let the user adopt the new keys of a change of the default keymaps,
+    /// or keep their old ones
+    ReviewKeymapChange {
+        change: KeymapChange,
+    },
 
     UpdateLogLevel {
         level: tracing_subscriber::filter::LevelFilter,
@@ -617,4 +963,26 @@ pub enum WindowCommand {
     },
     NextWorkspaceTab,
     PreviousWorkspaceTab,
+    CheckForUpdates,
+    /// Reload the config of all the windows
+    ReloadConfig,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use lapce_rpc::plugin::PROCESS_COMMANDS;
+
+    use super::LapceWorkbenchCommand;
+
+    #[test]
+    fn test_process_commands_exist() {
+        for name in PROCESS_COMMANDS {
+            assert!(
+                LapceWorkbenchCommand::from_str(name).is_ok(),
+                "{name} isn't a workbench command"
+            );
+        }
+    }
 }
@@ -0,0 +1,45 @@
+use std::path::Path;
+
+use lapce_rpc::style::{LineStyle, Style};
+
+/// The files in the git directory that git asks the editor to write a message
+/// in, such as when running `git commit` with Lapce as `GIT_EDITOR`
+const MESSAGE_FILES: &[&str] = &[
+    "COMMIT_EDITMSG",
+    "MERGE_MSG",
+    "SQUASH_MSG",
+    "TAG_EDITMSG",
+    "EDIT_DESCRIPTION",
+];
+
+/// The width the summary line of a commit message should fit in
+pub const SUMMARY_WIDTH: usize = 50;
+/// The width the body of a commit message is wrapped at
+pub const BODY_WIDTH: usize = 72;
+
+pub fn is_commit_message(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .map(|name| MESSAGE_FILES.contains(&name))
+        .unwrap_or(false)
+}
+
+/// The highlighting of a line of a commit message, being the summary on the
+/// first line and the comments git adds as instructions.
+pub fn line_styles(line: usize, content: &str) -> Vec<LineStyle> {
+    let content = content.trim_end_matches(['\r', '\n']);
+    let fg_color = if content.starts_with('#') {
+        "comment"
+    } else if line == 0 {
+        "text.title"
+    } else {
+        return Vec::new();
+    };
+    vec![LineStyle {
+        start: 0,
+        end: content.len(),
+        style: Style {
+            fg_color: Some(fg_color.to_string()),
+        },
+    }]
+}
@@ -1,4 +1,4 @@
-use std::{borrow::Cow, path::PathBuf, str::FromStr, sync::Arc};
+use std::{borrow::Cow, path::PathBuf, str::FromStr, sync::Arc, time::Duration};
 
 use floem::{
     peniko::kurbo::Rect,
@@ -7,7 +7,6 @@
         SignalSet, SignalUpdate, SignalWithUntracked,
     },
 };
-use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
 use lapce_core::{buffer::rope_text::RopeText, movement::Movement};
 use lapce_rpc::{plugin::PluginId, proxy::ProxyRpcHandler};
 use lsp_types::{
@@ -16,8 +15,12 @@
 };
 
 use crate::{
-    config::LapceConfig, doc::Document, editor::view_data::EditorViewData,
-    id::EditorId, snippet::Snippet,
+    config::{editor::CompletionPreselect, LapceConfig},
+    doc::Document,
+    editor::view_data::EditorViewData,
+    fuzzy::FuzzyMatcher,
+    id::EditorId,
+    snippet::Snippet,
 };
 
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -27,10 +30,30 @@ pub enum CompletionStatus {
     Done,
 }
 
+/// How long a completion request is waited on before the cached items shown
+/// for it are marked as stale
+pub const COMPLETION_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// How current the completion items shown are
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompletionFreshness {
+    /// The items answer the current request
+    Fresh,
+    /// The items are the ones last received for the file, shown while the
+    /// current request is pending
+    Refreshing,
+    /// The items are the ones last received for the file, the current request
+    /// having taken too long to be answered
+    Stale,
+}
+
 #[derive(Clone, PartialEq)]
 pub struct ScoredCompletionItem {
     pub item: CompletionItem,
     pub plugin_id: PluginId,
+    /// The priority of the provider of the item, for the items of the
+    /// providers with a higher one to come first among items matching as well
+    pub priority: i32,
     pub score: i64,
     pub label_score: i64,
     pub indices: Vec<usize>,
@@ -64,8 +87,17 @@ pub struct CompletionData {
     pub layout_rect: Rect,
     /// The editor id that was most recently used to trigger a completion.
     pub latest_editor_id: Option<EditorId>,
-    /// Matcher for filtering the completion items
-    matcher: Arc<SkimMatcherV2>,
+    pub freshness: CompletionFreshness,
+    /// Whether the completion was explicitly requested, rather than started
+    /// by typing
+    pub invoked: bool,
+    /// The labels of the accepted items, with when they were last accepted,
+    /// for preselecting the recently used ones
+    accepted: im::HashMap<String, usize>,
+    accepted_count: usize,
+    /// The items last received for each file, which are shown while a slow
+    /// server works on the current request
+    cached_items: im::HashMap<PathBuf, im::Vector<ScoredCompletionItem>>,
     config: ReadSignal<Arc<LapceConfig>>,
 }
 
@@ -83,8 +115,12 @@ pub fn new(cx: Scope, config: ReadSignal<Arc<LapceConfig>>) -> Self {
             input_items: im::HashMap::new(),
             filtered_items: im::Vector::new(),
             layout_rect: Rect::ZERO,
-            matcher: Arc::new(SkimMatcherV2::default().ignore_case()),
             latest_editor_id: None,
+            freshness: CompletionFreshness::Fresh,
+            invoked: false,
+            accepted: im::HashMap::new(),
+            accepted_count: 0,
+            cached_items: im::HashMap::new(),
             config,
         }
     }
@@ -96,6 +132,7 @@ pub fn receive(
         input: &str,
         resp: &CompletionResponse,
         plugin_id: PluginId,
+        priority: i32,
     ) {
         // If we've been canceled or the request id is old, ignore the response.
         if self.status == CompletionStatus::Inactive || self.request_id != request_id
@@ -113,15 +150,47 @@ pub fn receive(
             .map(|i| ScoredCompletionItem {
                 item: i.to_owned(),
                 plugin_id,
+                priority,
                 score: 0,
                 label_score: 0,
                 indices: Vec::new(),
             })
             .collect();
-        self.input_items.insert(input.to_string(), items);
+        // The items of every plugin responding to the request are shown together
+        let input_items = self.input_items.entry(input.to_string()).or_default();
+        input_items.append(items);
+        if !input_items.is_empty() {
+            self.cached_items
+                .insert(self.path.clone(), input_items.clone());
+        }
+        self.freshness = CompletionFreshness::Fresh;
+        self.filter_items();
+        // Only move the selection if the user hasn't moved it themselves
+        if self.active.get_untracked() == 0 {
+            self.preselect();
+        }
+    }
+
+    /// Start showing the items last received for the file while the new
+    /// request is pending, if there are any
+    pub fn show_cached(&mut self) {
+        self.freshness = if self.cached_items.contains_key(&self.path) {
+            CompletionFreshness::Refreshing
+        } else {
+            CompletionFreshness::Fresh
+        };
         self.filter_items();
     }
 
+    /// Mark the cached items shown as stale, if the request is still pending
+    pub fn request_timed_out(&mut self, request_id: usize) {
+        if self.request_id == request_id
+            && self.freshness == CompletionFreshness::Refreshing
+        {
+            self.freshness = CompletionFreshness::Stale;
+        }
+    }
+
     /// Request for completion items wit the current request id.
     pub fn request(
         &mut self,
@@ -148,6 +217,8 @@ pub fn cancel(&mut self) {
         self.input.clear();
         self.input_items.clear();
         self.filtered_items.clear();
+        self.freshness = CompletionFreshness::Fresh;
+        self.invoked = false;
     }
 
     pub fn update_input(&mut self, input: String) {
@@ -161,9 +232,58 @@ pub fn update_input(&mut self, input: String) {
         // `r` then it should stay on `println!` even as the overall filtering of the list changes.
         self.active.set(0);
         self.filter_items();
+        self.preselect();
+    }
+
+    /// Select the item the `completion-preselect` setting asks for among the
+    /// filtered items.
+    fn preselect(&self) {
+        let config = self.config.get_untracked();
+        let index = match config.editor.completion_preselect {
+            CompletionPreselect::First => None,
+            CompletionPreselect::RecentlyUsed => self
+                .filtered_items
+                .iter()
+                .enumerate()
+                .filter_map(|(i, item)| {
+                    self.accepted.get(&item.item.label).map(|when| (i, *when))
+                })
+                .max_by_key(|(_, when)| *when)
+                .map(|(i, _)| i),
+        };
+        self.active.set(index.unwrap_or(0));
+    }
+
+    /// Remember that the item was accepted, for the recently used ones to be
+    /// preselected.
+    pub fn record_accepted(&mut self, item: &ScoredCompletionItem) {
+        self.accepted_count += 1;
+        self.accepted
+            .insert(item.item.label.clone(), self.accepted_count);
+    }
+
+    /// Whether the only item matching an explicitly requested completion
+    /// should be inserted right away, without showing the list.
+    pub fn should_auto_insert(&self) -> bool {
+        self.invoked
+            && self.status != CompletionStatus::Inactive
+            && self.freshness == CompletionFreshness::Fresh
+            && self.filtered_items.len() == 1
+            && self
+                .config
+                .get_untracked()
+                .editor
+                .completion_auto_insert_single
     }
 
     fn all_items(&self) -> im::Vector<ScoredCompletionItem> {
+        if self.freshness != CompletionFreshness::Fresh {
+            return self
+                .cached_items
+                .get(&self.path)
+                .cloned()
+                .unwrap_or_default();
+        }
         self.input_items
             .get(&self.input)
             .cloned()
@@ -176,11 +296,14 @@ fn all_items(&self) -> im::Vector<ScoredCompletionItem> {
     pub fn filter_items(&mut self) {
         self.input_id += 1;
         if self.input.is_empty() {
-            self.filtered_items = self.all_items();
+            let mut items = self.all_items();
+            items.sort_by(|a, b| b.priority.cmp(&a.priority));
+            self.filtered_items = items;
             return;
         }
 
         // Filter the items by the fuzzy matching with the input text.
+        let matcher = FuzzyMatcher::from_config(&self.config.get_untracked());
         let mut items: im::Vector<ScoredCompletionItem> = self
             .all_items()
             .iter()
@@ -195,7 +318,7 @@ pub fn filter_items(&mut self) {
                     .map(|(shift, _)| shift)
                     .unwrap_or(0);
                 if let Some((score, mut indices)) =
-                    self.matcher.fuzzy_indices(filter_text, &self.input)
+                    matcher.fuzzy_indices(filter_text, &self.input)
                 {
                     if shift > 0 {
                         for idx in indices.iter_mut() {
@@ -207,7 +330,7 @@ pub fn filter_items(&mut self) {
                     item.label_score = score;
                     item.indices = indices;
                     if let Some(score) =
-                        self.matcher.fuzzy_match(&i.item.label, &self.input)
+                        matcher.fuzzy_match(&i.item.label, &self.input)
                     {
                         item.label_score = score;
                     }
@@ -217,10 +340,12 @@ pub fn filter_items(&mut self) {
                 }
             })
             .collect();
-        // Sort all the items by their score, then their label score, then their length.
+        // Sort all the items by their score, then the priority of their provider,
+        // then their label score, then their length.
         items.sort_by(|a, b| {
             b.score
                 .cmp(&a.score)
+                .then_with(|| b.priority.cmp(&a.priority))
                 .then_with(|| b.label_score.cmp(&a.label_score))
                 .then_with(|| a.item.label.len().cmp(&b.item.label.len()))
         });
@@ -282,6 +407,27 @@ pub fn current_item(&self) -> Option<&ScoredCompletionItem> {
         self.filtered_items.get(self.active.get_untracked())
     }
 
+    /// Replace an item with its resolved version from `completionItem/resolve`,
+    /// which can include fields such as the documentation that servers leave
+    /// out of the completion response.
+    pub fn set_resolved_item(&mut self, request_id: usize, item: CompletionItem) {
+        if self.status == CompletionStatus::Inactive || self.request_id != request_id
+        {
+            return;
+        }
+        let is_same = |i: &ScoredCompletionItem| {
+            i.item.label == item.label && i.item.data == item.data
+        };
+        for (_, items) in self.input_items.iter_mut() {
+            for i in items.iter_mut().filter(|i| is_same(i)) {
+                i.item = item.clone();
+            }
+        }
+        for i in self.filtered_items.iter_mut().filter(|i| is_same(i)) {
+            i.item = item.clone();
+        }
+    }
+
     /// Update the completion lens of the document with the active completion item.  
     pub fn update_document_completion(
         &self,
@@ -413,3 +559,150 @@ fn completion_lens_text(
         Some(Some(item.to_string()))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use floem::reactive::{create_runtime, create_scope, create_signal};
+
+    use super::*;
+
+    /// Run the test with the completion of the config
+    fn with_completion(
+        config: LapceConfig,
+        f: impl FnOnce(&mut CompletionData) + 'static,
+    ) {
+        let runtime = create_runtime();
+        create_scope(runtime, move |cx| {
+            let (config, _) = create_signal(cx, Arc::new(config));
+            let mut completion = CompletionData::new(cx, config);
+            completion.status = CompletionStatus::Started;
+            f(&mut completion);
+        })
+        .dispose();
+        runtime.dispose();
+    }
+
+    fn receive(completion: &mut CompletionData, labels: &[&str]) {
+        let items = labels
+            .iter()
+            .map(|label| CompletionItem {
+                label: label.to_string(),
+                ..Default::default()
+            })
+            .collect();
+        completion.receive(
+            completion.request_id,
+            "",
+            &CompletionResponse::Array(items),
+            PluginId(0),
+            0,
+        );
+    }
+
+    fn accept(completion: &mut CompletionData, label: &str) {
+        let item = completion
+            .filtered_items
+            .iter()
+            .find(|item| item.item.label == label)
+            .cloned()
+            .unwrap();
+        completion.record_accepted(&item);
+    }
+
+    #[test]
+    fn test_preselect() {
+        let mut config = LapceConfig::default_for_test();
+        config.editor.completion_preselect = CompletionPreselect::RecentlyUsed;
+        with_completion(config, |completion| {
+            receive(completion, &["print", "println", "panic"]);
+            assert_eq!(completion.active.get_untracked(), 0);
+
+            // The item accepted last is preselected
+            accept(completion, "panic");
+            accept(completion, "println");
+            completion.update_input(String::new());
+            let active = completion.current_item().unwrap();
+            assert_eq!(active.item.label, "println");
+
+            // Once the user moved the selection, new items keep it
+            completion.active.set(2);
+            receive(completion, &["eprintln"]);
+            assert_eq!(completion.active.get_untracked(), 2);
+        });
+
+        let config = LapceConfig::default_for_test();
+        with_completion(config, |completion| {
+            receive(completion, &["print", "println"]);
+            accept(completion, "println");
+            completion.update_input(String::new());
+            assert_eq!(completion.active.get_untracked(), 0);
+        });
+    }
+
+    #[test]
+    fn test_completion_freshness() {
+        with_completion(LapceConfig::default_for_test(), |completion| {
+            // Nothing received for the file yet, so nothing to show meanwhile
+            completion.show_cached();
+            assert_eq!(completion.freshness, CompletionFreshness::Fresh);
+            receive(completion, &["print", "println"]);
+            assert_eq!(completion.freshness, CompletionFreshness::Fresh);
+
+            // A new request shows the items of the last one meanwhile
+            completion.request_id += 1;
+            completion.input_items.clear();
+            completion.show_cached();
+            assert_eq!(completion.freshness, CompletionFreshness::Refreshing);
+            assert_eq!(completion.filtered_items.len(), 2);
+
+            // Only the timeout of the current request marks them stale
+            completion.request_timed_out(completion.request_id - 1);
+            assert_eq!(completion.freshness, CompletionFreshness::Refreshing);
+            completion.request_timed_out(completion.request_id);
+            assert_eq!(completion.freshness, CompletionFreshness::Stale);
+
+            // The answer replaces them, however late
+            receive(completion, &["panic"]);
+            assert_eq!(completion.freshness, CompletionFreshness::Fresh);
+            assert_eq!(completion.filtered_items.len(), 1);
+
+            // A timeout once it's answered changes nothing
+            completion.request_timed_out(completion.request_id);
+            assert_eq!(completion.freshness, CompletionFreshness::Fresh);
+
+            completion.request_id += 1;
+            completion.show_cached();
+            completion.cancel();
+            assert_eq!(completion.freshness, CompletionFreshness::Fresh);
+        });
+    }
+
+    #[test]
+    fn test_should_auto_insert() {
+        let mut config = LapceConfig::default_for_test();
+        config.editor.completion_auto_insert_single = true;
+        with_completion(config.clone(), |completion| {
+            receive(completion, &["println"]);
+            // Only an explicitly requested completion inserts its single item
+            assert!(!completion.should_auto_insert());
+            completion.invoked = true;
+            assert!(completion.should_auto_insert());
+
+            // Not while the item shown is the cached one of an older request
+            completion.show_cached();
+            assert!(!completion.should_auto_insert());
+        });
+        with_completion(config.clone(), |completion| {
+            completion.invoked = true;
+            receive(completion, &["print", "println"]);
+            assert!(!completion.should_auto_insert());
+        });
+
+        config.editor.completion_auto_insert_single = false;
+        with_completion(config, |completion| {
+            completion.invoked = true;
+            receive(completion, &["println"]);
+            assert!(!completion.should_auto_insert());
+        });
+    }
+}
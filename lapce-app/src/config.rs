@@ -6,13 +6,19 @@
 
 use floem::peniko::Color;
 use itertools::Itertools;
-use lapce_core::directory::Directory;
+use lapce_core::{
+    directory::Directory,
+    language::{
+        set_language_configs, set_plugin_grammars, LanguageConfig, PluginGrammar,
+    },
+};
 use lapce_proxy::plugin::wasi::find_all_volts;
-use lapce_rpc::plugin::VoltID;
+use lapce_rpc::plugin::{PluginPermissions, VoltID};
 use lsp_types::{CompletionItemKind, SymbolKind};
 use once_cell::sync::Lazy;
 use parking_lot::RwLock;
 use serde::Deserialize;
+use tracing::error;
 
 use self::{
     color::LapceColor,
@@ -25,7 +31,11 @@
     terminal::TerminalConfig,
     ui::UIConfig,
 };
-use crate::workspace::{LapceWorkspace, LapceWorkspaceType};
+use crate::{
+    profile::Profile,
+    safe_mode::{self, SafeModePart},
+    workspace::{LapceWorkspace, LapceWorkspaceType},
+};
 
 pub mod color;
 pub mod color_theme;
@@ -69,6 +79,9 @@ pub struct LapceConfig {
     pub icon_theme: IconThemeConfig,
     #[serde(flatten)]
     pub plugins: HashMap<String, HashMap<String, serde_json::Value>>,
+    /// The active profile, whose settings apply on top of the user's
+    #[serde(skip)]
+    pub profile: Option<String>,
     #[serde(skip)]
     pub default_color_theme: ColorThemeConfig,
     #[serde(skip)]
@@ -93,11 +106,17 @@ pub struct LapceConfig {
 }
 
 impl LapceConfig {
-    pub fn load(workspace: &LapceWorkspace, disabled_volts: &[VoltID]) -> Self {
-        let config = Self::merge_config(workspace, None, None);
+    pub fn load(
+        workspace: &LapceWorkspace,
+        profile: Option<&str>,
+        disabled_volts: &[VoltID],
+        plugin_permissions: &[(VoltID, PluginPermissions)],
+    ) -> Self {
+        let config = Self::merge_config(workspace, profile, None, None);
         let mut lapce_config: LapceConfig = config
             .try_deserialize()
             .unwrap_or_else(|_| DEFAULT_LAPCE_CONFIG.clone());
+        lapce_config.profile = profile.map(|profile| profile.to_string());
 
         lapce_config.available_color_themes =
             Self::load_color_themes(disabled_volts);
@@ -122,11 +141,53 @@ pub fn load(workspace: &LapceWorkspace, disabled_volts: &[VoltID]) -> Self {
 
         lapce_config.terminal.get_indexed_colors();
 
+        // The languages the plugins add can have settings in `languages.toml`
+        set_plugin_grammars(Self::load_plugin_grammars(
+            disabled_volts,
+            plugin_permissions,
+        ));
+        Self::load_language_configs(workspace);
+
         lapce_config
     }
 
+    /// Load the per-language settings from the user's `languages.toml`,
+    /// and the workspace's `.lapce/languages.toml` on top of it.
+    fn load_language_configs(workspace: &LapceWorkspace) {
+        if safe_mode::is_left_out(SafeModePart::Settings) {
+            set_language_configs(HashMap::new());
+            return;
+        }
+        let mut builder = config::Config::builder();
+        if let Some(path) = Self::languages_file() {
+            builder = builder
+                .add_source(config::File::from(path.as_path()).required(false));
+        }
+        if let LapceWorkspaceType::Local = workspace.kind {
+            if let Some(path) = workspace.path.as_ref() {
+                let path = path.join("./.lapce/languages.toml");
+                builder = builder
+                    .add_source(config::File::from(path.as_path()).required(false));
+            }
+        }
+
+        let configs: HashMap<String, LanguageConfig> = builder
+            .build()
+            .and_then(|config| config.try_deserialize())
+            .unwrap_or_else(|e| {
+                error!("failed to load languages.toml: {e}");
+                HashMap::new()
+            });
+        set_language_configs(configs);
+    }
+
+    /// Merge the sources of the settings, each overriding the ones before it:
+    /// the defaults, the color and icon themes, the user's `settings.toml`,
+    /// the profile's and then the workspace's `.lapce/settings.toml`, so that
+    /// a project can set e.g. its own tab width or theme.
     fn merge_config(
         workspace: &LapceWorkspace,
+        profile: Option<&str>,
         color_theme_config: Option<config::Config>,
         icon_theme_config: Option<config::Config>,
     ) -> config::Config {
@@ -147,6 +208,10 @@ fn merge_config(
                 .unwrap_or_else(|_| config.clone());
         }
 
+        if safe_mode::is_left_out(SafeModePart::Settings) {
+            return config;
+        }
+
         if let Some(path) = Self::settings_file() {
             config = config::Config::builder()
                 .add_source(config.clone())
@@ -155,6 +220,14 @@ fn merge_config(
                 .unwrap_or_else(|_| config.clone());
         }
 
+        if let Some(path) = profile.and_then(Profile::settings_file) {
+            config = config::Config::builder()
+                .add_source(config.clone())
+                .add_source(config::File::from(path.as_path()).required(false))
+                .build()
+                .unwrap_or_else(|_| config.clone());
+        }
+
         match workspace.kind {
             LapceWorkspaceType::Local => {
                 if let Some(path) = workspace.path.as_ref() {
@@ -200,6 +273,12 @@ fn default_lapce_config() -> LapceConfig {
         default_lapce_config
     }
 
+    /// The config of the default settings and color theme, whatever the files
+    /// of the user, for the tests and benchmarks
+    pub fn default_for_test() -> LapceConfig {
+        DEFAULT_LAPCE_CONFIG.clone()
+    }
+
     fn resolve_theme(&mut self, workspace: &LapceWorkspace) {
         let mut default_lapce_config = DEFAULT_LAPCE_CONFIG.clone();
         if let Some((_, color_theme_config)) = self
@@ -235,6 +314,7 @@ fn resolve_theme(&mut self, workspace: &LapceWorkspace) {
         if color_theme_config.is_some() || icon_theme_config.is_some() {
             if let Ok(new) = Self::merge_config(
                 workspace,
+                self.profile.as_deref(),
                 color_theme_config.cloned(),
                 icon_theme_config.cloned(),
             )
@@ -294,6 +374,14 @@ pub fn set_icon_theme(&mut self, workspace: &LapceWorkspace, theme: &str) {
         self.resolve_theme(workspace);
     }
 
+    /// Zoom the editor font by the given number of points, on top of its
+    /// configured size.  
+    /// Note that this does not save the config.
+    pub fn zoom_editor_font(&mut self, delta: isize) {
+        self.editor.zoom_font(delta);
+        self.update_id();
+    }
+
     /// Get the color by the name from the current theme if it exists
     /// Otherwise, get the color from the base them
     /// # Panics
@@ -311,6 +399,16 @@ pub fn get_style_color(&self, name: &str) -> Option<&Color> {
         self.color.syntax.get(name)
     }
 
+    /// Get a color given by a plugin, either the name of a color of the theme
+    /// or a hex color
+    pub fn plugin_color(&self, color: &str) -> Option<Color> {
+        self.color
+            .ui
+            .get(color)
+            .copied()
+            .or_else(|| Color::parse(color))
+    }
+
     pub fn completion_color(
         &self,
         kind: Option<CompletionItemKind>,
@@ -449,6 +547,53 @@ fn load_plugin_color_themes(
         themes
     }
 
+    /// The grammars, queries and language settings of the enabled plugins,
+    /// by language. The grammar libraries are only loaded from the plugins
+    /// the user allowed to load native code.
+    fn load_plugin_grammars(
+        disabled_volts: &[VoltID],
+        plugin_permissions: &[(VoltID, PluginPermissions)],
+    ) -> HashMap<String, PluginGrammar> {
+        let mut grammars = HashMap::new();
+        for meta in find_all_volts() {
+            if disabled_volts.contains(&meta.id()) {
+                continue;
+            }
+            let native_code = plugin_permissions.iter().any(|(id, permissions)| {
+                *id == meta.id() && permissions.native_code
+            });
+            for grammar in meta.grammars.clone().unwrap_or_default() {
+                let config = match grammar.config {
+                    Some(config) => {
+                        serde_json::from_value(config).unwrap_or_else(|e| {
+                            error!(
+                                "invalid {} settings in {}: {e}",
+                                grammar.language, meta.name
+                            );
+                            LanguageConfig::default()
+                        })
+                    }
+                    None => LanguageConfig::default(),
+                };
+                grammars.insert(
+                    grammar.language.to_lowercase(),
+                    PluginGrammar {
+                        display_name: grammar.name,
+                        extensions: grammar.extensions.unwrap_or_default(),
+                        files: grammar.files.unwrap_or_default(),
+                        grammar_dir: grammar
+                            .grammar
+                            .filter(|_| native_code)
+                            .map(PathBuf::from),
+                        queries_dir: grammar.queries.map(PathBuf::from),
+                        config,
+                    },
+                );
+            }
+        }
+        grammars
+    }
+
     fn load_plugin_icon_themes(
         disabled_volts: &[VoltID],
     ) -> HashMap<String, (String, config::Config, PathBuf)> {
@@ -502,6 +647,19 @@ pub fn settings_file() -> Option<PathBuf> {
         Some(path)
     }
 
+    pub fn languages_file() -> Option<PathBuf> {
+        let path = Directory::config_directory()?.join("languages.toml");
+
+        if !path.exists() {
+            let _ = std::fs::OpenOptions::new()
+                .create_new(true)
+                .write(true)
+                .open(&path);
+        }
+
+        Some(path)
+    }
+
     pub fn keymaps_file() -> Option<PathBuf> {
         let path = Directory::config_directory()?.join("keymaps.toml");
 
@@ -527,6 +685,11 @@ pub fn ui_svg(&self, icon: &'static str) -> String {
         })
     }
 
+    /// The SVG of a file a plugin gives, like the gutter icons of decorations
+    pub fn plugin_svg(&self, path: &Path) -> Option<String> {
+        self.svg_store.write().get_svg_on_disk(path)
+    }
+
     pub fn file_svg(&self, path: &Path) -> (String, Option<&Color>) {
         let svg = self
             .icon_theme
@@ -587,6 +750,25 @@ pub fn icon_theme_list(&self) -> im::Vector<String> {
         self.icon_theme_list.clone()
     }
 
+    /// The editor background, foreground and caret colors of a color theme,
+    /// for previewing it without switching to it.
+    pub fn color_theme_preview(&self, name: &str) -> Option<[Color; 3]> {
+        let (_, color_theme_config) =
+            self.available_color_themes.get(&name.to_lowercase())?;
+        let mut theme = config::Config::builder()
+            .add_source(DEFAULT_CONFIG.clone())
+            .add_source(color_theme_config.clone())
+            .build()
+            .and_then(|theme| theme.try_deserialize::<LapceConfig>())
+            .ok()?;
+        theme.resolve_colors(Some(&DEFAULT_LAPCE_CONFIG));
+        Some([
+            *theme.get_color(LapceColor::EDITOR_BACKGROUND),
+            *theme.get_color(LapceColor::EDITOR_FOREGROUND),
+            *theme.get_color(LapceColor::EDITOR_CARET),
+        ])
+    }
+
     pub fn terminal_font_family(&self) -> &str {
         if self.terminal.font_family.is_empty() {
             self.editor.font_family.as_str()
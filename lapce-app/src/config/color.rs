@@ -20,6 +20,8 @@ pub struct LapceColor {}
 impl LapceColor {
     pub const LAPCE_WARN: &str = "lapce.warn";
     pub const LAPCE_ERROR: &str = "lapce.error";
+    pub const LAPCE_INFO: &str = "lapce.info";
+    pub const LAPCE_HINT: &str = "lapce.hint";
     pub const LAPCE_DROPDOWN_SHADOW: &str = "lapce.dropdown_shadow";
     pub const LAPCE_BORDER: &str = "lapce.border";
     pub const LAPCE_SCROLL_BAR: &str = "lapce.scroll_bar";
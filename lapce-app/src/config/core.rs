@@ -1,3 +1,6 @@
+use std::path::PathBuf;
+
+use lapce_rpc::proxy::NetworkSettings;
 use serde::{Deserialize, Serialize};
 use structdesc::FieldNames;
 
@@ -14,4 +17,54 @@ pub struct CoreConfig {
         desc = "Enable customised titlebar and disable OS native one (Linux, BSD, Windows)"
     )]
     pub custom_titlebar: bool,
+    #[field_names(
+        desc = "Fuzzy matching bonus for matching the start of a camelCase word or of a number"
+    )]
+    pub fuzzy_camel_case_bonus: i64,
+    #[field_names(
+        desc = "Fuzzy matching bonus for matching the character after a path separator"
+    )]
+    pub fuzzy_path_separator_bonus: i64,
+    #[field_names(
+        desc = "Fuzzy matching bonus for recently used items, which halves every minute"
+    )]
+    pub fuzzy_recency_boost: i64,
+    #[field_names(
+        desc = "Fuzzy matching bonus for the files opened often and recently, which the most frecent files get close to"
+    )]
+    pub fuzzy_frecency_boost: i64,
+    #[field_names(
+        desc = "Show the files ignored by .gitignore and the other ignore files in the file explorer, dimmed, and in the file finder"
+    )]
+    pub show_ignored_files: bool,
+    #[field_names(
+        desc = "Check for new releases of Lapce in the background every hour"
+    )]
+    pub check_for_updates: bool,
+    #[field_names(
+        desc = "The proxy for installing plugins, checking for updates and the other network requests, such as http://127.0.0.1:8080. If empty, the proxy is taken from the environment"
+    )]
+    pub http_proxy: String,
+    #[field_names(
+        desc = "A PEM file of certificate authorities to trust in addition to the ones of the system, for networks that intercept TLS"
+    )]
+    pub ca_certificates: String,
+    #[field_names(
+        desc = "Turn off all network requests, such as installing plugins and checking for updates"
+    )]
+    pub offline: bool,
+}
+
+impl CoreConfig {
+    pub fn network_settings(&self) -> NetworkSettings {
+        NetworkSettings {
+            proxy: (!self.http_proxy.is_empty()).then(|| self.http_proxy.clone()),
+            ca_certificates: if self.ca_certificates.is_empty() {
+                Vec::new()
+            } else {
+                vec![PathBuf::from(&self.ca_certificates)]
+            },
+            offline: self.offline,
+        }
+    }
 }
@@ -1,3 +1,9 @@
+use std::collections::HashMap;
+
+use lapce_core::{
+    buffer::diff::{DiffAlgorithm, DiffOptions},
+    editor::scroll_beyond_last_line,
+};
 use serde::{Deserialize, Serialize};
 use structdesc::FieldNames;
 
@@ -12,6 +18,15 @@ pub enum ClickMode {
     DoubleClickAll,
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub enum CompletionPreselect {
+    #[default]
+    #[serde(rename = "first")]
+    First,
+    #[serde(rename = "recent")]
+    RecentlyUsed,
+}
+
 #[derive(FieldNames, Debug, Clone, Deserialize, Serialize, Default)]
 #[serde(rename_all = "kebab-case")]
 pub struct EditorConfig {
@@ -27,24 +42,69 @@ pub struct EditorConfig {
     line_height: f64,
     #[field_names(desc = "Set the tab width")]
     pub tab_width: usize,
+    #[field_names(
+        desc = "Experimental: Align the tab separated columns of adjacent lines with elastic tabstops"
+    )]
+    pub elastic_tabstops: bool,
+    #[field_names(desc = "Set the width at which reflowing hard-wraps lines")]
+    pub text_width: usize,
+    #[field_names(desc = "Set the language the scratchpad is highlighted as")]
+    pub scratchpad_language: String,
     #[field_names(desc = "If opened editors are shown in a tab")]
     pub show_tab: bool,
     #[field_names(desc = "If navigation breadcrumbs are shown for the file")]
     pub show_bread_crumbs: bool,
     #[field_names(desc = "If the editor can scroll beyond the last line")]
     pub scroll_beyond_last_line: bool,
+    #[field_names(
+        desc = "Set how far the editor scrolls beyond the last line, in percent of the viewport"
+    )]
+    pub scroll_beyond_last_line_percent: usize,
     #[field_names(
         desc = "Set the minimum number of visible lines above and below the cursor"
     )]
     pub cursor_surrounding_lines: usize,
+    #[field_names(
+        desc = "Set the minimum number of visible columns left and right of the cursor"
+    )]
+    pub cursor_surrounding_columns: usize,
+    #[field_names(
+        desc = "If the editor scrolls smoothly after the cursor while a held key moves it"
+    )]
+    pub smooth_scrolling: bool,
     #[field_names(
         desc = "Show code context like functions and classes at the top of editor when scroll"
     )]
     pub sticky_header: bool,
+    #[field_names(
+        desc = "Set how many minutes focus mode lasts before turning itself off, or 0 to keep it on until toggled off"
+    )]
+    pub focus_mode_minutes: u64,
+    #[field_names(
+        desc = "If fitting the selection in the editor can zoom the font out, until the view is restored"
+    )]
+    pub fit_selection_zoom: bool,
     #[field_names(
         desc = "If the editor should show the documentation of the current completion item"
     )]
     pub completion_show_documentation: bool,
+    #[field_names(
+        desc = "If Enter accepts the current completion item, rather than only Tab"
+    )]
+    pub completion_accept_on_enter: bool,
+    #[field_names(
+        desc = "If an explicitly requested completion with a single matching item inserts it right away"
+    )]
+    pub completion_auto_insert_single: bool,
+    #[field_names(
+        desc = "Which completion item is preselected.\nOptions: first (the best ranked), recent (the most recently accepted)."
+    )]
+    pub completion_preselect: CompletionPreselect,
+    /// The languages for which completion is not shown while typing,
+    /// by language name.
+    #[serde(default)]
+    #[field_names(skip)]
+    pub completion_disabled_languages: Vec<String>,
     #[field_names(
         desc = "If the editor should show the signature of the function as the parameters are being typed"
     )]
@@ -69,6 +129,14 @@ pub struct EditorConfig {
         desc = "Whether it should format the document on save (if there is an available formatter)"
     )]
     pub format_on_save: bool,
+    /// Per-language overrides of `format_on_save`, keyed by language name.
+    #[serde(default)]
+    #[field_names(skip)]
+    pub format_on_save_languages: HashMap<String, bool>,
+    #[field_names(
+        desc = "Whether it should organize imports on save (if the language server supports it)"
+    )]
+    pub organize_imports_on_save: bool,
 
     #[field_names(desc = "If matching brackets are highlighted")]
     pub highlight_matching_brackets: bool,
@@ -113,6 +181,14 @@ pub struct EditorConfig {
         desc = "Set the completion lens font size. If 0 it uses the inlay hint font size."
     )]
     pub completion_lens_font_size: usize,
+    #[field_names(
+        desc = "If the editor should suggest text to insert after the cursor as phantom text, accepted with Tab"
+    )]
+    pub enable_inline_suggestions: bool,
+    #[field_names(
+        desc = "Record the edits made to files with when they were made, to replay them with \"Replay Edits of Active File\""
+    )]
+    pub record_edits: bool,
     #[field_names(
         desc = "Set the cursor blink interval (in milliseconds). Set to 0 to completely disable."
     )]
@@ -125,6 +201,10 @@ pub struct EditorConfig {
         desc = "Whether the multiple cursor selection only selects whole words."
     )]
     pub multicursor_whole_words: bool,
+    #[field_names(
+        desc = "Whether the other occurrences of the selected text should be highlighted."
+    )]
+    pub highlight_selection_occurrences: bool,
     #[field_names(
         desc = "How the editor should render whitespace characters.\nOptions: none, all, boundary, trailing."
     )]
@@ -153,6 +233,18 @@ pub struct EditorConfig {
         desc = "Set the default number of visible lines above and below the diff block (-1 for infinite)"
     )]
     pub diff_context_lines: i32,
+    #[field_names(
+        desc = "How the changed lines of diffs and of the git gutter are found.\nOptions: myers (the fewest changed lines), patience, histogram (which keep moved and reindented blocks together)."
+    )]
+    pub diff_algorithm: DiffAlgorithm,
+    #[field_names(
+        desc = "If lines that only differ in their whitespace are the same in diffs and in the git gutter"
+    )]
+    pub diff_ignore_whitespace: bool,
+    #[field_names(
+        desc = "If lines that only differ in their case are the same in diffs and in the git gutter"
+    )]
+    pub diff_ignore_case: bool,
 }
 
 impl EditorConfig {
@@ -160,6 +252,36 @@ pub fn font_size(&self) -> usize {
         self.font_size.max(6).min(32)
     }
 
+    pub fn diff_options(&self) -> DiffOptions {
+        DiffOptions {
+            algorithm: self.diff_algorithm,
+            ignore_whitespace: self.diff_ignore_whitespace,
+            ignore_case: self.diff_ignore_case,
+        }
+    }
+
+    /// Zoom the font by the given number of points
+    pub fn zoom_font(&mut self, delta: isize) {
+        self.font_size = (self.font_size as isize + delta).max(0) as usize;
+    }
+
+    /// How many points the font has to be zoomed out by for the lines to fit
+    /// in the height, as far as the smallest size
+    pub fn fit_zoom(&self, lines: usize, height: f64) -> isize {
+        // The zoom starts from the size the font is shown at, which a size
+        // set over the largest one isn't
+        let mut zoomed = self.clone();
+        zoomed.font_size = self.font_size();
+        while lines as f64 * zoomed.line_height() as f64 > height {
+            let font_size = zoomed.font_size();
+            zoomed.zoom_font(-1);
+            if zoomed.font_size() == font_size {
+                break;
+            }
+        }
+        (zoomed.font_size() as isize - self.font_size as isize).min(0)
+    }
+
     pub fn line_height(&self) -> usize {
         const SCALE_OR_SIZE_LIMIT: f64 = 5.0;
 
@@ -173,6 +295,19 @@ pub fn line_height(&self) -> usize {
         (line_height.round() as usize).max(self.font_size)
     }
 
+    /// The height the editor can scroll beyond the last line in a viewport of
+    /// `viewport_height`
+    pub fn scroll_beyond_last_line_height(&self, viewport_height: f64) -> f64 {
+        if !self.scroll_beyond_last_line {
+            return 0.0;
+        }
+        scroll_beyond_last_line(
+            viewport_height,
+            self.line_height() as f64,
+            self.scroll_beyond_last_line_percent,
+        )
+    }
+
     pub fn inlay_hint_font_size(&self) -> usize {
         if self.inlay_hint_font_size < 5
             || self.inlay_hint_font_size > self.font_size
@@ -207,4 +342,57 @@ pub fn atomic_soft_tab_width(&self) -> Option<usize> {
             None
         }
     }
+
+    /// Whether documents of the given language should be formatted on save,
+    /// taking per-language overrides into account.
+    pub fn format_on_save(&self, language: &str) -> bool {
+        self.format_on_save_languages
+            .get(&language.to_lowercase())
+            .copied()
+            .unwrap_or(self.format_on_save)
+    }
+
+    /// Whether completion is shown in documents of the given language.
+    pub fn completion_enabled(&self, language: &str) -> bool {
+        !self
+            .completion_disabled_languages
+            .iter()
+            .any(|l| l.eq_ignore_ascii_case(language))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::LapceConfig;
+
+    #[test]
+    fn test_completion_enabled() {
+        let mut config = LapceConfig::default_for_test().editor;
+        assert!(config.completion_enabled("rust"));
+
+        config.completion_disabled_languages =
+            vec!["Markdown".to_string(), "plaintext".to_string()];
+        assert!(!config.completion_enabled("markdown"));
+        assert!(!config.completion_enabled("PlainText"));
+        assert!(config.completion_enabled("rust"));
+    }
+
+    #[test]
+    fn test_fit_zoom() {
+        let mut config = LapceConfig::default_for_test().editor;
+        config.font_size = 14;
+        config.line_height = 20.0;
+        assert_eq!(config.fit_zoom(10, 200.0), 0);
+
+        // The line height is a scale of the font size
+        config.line_height = 1.5;
+        assert_eq!(config.fit_zoom(10, 120.0), -6);
+        // It stops at the smallest size when the lines can't fit
+        assert_eq!(config.fit_zoom(100, 120.0), -8);
+
+        // A size over the largest one is zoomed from the largest one
+        config.font_size = 40;
+        assert_eq!(config.fit_zoom(10, 480.0), -8);
+        assert_eq!(config.fit_zoom(10, 270.0), -22);
+    }
 }
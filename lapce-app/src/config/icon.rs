@@ -30,6 +30,7 @@ impl LapceIcons {
     pub const SETTINGS: &str = "settings";
     pub const LIGHTBULB: &str = "lightbulb";
     pub const EXTENSIONS: &str = "extensions";
+    pub const PLUGIN_VIEWS: &str = "plugin_views";
     pub const BREADCRUMB_SEPARATOR: &str = "breadcrumb_separator";
 
     pub const FILE: &str = "file";
@@ -87,8 +88,10 @@ impl LapceIcons {
     pub const SEARCH_CASE_SENSITIVE: &'static str = "search.case_sensitive";
     pub const SEARCH_WHOLE_WORD: &'static str = "search.whole_word";
     pub const SEARCH_REGEX: &'static str = "search.regex";
+    pub const SEARCH_IN_SELECTION: &'static str = "search.in_selection";
     pub const SEARCH_REPLACE: &'static str = "search.replace";
     pub const SEARCH_REPLACE_ALL: &'static str = "search.replace_all";
+    pub const SEARCH_OPEN_EDITOR: &'static str = "search.open_editor";
 
     pub const FILE_TYPE_CODE: &str = "file-code";
     pub const FILE_TYPE_MEDIA: &str = "file-media";
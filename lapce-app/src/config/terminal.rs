@@ -21,6 +21,11 @@ pub struct TerminalConfig {
     pub line_height: usize,
     #[field_names(desc = "Set the terminal Shell")]
     pub shell: String,
+    /// The program used by "Run Current File" for each language, when the
+    /// file has no shebang line. Keyed by lowercase language name.
+    #[serde(default)]
+    #[field_names(skip)]
+    pub runners: HashMap<String, String>,
 
     #[serde(skip)]
     #[field_names(skip)]
@@ -24,6 +24,16 @@ pub struct UIConfig {
     #[field_names(desc = "Set the height for status line")]
     status_height: usize,
 
+    #[field_names(
+        desc = "Set the format of the status bar, with variables such as ${mode}, ${file}, ${path}, ${dirty}, ${branch}, ${language}, ${line}, ${column}, ${diag:errors} and ${diag:warnings}. If empty, it uses the default layout."
+    )]
+    pub status_format: String,
+
+    #[field_names(
+        desc = "Set the format of the editor tab titles, with variables such as ${file}, ${dir}, ${path} and ${dirty}. If empty, it shows the file name."
+    )]
+    pub tab_title_format: String,
+
     #[field_names(desc = "Set the minimum width for editor tab")]
     tab_min_width: usize,
 
@@ -7,12 +7,17 @@
 use crossbeam_channel::{unbounded, Sender};
 use floem::{peniko::kurbo::Vec2, reactive::SignalGetUntracked};
 use lapce_core::directory::Directory;
-use lapce_rpc::plugin::VoltID;
+use lapce_rpc::plugin::{PluginPermissions, VoltID};
 
 use crate::{
     app::{AppData, AppInfo},
+    burn_down::BurnDownInfo,
     doc::DocInfo,
+    frecency::FileFrecency,
+    keypress::review::KeymapEntry,
     panel::{data::PanelOrder, kind::PanelKind, position::PanelPosition},
+    safe_mode,
+    search_history::SearchHistoryInfo,
     window::{WindowData, WindowInfo},
     window_tab::WindowTabData,
     workspace::{LapceWorkspace, WorkspaceInfo},
@@ -25,7 +30,15 @@ pub enum SaveEvent {
     Doc(DocInfo),
     DisabledVolts(Vec<VoltID>),
     WorkspaceDisabledVolts(Arc<LapceWorkspace>, Vec<VoltID>),
+    PluginPermissions(Vec<(VoltID, PluginPermissions)>),
     PanelOrder(PanelOrder),
+    SearchHistory(Arc<LapceWorkspace>, SearchHistoryInfo),
+    FileFrecency(Arc<LapceWorkspace>, FileFrecency),
+    ActiveProfile(Option<String>),
+    WorkspaceProfile(Arc<LapceWorkspace>, Option<String>),
+    Scratchpad(Arc<LapceWorkspace>, String),
+    BurnDown(Arc<LapceWorkspace>, BurnDownInfo),
+    ReviewedKeymaps(Vec<KeymapEntry>),
 }
 
 #[derive(Clone)]
@@ -72,9 +85,34 @@ pub fn new() -> Result<Self> {
                         let _ = local_db
                             .insert_workspace_disabled_volts(workspace, volts);
                     }
+                    SaveEvent::PluginPermissions(permissions) => {
+                        let _ = local_db.insert_plugin_permissions(&permissions);
+                    }
                     SaveEvent::PanelOrder(order) => {
                         let _ = local_db.insert_panel_orders(&order);
                     }
+                    SaveEvent::SearchHistory(workspace, info) => {
+                        let _ = local_db.insert_search_history(&workspace, &info);
+                    }
+                    SaveEvent::FileFrecency(workspace, frecency) => {
+                        let _ = local_db.insert_file_frecency(&workspace, &frecency);
+                    }
+                    SaveEvent::ActiveProfile(profile) => {
+                        let _ = local_db.insert_active_profile(&profile);
+                    }
+                    SaveEvent::WorkspaceProfile(workspace, profile) => {
+                        let _ =
+                            local_db.insert_workspace_profile(&workspace, &profile);
+                    }
+                    SaveEvent::Scratchpad(workspace, text) => {
+                        let _ = local_db.insert_scratchpad(&workspace, &text);
+                    }
+                    SaveEvent::BurnDown(workspace, info) => {
+                        let _ = local_db.insert_burn_down(&workspace, &info);
+                    }
+                    SaveEvent::ReviewedKeymaps(keymaps) => {
+                        let _ = local_db.insert_reviewed_keymaps(&keymaps);
+                    }
                 }
             }
         });
@@ -144,6 +182,248 @@ pub fn get_workspace_disabled_volts(
         Ok(volts)
     }
 
+    /// The permissions granted to the plugins that were reviewed
+    pub fn get_plugin_permissions(
+        &self,
+    ) -> Result<Vec<(VoltID, PluginPermissions)>> {
+        let sled_db = self.get_db()?;
+        let permissions = sled_db
+            .get("plugin_permissions")?
+            .ok_or_else(|| anyhow!("can't find plugin permissions"))?;
+        let permissions = std::str::from_utf8(&permissions)?;
+        let permissions: Vec<(VoltID, PluginPermissions)> =
+            serde_json::from_str(permissions)?;
+        Ok(permissions)
+    }
+
+    pub fn save_plugin_permissions(
+        &self,
+        permissions: Vec<(VoltID, PluginPermissions)>,
+    ) {
+        let _ = self.save_tx.send(SaveEvent::PluginPermissions(permissions));
+    }
+
+    fn insert_plugin_permissions(
+        &self,
+        permissions: &[(VoltID, PluginPermissions)],
+    ) -> Result<()> {
+        let sled_db = self.get_db()?;
+        let permissions = serde_json::to_string(permissions)?;
+        sled_db.insert(b"plugin_permissions", permissions.as_str())?;
+        sled_db.flush()?;
+        Ok(())
+    }
+
+    /// The default keymaps as the user last reviewed them. Errors if they
+    /// were never reviewed.
+    pub fn get_reviewed_keymaps(&self) -> Result<Vec<KeymapEntry>> {
+        let sled_db = self.get_db()?;
+        let keymaps = sled_db
+            .get("reviewed_keymaps")?
+            .ok_or_else(|| anyhow!("can't find reviewed keymaps"))?;
+        let keymaps = std::str::from_utf8(&keymaps)?;
+        let keymaps: Vec<KeymapEntry> = serde_json::from_str(keymaps)?;
+        Ok(keymaps)
+    }
+
+    pub fn save_reviewed_keymaps(&self, keymaps: Vec<KeymapEntry>) {
+        let _ = self.save_tx.send(SaveEvent::ReviewedKeymaps(keymaps));
+    }
+
+    fn insert_reviewed_keymaps(&self, keymaps: &[KeymapEntry]) -> Result<()> {
+        let sled_db = self.get_db()?;
+        let keymaps = serde_json::to_string(keymaps)?;
+        sled_db.insert(b"reviewed_keymaps", keymaps.as_str())?;
+        sled_db.flush()?;
+        Ok(())
+    }
+
+    /// The profile last switched to, `None` for the default one
+    pub fn get_active_profile(&self) -> Result<Option<String>> {
+        let sled_db = self.get_db()?;
+        let profile = sled_db
+            .get("active_profile")?
+            .ok_or_else(|| anyhow!("can't find active profile"))?;
+        let profile = std::str::from_utf8(&profile)?;
+        let profile: Option<String> = serde_json::from_str(profile)?;
+        Ok(profile)
+    }
+
+    pub fn save_active_profile(&self, profile: Option<String>) {
+        let _ = self.save_tx.send(SaveEvent::ActiveProfile(profile));
+    }
+
+    fn insert_active_profile(&self, profile: &Option<String>) -> Result<()> {
+        let sled_db = self.get_db()?;
+        let profile = serde_json::to_string(profile)?;
+        sled_db.insert(b"active_profile", profile.as_str())?;
+        sled_db.flush()?;
+        Ok(())
+    }
+
+    /// The profile the workspace opens with, `None` for the default one. Errors
+    /// if the workspace wasn't assigned a profile.
+    pub fn get_workspace_profile(
+        &self,
+        workspace: &LapceWorkspace,
+    ) -> Result<Option<String>> {
+        let sled_db = self.get_db()?;
+        let profile = sled_db
+            .get(format!("profile:{workspace}"))?
+            .ok_or_else(|| anyhow!("can't find workspace profile"))?;
+        let profile = std::str::from_utf8(&profile)?;
+        let profile: Option<String> = serde_json::from_str(profile)?;
+        Ok(profile)
+    }
+
+    pub fn save_workspace_profile(
+        &self,
+        workspace: Arc<LapceWorkspace>,
+        profile: Option<String>,
+    ) {
+        let _ = self
+            .save_tx
+            .send(SaveEvent::WorkspaceProfile(workspace, profile));
+    }
+
+    fn insert_workspace_profile(
+        &self,
+        workspace: &LapceWorkspace,
+        profile: &Option<String>,
+    ) -> Result<()> {
+        let sled_db = self.get_db()?;
+        let profile = serde_json::to_string(profile)?;
+        sled_db.insert(format!("profile:{workspace}"), profile.as_str())?;
+        sled_db.flush()?;
+        Ok(())
+    }
+
+    /// The text of the scratchpad of the workspace
+    pub fn get_scratchpad(&self, workspace: &LapceWorkspace) -> Result<String> {
+        let sled_db = self.get_db()?;
+        let text = sled_db
+            .get(format!("scratchpad:{workspace}"))?
+            .ok_or_else(|| anyhow!("can't find scratchpad"))?;
+        Ok(std::str::from_utf8(&text)?.to_string())
+    }
+
+    pub fn save_scratchpad(&self, workspace: Arc<LapceWorkspace>, text: String) {
+        let _ = self.save_tx.send(SaveEvent::Scratchpad(workspace, text));
+    }
+
+    fn insert_scratchpad(
+        &self,
+        workspace: &LapceWorkspace,
+        text: &str,
+    ) -> Result<()> {
+        let sled_db = self.get_db()?;
+        sled_db.insert(format!("scratchpad:{workspace}"), text)?;
+        sled_db.flush()?;
+        Ok(())
+    }
+
+    /// The progress of the diagnostics burn-down of the workspace
+    pub fn get_burn_down(&self, workspace: &LapceWorkspace) -> Result<BurnDownInfo> {
+        let sled_db = self.get_db()?;
+        let info = sled_db
+            .get(format!("burn_down:{workspace}"))?
+            .ok_or_else(|| anyhow!("can't find burn down"))?;
+        let info = std::str::from_utf8(&info)?;
+        let info: BurnDownInfo = serde_json::from_str(info)?;
+        Ok(info)
+    }
+
+    pub fn save_burn_down(
+        &self,
+        workspace: Arc<LapceWorkspace>,
+        info: BurnDownInfo,
+    ) {
+        let _ = self.save_tx.send(SaveEvent::BurnDown(workspace, info));
+    }
+
+    fn insert_burn_down(
+        &self,
+        workspace: &LapceWorkspace,
+        info: &BurnDownInfo,
+    ) -> Result<()> {
+        let sled_db = self.get_db()?;
+        let info = serde_json::to_string(info)?;
+        sled_db.insert(format!("burn_down:{workspace}"), info.as_str())?;
+        sled_db.flush()?;
+        Ok(())
+    }
+
+    pub fn get_search_history(
+        &self,
+        workspace: &LapceWorkspace,
+    ) -> Result<SearchHistoryInfo> {
+        let sled_db = self.get_db()?;
+        let info = sled_db
+            .get(format!("search_history:{workspace}"))?
+            .ok_or_else(|| anyhow!("can't find search history"))?;
+        let info = std::str::from_utf8(&info)?;
+        let info: SearchHistoryInfo = serde_json::from_str(info)?;
+        Ok(info)
+    }
+
+    pub fn save_search_history(
+        &self,
+        workspace: Arc<LapceWorkspace>,
+        info: SearchHistoryInfo,
+    ) {
+        let _ = self.save_tx.send(SaveEvent::SearchHistory(workspace, info));
+    }
+
+    fn insert_search_history(
+        &self,
+        workspace: &LapceWorkspace,
+        info: &SearchHistoryInfo,
+    ) -> Result<()> {
+        let sled_db = self.get_db()?;
+        let info = serde_json::to_string(info)?;
+        sled_db.insert(format!("search_history:{workspace}"), info.as_str())?;
+        sled_db.flush()?;
+        Ok(())
+    }
+
+    pub fn get_file_frecency(
+        &self,
+        workspace: &LapceWorkspace,
+    ) -> Result<FileFrecency> {
+        let sled_db = self.get_db()?;
+        let frecency = sled_db
+            .get(format!("file_frecency:{workspace}"))?
+            .ok_or_else(|| anyhow!("can't find file frecency"))?;
+        let frecency = std::str::from_utf8(&frecency)?;
+        let frecency: FileFrecency = serde_json::from_str(frecency)?;
+        Ok(frecency)
+    }
+
+    pub fn save_file_frecency(
+        &self,
+        workspace: Arc<LapceWorkspace>,
+        frecency: FileFrecency,
+    ) {
+        if safe_mode::is_active() {
+            return;
+        }
+        let _ = self
+            .save_tx
+            .send(SaveEvent::FileFrecency(workspace, frecency));
+    }
+
+    fn insert_file_frecency(
+        &self,
+        workspace: &LapceWorkspace,
+        frecency: &FileFrecency,
+    ) -> Result<()> {
+        let sled_db = self.get_db()?;
+        let frecency = serde_json::to_string(frecency)?;
+        sled_db.insert(format!("file_frecency:{workspace}"), frecency.as_str())?;
+        sled_db.flush()?;
+        Ok(())
+    }
+
     pub fn recent_workspaces(&self) -> Result<Vec<LapceWorkspace>> {
         let sled_db = self.get_db()?;
         let workspaces = sled_db
@@ -155,7 +435,7 @@ pub fn recent_workspaces(&self) -> Result<Vec<LapceWorkspace>> {
     }
 
     pub fn update_recent_workspace(&self, workspace: &LapceWorkspace) -> Result<()> {
-        if workspace.path.is_none() {
+        if workspace.path.is_none() || safe_mode::is_active() {
             return Ok(());
         }
         self.save_tx
@@ -220,6 +500,10 @@ pub fn get_workspace_info(
     }
 
     pub fn save_window_tab(&self, data: Arc<WindowTabData>) -> Result<()> {
+        // The session of a safe mode start isn't kept over the user's own one
+        if safe_mode::is_active() {
+            return Ok(());
+        }
         let workspace = (*data.workspace).clone();
         let workspace_info = data.workspace_info();
 
@@ -244,6 +528,9 @@ fn insert_workspace(
     }
 
     pub fn save_app(&self, data: &AppData) -> Result<()> {
+        if safe_mode::is_active() {
+            return Ok(());
+        }
         let windows = data.windows.get_untracked();
         for window in &windows {
             let _ = self.save_window(window.clone());
@@ -270,6 +557,9 @@ pub fn insert_app_info(&self, info: AppInfo) -> Result<()> {
     }
 
     pub fn insert_app(&self, data: AppData) -> Result<()> {
+        if safe_mode::is_active() {
+            return Ok(());
+        }
         let windows = data.windows.get_untracked();
         for window in &windows {
             let _ = self.insert_window(window.clone());
@@ -358,6 +648,9 @@ pub fn get_panel_orders(&self) -> Result<PanelOrder> {
     }
 
     pub fn save_panel_orders(&self, order: PanelOrder) {
+        if safe_mode::is_active() {
+            return;
+        }
         let _ = self.save_tx.send(SaveEvent::PanelOrder(order));
     }
 
@@ -376,6 +669,9 @@ pub fn save_doc_position(
         cursor_offset: usize,
         scroll_offset: Vec2,
     ) {
+        if safe_mode::is_active() {
+            return;
+        }
         let info = DocInfo {
             workspace: workspace.clone(),
             path,
@@ -62,11 +62,113 @@ pub fn run_configs(workspace: Option<&Path>) -> Option<RunDebugConfigs> {
     Some(configs)
 }
 
+/// Build a run config that executes `path` directly, using the interpreter
+/// named in its shebang line or, failing that, the runner configured for its
+/// language. The file's directory is used as the working directory.
+pub fn current_file_run_config(
+    path: &Path,
+    first_line: &str,
+    language: &str,
+    runners: &HashMap<String, String>,
+) -> Option<RunDebugConfig> {
+    let program = shebang_program(first_line).or_else(|| {
+        runners
+            .get(&language.to_lowercase())
+            .filter(|runner| !runner.trim().is_empty())
+            .cloned()
+    })?;
+
+    let file = path.to_str()?.to_string();
+
+    Some(RunDebugConfig {
+        name: format!(
+            "Run {}",
+            path.file_name().and_then(|n| n.to_str()).unwrap_or("")
+        ),
+        program,
+        args: vec![file],
+        cwd: path
+            .parent()
+            .and_then(|p| p.to_str())
+            .map(|p| p.to_string()),
+        env: None,
+        debug_command: None,
+        dap_id: DapId::next(),
+    })
+}
+
+/// The program of the config split into its arguments, followed by the
+/// arguments of the config, for the terminal to run without a shell
+pub fn run_command(config: &RunDebugConfig) -> Vec<String> {
+    let mut command = split_args(&config.program);
+    command.extend(config.args.iter().cloned());
+    command
+}
+
+/// Split the arguments typed for a run on the whitespace outside of quotes,
+/// as a shell would. A backslash keeps a quote, a space or a backslash after
+/// it as it is, and is kept itself before anything else so that Windows paths
+/// come through.
+pub fn split_args(input: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut arg = String::new();
+    // Whether an argument was started, which can be an empty quoted one
+    let mut started = false;
+    let mut quote = None;
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('\''), c) => arg.push(c),
+            (_, '\\') => match chars.peek() {
+                Some(&next)
+                    if next == '"'
+                        || next == '\\'
+                        || (quote.is_none()
+                            && (next == '\'' || next.is_whitespace())) =>
+                {
+                    arg.push(next);
+                    chars.next();
+                }
+                _ => arg.push('\\'),
+            },
+            (Some(_), c) => arg.push(c),
+            (None, '"' | '\'') => quote = Some(c),
+            (None, c) if c.is_whitespace() => {
+                if started {
+                    args.push(std::mem::take(&mut arg));
+                    started = false;
+                }
+                continue;
+            }
+            (None, c) => arg.push(c),
+        }
+        started = true;
+    }
+    if started {
+        args.push(arg);
+    }
+    args
+}
+
+/// Extract the interpreter command from a shebang line, e.g.
+/// `#!/usr/bin/env python3` gives `/usr/bin/env python3`.
+fn shebang_program(first_line: &str) -> Option<String> {
+    let program = first_line.strip_prefix("#!")?.trim();
+    if program.is_empty() {
+        None
+    } else {
+        Some(program.to_string())
+    }
+}
+
 #[derive(Clone)]
 pub struct RunDebugData {
     pub active_term: RwSignal<Option<TermId>>,
     pub daps: RwSignal<im::HashMap<DapId, DapData>>,
     pub breakpoints: RwSignal<BTreeMap<PathBuf, Vec<LapceBreakpoint>>>,
+    /// The task that was run or debugged last, to run it again
+    pub last_run: RwSignal<Option<(RunDebugMode, RunDebugConfig)>>,
 }
 
 impl RunDebugData {
@@ -74,10 +176,12 @@ pub fn new(cx: Scope) -> Self {
         let active_term = create_rw_signal(cx, None);
         let daps = create_rw_signal(cx, im::HashMap::new());
         let breakpoints = create_rw_signal(cx, BTreeMap::new());
+        let last_run = create_rw_signal(cx, None);
         Self {
             active_term,
             daps,
             breakpoints,
+            last_run,
         }
     }
 
@@ -179,3 +283,59 @@ pub fn stopped(
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, path::Path};
+
+    use super::{current_file_run_config, run_command, split_args};
+
+    #[test]
+    fn test_current_file_run_config() {
+        let mut runners = HashMap::new();
+        runners.insert("python".to_string(), "python3".to_string());
+
+        let path = Path::new("/tmp/project/script.py");
+        let config =
+            current_file_run_config(path, "#!/usr/bin/env bash", "Python", &runners)
+                .unwrap();
+        assert_eq!(config.program, "/usr/bin/env bash");
+        assert_eq!(config.args, vec!["/tmp/project/script.py"]);
+        assert_eq!(config.cwd.as_deref(), Some("/tmp/project"));
+
+        let config =
+            current_file_run_config(path, "import os", "Python", &runners).unwrap();
+        assert_eq!(config.program, "python3");
+
+        // A path with spaces is passed as one argument, without quotes
+        let path = Path::new("/tmp/my project/script.py");
+        let config =
+            current_file_run_config(path, "#!/usr/bin/env python3", "", &runners)
+                .unwrap();
+        assert_eq!(
+            run_command(&config),
+            vec!["/usr/bin/env", "python3", "/tmp/my project/script.py"]
+        );
+
+        assert!(current_file_run_config(
+            Path::new("/tmp/project/main.rs"),
+            "fn main() {}",
+            "Rust",
+            &runners,
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn test_split_args() {
+        assert_eq!(
+            split_args(r#" a "b c" 'd e' f\ g "h\"i" "" "#),
+            vec!["a", "b c", "d e", "f g", "h\"i", ""]
+        );
+        assert_eq!(
+            split_args(r"C:\Users\me 'it\s'"),
+            vec![r"C:\Users\me", r"it\s"]
+        );
+        assert!(split_args("  ").is_empty());
+    }
+}
@@ -6,9 +6,11 @@
     sync::{atomic, Arc},
 };
 
+use encoding_rs::{Encoding, UTF_8};
 use floem::{
     cosmic_text::{Attrs, AttrsList, FamilyOwned, TextLayout},
     ext_event::create_ext_action,
+    peniko::Color,
     reactive::{
         create_rw_signal, ReadSignal, RwSignal, Scope, SignalGetUntracked,
         SignalSet, SignalUpdate, SignalWithUntracked,
@@ -17,8 +19,8 @@
 use itertools::Itertools;
 use lapce_core::{
     buffer::{
-        diff::{rope_diff, DiffLines},
-        rope_text::RopeText,
+        diff::{diff_hunks, hunk_summary, rope_diff, DiffHunk, DiffLines},
+        rope_text::{CharIndicesJoin, RopeText, RopeTextVal},
         Buffer, InvalLines,
     },
     command::EditCommand,
@@ -26,13 +28,13 @@
     editor::{EditType, Editor},
     language::LapceLanguage,
     register::{Clipboard, Register},
-    selection::{SelRegion, Selection},
+    selection::{InsertDrift, Selection},
     style::line_styles,
     syntax::{edit::SyntaxEdit, Syntax},
 };
 use lapce_rpc::{
     buffer::BufferId,
-    plugin::PluginId,
+    plugin::{PluginId, TextDecoration, VoltID},
     proxy::{ProxyResponse, ProxyRpcHandler},
     style::{LineStyle, LineStyles, Style},
 };
@@ -46,17 +48,37 @@
 use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
 
-use self::phantom_text::{PhantomText, PhantomTextKind, PhantomTextLine};
+use self::{
+    decoration::{DocDecoration, DocDecorations},
+    phantom_text::{PhantomText, PhantomTextKind, PhantomTextLine},
+};
 use crate::{
+    commit_message,
     config::{color::LapceColor, LapceConfig},
     editor::view_data::{LineExtraStyle, TextLayoutCache, TextLayoutLine},
-    find::{Find, FindProgress, FindResult},
+    find::{next_search_id, Find, FindProgress, FindResult},
     history::DocumentHistory,
+    rebase_todo,
+    replay::EditRecording,
+    tabstops,
     workspace::LapceWorkspace,
 };
 
+pub mod decoration;
 pub mod phantom_text;
 
+/// How many lines around a line are looked at to align its elastic tabstops
+const ELASTIC_TABSTOPS_LINES: usize = 100;
+
+/// The number of columns the tabs of the lines span, for the version of the
+/// text, the tab width and whether the tabstops are elastic they were worked
+/// out for
+#[derive(Default)]
+struct TabWidths {
+    key: (u64, usize, bool),
+    lines: HashMap<usize, Vec<usize>>,
+}
+
 pub struct SystemClipboard {}
 
 impl SystemClipboard {
@@ -101,6 +123,13 @@ pub enum DocContent {
     Local,
     /// A document of an old version in the source control
     History(DocHistory),
+    /// The global search results laid out for editing, which writes the edited
+    /// lines back to their files on save
+    SearchResults,
+    /// A Jupyter notebook rendered as text
+    Notebook(PathBuf),
+    /// A member of an archive, at a path that goes through the archive
+    Archive(PathBuf),
 }
 
 impl DocContent {
@@ -117,6 +146,9 @@ pub fn read_only(&self) -> bool {
             DocContent::File(_) => false,
             DocContent::Local => false,
             DocContent::History(_) => true,
+            DocContent::SearchResults => false,
+            DocContent::Notebook(_) => true,
+            DocContent::Archive(_) => true,
         }
     }
 
@@ -125,10 +157,21 @@ pub fn path(&self) -> Option<&PathBuf> {
             DocContent::File(path) => Some(path),
             DocContent::Local => None,
             DocContent::History(_) => None,
+            DocContent::SearchResults => None,
+            DocContent::Notebook(_) => None,
+            DocContent::Archive(_) => None,
         }
     }
 }
 
+/// What happened to the file of a document outside of the editor, while it's
+/// open
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FileGone {
+    Deleted,
+    Renamed(PathBuf),
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct DocInfo {
     pub workspace: LapceWorkspace,
@@ -137,6 +180,18 @@ pub struct DocInfo {
     pub cursor_offset: usize,
 }
 
+/// How many of the characters its encoding can't represent are looked for
+/// before saving a document
+pub const MAX_UNMAPPABLE_CHARS: usize = 1000;
+
+/// A character the encoding of its document can't represent
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UnmappableChar {
+    pub line: usize,
+    pub col: usize,
+    pub c: char,
+}
+
 /// A single document that can be viewed by multiple [`EditorData`]'s
 /// [`EditorViewData`]s and [`EditorView]s.  
 #[derive(Clone)]
@@ -154,6 +209,8 @@ pub struct Document {
     pub inlay_hints: Option<Spans<InlayHint>>,
     /// The diagnostics for the document
     pub diagnostics: DiagnosticData,
+    /// The decorations the plugins set
+    pub decorations: DocDecorations,
     /// Current completion lens text, if any.  
     /// This will be displayed even on views that are not focused.
     completion_lens: Option<String>,
@@ -163,6 +220,18 @@ pub struct Document {
     pub code_actions: im::HashMap<usize, Arc<(PluginId, CodeActionResponse)>>,
     /// Whether the buffer's content has been loaded/initialized into the buffer.
     loaded: bool,
+    /// The tab width used to display this document instead of the one of the
+    /// settings, without changing the file
+    tab_width: Option<usize>,
+    /// The path the file was last opened through, if it's a symlink to it
+    /// rather than the file itself
+    pub symlink: Option<PathBuf>,
+    /// Whether the file was deleted or renamed outside of the editor
+    pub gone: Option<FileGone>,
+    /// The edits made to the file, for replaying them, when they're recorded
+    recording: Rc<RefCell<Option<EditRecording>>>,
+    /// The encoding the file was read in, which it's saved in
+    pub encoding: &'static Encoding,
     /// Stores information about different versions of the document from source control.
     histories: RwSignal<im::HashMap<String, DocumentHistory>>,
     pub head_changes: RwSignal<im::Vector<DiffLines>>,
@@ -171,6 +240,8 @@ pub struct Document {
 
     /// A cache for the sticky headers which maps a line to the lines it should show in the header.
     pub sticky_headers: Rc<RefCell<HashMap<usize, Option<Vec<usize>>>>>,
+    /// A cache of the number of columns the tabs of the lines span
+    tab_widths: Rc<RefCell<TabWidths>>,
     proxy: ProxyRpcHandler,
     config: ReadSignal<Arc<LapceConfig>>,
     find: Find,
@@ -196,15 +267,22 @@ pub fn new(
             line_styles: Rc::new(RefCell::new(HashMap::new())),
             semantic_styles: None,
             inlay_hints: None,
+            decorations: DocDecorations::default(),
             diagnostics,
             completion_lens: None,
             completion_pos: (0, 0),
             content: DocContent::File(path),
             loaded: false,
+            tab_width: None,
+            symlink: None,
+            gone: None,
+            recording: Rc::new(RefCell::new(None)),
+            encoding: UTF_8,
             histories: create_rw_signal(cx, im::HashMap::new()),
             head_changes: create_rw_signal(cx, im::Vector::new()),
             text_layouts: Rc::new(RefCell::new(TextLayoutCache::new())),
             sticky_headers: Rc::new(RefCell::new(HashMap::new())),
+            tab_widths: Rc::new(RefCell::new(TabWidths::default())),
             code_actions: im::HashMap::new(),
             proxy,
             config,
@@ -228,8 +306,10 @@ pub fn new_local(
             syntax: Syntax::plaintext(),
             line_styles: Rc::new(RefCell::new(HashMap::new())),
             sticky_headers: Rc::new(RefCell::new(HashMap::new())),
+            tab_widths: Rc::new(RefCell::new(TabWidths::default())),
             semantic_styles: None,
             inlay_hints: None,
+            decorations: DocDecorations::default(),
             diagnostics: DiagnosticData {
                 expanded: create_rw_signal(cx, true),
                 diagnostics: create_rw_signal(cx, im::Vector::new()),
@@ -237,6 +317,11 @@ pub fn new_local(
             completion_lens: None,
             completion_pos: (0, 0),
             loaded: true,
+            tab_width: None,
+            symlink: None,
+            gone: None,
+            recording: Rc::new(RefCell::new(None)),
+            encoding: UTF_8,
             histories: create_rw_signal(cx, im::HashMap::new()),
             head_changes: create_rw_signal(cx, im::Vector::new()),
             text_layouts: Rc::new(RefCell::new(TextLayoutCache::new())),
@@ -255,10 +340,10 @@ pub fn new_hisotry(
         proxy: ProxyRpcHandler,
         config: ReadSignal<Arc<LapceConfig>>,
     ) -> Self {
-        let syntax = if let DocContent::History(history) = &content {
-            Syntax::init(&history.path)
-        } else {
-            Syntax::plaintext()
+        let syntax = match &content {
+            DocContent::History(history) => Syntax::init(&history.path),
+            DocContent::Archive(path) => Syntax::init(path),
+            _ => Syntax::plaintext(),
         };
         let (cx, _) = cx.run_child_scope(|cx| cx);
         Self {
@@ -270,8 +355,10 @@ pub fn new_hisotry(
             syntax,
             line_styles: Rc::new(RefCell::new(HashMap::new())),
             sticky_headers: Rc::new(RefCell::new(HashMap::new())),
+            tab_widths: Rc::new(RefCell::new(TabWidths::default())),
             semantic_styles: None,
             inlay_hints: None,
+            decorations: DocDecorations::default(),
             diagnostics: DiagnosticData {
                 expanded: create_rw_signal(cx, true),
                 diagnostics: create_rw_signal(cx, im::Vector::new()),
@@ -279,6 +366,11 @@ pub fn new_hisotry(
             completion_lens: None,
             completion_pos: (0, 0),
             loaded: true,
+            tab_width: None,
+            symlink: None,
+            gone: None,
+            recording: Rc::new(RefCell::new(None)),
+            encoding: UTF_8,
             histories: create_rw_signal(cx, im::HashMap::new()),
             head_changes: create_rw_signal(cx, im::Vector::new()),
             text_layouts: Rc::new(RefCell::new(TextLayoutCache::new())),
@@ -308,15 +400,25 @@ pub fn syntax(&self) -> &Syntax {
 
     pub fn set_syntax(&mut self, syntax: Syntax) {
         self.syntax = syntax;
+        self.buffer
+            .set_word_chars(self.syntax.language.word_chars());
         if self.semantic_styles.is_none() {
             self.clear_style_cache();
         }
         self.clear_sticky_headers_cache();
     }
 
+    /// Take the word characters of the language again, after the language
+    /// settings changed
+    pub fn update_word_chars(&mut self) {
+        self.buffer
+            .set_word_chars(self.syntax.language.word_chars());
+    }
+
     /// Set the syntax highlighting this document should use.
     pub fn set_language(&mut self, language: LapceLanguage) {
         self.syntax = Syntax::from_language(language);
+        self.buffer.set_word_chars(language.word_chars());
     }
 
     pub fn find(&self) -> &Find {
@@ -332,7 +434,15 @@ pub fn loaded(&self) -> bool {
     pub fn init_content(&mut self, content: Rope) {
         self.buffer.init_content(content);
         self.buffer.detect_indent(&self.syntax);
+        self.buffer
+            .set_word_chars(self.syntax.language.word_chars());
         self.loaded = true;
+        if self.is_recording_edits() {
+            *self.recording.borrow_mut() =
+                Some(EditRecording::new(self.buffer.text().clone()));
+        }
+        self.decorations
+            .text_changed(self.buffer.rev(), self.buffer.text());
         self.on_update(None);
         self.init_diagnostics();
         self.retrieve_head();
@@ -347,6 +457,35 @@ pub fn reload(&mut self, content: Rope, set_pristine: bool) {
         self.apply_deltas(&[delta]);
     }
 
+    /// A copy of the document to try edits on without anything else seeing
+    /// them. The copy has caches and signals of its own, and its edits are
+    /// neither sent to the proxy nor recorded.
+    pub fn detached_copy(&self, cx: Scope) -> Self {
+        Self {
+            scope: cx,
+            content: DocContent::Local,
+            line_styles: Rc::new(RefCell::new(HashMap::new())),
+            diagnostics: DiagnosticData {
+                expanded: create_rw_signal(
+                    cx,
+                    self.diagnostics.expanded.get_untracked(),
+                ),
+                diagnostics: create_rw_signal(
+                    cx,
+                    self.diagnostics.diagnostics.get_untracked(),
+                ),
+            },
+            recording: Rc::new(RefCell::new(None)),
+            histories: create_rw_signal(cx, im::HashMap::new()),
+            head_changes: create_rw_signal(cx, im::Vector::new()),
+            text_layouts: Rc::new(RefCell::new(TextLayoutCache::new())),
+            sticky_headers: Rc::new(RefCell::new(HashMap::new())),
+            tab_widths: Rc::new(RefCell::new(TabWidths::default())),
+            find_result: FindResult::new(cx),
+            ..self.clone()
+        }
+    }
+
     pub fn handle_file_changed(&mut self, content: Rope) {
         if self.buffer.is_pristine() {
             self.reload(content, true);
@@ -394,19 +533,37 @@ pub fn do_edit(
         cmd: &EditCommand,
         modal: bool,
         register: &mut Register,
+    ) -> Vec<(RopeDelta, InvalLines, SyntaxEdit)> {
+        self.do_edit_with_clipboard(
+            cursor,
+            cmd,
+            modal,
+            register,
+            &mut SystemClipboard {},
+        )
+    }
+
+    /// Run the edit command like [`Self::do_edit`], with the clipboard given
+    /// rather than the system one
+    pub fn do_edit_with_clipboard(
+        &mut self,
+        cursor: &mut Cursor,
+        cmd: &EditCommand,
+        modal: bool,
+        register: &mut Register,
+        clipboard: &mut impl Clipboard,
     ) -> Vec<(RopeDelta, InvalLines, SyntaxEdit)> {
         if self.content.read_only() && !cmd.not_changing_buffer() {
             return Vec::new();
         }
 
-        let mut clipboard = SystemClipboard {};
         let old_cursor = cursor.mode.clone();
         let deltas = Editor::do_edit(
             cursor,
             &mut self.buffer,
             cmd,
             &self.syntax,
-            &mut clipboard,
+            clipboard,
             modal,
             register,
         );
@@ -426,12 +583,17 @@ pub fn apply_deltas(&mut self, deltas: &[(RopeDelta, InvalLines, SyntaxEdit)]) {
             self.update_styles(delta);
             self.update_inlay_hints(delta);
             self.update_diagnostics(delta);
+            self.decorations.edited(rev + i as u64 + 1, delta);
             self.update_completion_lens(delta);
             if let DocContent::File(path) = &self.content {
                 self.proxy
                     .update(path.clone(), delta.clone(), rev + i as u64 + 1);
             }
         }
+        self.update_find_result(deltas);
+        self.record_edits(deltas);
+        self.decorations
+            .text_changed(self.rev(), self.buffer.text());
 
         // TODO(minor): We could avoid this potential allocation since most apply_delta callers are actually using a Vec
         // which we could reuse.
@@ -440,6 +602,58 @@ pub fn apply_deltas(&mut self, deltas: &[(RopeDelta, InvalLines, SyntaxEdit)]) {
         self.on_update(Some(edits));
     }
 
+    fn is_recording_edits(&self) -> bool {
+        self.content.is_file() && self.config.get_untracked().editor.record_edits
+    }
+
+    /// Record the edits for replaying them. The edits made before recording
+    /// was turned on are left out.
+    fn record_edits(&mut self, deltas: &[(RopeDelta, InvalLines, SyntaxEdit)]) {
+        if !self.is_recording_edits() {
+            // The recording starts over once it's turned on again, since the
+            // edits made in between aren't in it
+            self.recording.borrow_mut().take();
+            return;
+        }
+        let mut recording = self.recording.borrow_mut();
+        match recording.as_mut() {
+            Some(recording) => {
+                for (delta, _, _) in deltas {
+                    recording.record(delta);
+                }
+            }
+            None => {
+                *recording = Some(EditRecording::new(self.buffer.text().clone()));
+            }
+        }
+    }
+
+    /// The edits recorded of the document, if they're recorded
+    pub fn recording(&self) -> Option<EditRecording> {
+        self.recording.borrow().clone()
+    }
+
+    /// The characters of the document its encoding can't represent, which
+    /// would be lost saving it, up to [`MAX_UNMAPPABLE_CHARS`] of them
+    pub fn unmappable_chars(&self) -> Vec<UnmappableChar> {
+        // The encodings of Unicode represent every character
+        if self.encoding.output_encoding() == UTF_8 {
+            return Vec::new();
+        }
+        let text = self.buffer.text();
+        let mut buf = [0; 4];
+        CharIndicesJoin::new(text.iter_chunks(..text.len()).map(str::char_indices))
+            .filter(|(_, c)| {
+                !c.is_ascii() && self.encoding.encode(c.encode_utf8(&mut buf)).2
+            })
+            .take(MAX_UNMAPPABLE_CHARS)
+            .map(|(offset, c)| {
+                let (line, col) = self.buffer.offset_to_line_col(offset);
+                UnmappableChar { line, col, c }
+            })
+            .collect()
+    }
+
     /// Get the buffer's current revision. This is used to track whether the buffer has changed.
     pub fn rev(&self) -> u64 {
         self.buffer.rev()
@@ -494,6 +708,19 @@ fn clear_style_cache(&mut self) {
         self.clear_text_cache();
     }
 
+    /// The width tabs are displayed with in this document
+    pub fn tab_width(&self) -> usize {
+        self.tab_width
+            .unwrap_or_else(|| self.config.get_untracked().editor.tab_width)
+    }
+
+    /// Override the tab width of the settings for this document, or go back to
+    /// it with `None`.
+    pub fn set_tab_width(&mut self, tab_width: Option<usize>) {
+        self.tab_width = tab_width.map(|width| width.max(1));
+        self.clear_text_cache();
+    }
+
     fn clear_code_actions(&mut self) {
         self.code_actions.clear();
     }
@@ -518,15 +745,40 @@ fn styles(&self) -> Option<&Arc<Spans<Style>>> {
         }
     }
 
+    /// Whether the document is a message git asks to be written, such as a
+    /// commit message
+    pub fn is_commit_message(&self) -> bool {
+        self.content
+            .path()
+            .map(|path| commit_message::is_commit_message(path))
+            .unwrap_or(false)
+    }
+
+    /// Whether the document is the todo list of an interactive rebase
+    pub fn is_rebase_todo(&self) -> bool {
+        self.content
+            .path()
+            .map(|path| rebase_todo::is_rebase_todo(path))
+            .unwrap_or(false)
+    }
+
     /// Get the style information for the particular line from semantic/syntax highlighting.  
     /// This caches the result if possible.
     pub fn line_style(&self, line: usize) -> Arc<Vec<LineStyle>> {
         if self.line_styles.borrow().get(&line).is_none() {
             let styles = self.styles();
 
-            let line_styles = styles
-                .map(|styles| line_styles(self.buffer.text(), line, styles))
-                .unwrap_or_default();
+            let line_styles = match styles {
+                Some(styles) => line_styles(self.buffer.text(), line, styles),
+                None if self.is_commit_message() => commit_message::line_styles(
+                    line,
+                    &self.buffer.line_content(line),
+                ),
+                None if self.is_rebase_todo() => {
+                    rebase_todo::line_styles(&self.buffer.line_content(line))
+                }
+                None => Vec::new(),
+            };
             self.line_styles
                 .borrow_mut()
                 .insert(line, Arc::new(line_styles));
@@ -557,6 +809,9 @@ fn get_semantic_styles(
             DocContent::File(path) => path,
             DocContent::Local => return,
             DocContent::History(_) => return,
+            DocContent::SearchResults => return,
+            DocContent::Notebook(_) => return,
+            DocContent::Archive(_) => return,
         };
 
         let (rev, len) =
@@ -614,6 +869,9 @@ fn get_inlay_hints(cx: Scope, doc: RwSignal<Document>, proxy: &ProxyRpcHandler)
             DocContent::File(path) => path,
             DocContent::Local => return,
             DocContent::History(_) => return,
+            DocContent::SearchResults => return,
+            DocContent::Notebook(_) => return,
+            DocContent::Archive(_) => return,
         };
 
         let (buffer, rev, len) = doc.with_untracked(|doc| {
@@ -652,6 +910,16 @@ fn get_inlay_hints(cx: Scope, doc: RwSignal<Document>, proxy: &ProxyRpcHandler)
 
     /// Get the phantom text for a given line
     pub fn line_phantom_text(&self, line: usize) -> PhantomTextLine {
+        self.line_phantom_text_with(line, None)
+    }
+
+    /// The phantom text of the line, with the first line of the inline
+    /// suggestion an editor shows at the offset
+    pub fn line_phantom_text_with(
+        &self,
+        line: usize,
+        inline_suggestion: Option<(usize, &str)>,
+    ) -> PhantomTextLine {
         let config = self.config.get_untracked();
 
         let start_offset = self.buffer.offset_of_line(line);
@@ -758,6 +1026,24 @@ pub fn line_phantom_text(&self, line: usize) -> PhantomTextLine {
 
         text.append(&mut diag_text);
 
+        // Tabs are laid out as a single space, padded up to the columns they
+        // span right after them
+        let content = self.buffer.line_content(line);
+        let tab_cols = content.match_indices('\t').map(|(col, _)| col);
+        let tab_text = tab_cols
+            .zip(self.line_tab_widths(line, &config))
+            .filter(|(_, width)| *width > 1)
+            .map(|(col, width)| PhantomText {
+                kind: PhantomTextKind::Tab,
+                col: col + 1,
+                text: " ".repeat(width - 1),
+                fg: None,
+                font_size: None,
+                bg: None,
+                under_line: None,
+            });
+        text.extend(tab_text);
+
         let (completion_line, completion_col) = self.completion_pos;
         let completion_text = config
             .editor
@@ -781,6 +1067,54 @@ pub fn line_phantom_text(&self, line: usize) -> PhantomTextLine {
             text.push(completion_text);
         }
 
+        let inline_suggestion = inline_suggestion
+            .filter(|_| config.editor.enable_inline_suggestions)
+            // The completion lens takes its place while it's shown
+            .filter(|_| self.completion_lens.is_none())
+            .map(|(offset, suggestion)| {
+                (self.buffer.offset_to_line_col(offset), suggestion)
+            })
+            .filter(|((suggestion_line, _), _)| *suggestion_line == line)
+            .map(|((_, col), suggestion)| PhantomText {
+                kind: PhantomTextKind::InlineSuggestion,
+                col,
+                // The lines after the first are painted below it by the view
+                text: suggestion.lines().next().unwrap_or_default().to_string(),
+                fg: Some(*config.get_color(LapceColor::COMPLETION_LENS_FOREGROUND)),
+                font_size: None,
+                bg: None,
+                under_line: None,
+            });
+        if let Some(inline_suggestion) = inline_suggestion {
+            text.push(inline_suggestion);
+        }
+
+        // The virtual text of the decorations whose ranges end on this line
+        let decoration_text = self
+            .decorations
+            .iter()
+            .filter(|d| d.range.1 >= start_offset && d.range.1 < end_offset)
+            .filter_map(|d| {
+                let text = d.decoration.virtual_text.clone()?;
+                let (_, col) = self.buffer.offset_to_line_col(d.range.1);
+                let fg = d
+                    .decoration
+                    .virtual_text_color
+                    .as_deref()
+                    .and_then(|color| config.plugin_color(color))
+                    .unwrap_or(*config.get_color(LapceColor::EDITOR_DIM));
+                Some(PhantomText {
+                    kind: PhantomTextKind::Decoration,
+                    col,
+                    text,
+                    fg: Some(fg),
+                    font_size: None,
+                    bg: None,
+                    under_line: None,
+                })
+            });
+        text.extend(decoration_text);
+
         // if let Some(ime_text) = self.ime_text.as_ref() {
         //     let (ime_line, col, _) = self.ime_pos;
         //     if line == ime_line {
@@ -812,6 +1146,61 @@ pub fn line_phantom_text(&self, line: usize) -> PhantomTextLine {
         PhantomTextLine { text, max_severity }
     }
 
+    /// The number of columns each tab of the line spans
+    fn line_tab_widths(&self, line: usize, config: &LapceConfig) -> Vec<usize> {
+        let tab_width = self.tab_width();
+        let elastic = config.editor.elastic_tabstops;
+        let key = (self.buffer.rev(), tab_width, elastic);
+        let mut cache = self.tab_widths.borrow_mut();
+        if cache.key != key {
+            cache.key = key;
+            cache.lines.clear();
+        }
+        if let Some(widths) = cache.lines.get(&line) {
+            return widths.clone();
+        }
+
+        let content = self.buffer.line_content(line);
+        if !content.contains('\t') {
+            return Vec::new();
+        }
+        if !elastic {
+            let widths = tabstops::fixed_tab_widths(&content, tab_width);
+            cache.lines.insert(line, widths.clone());
+            return widths;
+        }
+
+        // The columns of cells only span adjacent lines with tabs
+        let has_tab = |line: &usize| self.buffer.line_content(*line).contains('\t');
+        let start = (line.saturating_sub(ELASTIC_TABSTOPS_LINES)..line)
+            .rev()
+            .take_while(has_tab)
+            .last()
+            .unwrap_or(line);
+        let end = (line + 1
+            ..=(line + ELASTIC_TABSTOPS_LINES).min(self.buffer.last_line()))
+            .take_while(has_tab)
+            .last()
+            .unwrap_or(line);
+        let lines: Vec<_> =
+            (start..=end).map(|l| self.buffer.line_content(l)).collect();
+        let lines: Vec<&str> = lines.iter().map(|l| l.as_ref()).collect();
+        // When the lines with tabs around it all fit, the widths of the other
+        // ones, which are laid out next, are worked out from the same lines
+        let whole = (start == 0 || !has_tab(&(start - 1)))
+            && (end == self.buffer.last_line() || !has_tab(&(end + 1)));
+        let indexes = if whole {
+            0..lines.len()
+        } else {
+            line - start..line - start + 1
+        };
+        for index in indexes {
+            let widths = tabstops::elastic_tab_widths(&lines, index, tab_width);
+            cache.lines.insert(start + index, widths);
+        }
+        cache.lines.get(&line).cloned().unwrap_or_default()
+    }
+
     /// Update the diagnostics' positions after an edit so that they appear in the correct place.
     fn update_diagnostics(&self, delta: &RopeDelta) {
         if self
@@ -859,6 +1248,52 @@ pub fn init_diagnostics(&mut self) {
         });
     }
 
+    /// Replace the decorations the plugin set with the key, moving the ones
+    /// for an older version of the document along with the edits made since
+    pub fn set_decorations(
+        &mut self,
+        volt: VoltID,
+        key: String,
+        version: Option<i32>,
+        decorations: Vec<TextDecoration>,
+    ) {
+        let rev = self.rev();
+        if self.decorations.set(
+            self.buffer.text(),
+            rev,
+            volt,
+            key,
+            version,
+            decorations,
+        ) {
+            self.clear_text_cache();
+        }
+    }
+
+    /// Keep only the decorations of the plugins `keep` is true for
+    pub fn retain_decorations(&mut self, keep: impl Fn(&VoltID) -> bool) {
+        if self.decorations.retain(self.buffer.text(), keep) {
+            self.clear_text_cache();
+        }
+    }
+
+    /// The decorations with some of their range on the line
+    fn line_decorations(
+        &self,
+        line: usize,
+    ) -> impl Iterator<Item = &DocDecoration> + '_ {
+        let start = self.buffer.offset_of_line(line);
+        let end = self.buffer.offset_of_line(line + 1);
+        self.decorations
+            .iter()
+            .filter(move |d| d.range.0 < end && d.range.1 >= start)
+    }
+
+    /// The gutter icon of a decoration whose range starts on the line
+    pub fn decoration_gutter_icon(&self, line: usize) -> Option<PathBuf> {
+        self.decorations.gutter_icon(line).cloned()
+    }
+
     /// Get the current completion lens text
     pub fn completion_lens(&self) -> Option<&str> {
         self.completion_lens.as_deref()
@@ -972,74 +1407,115 @@ pub fn update_find(&self, start_line: usize, end_line: usize) {
             self.find_result.reset();
         }
 
-        let mut find_progress = self.find_result.progress.get_untracked();
-        let search_range = match &find_progress {
-            FindProgress::Started => {
-                // start incremental find on visible region
-                let start = self.buffer.offset_of_line(start_line);
-                let end = self.buffer.offset_of_line(end_line + 1);
-                find_progress =
-                    FindProgress::InProgress(Selection::region(start, end));
-                Some((start, end))
+        if self.find_result.progress.get_untracked() != FindProgress::Started {
+            return;
+        }
+        let Some(query) = self.find.query() else {
+            self.find_result.progress.set(FindProgress::Ready);
+            return;
+        };
+
+        // The lines shown are searched right away, while every match, which
+        // the scroll bar shows and the find bar counts, is searched for in
+        // the background
+        let text = self.buffer.text().clone();
+        if !self.find.is_multiline_regex() {
+            let mut occurrences = Selection::new();
+            query.update_find(
+                &text,
+                self.buffer.offset_of_line(start_line),
+                self.buffer.offset_of_line(end_line + 1),
+                true,
+                &mut occurrences,
+            );
+            self.find_result
+                .occurrences
+                .set(self.find_result.scoped(occurrences));
+        }
+
+        let search_id = next_search_id();
+        self.find_result
+            .progress
+            .set(FindProgress::Searching(search_id));
+        let find_result = self.find_result.clone();
+        let send = create_ext_action(self.scope, move |occurrences| {
+            // The search changed or the text was edited since
+            if find_result.progress.get_untracked()
+                != FindProgress::Searching(search_id)
+            {
+                return;
             }
-            FindProgress::InProgress(searched_range) => {
-                if searched_range.regions().len() == 1
-                    && searched_range.min_offset() == 0
-                    && searched_range.max_offset() >= self.buffer.len()
-                {
-                    // the entire text has been searched
-                    // end find by executing multi-line regex queries on entire text
-                    // stop incremental find
-                    find_progress = FindProgress::Ready;
-                    Some((0, self.buffer.len()))
-                } else {
-                    let start = self.buffer.offset_of_line(start_line);
-                    let end = self.buffer.offset_of_line(end_line + 1);
-                    let mut range = Some((start, end));
-                    for region in searched_range.regions() {
-                        if region.min() <= start && region.max() >= end {
-                            range = None;
-                            break;
-                        }
-                    }
-                    if range.is_some() {
-                        let mut new_range = searched_range.clone();
-                        new_range.add_region(SelRegion::new(start, end, None));
-                        find_progress = FindProgress::InProgress(new_range);
+            find_result.progress.set(FindProgress::Ready);
+            find_result.occurrences.set(find_result.scoped(occurrences));
+        });
+        std::thread::spawn(move || {
+            let mut occurrences = Selection::new();
+            query.update_find(&text, 0, text.len(), true, &mut occurrences);
+            send(occurrences);
+        });
+    }
+
+    /// Keep the find matches anchored to the text across an edit, searching
+    /// the edited lines again for matches added or removed by it.
+    fn update_find_result(&self, deltas: &[(RopeDelta, InvalLines, SyntaxEdit)]) {
+        if self
+            .find_result
+            .scope
+            .with_untracked(|scope| scope.is_some())
+        {
+            self.find_result.scope.update(|scope| {
+                if let Some(scope) = scope.as_mut() {
+                    for (delta, _, _) in deltas {
+                        *scope = scope.apply_delta(delta, true, InsertDrift::Inside);
                     }
-                    range
                 }
+            });
+        }
+
+        match self.find_result.progress.get_untracked() {
+            FindProgress::Ready => {}
+            FindProgress::Searching(_) => {
+                // The matches found in the background are of the text before
+                // the edit, so search it again
+                self.find_result.reset();
+                return;
             }
-            FindProgress::Ready => None,
-        };
-        if search_range.is_some() {
-            self.find_result.progress.set(find_progress);
+            FindProgress::Started => return,
+        }
+        if !self.find.visual.get_untracked() || self.find.is_multiline_regex() {
+            // The matches are no longer shown, or a match can span any number
+            // of lines, so search everything again the next time they're shown
+            self.find_result.reset();
+            return;
         }
 
-        if let Some((search_range_start, search_range_end)) = search_range {
-            let mut occurrences = self.find_result.occurrences.get_untracked();
-            if !self.find.is_multiline_regex() {
-                self.find.update_find(
-                    self.buffer.text(),
-                    search_range_start,
-                    search_range_end,
-                    true,
-                    &mut occurrences,
-                );
-            } else {
-                // only execute multi-line regex queries if we are searching the entire text (last step)
-                if search_range_start == 0 && search_range_end == self.buffer.len() {
-                    self.find.update_find(
-                        self.buffer.text(),
-                        search_range_start,
-                        search_range_end,
-                        true,
-                        &mut occurrences,
-                    );
-                }
+        // The edited ranges, in the offsets of the text after all the deltas
+        let mut edited: Vec<(usize, usize)> = Vec::new();
+        let mut occurrences = self.find_result.occurrences.get_untracked();
+        for (delta, _, _) in deltas {
+            let mut transformer = Transformer::new(delta);
+            for (start, end) in edited.iter_mut() {
+                *start = transformer.transform(*start, false);
+                *end = transformer.transform(*end, true);
             }
-            self.find_result.occurrences.set(occurrences);
+            let (iv, new_len) = delta.summary();
+            edited.push((iv.start(), iv.start() + new_len));
+            occurrences = occurrences.apply_delta(delta, true, InsertDrift::Default);
         }
+
+        let text = self.buffer.text();
+        for (start, end) in edited {
+            let start_line = self.buffer.line_of_offset(start);
+            let end_line = self.buffer.line_of_offset(end);
+            let start = self.buffer.offset_of_line(start_line);
+            let end = self.buffer.offset_of_line(end_line + 1);
+            occurrences.delete_range(start, end);
+            self.find
+                .update_find(text, start, end, false, &mut occurrences);
+        }
+        self.find_result
+            .occurrences
+            .set(self.find_result.scoped(occurrences));
     }
 
     /// Get the sticky headers for a particular line, creating them if necessary.
@@ -1120,6 +1596,7 @@ pub fn trigger_head_change(&self) {
         let rev = self.rev();
         let left_rope = history;
         let right_rope = self.buffer().text().clone();
+        let options = self.config.get_untracked().editor.diff_options();
 
         let send = {
             let atomic_rev = atomic_rev.clone();
@@ -1140,12 +1617,83 @@ pub fn trigger_head_change(&self) {
         };
 
         rayon::spawn(move || {
-            let changes =
-                rope_diff(left_rope, right_rope, rev, atomic_rev.clone(), None);
+            let changes = rope_diff(
+                left_rope,
+                right_rope,
+                rev,
+                atomic_rev.clone(),
+                None,
+                options,
+            );
             send(changes.map(im::Vector::from));
         });
     }
 
+    /// The text of the file at `HEAD`, once it's retrieved
+    fn head_text(&self) -> Option<RopeTextVal> {
+        self.histories.with_untracked(|histories| {
+            histories
+                .get("head")
+                .map(|history| RopeTextVal::new(history.buffer.text().clone()))
+        })
+    }
+
+    /// The hunks of the changes to the file since `HEAD`
+    pub fn head_hunks(&self) -> Vec<DiffHunk> {
+        let changes = self.head_changes.get_untracked();
+        diff_hunks(&changes.into_iter().collect::<Vec<_>>())
+    }
+
+    /// The changes to the file since `HEAD` as a list, one item for each run
+    /// of hunks in the same function or other scope, like
+    /// "- Foo.bar: changed lines 3-4, added line 10"
+    pub fn head_changes_summary(&self) -> Vec<String> {
+        let mut items: Vec<(Vec<String>, Vec<String>)> = Vec::new();
+        for hunk in self.head_hunks() {
+            // A removal is in the scope of the line before it
+            let line = if hunk.right.is_empty() {
+                hunk.right.start.saturating_sub(1)
+            } else {
+                hunk.right.start
+            };
+            let offset = self.buffer.first_non_blank_character_on_line(line);
+            let scope = self.syntax.scope_names(offset);
+            let summary = hunk_summary(&hunk);
+            match items.last_mut() {
+                Some((last_scope, summaries)) if *last_scope == scope => {
+                    summaries.push(summary);
+                }
+                _ => items.push((scope, vec![summary])),
+            }
+        }
+        items
+            .into_iter()
+            .map(|(scope, summaries)| {
+                if scope.is_empty() {
+                    format!("- {}", summaries.join(", "))
+                } else {
+                    format!("- {}: {}", scope.join("."), summaries.join(", "))
+                }
+            })
+            .collect()
+    }
+
+    /// The lines of the file at `HEAD` that the hunk replaced
+    pub fn head_hunk_original(&self, hunk: &DiffHunk) -> Option<String> {
+        let head = self.head_text()?;
+        let start = head.offset_of_line(hunk.left.start);
+        let end = head.offset_of_line(hunk.left.end);
+        Some(head.slice_to_cow(start..end).to_string())
+    }
+
+    /// The lines of the document that the hunk replaced the ones of the file
+    /// at `HEAD` with
+    pub fn hunk_lines(&self, hunk: &DiffHunk) -> String {
+        let start = self.buffer.offset_of_line(hunk.right.start);
+        let end = self.buffer.offset_of_line(hunk.right.end);
+        self.buffer.slice_to_cow(start..end).to_string()
+    }
+
     /// Create rendable whitespace layout by creating a new text layout
     /// with invisible spaces and special utf8 characters that display
     /// the different white space characters.
@@ -1216,9 +1764,16 @@ fn new_whitespace_layout(
         Some(rendered_whitespaces)
     }
 
-    /// Create a new text layout for the given line.  
-    /// Typically you should use [`Document::get_text_layout`] instead.
-    fn new_text_layout(&self, line: usize, _font_size: usize) -> TextLayoutLine {
+    /// Create a new text layout for the given line, with the inline suggestion
+    /// an editor shows.  
+    /// Typically you should use [`Document::get_text_layout`] instead, which
+    /// caches the layouts of the lines without one.
+    pub fn new_text_layout(
+        &self,
+        line: usize,
+        _font_size: usize,
+        inline_suggestion: Option<(usize, &str)>,
+    ) -> TextLayoutLine {
         let config = self.config.get_untracked();
         let line_content_original = self.buffer.line_content(line);
 
@@ -1241,9 +1796,11 @@ fn new_text_layout(&self, line: usize, _font_size: usize) -> TextLayoutLine {
                     &line_content_original[..],
                 )
             };
-        // Combine the phantom text with the line content
-        let phantom_text = self.line_phantom_text(line);
-        let line_content = phantom_text.combine_with_text(line_content);
+        // Combine the phantom text with the line content, with the tabs as
+        // spaces since their width is given by the phantom text
+        let phantom_text = self.line_phantom_text_with(line, inline_suggestion);
+        let line_content =
+            phantom_text.combine_with_text(line_content.replace('\t', " "));
 
         let color = config.get_color(LapceColor::EDITOR_FOREGROUND);
         let family: Vec<FamilyOwned> =
@@ -1254,41 +1811,22 @@ fn new_text_layout(&self, line: usize, _font_size: usize) -> TextLayoutLine {
             .font_size(config.editor.font_size() as f32);
         let mut attrs_list = AttrsList::new(attrs);
 
-        // Apply various styles to the line's text based on our semantic/syntax highlighting
+        // Apply the styles of the semantic/syntax highlighting and of the
+        // phantom text
         let styles = self.line_style(line);
-        for line_style in styles.iter() {
-            if let Some(fg_color) = line_style.style.fg_color.as_ref() {
-                if let Some(fg_color) = config.get_style_color(fg_color) {
-                    let start = phantom_text.col_at(line_style.start);
-                    let end = phantom_text.col_at(line_style.end);
-                    attrs_list.add_span(start..end, attrs.color(*fg_color));
-                }
-            }
-        }
-
-        let font_size = config.editor.font_size();
-
-        // Apply phantom text specific styling
-        for (offset, size, col, phantom) in phantom_text.offset_size_iter() {
-            let start = col + offset;
-            let end = start + size;
-
+        for span in line_spans(&styles, &phantom_text, &config) {
             let mut attrs = attrs;
-            if let Some(fg) = phantom.fg {
-                attrs = attrs.color(fg);
+            if let Some(color) = span.color {
+                attrs = attrs.color(color);
             }
-            if let Some(phantom_font_size) = phantom.font_size {
-                attrs = attrs.font_size(phantom_font_size.min(font_size) as f32);
+            if let Some(font_size) = span.font_size {
+                attrs = attrs.font_size(font_size as f32);
             }
-            attrs_list.add_span(start..end, attrs);
-            // if let Some(font_family) = phantom.font_family.clone() {
-            //     layout_builder = layout_builder.range_attribute(
-            //         start..end,
-            //         TextAttribute::FontFamily(font_family),
-            //     );
-            // }
+            attrs_list.add_span(span.range, attrs);
         }
 
+        let font_size = config.editor.font_size();
+
         let mut text_layout = TextLayout::new();
         text_layout.set_text(&line_content, attrs_list);
 
@@ -1311,6 +1849,36 @@ fn new_text_layout(&self, line: usize, _font_size: usize) -> TextLayoutLine {
             }
         }
 
+        // The highlights and underlines of the decorations on this line
+        let line_start = self.buffer.offset_of_line(line);
+        let line_len = line_content_original.len();
+        for d in self.line_decorations(line) {
+            let bg_color = d
+                .decoration
+                .background
+                .as_deref()
+                .and_then(|color| config.plugin_color(color));
+            let under_line = d
+                .decoration
+                .underline
+                .as_deref()
+                .and_then(|color| config.plugin_color(color));
+            if bg_color.is_none() && under_line.is_none() {
+                continue;
+            }
+            let start = d.range.0.saturating_sub(line_start).min(line_len);
+            let end = (d.range.1 - line_start).min(line_len);
+            let x0 = text_layout.hit_position(phantom_text.col_at(start)).point.x;
+            let x1 = text_layout.hit_position(phantom_text.col_at(end)).point.x;
+            extra_style.push(LineExtraStyle {
+                x: x0,
+                width: Some(x1 - x0),
+                bg_color,
+                under_line,
+                wave_line: None,
+            });
+        }
+
         // Add the styling for the diagnostic severity, if applicable
         if let Some(max_severity) = phantom_text.max_severity {
             let theme_prop = if max_severity == DiagnosticSeverity::ERROR {
@@ -1333,49 +1901,6 @@ fn new_text_layout(&self, line: usize, _font_size: usize) -> TextLayoutLine {
             });
         }
 
-        self.diagnostics.diagnostics.with_untracked(|diags| {
-            for diag in diags {
-                if diag.diagnostic.range.start.line as usize <= line
-                    && line <= diag.diagnostic.range.end.line as usize
-                {
-                    let start = if diag.diagnostic.range.start.line as usize == line
-                    {
-                        let (_, col) = self.buffer.offset_to_line_col(diag.range.0);
-                        col
-                    } else {
-                        let offset =
-                            self.buffer.first_non_blank_character_on_line(line);
-                        let (_, col) = self.buffer.offset_to_line_col(offset);
-                        col
-                    };
-                    let start = phantom_text.col_after(start, true);
-
-                    let end = if diag.diagnostic.range.end.line as usize == line {
-                        let (_, col) = self.buffer.offset_to_line_col(diag.range.1);
-                        col
-                    } else {
-                        self.buffer.line_end_col(line, true)
-                    };
-                    let end = phantom_text.col_after(end, false);
-
-                    let x0 = text_layout.hit_position(start).point.x;
-                    let x1 = text_layout.hit_position(end).point.x;
-                    let color_name = match diag.diagnostic.severity {
-                        Some(DiagnosticSeverity::ERROR) => LapceColor::LAPCE_ERROR,
-                        _ => LapceColor::LAPCE_WARN,
-                    };
-                    let color = *config.get_color(color_name);
-                    extra_style.push(LineExtraStyle {
-                        x: x0,
-                        width: Some(x1 - x0),
-                        bg_color: None,
-                        under_line: None,
-                        wave_line: Some(color),
-                    });
-                }
-            }
-        });
-
         let whitespaces = Self::new_whitespace_layout(
             line_content_original,
             &text_layout,
@@ -1438,7 +1963,7 @@ pub fn get_text_layout(
             .is_some();
         // If there isn't an entry then we actually have to create it
         if !cache_exists {
-            let text_layout = Arc::new(self.new_text_layout(line, font_size));
+            let text_layout = Arc::new(self.new_text_layout(line, font_size, None));
             let mut cache = self.text_layouts.borrow_mut();
             let width = text_layout.text.size().width;
             if width > cache.max_width {
@@ -1462,3 +1987,50 @@ pub fn get_text_layout(
             .unwrap()
     }
 }
+
+/// A span of the text of a line as it's laid out, with its phantom text,
+/// painted otherwise than the rest of the line
+#[derive(Clone, Debug, PartialEq)]
+pub struct LineSpan {
+    pub range: std::ops::Range<usize>,
+    pub color: Option<Color>,
+    pub font_size: Option<usize>,
+}
+
+/// The spans of the line painted in the colors of its styles, then the ones
+/// of its phantom text, in the columns of the line with its phantom text
+pub fn line_spans(
+    styles: &[LineStyle],
+    phantom_text: &PhantomTextLine,
+    config: &LapceConfig,
+) -> Vec<LineSpan> {
+    let mut spans = Vec::new();
+    for line_style in styles {
+        let color = line_style
+            .style
+            .fg_color
+            .as_ref()
+            .and_then(|fg_color| config.get_style_color(fg_color));
+        if let Some(color) = color {
+            spans.push(LineSpan {
+                range: phantom_text.col_at(line_style.start)
+                    ..phantom_text.col_at(line_style.end),
+                color: Some(*color),
+                font_size: None,
+            });
+        }
+    }
+
+    let font_size = config.editor.font_size();
+    for (offset, size, col, phantom) in phantom_text.offset_size_iter() {
+        let start = col + offset;
+        spans.push(LineSpan {
+            range: start..start + size,
+            color: phantom.fg,
+            font_size: phantom
+                .font_size
+                .map(|phantom_font_size| phantom_font_size.min(font_size)),
+        });
+    }
+    spans
+}
@@ -0,0 +1,260 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    path::PathBuf,
+};
+
+use lapce_core::buffer::rope_text::{RopeText, RopeTextRef};
+use lapce_rpc::plugin::{TextDecoration, VoltID};
+use lapce_xi_rope::{Rope, RopeDelta, Transformer};
+
+/// How many of the last versions of the text are kept, to place the
+/// decorations that plugins worked out for a version that isn't the current
+/// one anymore
+const RECENT_VERSIONS: usize = 32;
+
+/// A decoration a plugin set on the document, with the offsets of its range
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DocDecoration {
+    pub range: (usize, usize),
+    pub decoration: TextDecoration,
+}
+
+/// The decorations the plugins set on a document, which move along with the
+/// text they're on as it's edited
+#[derive(Clone, Default)]
+pub struct DocDecorations {
+    /// The decorations by the volts of the plugins and the keys they gave
+    /// them
+    decorations: im::HashMap<(VoltID, String), Vec<DocDecoration>>,
+    /// The gutter icon of the decorations by the lines their ranges start on
+    gutter_icons: HashMap<usize, PathBuf>,
+    /// The last versions of the text, oldest first
+    texts: VecDeque<(u64, Rope)>,
+    /// The edits made since the oldest of the versions, with the versions
+    /// they made
+    deltas: VecDeque<(u64, RopeDelta)>,
+}
+
+impl DocDecorations {
+    /// Keep the text of the version, for the decorations worked out for it
+    /// that come once it's edited, and find the lines the decorations are on
+    /// in it
+    pub fn text_changed(&mut self, rev: u64, text: &Rope) {
+        self.texts.push_back((rev, text.clone()));
+        if self.texts.len() > RECENT_VERSIONS {
+            self.texts.pop_front();
+        }
+        if let Some((oldest, _)) = self.texts.front() {
+            let oldest = *oldest;
+            while self.deltas.front().map_or(false, |(rev, _)| *rev <= oldest) {
+                self.deltas.pop_front();
+            }
+        }
+        if !self.decorations.is_empty() {
+            self.index_lines(text);
+        }
+    }
+
+    /// Move the decorations along with the text they're on after the edit
+    /// that made the version, before [`Self::text_changed`] is told of the
+    /// text it made
+    pub fn edited(&mut self, rev: u64, delta: &RopeDelta) {
+        if !self.texts.is_empty() {
+            self.deltas.push_back((rev, delta.clone()));
+        }
+        for (_, decorations) in self.decorations.iter_mut() {
+            for decoration in decorations.iter_mut() {
+                decoration.range = transform(decoration.range, delta);
+            }
+        }
+    }
+
+    /// Replace the decorations the plugin set with the key. The ones for an
+    /// older version of the text are moved along with the edits made since,
+    /// while the ones for a version that isn't known are dropped. Returns
+    /// whether they were set.
+    pub fn set(
+        &mut self,
+        text: &Rope,
+        rev: u64,
+        volt: VoltID,
+        key: String,
+        version: Option<i32>,
+        decorations: Vec<TextDecoration>,
+    ) -> bool {
+        let version = version.map_or(rev, |version| version as u64);
+        let version_text = if version == rev {
+            Some(text)
+        } else {
+            self.texts
+                .iter()
+                .find(|(text_rev, _)| *text_rev == version)
+                .map(|(_, text)| text)
+        };
+        let Some(version_text) = version_text else {
+            return false;
+        };
+        let version_text = RopeTextRef::new(version_text);
+        let deltas = self
+            .deltas
+            .iter()
+            .filter(|(delta_rev, _)| *delta_rev > version && *delta_rev <= rev)
+            .map(|(_, delta)| delta)
+            .collect::<Vec<_>>();
+
+        let decorations: Vec<DocDecoration> = decorations
+            .into_iter()
+            .map(|decoration| {
+                let start = version_text.offset_of_position(&decoration.range.start);
+                let end = version_text.offset_of_position(&decoration.range.end);
+                let range =
+                    deltas.iter().fold((start, end.max(start)), |range, delta| {
+                        transform(range, delta)
+                    });
+                DocDecoration { range, decoration }
+            })
+            .collect();
+        if decorations.is_empty() {
+            self.decorations.remove(&(volt, key));
+        } else {
+            self.decorations.insert((volt, key), decorations);
+        }
+        self.index_lines(text);
+        true
+    }
+
+    /// Keep only the decorations of the plugins `keep` is true for. Returns
+    /// whether any were dropped.
+    pub fn retain(&mut self, text: &Rope, keep: impl Fn(&VoltID) -> bool) -> bool {
+        let len = self.decorations.len();
+        self.decorations.retain(|(volt, _), _| keep(volt));
+        if self.decorations.len() == len {
+            return false;
+        }
+        self.index_lines(text);
+        true
+    }
+
+    /// The volts of the plugins that set decorations
+    pub fn volts(&self) -> impl Iterator<Item = &VoltID> {
+        self.decorations.keys().map(|(volt, _)| volt)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &DocDecoration> {
+        self.decorations.values().flatten()
+    }
+
+    /// The gutter icon of a decoration with one whose range starts on the
+    /// line
+    pub fn gutter_icon(&self, line: usize) -> Option<&PathBuf> {
+        self.gutter_icons.get(&line)
+    }
+
+    fn index_lines(&mut self, text: &Rope) {
+        let text = RopeTextRef::new(text);
+        self.gutter_icons.clear();
+        for decoration in self.decorations.values().flatten() {
+            if let Some(icon) = decoration.decoration.gutter_icon.as_ref() {
+                self.gutter_icons
+                    .entry(text.line_of_offset(decoration.range.0))
+                    .or_insert_with(|| icon.clone());
+            }
+        }
+    }
+}
+
+/// The range once the edit is made, grown by text inserted at its end
+fn transform((start, end): (usize, usize), delta: &RopeDelta) -> (usize, usize) {
+    let mut transformer = Transformer::new(delta);
+    (
+        transformer.transform(start, false),
+        transformer.transform(end, true),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use lapce_xi_rope::{DeltaBuilder, Interval};
+    use lsp_types::{Position, Range};
+
+    use super::*;
+
+    fn insert(text: &Rope, offset: usize, s: &str) -> (RopeDelta, Rope) {
+        let mut builder = DeltaBuilder::new(text.len());
+        builder.replace(Interval::new(offset, offset), Rope::from(s));
+        let delta = builder.build();
+        let text = delta.apply(text);
+        (delta, text)
+    }
+
+    fn decoration(line: u32, start: u32, end: u32) -> TextDecoration {
+        TextDecoration {
+            range: Range::new(Position::new(line, start), Position::new(line, end)),
+            background: None,
+            underline: None,
+            gutter_icon: Some(PathBuf::from("icon.svg")),
+            virtual_text: None,
+            virtual_text_color: None,
+        }
+    }
+
+    fn ranges(decorations: &DocDecorations) -> Vec<(usize, usize)> {
+        decorations.iter().map(|d| d.range).collect()
+    }
+
+    #[test]
+    fn test_decorations_follow_edits() {
+        let volt = VoltID {
+            author: "lapce".to_string(),
+            name: "lint".to_string(),
+        };
+        let text = Rope::from("let a = 1;\nlet b = 2;\n");
+        let mut decorations = DocDecorations::default();
+        decorations.text_changed(0, &text);
+        assert!(decorations.set(
+            &text,
+            0,
+            volt.clone(),
+            "lint".to_string(),
+            Some(0),
+            vec![decoration(1, 4, 5)],
+        ));
+        assert_eq!(ranges(&decorations), vec![(15, 16)]);
+        assert!(decorations.gutter_icon(1).is_some());
+
+        // A line inserted above moves the decoration down with its text
+        let (delta, text) = insert(&text, 0, "// b\n");
+        decorations.edited(1, &delta);
+        decorations.text_changed(1, &text);
+        assert_eq!(ranges(&decorations), vec![(20, 21)]);
+        assert!(decorations.gutter_icon(1).is_none());
+        assert!(decorations.gutter_icon(2).is_some());
+
+        // Decorations worked out for the first version are moved along with
+        // the edit made since
+        assert!(decorations.set(
+            &text,
+            1,
+            volt.clone(),
+            "lint".to_string(),
+            Some(0),
+            vec![decoration(0, 4, 5)],
+        ));
+        assert_eq!(ranges(&decorations), vec![(9, 10)]);
+
+        // The ones for a version that isn't known are dropped
+        assert!(!decorations.set(
+            &text,
+            1,
+            volt.clone(),
+            "lint".to_string(),
+            Some(5),
+            vec![decoration(0, 0, 1)],
+        ));
+        assert_eq!(ranges(&decorations), vec![(9, 10)]);
+
+        assert!(decorations.retain(&text, |v| v != &volt));
+        assert_eq!(decorations.iter().count(), 0);
+        assert!(decorations.gutter_icon(1).is_none());
+    }
+}
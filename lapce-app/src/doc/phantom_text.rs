@@ -19,12 +19,19 @@ pub struct PhantomText {
 
 #[derive(Ord, Eq, PartialEq, PartialOrd)]
 pub enum PhantomTextKind {
+    /// The padding of tabs up to the columns they span, which comes first to
+    /// stay next to its tab
+    Tab,
     /// Input methods
     Ime,
     /// Completion lens
     Completion,
+    /// Inline suggestion, the ghost text after the cursor
+    InlineSuggestion,
     /// Inlay hints supplied by an LSP/PSP (like type annotations)
     InlayHint,
+    /// Virtual text of the decorations set by plugins
+    Decoration,
     /// Error lens
     Diagnostic,
 }
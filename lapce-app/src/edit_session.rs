@@ -0,0 +1,278 @@
+use std::{fmt, path::PathBuf};
+
+use lapce_core::{
+    buffer::{rope_text::RopeText, Buffer},
+    selection::Selection,
+};
+use lsp_types::{
+    DocumentChangeOperation, DocumentChanges, OneOf, TextDocumentEdit, TextEdit,
+    WorkspaceEdit,
+};
+
+/// Edits to several documents that are applied all together, or not at all
+/// if any of them can't be, so that a refactor doesn't leave the workspace
+/// half changed. The edits of a document can be tied to the revision they
+/// were made against, which it then has to still be at.
+#[derive(Clone, Debug, Default)]
+pub struct EditSession {
+    documents: Vec<DocumentEdits>,
+}
+
+/// The edits staged for one document
+#[derive(Clone, Debug)]
+pub struct DocumentEdits {
+    pub path: PathBuf,
+    /// The revision of the document the edits were made against, if they
+    /// apply to whatever it's at
+    pub rev: Option<u64>,
+    pub edits: Vec<TextEdit>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EditSessionError {
+    /// The document changed since the edits to it were made
+    Changed {
+        path: PathBuf,
+        rev: u64,
+        current: u64,
+    },
+    /// An edit is past the end of the document
+    OutOfRange { path: PathBuf },
+    /// Edits to the document overlap
+    Overlapping { path: PathBuf },
+    /// The document can't be edited
+    ReadOnly { path: PathBuf },
+    /// The document couldn't be loaded
+    NotLoaded { path: PathBuf },
+    /// Files would have to be created, renamed or deleted, which the edits
+    /// of a session can't do
+    FileOperations,
+}
+
+impl fmt::Display for EditSessionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EditSessionError::Changed { path, rev, current } => write!(
+                f,
+                "{} changed since the edits were made, from revision {rev} to {current}",
+                path.display()
+            ),
+            EditSessionError::OutOfRange { path } => {
+                write!(f, "an edit is past the end of {}", path.display())
+            }
+            EditSessionError::Overlapping { path } => {
+                write!(f, "edits to {} overlap", path.display())
+            }
+            EditSessionError::ReadOnly { path } => {
+                write!(f, "{} is read only", path.display())
+            }
+            EditSessionError::NotLoaded { path } => {
+                write!(f, "{} couldn't be loaded", path.display())
+            }
+            EditSessionError::FileOperations => {
+                write!(f, "creating, renaming and deleting files isn't supported")
+            }
+        }
+    }
+}
+
+impl EditSession {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The edits of the workspace edit, tied to the versions of the
+    /// documents it has, which are their revisions
+    pub fn from_workspace_edit(
+        edit: &WorkspaceEdit,
+    ) -> Result<Self, EditSessionError> {
+        let mut session = Self::new();
+        match edit.document_changes.as_ref() {
+            Some(DocumentChanges::Edits(edits)) => {
+                for edit in edits {
+                    session.stage_document_edit(edit);
+                }
+            }
+            Some(DocumentChanges::Operations(ops)) => {
+                for op in ops {
+                    match op {
+                        DocumentChangeOperation::Op(_) => {
+                            return Err(EditSessionError::FileOperations);
+                        }
+                        DocumentChangeOperation::Edit(edit) => {
+                            session.stage_document_edit(edit);
+                        }
+                    }
+                }
+            }
+            None => {
+                for (url, edits) in edit.changes.iter().flatten() {
+                    if let Ok(path) = url.to_file_path() {
+                        session.stage(path, None, edits.clone());
+                    }
+                }
+            }
+        }
+        Ok(session)
+    }
+
+    /// Stage edits to the document of the file, made against its revision
+    /// `rev`, or against whatever revision it's at if `None`. The positions
+    /// of all the edits to a document are in its text before any of them.
+    pub fn stage(&mut self, path: PathBuf, rev: Option<u64>, edits: Vec<TextEdit>) {
+        match self.documents.iter_mut().find(|d| d.path == path) {
+            Some(document) => {
+                document.rev = document.rev.or(rev);
+                document.edits.extend(edits);
+            }
+            None => self.documents.push(DocumentEdits { path, rev, edits }),
+        }
+    }
+
+    fn stage_document_edit(&mut self, edit: &TextDocumentEdit) {
+        let Ok(path) = edit.text_document.uri.to_file_path() else {
+            return;
+        };
+        let rev = edit.text_document.version.map(|v| v as u64);
+        let edits = edit
+            .edits
+            .iter()
+            .map(|e| match e {
+                OneOf::Left(e) => e.clone(),
+                OneOf::Right(e) => e.text_edit.clone(),
+            })
+            .collect();
+        self.stage(path, rev, edits);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.documents.is_empty()
+    }
+
+    pub fn documents(&self) -> &[DocumentEdits] {
+        &self.documents
+    }
+}
+
+impl DocumentEdits {
+    /// The edits as regions of the buffer, if they apply to it as it is
+    pub fn resolve(
+        &self,
+        buffer: &Buffer,
+    ) -> Result<Vec<(Selection, String)>, EditSessionError> {
+        if let Some(rev) = self.rev {
+            if rev != buffer.rev() {
+                return Err(EditSessionError::Changed {
+                    path: self.path.clone(),
+                    rev,
+                    current: buffer.rev(),
+                });
+            }
+        }
+
+        let mut regions = Vec::new();
+        for edit in &self.edits {
+            // The characters past the end of a line are at its end, like the
+            // LSP has it, but a line past the end of the document is a
+            // mistake
+            if edit.range.start.line as usize > buffer.last_line()
+                || edit.range.end.line as usize > buffer.last_line() + 1
+            {
+                return Err(EditSessionError::OutOfRange {
+                    path: self.path.clone(),
+                });
+            }
+            let start = buffer.offset_of_position(&edit.range.start);
+            let end = buffer.offset_of_position(&edit.range.end);
+            if start > end {
+                return Err(EditSessionError::OutOfRange {
+                    path: self.path.clone(),
+                });
+            }
+            regions.push((start, end, edit.new_text.clone()));
+        }
+
+        regions.sort_by_key(|(start, end, _)| (*start, *end));
+        if regions.windows(2).any(|r| r[1].0 < r[0].1) {
+            return Err(EditSessionError::Overlapping {
+                path: self.path.clone(),
+            });
+        }
+
+        Ok(regions
+            .into_iter()
+            .map(|(start, end, text)| (Selection::region(start, end), text))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::{Position, Range};
+
+    use super::*;
+
+    fn edit(start: (u32, u32), end: (u32, u32), text: &str) -> TextEdit {
+        TextEdit {
+            range: Range::new(
+                Position::new(start.0, start.1),
+                Position::new(end.0, end.1),
+            ),
+            new_text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_resolve() {
+        let buffer = Buffer::new("fn main() {\n    foo();\n}\n");
+        let path = PathBuf::from("/src/main.rs");
+
+        let mut session = EditSession::new();
+        session.stage(
+            path.clone(),
+            Some(buffer.rev()),
+            vec![edit((1, 4), (1, 7), "bar")],
+        );
+        session.stage(path.clone(), None, vec![edit((0, 3), (0, 7), "start")]);
+        let document = &session.documents()[0];
+        assert_eq!(session.documents().len(), 1);
+        let regions = document.resolve(&buffer).unwrap();
+        assert_eq!(
+            regions,
+            vec![
+                (Selection::region(3, 7), "start".to_string()),
+                (Selection::region(16, 19), "bar".to_string()),
+            ]
+        );
+
+        let stale = DocumentEdits {
+            path: path.clone(),
+            rev: Some(buffer.rev() + 1),
+            edits: Vec::new(),
+        };
+        assert!(matches!(
+            stale.resolve(&buffer),
+            Err(EditSessionError::Changed { .. })
+        ));
+
+        let overlapping = DocumentEdits {
+            path: path.clone(),
+            rev: None,
+            edits: vec![edit((0, 0), (0, 5), ""), edit((0, 3), (0, 4), "")],
+        };
+        assert_eq!(
+            overlapping.resolve(&buffer),
+            Err(EditSessionError::Overlapping { path: path.clone() })
+        );
+
+        let out_of_range = DocumentEdits {
+            path: path.clone(),
+            rev: None,
+            edits: vec![edit((10, 0), (10, 0), "")],
+        };
+        assert_eq!(
+            out_of_range.resolve(&buffer),
+            Err(EditSessionError::OutOfRange { path })
+        );
+    }
+}
@@ -1,6 +1,14 @@
-use std::{cmp::Ordering, collections::HashMap, str::FromStr, sync::Arc};
+use std::{
+    cmp::Ordering,
+    collections::{BTreeSet, HashMap},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use anyhow::Result;
+use encoding_rs::UTF_8;
 use floem::{
     ext_event::create_ext_action,
     glazier::{Modifiers, PointerButton, PointerEvent},
@@ -12,34 +20,59 @@
     },
 };
 use lapce_core::{
-    buffer::{diff::DiffLines, rope_text::RopeText, InvalLines},
+    buffer::{
+        diff::{DiffHunk, DiffLines},
+        rope_text::RopeText,
+        InvalLines,
+    },
     command::{EditCommand, FocusCommand, MotionModeCommand, MultiSelectionCommand},
     cursor::{Cursor, CursorMode},
     editor::EditType,
+    indent::{convert_indent_edits, IndentStyle},
     mode::{Mode, MotionMode},
     movement::Movement,
-    selection::{InsertDrift, Selection},
+    reflow,
+    register::{Clipboard, Register},
+    selection::{InsertDrift, SelRegion, Selection},
     syntax::edit::SyntaxEdit,
 };
-use lapce_rpc::{plugin::PluginId, proxy::ProxyResponse};
+use lapce_proxy::buffer::language_id_from_path;
+use lapce_rpc::{
+    buffer::EncodingFallback,
+    plugin::PluginId,
+    proxy::{ProxyResponse, ProxyRpcHandler},
+};
 use lapce_xi_rope::{Rope, RopeDelta, Transformer};
 use lsp_types::{
-    CompletionItem, CompletionTextEdit, GotoDefinitionResponse, Location, TextEdit,
+    CodeAction, CodeActionKind, CodeActionOrCommand, CodeActionResponse,
+    CompletionItem, CompletionTextEdit, Diagnostic, DiagnosticSeverity,
+    DocumentChangeOperation, DocumentChanges, FormattingOptions,
+    GotoDefinitionResponse, Location, Position, TextEdit, WorkspaceEdit,
 };
 use serde::{Deserialize, Serialize};
+use tracing::error;
 
 use crate::{
     command::{CommandExecuted, CommandKind, InternalCommand},
-    completion::{clear_completion_lens, CompletionStatus},
+    commit_message,
+    completion::{clear_completion_lens, CompletionStatus, COMPLETION_TIMEOUT},
     db::LapceDb,
-    doc::{DocContent, Document},
+    doc::{DocContent, Document, EditorDiagnostic, SystemClipboard, UnmappableChar},
     editor::location::{EditorLocation, EditorPosition},
     editor_tab::EditorTabChild,
     id::{EditorId, EditorTabId},
+    inline_suggestion::{next_word_len, InlineSuggestion},
     keypress::{condition::Condition, KeyPressFocus},
-    main_split::{MainSplitData, SplitDirection, SplitMoveDirection},
+    main_split::{
+        workspace_edits, MainSplitData, SplitDirection, SplitMoveDirection,
+    },
+    overlay::{OverlayEvent, OverlayKind},
     proxy::path_from_url,
+    rebase_todo::{self, RebaseAction},
+    search_history::SearchHistoryKind,
+    signature::SignatureStatus,
     snippet::Snippet,
+    timer::exec_after,
     window_tab::{CommonData, Focus, WindowTabData},
 };
 
@@ -48,12 +81,16 @@
     view_data::{EditorViewData, EditorViewKind},
 };
 
+pub mod breadcrumbs;
 pub mod diff;
 pub mod location;
 pub mod movement;
 pub mod view;
 pub mod view_data;
 
+/// How much the split commands grow or shrink an editor tab by
+const SPLIT_RESIZE_STEP: f64 = 40.0;
+
 #[derive(Clone, Debug)]
 pub enum InlineFindDirection {
     Left,
@@ -104,9 +141,39 @@ pub fn to_data(
             DocContent::Local => {
                 EditorData::new_local(data.scope, editor_id, data.common)
             }
-            DocContent::History(_) => {
+            DocContent::History(history) => {
+                let doc = data.history_doc(history.clone());
+                EditorData::new(
+                    data.scope,
+                    Some(editor_tab_id),
+                    editor_id,
+                    doc,
+                    data.common,
+                )
+            }
+            DocContent::SearchResults => {
                 EditorData::new_local(data.scope, editor_id, data.common)
             }
+            DocContent::Notebook(path) => {
+                let doc = data.notebook_doc(path.clone());
+                EditorData::new(
+                    data.scope,
+                    Some(editor_tab_id),
+                    editor_id,
+                    doc,
+                    data.common,
+                )
+            }
+            DocContent::Archive(path) => {
+                let doc = data.archive_doc(path.clone());
+                EditorData::new(
+                    data.scope,
+                    Some(editor_tab_id),
+                    editor_id,
+                    doc,
+                    data.common,
+                )
+            }
         };
         let editor_data = create_rw_signal(editor_data.scope, editor_data);
         data.editors.update(|editors| {
@@ -118,6 +185,60 @@ pub fn to_data(
 
 pub type SnippetIndex = Vec<(usize, (usize, usize))>;
 
+/// How long after a movement its repeats are held back for, to run them at
+/// once, about a frame
+const MOVE_BATCH_INTERVAL: Duration = Duration::from_millis(16);
+
+/// How soon after a movement a repeat of it is taken to be from the key being
+/// held down, longer than the slowest key repeat rates
+const KEY_REPEAT_INTERVAL: Duration = Duration::from_millis(150);
+
+/// The repeats of a movement that come in within a frame of it, as when a key
+/// is held down at a high repeat rate. They're run at once at the end of the
+/// frame, so that the cursor and the view are updated once a frame rather than
+/// once a key event.
+#[derive(Clone, Default)]
+pub struct MoveBatch {
+    /// The last movement run, with its modifiers and when it was run
+    last: Option<(Movement, Modifiers, Instant)>,
+    /// How many repeats of it are held back
+    pending: usize,
+}
+
+/// A save held back as the document has characters its encoding can't
+/// represent, until it's said what to do with them
+#[derive(Clone)]
+pub struct EncodingCheck {
+    pub editor: EditorData,
+    pub exit: bool,
+    pub allow_formatting: bool,
+    pub chars: Vec<UnmappableChar>,
+}
+
+impl EncodingCheck {
+    /// Save, writing what the fallback says in place of the characters. The
+    /// next saves check the document again.
+    pub fn save_with(&self, fallback: EncodingFallback) {
+        self.editor.common.encoding_check.set(None);
+        self.editor.save_with_fallback(
+            self.exit,
+            self.allow_formatting,
+            Some(fallback),
+        );
+    }
+
+    /// Save the file in UTF-8 from now on, which represents every character
+    pub fn save_as_utf8(&self) {
+        self.editor.common.encoding_check.set(None);
+        self.editor.view.doc.update(|doc| doc.encoding = UTF_8);
+        self.editor.save(self.exit, self.allow_formatting);
+    }
+
+    pub fn cancel(&self) {
+        self.editor.common.encoding_check.set(None);
+    }
+}
+
 #[derive(Clone)]
 pub struct EditorData {
     pub scope: Scope,
@@ -133,10 +254,21 @@ pub struct EditorData {
     pub snippet: RwSignal<Option<SnippetIndex>>,
     pub last_movement: RwSignal<Movement>,
     pub inline_find: RwSignal<Option<InlineFindDirection>>,
+    /// Whether the next character typed names the register for the next
+    /// yank, delete or paste, after `"` in normal mode
+    pub register_prefix: RwSignal<bool>,
     pub last_inline_find: RwSignal<Option<(InlineFindDirection, String)>>,
     pub find_focus: RwSignal<bool>,
     pub active: RwSignal<bool>,
     pub sticky_header_height: RwSignal<f64>,
+    /// The change to the file since `HEAD` whose popup is open, after
+    /// clicking its marker in the gutter
+    pub hunk_popup: RwSignal<Option<DiffHunk>>,
+    /// The repeats of the last movement held back to be run at once
+    pub move_batch: RwSignal<MoveBatch>,
+    /// The frame of scrolling part of the way to the cursor, while a held key
+    /// moves it and the `editor.smooth-scrolling` setting is on
+    pub scroll_easing: RwSignal<Option<u64>>,
     pub common: CommonData,
 }
 
@@ -170,6 +302,7 @@ pub fn new(
         let view = EditorViewData::new(
             doc,
             create_rw_signal(cx, EditorViewKind::Normal),
+            create_rw_signal(cx, None),
             common.config,
         );
         Self {
@@ -186,10 +319,14 @@ pub fn new(
             scroll_to: create_rw_signal(cx, None),
             last_movement: create_rw_signal(cx, Movement::Left),
             inline_find: create_rw_signal(cx, None),
+            register_prefix: create_rw_signal(cx, false),
             last_inline_find: create_rw_signal(cx, None),
             find_focus: create_rw_signal(cx, false),
             active: create_rw_signal(cx, false),
             sticky_header_height: create_rw_signal(cx, 0.0),
+            hunk_popup: create_rw_signal(cx, None),
+            move_batch: create_rw_signal(cx, MoveBatch::default()),
+            scroll_easing: create_rw_signal(cx, None),
             common,
         }
     }
@@ -246,26 +383,39 @@ pub fn copy(
             snippet: create_rw_signal(cx, None),
             last_movement: create_rw_signal(cx, self.last_movement.get_untracked()),
             inline_find: create_rw_signal(cx, None),
+            register_prefix: create_rw_signal(cx, false),
             last_inline_find: create_rw_signal(cx, None),
             find_focus: create_rw_signal(cx, false),
             active: create_rw_signal(cx, false),
             sticky_header_height: create_rw_signal(cx, 0.0),
+            hunk_popup: create_rw_signal(cx, None),
+            move_batch: create_rw_signal(cx, MoveBatch::default()),
+            scroll_easing: create_rw_signal(cx, None),
             common: self.common.clone(),
         }
     }
 
     fn run_edit_command(&self, cmd: &EditCommand) -> CommandExecuted {
+        let is_local = self.view.doc.with_untracked(|doc| doc.content.is_local());
         let modal = self
             .common
             .config
             .with_untracked(|config| config.core.modal)
-            && !self.view.doc.with_untracked(|doc| doc.content.is_local());
+            && !is_local;
+        // Without modes to leave, Escape closes the popup on top first
+        if let EditCommand::NormalMode = cmd {
+            if !modal && !is_local && self.common.overlays.escape() {
+                return CommandExecuted::Yes;
+            }
+        }
+        self.clear_inline_suggestion();
         let doc_before_edit = self
             .view
             .doc
             .with_untracked(|doc| doc.buffer().text().clone());
         let mut cursor = self.cursor.get_untracked();
         let mut register = self.common.register.get_untracked();
+        register.load_clipboard(&SystemClipboard {});
 
         let yank_data =
             if let lapce_core::cursor::CursorMode::Visual { .. } = &cursor.mode {
@@ -291,6 +441,7 @@ fn run_edit_command(&self, cmd: &EditCommand) -> CommandExecuted {
         }
 
         self.cursor.set(cursor);
+        register.store_clipboard(&mut SystemClipboard {});
         self.common.register.set(register);
 
         if show_completion(cmd, &doc_before_edit, &deltas) {
@@ -302,10 +453,21 @@ fn run_edit_command(&self, cmd: &EditCommand) -> CommandExecuted {
         if let EditCommand::NormalMode = cmd {
             self.snippet.set(None);
         }
+        if !deltas.is_empty() {
+            self.update_inline_suggestion();
+        }
 
         CommandExecuted::Yes
     }
 
+    /// Paste the content of the register `name` at the cursor
+    pub fn paste_register(&self, name: char) {
+        self.common
+            .register
+            .update(|register| register.selected = Some(name));
+        self.run_edit_command(&EditCommand::Paste);
+    }
+
     fn run_motion_mode_command(&self, cmd: &MotionModeCommand) -> CommandExecuted {
         let motion_mode = match cmd {
             MotionModeCommand::MotionModeDelete => MotionMode::Delete,
@@ -321,6 +483,7 @@ fn run_motion_mode_command(&self, cmd: &MotionModeCommand) -> CommandExecuted {
         });
 
         self.cursor.set(cursor);
+        register.store_clipboard(&mut SystemClipboard {});
         self.common.register.set(register);
 
         CommandExecuted::Yes
@@ -333,7 +496,7 @@ fn run_multi_selection_command(
         let mut cursor = self.cursor.get_untracked();
         movement::do_multi_selection(&self.view, &mut cursor, cmd);
         self.cursor.set(cursor);
-        // self.cancel_signature();
+        self.cancel_signature();
         self.cancel_completion();
         CommandExecuted::Yes
     }
@@ -344,6 +507,10 @@ fn run_move_command(
         count: Option<usize>,
         mods: Modifiers,
     ) -> CommandExecuted {
+        self.clear_inline_suggestion();
+        if count.is_none() && self.batch_movement(movement, mods) {
+            return CommandExecuted::Yes;
+        }
         if movement.is_jump() && movement != &self.last_movement.get_untracked() {
             let path = self
                 .view
@@ -362,14 +529,85 @@ fn run_move_command(
             }
         }
         self.last_movement.set(movement.clone());
+        self.move_cursor(movement, count.unwrap_or(1), mods);
+        CommandExecuted::Yes
+    }
+
+    /// Hold back a movement that repeats the one run less than a frame ago,
+    /// to be run with the other repeats that come in at the end of the frame.
+    /// Returns whether it was held back.
+    fn batch_movement(&self, movement: &Movement, mods: Modifiers) -> bool {
+        let now = Instant::now();
+        let since_last = self.move_batch.with_untracked(|batch| {
+            batch
+                .last
+                .as_ref()
+                .filter(|(last, last_mods, _)| {
+                    last == movement && *last_mods == mods
+                })
+                .map(|(_, _, at)| now.duration_since(*at))
+        });
+        // Scroll smoothly after the cursor while the key is held down
+        let easing = self.common.config.get_untracked().editor.smooth_scrolling
+            && since_last.map_or(false, |d| d < KEY_REPEAT_INTERVAL);
+        if self.scroll_easing.get_untracked().is_some() != easing {
+            self.scroll_easing.set(easing.then_some(0));
+        }
+
+        let batchable = matches!(
+            movement,
+            Movement::Up | Movement::Down | Movement::Left | Movement::Right
+        );
+        let since_last = since_last.filter(|d| *d < MOVE_BATCH_INTERVAL);
+        let Some(since_last) = since_last.filter(|_| batchable) else {
+            // What was held back was before this
+            self.run_move_batch();
+            self.move_batch.update(|batch| {
+                batch.last = Some((movement.clone(), mods, now));
+            });
+            return false;
+        };
+
+        let mut first = false;
+        self.move_batch.update(|batch| {
+            first = batch.pending == 0;
+            batch.pending += 1;
+        });
+        if first {
+            let editor = self.clone();
+            let wait = MOVE_BATCH_INTERVAL.saturating_sub(since_last);
+            exec_after(self.scope, wait, move || editor.run_move_batch());
+        }
+        true
+    }
+
+    /// Run the repeats of the last movement held back by
+    /// [`Self::batch_movement`], as one movement
+    fn run_move_batch(&self) {
+        let mut batch = None;
+        self.move_batch.update(|b| {
+            if b.pending == 0 {
+                return;
+            }
+            if let Some((movement, mods, at)) = b.last.as_mut() {
+                *at = Instant::now();
+                batch = Some((movement.clone(), *mods, b.pending));
+            }
+            b.pending = 0;
+        });
+        if let Some((movement, mods, count)) = batch {
+            self.move_cursor(&movement, count, mods);
+        }
+    }
 
+    fn move_cursor(&self, movement: &Movement, count: usize, mods: Modifiers) {
         let mut cursor = self.cursor.get_untracked();
         self.common.register.update(|register| {
             movement::move_cursor(
                 &self.view,
                 &mut cursor,
                 movement,
-                count.unwrap_or(1),
+                count,
                 mods.shift(),
                 register,
             )
@@ -393,7 +631,18 @@ fn run_move_command(
             })
         }
         self.cancel_completion();
-        CommandExecuted::Yes
+        self.update_signature();
+    }
+
+    /// Go on scrolling part of the way to the cursor in the next frame, while
+    /// it's still the frame given
+    pub fn next_scroll_easing_frame(&self, frame: u64) {
+        let scroll_easing = self.scroll_easing;
+        exec_after(self.scope, MOVE_BATCH_INTERVAL, move || {
+            if scroll_easing.get_untracked() == Some(frame) {
+                scroll_easing.set(Some(frame + 1));
+            }
+        });
     }
 
     fn run_focus_command(
@@ -473,6 +722,35 @@ fn run_focus_command(
                         .send(InternalCommand::SplitExchange { editor_tab_id });
                 }
             }
+            FocusCommand::SplitRotate => {
+                if let Some(editor_tab_id) = self.editor_tab_id {
+                    self.common
+                        .internal_command
+                        .send(InternalCommand::SplitRotate { editor_tab_id });
+                }
+            }
+            FocusCommand::SplitGrow | FocusCommand::SplitShrink => {
+                if let Some(editor_tab_id) = self.editor_tab_id {
+                    let delta = if cmd == &FocusCommand::SplitGrow {
+                        SPLIT_RESIZE_STEP
+                    } else {
+                        -SPLIT_RESIZE_STEP
+                    };
+                    self.common.internal_command.send(
+                        InternalCommand::SplitResize {
+                            editor_tab_id,
+                            delta,
+                        },
+                    );
+                }
+            }
+            FocusCommand::SplitMaximize => {
+                if let Some(editor_tab_id) = self.editor_tab_id {
+                    self.common
+                        .internal_command
+                        .send(InternalCommand::SplitMaximize { editor_tab_id });
+                }
+            }
             FocusCommand::SplitClose => {
                 if let Some(editor_tab_id) = self.editor_tab_id {
                     self.common.internal_command.send(
@@ -518,6 +796,15 @@ fn run_focus_command(
             FocusCommand::ListSelect => {
                 self.select_completion();
             }
+            FocusCommand::GetCompletion => {
+                self.update_completion(true);
+            }
+            FocusCommand::AcceptInlineSuggestion => {
+                return self.accept_inline_suggestion(true);
+            }
+            FocusCommand::AcceptInlineSuggestionWord => {
+                return self.accept_inline_suggestion(false);
+            }
             FocusCommand::JumpToNextSnippetPlaceholder => {
                 self.snippet.update(|snippet| {
                     if let Some(snippet_mut) = snippet.as_mut() {
@@ -549,7 +836,7 @@ fn run_focus_command(
                         if last_placeholder {
                             *snippet = None;
                         }
-                        // self.update_signature();
+                        self.update_signature();
                         self.cancel_completion();
                     }
                 });
@@ -581,7 +868,7 @@ fn run_focus_command(
                                     cursor.set_insert(selection);
                                 });
                             }
-                            // self.update_signature();
+                            self.update_signature();
                             self.cancel_completion();
                         }
                     }
@@ -590,6 +877,22 @@ fn run_focus_command(
             FocusCommand::GotoDefinition => {
                 self.go_to_definition();
             }
+            FocusCommand::GetSignature => {
+                self.update_signature();
+            }
+            FocusCommand::NextSignature => {
+                self.common.signature.update(|signature| signature.next());
+            }
+            FocusCommand::PreviousSignature => {
+                self.common
+                    .signature
+                    .update(|signature| signature.previous());
+            }
+            FocusCommand::ToggleSignatureDocumentation => {
+                self.common
+                    .signature
+                    .update(|signature| signature.toggle_documentation());
+            }
             FocusCommand::ShowCodeActions => {
                 self.show_code_actions(false);
             }
@@ -605,6 +908,39 @@ fn run_focus_command(
             FocusCommand::Save => {
                 self.save(false, true);
             }
+            FocusCommand::FormatDocument => {
+                self.format_document();
+            }
+            FocusCommand::FormatSelection => {
+                self.format_selection();
+            }
+            FocusCommand::OrganizeImports => {
+                self.organize_imports();
+            }
+            FocusCommand::ConvertIndentationToTabs => {
+                self.convert_indentation(IndentStyle::Tabs);
+            }
+            FocusCommand::Reflow => {
+                self.reflow();
+            }
+            FocusCommand::RebasePick => {
+                self.set_rebase_action(RebaseAction::Pick);
+            }
+            FocusCommand::RebaseReword => {
+                self.set_rebase_action(RebaseAction::Reword);
+            }
+            FocusCommand::RebaseEdit => {
+                self.set_rebase_action(RebaseAction::Edit);
+            }
+            FocusCommand::RebaseSquash => {
+                self.set_rebase_action(RebaseAction::Squash);
+            }
+            FocusCommand::RebaseFixup => {
+                self.set_rebase_action(RebaseAction::Fixup);
+            }
+            FocusCommand::RebaseDrop => {
+                self.set_rebase_action(RebaseAction::Drop);
+            }
             FocusCommand::InlineFindLeft => {
                 self.inline_find.set(Some(InlineFindDirection::Left));
             }
@@ -622,9 +958,31 @@ fn run_focus_command(
             FocusCommand::ClearSearch => {
                 self.clear_search();
             }
+            FocusCommand::ToggleSearchInSelection => {
+                self.toggle_search_in_selection();
+            }
             FocusCommand::Search => {
                 self.search();
             }
+            FocusCommand::ReplaceNext => {
+                self.replace_next(mods);
+            }
+            FocusCommand::ReplaceAll => {
+                self.replace_all();
+            }
+            FocusCommand::SearchHistoryPrevious
+            | FocusCommand::SearchHistoryNext => {
+                if self.common.find.visual.get_untracked()
+                    && self.find_focus.get_untracked()
+                {
+                    self.common.internal_command.send(
+                        InternalCommand::NavigateFindHistory {
+                            replace: self.common.find.replace_focus.get_untracked(),
+                            previous: *cmd == FocusCommand::SearchHistoryPrevious,
+                        },
+                    );
+                }
+            }
             FocusCommand::FocusFindEditor => {
                 self.common.find.replace_focus.set(false);
             }
@@ -653,6 +1011,22 @@ fn run_focus_command(
 
     /// Jump to the next/previous column on the line which matches the given text
     fn inline_find(&self, direction: InlineFindDirection, c: &str) {
+        if let Some(offset) = self.inline_find_offset(direction, c) {
+            self.run_move_command(
+                &lapce_core::movement::Movement::Offset(offset),
+                None,
+                Modifiers::empty(),
+            );
+        }
+    }
+
+    /// The offset of the next/previous column on the line which matches the
+    /// given text
+    pub(crate) fn inline_find_offset(
+        &self,
+        direction: InlineFindDirection,
+        c: &str,
+    ) -> Option<usize> {
         let offset = self.cursor.with_untracked(|c| c.offset());
         let (line_content, line_start_offset) =
             self.view.doc.with_untracked(|doc| {
@@ -662,7 +1036,7 @@ fn inline_find(&self, direction: InlineFindDirection, c: &str) {
                 (line_content.to_string(), line_start_offset)
             });
         let index = offset - line_start_offset;
-        if let Some(new_index) = match direction {
+        let new_index = match direction {
             InlineFindDirection::Left => line_content[..index].rfind(c),
             InlineFindDirection::Right => {
                 if index + 1 >= line_content.len() {
@@ -680,15 +1054,8 @@ fn inline_find(&self, direction: InlineFindDirection, c: &str) {
                     line_content[index..].find(c).map(|i| i + index)
                 }
             }
-        } {
-            self.run_move_command(
-                &lapce_core::movement::Movement::Offset(
-                    new_index + line_start_offset,
-                ),
-                None,
-                Modifiers::empty(),
-            );
-        }
+        }?;
+        Some(new_index + line_start_offset)
     }
 
     fn go_to_definition(&self) {
@@ -851,6 +1218,7 @@ fn page_move(&self, down: bool, mods: Modifiers) {
     }
 
     fn scroll(&self, down: bool, count: usize, mods: Modifiers) {
+        self.common.overlays.dismiss(OverlayEvent::Scroll);
         let config = self.common.config.get_untracked();
         let viewport = self.viewport.get_untracked();
         let line_height = config.editor.line_height() as f64;
@@ -900,11 +1268,15 @@ fn scroll(&self, down: bool, count: usize, mods: Modifiers) {
         };
     }
 
-    fn select_completion(&self) {
-        let item = self
-            .common
-            .completion
-            .with_untracked(|c| c.current_item().cloned());
+    pub fn select_completion(&self) {
+        let item = self.common.completion.try_update(|c| {
+            let item = c.current_item().cloned();
+            if let Some(item) = item.as_ref() {
+                c.record_accepted(item);
+            }
+            item
+        });
+        let item = item.flatten();
         self.cancel_completion();
         if let Some(item) = item {
             if item.item.data.is_some() {
@@ -956,18 +1328,120 @@ pub fn cancel_completion(&self) {
         self.common.completion.update(|c| {
             c.cancel();
         });
+        self.common.overlays.hidden(OverlayKind::Completion);
 
         clear_completion_lens(self.view.doc);
     }
 
+    /// Ask the inline suggestion providers for text to suggest after the
+    /// cursor, when it's a single caret in insert mode
+    fn update_inline_suggestion(&self) {
+        let config = self.common.config.get_untracked();
+        if !config.editor.enable_inline_suggestions
+            || self.get_mode() != Mode::Insert
+        {
+            return;
+        }
+        let offset = match self.cursor.with_untracked(|c| c.mode.clone()) {
+            CursorMode::Insert(selection)
+                if selection.len() == 1 && selection.is_caret() =>
+            {
+                selection.get_cursor_offset()
+            }
+            _ => return,
+        };
+        let (content, text, rev) = self.view.doc.with_untracked(|doc| {
+            (doc.content.clone(), doc.buffer().text().clone(), doc.rev())
+        });
+        let DocContent::File(path) = content else {
+            return;
+        };
+        let view = self.view.clone();
+        let cursor = self.cursor;
+        self.common.inline_suggestions.suggest(
+            &path,
+            text,
+            offset,
+            move |suggestion| {
+                let Some(text) = suggestion else {
+                    return;
+                };
+                // The text or the cursor changed while the provider thought
+                if view.rev() != rev
+                    || cursor.with_untracked(|c| c.offset()) != offset
+                {
+                    return;
+                }
+                view.set_inline_suggestion(Some(InlineSuggestion {
+                    rev,
+                    offset,
+                    text,
+                }));
+            },
+        );
+    }
+
+    fn clear_inline_suggestion(&self) {
+        self.view.set_inline_suggestion(None);
+    }
+
+    /// The inline suggestion, if it's shown at the cursor
+    fn inline_suggestion_at_cursor(&self) -> Option<String> {
+        let offset = self.cursor.with_untracked(|c| c.offset());
+        self.view
+            .current_inline_suggestion()
+            .filter(|suggestion| suggestion.offset == offset)
+            .map(|suggestion| suggestion.text)
+    }
+
+    /// Insert the inline suggestion, or only its next word and keep showing
+    /// the rest of it
+    fn accept_inline_suggestion(&self, whole: bool) -> CommandExecuted {
+        let Some(suggestion) = self.inline_suggestion_at_cursor() else {
+            return CommandExecuted::No;
+        };
+        let len = if whole {
+            suggestion.len()
+        } else {
+            next_word_len(&suggestion)
+        };
+        let (accepted, rest) = suggestion.split_at(len);
+        let offset = self.cursor.with_untracked(|c| c.offset());
+        let selection = Selection::caret(offset);
+        self.do_edit(&selection, &[(selection.clone(), accepted)]);
+        if !rest.is_empty() {
+            let offset = self.cursor.with_untracked(|c| c.offset());
+            self.view.set_inline_suggestion(Some(InlineSuggestion {
+                rev: self.view.rev(),
+                offset,
+                text: rest.to_string(),
+            }));
+        }
+        CommandExecuted::Yes
+    }
+
     /// Update the displayed autocompletion box  
-    /// Sends a request to the LSP for completion information
-    fn update_completion(&self, display_if_empty_input: bool) {
+    /// Sends a request to the LSP for completion information  
+    /// `invoked` is whether the completion was explicitly requested, in which
+    /// case it is shown even without input and in languages it is disabled for
+    fn update_completion(&self, invoked: bool) {
         if self.get_mode() != Mode::Insert {
             self.cancel_completion();
             return;
         }
 
+        if !invoked {
+            let language = self
+                .view
+                .doc
+                .with_untracked(|doc| doc.syntax().language.to_string());
+            let config = self.common.config.get_untracked();
+            if !config.editor.completion_enabled(&language) {
+                self.cancel_completion();
+                return;
+            }
+        }
+
         let path = match self.view.doc.with_untracked(|doc| {
             if doc.loaded() {
                 doc.content.path().cloned()
@@ -996,8 +1470,12 @@ fn update_completion(&self, display_if_empty_input: bool) {
             };
             (start_offset, input, char)
         });
-        if !display_if_empty_input && input.is_empty() && char != "." && char != ":"
-        {
+        let is_trigger_character = char == "."
+            || char == ":"
+            || self.common.plugin_providers.with_untracked(|providers| {
+                providers.is_trigger_character(language_id_from_path(&path), &char)
+            });
+        if !invoked && input.is_empty() && !is_trigger_character {
             self.cancel_completion();
             return;
         }
@@ -1009,6 +1487,7 @@ fn update_completion(&self, display_if_empty_input: bool) {
         }) {
             self.common.completion.update(|completion| {
                 completion.update_input(input.clone());
+                completion.invoked = invoked;
 
                 let cursor_offset = self.cursor.with_untracked(|c| c.offset());
                 completion.update_document_completion(&self.view, cursor_offset);
@@ -1039,6 +1518,13 @@ fn update_completion(&self, display_if_empty_input: bool) {
                     );
                 }
             });
+            if self
+                .common
+                .completion
+                .with_untracked(|c| c.should_auto_insert())
+            {
+                self.select_completion();
+            }
             return;
         }
 
@@ -1047,6 +1533,7 @@ fn update_completion(&self, display_if_empty_input: bool) {
             completion.offset = start_offset;
             completion.input = input.clone();
             completion.status = CompletionStatus::Started;
+            completion.invoked = invoked;
             completion.input_items.clear();
             completion.request_id += 1;
             let start_pos = self
@@ -1074,7 +1561,90 @@ fn update_completion(&self, display_if_empty_input: bool) {
                     position,
                 );
             }
+            completion.show_cached();
+        });
+        if self.has_completions() {
+            self.common.overlays.show(OverlayKind::Completion);
+        }
+
+        // Let the cached items shown say they're stale if the server takes
+        // too long to answer
+        let completion = self.common.completion;
+        let request_id = completion.with_untracked(|c| c.request_id);
+        exec_after(self.scope, COMPLETION_TIMEOUT, move || {
+            completion.update(|completion| completion.request_timed_out(request_id));
+        });
+    }
+
+    /// Request the signature help of the call the cursor is in, or close it
+    /// if the cursor isn't in the arguments of a call.
+    fn update_signature(&self) {
+        if self.get_mode() != Mode::Insert
+            || !self
+                .common
+                .config
+                .with_untracked(|config| config.editor.show_signature)
+        {
+            self.cancel_signature();
+            return;
+        }
+
+        let path = match self.view.doc.with_untracked(|doc| {
+            if doc.loaded() {
+                doc.content.path().cloned()
+            } else {
+                None
+            }
+        }) {
+            Some(path) => path,
+            None => return,
+        };
+
+        let offset = self.cursor.with_untracked(|c| c.offset());
+        let start_offset = self.view.doc.with_untracked(|doc| {
+            doc.syntax()
+                .find_enclosing_parentheses(offset)
+                .map(|(start, _)| start)
+        });
+        let Some(start_offset) = start_offset else {
+            self.cancel_signature();
+            return;
+        };
+
+        let position = self
+            .view
+            .doc
+            .with_untracked(|doc| doc.buffer().offset_to_position(offset));
+        self.common.signature.update(|signature| {
+            signature.request(
+                self.editor_id,
+                &self.common.proxy,
+                path,
+                start_offset,
+                position,
+            );
+        });
+    }
+
+    pub fn cancel_signature(&self) {
+        if self
+            .common
+            .signature
+            .with_untracked(|s| s.status == SignatureStatus::Inactive)
+        {
+            return;
+        }
+        self.common.signature.update(|signature| {
+            signature.cancel();
         });
+        self.common.overlays.hidden(OverlayKind::Signature);
+    }
+
+    /// Check if there are signatures that are being rendered
+    fn has_signature(&self) -> bool {
+        self.common
+            .signature
+            .with_untracked(|signature| signature.is_active())
     }
 
     /// Check if there are completions that are being rendered
@@ -1300,16 +1870,87 @@ pub fn do_text_edit(&self, edits: &[TextEdit]) {
         self.do_edit(&selection, &edits);
     }
 
+    /// Open the popup of the change to the file since `HEAD` whose marker in
+    /// the gutter is at the line
+    pub fn show_hunk_at(&self, line: usize) {
+        let hunks = self.view.doc.with_untracked(|doc| doc.head_hunks());
+        let hunk = hunks.into_iter().find(|hunk| {
+            hunk.right.contains(&line)
+                || (hunk.right.is_empty() && hunk.right.start == line)
+        });
+        if let Some(hunk) = hunk {
+            self.hunk_popup.set(Some(hunk));
+            self.common.overlays.show(OverlayKind::Hunk);
+        }
+    }
+
+    pub fn close_hunk_popup(&self) {
+        if self.hunk_popup.with_untracked(|hunk| hunk.is_some()) {
+            self.hunk_popup.set(None);
+            self.common.overlays.hidden(OverlayKind::Hunk);
+        }
+    }
+
+    /// Replace the lines of the change with the ones at `HEAD`, as an edit
+    /// that can be undone
+    pub fn revert_hunk(&self, hunk: &DiffHunk) {
+        self.close_hunk_popup();
+        let edit = self.view.doc.with_untracked(|doc| {
+            let original = doc.head_hunk_original(hunk)?;
+            let buffer = doc.buffer();
+            let start = buffer.offset_of_line(hunk.right.start);
+            let end = buffer.offset_of_line(hunk.right.end);
+            let selection = self.cursor.get_untracked().edit_selection(buffer);
+            Some((selection, Selection::region(start, end), original))
+        });
+        if let Some((selection, region, original)) = edit {
+            self.do_edit(&selection, &[(region, original.as_str())]);
+        }
+    }
+
+    /// Stage the change alone, leaving the other changes to the file
+    pub fn stage_hunk(&self, hunk: &DiffHunk) {
+        self.close_hunk_popup();
+        let (path, lines) = self.view.doc.with_untracked(|doc| {
+            let path = match &doc.content {
+                DocContent::File(path) => Some(path.clone()),
+                _ => None,
+            };
+            (path, doc.hunk_lines(hunk))
+        });
+        if let Some(path) = path {
+            self.common
+                .proxy
+                .git_stage_hunk(path, hunk.left.clone(), lines);
+        }
+    }
+
+    /// Copy the lines of the file at `HEAD` that the change replaced
+    pub fn copy_hunk_original(&self, hunk: &DiffHunk) {
+        self.close_hunk_popup();
+        let original = self
+            .view
+            .doc
+            .with_untracked(|doc| doc.head_hunk_original(hunk));
+        if let Some(original) = original {
+            SystemClipboard {}.put_string(original);
+        }
+    }
+
     fn apply_deltas(&self, deltas: &[(RopeDelta, InvalLines, SyntaxEdit)]) {
         if !deltas.is_empty() && !self.confirmed.get_untracked() {
             self.confirmed.set(true);
         }
+        if !deltas.is_empty() {
+            // The lines of the change would be out of date
+            self.close_hunk_popup();
+        }
         for (delta, _, _) in deltas {
             // self.inactive_apply_delta(delta);
             self.update_snippet_offset(delta);
             // self.update_breakpoints(delta);
         }
-        // self.update_signature();
+        self.update_signature();
     }
 
     fn update_snippet_offset(&self, delta: &RopeDelta) {
@@ -1498,11 +2139,14 @@ pub fn show_code_actions(&self, mouse_click: bool) {
         }
     }
 
-    fn do_save(&self) {
-        let (rev, content) = self
-            .view
-            .doc
-            .with_untracked(|doc| (doc.rev(), doc.content.clone()));
+    fn do_save(&self, fallback: Option<EncodingFallback>) {
+        let (rev, content, encoding) = self.view.doc.with_untracked(|doc| {
+            (
+                doc.rev(),
+                doc.content.clone(),
+                doc.encoding.name().to_string(),
+            )
+        });
 
         let doc = self.view.doc;
         let send = create_ext_action(self.scope, move |result| {
@@ -1517,13 +2161,30 @@ fn do_save(&self) {
         });
 
         if let DocContent::File(path) = content {
-            self.common.proxy.save(rev, path, move |result| {
-                send(result);
-            })
+            self.common.proxy.save_with_encoding(
+                rev,
+                path,
+                encoding,
+                fallback.unwrap_or_default(),
+                move |result| {
+                    send(result);
+                },
+            )
         }
     }
 
     pub fn save(&self, exit: bool, allow_formatting: bool) {
+        self.save_with_fallback(exit, allow_formatting, None);
+    }
+
+    /// Save, with what to write in place of the characters the encoding of
+    /// the document can't represent, once that was asked for this save
+    fn save_with_fallback(
+        &self,
+        exit: bool,
+        allow_formatting: bool,
+        fallback: Option<EncodingFallback>,
+    ) {
         let (rev, is_pristine, content) = self.view.doc.with_untracked(|doc| {
             (doc.rev(), doc.buffer().is_pristine(), doc.content.clone())
         });
@@ -1533,43 +2194,443 @@ pub fn save(&self, exit: bool, allow_formatting: bool) {
             return;
         }
 
-        let config = self.common.config.get_untracked();
         if let DocContent::File(path) = content {
-            let format_on_save = allow_formatting && config.editor.format_on_save;
-            if format_on_save {
+            if rebase_todo::is_rebase_todo(&path) && !self.validate_rebase_todo() {
+                return;
+            }
+            let chars = if fallback.is_some() {
+                Vec::new()
+            } else {
+                self.view.doc.with_untracked(|doc| doc.unmappable_chars())
+            };
+            if !chars.is_empty() {
+                self.common.encoding_check.set(Some(EncodingCheck {
+                    editor: self.clone(),
+                    exit,
+                    allow_formatting,
+                    chars,
+                }));
+                return;
+            }
+            let config = self.common.config.get_untracked();
+            if allow_formatting && config.editor.organize_imports_on_save {
                 let editor = self.clone();
-                let send = create_ext_action(self.scope, move |result| {
-                    if let Ok(Ok(ProxyResponse::GetDocumentFormatting { edits })) =
-                        result
-                    {
-                        let current_rev =
-                            editor.view.doc.with_untracked(|doc| doc.rev());
-                        if current_rev == rev {
-                            editor.do_text_edit(&edits);
+                let local_path = path.clone();
+                let send = create_ext_action(
+                    self.scope,
+                    move |edit: Option<WorkspaceEdit>| {
+                        if let Some(edit) = edit {
+                            let current_rev =
+                                editor.view.doc.with_untracked(|doc| doc.rev());
+                            if current_rev == rev {
+                                editor.apply_organize_imports(&local_path, &edit);
+                            }
                         }
-                    }
-                    editor.do_save();
-                });
+                        editor.format_and_save(
+                            local_path,
+                            allow_formatting,
+                            fallback,
+                        );
+                    },
+                );
 
-                let (tx, rx) = crossbeam_channel::bounded(1);
                 let proxy = self.common.proxy.clone();
+                let range = self.view.doc.with_untracked(|doc| lsp_types::Range {
+                    start: Position::new(0, 0),
+                    end: doc.buffer().offset_to_position(doc.buffer().len()),
+                });
                 std::thread::spawn(move || {
-                    proxy.get_document_formatting(path, move |result| {
-                        let _ = tx.send(result);
+                    send(request_organize_imports_edit(&proxy, path, range));
+                });
+            } else {
+                self.format_and_save(path, allow_formatting, fallback);
+            }
+        } else if content == DocContent::SearchResults {
+            self.common
+                .internal_command
+                .send(InternalCommand::ApplySearchResults);
+        }
+    }
+
+    fn format_and_save(
+        &self,
+        path: PathBuf,
+        allow_formatting: bool,
+        fallback: Option<EncodingFallback>,
+    ) {
+        let (rev, language) = self
+            .view
+            .doc
+            .with_untracked(|doc| (doc.rev(), doc.syntax().language.to_string()));
+        let config = self.common.config.get_untracked();
+        let format_on_save =
+            allow_formatting && config.editor.format_on_save(&language);
+        if format_on_save {
+            let editor = self.clone();
+            let send = create_ext_action(self.scope, move |result| {
+                if let Ok(Ok(ProxyResponse::GetDocumentFormatting { edits })) =
+                    result
+                {
+                    let current_rev =
+                        editor.view.doc.with_untracked(|doc| doc.rev());
+                    if current_rev == rev {
+                        editor.do_text_edit(&edits);
+                    }
+                }
+                editor.do_save(fallback);
+            });
+
+            let (tx, rx) = crossbeam_channel::bounded(1);
+            let proxy = self.common.proxy.clone();
+            let options = self.formatting_options();
+            std::thread::spawn(move || {
+                proxy.get_document_formatting(path, options, move |result| {
+                    let _ = tx.send(result);
+                });
+                let result = rx.recv_timeout(Duration::from_secs(1));
+                send(result);
+            });
+        } else {
+            self.do_save(fallback);
+        }
+    }
+
+    /// Run the organize imports code action of the language server for this
+    /// document.
+    fn organize_imports(&self) {
+        let (content, range) = self.view.doc.with_untracked(|doc| {
+            (
+                doc.content.clone(),
+                lsp_types::Range {
+                    start: Position::new(0, 0),
+                    end: doc.buffer().offset_to_position(doc.buffer().len()),
+                },
+            )
+        });
+        let DocContent::File(path) = content else {
+            return;
+        };
+
+        let internal_command = self.common.internal_command;
+        let send = create_ext_action(self.scope, move |result| {
+            if let Ok(ProxyResponse::GetCodeActionsResponse { plugin_id, resp }) =
+                result
+            {
+                if let Some(action) = organize_imports_action(resp) {
+                    internal_command.send(InternalCommand::RunCodeAction {
+                        plugin_id,
+                        action: CodeActionOrCommand::CodeAction(action),
                     });
-                    let result = rx.recv_timeout(std::time::Duration::from_secs(1));
-                    send(result);
+                }
+            }
+        });
+        self.common
+            .proxy
+            .get_organize_imports(path, range, move |result| {
+                send(result);
+            });
+    }
+
+    /// Apply an organize imports edit before saving. The part which targets
+    /// this document is applied right away as a single edit, the edits to
+    /// other files go through the workspace edit, like a code action.
+    fn apply_organize_imports(&self, path: &Path, edit: &WorkspaceEdit) {
+        if let Some(DocumentChanges::Operations(ops)) =
+            edit.document_changes.as_ref()
+        {
+            if ops
+                .iter()
+                .any(|op| matches!(op, DocumentChangeOperation::Op(_)))
+            {
+                error!(
+                    "Organize imports on save skipped, its edit creates, renames or deletes files"
+                );
+                return;
+            }
+        }
+        let Some(edits) = workspace_edits(edit) else {
+            return;
+        };
+        let mut other_files = HashMap::new();
+        for (url, edits) in edits {
+            if url.to_file_path().ok().as_deref() == Some(path) {
+                self.do_text_edit(&edits);
+            } else {
+                other_files.insert(url, edits);
+            }
+        }
+        if !other_files.is_empty() {
+            self.common
+                .internal_command
+                .send(InternalCommand::ApplyWorkspaceEdit {
+                    edit: WorkspaceEdit {
+                        changes: Some(other_files),
+                        ..Default::default()
+                    },
                 });
+        }
+    }
+
+    /// Request formatting edits for the whole document and apply them as a
+    /// single edit, so that one undo reverts the formatting.
+    fn format_document(&self) {
+        let (rev, content) = self
+            .view
+            .doc
+            .with_untracked(|doc| (doc.rev(), doc.content.clone()));
+        let DocContent::File(path) = content else {
+            return;
+        };
+
+        let editor = self.clone();
+        let send = create_ext_action(self.scope, move |result| {
+            if let Ok(ProxyResponse::GetDocumentFormatting { edits }) = result {
+                editor.apply_formatting(rev, &edits);
+            }
+        });
+        self.common.proxy.get_document_formatting(
+            path,
+            self.formatting_options(),
+            move |result| {
+                send(result);
+            },
+        );
+    }
+
+    /// Request formatting edits for the current selection. Falls back to
+    /// formatting the whole document when there is no selection.
+    fn format_selection(&self) {
+        let (rev, content, range) = self.view.doc.with_untracked(|doc| {
+            let selection = self.cursor.get_untracked().edit_selection(doc.buffer());
+            let range = if selection.is_caret() {
+                None
+            } else {
+                Some(lsp_types::Range {
+                    start: doc.buffer().offset_to_position(selection.min_offset()),
+                    end: doc.buffer().offset_to_position(selection.max_offset()),
+                })
+            };
+            (doc.rev(), doc.content.clone(), range)
+        });
+        let DocContent::File(path) = content else {
+            return;
+        };
+        let Some(range) = range else {
+            self.format_document();
+            return;
+        };
+
+        let editor = self.clone();
+        let send = create_ext_action(self.scope, move |result| {
+            if let Ok(ProxyResponse::GetDocumentRangeFormatting { edits }) = result {
+                editor.apply_formatting(rev, &edits);
+            }
+        });
+        self.common.proxy.get_document_range_formatting(
+            path,
+            range,
+            self.formatting_options(),
+            move |result| {
+                send(result);
+            },
+        );
+    }
+
+    /// Re-indent the whole document with the given style as a single edit,
+    /// and use that style for further editing.
+    pub fn convert_indentation(&self, style: IndentStyle) {
+        let tab_width = self.view.doc.with_untracked(|doc| doc.tab_width());
+        let (selection, edits) = self.view.doc.with_untracked(|doc| {
+            (
+                self.cursor.get_untracked().edit_selection(doc.buffer()),
+                convert_indent_edits(doc.buffer().text(), tab_width, style),
+            )
+        });
+        if !edits.is_empty() {
+            let edits = edits
+                .iter()
+                .map(|(selection, content)| (selection, content.as_str()))
+                .collect::<Vec<_>>();
+            self.do_edit(&selection, &edits);
+        }
+        self.view.doc.update(|doc| {
+            doc.buffer_mut().set_indent_style(style);
+        });
+    }
+
+    /// Hard-wrap the selected lines, or the paragraph around the cursor, at the
+    /// line width of the language or else the configured text width, or at the
+    /// body width of a commit message.
+    pub fn reflow(&self) {
+        let text_width = self.common.config.get_untracked().editor.text_width;
+        let cursor = self.cursor.get_untracked();
+        let edit = self.view.doc.with_untracked(|doc| {
+            let buffer = doc.buffer();
+            let language = doc.syntax().language;
+            let width = if doc.is_commit_message() {
+                commit_message::BODY_WIDTH
             } else {
-                self.do_save();
+                language.line_width().unwrap_or(text_width)
+            };
+            let comment_token = language.comment_token();
+            let is_blank = |line: usize| {
+                reflow::is_blank(&buffer.line_content(line), &comment_token)
+            };
+
+            let selection = cursor.edit_selection(buffer);
+            let has_selection = match &cursor.mode {
+                CursorMode::Normal(_) => false,
+                CursorMode::Visual { .. } => true,
+                CursorMode::Insert(selection) => !selection.is_caret(),
+            };
+            let (start_line, end_line) = if has_selection {
+                let end = selection.max_offset();
+                let end = end.saturating_sub(1).max(selection.min_offset());
+                (
+                    buffer.line_of_offset(selection.min_offset()),
+                    buffer.line_of_offset(end),
+                )
+            } else {
+                let line = buffer.line_of_offset(cursor.offset());
+                if is_blank(line) {
+                    return None;
+                }
+                let mut start_line = line;
+                while start_line > 0 && !is_blank(start_line - 1) {
+                    start_line -= 1;
+                }
+                let mut end_line = line;
+                while end_line < buffer.last_line() && !is_blank(end_line + 1) {
+                    end_line += 1;
+                }
+                (start_line, end_line)
+            };
+
+            let start = buffer.offset_of_line(start_line);
+            let end = buffer.line_end_offset(end_line, true);
+            let text = buffer.slice_to_cow(start..end);
+            let reflowed = reflow::reflow(&text, width, &comment_token);
+            (reflowed != text).then_some((start, end, reflowed))
+        });
+
+        if let Some((start, end, reflowed)) = edit {
+            self.do_edit(
+                &Selection::caret(start),
+                &[(Selection::region(start, end), reflowed.as_str())],
+            );
+        }
+    }
+
+    /// Change the action of the commits on the selected lines of the todo list
+    /// of an interactive rebase.
+    fn set_rebase_action(&self, action: RebaseAction) {
+        let cursor = self.cursor.get_untracked();
+        let (selection, edits) = self.view.doc.with_untracked(|doc| {
+            let buffer = doc.buffer();
+            let selection = cursor.edit_selection(buffer);
+            if !doc.is_rebase_todo() {
+                return (selection, Vec::new());
+            }
+
+            let mut lines = BTreeSet::new();
+            for region in selection.regions() {
+                let end = region.max().saturating_sub(1).max(region.min());
+                lines.extend(
+                    buffer.line_of_offset(region.min())..=buffer.line_of_offset(end),
+                );
             }
+            let edits: Vec<(Selection, String)> = lines
+                .into_iter()
+                .filter_map(|line| {
+                    let start = buffer.offset_of_line(line);
+                    let end = buffer.line_end_offset(line, true);
+                    let content = buffer.slice_to_cow(start..end);
+                    let new_content = rebase_todo::set_action(&content, action)?;
+                    (new_content != content)
+                        .then(|| (Selection::region(start, end), new_content))
+                })
+                .collect();
+            (selection, edits)
+        });
+
+        if !edits.is_empty() {
+            let edits: Vec<(&Selection, &str)> = edits
+                .iter()
+                .map(|(selection, content)| (selection, content.as_str()))
+                .collect();
+            self.do_edit(&selection, &edits);
         }
     }
 
+    /// Check the todo list of an interactive rebase, showing the problems
+    /// that would make git reject it as diagnostics. Returns whether it's
+    /// valid.
+    fn validate_rebase_todo(&self) -> bool {
+        let diagnostics = self.view.doc.with_untracked(|doc| {
+            let buffer = doc.buffer();
+            rebase_todo::validate(&buffer.to_string())
+                .into_iter()
+                .map(|(line, message)| {
+                    let start = buffer.offset_of_line(line);
+                    let end = buffer.line_end_offset(line, true);
+                    EditorDiagnostic {
+                        range: (start, end),
+                        diagnostic: Diagnostic {
+                            range: lsp_types::Range {
+                                start: buffer.offset_to_position(start),
+                                end: buffer.offset_to_position(end),
+                            },
+                            severity: Some(DiagnosticSeverity::ERROR),
+                            source: Some("rebase".to_string()),
+                            message,
+                            ..Default::default()
+                        },
+                    }
+                })
+                .collect::<im::Vector<_>>()
+        });
+        let is_valid = diagnostics.is_empty();
+        self.view.doc.with_untracked(|doc| {
+            doc.diagnostics.diagnostics.set(diagnostics);
+        });
+        self.view.doc.update(|doc| doc.clear_text_cache());
+        is_valid
+    }
+
+    /// The formatting options sent to the language server, based on the
+    /// indentation used by the document.
+    fn formatting_options(&self) -> FormattingOptions {
+        let indent = self.view.indent_unit();
+        let insert_spaces = !indent.starts_with('\t');
+        let tab_size = if insert_spaces {
+            indent.len()
+        } else {
+            self.view.doc.with_untracked(|doc| doc.tab_width())
+        };
+        FormattingOptions {
+            tab_size: tab_size as u32,
+            insert_spaces,
+            ..Default::default()
+        }
+    }
+
+    /// Apply formatter edits if the document hasn't changed since they were
+    /// requested. The cursor is carried through the edit so the view stays
+    /// where it was.
+    fn apply_formatting(&self, rev: u64, edits: &[TextEdit]) {
+        if edits.is_empty() {
+            return;
+        }
+        if self.view.doc.with_untracked(|doc| doc.rev()) != rev {
+            return;
+        }
+        self.do_text_edit(edits);
+    }
+
     fn search_whole_word_forward(&self, mods: Modifiers) {
         let offset = self.cursor.with_untracked(|c| c.offset());
+        let (start, end) = self.view.select_word(offset);
         let (word, buffer) = self.view.doc.with_untracked(|doc| {
-            let (start, end) = doc.buffer().select_word(offset);
             (
                 doc.buffer().slice_to_cow(start..end).to_string(),
                 doc.buffer().clone(),
@@ -1589,10 +2650,87 @@ fn search_whole_word_forward(&self, mods: Modifiers) {
         }
     }
 
+    /// Add the patterns in the find bar to the search history.
+    fn record_search_history(&self, replace: bool) {
+        let history = &self.common.search_history;
+        let pattern = self.common.find.search_string.with_untracked(|search| {
+            search.as_ref().map(|search| search.content.clone())
+        });
+        if let Some(pattern) = pattern {
+            history.record(SearchHistoryKind::Search, &pattern);
+        }
+        if replace {
+            let replacement = self.common.find.replace_string.get_untracked();
+            history.record(SearchHistoryKind::Replace, &replacement);
+        }
+    }
+
+    /// The first match after `offset`, or the last one before it if `reverse`,
+    /// wrapping around. When searching in selection, only the matches within
+    /// it are considered.
+    fn next_match(
+        &self,
+        text: &Rope,
+        offset: usize,
+        reverse: bool,
+    ) -> Option<(usize, usize)> {
+        let find = &self.common.find;
+        let find_result = self.view.find_result();
+        if find_result.scope.with_untracked(|scope| scope.is_none()) {
+            return find.next(text, offset, reverse, true);
+        }
+
+        let matches = find_result.scoped(find.all_matches(text));
+        let regions = matches.regions();
+        let region = if reverse {
+            regions
+                .iter()
+                .rev()
+                .find(|region| region.min() < offset)
+                .or_else(|| regions.last())
+        } else {
+            regions
+                .iter()
+                .find(|region| region.min() > offset)
+                .or_else(|| regions.first())
+        };
+        region.map(|region| (region.min(), region.max()))
+    }
+
+    /// Constrain the find bar to the current selection, or search the whole
+    /// document again if it already is.
+    pub fn toggle_search_in_selection(&self) {
+        let find_result = self.view.find_result();
+        let scope = if find_result.scope.with_untracked(|scope| scope.is_some()) {
+            None
+        } else {
+            let buffer = self.view.doc.with_untracked(|doc| doc.buffer().clone());
+            let selection =
+                self.cursor.with_untracked(|c| c.edit_selection(&buffer));
+            let mut scope = Selection::new();
+            for region in selection.regions() {
+                if !region.is_caret() {
+                    scope.add_region(SelRegion::new(
+                        region.min(),
+                        region.max(),
+                        None,
+                    ));
+                }
+            }
+            if scope.is_empty() {
+                return;
+            }
+            Some(scope)
+        };
+        find_result.scope.set(scope);
+        find_result.reset();
+    }
+
     fn search_forward(&self, mods: Modifiers) {
+        self.record_search_history(false);
         let offset = self.cursor.with_untracked(|c| c.offset());
         let buffer = self.view.doc.with_untracked(|doc| doc.buffer().clone());
-        let next = self.common.find.next(buffer.text(), offset, false, true);
+        let next = self.next_match(buffer.text(), offset, false);
 
         if let Some((start, _end)) = next {
             self.run_move_command(
@@ -1604,9 +2742,10 @@ fn search_forward(&self, mods: Modifiers) {
     }
 
     fn search_backward(&self, mods: Modifiers) {
+        self.record_search_history(false);
         let offset = self.cursor.with_untracked(|c| c.offset());
         let buffer = self.view.doc.with_untracked(|doc| doc.buffer().clone());
-        let next = self.common.find.next(buffer.text(), offset, true, true);
+        let next = self.next_match(buffer.text(), offset, true);
 
         if let Some((start, _end)) = next {
             self.run_move_command(
@@ -1617,6 +2756,76 @@ fn search_backward(&self, mods: Modifiers) {
         }
     }
 
+    /// Replace the match at the cursor and move to the next one. If the
+    /// cursor isn't at a match, it only moves to the next one.
+    pub fn replace_next(&self, mods: Modifiers) {
+        self.record_search_history(true);
+        let offset = self.cursor.with_untracked(|c| c.offset());
+        let text = self
+            .view
+            .doc
+            .with_untracked(|doc| doc.buffer().text().clone());
+        let find = &self.common.find;
+        let find_result = self.view.find_result();
+        let Some((start, end)) = find
+            .match_at(&text, offset)
+            .filter(|(start, end)| find_result.in_scope(*start, *end))
+        else {
+            self.search_forward(mods);
+            return;
+        };
+
+        let replacement = find.replacement(&text, start, end);
+        self.do_edit(
+            &Selection::caret(start),
+            &[(Selection::region(start, end), replacement.as_str())],
+        );
+
+        // Continue after the replacement, so that it isn't matched itself
+        let text = self
+            .view
+            .doc
+            .with_untracked(|doc| doc.buffer().text().clone());
+        let after = (start + replacement.len()).saturating_sub(1);
+        if let Some((start, _)) = self.next_match(&text, after, false) {
+            self.run_move_command(
+                &lapce_core::movement::Movement::Offset(start),
+                None,
+                mods,
+            );
+        }
+    }
+
+    /// Replace all the matches in the document, as a single edit.
+    pub fn replace_all(&self) {
+        self.record_search_history(true);
+        let buffer = self.view.doc.with_untracked(|doc| doc.buffer().clone());
+        let text = buffer.text();
+        let find = &self.common.find;
+        let matches = self.view.find_result().scoped(find.all_matches(text));
+        let edits: Vec<(Selection, String)> = matches
+            .regions()
+            .iter()
+            .map(|region| {
+                let (start, end) = (region.min(), region.max());
+                (
+                    Selection::region(start, end),
+                    find.replacement(text, start, end),
+                )
+            })
+            .collect();
+        if edits.is_empty() {
+            return;
+        }
+
+        let selection = self.cursor.with_untracked(|c| c.edit_selection(&buffer));
+        let edits: Vec<(&Selection, &str)> = edits
+            .iter()
+            .map(|(selection, replacement)| (selection, replacement.as_str()))
+            .collect();
+        self.do_edit(&selection, &edits);
+    }
+
     pub fn save_doc_position(&self) {
         let path = match self.view.doc.with_untracked(|doc| {
             if doc.loaded() {
@@ -1762,9 +2971,42 @@ pub fn word_at_cursor(&self) -> String {
         }
     }
 
+    /// The first and last lines of the selection, or of the function or other
+    /// scope around the cursor if nothing is selected, or else the line of the
+    /// cursor
+    pub fn fit_lines(&self) -> (usize, usize) {
+        let (offset, selection) = self.cursor.with_untracked(|cursor| {
+            let selection = match &cursor.mode {
+                CursorMode::Normal(_) => None,
+                _ => Some(
+                    self.view
+                        .doc
+                        .with_untracked(|doc| cursor.edit_selection(doc.buffer())),
+                ),
+            };
+            (cursor.offset(), selection)
+        });
+        let (start, end) = match selection.filter(|s| !s.is_caret()) {
+            // The end of the selection is past its last character, which can
+            // be at the start of the next line
+            Some(selection) => (
+                selection.min_offset(),
+                selection.max_offset().saturating_sub(1),
+            ),
+            None => self
+                .view
+                .doc
+                .with_untracked(|doc| doc.syntax().focus_scope(offset))
+                .unwrap_or((offset, offset)),
+        };
+        let start_line = self.view.line_of_offset(start);
+        (start_line, self.view.line_of_offset(end).max(start_line))
+    }
+
     pub fn clear_search(&self) {
         self.common.find.visual.set(false);
         self.find_focus.set(false);
+        self.view.find_result().scope.set(None);
     }
 
     fn search(&self) {
@@ -1794,6 +3036,7 @@ pub fn pointer_down(&self, pointer_event: &PointerEvent) {
             self.common.focus.set(Focus::Workbench);
             self.find_focus.set(false);
         }
+        self.clear_inline_suggestion();
         match pointer_event.button {
             PointerButton::Left => {
                 self.active.set(true);
@@ -2095,7 +3338,22 @@ fn check_condition(&self, condition: Condition) -> bool {
             }
             Condition::ListFocus => self.has_completions(),
             Condition::CompletionFocus => self.has_completions(),
+            Condition::CompletionTabOnly => {
+                self.has_completions()
+                    && !self
+                        .common
+                        .config
+                        .get_untracked()
+                        .editor
+                        .completion_accept_on_enter
+            }
+            Condition::SignatureFocus => self.has_signature(),
             Condition::InSnippet => self.snippet.with_untracked(|s| s.is_some()),
+            Condition::InlineSuggestionVisible => {
+                !self.has_completions()
+                    && self.snippet.with_untracked(|s| s.is_none())
+                    && self.inline_suggestion_at_cursor().is_some()
+            }
             Condition::EditorFocus => {
                 self.view.doc.with_untracked(|doc| !doc.content.is_local())
             }
@@ -2118,6 +3376,9 @@ fn check_condition(&self, condition: Condition) -> bool {
                     self.common.find.visual.get_untracked()
                 }
             }
+            Condition::RebaseTodoFocus => {
+                self.view.doc.with_untracked(|doc| doc.is_rebase_todo())
+            }
             _ => false,
         }
     }
@@ -2128,6 +3389,10 @@ fn run_command(
         count: Option<usize>,
         mods: floem::glazier::Modifiers,
     ) -> crate::command::CommandExecuted {
+        // The movements held back come before any other command
+        if !matches!(command.kind, CommandKind::Move(_)) {
+            self.run_move_batch();
+        }
         if self.common.find.visual.get_untracked() && self.find_focus.get_untracked()
         {
             match &command.kind {
@@ -2157,7 +3422,8 @@ fn run_command(
             }
         }
 
-        match &command.kind {
+        let mode = self.get_mode();
+        let executed = match &command.kind {
             crate::command::CommandKind::Workbench(_) => CommandExecuted::No,
             crate::command::CommandKind::Edit(cmd) => self.run_edit_command(cmd),
             crate::command::CommandKind::Move(cmd) => {
@@ -2176,7 +3442,11 @@ fn run_command(
             crate::command::CommandKind::MultiSelection(cmd) => {
                 self.run_multi_selection_command(cmd)
             }
+        };
+        if self.get_mode() != mode {
+            self.common.overlays.dismiss(OverlayEvent::ModeChange);
         }
+        executed
     }
 
     fn expect_char(&self) -> bool {
@@ -2185,10 +3455,12 @@ fn expect_char(&self) -> bool {
             false
         } else {
             self.inline_find.with_untracked(|f| f.is_some())
+                || self.register_prefix.get_untracked()
         }
     }
 
     fn receive_char(&self, c: &str) {
+        self.run_move_batch();
         if self.common.find.visual.get_untracked() && self.find_focus.get_untracked()
         {
             // find/relace editor receive char
@@ -2222,10 +3494,19 @@ fn receive_char(&self, c: &str) {
                     self.cancel_completion();
                 }
                 self.apply_deltas(&deltas);
+                self.update_inline_suggestion();
             } else if let Some(direction) = self.inline_find.get_untracked() {
                 self.inline_find(direction.clone(), c);
                 self.last_inline_find.set(Some((direction, c.to_string())));
                 self.inline_find.set(None);
+            } else if self.register_prefix.get_untracked() {
+                self.register_prefix.set(false);
+                let name = c.chars().next().filter(|c| Register::is_valid_name(*c));
+                self.common
+                    .register
+                    .update(|register| register.selected = name);
+            } else if c == "\"" {
+                self.register_prefix.set(true);
             }
         }
     }
@@ -2267,3 +3548,52 @@ fn show_completion(
 
     show_completion
 }
+
+/// Find the organize imports action among the code actions of a response.
+fn organize_imports_action(resp: CodeActionResponse) -> Option<CodeAction> {
+    resp.into_iter().find_map(|item| match item {
+        CodeActionOrCommand::CodeAction(action)
+            if action.kind.as_ref().map_or(false, |kind| {
+                kind.as_str()
+                    .starts_with(CodeActionKind::SOURCE_ORGANIZE_IMPORTS.as_str())
+            }) =>
+        {
+            Some(action)
+        }
+        _ => None,
+    })
+}
+
+/// Request the organize imports edit for a document, resolving the code
+/// action if the language server didn't include the edit. Each request waits
+/// at most a second, so this must not be called on the UI thread.
+fn request_organize_imports_edit(
+    proxy: &ProxyRpcHandler,
+    path: PathBuf,
+    range: lsp_types::Range,
+) -> Option<WorkspaceEdit> {
+    let timeout = Duration::from_secs(1);
+
+    let (tx, rx) = crossbeam_channel::bounded(1);
+    proxy.get_organize_imports(path, range, move |result| {
+        let _ = tx.send(result);
+    });
+    let Ok(Ok(ProxyResponse::GetCodeActionsResponse { plugin_id, resp })) =
+        rx.recv_timeout(timeout)
+    else {
+        return None;
+    };
+    let action = organize_imports_action(resp)?;
+    if action.edit.is_some() {
+        return action.edit;
+    }
+
+    let (tx, rx) = crossbeam_channel::bounded(1);
+    proxy.code_action_resolve(action, plugin_id, move |result| {
+        let _ = tx.send(result);
+    });
+    match rx.recv_timeout(timeout) {
+        Ok(Ok(ProxyResponse::CodeActionResolveResponse { item })) => item.edit,
+        _ => None,
+    }
+}
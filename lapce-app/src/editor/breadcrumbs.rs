@@ -0,0 +1,191 @@
+use lsp_types::{
+    DocumentSymbol, DocumentSymbolResponse, Position, Range, SymbolKind,
+};
+
+/// A symbol of a document for the breadcrumbs, with the symbols it contains
+#[derive(Clone, Debug, PartialEq)]
+pub struct SymbolNode {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub range: Range,
+    /// Where the name of the symbol is, which is where picking it jumps to
+    pub selection_range: Range,
+    pub children: Vec<SymbolNode>,
+}
+
+impl SymbolNode {
+    fn from_nested(symbol: DocumentSymbol) -> Self {
+        let mut children: Vec<SymbolNode> = symbol
+            .children
+            .unwrap_or_default()
+            .into_iter()
+            .map(SymbolNode::from_nested)
+            .collect();
+        children.sort_by_key(|child| child.range.start);
+        Self {
+            name: symbol.name,
+            kind: symbol.kind,
+            range: symbol.range,
+            selection_range: symbol.selection_range,
+            children,
+        }
+    }
+
+    fn contains(&self, range: &Range) -> bool {
+        self.range.start <= range.start && range.end <= self.range.end
+    }
+}
+
+/// A section of the symbol path in the breadcrumbs
+#[derive(Clone, Debug, PartialEq)]
+pub enum SymbolCrumb {
+    /// A symbol from the language server, by its indices in the tree
+    Symbol {
+        path: Vec<usize>,
+        name: String,
+        kind: SymbolKind,
+    },
+    /// A line enclosing the cursor by the syntax, when there are no symbols
+    Header { line: usize, label: String },
+}
+
+/// The symbols of the response as a tree. The symbols of a flat response are
+/// nested in the ones whose range contains theirs.
+pub fn symbol_tree(resp: DocumentSymbolResponse) -> Vec<SymbolNode> {
+    match resp {
+        DocumentSymbolResponse::Nested(symbols) => {
+            let mut symbols: Vec<SymbolNode> =
+                symbols.into_iter().map(SymbolNode::from_nested).collect();
+            symbols.sort_by_key(|symbol| symbol.range.start);
+            symbols
+        }
+        DocumentSymbolResponse::Flat(mut symbols) => {
+            // Containers come before what they contain
+            symbols.sort_by(|a, b| {
+                let (a, b) = (a.location.range, b.location.range);
+                a.start.cmp(&b.start).then(b.end.cmp(&a.end))
+            });
+            let mut tree = Vec::new();
+            for symbol in symbols {
+                let range = symbol.location.range;
+                insert_symbol(
+                    &mut tree,
+                    SymbolNode {
+                        name: symbol.name,
+                        kind: symbol.kind,
+                        range,
+                        selection_range: range,
+                        children: Vec::new(),
+                    },
+                );
+            }
+            tree
+        }
+    }
+}
+
+fn insert_symbol(symbols: &mut Vec<SymbolNode>, symbol: SymbolNode) {
+    match symbols.last_mut() {
+        Some(last) if last.contains(&symbol.range) => {
+            insert_symbol(&mut last.children, symbol)
+        }
+        _ => symbols.push(symbol),
+    }
+}
+
+/// The indices of the symbols containing the position, from the outermost,
+/// each among the children of the one before it
+pub fn symbol_path(symbols: &[SymbolNode], position: Position) -> Vec<usize> {
+    let mut path = Vec::new();
+    let mut siblings = symbols;
+    while let Some(index) = siblings.iter().position(|symbol| {
+        symbol.range.start <= position && position <= symbol.range.end
+    }) {
+        path.push(index);
+        siblings = &siblings[index].children;
+    }
+    path
+}
+
+/// The symbols among which the last symbol of the path is, being the children
+/// of the symbol before it
+pub fn symbol_siblings<'a>(
+    symbols: &'a [SymbolNode],
+    path: &[usize],
+) -> &'a [SymbolNode] {
+    let Some((_, parents)) = path.split_last() else {
+        return &[];
+    };
+    let mut siblings = symbols;
+    for index in parents {
+        match siblings.get(*index) {
+            Some(symbol) => siblings = &symbol.children,
+            None => return &[],
+        }
+    }
+    siblings
+}
+
+/// The label of a line of code enclosing the cursor, as the breadcrumbs show
+/// it when there are no symbols: the line without its body
+pub fn header_label(line: &str) -> String {
+    line.trim()
+        .trim_end_matches(|c: char| c == '{' || c == ':' || c.is_whitespace())
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::{Location, SymbolInformation, Url};
+
+    use super::*;
+
+    fn range(start: u32, end: u32) -> Range {
+        Range::new(Position::new(start, 0), Position::new(end, 0))
+    }
+
+    #[allow(deprecated)]
+    fn flat(name: &str, start: u32, end: u32) -> SymbolInformation {
+        SymbolInformation {
+            name: name.to_string(),
+            kind: SymbolKind::FUNCTION,
+            tags: None,
+            deprecated: None,
+            location: Location::new(
+                Url::parse("file:///main.rs").unwrap(),
+                range(start, end),
+            ),
+            container_name: None,
+        }
+    }
+
+    #[test]
+    fn test_flat_symbols_nest() {
+        let tree = symbol_tree(DocumentSymbolResponse::Flat(vec![
+            flat("new", 2, 4),
+            flat("Foo", 1, 10),
+            flat("main", 12, 14),
+            flat("len", 5, 8),
+        ]));
+        let names = |symbols: &[SymbolNode]| {
+            symbols.iter().map(|s| s.name.clone()).collect::<Vec<_>>()
+        };
+        assert_eq!(names(&tree), vec!["Foo", "main"]);
+        assert_eq!(names(&tree[0].children), vec!["new", "len"]);
+
+        let path = symbol_path(&tree, Position::new(6, 4));
+        assert_eq!(path, vec![0, 1]);
+        assert_eq!(names(symbol_siblings(&tree, &path)), vec!["new", "len"]);
+        assert_eq!(
+            names(symbol_siblings(&tree, &path[..1])),
+            vec!["Foo", "main"]
+        );
+        assert!(symbol_path(&tree, Position::new(11, 0)).is_empty());
+    }
+
+    #[test]
+    fn test_header_label() {
+        assert_eq!(header_label("    impl Foo for Bar {"), "impl Foo for Bar");
+        assert_eq!(header_label("def main():"), "def main()");
+    }
+}
@@ -3,6 +3,7 @@
 use floem::{
     event::EventListener,
     ext_event::create_ext_action,
+    peniko::kurbo::Vec2,
     reactive::{
         create_effect, create_memo, create_rw_signal, RwSignal, Scope, SignalGet,
         SignalGetUntracked, SignalSet, SignalUpdate, SignalWith,
@@ -16,8 +17,6 @@
     diff::{expand_diff_lines, rope_diff, DiffExpand, DiffLines},
     rope_text::RopeText,
 };
-use lapce_rpc::proxy::ProxyResponse;
-use lapce_xi_rope::Rope;
 use serde::{Deserialize, Serialize};
 
 use crate::{
@@ -29,7 +28,10 @@
     window_tab::CommonData,
 };
 
-use super::{EditorData, EditorViewKind};
+use super::{
+    location::{EditorLocation, EditorPosition},
+    EditorData, EditorViewKind,
+};
 
 #[derive(Clone)]
 pub struct DiffInfo {
@@ -41,6 +43,11 @@ pub struct DiffInfo {
 pub struct DiffEditorInfo {
     pub left_content: DocContent,
     pub right_content: DocContent,
+    /// The cursor offset of the right editor, the one that can be edited
+    #[serde(default)]
+    pub offset: usize,
+    #[serde(default)]
+    pub scroll_offset: (f64, f64),
 }
 
 impl DiffEditorInfo {
@@ -61,7 +68,9 @@ pub fn to_data(
                     let (doc, _) = data.get_doc(path.clone());
                     doc
                 }
-                DocContent::Local => create_rw_signal(
+                DocContent::Notebook(path) => data.notebook_doc(path.clone()),
+                DocContent::Archive(path) => data.archive_doc(path.clone()),
+                DocContent::Local | DocContent::SearchResults => create_rw_signal(
                     cx,
                     Document::new_local(
                         cx,
@@ -70,35 +79,7 @@ pub fn to_data(
                         common.config,
                     ),
                 ),
-                DocContent::History(history) => {
-                    let doc = Document::new_hisotry(
-                        cx,
-                        content.clone(),
-                        common.find.clone(),
-                        common.proxy.clone(),
-                        common.config,
-                    );
-                    let doc = create_rw_signal(doc.scope, doc);
-
-                    let send = create_ext_action(cx, move |result| {
-                        if let Ok(ProxyResponse::BufferHeadResponse {
-                            content,
-                            ..
-                        }) = result
-                        {
-                            doc.update(|doc| {
-                                doc.init_content(Rope::from(content));
-                            });
-                        }
-                    });
-                    common.proxy.get_buffer_head(
-                        history.path.clone(),
-                        move |result| {
-                            send(result);
-                        },
-                    );
-                    doc
-                }
+                DocContent::History(history) => data.history_doc(history.clone()),
             }
         };
 
@@ -118,6 +99,24 @@ pub fn to_data(
             diff_editors.insert(diff_editor_id, diff_editor_data.clone());
         });
 
+        if let DocContent::File(path) = &self.right_content {
+            let new_doc = !right_doc.with_untracked(|doc| doc.loaded());
+            diff_editor_data.right.get_untracked().go_to_location(
+                EditorLocation {
+                    path: path.clone(),
+                    position: Some(EditorPosition::Offset(self.offset)),
+                    scroll_offset: Some(Vec2::new(
+                        self.scroll_offset.0,
+                        self.scroll_offset.1,
+                    )),
+                    ignore_unconfirmed: false,
+                    same_editor_tab: false,
+                },
+                new_doc,
+                None,
+            );
+        }
+
         diff_editor_data
     }
 }
@@ -163,6 +162,12 @@ pub fn new(
     }
 
     pub fn diff_editor_info(&self) -> DiffEditorInfo {
+        let (offset, scroll_offset) = self.right.with_untracked(|editor| {
+            (
+                editor.cursor.with_untracked(|cursor| cursor.offset()),
+                editor.viewport.with_untracked(|viewport| viewport.origin()),
+            )
+        });
         DiffEditorInfo {
             left_content: self.left.get_untracked().view.doc.get_untracked().content,
             right_content: self
@@ -172,6 +177,8 @@ pub fn diff_editor_info(&self) -> DiffEditorInfo {
                 .doc
                 .get_untracked()
                 .content,
+            offset,
+            scroll_offset: (scroll_offset.x, scroll_offset.y),
         }
     }
 
@@ -217,7 +224,12 @@ fn listen_diff_changes(&self) {
             right_doc.with(|doc| (doc.content.clone(), doc.rev()))
         });
 
+        let config = right.with_untracked(|editor| editor.common.config);
+        let options =
+            create_memo(cx, move |_| config.with(|c| c.editor.diff_options()));
+
         create_effect(cx, move |_| {
+            let options = options.get();
             let (_, left_rev) = left_doc_rev.get();
             let (left_editor_view, left_doc) =
                 left.with_untracked(|editor| (editor.view.kind, editor.view.doc));
@@ -268,6 +280,7 @@ fn listen_diff_changes(&self) {
                     right_rev,
                     right_atomic_rev.clone(),
                     Some(3),
+                    options,
                 );
                 send(changes);
             });
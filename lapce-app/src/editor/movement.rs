@@ -2,7 +2,9 @@
 
 use std::collections::HashSet;
 
-use floem::reactive::{SignalGetUntracked, SignalUpdate};
+use floem::reactive::{
+    SignalGetUntracked, SignalSet, SignalUpdate, SignalWithUntracked,
+};
 use lapce_core::{
     buffer::rope_text::RopeText,
     command::MultiSelectionCommand,
@@ -15,7 +17,7 @@
     soft_tab::{snap_to_soft_tab, SnapDirection},
 };
 
-use crate::doc::Document;
+use crate::{doc::Document, find::Find};
 
 use super::view_data::EditorViewData;
 
@@ -579,31 +581,21 @@ pub fn do_multi_selection(
         SelectAllCurrent => {
             if let CursorMode::Insert(mut selection) = cursor.mode.clone() {
                 if !selection.is_empty() {
-                    let find = view.find();
-                    let config = view.config.get_untracked();
-
                     let first = selection.first().unwrap();
                     let (start, end) = if first.is_caret() {
                         rope_text.select_word(first.start)
                     } else {
                         (first.min(), first.max())
                     };
-                    let search_str = rope_text.slice_to_cow(start..end);
-                    let case_sensitive = find.case_sensitive(false);
-                    let multicursor_case_sensitive =
-                        config.editor.multicursor_case_sensitive;
-                    let case_sensitive =
-                        multicursor_case_sensitive || case_sensitive;
-                    // let search_whole_word = config.editor.multicursor_whole_words;
-                    find.set_case_sensitive(case_sensitive);
-                    find.set_find(&search_str);
-                    let mut offset = 0;
-                    while let Some((start, end)) =
-                        find.next(rope_text.text(), offset, false, false)
-                    {
-                        offset = end;
-                        selection.add_region(SelRegion::new(start, end, None));
-                    }
+                    with_occurrence_find(view, start, end, |find| {
+                        let mut offset = 0;
+                        while let Some((start, end)) =
+                            find.next(rope_text.text(), offset, false, false)
+                        {
+                            offset = end;
+                            selection.add_region(SelRegion::new(start, end, None));
+                        }
+                    });
                 }
                 cursor.set_insert(selection);
             }
@@ -621,39 +613,30 @@ pub fn do_multi_selection(
                         }
                     }
                     if !had_caret {
-                        let find = view.find();
-                        let config = view.config.get_untracked();
-
-                        let r = selection.last_inserted().unwrap();
-                        let search_str = rope_text.slice_to_cow(r.min()..r.max());
-                        let case_sensitive = find.case_sensitive(false);
-                        let case_sensitive =
-                            config.editor.multicursor_case_sensitive
-                                || case_sensitive;
-                        // let search_whole_word =
-                        // config.editor.multicursor_whole_words;
-                        find.set_case_sensitive(case_sensitive);
-                        find.set_find(&search_str);
-                        let mut offset = r.max();
-                        let mut seen = HashSet::new();
-                        while let Some((start, end)) =
-                            find.next(rope_text.text(), offset, false, true)
-                        {
-                            if !selection
-                                .regions()
-                                .iter()
-                                .any(|r| r.min() == start && r.max() == end)
+                        let r = *selection.last_inserted().unwrap();
+                        with_occurrence_find(view, r.min(), r.max(), |find| {
+                            let mut offset = r.max();
+                            let mut seen = HashSet::new();
+                            while let Some((start, end)) =
+                                find.next(rope_text.text(), offset, false, true)
                             {
-                                selection
-                                    .add_region(SelRegion::new(start, end, None));
-                                break;
+                                if !selection
+                                    .regions()
+                                    .iter()
+                                    .any(|r| r.min() == start && r.max() == end)
+                                {
+                                    selection.add_region(SelRegion::new(
+                                        start, end, None,
+                                    ));
+                                    break;
+                                }
+                                if seen.contains(&end) {
+                                    break;
+                                }
+                                offset = end;
+                                seen.insert(offset);
                             }
-                            if seen.contains(&end) {
-                                break;
-                            }
-                            offset = end;
-                            seen.insert(offset);
-                        }
+                        });
                     }
                 }
                 cursor.set_insert(selection);
@@ -662,38 +645,36 @@ pub fn do_multi_selection(
         SelectSkipCurrent => {
             if let CursorMode::Insert(mut selection) = cursor.mode.clone() {
                 if !selection.is_empty() {
-                    let r = selection.last_inserted().unwrap();
+                    let r = *selection.last_inserted().unwrap();
                     if r.is_caret() {
                         let (start, end) = rope_text.select_word(r.start);
                         selection.replace_last_inserted_region(SelRegion::new(
                             start, end, None,
                         ));
                     } else {
-                        let find = view.find();
-
-                        let search_str = rope_text.slice_to_cow(r.min()..r.max());
-                        find.set_find(&search_str);
-                        let mut offset = r.max();
-                        let mut seen = HashSet::new();
-                        while let Some((start, end)) =
-                            find.next(rope_text.text(), offset, false, true)
-                        {
-                            if !selection
-                                .regions()
-                                .iter()
-                                .any(|r| r.min() == start && r.max() == end)
+                        with_occurrence_find(view, r.min(), r.max(), |find| {
+                            let mut offset = r.max();
+                            let mut seen = HashSet::new();
+                            while let Some((start, end)) =
+                                find.next(rope_text.text(), offset, false, true)
                             {
-                                selection.replace_last_inserted_region(
-                                    SelRegion::new(start, end, None),
-                                );
-                                break;
+                                if !selection
+                                    .regions()
+                                    .iter()
+                                    .any(|r| r.min() == start && r.max() == end)
+                                {
+                                    selection.replace_last_inserted_region(
+                                        SelRegion::new(start, end, None),
+                                    );
+                                    break;
+                                }
+                                if seen.contains(&end) {
+                                    break;
+                                }
+                                offset = end;
+                                seen.insert(offset);
                             }
-                            if seen.contains(&end) {
-                                break;
-                            }
-                            offset = end;
-                            seen.insert(offset);
-                        }
+                        });
                     }
                 }
                 cursor.set_insert(selection);
@@ -706,6 +687,36 @@ pub fn do_multi_selection(
     }
 }
 
+/// Run `f` with a find of its own searching the other occurrences of the text
+/// in `start..end`, following the multicursor settings, which leaves the
+/// state of the find bar alone.  
+/// Whole word matching is only used when the text is a whole word itself.
+fn with_occurrence_find<T>(
+    view: &EditorViewData,
+    start: usize,
+    end: usize,
+    f: impl FnOnce(&Find) -> T,
+) -> T {
+    let rope_text = view.rope_text();
+    let config = view.config.get_untracked();
+
+    let search_str = rope_text.slice_to_cow(start..end);
+    let case_sensitive = config.editor.multicursor_case_sensitive
+        || view.find().case_sensitive(false);
+    let whole_words = config.editor.multicursor_whole_words
+        && rope_text.select_word(start) == (start, end);
+
+    let scope = view.doc.with_untracked(|doc| doc.scope);
+    let (find, cx) = scope.run_child_scope(Find::new);
+    find.set_case_sensitive(case_sensitive);
+    find.whole_words.set(whole_words);
+    // The selected text is matched literally, which is the default
+    find.set_find(&search_str);
+    let result = f(&find);
+    cx.dispose();
+    result
+}
+
 pub fn do_motion_mode(
     doc: &mut Document,
     cursor: &mut Cursor,
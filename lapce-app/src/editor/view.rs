@@ -1,4 +1,4 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, path::PathBuf, sync::Arc, time::Duration};
 
 use floem::{
     context::PaintCx,
@@ -7,13 +7,14 @@
     ext_event::create_ext_action,
     glazier::{Modifiers, PointerType},
     id::Id,
+    menu::{Menu, MenuItem},
     peniko::{
-        kurbo::{BezPath, Line, Point, Rect, Size},
+        kurbo::{BezPath, Line, Point, Rect, Size, Vec2},
         Color,
     },
     reactive::{
         create_effect, create_memo, create_rw_signal, on_cleanup, ReadSignal,
-        RwSignal, SignalGet, SignalGetUntracked, SignalSet, SignalUpdate,
+        RwSignal, Scope, SignalGet, SignalGetUntracked, SignalSet, SignalUpdate,
         SignalWith, SignalWithUntracked,
     },
     style::{ComputedStyle, CursorStyle, Style},
@@ -23,24 +24,42 @@
     Renderer, ViewContext,
 };
 use lapce_core::{
-    buffer::{diff::DiffLines, rope_text::RopeText},
+    buffer::{
+        diff::{DiffHunk, DiffLines},
+        rope_text::RopeText,
+    },
     cursor::{ColPosition, CursorMode},
     mode::{Mode, VisualMode},
     selection::Selection,
 };
+use lapce_rpc::{
+    file::FileNodeItem,
+    protocol::Capability,
+    proxy::{ProxyResponse, ProxyRpcHandler},
+};
 use lapce_xi_rope::find::CaseMatching;
+use lsp_types::{DiagnosticSeverity, DocumentSymbolResponse};
 
 use super::{
+    breadcrumbs::{
+        header_label, symbol_path, symbol_siblings, symbol_tree, SymbolCrumb,
+        SymbolNode,
+    },
+    location::{EditorLocation, EditorPosition},
     view_data::{EditorViewData, LineExtraStyle},
     EditorData,
 };
 use crate::{
     app::clickable_icon,
-    command::InternalCommand,
+    command::{InternalCommand, LapceWorkbenchCommand},
+    commit_message,
     config::{color::LapceColor, icon::LapceIcons, LapceConfig},
     doc::{DocContent, Document},
+    listener::Listener,
     main_split::MainSplitData,
+    overlay::OverlayEvent,
     text_input::text_input,
+    timer::exec_after,
     workspace::LapceWorkspace,
 };
 
@@ -109,6 +128,39 @@ pub fn editor_view(
         rev
     });
 
+    create_effect(cx.scope, move |_| {
+        let doc = editor.with(|editor| editor.view.doc);
+        let diagnostics = doc.with(|doc| doc.diagnostics.diagnostics);
+        diagnostics.track();
+        id.request_paint();
+    });
+
+    create_effect(cx.scope, move |_| {
+        let doc = editor.with(|editor| editor.view.doc);
+        let scope = doc.with(|doc| doc.find_result.scope);
+        scope.track();
+        id.request_paint();
+    });
+
+    create_effect(cx.scope, move |_| {
+        let doc = editor.with(|editor| editor.view.doc);
+        let occurrences = doc.with(|doc| doc.find_result.occurrences);
+        occurrences.track();
+        id.request_paint();
+    });
+
+    create_effect(cx.scope, move |_| {
+        let inline_suggestion = editor.with(|editor| editor.view.inline_suggestion);
+        inline_suggestion.track();
+        id.request_layout();
+    });
+
+    create_effect(cx.scope, move |_| {
+        let focus_mode = editor.with(|editor| editor.common.focus_mode);
+        focus_mode.track();
+        id.request_paint();
+    });
+
     create_effect(cx.scope, move |last_rev| {
         let (doc, sticky_header_height_signal, config) =
             editor.with_untracked(|editor| {
@@ -293,12 +345,13 @@ fn paint_cursor(
                 *end,
                 mode,
                 cursor.horiz.as_ref(),
-                7.5,
+                editor_char_width(&config),
                 is_active,
                 screen_lines,
             ),
             CursorMode::Insert(selection) => {
-                insert_cursor(&view, selection, 7.5, is_active, screen_lines)
+                let char_width = editor_char_width(&config);
+                insert_cursor(&view, selection, char_width, is_active, screen_lines)
             }
         });
 
@@ -372,6 +425,80 @@ fn paint_wave_line(
         cx.stroke(&path, color, 1.0);
     }
 
+    /// Paint wavy underlines under the diagnostics in the visible lines.  
+    /// This is done at paint time rather than cached with the text layouts,
+    /// as the diagnostics' offsets are moved along with each edit.
+    fn paint_diagnostics(&self, cx: &mut PaintCx, screen_lines: &ScreenLines) {
+        if screen_lines.lines.is_empty() {
+            return;
+        }
+
+        let (view, config) = self
+            .editor
+            .with_untracked(|editor| (editor.view.clone(), editor.common.config));
+        let config = config.get_untracked();
+        let line_height = config.editor.line_height() as f64;
+        let font_size = config.editor.font_size();
+        let rope_text = view.rope_text();
+
+        let min_line = *screen_lines.lines.first().unwrap();
+        let max_line = *screen_lines.lines.last().unwrap();
+
+        let diagnostics = view.doc.with_untracked(|doc| doc.diagnostics.diagnostics);
+        for diag in diagnostics.get_untracked() {
+            let (start, end) = diag.range;
+            let (start_line, start_col) = rope_text.offset_to_line_col(start);
+            let (end_line, end_col) = rope_text.offset_to_line_col(end);
+            if end_line < min_line || start_line > max_line {
+                continue;
+            }
+
+            let color = config.get_color(match diag.diagnostic.severity {
+                Some(DiagnosticSeverity::ERROR) => LapceColor::LAPCE_ERROR,
+                Some(DiagnosticSeverity::INFORMATION) => LapceColor::LAPCE_INFO,
+                Some(DiagnosticSeverity::HINT) => LapceColor::LAPCE_HINT,
+                _ => LapceColor::LAPCE_WARN,
+            });
+
+            for line in &screen_lines.lines {
+                let line = *line;
+                if line < start_line {
+                    continue;
+                }
+                if line > end_line {
+                    break;
+                }
+                let Some(info) = screen_lines.info.get(&line) else {
+                    continue;
+                };
+
+                let left_col = if line == start_line {
+                    start_col
+                } else {
+                    let offset = rope_text.first_non_blank_character_on_line(line);
+                    rope_text.offset_to_line_col(offset).1
+                };
+                let right_col = if line == end_line {
+                    end_col
+                } else {
+                    rope_text.line_end_col(line, true)
+                };
+
+                // Shift it by the inlay hints
+                let phantom_text = view.line_phantom_text(line);
+                let left_col = phantom_text.col_after(left_col, true);
+                let right_col = phantom_text.col_after(right_col, false);
+
+                let text_layout = view.get_text_layout(line, font_size);
+                let height = text_layout.text.size().height;
+                let x0 = text_layout.text.hit_position(left_col).point.x;
+                let x1 = text_layout.text.hit_position(right_col).point.x;
+                let y = info.y as f64 + (line_height - height) / 2.0 + height;
+                self.paint_wave_line(cx, x1 - x0, Point::new(x0, y), *color);
+            }
+        }
+    }
+
     fn paint_extra_style(
         &self,
         cx: &mut PaintCx,
@@ -438,6 +565,30 @@ fn paint_text(
         indent_text.set_text(&format!("{indent_unit}a"), attrs_list);
         let indent_text_width = indent_text.hit_position(indent_unit.len()).point.x;
 
+        // Rulers at the widths of the summary and of the body of a commit
+        // message, or at the line width configured for the language
+        let rulers = view.doc.with_untracked(|doc| {
+            if doc.is_commit_message() {
+                vec![commit_message::SUMMARY_WIDTH, commit_message::BODY_WIDTH]
+            } else {
+                doc.syntax().language.line_width().into_iter().collect()
+            }
+        });
+        if !rulers.is_empty() {
+            let char_width = editor_char_width(&config);
+            for column in rulers {
+                let x = (column as f64 * char_width).round() + 0.5;
+                cx.stroke(
+                    &Line::new(
+                        Point::new(x, viewport.y0),
+                        Point::new(x, viewport.y1),
+                    ),
+                    config.get_color(LapceColor::EDITOR_INDENT_GUIDE),
+                    1.0,
+                );
+            }
+        }
+
         let last_line = view.last_line();
 
         for line in &screen_lines.lines {
@@ -520,6 +671,76 @@ fn paint_text(
         }
     }
 
+    /// Outline the other occurrences of the selected text in the visible lines
+    fn paint_selection_occurrences(
+        &self,
+        cx: &mut PaintCx,
+        screen_lines: &ScreenLines,
+    ) {
+        if screen_lines.lines.is_empty() {
+            return;
+        }
+
+        let (view, cursor, config) = self.editor.with_untracked(|editor| {
+            (editor.view.clone(), editor.cursor, editor.common.config)
+        });
+        let config = config.get_untracked();
+        if !config.editor.highlight_selection_occurrences {
+            return;
+        }
+
+        let selected = cursor.with_untracked(|cursor| match &cursor.mode {
+            CursorMode::Insert(selection) if selection.len() == 1 => selection
+                .first()
+                .filter(|region| !region.is_caret())
+                .map(|region| (region.min(), region.max())),
+            _ => None,
+        });
+        let Some((selected_start, selected_end)) = selected else {
+            return;
+        };
+        if view.line_of_offset(selected_start) != view.line_of_offset(selected_end) {
+            return;
+        }
+        let text = view.text();
+        let selected = text.slice_to_cow(selected_start..selected_end);
+        if selected.trim().is_empty() {
+            return;
+        }
+
+        let min_line = *screen_lines.lines.first().unwrap();
+        let max_line = *screen_lines.lines.last().unwrap();
+        let start = view.offset_of_line(min_line);
+        let end = view.offset_of_line(max_line + 1);
+        let visible = text.slice_to_cow(start..end);
+
+        let line_height = config.editor.line_height() as f64;
+        let color = config.get_color(LapceColor::EDITOR_SELECTION);
+        for (i, _) in visible.match_indices(selected.as_ref()) {
+            let offset = start + i;
+            if offset == selected_start {
+                continue;
+            }
+            let (line, left_col) = view.offset_to_line_col(offset);
+            let Some(info) = screen_lines.info.get(&line) else {
+                continue;
+            };
+            let right_col = left_col + selected.len();
+
+            // Shift it by the inlay hints
+            let phantom_text = view.line_phantom_text(line);
+            let left_col = phantom_text.col_after(left_col, false);
+            let right_col = phantom_text.col_after(right_col, false);
+
+            let x0 = view.line_point_of_line_col(line, left_col, 12).x;
+            let x1 = view.line_point_of_line_col(line, right_col, 12).x;
+            let rect = Size::new(x1 - x0, line_height)
+                .to_rect()
+                .with_origin(Point::new(x0, info.y as f64));
+            cx.stroke(&rect, color, 1.0);
+        }
+    }
+
     fn paint_find(&self, cx: &mut PaintCx, screen_lines: &ScreenLines) {
         let visual = self.editor.with_untracked(|e| e.common.find.visual);
         if !visual.get_untracked() {
@@ -600,6 +821,105 @@ fn paint_find(&self, cx: &mut PaintCx, screen_lines: &ScreenLines) {
         }
     }
 
+    /// Dim the lines outside the scope of the cursor when in focus mode
+    /// Paint the lines of a multi-line inline suggestion after its first one
+    /// below the line it's on, over the lines of the text there
+    fn paint_inline_suggestion(
+        &self,
+        cx: &mut PaintCx,
+        viewport: Rect,
+        screen_lines: &ScreenLines,
+    ) {
+        let (view, config) = self
+            .editor
+            .with_untracked(|editor| (editor.view.clone(), editor.common.config));
+        let Some(suggestion) = view.current_inline_suggestion() else {
+            return;
+        };
+        let config = config.get_untracked();
+        if !config.editor.enable_inline_suggestions {
+            return;
+        }
+        let rest = suggestion.text.lines().skip(1).collect::<Vec<_>>();
+        if rest.is_empty() {
+            return;
+        }
+        let line = view.line_of_offset(suggestion.offset);
+        let Some(info) = screen_lines.info.get(&line) else {
+            return;
+        };
+        let line_height = config.editor.line_height() as f64;
+        let y = info.y as f64 + line_height;
+
+        let rect = Size::new(viewport.width(), line_height * rest.len() as f64)
+            .to_rect()
+            .with_origin(Point::new(viewport.x0, y));
+        cx.fill(&rect, config.get_color(LapceColor::EDITOR_BACKGROUND));
+
+        let family: Vec<FamilyOwned> =
+            FamilyOwned::parse_list(&config.editor.font_family).collect();
+        let attrs = Attrs::new()
+            .color(*config.get_color(LapceColor::COMPLETION_LENS_FOREGROUND))
+            .family(&family)
+            .font_size(config.editor.font_size() as f32);
+        let attrs_list = AttrsList::new(attrs);
+        let tab = " ".repeat(config.editor.tab_width);
+        for (i, text) in rest.into_iter().enumerate() {
+            let mut text_layout = TextLayout::new();
+            text_layout.set_text(&text.replace('\t', &tab), attrs_list.clone());
+            let height = text_layout.size().height;
+            cx.draw_text(
+                &text_layout,
+                Point::new(
+                    0.0,
+                    y + i as f64 * line_height + (line_height - height) / 2.0,
+                ),
+            );
+        }
+    }
+
+    fn paint_focus_mode(
+        &self,
+        cx: &mut PaintCx,
+        viewport: Rect,
+        screen_lines: &ScreenLines,
+    ) {
+        let (view, cursor, common) = self.editor.with_untracked(|editor| {
+            (editor.view.clone(), editor.cursor, editor.common.clone())
+        });
+        if common.focus_mode.get_untracked().is_none() {
+            return;
+        }
+        let config = common.config.get_untracked();
+        let line_height = config.editor.line_height() as f64;
+
+        let offset = cursor.with_untracked(|cursor| cursor.offset());
+        let Some((start, end)) = view
+            .doc
+            .with_untracked(|doc| doc.syntax().focus_scope(offset))
+        else {
+            return;
+        };
+        let start_line = view.line_of_offset(start);
+        let end_line = view.line_of_offset(end);
+
+        let color = config
+            .get_color(LapceColor::EDITOR_BACKGROUND)
+            .with_alpha_factor(0.6);
+        for line in &screen_lines.lines {
+            if (start_line..=end_line).contains(line) {
+                continue;
+            }
+            let Some(info) = screen_lines.info.get(line) else {
+                continue;
+            };
+            let rect = Size::new(viewport.width(), line_height)
+                .to_rect()
+                .with_origin(Point::new(viewport.x0, info.y as f64));
+            cx.fill(&rect, color);
+        }
+    }
+
     fn paint_sticky_headers(&self, cx: &mut PaintCx, viewport: Rect) {
         let (view, editor_view, config) = self.editor.with_untracked(|editor| {
             (editor.view.clone(), editor.view.kind, editor.common.config)
@@ -721,11 +1041,8 @@ fn paint_scroll_bar(
         let total_height = viewport.height();
         let total_width = viewport.width();
         let line_height = config.editor.line_height();
-        let content_height = if config.editor.scroll_beyond_last_line {
-            (total_len * line_height) as f64 + total_height - line_height as f64
-        } else {
-            (total_len * line_height) as f64
-        };
+        let content_height = (total_len * line_height) as f64
+            + config.editor.scroll_beyond_last_line_height(total_height);
 
         let colors = changes_colors(changes, 0, total_len, &config);
         for (y, height, _, color) in colors {
@@ -741,6 +1058,32 @@ fn paint_scroll_bar(
             );
             cx.fill(&rect, color);
         }
+
+        let visual = self.editor.with_untracked(|e| e.common.find.visual);
+        if !visual.get_untracked() {
+            return;
+        }
+        let occurrences =
+            doc.with_untracked(|doc| doc.find_result.occurrences.get_untracked());
+        let color = config.get_color(LapceColor::EDITOR_FOREGROUND);
+        let mut last_line = None;
+        for region in occurrences.regions() {
+            let line =
+                doc.with_untracked(|doc| doc.buffer().line_of_offset(region.min()));
+            if last_line == Some(line) {
+                continue;
+            }
+            last_line = Some(line);
+            let y = (line * line_height) as f64 / content_height * total_height;
+            let rect =
+                Rect::ZERO
+                    .with_size(Size::new(3.0, 2.0))
+                    .with_origin(Point::new(
+                        viewport.x0 + total_width - BAR_WIDTH + 5.0,
+                        y + viewport.y0,
+                    ));
+            cx.fill(&rect, color);
+        }
     }
 }
 
@@ -849,8 +1192,12 @@ fn paint(&mut self, cx: &mut PaintCx) {
 
         self.paint_cursor(cx, is_local, &screen_lines);
         self.paint_diff_sections(cx, viewport, &screen_lines, &config);
+        self.paint_selection_occurrences(cx, &screen_lines);
         self.paint_find(cx, &screen_lines);
         self.paint_text(cx, viewport, &screen_lines);
+        self.paint_inline_suggestion(cx, viewport, &screen_lines);
+        self.paint_diagnostics(cx, &screen_lines);
+        self.paint_focus_mode(cx, viewport, &screen_lines);
         self.paint_sticky_headers(cx, viewport);
         self.paint_scroll_bar(cx, viewport, is_local, config);
     }
@@ -963,16 +1310,14 @@ pub fn cursor_caret(
     offset: usize,
     block: bool,
 ) -> CursorRender {
-    let (line, col) = view.offset_to_line_col(offset);
-    let phantom_text = view.line_phantom_text(line);
-    let col = phantom_text.col_after(col, block);
-    let x0 = view.line_point_of_line_col(line, col, 12).x;
+    let line = view.line_of_offset(offset);
+    let (x0, x1) = view.grapheme_x_extent(offset, 12, block);
     if block {
-        let right_offset = view.move_right(offset, Mode::Insert, 1);
-        let (_, right_col) = view.offset_to_line_col(right_offset);
-        let x1 = view.line_point_of_line_col(line, right_col, 12).x;
-
-        let width = if x1 > x0 { x1 - x0 } else { 7.0 };
+        let width = if x1 > x0 {
+            x1 - x0
+        } else {
+            editor_char_width(&view.config.get_untracked())
+        };
         CursorRender::Caret { x: x0, width, line }
     } else {
         CursorRender::Caret {
@@ -983,6 +1328,18 @@ pub fn cursor_caret(
     }
 }
 
+/// The width of a character of the editor font
+fn editor_char_width(config: &LapceConfig) -> f64 {
+    let family: Vec<FamilyOwned> =
+        FamilyOwned::parse_list(&config.editor.font_family).collect();
+    let attrs = Attrs::new()
+        .family(&family)
+        .font_size(config.editor.font_size() as f32);
+    let mut char_text = TextLayout::new();
+    char_text.set_text("a", AttrsList::new(attrs));
+    char_text.size().width
+}
+
 #[allow(clippy::too_many_arguments)]
 fn visual_cursor(
     view: &EditorViewData,
@@ -1059,11 +1416,9 @@ fn visual_cursor(
             }
         };
 
-        let phantom_text = view.line_phantom_text(line);
-        let left_col = phantom_text.col_after(left_col, false);
-        let right_col = phantom_text.col_after(right_col, false);
-        let x0 = view.line_point_of_line_col(line, left_col, 12).x;
-        let mut x1 = view.line_point_of_line_col(line, right_col, 12).x;
+        let line_start = view.offset_of_line(line);
+        let x0 = view.grapheme_x_extent(line_start + left_col, 12, false).0;
+        let mut x1 = view.grapheme_x_extent(line_start + right_col, 12, false).0;
         if line_end {
             x1 += char_width;
         }
@@ -1130,13 +1485,10 @@ fn insert_cursor(
                 _ => (view.line_end_col(line, true), true),
             };
 
-            // Shift it by the inlay hints
-            let phantom_text = view.line_phantom_text(line);
-            let left_col = phantom_text.col_after(left_col, false);
-            let right_col = phantom_text.col_after(right_col, false);
-
-            let x0 = view.line_point_of_line_col(line, left_col, 12).x;
-            let mut x1 = view.line_point_of_line_col(line, right_col, 12).x;
+            // The extents are shifted by the inlay hints
+            let line_start = view.offset_of_line(line);
+            let x0 = view.grapheme_x_extent(line_start + left_col, 12, false).0;
+            let mut x1 = view.grapheme_x_extent(line_start + right_col, 12, false).0;
             if line_end {
                 x1 += char_width;
             }
@@ -1243,6 +1595,7 @@ pub fn editor_container_view(
                             replace_focus,
                             is_active,
                         ),
+                        hunk_popup(editor, gutter_rect, config),
                     )
                 })
                 .on_resize(move |_, rect| {
@@ -1251,11 +1604,244 @@ pub fn editor_container_view(
                 .style(|| Style::BASE.absolute().size_pct(100.0, 100.0))
             })
             .style(|| Style::BASE.size_pct(100.0, 100.0)),
+            commit_message_diff(editor, config),
         )
     })
     .style(|| Style::BASE.flex_col().size_pct(100.0, 100.0))
 }
 
+/// The diff of a change to the file, the lines it replaced and the lines it
+/// has now, shown under it after clicking its marker in the gutter, with what
+/// can be done with the change
+fn hunk_popup(
+    editor: RwSignal<EditorData>,
+    gutter_rect: RwSignal<Rect>,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    let (hunk, doc, viewport) = editor.with_untracked(|editor| {
+        (editor.hunk_popup, editor.view.doc, editor.viewport)
+    });
+    // Each line of the diff, and whether it was added
+    let diff_lines = move || {
+        let Some(hunk) = hunk.get() else {
+            return Vec::new();
+        };
+        doc.with_untracked(|doc| {
+            let original = doc.head_hunk_original(&hunk).unwrap_or_default();
+            let buffer = doc.buffer();
+            let current = buffer.slice_to_cow(
+                buffer.offset_of_line(hunk.right.start)
+                    ..buffer.offset_of_line(hunk.right.end),
+            );
+            original
+                .lines()
+                .map(|line| (false, format!("-{line}")))
+                .chain(current.lines().map(|line| (true, format!("+{line}"))))
+                .collect::<Vec<_>>()
+        })
+    };
+    // A proxy of an older version can't stage a hunk
+    let can_stage = editor.with_untracked(|editor| {
+        editor.common.proxy.supports(Capability::StageHunks)
+    });
+    let button = move |text: &'static str,
+                       action: fn(&EditorData, &DiffHunk),
+                       shown: bool| {
+        label(move || text.to_string())
+            .on_click(move |_| {
+                if let Some(hunk) = hunk.get_untracked() {
+                    editor.with_untracked(|editor| action(editor, &hunk));
+                }
+                true
+            })
+            .style(move || {
+                Style::BASE
+                    .padding_horiz_px(10.0)
+                    .border(1.0)
+                    .border_radius(6.0)
+                    .margin_right_px(6.0)
+                    .border_color(*config.get().get_color(LapceColor::LAPCE_BORDER))
+                    .apply_if(!shown, |s| s.hide())
+            })
+            .hover_style(move || {
+                Style::BASE.cursor(CursorStyle::Pointer).background(
+                    *config.get().get_color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                )
+            })
+    };
+
+    stack(move || {
+        (
+            scroll(move || {
+                list(
+                    move || diff_lines().into_iter().enumerate(),
+                    |(i, line)| (*i, line.clone()),
+                    move |(_, (added, line))| {
+                        label(move || line.clone()).style(move || {
+                            let color = if added {
+                                LapceColor::SOURCE_CONTROL_ADDED
+                            } else {
+                                LapceColor::SOURCE_CONTROL_REMOVED
+                            };
+                            Style::BASE.color(*config.get().get_color(color))
+                        })
+                    },
+                )
+                .style(|| Style::BASE.flex_col().padding_horiz_px(10.0))
+            })
+            .style(move || {
+                let config = config.get();
+                Style::BASE
+                    .width_pct(100.0)
+                    .max_height_px(200.0)
+                    .font_family(config.editor.font_family.clone())
+                    .font_size(config.editor.font_size() as f32)
+                    .apply_if(diff_lines().is_empty(), |s| s.hide())
+            }),
+            stack(move || {
+                (
+                    button("Revert Hunk", EditorData::revert_hunk, true),
+                    button("Stage Hunk", EditorData::stage_hunk, can_stage),
+                    button("Copy Original", EditorData::copy_hunk_original, true),
+                )
+            })
+            .style(|| Style::BASE.padding_px(6.0).line_height(1.6)),
+        )
+    })
+    .style(move || {
+        let config = config.get();
+        let line_height = config.editor.line_height() as f64;
+        let line = hunk.get().map(|hunk| hunk.right.end).unwrap_or(0);
+        let top = line as f64 * line_height - viewport.get().y0;
+        Style::BASE
+            .absolute()
+            .flex_col()
+            .min_width_px(300.0)
+            .margin_left_px(gutter_rect.get().width() as f32)
+            .margin_top_px(top as f32)
+            .border(1.0)
+            .border_radius(6.0)
+            .border_color(*config.get_color(LapceColor::LAPCE_BORDER))
+            .background(*config.get_color(LapceColor::PANEL_BACKGROUND))
+            .apply_if(hunk.with(|hunk| hunk.is_none()), |s| s.hide())
+    })
+}
+
+/// The staged changes shown below the editor of a commit message, as context
+/// for writing it
+fn commit_message_diff(
+    editor: RwSignal<EditorData>,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    let (doc, proxy) = editor
+        .with_untracked(|editor| (editor.view.doc, editor.common.proxy.clone()));
+    let (is_commit_message, path) = doc.with_untracked(|doc| {
+        (doc.is_commit_message(), doc.content.path().cloned())
+    });
+
+    let cx = ViewContext::get_current();
+    let expanded = create_rw_signal(cx.scope, true);
+    let diff = create_rw_signal(cx.scope, String::new());
+    if let (true, Some(path)) = (is_commit_message, path) {
+        let send = create_ext_action(cx.scope, move |result| {
+            if let Ok(ProxyResponse::GitStagedDiffResponse { diff: staged }) = result
+            {
+                diff.set(staged);
+            }
+        });
+        proxy.git_staged_diff(path, move |result| {
+            send(result);
+        });
+    }
+
+    stack(move || {
+        (
+            stack(|| {
+                (
+                    svg(move || {
+                        config.get().ui_svg(if expanded.get() {
+                            LapceIcons::ITEM_OPENED
+                        } else {
+                            LapceIcons::ITEM_CLOSED
+                        })
+                    })
+                    .style(move || {
+                        let config = config.get();
+                        let size = config.ui.icon_size() as f32;
+                        Style::BASE
+                            .size_px(size, size)
+                            .margin_right_px(6.0)
+                            .color(*config.get_color(LapceColor::LAPCE_ICON_ACTIVE))
+                    }),
+                    label(|| "Staged Changes".to_string()),
+                )
+            })
+            .on_click(move |_| {
+                expanded.update(|expanded| *expanded = !*expanded);
+                true
+            })
+            .style(move || {
+                Style::BASE
+                    .items_center()
+                    .width_pct(100.0)
+                    .padding_horiz_px(10.0)
+                    .height_px(config.get().ui.header_height() as f32)
+                    .cursor(CursorStyle::Pointer)
+            }),
+            scroll(move || {
+                list(
+                    move || {
+                        diff.get()
+                            .lines()
+                            .map(|line| line.to_string())
+                            .enumerate()
+                            .collect::<Vec<_>>()
+                    },
+                    |(i, line)| (*i, line.clone()),
+                    move |(_, line)| {
+                        let color =
+                            if line.starts_with("+++") || line.starts_with("---") {
+                                None
+                            } else if line.starts_with('+') {
+                                Some(LapceColor::SOURCE_CONTROL_ADDED)
+                            } else if line.starts_with('-') {
+                                Some(LapceColor::SOURCE_CONTROL_REMOVED)
+                            } else {
+                                None
+                            };
+                        label(move || line.clone()).style(move || {
+                            let config = config.get();
+                            Style::BASE.apply_opt(color, |s, color| {
+                                s.color(*config.get_color(color))
+                            })
+                        })
+                    },
+                )
+                .style(|| Style::BASE.flex_col().padding_horiz_px(10.0))
+            })
+            .style(move || {
+                let config = config.get();
+                Style::BASE
+                    .width_pct(100.0)
+                    .height_px(200.0)
+                    .font_family(config.editor.font_family.clone())
+                    .font_size(config.editor.font_size() as f32)
+                    .apply_if(!expanded.get(), |s| s.hide())
+            }),
+        )
+    })
+    .style(move || {
+        let config = config.get();
+        Style::BASE
+            .flex_col()
+            .width_pct(100.0)
+            .border_top(1.0)
+            .border_color(*config.get_color(LapceColor::LAPCE_BORDER))
+            .background(*config.get_color(LapceColor::PANEL_BACKGROUND))
+            .apply_if(!is_commit_message, |s| s.hide())
+    })
+}
+
 fn editor_gutter(
     editor: RwSignal<EditorData>,
     is_active: impl Fn(bool) -> bool + 'static + Copy,
@@ -1301,6 +1887,11 @@ fn editor_gutter(
 
     let gutter_width = create_memo(cx.scope, move |_| gutter_rect.get().width());
 
+    let decoration_icon = move |line: usize| {
+        let doc = editor.with(|editor| editor.view.doc);
+        doc.with(|doc| doc.decoration_gutter_icon(line))
+    };
+
     let current_line = create_memo(cx.scope, move |_| {
         let doc = editor.with(|editor| editor.view.doc);
         let (offset, mode) =
@@ -1369,9 +1960,12 @@ fn editor_gutter(
                         .justify_end()
                 }),
                 container(|| {
-                    container(|| {
-                        container(|| {
-                            svg(move || config.get().ui_svg(LapceIcons::LIGHTBULB))
+                    stack(|| {
+                        (
+                            container(|| {
+                                svg(move || {
+                                    config.get().ui_svg(LapceIcons::LIGHTBULB)
+                                })
                                 .style(move || {
                                     let config = config.get();
                                     let size = config.ui.icon_size() as f32;
@@ -1379,19 +1973,35 @@ fn editor_gutter(
                                         *config.get_color(LapceColor::LAPCE_WARN),
                                     )
                                 })
-                        })
-                        .on_click(move |_| {
-                            editor.with_untracked(|editor| {
-                                editor.show_code_actions(true);
-                            });
-                            true
-                        })
-                        .style(move || {
-                            Style::BASE.apply_if(
-                                code_action_line.get() != Some(line),
-                                |s| s.hide(),
-                            )
-                        })
+                            })
+                            .on_click(move |_| {
+                                editor.with_untracked(|editor| {
+                                    editor.show_code_actions(true);
+                                });
+                                true
+                            })
+                            .style(move || {
+                                Style::BASE.apply_if(
+                                    code_action_line.get() != Some(line),
+                                    |s| s.hide(),
+                                )
+                            }),
+                            // The icon of a plugin's decoration, which the
+                            // lightbulb takes the place of
+                            svg(move || {
+                                decoration_icon(line)
+                                    .and_then(|icon| config.get().plugin_svg(&icon))
+                                    .unwrap_or_default()
+                            })
+                            .style(move || {
+                                let size = config.get().ui.icon_size() as f32;
+                                Style::BASE.size_px(size, size).apply_if(
+                                    code_action_line.get() == Some(line)
+                                        || decoration_icon(line).is_none(),
+                                    |s| s.hide(),
+                                )
+                            }),
+                        )
                     })
                     .style(move || {
                         Style::BASE
@@ -1468,33 +2078,44 @@ fn editor_gutter(
                                 (*y, *height, *removed, *color)
                             },
                             move |(y, height, removed, color)| {
-                                empty().style(move || {
-                                    let line_height =
-                                        config.get().editor.line_height();
-                                    let viewport = viewport.get();
-                                    let mut margin_top = (y * line_height) as f32
-                                        - viewport.y0 as f32;
-                                    if removed {
-                                        margin_top -= 5.0;
-                                    }
-                                    let height = if removed {
-                                        10.0
-                                    } else {
-                                        (height * line_height) as f32
-                                    };
-                                    Style::BASE
-                                        .absolute()
-                                        .width_px(3.0)
-                                        .height_px(height)
-                                        .margin_left_px(
-                                            gutter_width.get() as f32
-                                                - padding_right
-                                                + padding_left
-                                                - 3.0,
-                                        )
-                                        .margin_top_px(margin_top)
-                                        .background(color)
-                                })
+                                empty()
+                                    .on_click(move |_| {
+                                        editor.with_untracked(|editor| {
+                                            editor.show_hunk_at(y)
+                                        });
+                                        true
+                                    })
+                                    .hover_style(|| {
+                                        Style::BASE.cursor(CursorStyle::Pointer)
+                                    })
+                                    .style(move || {
+                                        let line_height =
+                                            config.get().editor.line_height();
+                                        let viewport = viewport.get();
+                                        let mut margin_top = (y * line_height)
+                                            as f32
+                                            - viewport.y0 as f32;
+                                        if removed {
+                                            margin_top -= 5.0;
+                                        }
+                                        let height = if removed {
+                                            10.0
+                                        } else {
+                                            (height * line_height) as f32
+                                        };
+                                        Style::BASE
+                                            .absolute()
+                                            .width_px(3.0)
+                                            .height_px(height)
+                                            .margin_left_px(
+                                                gutter_width.get() as f32
+                                                    - padding_right
+                                                    + padding_left
+                                                    - 3.0,
+                                            )
+                                            .margin_top_px(margin_top)
+                                            .background(color)
+                                    })
                             },
                         )
                         .style(|| Style::BASE.absolute().size_pct(100.0, 100.0)),
@@ -1556,11 +2177,98 @@ fn editor_gutter(
     })
 }
 
+/// How long the document has to stay unchanged before the breadcrumbs ask for
+/// its symbols again
+const BREADCRUMBS_SYMBOLS_DELAY: Duration = Duration::from_millis(500);
+
 fn editor_breadcrumbs(
     workspace: Arc<LapceWorkspace>,
     editor: RwSignal<EditorData>,
     config: ReadSignal<Arc<LapceConfig>>,
 ) -> impl View {
+    let cx = ViewContext::get_current();
+    let (proxy, internal_command) = editor.with_untracked(|editor| {
+        (editor.common.proxy.clone(), editor.common.internal_command)
+    });
+    let symbols = create_rw_signal(cx.scope, Arc::new(Vec::<SymbolNode>::new()));
+    {
+        let proxy = proxy.clone();
+        create_effect(cx.scope, move |_| {
+            let doc = editor.with(|editor| editor.view.doc);
+            let (path, version) = doc.with(|doc| {
+                let path = match &doc.content {
+                    DocContent::File(path) => Some(path.clone()),
+                    _ => None,
+                };
+                (path, (doc.buffer_id, doc.rev()))
+            });
+            let Some(path) = path else {
+                symbols.set(Arc::new(Vec::new()));
+                return;
+            };
+            // Ask for the symbols once the document stops changing
+            let proxy = proxy.clone();
+            exec_after(cx.scope, BREADCRUMBS_SYMBOLS_DELAY, move || {
+                let current = doc.with_untracked(|doc| (doc.buffer_id, doc.rev()));
+                if current != version {
+                    return;
+                }
+                let send = create_ext_action(
+                    cx.scope,
+                    move |resp: Option<DocumentSymbolResponse>| {
+                        let tree = resp.map(symbol_tree).unwrap_or_default();
+                        symbols.set(Arc::new(tree));
+                    },
+                );
+                proxy.get_document_symbols(path, move |result| {
+                    send(match result {
+                        Ok(ProxyResponse::GetDocumentSymbols { resp }) => Some(resp),
+                        _ => None,
+                    });
+                });
+            });
+        });
+    }
+
+    // The symbols containing the cursor, or the lines enclosing it by the
+    // syntax when the language server gives no symbols
+    let symbol_crumbs = create_memo(cx.scope, move |_| {
+        let doc = editor.with(|editor| editor.view.doc);
+        let offset = editor
+            .with(|editor| editor.cursor)
+            .with(|cursor| cursor.offset());
+        symbols.with(|symbols| {
+            doc.with(|doc| {
+                if symbols.is_empty() {
+                    let line = doc.buffer().line_of_offset(offset);
+                    return doc
+                        .sticky_headers(line)
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|line| SymbolCrumb::Header {
+                            line,
+                            label: header_label(&doc.buffer().line_content(line)),
+                        })
+                        .collect::<Vec<_>>();
+                }
+                let position = doc.buffer().offset_to_position(offset);
+                let path = symbol_path(symbols, position);
+                (1..=path.len())
+                    .filter_map(|len| {
+                        let path = path[..len].to_vec();
+                        let symbol =
+                            symbol_siblings(symbols, &path).get(*path.last()?)?;
+                        Some(SymbolCrumb::Symbol {
+                            name: symbol.name.clone(),
+                            kind: symbol.kind,
+                            path,
+                        })
+                    })
+                    .collect()
+            })
+        })
+    });
+
     container(move || {
         scroll(move || {
             stack(|| {
@@ -1581,7 +2289,7 @@ fn editor_breadcrumbs(
                                         }
                                     })
                                     .unwrap_or_default();
-                                let mut path = full_path;
+                                let mut path = full_path.clone();
                                 if let Some(workspace_path) =
                                     workspace.clone().path.as_ref()
                                 {
@@ -1590,44 +2298,64 @@ fn editor_breadcrumbs(
                                         .unwrap_or(&path)
                                         .to_path_buf();
                                 }
+                                // The full path of each section goes up from the
+                                // file as the relative path does
                                 path.ancestors()
+                                    .zip(full_path.ancestors())
                                     .collect::<Vec<_>>()
                                     .iter()
                                     .rev()
-                                    .filter_map(|path| {
-                                        Some(path.file_name()?.to_str()?.to_string())
+                                    .filter_map(|(path, full_path)| {
+                                        Some((
+                                            path.file_name()?.to_str()?.to_string(),
+                                            full_path.to_path_buf(),
+                                        ))
                                     })
                                     .collect::<Vec<_>>()
                                     .into_iter()
                                     .enumerate()
                             },
-                            |(i, section)| (*i, section.to_string()),
-                            move |(i, section)| {
+                            |(i, (section, _))| (*i, section.to_string()),
+                            move |(i, (section, full_path))| {
+                                let proxy = proxy.clone();
                                 stack(move || {
+                                    let id = ViewContext::get_current().id;
                                     (
-                                        svg(move || {
-                                            config.get().ui_svg(
-                                                LapceIcons::BREADCRUMB_SEPARATOR,
-                                            )
-                                        })
-                                        .style(move || {
-                                            let config = config.get();
-                                            let size = config.ui.icon_size() as f32;
-                                            Style::BASE
-                                                .apply_if(i == 0, |s| s.hide())
-                                                .size_px(size, size)
-                                                .color(*config.get_color(
-                                                    LapceColor::LAPCE_ICON_ACTIVE,
-                                                ))
-                                        }),
-                                        label(move || section.clone()),
+                                        breadcrumb_separator(i == 0, config),
+                                        label(move || section.clone()).on_click(
+                                            move |_| {
+                                                if let Some(dir) = full_path.parent()
+                                                {
+                                                    show_dir_menu(
+                                                        cx.scope,
+                                                        id,
+                                                        dir.to_path_buf(),
+                                                        proxy.clone(),
+                                                        internal_command,
+                                                        config,
+                                                    );
+                                                }
+                                                true
+                                            },
+                                        ),
                                     )
                                 })
+                                .hover_style(|| {
+                                    Style::BASE.cursor(CursorStyle::Pointer)
+                                })
                                 .style(|| Style::BASE.items_center())
                             },
                         )
-                        .style(|| Style::BASE.padding_horiz_px(10.0))
+                        .style(|| Style::BASE.padding_left_px(10.0))
                     },
+                    list(
+                        move || symbol_crumbs.get().into_iter().enumerate(),
+                        |(i, crumb)| (*i, crumb.clone()),
+                        move |(_, crumb)| {
+                            breadcrumb_symbol(editor, crumb, symbols, config)
+                        },
+                    )
+                    .style(|| Style::BASE.padding_right_px(10.0)),
                     label(move || {
                         let doc = editor.with(|editor| editor.view.doc);
                         doc.with_untracked(|doc| {
@@ -1676,6 +2404,167 @@ fn editor_breadcrumbs(
     })
 }
 
+fn breadcrumb_separator(
+    hide: bool,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    svg(move || config.get().ui_svg(LapceIcons::BREADCRUMB_SEPARATOR)).style(
+        move || {
+            let config = config.get();
+            let size = config.ui.icon_size() as f32;
+            Style::BASE
+                .apply_if(hide, |s| s.hide())
+                .size_px(size, size)
+                .color(*config.get_color(LapceColor::LAPCE_ICON_ACTIVE))
+        },
+    )
+}
+
+fn breadcrumb_symbol(
+    editor: RwSignal<EditorData>,
+    crumb: SymbolCrumb,
+    symbols: RwSignal<Arc<Vec<SymbolNode>>>,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    let (kind, text) = match &crumb {
+        SymbolCrumb::Symbol { name, kind, .. } => (Some(*kind), name.clone()),
+        SymbolCrumb::Header { label, .. } => (None, label.clone()),
+    };
+    stack(move || {
+        let id = ViewContext::get_current().id;
+        (
+            breadcrumb_separator(false, config),
+            svg(move || {
+                let config = config.get();
+                kind.and_then(|kind| config.symbol_svg(&kind))
+                    .unwrap_or_else(|| config.ui_svg(LapceIcons::FILE))
+            })
+            .style(move || {
+                let config = config.get();
+                let size = config.ui.icon_size() as f32;
+                Style::BASE
+                    .size_px(size, size)
+                    .margin_right_px(3.0)
+                    .color(*config.get_color(LapceColor::LAPCE_ICON_ACTIVE))
+                    .apply_if(kind.is_none(), |s| s.hide())
+            }),
+            label(move || text.clone()).on_click(move |_| {
+                show_symbol_menu(
+                    id,
+                    editor,
+                    &crumb,
+                    &symbols.get_untracked(),
+                    config,
+                );
+                true
+            }),
+        )
+    })
+    .hover_style(|| Style::BASE.cursor(CursorStyle::Pointer))
+    .style(|| Style::BASE.items_center())
+}
+
+/// Show the entries of the directory in a menu under the breadcrumb of the
+/// view. Picking a file opens it, picking a directory shows its entries.
+fn show_dir_menu(
+    scope: Scope,
+    id: Id,
+    dir: PathBuf,
+    proxy: ProxyRpcHandler,
+    internal_command: Listener<InternalCommand>,
+    config: ReadSignal<Arc<LapceConfig>>,
+) {
+    let send = {
+        let proxy = proxy.clone();
+        create_ext_action(scope, move |mut items: Vec<FileNodeItem>| {
+            items.sort();
+            let mut menu = Menu::new("");
+            for item in items {
+                let Some(name) = item.path_buf.file_name() else {
+                    continue;
+                };
+                let name = name.to_string_lossy().to_string();
+                let proxy = proxy.clone();
+                let entry = if item.is_dir {
+                    MenuItem::new(format!("{name}/")).action(move || {
+                        show_dir_menu(
+                            scope,
+                            id,
+                            item.path_buf.clone(),
+                            proxy.clone(),
+                            internal_command,
+                            config,
+                        );
+                    })
+                } else {
+                    MenuItem::new(name).action(move || {
+                        internal_command.send(InternalCommand::OpenFile {
+                            path: item.path_buf.clone(),
+                        });
+                    })
+                };
+                menu = menu.entry(entry);
+            }
+            let line_height = config.get_untracked().editor.line_height();
+            id.show_context_menu(menu, Point::new(0.0, line_height as f64));
+        })
+    };
+    proxy.read_dir(dir, move |result| {
+        if let Ok(ProxyResponse::ReadDirResponse { items }) = result {
+            send(items);
+        }
+    });
+}
+
+/// Show the symbols among which the one of the breadcrumb is in a menu under
+/// it, picking one jumps to it
+fn show_symbol_menu(
+    id: Id,
+    editor: RwSignal<EditorData>,
+    crumb: &SymbolCrumb,
+    symbols: &[SymbolNode],
+    config: ReadSignal<Arc<LapceConfig>>,
+) {
+    let (doc, internal_command) = editor
+        .with_untracked(|editor| (editor.view.doc, editor.common.internal_command));
+    let Some(path) = doc.with_untracked(|doc| doc.content.path().cloned()) else {
+        return;
+    };
+    let jump = move |position: EditorPosition| {
+        let path = path.clone();
+        move || {
+            internal_command.send(InternalCommand::JumpToLocation {
+                location: EditorLocation {
+                    path: path.clone(),
+                    position: Some(position),
+                    scroll_offset: None,
+                    ignore_unconfirmed: false,
+                    same_editor_tab: false,
+                },
+            });
+        }
+    };
+    let menu = match crumb {
+        SymbolCrumb::Symbol { path, .. } => symbol_siblings(symbols, path)
+            .iter()
+            .fold(Menu::new(""), |menu, symbol| {
+                menu.entry(MenuItem::new(symbol.name.clone()).action(jump(
+                    EditorPosition::Position(symbol.selection_range.start),
+                )))
+            }),
+        // The lines enclosing the cursor have no siblings to pick from
+        SymbolCrumb::Header { line, label } => Menu::new("").entry(
+            MenuItem::new(label.clone()).action(jump(EditorPosition::Line(*line))),
+        ),
+    };
+    let line_height = config.get_untracked().editor.line_height();
+    id.show_context_menu(menu, Point::new(0.0, line_height as f64));
+}
+
+/// The part of the way to the cursor the editor scrolls each frame, while a
+/// held key moves it
+const SCROLL_EASING: f64 = 0.35;
+
 fn editor_content(
     editor: RwSignal<EditorData>,
     is_active: impl Fn(bool) -> bool + 'static + Copy,
@@ -1687,6 +2576,7 @@ fn editor_content(
         window_origin,
         viewport,
         sticky_header_height,
+        scroll_easing,
         config,
     ) = editor.with_untracked(|editor| {
         (
@@ -1696,20 +2586,32 @@ fn editor_content(
             editor.window_origin,
             editor.viewport,
             editor.sticky_header_height,
+            editor.scroll_easing,
             editor.common.config,
         )
     });
 
+    // Keep the first visible line in place when the line height changes, as
+    // when zooming the editor font
+    let scope = editor.with_untracked(|editor| editor.scope);
+    create_effect(scope, move |last_line_height| {
+        let line_height = config.get().editor.line_height() as f64;
+        if let Some(last) = last_line_height.filter(|last| *last != line_height) {
+            let viewport = viewport.get_untracked();
+            let line = (viewport.y0 / last).round();
+            scroll_to.set(Some(Vec2::new(viewport.x0, line * line_height)));
+        }
+        line_height
+    });
+
     scroll(|| {
         let editor_content_view = editor_view(editor, move || is_active(false))
             .style(move || {
                 let config = config.get();
-                let padding_bottom = if config.editor.scroll_beyond_last_line {
-                    viewport.get().height() as f32
-                        - config.editor.line_height() as f32
-                } else {
-                    0.0
-                };
+                let padding_bottom = config
+                    .editor
+                    .scroll_beyond_last_line_height(viewport.get().height())
+                    as f32;
                 Style::BASE
                     .padding_bottom_px(padding_bottom)
                     .cursor(CursorStyle::Text)
@@ -1739,6 +2641,34 @@ fn editor_content(
                 }
                 true
             })
+            .on_event(EventListener::PointerWheel, move |event| {
+                // Ctrl+wheel zooms the editor font instead of scrolling
+                let Event::PointerWheel(pointer_event) = event else {
+                    return false;
+                };
+                let PointerType::Mouse(info) = &pointer_event.pointer_type else {
+                    return false;
+                };
+                if !pointer_event.modifiers.ctrl() {
+                    // The popups would be left pointing at text scrolled away
+                    editor.with_untracked(|editor| {
+                        editor.common.overlays.dismiss(OverlayEvent::Scroll)
+                    });
+                    return false;
+                }
+                if info.wheel_delta.y == 0.0 {
+                    return false;
+                }
+                let command = if info.wheel_delta.y < 0.0 {
+                    LapceWorkbenchCommand::EditorFontZoomIn
+                } else {
+                    LapceWorkbenchCommand::EditorFontZoomOut
+                };
+                editor.with_untracked(|editor| {
+                    editor.common.workbench_command.send(command)
+                });
+                true
+            })
     })
     .scroll_bar_color(move || *config.get().get_color(LapceColor::LAPCE_SCROLL_BAR))
     .on_resize(move |point, _rect| {
@@ -1763,7 +2693,12 @@ fn editor_content(
                     x,
                     (view.visual_line(line) * line_height) as f64,
                 ))
-                .inflate(10.0, 0.0);
+                .inflate(
+                    (config.editor.cursor_surrounding_columns as f64
+                        * editor_char_width(&config))
+                    .max(10.0),
+                    0.0,
+                );
 
             let viewport = viewport.get_untracked();
             let smallest_distance = (viewport.y0 - rect.y0)
@@ -1788,7 +2723,24 @@ fn editor_content(
                     + sticky_header_height.get_untracked();
                 rect.y1 +=
                     (config.editor.cursor_surrounding_lines * line_height) as f64;
-                rect
+                let Some(frame) = scroll_easing.get() else {
+                    return rect;
+                };
+                // Scroll part of the way to the cursor, and on in the next
+                // frames
+                let distance = if rect.y0 < viewport.y0 {
+                    rect.y0 - viewport.y0
+                } else if rect.y1 > viewport.y1 {
+                    rect.y1 - viewport.y1
+                } else {
+                    0.0
+                };
+                if distance.abs() < 1.0 {
+                    return rect;
+                }
+                editor
+                    .with_untracked(|editor| editor.next_scroll_easing_frame(frame));
+                rect - Vec2::new(0.0, distance * (1.0 - SCROLL_EASING))
             }
         } else {
             Rect::ZERO
@@ -1959,6 +2911,29 @@ fn find_view(
                             is_active,
                             replace_focus,
                         ),
+                        label(move || {
+                            let editor = editor.get();
+                            let offset = editor.cursor.with(|c| c.offset());
+                            let (index, total) = editor
+                                .view
+                                .doc
+                                .with(|doc| doc.find_result.clone())
+                                .match_index(offset);
+                            match (index, total) {
+                                (_, 0) => "No results".to_string(),
+                                (Some(index), total) => {
+                                    format!("{index} of {total}")
+                                }
+                                (None, total) => format!("? of {total}"),
+                            }
+                        })
+                        .style(move || {
+                            let config = config.get();
+                            Style::BASE
+                                .min_width_px(70.0)
+                                .padding_left_px(6.0)
+                                .color(*config.get_color(LapceColor::EDITOR_DIM))
+                        }),
                         clickable_icon(
                             || LapceIcons::SEARCH_BACKWARD,
                             move || {
@@ -1983,6 +2958,20 @@ fn find_view(
                             config,
                         )
                         .style(|| Style::BASE.padding_left_px(6.0)),
+                        clickable_icon(
+                            || LapceIcons::SEARCH_IN_SELECTION,
+                            move || {
+                                editor.get_untracked().toggle_search_in_selection();
+                            },
+                            move || {
+                                let doc = editor.with(|editor| editor.view.doc);
+                                let scope = doc.with(|doc| doc.find_result.scope);
+                                scope.with(|scope| scope.is_some())
+                            },
+                            || false,
+                            config,
+                        )
+                        .style(|| Style::BASE.padding_left_px(6.0)),
                         clickable_icon(
                             || LapceIcons::CLOSE,
                             move || {
@@ -2013,7 +3002,11 @@ fn find_view(
                         ),
                         clickable_icon(
                             || LapceIcons::SEARCH_REPLACE,
-                            move || {},
+                            move || {
+                                editor
+                                    .get_untracked()
+                                    .replace_next(Modifiers::empty());
+                            },
                             move || false,
                             || false,
                             config,
@@ -2021,7 +3014,9 @@ fn find_view(
                         .style(|| Style::BASE.padding_left_px(6.0)),
                         clickable_icon(
                             || LapceIcons::SEARCH_REPLACE_ALL,
-                            move || {},
+                            move || {
+                                editor.get_untracked().replace_all();
+                            },
                             move || false,
                             || false,
                             config,
@@ -2070,7 +3065,7 @@ fn find_view(
     })
 }
 
-fn changes_colors(
+pub(crate) fn changes_colors(
     changes: im::Vector<DiffLines>,
     min_line: usize,
     max_line: usize,
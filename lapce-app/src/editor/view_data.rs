@@ -5,7 +5,7 @@
     peniko::{kurbo::Point, Color},
     reactive::{
         create_rw_signal, ReadSignal, RwSignal, Scope, SignalGetUntracked,
-        SignalWith, SignalWithUntracked,
+        SignalSet, SignalWith, SignalWithUntracked,
     },
     views::VirtualListVector,
 };
@@ -26,6 +26,7 @@
     config::LapceConfig,
     doc::{phantom_text::PhantomTextLine, Document},
     find::{Find, FindResult},
+    inline_suggestion::InlineSuggestion,
 };
 
 use super::diff::DiffInfo;
@@ -119,6 +120,9 @@ pub struct EditorViewData {
     pub kind: RwSignal<EditorViewKind>,
     /// The text layouts for the document. This may be shared with other views.
     pub text_layouts: Rc<RefCell<TextLayoutCache>>,
+    /// The inline suggestion shown at the cursor of this view, which the
+    /// other views of the document don't show
+    pub inline_suggestion: RwSignal<Option<InlineSuggestion>>,
 
     pub config: ReadSignal<Arc<LapceConfig>>,
 }
@@ -148,12 +152,14 @@ impl EditorViewData {
     pub fn new(
         doc: RwSignal<Document>,
         kind: RwSignal<EditorViewKind>,
+        inline_suggestion: RwSignal<Option<InlineSuggestion>>,
         config: ReadSignal<Arc<LapceConfig>>,
     ) -> EditorViewData {
         EditorViewData {
             doc,
             kind,
             text_layouts: Rc::new(RefCell::new(TextLayoutCache::new())),
+            inline_suggestion,
             config,
         }
     }
@@ -226,12 +232,52 @@ pub fn duplicate(&self, cx: Scope) -> Self {
             doc: self.doc,
             text_layouts: Rc::new(RefCell::new(TextLayoutCache::new())),
             kind: create_rw_signal(cx, self.kind.get_untracked()),
+            inline_suggestion: create_rw_signal(cx, None),
             config: self.config,
         }
     }
 
     pub fn line_phantom_text(&self, line: usize) -> PhantomTextLine {
-        self.doc.with_untracked(|doc| doc.line_phantom_text(line))
+        let suggestion = self.current_inline_suggestion();
+        self.doc.with_untracked(|doc| {
+            doc.line_phantom_text_with(
+                line,
+                suggestion.as_ref().map(|s| (s.offset, s.text.as_str())),
+            )
+        })
+    }
+
+    /// The inline suggestion of the view, if it was worked out for the
+    /// current version of the document
+    pub fn current_inline_suggestion(&self) -> Option<InlineSuggestion> {
+        let rev = self.rev();
+        self.inline_suggestion
+            .get_untracked()
+            .filter(|suggestion| suggestion.rev == rev)
+    }
+
+    /// Show the inline suggestion in this view, dropping the layouts of the
+    /// lines the old and new suggestions are on so that they're laid out
+    /// again with it
+    pub fn set_inline_suggestion(&self, suggestion: Option<InlineSuggestion>) {
+        let old = self.inline_suggestion.get_untracked();
+        if old == suggestion {
+            return;
+        }
+        let lines = [old.as_ref(), suggestion.as_ref()]
+            .into_iter()
+            .flatten()
+            .map(|suggestion| self.offset_to_line_col(suggestion.offset).0)
+            .collect::<Vec<_>>();
+        {
+            let mut text_layouts = self.text_layouts.borrow_mut();
+            for layouts in text_layouts.layouts.values_mut() {
+                for line in &lines {
+                    layouts.remove(line);
+                }
+            }
+        }
+        self.inline_suggestion.set(suggestion);
     }
 
     /// Get the text layout for the given line.  
@@ -272,9 +318,19 @@ pub fn get_text_layout(
             .is_some();
         // If there isn't an entry then we actually have to create it
         if !cache_exists {
-            let text_layout = self
-                .doc
-                .with_untracked(|doc| doc.get_text_layout(line, font_size));
+            let suggestion = self
+                .current_inline_suggestion()
+                .filter(|s| self.offset_to_line_col(s.offset).0 == line);
+            let text_layout = self.doc.with_untracked(|doc| match &suggestion {
+                // The line the suggestion is on is laid out for this view
+                // only, rather than in the layouts the views share
+                Some(suggestion) => Arc::new(doc.new_text_layout(
+                    line,
+                    font_size,
+                    Some((suggestion.offset, &suggestion.text)),
+                )),
+                None => doc.get_text_layout(line, font_size),
+            });
             let mut cache = self.text_layouts.borrow_mut();
             let width = text_layout.text.size().width;
             if width > cache.max_width {
@@ -349,6 +405,8 @@ pub fn line_end_col(&self, line: usize, caret: bool) -> usize {
             .with_untracked(|doc| doc.buffer().line_end_col(line, caret))
     }
 
+    /// Select the word at the given offset, taking the language specific word
+    /// characters into account.
     pub fn select_word(&self, offset: usize) -> (usize, usize) {
         self.doc
             .with_untracked(|doc| doc.buffer().select_word(offset))
@@ -375,6 +433,62 @@ pub fn line_point_of_line_col(
         text_layout.text.hit_position(col).point
     }
 
+    /// The horizontal extent of the grapheme cluster at the offset, in the text
+    /// layout of its line. Wide characters get their full width, and clusters
+    /// sharing a glyph, as the characters of a ligature do, each get an even
+    /// part of it. The extent is empty at the end of the line.
+    pub fn grapheme_x_extent(
+        &self,
+        offset: usize,
+        font_size: usize,
+        before_phantom: bool,
+    ) -> (f64, f64) {
+        let (line, col) = self.offset_to_line_col(offset);
+        let line_start = offset - col;
+        let line_end = line_start + self.line_end_col(line, true);
+        let phantom_text = self.line_phantom_text(line);
+        let text_layout = self.get_text_layout(line, font_size);
+        let x_of = |offset: usize| {
+            let col = phantom_text.col_after(offset - line_start, before_phantom);
+            text_layout.text.hit_position(col).point.x
+        };
+
+        self.doc.with_untracked(|doc| {
+            let buffer = doc.buffer();
+            let x0 = x_of(offset);
+
+            // The clusters before it that are drawn by the same glyph
+            let mut start = offset;
+            let mut index = 0;
+            while start > line_start {
+                let prev = buffer.prev_grapheme_offset(start, 1, line_start);
+                if x_of(prev) != x0 {
+                    break;
+                }
+                start = prev;
+                index += 1;
+            }
+
+            let mut end = buffer.next_grapheme_offset(offset, 1, line_end);
+            if end == offset {
+                return (x0, x0);
+            }
+            let mut count = index + 1;
+            let mut x1 = x_of(end);
+            while x1 <= x0 && end < line_end {
+                end = buffer.next_grapheme_offset(end, 1, line_end);
+                x1 = x_of(end);
+                count += 1;
+            }
+            if x1 <= x0 {
+                return (x0, x0);
+            }
+
+            let width = (x1 - x0) / count as f64;
+            (x0 + width * index as f64, x0 + width * (index + 1) as f64)
+        })
+    }
+
     /// Get the (point above, point below) of a particular offset within the editor.
     pub fn points_of_offset(&self, offset: usize) -> (Point, Point) {
         let (line, col) = self.offset_to_line_col(offset);
@@ -653,7 +767,7 @@ pub fn line_col_of_point(
         // Ensure that the column doesn't end up out of bounds, so things like clicking on the far
         // right end will just go to the end of the line.
         let max_col = self.line_end_col(line, mode != Mode::Normal);
-        let mut col = col.min(max_col);
+        let mut col = self.grapheme_start_col(line, col.min(max_col));
 
         if config.editor.atomic_soft_tabs && config.editor.tab_width > 1 {
             col = snap_to_soft_tab_line_col(
@@ -679,9 +793,10 @@ pub fn line_horiz_col(
             ColPosition::Col(x) => {
                 let text_layout = self.get_text_layout(line, font_size);
                 let hit_point = text_layout.text.hit_point(Point::new(x, 0.0));
-                let n = hit_point.index;
+                let phantom_text = self.line_phantom_text(line);
+                let n = phantom_text.before_col(hit_point.index);
 
-                n.min(self.line_end_col(line, caret))
+                self.grapheme_start_col(line, n.min(self.line_end_col(line, caret)))
             }
             ColPosition::End => self.line_end_col(line, caret),
             ColPosition::Start => 0,
@@ -691,6 +806,15 @@ pub fn line_horiz_col(
         }
     }
 
+    /// The column of the start of the grapheme cluster containing the column,
+    /// as a hit on the text layout can land within a cluster
+    fn grapheme_start_col(&self, line: usize, col: usize) -> usize {
+        self.doc.with_untracked(|doc| {
+            let line_start = doc.buffer().offset_of_line(line);
+            doc.buffer().grapheme_start_offset(line_start + col) - line_start
+        })
+    }
+
     /// Advance to the right in the manner of the given mode.  
     /// This is not the same as the [`Movement::Right`] command.
     pub fn move_right(&self, offset: usize, mode: Mode, count: usize) -> usize {
@@ -57,6 +57,8 @@ pub struct EditorTabInfo {
     pub active: usize,
     pub is_focus: bool,
     pub children: Vec<EditorTabChildInfo>,
+    #[serde(default)]
+    pub pinned: usize,
 }
 
 impl EditorTabInfo {
@@ -73,6 +75,7 @@ pub fn to_data(
                 editor_tab_id,
                 split,
                 active: self.active,
+                pinned: self.pinned.min(self.children.len()),
                 children: self
                     .children
                     .iter()
@@ -110,6 +113,11 @@ pub enum EditorTabChildSource {
         right: RwSignal<Document>,
     },
     Settings,
+    /// A document that isn't a file, such as the search results buffer or a
+    /// rendered notebook, found in the editors by its content
+    Content {
+        doc: RwSignal<Document>,
+    },
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -168,6 +176,9 @@ pub struct EditorTabData {
     pub editor_tab_id: EditorTabId,
     pub active: usize,
     pub children: Vec<(RwSignal<usize>, EditorTabChild)>,
+    /// How many of the children are pinned, the pinned ones always being the
+    /// first children
+    pub pinned: usize,
     pub window_origin: Point,
     pub layout_rect: Rect,
     pub locations: RwSignal<im::Vector<EditorLocation>>,
@@ -179,19 +190,23 @@ pub fn get_editor(
         &self,
         editors: &im::HashMap<EditorId, RwSignal<EditorData>>,
         path: &Path,
+    ) -> Option<(usize, RwSignal<EditorData>)> {
+        self.get_editor_with_content(editors, &DocContent::File(path.to_path_buf()))
+    }
+
+    /// The editor of a document with the content
+    pub fn get_editor_with_content(
+        &self,
+        editors: &im::HashMap<EditorId, RwSignal<EditorData>>,
+        content: &DocContent,
     ) -> Option<(usize, RwSignal<EditorData>)> {
         for (i, child) in self.children.iter().enumerate() {
             if let (_, EditorTabChild::Editor(editor_id)) = child {
                 if let Some(editor) = editors.get(editor_id) {
                     let e = editor.get_untracked();
-                    let is_path = e.view.doc.with_untracked(|doc| {
-                        if let DocContent::File(p) = &doc.content {
-                            p == path
-                        } else {
-                            false
-                        }
-                    });
-                    if is_path {
+                    let is_content =
+                        e.view.doc.with_untracked(|doc| &doc.content == content);
+                    if is_content {
                         return Some((i, *editor));
                     }
                 }
@@ -205,7 +220,7 @@ pub fn get_unconfirmed_editor_tab_child(
         editors: &im::HashMap<EditorId, RwSignal<EditorData>>,
         diff_editors: &im::HashMap<EditorId, DiffEditorData>,
     ) -> Option<(usize, EditorTabChild)> {
-        for (i, (_, child)) in self.children.iter().enumerate() {
+        for (i, (_, child)) in self.children.iter().enumerate().skip(self.pinned) {
             match child {
                 EditorTabChild::Editor(editor_id) => {
                     if let Some(editor) = editors.get(editor_id) {
@@ -241,7 +256,7 @@ pub fn get_unconfirmed_editor(
         &self,
         editors: &im::HashMap<EditorId, RwSignal<EditorData>>,
     ) -> Option<(usize, RwSignal<EditorData>)> {
-        for (i, child) in self.children.iter().enumerate() {
+        for (i, child) in self.children.iter().enumerate().skip(self.pinned) {
             if let (_, EditorTabChild::Editor(editor_id)) = child {
                 if let Some(editor) = editors.get(editor_id) {
                     let e = editor.get_untracked();
@@ -265,7 +280,38 @@ pub fn tab_info(&self, data: &WindowTabData) -> EditorTabInfo {
                 .iter()
                 .map(|(_, child)| child.child_info(data))
                 .collect(),
+            pinned: self.pinned,
         };
         info
     }
+
+    pub fn is_pinned(&self, index: usize) -> bool {
+        index < self.pinned
+    }
+
+    /// Pin the child at the index, or unpin it if it's pinned. A pinned child
+    /// moves to the end of the pinned ones, and an unpinned one to the start of
+    /// the others.
+    pub fn toggle_pin(&mut self, index: usize) {
+        if index >= self.children.len() {
+            return;
+        }
+        let active = self.children.get(self.active).map(|(_, c)| c.clone());
+        let to = if self.is_pinned(index) {
+            self.pinned -= 1;
+            self.pinned
+        } else {
+            self.pinned += 1;
+            self.pinned - 1
+        };
+        let item = self.children.remove(index);
+        self.children.insert(to, item);
+        if let Some(active) = active {
+            if let Some(active) =
+                self.children.iter().position(|(_, c)| c == &active)
+            {
+                self.active = active;
+            }
+        }
+    }
 }
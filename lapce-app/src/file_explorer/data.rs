@@ -1,32 +1,159 @@
-use std::path::PathBuf;
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
 
-use floem::reactive::{create_rw_signal, RwSignal, Scope, SignalUpdate};
+use floem::{
+    ext_event::create_ext_action,
+    glazier::Modifiers,
+    reactive::{
+        create_effect, create_memo, create_rw_signal, RwSignal, Scope, SignalGet,
+        SignalGetUntracked, SignalSet, SignalUpdate, SignalWith,
+        SignalWithUntracked,
+    },
+};
 use indexmap::IndexMap;
+use lapce_core::{command::FocusCommand, mode::Mode, selection::Selection};
+use lapce_rpc::{
+    file::{archive_member, is_archive, FileChange},
+    proxy::ProxyResponse,
+    RpcError,
+};
+use lapce_xi_rope::Rope;
+use tracing::error;
 
 use super::node::FileNode;
-use crate::window_tab::CommonData;
+use crate::{
+    command::{CommandExecuted, CommandKind, InternalCommand, LapceCommand},
+    editor::EditorData,
+    id::EditorId,
+    keypress::{condition::Condition, KeyPressFocus},
+    main_split::canonical_path,
+    panel::kind::PanelKind,
+    window_tab::{CommonData, Focus},
+};
+
+/// What the name being typed in the explorer is for
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Naming {
+    /// A file to create in the directory
+    NewFile(PathBuf),
+    /// A folder to create in the directory
+    NewFolder(PathBuf),
+    /// A new name for the file or folder
+    Rename(PathBuf),
+}
+
+impl Naming {
+    /// The directory a file or folder is being created in
+    pub fn new_in(&self) -> Option<&Path> {
+        match self {
+            Naming::NewFile(dir) | Naming::NewFolder(dir) => Some(dir),
+            Naming::Rename(_) => None,
+        }
+    }
+}
+
+/// The paths cut or copied in the explorer, to be pasted in a directory
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileClipboard {
+    pub paths: Vec<PathBuf>,
+    /// Whether pasting moves the paths rather than copying them
+    pub cut: bool,
+}
+
+/// What the notification about deleting paths from the explorer says
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DeleteNotice {
+    /// Deleting the paths for good waits to be confirmed
+    Confirm(Vec<PathBuf>),
+    /// The paths were moved to the trash, and can be put back
+    Trashed(Vec<PathBuf>),
+    /// The paths were deleted for good
+    Deleted(Vec<PathBuf>),
+    /// Putting the paths back from the trash failed
+    RestoreFailed(String),
+}
 
 #[derive(Clone)]
 pub struct FileExplorerData {
     pub root: FileNode,
     pub common: CommonData,
     pub all_files: RwSignal<im::HashMap<PathBuf, FileNode>>,
+    /// The selected paths, the last one being where a range selection starts
+    pub selected: RwSignal<Vec<PathBuf>>,
+    pub naming: RwSignal<Option<Naming>>,
+    /// The editor the name is typed in
+    pub naming_editor: EditorData,
+    pub clipboard: RwSignal<Option<FileClipboard>>,
+    /// The path being revealed, until the folders down to it are read
+    revealing: RwSignal<Option<PathBuf>>,
+    /// The path last revealed, which the tree is scrolled to
+    pub revealed: RwSignal<Option<PathBuf>>,
+    pub delete_notice: RwSignal<Option<DeleteNotice>>,
+}
+
+impl KeyPressFocus for FileExplorerData {
+    fn get_mode(&self) -> Mode {
+        Mode::Insert
+    }
+
+    fn check_condition(&self, condition: Condition) -> bool {
+        let naming = self.naming.with_untracked(|naming| naming.is_some());
+        match condition {
+            Condition::PanelFocus => true,
+            Condition::ListFocus | Condition::ModalFocus => naming,
+            Condition::FileExplorerFocus => !naming,
+            _ => false,
+        }
+    }
+
+    fn run_command(
+        &self,
+        command: &LapceCommand,
+        count: Option<usize>,
+        mods: Modifiers,
+    ) -> CommandExecuted {
+        let naming = self.naming.with_untracked(|naming| naming.is_some());
+        match &command.kind {
+            CommandKind::Focus(cmd) => self.run_focus_command(cmd, naming),
+            CommandKind::Edit(_)
+            | CommandKind::Move(_)
+            | CommandKind::MultiSelection(_)
+                if naming =>
+            {
+                self.naming_editor.run_command(command, count, mods)
+            }
+            _ => CommandExecuted::No,
+        }
+    }
+
+    fn receive_char(&self, c: &str) {
+        if self.naming.with_untracked(|naming| naming.is_some()) {
+            self.naming_editor.receive_char(c);
+        }
+    }
 }
 
 impl FileExplorerData {
     pub fn new(cx: Scope, common: CommonData) -> Self {
         let path = common.workspace.path.clone().unwrap_or_default();
         let all_files = create_rw_signal(cx, im::HashMap::new());
+        let config = common.config;
+        let show_ignored =
+            create_memo(cx, move |_| config.with(|c| c.core.show_ignored_files));
         let root = FileNode {
             scope: cx,
             path: path.clone(),
             is_dir: true,
+            is_ignored: false,
             read: create_rw_signal(cx, false),
             expanded: create_rw_signal(cx, false),
             children: create_rw_signal(cx, IndexMap::new()),
             children_open_count: create_rw_signal(cx, 0),
             all_files,
             line_height: common.ui_line_height,
+            show_ignored,
             internal_command: common.internal_command,
         };
         all_files.update(|all_files| {
@@ -35,10 +162,654 @@ pub fn new(cx: Scope, common: CommonData) -> Self {
         if common.workspace.path.is_some() {
             root.toggle_expand(&common.proxy);
         }
-        Self {
+
+        {
+            // List or leave out the ignored entries of the directories read
+            // when the setting is toggled
+            let proxy = common.proxy.clone();
+            create_effect(cx, move |last| {
+                let show_ignored = show_ignored.get();
+                if last.is_some() && last != Some(show_ignored) {
+                    let nodes = all_files.get_untracked();
+                    for node in nodes.values() {
+                        if node.is_dir && node.read.get_untracked() {
+                            node.reload(&proxy);
+                        }
+                    }
+                }
+                show_ignored
+            });
+        }
+        let naming_editor =
+            EditorData::new_local(cx, EditorId::next(), common.clone());
+        let explorer = Self {
             root,
             common,
             all_files,
+            selected: create_rw_signal(cx, Vec::new()),
+            naming: create_rw_signal(cx, None),
+            naming_editor,
+            clipboard: create_rw_signal(cx, None),
+            revealing: create_rw_signal(cx, None),
+            revealed: create_rw_signal(cx, None),
+            delete_notice: create_rw_signal(cx, None),
+        };
+
+        {
+            // Go on revealing a path as the folders down to it are read
+            let explorer = explorer.clone();
+            create_effect(cx, move |_| {
+                all_files.with(|_| ());
+                explorer.continue_reveal();
+            });
+        }
+
+        explorer
+    }
+
+    fn run_focus_command(
+        &self,
+        cmd: &FocusCommand,
+        naming: bool,
+    ) -> CommandExecuted {
+        match cmd {
+            FocusCommand::ListSelect if naming => self.confirm_naming(),
+            FocusCommand::ModalClose if naming => self.cancel_naming(),
+            _ if naming => return CommandExecuted::No,
+            FocusCommand::FileExplorerNewFile => {
+                self.start_naming(Naming::NewFile(self.target_dir()))
+            }
+            FocusCommand::FileExplorerNewFolder => {
+                self.start_naming(Naming::NewFolder(self.target_dir()))
+            }
+            FocusCommand::FileExplorerRename => {
+                let Some(path) = self.selected.get_untracked().last().cloned()
+                else {
+                    return CommandExecuted::No;
+                };
+                self.start_naming(Naming::Rename(path));
+            }
+            FocusCommand::FileExplorerDelete => self.delete_selected(),
+            FocusCommand::FileExplorerDeletePermanently => {
+                self.delete_selected_permanently()
+            }
+            FocusCommand::FileExplorerCut => self.cut_selected(),
+            FocusCommand::FileExplorerCopy => self.copy_selected(),
+            FocusCommand::FileExplorerPaste => self.paste(self.target_dir()),
+            _ => return CommandExecuted::No,
+        }
+        CommandExecuted::Yes
+    }
+
+    /// Select the node clicked on, with ctrl toggling it in the selection
+    /// and shift selecting up to it, opening it on a plain click
+    pub fn click(&self, node: &FileNode, mods: Modifiers) {
+        self.common.focus.set(Focus::Panel(PanelKind::FileExplorer));
+        if self.naming.with_untracked(|naming| naming.is_some()) {
+            self.cancel_naming();
+        }
+        let path = node.path.clone();
+        if mods.ctrl() || mods.meta() {
+            self.selected.update(|selected| {
+                match selected.iter().position(|p| p == &path) {
+                    Some(i) => {
+                        selected.remove(i);
+                    }
+                    None => selected.push(path),
+                }
+            });
+        } else if mods.shift() {
+            let visible = self.visible_paths();
+            let anchor = self.selected.get_untracked().last().cloned();
+            let range = anchor
+                .and_then(|anchor| visible.iter().position(|p| p == &anchor))
+                .zip(visible.iter().position(|p| p == &path));
+            // The anchor stays last, where the range started
+            let selected = match range {
+                Some((anchor, end)) if anchor <= end => {
+                    visible[anchor..=end].iter().rev().cloned().collect()
+                }
+                Some((anchor, end)) => visible[end..=anchor].to_vec(),
+                None => vec![path],
+            };
+            self.selected.set(selected);
+        } else {
+            self.selected.set(vec![path]);
+            node.click(&self.common.proxy);
+        }
+    }
+
+    /// Expand the folders down to the path, then select it and scroll it into
+    /// view
+    pub fn reveal(&self, path: PathBuf) {
+        // The files are opened at their resolved paths, which are in the
+        // resolved workspace folder if it's reached through a symlink
+        let path = if path.starts_with(&self.root.path) {
+            path
+        } else {
+            let (root, _) = canonical_path(&self.root.path);
+            match path.strip_prefix(root) {
+                Ok(rest) => self.root.path.join(rest),
+                Err(_) => return,
+            }
+        };
+        if path == self.root.path {
+            return;
+        }
+        self.revealing.set(Some(path));
+        self.continue_reveal();
+    }
+
+    /// Expand the folders down to the path being revealed as far as they're
+    /// read, and select it once its node is there
+    fn continue_reveal(&self) {
+        let Some(path) = self.revealing.get_untracked() else {
+            return;
+        };
+        let mut dirs = path
+            .ancestors()
+            .skip(1)
+            .take_while(|dir| dir.starts_with(&self.root.path))
+            .collect::<Vec<_>>();
+        dirs.reverse();
+        for dir in dirs {
+            // Wait for the parent folder to be read
+            let Some(node) = self.node(dir) else {
+                return;
+            };
+            if !node.expanded.get_untracked() {
+                node.toggle_expand(&self.common.proxy);
+            }
+        }
+        if self.node(&path).is_none() {
+            return;
+        }
+        self.revealing.set(None);
+        // An archive revealed is opened, to browse its members
+        if is_archive(&path) {
+            if let Some(node) = self.node(&path) {
+                if node.is_dir && !node.expanded.get_untracked() {
+                    node.toggle_expand(&self.common.proxy);
+                }
+            }
+        }
+        self.selected.set(vec![path.clone()]);
+        self.revealed.set(Some(path));
+    }
+
+    /// The position of the path among the nodes shown in the tree
+    pub fn visible_index(&self, path: &Path) -> Option<usize> {
+        self.visible_paths().iter().position(|p| p == path)
+    }
+
+    /// The paths of the nodes shown in the tree, in order
+    fn visible_paths(&self) -> Vec<PathBuf> {
+        fn visit(node: &FileNode, paths: &mut Vec<PathBuf>) {
+            if !node.is_dir || !node.expanded.get_untracked() {
+                return;
+            }
+            node.children.with_untracked(|children| {
+                for child in children.values() {
+                    paths.push(child.path.clone());
+                    visit(child, paths);
+                }
+            });
+        }
+        let mut paths = Vec::new();
+        visit(&self.root, &mut paths);
+        paths
+    }
+
+    /// The selected paths, without the ones in a selected folder since they
+    /// go with it
+    fn selected_roots(&self) -> Vec<PathBuf> {
+        let selected = self.selected.get_untracked();
+        selected
+            .iter()
+            .filter(|path| {
+                !selected
+                    .iter()
+                    .any(|other| other != *path && path.starts_with(other))
+            })
+            // The members of archives are read only
+            .filter(|path| archive_member(path).is_none())
+            .cloned()
+            .collect()
+    }
+
+    /// The directory the selection is in, or is, which is where new files and
+    /// pasted ones go
+    pub fn target_dir(&self) -> PathBuf {
+        self.selected
+            .get_untracked()
+            .last()
+            .and_then(|path| {
+                let is_dir = self.all_files.with_untracked(|all_files| {
+                    all_files.get(path).map(|node| node.is_dir)
+                })?;
+                if is_dir {
+                    Some(path.clone())
+                } else {
+                    path.parent().map(Path::to_path_buf)
+                }
+            })
+            .unwrap_or_else(|| self.root.path.clone())
+    }
+
+    pub fn start_naming(&self, naming: Naming) {
+        let read_only = match &naming {
+            Naming::NewFile(dir) | Naming::NewFolder(dir) => in_archive(dir),
+            Naming::Rename(path) => archive_member(path).is_some(),
+        };
+        if read_only {
+            return;
+        }
+        let (text, selection) = match &naming {
+            Naming::NewFile(dir) | Naming::NewFolder(dir) => {
+                if let Some(node) = self.node(dir) {
+                    if !node.expanded.get_untracked() {
+                        node.toggle_expand(&self.common.proxy);
+                    }
+                }
+                (String::new(), Selection::caret(0))
+            }
+            Naming::Rename(path) => {
+                let name = path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                // Select the name without its extension, as that's usually
+                // what's being changed
+                let stem = path
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().len())
+                    .unwrap_or(name.len());
+                (name, Selection::region(0, stem))
+            }
+        };
+        self.naming_editor
+            .view
+            .doc
+            .update(|doc| doc.reload(Rope::from(text), true));
+        self.naming_editor
+            .cursor
+            .update(|cursor| cursor.set_insert(selection));
+        self.naming.set(Some(naming));
+        self.common.focus.set(Focus::Panel(PanelKind::FileExplorer));
+    }
+
+    pub fn cancel_naming(&self) {
+        self.naming.set(None);
+    }
+
+    fn confirm_naming(&self) {
+        let Some(naming) = self.naming.get_untracked() else {
+            return;
+        };
+        self.naming.set(None);
+        let name = self
+            .naming_editor
+            .view
+            .doc
+            .with_untracked(|doc| doc.buffer().to_string());
+        let name = name.trim();
+        if name.is_empty() {
+            return;
+        }
+
+        let proxy = &self.common.proxy;
+        match naming {
+            Naming::NewFile(dir) => {
+                let path = dir.join(name);
+                let internal_command = self.common.internal_command;
+                let explorer = self.clone();
+                let done = {
+                    let path = path.clone();
+                    move || {
+                        explorer.selected.set(vec![path.clone()]);
+                        internal_command.send(InternalCommand::OpenFile { path });
+                    }
+                };
+                proxy.create_file(path, self.on_done(vec![dir], done));
+            }
+            Naming::NewFolder(dir) => {
+                let path = dir.join(name);
+                let explorer = self.clone();
+                let done = {
+                    let path = path.clone();
+                    move || explorer.selected.set(vec![path])
+                };
+                proxy.create_directory(path, self.on_done(vec![dir], done));
+            }
+            Naming::Rename(from) => {
+                let Some(dir) = from.parent().map(Path::to_path_buf) else {
+                    return;
+                };
+                let to = dir.join(name);
+                if to == from {
+                    return;
+                }
+                let explorer = self.clone();
+                let done = {
+                    let to = to.clone();
+                    move || explorer.selected.set(vec![to])
+                };
+                proxy.rename_path(from, to, self.on_done(vec![dir], done));
+            }
+        }
+    }
+
+    /// Move the selected paths to the trash
+    pub fn delete_selected(&self) {
+        self.delete_notice.set(None);
+        for path in self.selected_roots() {
+            let Some(dir) = path.parent().map(Path::to_path_buf) else {
+                continue;
+            };
+            let done = self.notice_deleted(path.clone(), false);
+            self.common
+                .proxy
+                .trash_path(path, self.on_done(vec![dir], done));
+        }
+        self.selected.set(Vec::new());
+    }
+
+    /// Ask to confirm deleting the selected paths for good
+    pub fn delete_selected_permanently(&self) {
+        let paths = self.selected_roots();
+        if !paths.is_empty() {
+            self.delete_notice.set(Some(DeleteNotice::Confirm(paths)));
+        }
+    }
+
+    /// Delete the paths waiting to be confirmed for good
+    pub fn confirm_delete(&self) {
+        let Some(DeleteNotice::Confirm(paths)) = self.delete_notice.get_untracked()
+        else {
+            return;
+        };
+        self.delete_notice.set(None);
+        for path in paths {
+            let Some(dir) = path.parent().map(Path::to_path_buf) else {
+                continue;
+            };
+            let done = self.notice_deleted(path.clone(), true);
+            self.common
+                .proxy
+                .delete_path(path, self.on_done(vec![dir], done));
         }
+        self.selected.set(Vec::new());
+    }
+
+    /// Put the paths last moved to the trash back where they were
+    pub fn undo_delete(&self) {
+        let Some(DeleteNotice::Trashed(paths)) = self.delete_notice.get_untracked()
+        else {
+            return;
+        };
+        let dirs: Vec<PathBuf> = paths
+            .iter()
+            .filter_map(|path| path.parent().map(Path::to_path_buf))
+            .collect();
+        let explorer = self.clone();
+        let restored = paths.clone();
+        let send = create_ext_action(
+            self.common.scope,
+            move |result: Result<ProxyResponse, RpcError>| {
+                for dir in &dirs {
+                    if let Some(node) = explorer.node(dir) {
+                        if node.read.get_untracked() {
+                            node.reload(&explorer.common.proxy);
+                        }
+                    }
+                }
+                match result {
+                    Ok(_) => {
+                        explorer.delete_notice.set(None);
+                        explorer.selected.set(restored);
+                    }
+                    Err(err) => explorer
+                        .delete_notice
+                        .set(Some(DeleteNotice::RestoreFailed(err.message))),
+                }
+            },
+        );
+        self.common
+            .proxy
+            .restore_trashed_paths(paths, move |result| send(result));
+    }
+
+    pub fn dismiss_delete_notice(&self) {
+        self.delete_notice.set(None);
+    }
+
+    /// Add the path to the notice of the paths trashed, or deleted for good,
+    /// which starts over when the last notice is of something else
+    fn notice_deleted(
+        &self,
+        path: PathBuf,
+        permanent: bool,
+    ) -> impl FnOnce() + 'static {
+        let delete_notice = self.delete_notice;
+        move || {
+            delete_notice.update(|notice| match notice {
+                Some(DeleteNotice::Trashed(paths)) if !permanent => paths.push(path),
+                Some(DeleteNotice::Deleted(paths)) if permanent => paths.push(path),
+                _ if permanent => *notice = Some(DeleteNotice::Deleted(vec![path])),
+                _ => *notice = Some(DeleteNotice::Trashed(vec![path])),
+            });
+        }
+    }
+
+    pub fn cut_selected(&self) {
+        self.clipboard.set(Some(FileClipboard {
+            paths: self.selected_roots(),
+            cut: true,
+        }));
+    }
+
+    pub fn copy_selected(&self) {
+        self.clipboard.set(Some(FileClipboard {
+            paths: self.selected_roots(),
+            cut: false,
+        }));
+    }
+
+    /// Paste the paths cut or copied into the directory. Cut paths are moved
+    /// there and only pasted once, copies get a new name if theirs is taken.
+    pub fn paste(&self, dir: PathBuf) {
+        let Some(clipboard) = self.clipboard.get_untracked() else {
+            return;
+        };
+        if clipboard.cut {
+            self.clipboard.set(None);
+            self.move_paths(clipboard.paths, dir);
+            return;
+        }
+        if in_archive(&dir) {
+            return;
+        }
+        for path in clipboard.paths {
+            // A folder can't be copied into itself
+            if dir.starts_with(&path) {
+                continue;
+            }
+            let Some(new_path) =
+                free_copy_path(&path, &dir, |path| self.node(path).is_some())
+            else {
+                continue;
+            };
+            self.common.proxy.duplicate_path(
+                path,
+                new_path,
+                self.on_done(vec![dir.clone()], || {}),
+            );
+        }
+    }
+
+    /// Move the paths into the directory, as dragging them onto it does
+    pub fn move_paths(&self, paths: Vec<PathBuf>, dir: PathBuf) {
+        if in_archive(&dir) {
+            return;
+        }
+        for path in paths {
+            let (Some(name), Some(parent)) = (path.file_name(), path.parent())
+            else {
+                continue;
+            };
+            if parent == dir
+                || dir.starts_with(&path)
+                || archive_member(&path).is_some()
+            {
+                continue;
+            }
+            let to = dir.join(name);
+            let dirs = vec![parent.to_path_buf(), dir.clone()];
+            self.common
+                .proxy
+                .rename_path(path, to, self.on_done(dirs, || {}));
+        }
+    }
+
+    /// Reload the directories that the changes made outside of the editor
+    /// touched, and keep the selection on the paths that were renamed
+    pub fn files_changed(&self, changes: &[FileChange]) {
+        let dirs: BTreeSet<&Path> = changes
+            .iter()
+            .flat_map(|change| change.paths())
+            .filter_map(|path| path.parent())
+            .collect();
+        for dir in dirs {
+            if let Some(node) = self.node(dir) {
+                if node.read.get_untracked() {
+                    node.reload(&self.common.proxy);
+                }
+            }
+        }
+
+        self.selected.update(|selected| {
+            for change in changes {
+                match change {
+                    FileChange::Created(_) | FileChange::Modified(_) => {}
+                    FileChange::Removed(path) => {
+                        selected.retain(|p| !p.starts_with(path));
+                    }
+                    FileChange::Renamed { from, to } => {
+                        for p in selected.iter_mut() {
+                            if let Ok(rest) = p.strip_prefix(from) {
+                                *p = to.join(rest);
+                            }
+                        }
+                    }
+                }
+            }
+        });
+    }
+
+    fn node(&self, path: &Path) -> Option<FileNode> {
+        self.all_files
+            .with_untracked(|all_files| all_files.get(path).cloned())
+    }
+
+    /// The callback of an operation of the proxy on the files, which reloads
+    /// the directories it changed and then runs `done` if it succeeded
+    fn on_done(
+        &self,
+        dirs: Vec<PathBuf>,
+        done: impl FnOnce() + 'static,
+    ) -> impl FnOnce(Result<ProxyResponse, RpcError>) + Send {
+        let explorer = self.clone();
+        let send = create_ext_action(
+            self.common.scope,
+            move |result: Result<ProxyResponse, RpcError>| {
+                for dir in dirs {
+                    if let Some(node) = explorer.node(&dir) {
+                        if node.read.get_untracked() {
+                            node.reload(&explorer.common.proxy);
+                        }
+                    }
+                }
+                match result {
+                    Ok(_) => done(),
+                    Err(err) => error!("{}", err.message),
+                }
+            },
+        );
+        move |result| send(result)
+    }
+}
+
+/// Whether the path is an archive or a folder in one, which nothing can be
+/// created in
+fn in_archive(path: &Path) -> bool {
+    is_archive(path) || archive_member(path).is_some()
+}
+
+/// A path in the directory for a copy of the path, with " copy" and then a
+/// number added to its name until it's not taken by what the explorer knows
+/// of. Anything it doesn't know of is left to the proxy to refuse.
+fn free_copy_path(
+    path: &Path,
+    dir: &Path,
+    taken: impl Fn(&Path) -> bool,
+) -> Option<PathBuf> {
+    let name = path.file_name()?;
+    let new_path = dir.join(name);
+    if !taken(&new_path) {
+        return Some(new_path);
+    }
+    let stem = path.file_stem()?.to_string_lossy().to_string();
+    let extension = path
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default();
+    (1..)
+        .map(|i| {
+            let suffix = if i == 1 {
+                " copy".to_string()
+            } else {
+                format!(" copy {i}")
+            };
+            dir.join(format!("{stem}{suffix}{extension}"))
+        })
+        .find(|path| !taken(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn test_free_copy_path() {
+        let dir = Path::new("/project/src");
+        let taken = ["/project/src/main.rs", "/project/src/main copy.rs"]
+            .into_iter()
+            .map(PathBuf::from)
+            .collect::<HashSet<_>>();
+        let taken = |path: &Path| taken.contains(path);
+
+        // Pasted into another directory, the name is kept
+        assert_eq!(
+            free_copy_path(Path::new("/other/main.rs"), dir, |_| false),
+            Some(PathBuf::from("/project/src/main.rs"))
+        );
+        // and numbered after " copy" when the names are taken
+        assert_eq!(
+            free_copy_path(Path::new("/project/src/main.rs"), dir, taken),
+            Some(PathBuf::from("/project/src/main copy 2.rs"))
+        );
+        assert_eq!(
+            free_copy_path(Path::new("/project/src/lib.rs"), dir, taken),
+            Some(PathBuf::from("/project/src/lib.rs"))
+        );
+        // Folders and files without an extension get the suffix at the end
+        assert_eq!(
+            free_copy_path(Path::new("/project/src"), Path::new("/project"), |p| {
+                p == Path::new("/project/src")
+            }),
+            Some(PathBuf::from("/project/src copy"))
+        );
     }
 }
@@ -9,7 +9,11 @@
     views::VirtualListVector,
 };
 use indexmap::IndexMap;
-use lapce_rpc::proxy::{ProxyResponse, ProxyRpcHandler};
+use lapce_rpc::{
+    file::is_archive,
+    protocol::Capability,
+    proxy::{ProxyResponse, ProxyRpcHandler},
+};
 
 use crate::{command::InternalCommand, listener::Listener};
 
@@ -18,12 +22,17 @@ pub struct FileNode {
     pub scope: Scope,
     pub path: PathBuf,
     pub is_dir: bool,
+    /// Whether the ignore files of the workspace, such as `.gitignore`, match
+    /// the path
+    pub is_ignored: bool,
     pub read: RwSignal<bool>,
     pub expanded: RwSignal<bool>,
     pub children: RwSignal<IndexMap<PathBuf, FileNode>>,
     pub children_open_count: RwSignal<usize>,
     pub all_files: RwSignal<im::HashMap<PathBuf, FileNode>>,
     pub line_height: Memo<f64>,
+    /// Whether the entries that are ignored are listed
+    pub show_ignored: Memo<bool>,
     pub internal_command: Listener<InternalCommand>,
 }
 
@@ -115,32 +124,60 @@ fn read_dir(&self, proxy: &ProxyRpcHandler) {
         if self.read.get_untracked() {
             return;
         }
+        self.reload(proxy);
+    }
+
+    /// Read the entries of the directory again, keeping the nodes of the ones
+    /// that are still there with what of them is expanded
+    pub fn reload(&self, proxy: &ProxyRpcHandler) {
+        if !self.is_dir {
+            return;
+        }
         self.read.set(true);
         let cx = self.scope;
         let file_node = self.clone();
+        // Archives are browsed like folders, if the proxy can read them
+        let archives = proxy.supports(Capability::Archives);
         let send = create_ext_action(self.scope, move |result| {
             if let Ok(ProxyResponse::ReadDirResponse { items }) = result {
+                let existing = file_node.children.get_untracked();
+                let show_ignored = file_node.show_ignored.get_untracked();
                 let items = items
                     .into_iter()
+                    .filter(|item| show_ignored || !item.is_ignored)
                     .map(|item| {
-                        (
-                            item.path_buf.clone(),
-                            FileNode {
+                        let is_dir =
+                            item.is_dir || (archives && is_archive(&item.path_buf));
+                        let node = existing
+                            .get(&item.path_buf)
+                            .filter(|node| {
+                                node.is_dir == is_dir
+                                    && node.is_ignored == item.is_ignored
+                            })
+                            .cloned()
+                            .unwrap_or_else(|| FileNode {
                                 scope: cx,
-                                path: item.path_buf,
-                                is_dir: item.is_dir,
+                                path: item.path_buf.clone(),
+                                is_dir,
+                                is_ignored: item.is_ignored,
                                 read: create_rw_signal(cx, false),
                                 expanded: create_rw_signal(cx, false),
                                 children: create_rw_signal(cx, IndexMap::new()),
                                 children_open_count: create_rw_signal(cx, 0),
                                 all_files: file_node.all_files,
                                 line_height: file_node.line_height,
+                                show_ignored: file_node.show_ignored,
                                 internal_command: file_node.internal_command,
-                            },
-                        )
+                            });
+                        (item.path_buf, node)
                     })
                     .collect::<IndexMap<PathBuf, FileNode>>();
                 file_node.all_files.update(|all_files| {
+                    for path in existing.keys() {
+                        if !items.contains_key(path) {
+                            all_files.retain(|p, _| !p.starts_with(path));
+                        }
+                    }
                     for (_, item) in items.iter() {
                         all_files.insert(item.path.clone(), item.clone());
                     }
@@ -1,31 +1,47 @@
-use std::{path::PathBuf, sync::Arc};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 use floem::{
-    peniko::Color,
-    reactive::{ReadSignal, SignalGet},
+    event::{Event, EventListener},
+    id::Id,
+    menu::{Menu, MenuItem},
+    peniko::{
+        kurbo::{Point, Rect},
+        Color,
+    },
+    reactive::{create_rw_signal, ReadSignal, SignalGet, SignalSet, SignalWith},
     style::{CursorStyle, Style},
     view::View,
     views::{
         container, container_box, label, scroll, stack, svg, virtual_list,
         Decorators, VirtualListDirection, VirtualListItemSize, VirtualListVector,
     },
+    ViewContext,
 };
-use lapce_rpc::proxy::ProxyRpcHandler;
 
-use super::node::FileNode;
+use super::{
+    data::{FileExplorerData, Naming},
+    node::FileNode,
+};
 use crate::{
+    command::InternalCommand,
     config::{color::LapceColor, icon::LapceIcons, LapceConfig},
-    panel::{position::PanelPosition, view::panel_header},
-    window_tab::WindowTabData,
+    listener::Listener,
+    panel::{kind::PanelKind, position::PanelPosition, view::panel_header},
+    text_input::text_input,
+    window_tab::{DragContent, Focus, WindowTabData},
 };
 
 pub fn file_explorer_panel(
     window_tab_data: Arc<WindowTabData>,
     position: PanelPosition,
 ) -> impl View {
+    let cx = ViewContext::get_current();
     let config = window_tab_data.common.config;
-    let root_file_node = window_tab_data.file_explorer.root.clone();
-    let proxy = window_tab_data.common.proxy.clone();
+    let explorer = window_tab_data.file_explorer.clone();
+    let root_file_node = explorer.root.clone();
     stack(|| {
         (
             stack(move || (panel_header("Open Editors".to_string(), config),))
@@ -34,19 +50,74 @@ pub fn file_explorer_panel(
                 (
                     panel_header("File Explorer".to_string(), config),
                     container(|| {
-                        scroll(move || {
-                            file_node_view(
-                                root_file_node.clone(),
-                                proxy.clone(),
-                                0,
-                                config,
-                            )
+                        scroll({
+                            let explorer = explorer.clone();
+                            move || {
+                                let root_path = root_file_node.path.clone();
+                                stack(|| {
+                                    (
+                                        new_name_input(
+                                            explorer.clone(),
+                                            root_path,
+                                            0,
+                                            config,
+                                        ),
+                                        file_node_view(
+                                            root_file_node.clone(),
+                                            explorer.clone(),
+                                            0,
+                                            config,
+                                        ),
+                                    )
+                                })
+                                .style(|| {
+                                    Style::BASE.flex_col().min_width_pct(100.0)
+                                })
+                            }
                         })
                         .scroll_bar_color(move || {
                             *config.get().get_color(LapceColor::LAPCE_SCROLL_BAR)
                         })
+                        .on_ensure_visible({
+                            let explorer = explorer.clone();
+                            move || {
+                                let line_height =
+                                    explorer.common.ui_line_height.get();
+                                let index = explorer.revealed.with(|path| {
+                                    path.as_ref().and_then(|path| {
+                                        explorer.visible_index(path)
+                                    })
+                                });
+                                let y = index.unwrap_or(0) as f64 * line_height;
+                                Rect::new(0.0, y, 0.0, y + line_height)
+                            }
+                        })
                         .style(|| Style::BASE.absolute().size_pct(100.0, 100.0))
                     })
+                    .on_event(EventListener::PointerDown, {
+                        let explorer = explorer.clone();
+                        move |event| {
+                            if let Event::PointerDown(pointer_event) = event {
+                                if pointer_event.button.is_right() {
+                                    explorer.selected.set(Vec::new());
+                                    show_context_menu(&explorer, cx.id);
+                                }
+                            }
+                            explorer
+                                .common
+                                .focus
+                                .set(Focus::Panel(PanelKind::FileExplorer));
+                            false
+                        }
+                    })
+                    .on_event(EventListener::Drop, {
+                        let explorer = explorer.clone();
+                        move |_| {
+                            // Dropped below the tree, into the workspace
+                            let root = explorer.root.path.clone();
+                            drop_paths(&explorer, root)
+                        }
+                    })
                     .style(|| Style::BASE.size_pct(100.0, 100.0).line_height(1.6)),
                 )
             })
@@ -62,129 +133,43 @@ pub fn file_explorer_panel(
 
 fn file_node_view(
     file_node: FileNode,
-    proxy: ProxyRpcHandler,
+    explorer: FileExplorerData,
     level: usize,
     config: ReadSignal<Arc<LapceConfig>>,
 ) -> impl View {
+    let naming = explorer.naming;
+    let line_height = explorer.common.ui_line_height;
     virtual_list(
         VirtualListDirection::Vertical,
-        VirtualListItemSize::Fn(Box::new(|(_, file_node): &(PathBuf, FileNode)| {
-            file_node.total_size().unwrap_or(0.0)
-        })),
+        VirtualListItemSize::Fn(Box::new(
+            move |(path, file_node): &(PathBuf, FileNode)| {
+                // The input of a name for a new file in the directory takes a
+                // line under it
+                let new_in = naming.with(|naming| {
+                    naming.as_ref().and_then(Naming::new_in) == Some(path.as_path())
+                });
+                let extra = if new_in { line_height.get() } else { 0.0 };
+                file_node.total_size().unwrap_or(0.0) + extra
+            },
+        )),
         move || file_node.clone(),
         |(path, _)| path.to_owned(),
         move |(path, file_node)| {
-            let proxy = proxy.clone();
+            let explorer = explorer.clone();
             stack(move || {
                 (
-                    {
-                        let file_node = file_node.clone();
-                        let proxy = proxy.clone();
-                        let expanded = file_node.expanded;
-                        let is_dir = file_node.is_dir;
-                        stack(|| {
-                            (
-                                svg(move || {
-                                    let config = config.get();
-                                    let expanded = expanded.get();
-                                    let svg_str = match expanded {
-                                        true => LapceIcons::ITEM_OPENED,
-                                        false => LapceIcons::ITEM_CLOSED,
-                                    };
-                                    config.ui_svg(svg_str)
-                                })
-                                .style(move || {
-                                    let config = config.get();
-                                    let size = config.ui.icon_size() as f32;
-
-                                    let color = if is_dir {
-                                        *config
-                                            .get_color(LapceColor::LAPCE_ICON_ACTIVE)
-                                    } else {
-                                        Color::TRANSPARENT
-                                    };
-                                    Style::BASE
-                                        .size_px(size, size)
-                                        .margin_left_px(10.0)
-                                        .color(color)
-                                }),
-                                {
-                                    let path = path.clone();
-                                    let path_for_style = path.clone();
-                                    svg(move || {
-                                        let config = config.get();
-                                        if is_dir {
-                                            let expanded = expanded.get();
-                                            let svg_str = match expanded {
-                                                true => LapceIcons::DIRECTORY_OPENED,
-                                                false => {
-                                                    LapceIcons::DIRECTORY_CLOSED
-                                                }
-                                            };
-                                            config.ui_svg(svg_str)
-                                        } else {
-                                            config.file_svg(&path).0
-                                        }
-                                    })
-                                    .style(
-                                        move || {
-                                            let config = config.get();
-                                            let size = config.ui.icon_size() as f32;
-
-                                            Style::BASE
-                                                .size_px(size, size)
-                                                .margin_horiz_px(6.0)
-                                                .apply_if(is_dir, |s| {
-                                                    s.color(*config.get_color(
-                                                    LapceColor::LAPCE_ICON_ACTIVE,
-                                                ))
-                                                })
-                                                .apply_if(!is_dir, |s| {
-                                                    s.apply_opt(
-                                                        config
-                                                            .file_svg(
-                                                                &path_for_style,
-                                                            )
-                                                            .1
-                                                            .cloned(),
-                                                        Style::color,
-                                                    )
-                                                })
-                                        },
-                                    )
-                                },
-                                label(move || {
-                                    path.file_name()
-                                        .map(|f| f.to_string_lossy().to_string())
-                                        .unwrap_or_default()
-                                }),
-                            )
-                        })
-                        .on_click(move |_| {
-                            file_node.click(&proxy);
-                            true
-                        })
-                        .style(move || {
-                            Style::BASE
-                                .items_center()
-                                .padding_right_px(10.0)
-                                .padding_left_px((level * 10) as f32)
-                                .min_width_pct(100.0)
-                        })
-                        .hover_style(move || {
-                            Style::BASE
-                                .background(
-                                    *config.get().get_color(
-                                        LapceColor::PANEL_HOVERED_BACKGROUND,
-                                    ),
-                                )
-                                .cursor(CursorStyle::Pointer)
-                        })
-                    },
+                    file_node_row(
+                        path.clone(),
+                        file_node.clone(),
+                        explorer.clone(),
+                        level,
+                        config,
+                    ),
+                    new_name_input(explorer.clone(), path, level + 1, config),
                     container_box(move || {
                         Box::new(file_node_view(
                             file_node,
-                            proxy.clone(),
+                            explorer,
                             level + 1,
                             config,
                         ))
@@ -196,3 +181,384 @@ fn file_node_view(
     )
     .style(|| Style::BASE.flex_col().min_width_pct(100.0))
 }
+
+fn file_node_row(
+    path: PathBuf,
+    file_node: FileNode,
+    explorer: FileExplorerData,
+    level: usize,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    let cx = ViewContext::get_current();
+    let expanded = file_node.expanded;
+    let is_dir = file_node.is_dir;
+    let is_ignored = file_node.is_ignored;
+    let selected = explorer.selected;
+    let clipboard = explorer.clipboard;
+    let naming = explorer.naming;
+    let dragging = explorer.common.dragging;
+    let dragging_over = create_rw_signal(cx.scope, false);
+    let is_renaming = {
+        let path = path.clone();
+        move || {
+            naming.with(|naming| {
+                naming.as_ref() == Some(&Naming::Rename(path.clone()))
+            })
+        }
+    };
+    let is_selected = {
+        let path = path.clone();
+        move || selected.with(|selected| selected.contains(&path))
+    };
+    let is_cut = {
+        let path = path.clone();
+        move || {
+            clipboard.with(|clipboard| {
+                clipboard
+                    .as_ref()
+                    .map(|c| c.cut && c.paths.contains(&path))
+                    .unwrap_or(false)
+            })
+        }
+    };
+
+    stack(|| {
+        (
+            svg(move || {
+                let config = config.get();
+                let expanded = expanded.get();
+                let svg_str = match expanded {
+                    true => LapceIcons::ITEM_OPENED,
+                    false => LapceIcons::ITEM_CLOSED,
+                };
+                config.ui_svg(svg_str)
+            })
+            .style(move || {
+                let config = config.get();
+                let size = config.ui.icon_size() as f32;
+
+                let color = if is_dir {
+                    *config.get_color(LapceColor::LAPCE_ICON_ACTIVE)
+                } else {
+                    Color::TRANSPARENT
+                };
+                Style::BASE
+                    .size_px(size, size)
+                    .margin_left_px(10.0)
+                    .color(color)
+            }),
+            {
+                let path = path.clone();
+                let path_for_style = path.clone();
+                svg(move || {
+                    let config = config.get();
+                    if is_dir {
+                        let expanded = expanded.get();
+                        let svg_str = match expanded {
+                            true => LapceIcons::DIRECTORY_OPENED,
+                            false => LapceIcons::DIRECTORY_CLOSED,
+                        };
+                        config.ui_svg(svg_str)
+                    } else {
+                        config.file_svg(&path).0
+                    }
+                })
+                .style(move || {
+                    let config = config.get();
+                    let size = config.ui.icon_size() as f32;
+
+                    Style::BASE
+                        .size_px(size, size)
+                        .margin_horiz_px(6.0)
+                        .apply_if(is_dir, |s| {
+                            s.color(*config.get_color(LapceColor::LAPCE_ICON_ACTIVE))
+                        })
+                        .apply_if(!is_dir, |s| {
+                            s.apply_opt(
+                                config.file_svg(&path_for_style).1.cloned(),
+                                Style::color,
+                            )
+                        })
+                })
+            },
+            {
+                let path = path.clone();
+                let is_renaming = is_renaming.clone();
+                label(move || {
+                    path.file_name()
+                        .map(|f| f.to_string_lossy().to_string())
+                        .unwrap_or_default()
+                })
+                .style(move || {
+                    let config = config.get();
+                    Style::BASE
+                        .apply_if(is_cut() || is_ignored, |s| {
+                            s.color(
+                                *config.get_color(LapceColor::PANEL_FOREGROUND_DIM),
+                            )
+                        })
+                        .apply_if(is_renaming(), |s| s.hide())
+                })
+            },
+            name_input(explorer.clone(), is_renaming, config),
+        )
+    })
+    .on_click({
+        let explorer = explorer.clone();
+        let file_node = file_node.clone();
+        move |event| {
+            if let Event::PointerUp(pointer_event) = event {
+                explorer.click(&file_node, pointer_event.modifiers);
+            }
+            true
+        }
+    })
+    .on_event(EventListener::PointerDown, {
+        let explorer = explorer.clone();
+        let path = path.clone();
+        move |event| {
+            if let Event::PointerDown(pointer_event) = event {
+                if pointer_event.button.is_right() {
+                    if !explorer.selected.with_untracked(|s| s.contains(&path)) {
+                        explorer.selected.set(vec![path.clone()]);
+                    }
+                    explorer
+                        .common
+                        .focus
+                        .set(Focus::Panel(PanelKind::FileExplorer));
+                    show_context_menu(&explorer, cx.id);
+                    return true;
+                }
+            }
+            false
+        }
+    })
+    .draggable()
+    .on_event(EventListener::DragStart, {
+        let explorer = explorer.clone();
+        let path = path.clone();
+        move |_| {
+            // The selection goes along if the node is in it
+            let paths = explorer.selected.with_untracked(|selected| {
+                if selected.contains(&path) {
+                    selected.clone()
+                } else {
+                    vec![path.clone()]
+                }
+            });
+            dragging.set(Some(DragContent::FileExplorer(paths)));
+            true
+        }
+    })
+    .on_event(EventListener::DragEnd, move |_| {
+        dragging.set(None);
+        true
+    })
+    .on_event(EventListener::DragEnter, move |_| {
+        if dragging
+            .with_untracked(|d| matches!(d, Some(DragContent::FileExplorer(_))))
+        {
+            dragging_over.set(true);
+        }
+        false
+    })
+    .on_event(EventListener::DragLeave, move |_| {
+        dragging_over.set(false);
+        false
+    })
+    .on_event(EventListener::Drop, {
+        let explorer = explorer.clone();
+        move |_| {
+            dragging_over.set(false);
+            let dir = if is_dir {
+                path.clone()
+            } else {
+                match path.parent() {
+                    Some(dir) => dir.to_path_buf(),
+                    None => return false,
+                }
+            };
+            drop_paths(&explorer, dir)
+        }
+    })
+    .style(move || {
+        let config = config.get();
+        Style::BASE
+            .items_center()
+            .padding_right_px(10.0)
+            .padding_left_px((level * 10) as f32)
+            .min_width_pct(100.0)
+            .apply_if(is_selected() || dragging_over.get(), |s| {
+                s.background(*config.get_color(LapceColor::PANEL_CURRENT_BACKGROUND))
+            })
+    })
+    .hover_style(move || {
+        Style::BASE
+            .background(
+                *config.get().get_color(LapceColor::PANEL_HOVERED_BACKGROUND),
+            )
+            .cursor(CursorStyle::Pointer)
+    })
+}
+
+/// The input of the name being typed, hidden unless `is_shown`
+fn name_input(
+    explorer: FileExplorerData,
+    is_shown: impl Fn() -> bool + 'static,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    let focus = explorer.common.focus;
+    let is_focused = move || focus.get() == Focus::Panel(PanelKind::FileExplorer);
+    text_input(explorer.naming_editor, is_focused).style(move || {
+        let config = config.get();
+        Style::BASE
+            .flex_grow(1.0)
+            .padding_vert_px(0.0)
+            .padding_horiz_px(2.0)
+            .border(1.0)
+            .border_color(*config.get_color(LapceColor::EDITOR_FOCUS))
+            .apply_if(!is_shown(), |s| s.hide())
+    })
+}
+
+/// The line to type the name of a new file or folder in the directory on,
+/// under the directory
+fn new_name_input(
+    explorer: FileExplorerData,
+    dir: PathBuf,
+    level: usize,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    let naming = explorer.naming;
+    let is_shown = move || {
+        naming.with(|naming| {
+            naming.as_ref().and_then(Naming::new_in) == Some(dir.as_path())
+        })
+    };
+    let is_shown_for_style = is_shown.clone();
+    container(|| name_input(explorer, is_shown, config)).style(move || {
+        let config = config.get();
+        let size = config.ui.icon_size() as f32;
+        // Lined up with the names of the nodes in the directory
+        Style::BASE
+            .items_center()
+            .padding_right_px(10.0)
+            .padding_left_px((level * 10) as f32 + size * 2.0 + 22.0)
+            .min_width_pct(100.0)
+            .apply_if(!is_shown_for_style(), |s| s.hide())
+    })
+}
+
+/// Move the paths dragged in the explorer into the directory, returning
+/// whether there were any
+fn drop_paths(explorer: &FileExplorerData, dir: PathBuf) -> bool {
+    let dragging = explorer.common.dragging;
+    let Some(DragContent::FileExplorer(paths)) = dragging.get_untracked() else {
+        return false;
+    };
+    dragging.set(None);
+    explorer.move_paths(paths, dir);
+    true
+}
+
+/// Show the operations on the selected files and folders in a menu, or the
+/// ones on the workspace when nothing is selected
+fn show_context_menu(explorer: &FileExplorerData, id: Id) {
+    let has_selection = explorer.selected.with_untracked(|s| !s.is_empty());
+    let can_paste = explorer.clipboard.with_untracked(|c| c.is_some());
+    let action = |f: fn(&FileExplorerData)| {
+        let explorer = explorer.clone();
+        move || f(&explorer)
+    };
+
+    let menu = Menu::new("")
+        .entry(MenuItem::new("New File").action(action(|explorer| {
+            explorer.start_naming(Naming::NewFile(explorer.target_dir()))
+        })))
+        .entry(MenuItem::new("New Folder").action(action(|explorer| {
+            explorer.start_naming(Naming::NewFolder(explorer.target_dir()))
+        })))
+        .separator()
+        .entry(
+            MenuItem::new("Cut")
+                .enabled(has_selection)
+                .action(action(FileExplorerData::cut_selected)),
+        )
+        .entry(
+            MenuItem::new("Copy")
+                .enabled(has_selection)
+                .action(action(FileExplorerData::copy_selected)),
+        )
+        .entry(
+            MenuItem::new("Paste")
+                .enabled(can_paste)
+                .action(action(|explorer| explorer.paste(explorer.target_dir()))),
+        )
+        .separator()
+        .entry(
+            MenuItem::new("Rename")
+                .enabled(has_selection)
+                .action(action(|explorer| {
+                    if let Some(path) = explorer.selected.get_untracked().last() {
+                        explorer.start_naming(Naming::Rename(path.clone()));
+                    }
+                })),
+        )
+        .entry(
+            MenuItem::new("Delete")
+                .enabled(has_selection)
+                .action(action(FileExplorerData::delete_selected)),
+        )
+        .entry(
+            MenuItem::new("Delete Permanently")
+                .enabled(has_selection)
+                .action(action(FileExplorerData::delete_selected_permanently)),
+        );
+    let menu = match explorer.selected.get_untracked().last() {
+        Some(path) => path_menu_entries(
+            menu.separator(),
+            path,
+            explorer.common.internal_command,
+        ),
+        None => menu,
+    };
+    id.show_context_menu(menu, Point::ZERO);
+}
+
+/// The entries of the context menus of a file or folder, in the explorer and
+/// on editor tabs, to copy its path or open it outside of the editor
+pub fn path_menu_entries(
+    menu: Menu,
+    path: &Path,
+    internal_command: Listener<InternalCommand>,
+) -> Menu {
+    let send = |command: fn(PathBuf) -> InternalCommand| {
+        let path = path.to_path_buf();
+        move || internal_command.send(command(path.clone()))
+    };
+    menu.entry(MenuItem::new("Copy Path").action(send(|path| {
+        InternalCommand::CopyPath {
+            path,
+            relative: false,
+        }
+    })))
+    .entry(MenuItem::new("Copy Relative Path").action(send(|path| {
+        InternalCommand::CopyPath {
+            path,
+            relative: true,
+        }
+    })))
+    .entry(
+        MenuItem::new("Copy Name")
+            .action(send(|path| InternalCommand::CopyFileName { path })),
+    )
+    .separator()
+    .entry(
+        MenuItem::new("Reveal in System File Manager")
+            .action(send(|path| InternalCommand::RevealInFileManager { path })),
+    )
+    .entry(
+        MenuItem::new("Open in New Terminal")
+            .action(send(|path| InternalCommand::OpenInTerminal { path })),
+    )
+}
@@ -1,4 +1,7 @@
-use std::cmp::{max, min};
+use std::{
+    cmp::{max, min},
+    sync::atomic::{AtomicU64, Ordering},
+};
 
 use floem::reactive::{
     create_effect, create_rw_signal, RwSignal, Scope, SignalGet, SignalGetUntracked,
@@ -17,17 +20,27 @@
 
 const REGEX_SIZE_LIMIT: usize = 1000000;
 
+static SEARCH_ID: AtomicU64 = AtomicU64::new(0);
+
+/// The id of a search of the whole text, which tells its matches apart from
+/// the ones of the searches before it
+pub fn next_search_id() -> u64 {
+    SEARCH_ID.fetch_add(1, Ordering::Relaxed) + 1
+}
+
 /// Indicates what changed in the find state.
 #[derive(PartialEq, Debug, Clone)]
 pub enum FindProgress {
-    /// Incremental find is done/not running.
+    /// The whole text has been searched, and the matches are kept up to date
+    /// with edits.
     Ready,
 
+    /// The whole text is being searched in the background by the search with
+    /// the id, while the matches of the lines shown are already known.
+    Searching(u64),
+
     /// The find process just started.
     Started,
-
-    /// Incremental find is in progress. Keeps tracked of already searched range.
-    InProgress(Selection),
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -76,6 +89,9 @@ pub struct Find {
     pub replace_active: RwSignal<bool>,
     /// replace editor is focused
     pub replace_focus: RwSignal<bool>,
+    /// The text to replace the matches with, which can refer to the capture
+    /// groups of a regex with `$1` or `${name}`
+    pub replace_string: RwSignal<String>,
 }
 
 impl Find {
@@ -88,6 +104,7 @@ pub fn new(cx: Scope) -> Self {
             is_regex: create_rw_signal(cx, false),
             replace_active: create_rw_signal(cx, false),
             replace_focus: create_rw_signal(cx, false),
+            replace_string: create_rw_signal(cx, String::new()),
         };
 
         {
@@ -300,6 +317,48 @@ pub fn next(
         )
     }
 
+    /// The match that starts at `offset`, if there is one.
+    pub fn match_at(&self, text: &Rope, offset: usize) -> Option<(usize, usize)> {
+        let line = text.line_of_offset(offset);
+        let line_end = text.offset_of_line(line + 1);
+        let mut occurrences = Selection::new();
+        self.update_find(text, offset, line_end, false, &mut occurrences);
+        occurrences
+            .regions()
+            .iter()
+            .find(|region| region.min() == offset)
+            .map(|region| (region.min(), region.max()))
+    }
+
+    /// All the matches in the text, without overlapping ones.
+    pub fn all_matches(&self, text: &Rope) -> Selection {
+        let mut occurrences = Selection::new();
+        self.update_find(text, 0, text.len(), false, &mut occurrences);
+        occurrences
+    }
+
+    /// The text to replace the match at `start..end` with. For a regex search
+    /// the capture groups are expanded, which only needs the text of the match
+    /// rather than of the whole buffer.
+    pub fn replacement(&self, text: &Rope, start: usize, end: usize) -> String {
+        let replace_string = self.replace_string.get_untracked();
+        self.search_string.with_untracked(|search| {
+            let regex = search.as_ref().and_then(|search| search.regex.as_ref());
+            let Some(regex) = regex else {
+                return replace_string;
+            };
+            let matched = text.slice_to_cow(start..end);
+            match regex.captures(&matched) {
+                Some(captures) => {
+                    let mut replacement = String::new();
+                    captures.expand(&replace_string, &mut replacement);
+                    replacement
+                }
+                None => replace_string,
+            }
+        })
+    }
+
     /// Checks if the start and end of a match is matching whole words.
     fn is_matching_whole_words(
         &self,
@@ -307,18 +366,7 @@ fn is_matching_whole_words(
         start: usize,
         end: usize,
     ) -> bool {
-        let mut word_end_cursor = WordCursor::new(text, end - 1);
-        let mut word_start_cursor = WordCursor::new(text, start + 1);
-
-        if word_start_cursor.prev_code_boundary() != start {
-            return false;
-        }
-
-        if word_end_cursor.next_code_boundary() != end {
-            return false;
-        }
-
-        true
+        is_matching_whole_words(text, start, end)
     }
 
     /// Returns `true` if the search query is a multi-line regex.
@@ -332,6 +380,16 @@ pub(crate) fn is_multiline_regex(&self) -> bool {
         })
     }
 
+    /// The settings of the current search, if there is one
+    pub fn query(&self) -> Option<FindQuery> {
+        let search = self.search_string.get_untracked()?;
+        Some(FindQuery {
+            search,
+            case_matching: self.case_matching.get_untracked(),
+            whole_words: self.whole_words.get_untracked(),
+        })
+    }
+
     /// Execute the search on the provided text in the range provided by `start` and `end`.
     pub fn update_find(
         &self,
@@ -341,11 +399,32 @@ pub fn update_find(
         include_slop: bool,
         occurrences: &mut Selection,
     ) {
-        if self.search_string.with_untracked(|search| search.is_none()) {
-            return;
+        if let Some(query) = self.query() {
+            query.update_find(text, start, end, include_slop, occurrences);
         }
+    }
+}
+
+/// The settings of a search, taken out of the signals of [`Find`] so that it
+/// can run on another thread
+#[derive(Clone)]
+pub struct FindQuery {
+    pub search: FindSearchString,
+    pub case_matching: CaseMatching,
+    pub whole_words: bool,
+}
 
-        let search = self.search_string.get_untracked().unwrap();
+impl FindQuery {
+    /// Execute the search on the provided text in the range provided by `start` and `end`.
+    pub fn update_find(
+        &self,
+        text: &Rope,
+        start: usize,
+        end: usize,
+        include_slop: bool,
+        occurrences: &mut Selection,
+    ) {
+        let search = &self.search;
         let search_string = &search.content;
         // extend the search by twice the string length (twice, because case matching may increase
         // the length of an occurrence)
@@ -372,8 +451,8 @@ pub fn update_find(
 
         let mut raw_lines = text.lines_raw(from..to);
 
-        let case_matching = self.case_matching.get_untracked();
-        let whole_words = self.whole_words.get_untracked();
+        let case_matching = self.case_matching;
+        let whole_words = self.whole_words;
         while let Some(start) = find(
             &mut find_cursor,
             &mut raw_lines,
@@ -383,7 +462,7 @@ pub fn update_find(
         ) {
             let end = find_cursor.pos();
 
-            if whole_words && !self.is_matching_whole_words(text, start, end) {
+            if whole_words && !is_matching_whole_words(text, start, end) {
                 raw_lines = text.lines_raw(find_cursor.pos()..to);
                 continue;
             }
@@ -421,6 +500,22 @@ pub fn update_find(
     }
 }
 
+/// Checks if the start and end of a match is matching whole words.
+fn is_matching_whole_words(text: &Rope, start: usize, end: usize) -> bool {
+    let mut word_end_cursor = WordCursor::new(text, end - 1);
+    let mut word_start_cursor = WordCursor::new(text, start + 1);
+
+    if word_start_cursor.prev_code_boundary() != start {
+        return false;
+    }
+
+    if word_end_cursor.next_code_boundary() != end {
+        return false;
+    }
+
+    true
+}
+
 #[derive(Clone)]
 pub struct FindResult {
     pub progress: RwSignal<FindProgress>,
@@ -429,6 +524,8 @@ pub struct FindResult {
     pub case_matching: RwSignal<CaseMatching>,
     pub whole_words: RwSignal<bool>,
     pub is_regex: RwSignal<bool>,
+    /// The ranges the search is constrained to, when searching in selection
+    pub scope: RwSignal<Option<Selection>>,
 }
 
 impl FindResult {
@@ -440,6 +537,7 @@ pub fn new(cx: Scope) -> Self {
             case_matching: create_rw_signal(cx, CaseMatching::Exact),
             whole_words: create_rw_signal(cx, false),
             is_regex: create_rw_signal(cx, false),
+            scope: create_rw_signal(cx, None),
         }
     }
 
@@ -447,4 +545,84 @@ pub fn reset(&self) {
         self.progress.set(FindProgress::Started);
         self.occurrences.set(Selection::new());
     }
+
+    /// The 1-based index of the match at `offset`, if there is one, and the
+    /// total number of matches.
+    pub fn match_index(&self, offset: usize) -> (Option<usize>, usize) {
+        self.occurrences.with(|occurrences| {
+            let index = occurrences
+                .regions()
+                .iter()
+                .position(|region| region.min() <= offset && offset <= region.max())
+                .map(|index| index + 1);
+            (index, occurrences.len())
+        })
+    }
+
+    /// Whether the match at `start..end` is within the ranges the search is
+    /// constrained to, if it is.
+    pub fn in_scope(&self, start: usize, end: usize) -> bool {
+        self.scope.with_untracked(|scope| match scope {
+            Some(scope) => scope
+                .regions()
+                .iter()
+                .any(|region| region.min() <= start && end <= region.max()),
+            None => true,
+        })
+    }
+
+    /// The matches within the ranges the search is constrained to.
+    pub fn scoped(&self, occurrences: Selection) -> Selection {
+        if self.scope.with_untracked(|scope| scope.is_none()) {
+            return occurrences;
+        }
+        let mut scoped = Selection::new();
+        for region in occurrences.regions() {
+            if self.in_scope(region.min(), region.max()) {
+                scoped.add_region(*region);
+            }
+        }
+        scoped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use floem::reactive::{create_runtime, create_scope};
+
+    use super::*;
+
+    #[test]
+    fn test_replace_with_capture_groups() {
+        let runtime = create_runtime();
+        create_scope(runtime, |cx| {
+            let find = Find::new(cx);
+            let text = Rope::from("a=1 b=22\nc=3\n");
+            find.is_regex.set(true);
+            find.set_find(r"(\w+)=(?P<value>\d+)");
+            find.replace_string.set("${value}=$1".to_string());
+
+            let matches: Vec<(usize, usize)> = find
+                .all_matches(&text)
+                .regions()
+                .iter()
+                .map(|region| (region.min(), region.max()))
+                .collect();
+            assert_eq!(matches, vec![(0, 3), (4, 8), (9, 12)]);
+
+            // Only a match starting at the offset is replaced
+            assert_eq!(find.match_at(&text, 4), Some((4, 8)));
+            assert_eq!(find.match_at(&text, 5), None);
+
+            assert_eq!(find.replacement(&text, 4, 8), "22=b");
+            assert_eq!(find.replacement(&text, 9, 12), "3=c");
+
+            // Without a regex the replacement is taken as it is
+            find.is_regex.set(false);
+            find.set_find("b=22");
+            assert_eq!(find.replacement(&text, 4, 8), "${value}=$1");
+        })
+        .dispose();
+        runtime.dispose();
+    }
 }
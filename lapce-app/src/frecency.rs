@@ -0,0 +1,125 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+/// The number of the most recent visits of a file kept for weighting its visit
+/// count by their age
+const RECENT_VISITS: usize = 10;
+/// The number of files kept, dropping the least frecent ones first
+const MAX_FILES: usize = 1000;
+/// The weight of a visit younger than the number of days
+const AGE_WEIGHTS: &[(u64, f64)] = &[(4, 1.0), (14, 0.7), (31, 0.5), (90, 0.3)];
+/// The weight of a visit older than all of the above
+const OLD_WEIGHT: f64 = 0.1;
+
+const SECONDS_PER_DAY: u64 = 60 * 60 * 24;
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct FileVisits {
+    count: u32,
+    /// The time of the most recent visits in seconds since the unix epoch,
+    /// oldest first
+    recent: Vec<u64>,
+}
+
+impl FileVisits {
+    /// The visit count, weighted by the average age weight of the recent
+    /// visits.
+    fn frecency(&self, now: u64) -> f64 {
+        if self.recent.is_empty() {
+            return 0.0;
+        }
+        let weights: f64 = self
+            .recent
+            .iter()
+            .map(|time| visit_weight(now.saturating_sub(*time)))
+            .sum();
+        self.count as f64 * weights / self.recent.len() as f64
+    }
+}
+
+fn visit_weight(age: u64) -> f64 {
+    let days = age / SECONDS_PER_DAY;
+    AGE_WEIGHTS
+        .iter()
+        .find(|(max_days, _)| days < *max_days)
+        .map(|(_, weight)| *weight)
+        .unwrap_or(OLD_WEIGHT)
+}
+
+/// The current time in seconds since the unix epoch, as used for the visits
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or(0)
+}
+
+/// How often and how recently the files of a workspace were opened, for
+/// ranking them in the file palette. Frequent visits count for less the older
+/// they are.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct FileFrecency {
+    files: HashMap<PathBuf, FileVisits>,
+}
+
+impl FileFrecency {
+    pub fn visit(&mut self, path: &Path, now: u64) {
+        let visits = self.files.entry(path.to_path_buf()).or_default();
+        visits.count = visits.count.saturating_add(1);
+        visits.recent.push(now);
+        if visits.recent.len() > RECENT_VISITS {
+            visits.recent.remove(0);
+        }
+
+        if self.files.len() > MAX_FILES {
+            let least = self
+                .files
+                .iter()
+                .map(|(path, visits)| (path, visits.frecency(now)))
+                .min_by(|(_, a), (_, b)| a.total_cmp(b))
+                .map(|(path, _)| path.clone());
+            if let Some(least) = least {
+                self.files.remove(&least);
+            }
+        }
+    }
+
+    pub fn frecency(&self, path: &Path, now: u64) -> f64 {
+        self.files
+            .get(path)
+            .map(|visits| visits.frecency(now))
+            .unwrap_or(0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frecency() {
+        let now = 100 * SECONDS_PER_DAY;
+        let mut frecency = FileFrecency::default();
+        let (frequent, recent) = (Path::new("/frequent"), Path::new("/recent"));
+        for _ in 0..5 {
+            frecency.visit(frequent, now - 30 * SECONDS_PER_DAY);
+        }
+        frecency.visit(recent, now);
+        assert_eq!(frecency.frecency(Path::new("/unvisited"), now), 0.0);
+        assert_eq!(frecency.frecency(recent, now), 1.0);
+        assert_eq!(frecency.frecency(frequent, now), 2.5);
+
+        // The frequent visits age, while the recent one is repeated
+        frecency.visit(recent, now);
+        frecency.visit(recent, now);
+        let later = now + 5 * SECONDS_PER_DAY;
+        assert!(
+            frecency.frecency(recent, later) > frecency.frecency(frequent, later)
+        );
+    }
+}
@@ -0,0 +1,255 @@
+use std::time::Instant;
+
+use crate::config::LapceConfig;
+
+const SCORE_MATCH: i64 = 16;
+const SCORE_GAP_START: i64 = -3;
+const SCORE_GAP_EXTENSION: i64 = -1;
+/// Bonus for a match at the start of a word, after whitespace or punctuation
+const BONUS_BOUNDARY: i64 = 8;
+/// Minimum bonus for a match directly following the previous match
+const BONUS_CONSECUTIVE: i64 = 4;
+/// The frecency that gets half of the frecency boost
+const FRECENCY_HALF_BOOST: f64 = 5.0;
+/// The bonus of the first character of the pattern is multiplied by this, as
+/// where the match starts matters the most
+const BONUS_FIRST_CHAR_MULTIPLIER: i64 = 2;
+
+/// The tunable parts of the fuzzy matching score
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FuzzyScoring {
+    /// Bonus for a match on a camelCase hump, or on the first digit of a
+    /// number
+    pub camel_case_bonus: i64,
+    /// Bonus for a match directly after a path separator
+    pub path_separator_bonus: i64,
+    /// Bonus for an item that was just used, which decays with time since
+    pub recency_boost: i64,
+    /// Bonus for a file opened often and recently, which the most frecent
+    /// files get close to
+    pub frecency_boost: i64,
+}
+
+impl FuzzyScoring {
+    pub fn from_config(config: &LapceConfig) -> Self {
+        Self {
+            camel_case_bonus: config.core.fuzzy_camel_case_bonus,
+            path_separator_bonus: config.core.fuzzy_path_separator_bonus,
+            recency_boost: config.core.fuzzy_recency_boost,
+            frecency_boost: config.core.fuzzy_frecency_boost,
+        }
+    }
+}
+
+impl Default for FuzzyScoring {
+    fn default() -> Self {
+        Self {
+            camel_case_bonus: 7,
+            path_separator_bonus: 9,
+            recency_boost: 32,
+            frecency_boost: 24,
+        }
+    }
+}
+
+/// The fuzzy matcher shared by the palette, completion and symbol search.
+///
+/// Matching is case insensitive and works on chars rather than bytes, with
+/// the Unicode lowercase mapping. The returned indices are the byte offsets of
+/// the matched chars in the choice.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FuzzyMatcher {
+    pub scoring: FuzzyScoring,
+}
+
+impl FuzzyMatcher {
+    pub fn new(scoring: FuzzyScoring) -> Self {
+        Self { scoring }
+    }
+
+    pub fn from_config(config: &LapceConfig) -> Self {
+        Self::new(FuzzyScoring::from_config(config))
+    }
+
+    pub fn fuzzy_match(&self, choice: &str, pattern: &str) -> Option<i64> {
+        self.fuzzy_indices(choice, pattern).map(|(score, _)| score)
+    }
+
+    /// Find the best alignment of `pattern` in `choice`, returning its score
+    /// and the byte offsets of the matched chars.
+    pub fn fuzzy_indices(
+        &self,
+        choice: &str,
+        pattern: &str,
+    ) -> Option<(i64, Vec<usize>)> {
+        let pattern: Vec<char> = pattern.chars().map(fold_case).collect();
+        if pattern.is_empty() {
+            return Some((0, Vec::new()));
+        }
+
+        let choice: Vec<(usize, char)> = choice.char_indices().collect();
+        if !is_subsequence(&choice, &pattern) {
+            return None;
+        }
+
+        let bonuses = self.bonuses(&choice);
+        let n = choice.len();
+        let m = pattern.len();
+
+        // `scores[i * n + j]` is the best score of matching `pattern[..=i]`
+        // with `pattern[i]` matched at `choice[j]`, and `prev` the position
+        // `pattern[i - 1]` was matched at for that score.
+        let mut scores: Vec<Option<i64>> = vec![None; m * n];
+        let mut prev: Vec<usize> = vec![0; m * n];
+        for (i, p) in pattern.iter().enumerate() {
+            // The best score of the previous pattern char matched before
+            // `j - 1`, with the gap up to `j` already penalized.
+            let mut gap_best: Option<(i64, usize)> = None;
+            for (j, (_, c)) in choice.iter().enumerate() {
+                if i > 0 && j > 1 {
+                    let start = scores[(i - 1) * n + j - 2]
+                        .map(|score| (score + SCORE_GAP_START, j - 2));
+                    let extend =
+                        gap_best.map(|(score, k)| (score + SCORE_GAP_EXTENSION, k));
+                    gap_best = match (start, extend) {
+                        (Some(a), Some(b)) => Some(if a.0 >= b.0 { a } else { b }),
+                        (a, b) => a.or(b),
+                    };
+                }
+
+                if fold_case(*c) != *p {
+                    continue;
+                }
+
+                let idx = i * n + j;
+                if i == 0 {
+                    scores[idx] =
+                        Some(SCORE_MATCH + bonuses[j] * BONUS_FIRST_CHAR_MULTIPLIER);
+                    continue;
+                }
+
+                let consecutive = (j > 0)
+                    .then(|| scores[(i - 1) * n + j - 1])
+                    .flatten()
+                    .map(|score| {
+                        (
+                            score + SCORE_MATCH + bonuses[j].max(BONUS_CONSECUTIVE),
+                            j - 1,
+                        )
+                    });
+                let gapped =
+                    gap_best.map(|(score, k)| (score + SCORE_MATCH + bonuses[j], k));
+                let best = match (consecutive, gapped) {
+                    (Some(a), Some(b)) => Some(if a.0 >= b.0 { a } else { b }),
+                    (a, b) => a.or(b),
+                };
+                if let Some((score, k)) = best {
+                    scores[idx] = Some(score);
+                    prev[idx] = k;
+                }
+            }
+        }
+
+        let last_row = (m - 1) * n;
+        let (mut j, score) = (0..n)
+            .filter_map(|j| scores[last_row + j].map(|score| (j, score)))
+            // Prefer the earliest match on equal scores
+            .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(&a.0)))?;
+
+        let mut indices = vec![0; m];
+        for i in (0..m).rev() {
+            indices[i] = choice[j].0;
+            j = prev[i * n + j];
+        }
+        Some((score, indices))
+    }
+
+    /// The score bonus of an item last used at `last_used`, which halves
+    /// every minute.
+    pub fn recency_bonus(&self, last_used: Option<Instant>) -> i64 {
+        let Some(last_used) = last_used else { return 0 };
+        let minutes = last_used.elapsed().as_secs() / 60;
+        self.scoring.recency_boost >> minutes.min(63)
+    }
+
+    /// The score bonus of a file with the `frecency`, growing with it up to the
+    /// frecency boost.
+    pub fn frecency_bonus(&self, frecency: f64) -> i64 {
+        let boost = self.scoring.frecency_boost as f64;
+        (boost * frecency / (frecency + FRECENCY_HALF_BOOST)).round() as i64
+    }
+
+    /// The bonus of matching each char of the choice, based on the char
+    /// before it.
+    fn bonuses(&self, choice: &[(usize, char)]) -> Vec<i64> {
+        let mut last = None;
+        choice
+            .iter()
+            .map(|(_, c)| {
+                let bonus = match last {
+                    None => BONUS_BOUNDARY,
+                    Some('/' | '\\') => self.scoring.path_separator_bonus,
+                    Some(last) if !last.is_alphanumeric() => BONUS_BOUNDARY,
+                    Some(last)
+                        if (last.is_lowercase() && c.is_uppercase())
+                            || (!last.is_numeric() && c.is_numeric()) =>
+                    {
+                        self.scoring.camel_case_bonus
+                    }
+                    _ => 0,
+                };
+                last = Some(*c);
+                bonus
+            })
+            .collect()
+    }
+}
+
+fn fold_case(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+fn is_subsequence(choice: &[(usize, char)], pattern: &[char]) -> bool {
+    let mut pattern = pattern.iter().peekable();
+    for (_, c) in choice {
+        match pattern.peek() {
+            Some(p) if fold_case(*c) == **p => {
+                pattern.next();
+            }
+            Some(_) => {}
+            None => break,
+        }
+    }
+    pattern.peek().is_none()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_indices() {
+        let matcher = FuzzyMatcher::default();
+        assert_eq!(matcher.fuzzy_indices("abc", "d"), None);
+        assert_eq!(matcher.fuzzy_indices("abc", "ca"), None);
+        let (_, indices) = matcher.fuzzy_indices("a_b_abc", "abc").unwrap();
+        assert_eq!(indices, vec![4, 5, 6]);
+        let (_, indices) = matcher.fuzzy_indices("ÄbÖ", "äö").unwrap();
+        assert_eq!(indices, vec![0, 3]);
+    }
+
+    #[test]
+    fn test_scoring_bonuses() {
+        let matcher = FuzzyMatcher::default();
+        let camel = matcher.fuzzy_match("getFileName", "fn").unwrap();
+        let plain = matcher.fuzzy_match("getfilename", "fn").unwrap();
+        assert!(camel > plain);
+
+        let path = matcher.fuzzy_match("src/main.rs", "m").unwrap();
+        let word = matcher.fuzzy_match("srcxmain.rs", "m").unwrap();
+        assert!(path > word);
+
+        assert_eq!(matcher.recency_bonus(Some(Instant::now())), 32);
+        assert_eq!(matcher.recency_bonus(None), 0);
+    }
+}
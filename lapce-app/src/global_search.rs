@@ -1,24 +1,36 @@
-use std::{ops::Range, path::PathBuf};
+use std::{
+    collections::HashSet,
+    ops::Range,
+    path::{Path, PathBuf},
+};
 
 use floem::{
     ext_event::create_ext_action,
     reactive::{
         create_effect, create_rw_signal, Memo, RwSignal, Scope, SignalGet,
         SignalGetUntracked, SignalSet, SignalUpdate, SignalWith,
+        SignalWithUntracked,
     },
     views::VirtualListVector,
 };
 use indexmap::IndexMap;
-use lapce_core::{mode::Mode, selection::Selection};
-use lapce_rpc::proxy::{ProxyResponse, SearchMatch};
+use lapce_core::{
+    buffer::rope_text::RopeText, command::FocusCommand, editor::EditType,
+    mode::Mode, selection::Selection,
+};
+use lapce_rpc::proxy::{LineEdit, ProxyResponse, SearchMatch, SearchReplacement};
 use lapce_xi_rope::Rope;
+use regex::{Regex, RegexBuilder};
 
 use crate::{
     command::{CommandExecuted, CommandKind},
+    doc::{DocContent, Document},
     editor::EditorData,
     id::EditorId,
     keypress::{condition::Condition, KeyPressFocus},
     main_split::MainSplitData,
+    search_history::SearchHistoryKind,
+    search_results::{SearchResultsBuffer, CONFLICT_SEPARATOR},
     window_tab::CommonData,
 };
 
@@ -26,10 +38,26 @@
 pub struct SearchMatchData {
     pub expanded: RwSignal<bool>,
     pub matches: RwSignal<im::Vector<SearchMatch>>,
+    /// The matches, by line and start column, that were unchecked so that
+    /// replacing skips them
+    pub excluded: RwSignal<HashSet<(usize, usize)>>,
     pub line_height: Memo<f64>,
 }
 
 impl SearchMatchData {
+    pub fn is_excluded(&self, m: &SearchMatch) -> bool {
+        self.excluded
+            .with(|excluded| excluded.contains(&(m.line, m.start)))
+    }
+
+    pub fn toggle_excluded(&self, m: &SearchMatch) {
+        self.excluded.update(|excluded| {
+            if !excluded.remove(&(m.line, m.start)) {
+                excluded.insert((m.line, m.start));
+            }
+        });
+    }
+
     pub fn height(&self) -> f64 {
         let line_height = self.line_height.get();
         let count = if self.expanded.get() {
@@ -44,7 +72,13 @@ pub fn height(&self) -> f64 {
 #[derive(Clone)]
 pub struct GlobalSearchData {
     pub editor: EditorData,
+    pub replace_editor: EditorData,
+    /// Whether the replace input has the focus rather than the search input
+    pub replace_focus: RwSignal<bool>,
     pub search_result: RwSignal<IndexMap<PathBuf, SearchMatchData>>,
+    /// The document of the search results opened for editing
+    pub results_doc: RwSignal<Option<RwSignal<Document>>>,
+    pub results_buffer: RwSignal<SearchResultsBuffer>,
     pub main_split: MainSplitData,
     pub common: CommonData,
 }
@@ -55,7 +89,10 @@ fn get_mode(&self) -> Mode {
     }
 
     fn check_condition(&self, condition: Condition) -> bool {
-        matches!(condition, Condition::PanelFocus)
+        matches!(
+            condition,
+            Condition::PanelFocus | Condition::GlobalSearchFocus
+        )
     }
 
     fn run_command(
@@ -66,11 +103,13 @@ fn run_command(
     ) -> CommandExecuted {
         match &command.kind {
             CommandKind::Workbench(_) => {}
-            CommandKind::Focus(_) => {}
+            CommandKind::Focus(cmd) => {
+                self.run_focus_command(cmd);
+            }
             CommandKind::Edit(_)
             | CommandKind::Move(_)
             | CommandKind::MultiSelection(_) => {
-                self.editor.run_command(command, count, mods);
+                self.focused_editor().run_command(command, count, mods);
             }
             CommandKind::MotionMode(_) => {}
         }
@@ -78,7 +117,7 @@ fn run_command(
     }
 
     fn receive_char(&self, c: &str) {
-        self.editor.receive_char(c);
+        self.focused_editor().receive_char(c);
     }
 }
 
@@ -114,11 +153,20 @@ fn slice(&mut self, _range: Range<usize>) -> Self::ItemIterator {
 impl GlobalSearchData {
     pub fn new(cx: Scope, main_split: MainSplitData, common: CommonData) -> Self {
         let editor = EditorData::new_local(cx, EditorId::next(), common.clone());
+        let replace_editor =
+            EditorData::new_local(cx, EditorId::next(), common.clone());
+        let replace_focus = create_rw_signal(cx, false);
         let search_result = create_rw_signal(cx, IndexMap::new());
+        let results_doc = create_rw_signal(cx, None);
+        let results_buffer = create_rw_signal(cx, SearchResultsBuffer::default());
 
         let global_search = Self {
             editor,
+            replace_editor,
+            replace_focus,
             search_result,
+            results_doc,
+            results_buffer,
             main_split,
             common,
         };
@@ -172,6 +220,15 @@ pub fn new(cx: Scope, main_split: MainSplitData, common: CommonData) -> Self {
         global_search
     }
 
+    fn new_match_data(&self) -> SearchMatchData {
+        SearchMatchData {
+            expanded: create_rw_signal(self.common.scope, true),
+            matches: create_rw_signal(self.common.scope, im::Vector::new()),
+            excluded: create_rw_signal(self.common.scope, HashSet::new()),
+            line_height: self.common.ui_line_height,
+        }
+    }
+
     fn update_matches(&self, matches: IndexMap<PathBuf, Vec<SearchMatch>>) {
         let current = self.search_result.get_untracked();
 
@@ -179,17 +236,10 @@ fn update_matches(&self, matches: IndexMap<PathBuf, Vec<SearchMatch>>) {
             matches
                 .into_iter()
                 .map(|(path, matches)| {
-                    let match_data =
-                        current.get(&path).cloned().unwrap_or_else(|| {
-                            SearchMatchData {
-                                expanded: create_rw_signal(self.common.scope, true),
-                                matches: create_rw_signal(
-                                    self.common.scope,
-                                    im::Vector::new(),
-                                ),
-                                line_height: self.common.ui_line_height,
-                            }
-                        });
+                    let match_data = current
+                        .get(&path)
+                        .cloned()
+                        .unwrap_or_else(|| self.new_match_data());
 
                     match_data.matches.set(matches.into());
 
@@ -209,4 +259,380 @@ pub fn set_pattern(&self, pattern: String) {
             .cursor
             .update(|cursor| cursor.set_insert(Selection::region(0, pattern_len)));
     }
+
+    fn run_focus_command(&self, cmd: &FocusCommand) -> CommandExecuted {
+        match cmd {
+            FocusCommand::SearchHistoryPrevious
+            | FocusCommand::SearchHistoryNext => {
+                let kind = if self.replace_focus.get_untracked() {
+                    SearchHistoryKind::Replace
+                } else {
+                    SearchHistoryKind::Search
+                };
+                self.common.search_history.navigate(
+                    kind,
+                    self.focused_editor(),
+                    *cmd == FocusCommand::SearchHistoryPrevious,
+                );
+            }
+            FocusCommand::GlobalSearchRefresh => {
+                self.record_search_history(false);
+            }
+            _ => return CommandExecuted::No,
+        }
+        CommandExecuted::Yes
+    }
+
+    /// Record the search pattern, and the replacement if `replace` is set, in
+    /// the search history.
+    fn record_search_history(&self, replace: bool) {
+        let history = &self.common.search_history;
+        let pattern = self
+            .editor
+            .view
+            .doc
+            .with_untracked(|doc| doc.buffer().to_string());
+        history.record(SearchHistoryKind::Search, &pattern);
+        if replace {
+            history.record(SearchHistoryKind::Replace, &self.replace_text());
+        }
+    }
+
+    fn focused_editor(&self) -> &EditorData {
+        if self.replace_focus.get_untracked() {
+            &self.replace_editor
+        } else {
+            &self.editor
+        }
+    }
+
+    pub fn replace_text(&self) -> String {
+        self.replace_editor
+            .view
+            .doc
+            .with(|doc| doc.buffer().to_string())
+    }
+
+    /// The regex of the search pattern if it is a regex search, for expanding
+    /// the capture groups in the replacement.
+    pub fn replace_regex(&self) -> Option<Regex> {
+        if !self.common.find.is_regex.get_untracked() {
+            return None;
+        }
+        let pattern = self
+            .editor
+            .view
+            .doc
+            .with_untracked(|doc| doc.buffer().to_string());
+        RegexBuilder::new(&pattern)
+            .case_insensitive(!self.common.find.case_sensitive(false))
+            .build()
+            .ok()
+    }
+
+    /// Replace all the checked matches. Open documents are edited, so that the
+    /// replacement can be undone, while the other files are changed on disk.
+    pub fn replace_all(&self) {
+        self.record_search_history(true);
+        let replace_text = self.replace_text();
+        let regex = self.replace_regex();
+        let search_result = self.search_result.get_untracked();
+
+        let mut skipped = Vec::new();
+        for (path, match_data) in search_result.iter() {
+            let matches: Vec<SearchMatch> = match_data
+                .matches
+                .get_untracked()
+                .into_iter()
+                .filter(|m| !match_data.is_excluded(m))
+                .collect();
+            if matches.is_empty() {
+                continue;
+            }
+
+            let doc = self
+                .main_split
+                .doc_of_path(path)
+                .filter(|doc| doc.with_untracked(|doc| doc.loaded()));
+            if let Some(doc) = doc {
+                doc.update(|doc| {
+                    let buffer = doc.buffer();
+                    let mut edits: Vec<(Selection, String)> = Vec::new();
+                    let mut doc_skipped = Vec::new();
+                    for m in matches {
+                        // The document may have changed since the search
+                        let region = m
+                            .line
+                            .checked_sub(1)
+                            .filter(|line| *line <= buffer.last_line())
+                            .filter(|line| {
+                                buffer.line_content(*line).get(m.start..m.end)
+                                    == Some(m.matched())
+                            })
+                            .map(|line| buffer.offset_of_line(line));
+                        match region {
+                            Some(offset) => edits.push((
+                                Selection::region(offset + m.start, offset + m.end),
+                                expand_replacement(
+                                    regex.as_ref(),
+                                    m.matched(),
+                                    &replace_text,
+                                ),
+                            )),
+                            None => doc_skipped.push(m),
+                        }
+                    }
+                    let edits: Vec<(&Selection, &str)> = edits
+                        .iter()
+                        .map(|(selection, text)| (selection, text.as_str()))
+                        .collect();
+                    doc.do_raw_edit(&edits, EditType::Other);
+                    skipped.push((path.clone(), doc_skipped));
+                });
+            } else {
+                let replacements = matches
+                    .iter()
+                    .map(|m| SearchReplacement {
+                        line: m.line,
+                        start: m.start,
+                        end: m.end,
+                        original: m.matched().to_string(),
+                        replacement: expand_replacement(
+                            regex.as_ref(),
+                            m.matched(),
+                            &replace_text,
+                        ),
+                    })
+                    .collect();
+                let global_search = self.clone();
+                let local_path = path.clone();
+                let send = create_ext_action(self.common.scope, move |result| {
+                    if let Ok(ProxyResponse::ReplaceInFileResponse { skipped }) =
+                        result
+                    {
+                        let skipped = matches
+                            .into_iter()
+                            .filter(|m| skipped.contains(&(m.line, m.start)))
+                            .collect();
+                        global_search.keep_skipped(local_path, skipped);
+                    }
+                });
+                self.common.proxy.replace_in_file(
+                    path.clone(),
+                    replacements,
+                    move |result| {
+                        send(result);
+                    },
+                );
+            }
+        }
+
+        // Only the unchecked matches are left
+        self.search_result.update(|search_result| {
+            search_result.retain(|_, match_data| {
+                let excluded = match_data.excluded.get_untracked();
+                match_data.matches.update(|matches| {
+                    matches.retain(|m| excluded.contains(&(m.line, m.start)))
+                });
+                !match_data.matches.with_untracked(|m| m.is_empty())
+            });
+        });
+        for (path, skipped) in skipped {
+            self.keep_skipped(path, skipped);
+        }
+    }
+
+    /// Put the matches that weren't replaced, since the text they matched
+    /// changed in the meantime, back in the results
+    fn keep_skipped(&self, path: PathBuf, skipped: Vec<SearchMatch>) {
+        if skipped.is_empty() {
+            return;
+        }
+        let match_data = self
+            .search_result
+            .with_untracked(|result| result.get(&path).cloned())
+            .unwrap_or_else(|| self.new_match_data());
+        match_data.matches.update(|matches| {
+            matches.extend(skipped);
+            matches.sort_by(|a, b| (a.line, a.start).cmp(&(b.line, b.start)));
+        });
+        self.search_result.update(|result| {
+            result.entry(path).or_insert(match_data);
+        });
+    }
+}
+
+impl GlobalSearchData {
+    /// Open the search results in an editable buffer, whose edited lines are
+    /// written back to their files on save.
+    pub fn open_results_buffer(&self) {
+        let results = self.search_result.get_untracked().into_iter().map(
+            |(path, match_data)| {
+                (
+                    path,
+                    match_data.matches.get_untracked().into_iter().collect(),
+                )
+            },
+        );
+        let buffer =
+            SearchResultsBuffer::new(self.common.workspace.path.clone(), results);
+        let text = buffer.text();
+        self.results_buffer.set(buffer);
+
+        let doc = match self.results_doc.get_untracked() {
+            Some(doc) => doc,
+            None => {
+                let mut doc = Document::new_local(
+                    self.common.scope,
+                    self.common.find.clone(),
+                    self.common.proxy.clone(),
+                    self.common.config,
+                );
+                doc.content = DocContent::SearchResults;
+                let doc = create_rw_signal(self.common.scope, doc);
+                self.results_doc.set(Some(doc));
+                doc
+            }
+        };
+        doc.update(|doc| doc.reload(Rope::from(text), true));
+        self.main_split.open_search_results(doc);
+    }
+
+    /// Write the edited lines of the search results buffer back to their files.
+    /// Open documents are edited, while the other files are changed on disk.
+    /// The lines that changed in the meantime are skipped and marked as
+    /// conflicting, and applying again overwrites them.
+    pub fn apply_results_buffer(&self) {
+        let Some(results_doc) = self.results_doc.get_untracked() else {
+            return;
+        };
+        let text = results_doc.with_untracked(|doc| doc.buffer().to_string());
+        let edits = self
+            .results_buffer
+            .with_untracked(|buffer| buffer.edits(&text));
+        results_doc.update(|doc| doc.buffer_mut().set_pristine());
+
+        for (path, edits) in edits {
+            let doc = self
+                .main_split
+                .doc_of_path(&path)
+                .filter(|doc| doc.with_untracked(|doc| doc.loaded()));
+            if let Some(doc) = doc {
+                let conflicts = doc
+                    .try_update(|doc| edit_doc_lines(doc, &edits))
+                    .unwrap_or_default();
+                self.results_applied(&path, edits, conflicts);
+            } else {
+                let global_search = self.clone();
+                let local_path = path.clone();
+                let local_edits = edits.clone();
+                let send = create_ext_action(self.common.scope, move |result| {
+                    if let Ok(ProxyResponse::EditLinesInFileResponse { conflicts }) =
+                        result
+                    {
+                        global_search.results_applied(
+                            &local_path,
+                            local_edits,
+                            conflicts,
+                        );
+                    }
+                });
+                self.common
+                    .proxy
+                    .edit_lines_in_file(path, edits, move |result| {
+                        send(result);
+                    });
+            }
+        }
+    }
+
+    /// Record the content the edited lines of the file have now, marking the
+    /// conflicting ones in the results buffer.
+    fn results_applied(
+        &self,
+        path: &Path,
+        edits: Vec<LineEdit>,
+        conflicts: Vec<(usize, String)>,
+    ) {
+        self.results_buffer.update(|buffer| {
+            for edit in edits {
+                buffer.update_line(path, edit.line, edit.replacement);
+            }
+            for (line, content) in &conflicts {
+                buffer.update_line(path, *line, content.clone());
+            }
+        });
+        if conflicts.is_empty() {
+            return;
+        }
+
+        let Some(results_doc) = self.results_doc.get_untracked() else {
+            return;
+        };
+        let lines: Vec<usize> = conflicts.iter().map(|(line, _)| *line).collect();
+        let text = results_doc.with_untracked(|doc| doc.buffer().to_string());
+        let separators = self.results_buffer.with_untracked(|buffer| {
+            buffer.conflict_separators(&text, path, &lines)
+        });
+        let edits: Vec<(Selection, &str)> = separators
+            .into_iter()
+            .map(|offset| {
+                let selection =
+                    Selection::region(offset, offset + CONFLICT_SEPARATOR.len());
+                (selection, CONFLICT_SEPARATOR)
+            })
+            .collect();
+        results_doc.update(|doc| {
+            doc.do_raw_edit(&edits, EditType::Other);
+        });
+    }
+}
+
+/// Apply the line edits to an open document, returning the lines that were
+/// skipped because they changed in the meantime, with their current content.
+fn edit_doc_lines(doc: &mut Document, edits: &[LineEdit]) -> Vec<(usize, String)> {
+    let buffer = doc.buffer();
+    let mut conflicts = Vec::new();
+    let mut selections = Vec::new();
+    for edit in edits {
+        let line = edit.line.checked_sub(1);
+        let Some(line) = line.filter(|line| *line <= buffer.last_line()) else {
+            conflicts.push((edit.line, String::new()));
+            continue;
+        };
+        let content = buffer.line_content(line);
+        let content = content.trim_end_matches(['\r', '\n']);
+        let Some(range) = edit.range(content) else {
+            conflicts.push((edit.line, edit.current(content).to_string()));
+            continue;
+        };
+        let offset = buffer.offset_of_line(line);
+        selections.push((
+            Selection::region(offset + range.start, offset + range.end),
+            edit.replacement.as_str(),
+        ));
+    }
+
+    if !selections.is_empty() {
+        doc.do_raw_edit(&selections, EditType::Other);
+    }
+    conflicts
+}
+
+/// The text to replace `matched` with, with the capture groups of the regex
+/// expanded in `replacement`.
+pub fn expand_replacement(
+    regex: Option<&Regex>,
+    matched: &str,
+    replacement: &str,
+) -> String {
+    let captures = regex.and_then(|regex| regex.captures(matched));
+    match captures {
+        Some(captures) => {
+            let mut text = String::new();
+            captures.expand(replacement, &mut text);
+            text
+        }
+        None => replacement.to_string(),
+    }
 }
@@ -0,0 +1,236 @@
+//! Golden snapshots of what the widgets paint, to refactor the painting code
+//! safely. Floem can't render a view offscreen, so rather than pixels a
+//! snapshot holds what the painting code of a widget works out: the text of
+//! the lines of an editor with the spans of colors they're laid out with, the
+//! rows of the palette with the chars the input matched, and the markers of
+//! the changes of a diff.
+//!
+//! Set `LAPCE_UPDATE_GOLDEN=1` when running the tests to write the snapshots
+//! again after an intended change, and review their diff.
+
+use std::{
+    path::PathBuf,
+    sync::{atomic::AtomicU64, Arc},
+};
+
+use floem::peniko::Color;
+use lapce_core::buffer::diff::{DiffBothInfo, DiffLines};
+use lapce_rpc::style::LineStyle;
+
+use crate::{
+    app::palette_path_parts,
+    config::{color::LapceColor, LapceConfig},
+    doc::{line_spans, phantom_text::PhantomTextLine},
+    editor::{location::EditorLocation, view::changes_colors},
+    fuzzy::FuzzyMatcher,
+    palette::{
+        item::{PaletteItem, PaletteItemContent},
+        kind::PaletteKind,
+        PaletteData,
+    },
+};
+
+/// Compare the rendering with the snapshot of the name in `tests/golden`,
+/// or write it if asked to.
+fn assert_golden(name: &str, actual: &str) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden")
+        .join(format!("{name}.txt"));
+    if std::env::var_os("LAPCE_UPDATE_GOLDEN").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, actual).unwrap();
+        return;
+    }
+    let expected = std::fs::read_to_string(&path).unwrap_or_else(|_| {
+        panic!(
+            "no snapshot at {}, run with LAPCE_UPDATE_GOLDEN=1 to write it",
+            path.display()
+        )
+    });
+    assert_eq!(
+        expected.replace("\r\n", "\n"),
+        actual,
+        "the rendering differs from the snapshot {name}"
+    );
+}
+
+fn hex(color: &Color) -> String {
+    format!("#{:02X}{:02X}{:02X}", color.r, color.g, color.b)
+}
+
+/// Render the lines of an editor as they're laid out: the text in the
+/// foreground color, with the spans the editor paints in other colors.
+fn paint_lines(
+    config: &LapceConfig,
+    text: &str,
+    line_styles: impl Fn(usize, &str) -> Vec<LineStyle>,
+) -> String {
+    let mut rendered = format!(
+        "foreground {}\n",
+        hex(config.get_color(LapceColor::EDITOR_FOREGROUND))
+    );
+    for (line, content) in text.lines().enumerate() {
+        rendered.push_str(format!("{line:>3} │ {content}").trim_end());
+        rendered.push('\n');
+        let styles = line_styles(line, content);
+        for span in line_spans(&styles, &PhantomTextLine::default(), config) {
+            if let Some(color) = span.color {
+                rendered.push_str(&format!(
+                    "    ╰ {}..{} {}\n",
+                    span.range.start,
+                    span.range.end,
+                    hex(&color)
+                ));
+            }
+        }
+    }
+    rendered
+}
+
+/// Render the rows of the palette of the kind once the items are matched with
+/// the input, with the chars the input matched in brackets
+fn paint_palette(kind: PaletteKind, input: &str, items: &[PaletteItem]) -> String {
+    let items = PaletteData::filter_items(
+        Arc::new(AtomicU64::new(0)),
+        0,
+        input,
+        kind.pipeline(),
+        items.iter().cloned().collect(),
+        &FuzzyMatcher::default(),
+    )
+    .unwrap();
+
+    let focus = |text: &str, indices: &[usize]| {
+        text.char_indices()
+            .map(|(i, c)| {
+                if indices.contains(&i) {
+                    format!("[{c}]")
+                } else {
+                    c.to_string()
+                }
+            })
+            .collect::<String>()
+    };
+    let mut rendered = format!("input \"{input}\"\n");
+    for item in items {
+        let row = match &item.content {
+            PaletteItemContent::Header { name } => format!("── {name}"),
+            PaletteItemContent::File { path, .. }
+            | PaletteItemContent::Reference { path, .. } => {
+                let ((file_name, file_name_indices), (folder, folder_indices)) =
+                    palette_path_parts(
+                        path,
+                        item.decoration.folder.as_deref(),
+                        &item.indices,
+                    );
+                format!(
+                    "{}  {}",
+                    focus(&file_name, &file_name_indices),
+                    focus(&folder, &folder_indices)
+                )
+            }
+            _ => focus(&item.filter_text, &item.indices),
+        };
+        rendered.push_str(row.trim_end());
+        rendered.push('\n');
+    }
+    rendered
+}
+
+#[test]
+fn test_commit_message_editor() {
+    let text = "Add the golden tests of the editor
+
+The snapshots hold the colors of the text.
+# Please enter the commit message for your changes.
+# On branch master
+";
+    let rendered = paint_lines(
+        &LapceConfig::default_for_test(),
+        text,
+        crate::commit_message::line_styles,
+    );
+    assert_golden("commit_message_editor", &rendered);
+}
+
+#[test]
+fn test_rebase_todo_editor() {
+    let text = "pick 1a2b3c4 Add a feature
+squash 5d6e7f8 Fix the feature
+exec cargo test
+
+# Rebase 0a1b2c3..5d6e7f8 onto 0a1b2c3 (3 commands)
+";
+    let rendered = paint_lines(&LapceConfig::default_for_test(), text, |_, line| {
+        crate::rebase_todo::line_styles(line)
+    });
+    assert_golden("rebase_todo_editor", &rendered);
+}
+
+#[test]
+fn test_reference_palette() {
+    let reference = |path: &str| {
+        let path = PathBuf::from(path);
+        PaletteItem {
+            content: PaletteItemContent::Reference {
+                path: path.clone(),
+                location: EditorLocation {
+                    path: PathBuf::from("/workspace").join(&path),
+                    position: None,
+                    scroll_offset: None,
+                    ignore_unconfirmed: false,
+                    same_editor_tab: false,
+                },
+            },
+            filter_text: path.to_string_lossy().to_string(),
+            score: 0,
+            frecency: 0,
+            indices: Vec::new(),
+            last_used: None,
+            decoration: Default::default(),
+        }
+    };
+    let items = [
+        reference("crates/editor/src/view/paint/text/lines.rs"),
+        reference("crates/editor/src/view/paint/text/lines.rs"),
+        reference("docs/guide.md"),
+    ];
+
+    let rendered = [
+        paint_palette(PaletteKind::Reference, "", &items),
+        paint_palette(PaletteKind::Reference, "gu", &items),
+    ]
+    .concat();
+    assert_golden("reference_palette", &rendered);
+}
+
+#[test]
+fn test_diff_change_markers() {
+    let both = |left: std::ops::Range<usize>, right: std::ops::Range<usize>| {
+        DiffLines::Both(DiffBothInfo {
+            left,
+            right,
+            skip: None,
+        })
+    };
+    // A line changed, a line added and the last line removed
+    let changes = im::vector![
+        both(0..1, 0..1),
+        DiffLines::Left(1..2),
+        DiffLines::Right(1..2),
+        both(2..3, 2..3),
+        DiffLines::Right(3..4),
+        both(3..5, 4..6),
+        DiffLines::Left(5..6),
+    ];
+
+    let config = LapceConfig::default_for_test();
+    let mut rendered = String::new();
+    for (line, height, removed, color) in changes_colors(changes, 0, 100, &config) {
+        let removed = if removed { " removed" } else { "" };
+        rendered
+            .push_str(&format!("{line:>3} +{height} {}{removed}\n", hex(&color)));
+    }
+    assert_golden("diff_change_markers", &rendered);
+}
@@ -0,0 +1,252 @@
+use std::{cell::RefCell, path::Path, rc::Rc};
+
+use floem::{
+    ext_event::create_ext_action,
+    reactive::{RwSignal, Scope},
+};
+use lapce_core::buffer::rope_text::{RopeText, RopeTextRef};
+use lapce_proxy::buffer::language_id_from_path;
+use lapce_rpc::{
+    plugin::{PluginProviders, ProviderKind},
+    proxy::{ProxyResponse, ProxyRpcHandler},
+};
+use lapce_xi_rope::Rope;
+
+/// The inline suggestion shown in an editor: the text to insert at the
+/// offset, for the version of the document it was worked out for
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InlineSuggestion {
+    pub rev: u64,
+    pub offset: usize,
+    pub text: String,
+}
+
+/// A source of inline suggestions: text to insert at the cursor, shown dimmed
+/// after it as "ghost text" until it's accepted or the cursor moves.
+///
+/// The provider answers through `send`, either right away or later on, as one
+/// that waits on a plugin would. An answer that comes once the text or the
+/// cursor changed is dropped.
+pub trait InlineSuggestionProvider {
+    fn suggest(
+        &self,
+        path: &Path,
+        text: &Rope,
+        offset: usize,
+        send: Box<dyn FnOnce(Option<String>)>,
+    );
+}
+
+/// The providers of inline suggestions, asked in the order they were
+/// registered until one has a suggestion
+#[derive(Clone)]
+pub struct InlineSuggestionProviders {
+    providers: Rc<RefCell<Vec<Rc<dyn InlineSuggestionProvider>>>>,
+}
+
+impl InlineSuggestionProviders {
+    /// The plugins that registered to provide inline suggestions, then
+    /// [`LineRepetition`]
+    pub fn new(
+        cx: Scope,
+        proxy: ProxyRpcHandler,
+        plugin_providers: RwSignal<PluginProviders>,
+    ) -> Self {
+        let providers = Self {
+            providers: Rc::new(RefCell::new(Vec::new())),
+        };
+        providers.register(Rc::new(PluginInlineSuggestions {
+            cx,
+            proxy,
+            plugin_providers,
+        }));
+        providers.register(Rc::new(LineRepetition));
+        providers
+    }
+
+    pub fn register(&self, provider: Rc<dyn InlineSuggestionProvider>) {
+        self.providers.borrow_mut().push(provider);
+    }
+
+    pub fn suggest(
+        &self,
+        path: &Path,
+        text: Rope,
+        offset: usize,
+        send: impl FnOnce(Option<String>) + 'static,
+    ) {
+        let providers = self.providers.borrow().clone();
+        ask(providers, 0, path, text, offset, Box::new(send));
+    }
+}
+
+/// Ask the provider at `index`, and the ones after it if it has nothing
+fn ask(
+    providers: Vec<Rc<dyn InlineSuggestionProvider>>,
+    index: usize,
+    path: &Path,
+    text: Rope,
+    offset: usize,
+    send: Box<dyn FnOnce(Option<String>)>,
+) {
+    let Some(provider) = providers.get(index).cloned() else {
+        send(None);
+        return;
+    };
+    let rope = text.clone();
+    let next_path = path.to_path_buf();
+    provider.suggest(
+        path,
+        &rope,
+        offset,
+        Box::new(move |suggestion| match suggestion {
+            Some(suggestion) if !suggestion.is_empty() => send(Some(suggestion)),
+            _ => ask(providers, index + 1, &next_path, text, offset, send),
+        }),
+    );
+}
+
+/// Asks the plugins that registered to provide inline suggestions for the
+/// language of the document, through the proxy
+struct PluginInlineSuggestions {
+    cx: Scope,
+    proxy: ProxyRpcHandler,
+    plugin_providers: RwSignal<PluginProviders>,
+}
+
+impl InlineSuggestionProvider for PluginInlineSuggestions {
+    fn suggest(
+        &self,
+        path: &Path,
+        text: &Rope,
+        offset: usize,
+        send: Box<dyn FnOnce(Option<String>)>,
+    ) {
+        let language_id = language_id_from_path(path);
+        if !self.plugin_providers.with_untracked(|providers| {
+            providers.has_provider(ProviderKind::InlineSuggestion, language_id)
+        }) {
+            send(None);
+            return;
+        }
+        let position = RopeTextRef::new(text).offset_to_position(offset);
+        let send = create_ext_action(self.cx, move |result| {
+            let text = match result {
+                Ok(ProxyResponse::InlineSuggestionResponse { text }) => text,
+                _ => None,
+            };
+            send(text);
+        });
+        self.proxy.get_inline_suggestion(
+            path.to_path_buf(),
+            position,
+            move |result| {
+                send(result);
+            },
+        );
+    }
+}
+
+/// How many lines above the cursor [`LineRepetition`] looks through
+const LINE_REPETITION_LOOKBACK: usize = 500;
+
+/// How much of the line has to be typed before [`LineRepetition`] suggests
+/// anything
+const LINE_REPETITION_MIN_PREFIX: usize = 3;
+
+/// Suggests the rest of the nearest line above that starts like what's been
+/// typed on the line of the cursor, for code that repeats itself
+pub struct LineRepetition;
+
+impl InlineSuggestionProvider for LineRepetition {
+    fn suggest(
+        &self,
+        _path: &Path,
+        text: &Rope,
+        offset: usize,
+        send: Box<dyn FnOnce(Option<String>)>,
+    ) {
+        send(line_repetition(text, offset));
+    }
+}
+
+fn line_repetition(text: &Rope, offset: usize) -> Option<String> {
+    let text = RopeTextRef::new(text);
+    let line = text.line_of_offset(offset);
+    let line_start = text.offset_of_line(line);
+    let line_end = text.line_end_offset(line, true);
+    // Only at the end of the line, where the ghost text doesn't hide anything
+    if !text.slice_to_cow(offset..line_end).trim().is_empty() {
+        return None;
+    }
+    let typed = text.slice_to_cow(line_start..offset);
+    let prefix = typed.trim_start();
+    if prefix.chars().count() < LINE_REPETITION_MIN_PREFIX {
+        return None;
+    }
+
+    (line.saturating_sub(LINE_REPETITION_LOOKBACK)..line)
+        .rev()
+        .find_map(|line| {
+            let content = text.line_content(line);
+            let content = content.trim_start().trim_end_matches(['\n', '\r']);
+            content
+                .strip_prefix(prefix)
+                .filter(|rest| !rest.trim().is_empty())
+                .map(|rest| rest.to_string())
+        })
+}
+
+/// The length of the start of the suggestion that accepting a word of it
+/// takes: the whitespace before its first word and that word, or the run of
+/// punctuation it starts with
+pub fn next_word_len(suggestion: &str) -> usize {
+    let start = suggestion.len() - suggestion.trim_start().len();
+    let rest = &suggestion[start..];
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    let word = match rest.chars().next() {
+        Some(c) if is_word(c) => rest.find(|c| !is_word(c)),
+        Some(_) => rest.find(|c: char| is_word(c) || c.is_whitespace()),
+        None => None,
+    };
+    start + word.unwrap_or(rest.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_repetition() {
+        let text = Rope::from(
+            "let width = size.width;\nlet height = size.height;\n    let w",
+        );
+        assert_eq!(
+            line_repetition(&text, text.len()),
+            Some("idth = size.width;".to_string())
+        );
+        // Nothing typed beyond the indent yet
+        let text = Rope::from("let width = size.width;\n    le");
+        assert_eq!(line_repetition(&text, text.len()), None);
+        // Not at the end of the line
+        let text = Rope::from("let width = size.width;\nlet w)");
+        assert_eq!(line_repetition(&text, text.len() - 1), None);
+        // The nearest line above wins
+        let text =
+            Rope::from("let x = 1;\nlet x = 2;\nlet x = 3;\nlet x = 2;\nlet x");
+        assert_eq!(
+            line_repetition(&text, text.len()),
+            Some(" = 2;".to_string())
+        );
+    }
+
+    #[test]
+    fn test_next_word_len() {
+        assert_eq!(next_word_len("idth = size"), 4);
+        assert_eq!(next_word_len(" = size"), 2);
+        assert_eq!(next_word_len(" size.width"), 5);
+        assert_eq!(next_word_len(".width;"), 1);
+        assert_eq!(next_word_len(");"), 2);
+        assert_eq!(next_word_len("  "), 2);
+    }
+}
@@ -1,8 +1,10 @@
 pub mod condition;
+mod dry_run;
 mod key;
 pub mod keymap;
 mod loader;
 mod press;
+pub mod review;
 
 use std::{path::PathBuf, str::FromStr};
 
@@ -14,16 +16,15 @@
 
 use self::{key::Key, keymap::KeyMap, loader::KeyMapLoader, press::KeyPress};
 use crate::{
-    command::{
-        lapce_internal_commands, CommandExecuted, CommandKind, LapceCommand,
-        LapceWorkbenchCommand,
-    },
+    command::{lapce_internal_commands, CommandExecuted, CommandKind, LapceCommand},
     config::LapceConfig,
     keypress::{
         condition::{CheckCondition, Condition},
         keymap::KeymapMatch,
     },
     listener::Listener,
+    profile::Profile,
+    safe_mode::{self, SafeModePart},
 };
 
 const DEFAULT_KEYMAPS_COMMON: &str =
@@ -53,6 +54,12 @@ fn focus_only(&self) -> bool {
         false
     }
 
+    /// Whether the commands are only reported rather than run, in which case
+    /// the focus is given the workbench commands too
+    fn is_dry_run(&self) -> bool {
+        false
+    }
+
     fn receive_char(&self, c: &str);
 }
 
@@ -78,7 +85,7 @@ fn from(ev: &'a MouseEvent) -> Self {
 pub struct KeyPressData {
     count: Option<usize>,
     pending_keypress: Vec<KeyPress>,
-    workbench_cmd: Listener<LapceWorkbenchCommand>,
+    lapce_command: Listener<LapceCommand>,
     pub commands: IndexMap<String, LapceCommand>,
     keymaps: IndexMap<Vec<KeyPress>, Vec<KeyMap>>,
     pub command_keymaps: IndexMap<String, Vec<KeyMap>>,
@@ -87,12 +94,31 @@ pub struct KeyPressData {
 }
 
 impl KeyPressData {
-    pub fn new(
-        config: &LapceConfig,
-        workbench_cmd: Listener<LapceWorkbenchCommand>,
-    ) -> Self {
+    pub fn new(config: &LapceConfig, lapce_command: Listener<LapceCommand>) -> Self {
         let (keymaps, command_keymaps) =
             Self::get_keymaps(config).unwrap_or((IndexMap::new(), IndexMap::new()));
+        Self::from_keymaps(keymaps, command_keymaps, lapce_command)
+    }
+
+    /// The keymaps of the string alone, without the default ones or the
+    /// user's
+    #[cfg(test)]
+    pub(crate) fn with_keymaps(
+        keymaps: &str,
+        modal: bool,
+        lapce_command: Listener<LapceCommand>,
+    ) -> Self {
+        let mut loader = KeyMapLoader::new();
+        loader.load_from_str(keymaps, modal).unwrap();
+        let (keymaps, command_keymaps) = loader.finalize();
+        Self::from_keymaps(keymaps, command_keymaps, lapce_command)
+    }
+
+    fn from_keymaps(
+        keymaps: IndexMap<Vec<KeyPress>, Vec<KeyMap>>,
+        command_keymaps: IndexMap<String, Vec<KeyMap>>,
+        lapce_command: Listener<LapceCommand>,
+    ) -> Self {
         let mut keypress = Self {
             count: None,
             pending_keypress: Vec::new(),
@@ -101,18 +127,23 @@ pub fn new(
             commands: lapce_internal_commands(),
             commands_with_keymap: Vec::new(),
             commands_without_keymap: Vec::new(),
-            workbench_cmd,
+            lapce_command,
         };
         keypress.load_commands();
         keypress
     }
 
+    /// The keymaps of all the commands that have one
+    pub fn commands_with_keymap(&self) -> &[KeyMap] {
+        &self.commands_with_keymap
+    }
+
     fn load_commands(&mut self) {
         let mut commands_with_keymap = Vec::new();
         let mut commands_without_keymap = Vec::new();
         for (_, keymaps) in self.command_keymaps.iter() {
             for keymap in keymaps.iter() {
-                if self.commands.get(&keymap.command).is_some() {
+                if self.command(&keymap.command).is_some() {
                     commands_with_keymap.push(keymap.clone());
                 }
             }
@@ -157,6 +188,20 @@ fn handle_count<T: KeyPressFocus>(
         false
     }
 
+    /// The command a keymap runs, with the argument after a `:` in its name
+    /// as the data, like the name of the task in `run_task:Build`
+    pub fn command(&self, command: &str) -> Option<LapceCommand> {
+        if let Some(cmd) = self.commands.get(command) {
+            return Some(cmd.clone());
+        }
+        let (command, arg) = command.split_once(':')?;
+        let cmd = self.commands.get(command)?;
+        Some(LapceCommand {
+            kind: cmd.kind.clone(),
+            data: Some(serde_json::json!(arg)),
+        })
+    }
+
     fn run_command<T: KeyPressFocus>(
         &self,
         command: &str,
@@ -164,10 +209,13 @@ fn run_command<T: KeyPressFocus>(
         mods: Modifiers,
         focus: &T,
     ) -> CommandExecuted {
-        if let Some(cmd) = self.commands.get(command) {
+        if let Some(cmd) = self.command(command) {
             match &cmd.kind {
-                CommandKind::Workbench(cmd) => {
-                    self.workbench_cmd.send(cmd.clone());
+                CommandKind::Workbench(_) if focus.is_dry_run() => {
+                    focus.run_command(&cmd, count, mods)
+                }
+                CommandKind::Workbench(_) => {
+                    self.lapce_command.send(cmd.clone());
                     CommandExecuted::Yes
                 }
                 CommandKind::Move(_)
@@ -175,7 +223,7 @@ fn run_command<T: KeyPressFocus>(
                 | CommandKind::Focus(_)
                 | CommandKind::MotionMode(_)
                 | CommandKind::MultiSelection(_) => {
-                    focus.run_command(cmd, count, mods)
+                    focus.run_command(&cmd, count, mods)
                 }
             }
         } else {
@@ -207,6 +255,16 @@ pub fn key_down<'a, T: KeyPressFocus>(
                 mods: ev.mods,
             },
         };
+        self.key_press(keypress, focus)
+    }
+
+    /// Dispatch a key press to the command of the keymap it completes, or to
+    /// the focus as a typed character
+    fn key_press<T: KeyPressFocus>(
+        &mut self,
+        keypress: KeyPress,
+        focus: &T,
+    ) -> bool {
         let mods = keypress.mods;
 
         let mode = focus.get_mode();
@@ -433,7 +491,12 @@ fn get_keymaps(
             error!("Failed to load OS defaults: {err}");
         }
 
-        if let Some(path) = Self::file() {
+        if safe_mode::is_left_out(SafeModePart::Keymaps) {
+            return Ok(loader.finalize());
+        }
+
+        let profile_file = config.profile.as_deref().and_then(Profile::keymaps_file);
+        for path in Self::file().into_iter().chain(profile_file) {
             if let Ok(content) = std::fs::read_to_string(&path) {
                 if let Err(err) = loader.load_from_str(&content, is_modal) {
                     error!("Failed to load from {path:?}: {err}");
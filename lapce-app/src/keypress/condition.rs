@@ -49,10 +49,16 @@ pub enum Condition {
     PaletteFocus,
     #[strum(serialize = "completion_focus")]
     CompletionFocus,
+    #[strum(serialize = "completion_tab_only")]
+    CompletionTabOnly,
+    #[strum(serialize = "signature_focus")]
+    SignatureFocus,
     #[strum(serialize = "modal_focus")]
     ModalFocus,
     #[strum(serialize = "in_snippet")]
     InSnippet,
+    #[strum(serialize = "inline_suggestion_visible")]
+    InlineSuggestionVisible,
     #[strum(serialize = "terminal_focus")]
     TerminalFocus,
     #[strum(serialize = "source_control_focus")]
@@ -67,6 +73,12 @@ pub enum Condition {
     SearchFocus,
     #[strum(serialize = "replace_focus")]
     ReplaceFocus,
+    #[strum(serialize = "global_search_focus")]
+    GlobalSearchFocus,
+    #[strum(serialize = "rebase_todo_focus")]
+    RebaseTodoFocus,
+    #[strum(serialize = "file_explorer_focus")]
+    FileExplorerFocus,
 }
 
 #[cfg(test)]
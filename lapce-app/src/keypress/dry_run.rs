@@ -0,0 +1,486 @@
+//! A dry run of keys in an editor, to see what the modal editing makes of
+//! them. The keys go through the same dispatch as when they're pressed, but
+//! the commands run on a copy of the editor and its document, so what they'd
+//! do is reported without changing anything.
+
+use std::{cell::RefCell, sync::Arc};
+
+use floem::{
+    glazier::{KbKey, Modifiers},
+    reactive::{
+        create_rw_signal, SignalGetUntracked, SignalSet, SignalUpdate,
+        SignalWithUntracked,
+    },
+};
+use lapce_core::{
+    buffer::rope_text::RopeText,
+    command::{FocusCommand, MotionModeCommand},
+    cursor::CursorMode,
+    mode::{Mode, MotionMode},
+    movement::Movement,
+    register::{Clipboard, Register},
+};
+use lapce_xi_rope::Rope;
+
+use super::{
+    condition::Condition, key::Key, press::KeyPress, KeyPressData, KeyPressFocus,
+};
+use crate::{
+    command::{CommandExecuted, CommandKind, LapceCommand},
+    config::LapceConfig,
+    doc::SystemClipboard,
+    editor::{movement, EditorData, InlineFindDirection},
+    id::EditorId,
+};
+
+/// The width of the column of the keys in the report
+const KEY_WIDTH: usize = 12;
+
+impl KeyPressData {
+    /// Dispatch the keys to a copy of the editor, giving the report of what
+    /// each of them does. The keys are written as in the keymaps file and
+    /// separated by spaces, like `3 d w` or `ctrl+w l`.
+    pub fn dry_run(&self, keys: &str, editor: &EditorData) -> String {
+        let mut keypress = self.clone();
+        keypress.count = None;
+        keypress.pending_keypress.clear();
+        let focus = DryRun::new(editor);
+
+        let mut report = format!("Dry run of `{keys}` {}\n\n", focus.position());
+        for key in keys.split_whitespace() {
+            let Some(press) = parse_key(key) else {
+                report.push_str(&format!("{key:<KEY_WIDTH$}unknown key, skipped\n"));
+                continue;
+            };
+            keypress.key_press(press, &focus);
+            let mut outcome = focus.take_outcome();
+            if outcome.is_empty() {
+                outcome.push(if !keypress.pending_keypress.is_empty() {
+                    "waits for the next key of a keymap".to_string()
+                } else if let Some(count) = keypress.count {
+                    format!("count {count}")
+                } else {
+                    "no command, and not typed".to_string()
+                });
+            }
+            for (i, line) in outcome.iter().enumerate() {
+                let key = if i == 0 { key } else { "" };
+                report.push_str(&format!("{key:<KEY_WIDTH$}{line}\n"));
+            }
+        }
+        report.push_str(&format!(
+            "\nEnds {}. Nothing was changed, the commands ran on a copy of the \
+             document.\n",
+            focus.position()
+        ));
+        report
+    }
+}
+
+/// The key press of a key as written in the keymaps, the way the keyboard
+/// event would give it: a letter typed with shift is upper case
+fn parse_key(key: &str) -> Option<KeyPress> {
+    let mut chars = key.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        if c.is_uppercase() {
+            return Some(KeyPress {
+                key: Key::Keyboard(KbKey::Character(c.to_string())),
+                mods: Modifiers::SHIFT,
+            });
+        }
+    }
+    let mut press = KeyPress::parse(key).pop()?;
+    if press.mods.shift() {
+        if let Key::Keyboard(KbKey::Character(c)) = &press.key {
+            if c.chars().all(|c| c.is_alphabetic()) {
+                press.key = Key::Keyboard(KbKey::Character(c.to_uppercase()));
+            }
+        }
+    }
+    Some(press)
+}
+
+/// The clipboard of a dry run, starting with the content of the system one
+/// but never writing to it
+struct DryRunClipboard(Option<String>);
+
+impl Clipboard for DryRunClipboard {
+    fn get_string(&self) -> Option<String> {
+        self.0.clone()
+    }
+
+    fn put_string(&mut self, s: impl AsRef<str>) {
+        self.0 = Some(s.as_ref().to_string());
+    }
+}
+
+struct DryRunState {
+    register: Register,
+    clipboard: DryRunClipboard,
+    /// What the key being dispatched did
+    outcome: Vec<String>,
+}
+
+/// The focus of a dry run: a copy of the editor, on a copy of its document,
+/// that the commands of the keys run on the way the editor runs them. The
+/// commands that act on the rest of the workbench are only reported.
+struct DryRun<'a> {
+    editor: &'a EditorData,
+    copy: EditorData,
+    modal: bool,
+    config: Arc<LapceConfig>,
+    state: RefCell<DryRunState>,
+}
+
+impl<'a> DryRun<'a> {
+    fn new(editor: &'a EditorData) -> Self {
+        let config = editor.common.config.get_untracked();
+        let is_local = editor.view.doc.with_untracked(|doc| doc.content.is_local());
+        let mut copy = editor.copy(editor.scope, None, EditorId::next());
+        let doc = editor
+            .view
+            .doc
+            .with_untracked(|doc| doc.detached_copy(copy.scope));
+        copy.update_doc(create_rw_signal(copy.scope, doc));
+        copy.inline_find.set(editor.inline_find.get_untracked());
+        copy.register_prefix
+            .set(editor.register_prefix.get_untracked());
+
+        let clipboard = DryRunClipboard(SystemClipboard {}.get_string());
+        let mut register = editor.common.register.get_untracked();
+        register.load_clipboard(&clipboard);
+        Self {
+            editor,
+            copy,
+            modal: config.core.modal && !is_local,
+            config,
+            state: RefCell::new(DryRunState {
+                register,
+                clipboard,
+                outcome: Vec::new(),
+            }),
+        }
+    }
+
+    fn take_outcome(&self) -> Vec<String> {
+        std::mem::take(&mut self.state.borrow_mut().outcome)
+    }
+
+    fn text(&self) -> Rope {
+        self.copy.view.text()
+    }
+
+    /// The mode and the position of the cursor
+    fn position(&self) -> String {
+        let cursor = self.copy.cursor.get_untracked();
+        let (line, col) =
+            self.copy.view.doc.with_untracked(|doc| {
+                doc.buffer().offset_to_line_col(cursor.offset())
+            });
+        format!(
+            "in {:?} mode at {}:{}",
+            cursor.get_mode(),
+            line + 1,
+            col + 1
+        )
+    }
+
+    /// Report the change of the text since it was `before`, and where the
+    /// cursor is
+    fn report(&self, state: &mut DryRunState, before: &Rope) {
+        let after = self.text();
+        if let Some(edit) = describe_edit(before, &after) {
+            state.outcome.push(edit);
+        }
+        let cursor = self.copy.cursor.get_untracked();
+        let pending = match &cursor.motion_mode {
+            Some(motion_mode) => format!(", {motion_mode:?} waits for a motion"),
+            None => String::new(),
+        };
+        state
+            .outcome
+            .push(format!("cursor {}{pending}", self.position()));
+    }
+
+    /// Move the cursor with the movement the way the editor does, running the
+    /// pending motion mode over what it covers
+    fn move_cursor(
+        &self,
+        state: &mut DryRunState,
+        movement: &Movement,
+        count: usize,
+        modify: bool,
+    ) {
+        state.outcome.push(format!("moves {movement:?}"));
+        let mut cursor = self.copy.cursor.get_untracked();
+        if let Some(motion_mode) = &cursor.motion_mode {
+            state.outcome.push(format!("runs {motion_mode:?} over it"));
+        }
+        movement::move_cursor(
+            &self.copy.view,
+            &mut cursor,
+            movement,
+            count,
+            modify,
+            &mut state.register,
+        );
+        self.copy.cursor.set(cursor);
+    }
+}
+
+impl Drop for DryRun<'_> {
+    fn drop(&mut self) {
+        self.copy.scope.dispose();
+    }
+}
+
+impl KeyPressFocus for DryRun<'_> {
+    fn get_mode(&self) -> Mode {
+        self.copy.cursor.with_untracked(|c| c.get_mode())
+    }
+
+    fn check_condition(&self, condition: Condition) -> bool {
+        self.editor.check_condition(condition)
+    }
+
+    fn run_command(
+        &self,
+        command: &LapceCommand,
+        count: Option<usize>,
+        mods: Modifiers,
+    ) -> CommandExecuted {
+        let mut state = self.state.borrow_mut();
+        let state = &mut *state;
+        let count_label = match count {
+            Some(count) => format!(" with count {count}"),
+            None => String::new(),
+        };
+        state
+            .outcome
+            .push(format!("runs {}{count_label}", command.kind.str()));
+        let before = self.text();
+        let mut cursor = self.copy.cursor.get_untracked();
+        match &command.kind {
+            CommandKind::Edit(cmd) => {
+                let yank_data = if let CursorMode::Visual { .. } = &cursor.mode {
+                    Some(
+                        self.copy
+                            .view
+                            .doc
+                            .with_untracked(|doc| cursor.yank(doc.buffer())),
+                    )
+                } else {
+                    None
+                };
+                let DryRunState {
+                    register,
+                    clipboard,
+                    ..
+                } = &mut *state;
+                let deltas = self
+                    .copy
+                    .view
+                    .doc
+                    .try_update(|doc| {
+                        doc.do_edit_with_clipboard(
+                            &mut cursor,
+                            cmd,
+                            self.modal,
+                            register,
+                            clipboard,
+                        )
+                    })
+                    .unwrap();
+                if !deltas.is_empty() {
+                    if let Some(data) = yank_data {
+                        state.register.add_delete(data);
+                    }
+                }
+                self.copy.cursor.set(cursor);
+            }
+            CommandKind::Move(cmd) => {
+                let movement = cmd.to_movement(count);
+                self.move_cursor(state, &movement, count.unwrap_or(1), mods.shift());
+            }
+            CommandKind::MotionMode(cmd) => {
+                let motion_mode = match cmd {
+                    MotionModeCommand::MotionModeDelete => MotionMode::Delete,
+                    MotionModeCommand::MotionModeIndent => MotionMode::Indent,
+                    MotionModeCommand::MotionModeOutdent => MotionMode::Outdent,
+                    MotionModeCommand::MotionModeYank => MotionMode::Yank,
+                };
+                self.copy.view.doc.update(|doc| {
+                    movement::do_motion_mode(
+                        doc,
+                        &mut cursor,
+                        motion_mode,
+                        &mut state.register,
+                    );
+                });
+                self.copy.cursor.set(cursor);
+            }
+            CommandKind::MultiSelection(cmd) => {
+                movement::do_multi_selection(&self.copy.view, &mut cursor, cmd);
+                self.copy.cursor.set(cursor);
+            }
+            CommandKind::Focus(
+                FocusCommand::InlineFindLeft | FocusCommand::InlineFindRight,
+            ) => {
+                let direction = match &command.kind {
+                    CommandKind::Focus(FocusCommand::InlineFindLeft) => {
+                        InlineFindDirection::Left
+                    }
+                    _ => InlineFindDirection::Right,
+                };
+                self.copy.inline_find.set(Some(direction));
+                state.outcome.push("finds the next key typed".to_string());
+                return CommandExecuted::Yes;
+            }
+            CommandKind::Workbench(_) | CommandKind::Focus(_) => {
+                state
+                    .outcome
+                    .push("acts outside of the text, not simulated".to_string());
+                return CommandExecuted::Yes;
+            }
+        }
+        self.report(state, &before);
+        CommandExecuted::Yes
+    }
+
+    fn expect_char(&self) -> bool {
+        self.copy.inline_find.with_untracked(|f| f.is_some())
+            || self.copy.register_prefix.get_untracked()
+    }
+
+    fn is_dry_run(&self) -> bool {
+        true
+    }
+
+    fn receive_char(&self, c: &str) {
+        let mut state = self.state.borrow_mut();
+        let state = &mut *state;
+        let before = self.text();
+        if self.get_mode() == Mode::Insert {
+            state.outcome.push(format!("types {c:?}"));
+            let mut cursor = self.copy.cursor.get_untracked();
+            self.copy.view.doc.update(|doc| {
+                doc.do_insert(&mut cursor, c, &self.config);
+            });
+            self.copy.cursor.set(cursor);
+        } else if let Some(direction) = self.copy.inline_find.get_untracked() {
+            self.copy.inline_find.set(None);
+            match self.copy.inline_find_offset(direction, c) {
+                Some(offset) => {
+                    self.move_cursor(state, &Movement::Offset(offset), 1, false)
+                }
+                None => {
+                    state.outcome.push(format!("finds no {c:?} on the line"));
+                    return;
+                }
+            }
+        } else if self.copy.register_prefix.get_untracked() {
+            self.copy.register_prefix.set(false);
+            let name = c.chars().next().filter(|c| Register::is_valid_name(*c));
+            state.register.selected = name;
+            match name {
+                Some(name) => {
+                    state.outcome.push(format!("selects the register {name}"))
+                }
+                None => state.outcome.push(format!("{c:?} isn't a register")),
+            }
+            return;
+        } else if c == "\"" {
+            self.copy.register_prefix.set(true);
+            state
+                .outcome
+                .push("names a register with the next key".to_string());
+            return;
+        } else {
+            state
+                .outcome
+                .push(format!("{c:?} is ignored in {:?} mode", self.get_mode()));
+            return;
+        }
+        self.report(state, &before);
+    }
+}
+
+/// The change between the texts, as the range of the text before that was
+/// replaced and what it was replaced with
+fn describe_edit(before: &Rope, after: &Rope) -> Option<String> {
+    if before.ptr_eq(after) {
+        return None;
+    }
+    let before = before.to_string();
+    let after = after.to_string();
+    if before == after {
+        return None;
+    }
+    let prefix = before
+        .char_indices()
+        .zip(after.chars())
+        .find(|((_, b), a)| b != a)
+        .map_or(before.len().min(after.len()), |((i, _), _)| i);
+    let suffix = before[prefix..]
+        .chars()
+        .rev()
+        .zip(after[prefix..].chars().rev())
+        .take_while(|(b, a)| b == a)
+        .map(|(b, _)| b.len_utf8())
+        .sum::<usize>();
+    let removed = &before[prefix..before.len() - suffix];
+    let inserted = &after[prefix..after.len() - suffix];
+    Some(format!(
+        "edits {prefix}..{}: {removed:?} becomes {inserted:?}",
+        before.len() - suffix
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_key() {
+        let press = parse_key("W").unwrap();
+        assert_eq!(press.key, Key::Keyboard(KbKey::Character("W".to_string())));
+        assert_eq!(press.mods, Modifiers::SHIFT);
+
+        let press = parse_key("shift+w").unwrap();
+        assert_eq!(press.key, Key::Keyboard(KbKey::Character("W".to_string())));
+        assert_eq!(press.mods, Modifiers::SHIFT);
+
+        let press = parse_key("ctrl+w").unwrap();
+        assert_eq!(press.key, Key::Keyboard(KbKey::Character("w".to_string())));
+        assert_eq!(press.mods, Modifiers::CONTROL);
+    }
+
+    #[test]
+    fn test_describe_edit() {
+        let before = Rope::from("let a = 1;\nlet b = 2;\n");
+        assert_eq!(describe_edit(&before, &before.clone()), None);
+
+        // `dw` on the `a`
+        let after = Rope::from("let = 1;\nlet b = 2;\n");
+        assert_eq!(
+            describe_edit(&before, &after).unwrap(),
+            "edits 4..6: \"a \" becomes \"\""
+        );
+
+        // `dd` on the first line, which the shortest change covering it
+        // leaves the start of the next line of
+        let after = Rope::from("let b = 2;\n");
+        assert_eq!(
+            describe_edit(&before, &after).unwrap(),
+            "edits 4..15: \"a = 1;\\nlet \" becomes \"\""
+        );
+
+        // Typing within repeated characters takes the first of them
+        let before = Rope::from("aaa");
+        let after = Rope::from("aaaa");
+        assert_eq!(
+            describe_edit(&before, &after).unwrap(),
+            "edits 3..3: \"\" becomes \"a\""
+        );
+    }
+}
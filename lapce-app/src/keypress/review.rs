@@ -0,0 +1,435 @@
+//! The review of the default keymaps an update changed. The defaults the user
+//! last reviewed are kept, and the commands whose keys changed since are
+//! listed against the user's own keymaps, so each of them can be moved to the
+//! new keys or kept on the old ones.
+
+use anyhow::{anyhow, Result};
+use indexmap::IndexMap;
+use lapce_core::mode::Modes;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    KeyPressData, DEFAULT_KEYMAPS_COMMON, DEFAULT_KEYMAPS_MACOS,
+    DEFAULT_KEYMAPS_NONMACOS,
+};
+use crate::db::LapceDb;
+
+/// A keymap as it's written in a keymaps file
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct KeymapEntry {
+    pub key: String,
+    pub command: String,
+    pub when: Option<String>,
+    pub mode: Option<String>,
+}
+
+impl KeymapEntry {
+    fn from_table(table: &toml_edit::Table) -> Option<Self> {
+        let get = |name: &str| {
+            table
+                .get(name)
+                .and_then(|v| v.as_str())
+                .map(|v| v.trim().to_string())
+        };
+        Some(Self {
+            key: normalize_key(&get("key")?),
+            command: get("command").unwrap_or_default(),
+            when: get("when"),
+            mode: get("mode"),
+        })
+    }
+
+    fn to_table(&self) -> toml_edit::Table {
+        let mut table = toml_edit::Table::new();
+        table.insert("key", toml_edit::value(self.key.as_str()));
+        table.insert("command", toml_edit::value(self.command.as_str()));
+        if let Some(when) = &self.when {
+            table.insert("when", toml_edit::value(when.as_str()));
+        }
+        if let Some(mode) = &self.mode {
+            table.insert("mode", toml_edit::value(mode.as_str()));
+        }
+        table
+    }
+
+    /// The command, the condition and the modes the keymap binds its key for,
+    /// which the changes are grouped by
+    fn binding(&self) -> (&str, Option<&str>, Option<&str>) {
+        (
+            self.command.strip_prefix('-').unwrap_or(&self.command),
+            self.when.as_deref(),
+            self.mode.as_deref(),
+        )
+    }
+}
+
+/// How a change is resolved
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeymapResolution {
+    /// Drop the user's keymaps for the command, so the new defaults apply
+    Adopt,
+    /// Bind the command to the keys it had before in the user's keymaps
+    KeepMine,
+}
+
+/// The keys of a command that changed between the defaults last reviewed and
+/// the current ones
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeymapChange {
+    pub command: String,
+    pub when: Option<String>,
+    pub mode: Option<String>,
+    /// The keys of the command in the reviewed defaults
+    pub old_keys: Vec<String>,
+    /// The keys of the command in the current defaults
+    pub new_keys: Vec<String>,
+    /// The keymaps of the user's file that bind or unbind the command
+    pub overrides: Vec<KeymapEntry>,
+}
+
+impl KeymapChange {
+    /// What changed, like `ctrl+k changed to ctrl+shift+k`
+    pub fn description(&self) -> String {
+        let old = self.old_keys.join(", ");
+        let new = self.new_keys.join(", ");
+        let mut description = if self.old_keys.is_empty() {
+            format!("added on {new}")
+        } else if self.new_keys.is_empty() {
+            format!("removed from {old}")
+        } else {
+            format!("{old} changed to {new}")
+        };
+        if let Some(when) = &self.when {
+            description.push_str(&format!(" when {when}"));
+        }
+        if !self.overrides.is_empty() {
+            description.push_str(", you have your own keymaps for it");
+        }
+        description
+    }
+
+    fn entries<'a>(
+        &'a self,
+        keys: &'a [String],
+        unbind: bool,
+    ) -> impl Iterator<Item = KeymapEntry> + 'a {
+        keys.iter().map(move |key| KeymapEntry {
+            key: key.clone(),
+            command: if unbind {
+                format!("-{}", self.command)
+            } else {
+                self.command.clone()
+            },
+            when: self.when.clone(),
+            mode: self.mode.clone(),
+        })
+    }
+
+    fn is_binding(&self, entry: &KeymapEntry) -> bool {
+        entry.binding()
+            == (
+                self.command.as_str(),
+                self.when.as_deref(),
+                self.mode.as_deref(),
+            )
+    }
+}
+
+/// The keymaps of a keymaps file, in the order they're written
+pub fn parse_keymaps(s: &str) -> Result<Vec<KeymapEntry>> {
+    let document: toml_edit::Document = s.parse()?;
+    let Some(keymaps) = document.get("keymaps") else {
+        return Ok(Vec::new());
+    };
+    let keymaps = keymaps
+        .as_array_of_tables()
+        .ok_or_else(|| anyhow!("keymaps isn't an array of tables"))?;
+    Ok(keymaps.iter().filter_map(KeymapEntry::from_table).collect())
+}
+
+/// The default keymaps of this build
+pub fn default_keymaps() -> Vec<KeymapEntry> {
+    let os_keymaps = if std::env::consts::OS == "macos" {
+        DEFAULT_KEYMAPS_MACOS
+    } else {
+        DEFAULT_KEYMAPS_NONMACOS
+    };
+    [DEFAULT_KEYMAPS_COMMON, os_keymaps]
+        .into_iter()
+        .flat_map(|s| parse_keymaps(s).unwrap_or_default())
+        .collect()
+}
+
+/// The commands whose keys differ between the reviewed and the current
+/// defaults, in the order of the current ones, with the keymaps the user has
+/// for them. The keymaps that only apply to modal editing are left out
+/// without it.
+pub fn keymap_changes(
+    reviewed: &[KeymapEntry],
+    defaults: &[KeymapEntry],
+    user: &[KeymapEntry],
+    modal: bool,
+) -> Vec<KeymapChange> {
+    type Keys = (Vec<String>, Vec<String>);
+    let mut bindings: IndexMap<(&str, Option<&str>, Option<&str>), Keys> =
+        IndexMap::new();
+    for entry in defaults {
+        bindings
+            .entry(entry.binding())
+            .or_default()
+            .1
+            .push(entry.key.clone());
+    }
+    for entry in reviewed {
+        bindings
+            .entry(entry.binding())
+            .or_default()
+            .0
+            .push(entry.key.clone());
+    }
+
+    bindings
+        .into_iter()
+        .filter(|(_, (old_keys, new_keys))| {
+            let mut old_keys = old_keys.clone();
+            let mut new_keys = new_keys.clone();
+            old_keys.sort();
+            new_keys.sort();
+            old_keys != new_keys
+        })
+        .map(
+            |((command, when, mode), (old_keys, new_keys))| KeymapChange {
+                command: command.to_string(),
+                when: when.map(|w| w.to_string()),
+                mode: mode.map(|m| m.to_string()),
+                old_keys,
+                new_keys,
+                overrides: Vec::new(),
+            },
+        )
+        .filter(|change| modal || !is_modal_only(change.mode.as_deref()))
+        .map(|mut change| {
+            change.overrides = user
+                .iter()
+                .filter(|entry| change.is_binding(entry))
+                .cloned()
+                .collect();
+            change
+        })
+        .collect()
+}
+
+/// The reviewed defaults with the change taken as reviewed
+fn mark_reviewed(reviewed: &mut Vec<KeymapEntry>, change: &KeymapChange) {
+    reviewed.retain(|entry| !change.is_binding(entry));
+    reviewed.extend(change.entries(&change.new_keys, false));
+}
+
+/// The content of the user's keymaps file with the change resolved
+pub fn resolve_keymaps_file(
+    content: &str,
+    change: &KeymapChange,
+    resolution: KeymapResolution,
+) -> Result<String> {
+    let mut document: toml_edit::Document = content.parse()?;
+    if !document.contains_key("keymaps") {
+        document.insert(
+            "keymaps",
+            toml_edit::Item::ArrayOfTables(toml_edit::ArrayOfTables::new()),
+        );
+    }
+    let keymaps = document
+        .get_mut("keymaps")
+        .and_then(|keymaps| keymaps.as_array_of_tables_mut())
+        .ok_or_else(|| anyhow!("keymaps isn't an array of tables"))?;
+
+    match resolution {
+        KeymapResolution::Adopt => {
+            for i in (0..keymaps.len()).rev() {
+                let is_override = keymaps
+                    .get(i)
+                    .and_then(KeymapEntry::from_table)
+                    .map(|entry| change.is_binding(&entry))
+                    .unwrap_or(false);
+                if is_override {
+                    keymaps.remove(i);
+                }
+            }
+        }
+        KeymapResolution::KeepMine => {
+            // The old keys are bound back, and the new ones unbound
+            let existing: Vec<KeymapEntry> =
+                keymaps.iter().filter_map(KeymapEntry::from_table).collect();
+            let added: Vec<String> = change
+                .new_keys
+                .iter()
+                .filter(|key| !change.old_keys.contains(key))
+                .cloned()
+                .collect();
+            let removed: Vec<String> = change
+                .old_keys
+                .iter()
+                .filter(|key| !change.new_keys.contains(key))
+                .cloned()
+                .collect();
+            for entry in change
+                .entries(&removed, false)
+                .chain(change.entries(&added, true))
+            {
+                if !existing.contains(&entry) {
+                    keymaps.push(entry.to_table());
+                }
+            }
+        }
+    }
+
+    Ok(document.to_string())
+}
+
+impl KeyPressData {
+    /// The changes of the default keymaps the user hasn't reviewed. The
+    /// first time, the current defaults are taken as reviewed, since there's
+    /// nothing they changed from.
+    pub fn unreviewed_keymap_changes(
+        db: &LapceDb,
+        modal: bool,
+    ) -> Vec<KeymapChange> {
+        let defaults = default_keymaps();
+        let Ok(reviewed) = db.get_reviewed_keymaps() else {
+            db.save_reviewed_keymaps(defaults);
+            return Vec::new();
+        };
+        let user = Self::file()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|content| parse_keymaps(&content).ok())
+            .unwrap_or_default();
+        keymap_changes(&reviewed, &defaults, &user, modal)
+    }
+
+    /// Resolve the change in the user's keymaps file, and take it as reviewed
+    pub fn resolve_keymap_change(
+        db: &LapceDb,
+        change: &KeymapChange,
+        resolution: KeymapResolution,
+    ) -> Result<()> {
+        let path =
+            Self::file().ok_or_else(|| anyhow!("can't get the keymaps file"))?;
+        let content = std::fs::read_to_string(&path).unwrap_or_default();
+        let content = resolve_keymaps_file(&content, change, resolution)?;
+        std::fs::write(&path, content)?;
+
+        let mut reviewed = db.get_reviewed_keymaps().unwrap_or_default();
+        mark_reviewed(&mut reviewed, change);
+        db.save_reviewed_keymaps(reviewed);
+        Ok(())
+    }
+}
+
+/// Whether keymaps of the modes only apply to modal editing, which they're
+/// ignored without, as the loader does
+fn is_modal_only(mode: Option<&str>) -> bool {
+    let modes = mode.map(Modes::parse).unwrap_or(Modes::empty());
+    !modes.is_empty()
+        && !modes.contains(Modes::INSERT)
+        && !modes.contains(Modes::TERMINAL)
+}
+
+/// The key as the keymaps compare it, lower case with single spaces between
+/// the key presses
+fn normalize_key(key: &str) -> String {
+    key.split_whitespace()
+        .map(|press| press.to_lowercase())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(key: &str, command: &str) -> KeymapEntry {
+        KeymapEntry {
+            key: key.to_string(),
+            command: command.to_string(),
+            when: None,
+            mode: None,
+        }
+    }
+
+    #[test]
+    fn test_keymap_changes() {
+        let reviewed = vec![
+            entry("ctrl+k", "kill_line"),
+            entry("ctrl+s", "save"),
+            entry("ctrl+q", "quit"),
+        ];
+        let defaults = vec![
+            entry("ctrl+shift+k", "kill_line"),
+            entry("ctrl+s", "save"),
+            entry("ctrl+d", "duplicate"),
+        ];
+        let user = vec![entry("ctrl+k", "-kill_line"), entry("ctrl+w", "save")];
+
+        let changes = keymap_changes(&reviewed, &defaults, &user, false);
+        let summary: Vec<(&str, Vec<String>, Vec<String>, usize)> = changes
+            .iter()
+            .map(|c| {
+                (
+                    c.command.as_str(),
+                    c.old_keys.clone(),
+                    c.new_keys.clone(),
+                    c.overrides.len(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    "kill_line",
+                    vec!["ctrl+k".to_string()],
+                    vec!["ctrl+shift+k".to_string()],
+                    1
+                ),
+                ("duplicate", vec![], vec!["ctrl+d".to_string()], 0),
+                ("quit", vec!["ctrl+q".to_string()], vec![], 0),
+            ]
+        );
+
+        let mut reviewed = reviewed;
+        for change in &changes {
+            mark_reviewed(&mut reviewed, change);
+        }
+        assert!(keymap_changes(&reviewed, &defaults, &user, false).is_empty());
+    }
+
+    #[test]
+    fn test_resolve_keymaps_file() {
+        let change = KeymapChange {
+            command: "kill_line".to_string(),
+            when: None,
+            mode: None,
+            old_keys: vec!["ctrl+k".to_string()],
+            new_keys: vec!["ctrl+shift+k".to_string()],
+            overrides: Vec::new(),
+        };
+        let content = "[[keymaps]]\nkey = \"ctrl+k\"\ncommand = \"-kill_line\"\n\n[[keymaps]]\nkey = \"ctrl+w\"\ncommand = \"save\"\n";
+
+        let adopted =
+            resolve_keymaps_file(content, &change, KeymapResolution::Adopt).unwrap();
+        assert_eq!(
+            parse_keymaps(&adopted).unwrap(),
+            vec![entry("ctrl+w", "save")]
+        );
+
+        let kept =
+            resolve_keymaps_file("", &change, KeymapResolution::KeepMine).unwrap();
+        assert_eq!(
+            parse_keymaps(&kept).unwrap(),
+            vec![
+                entry("ctrl+k", "kill_line"),
+                entry("ctrl+shift+k", "-kill_line")
+            ]
+        );
+    }
+}
@@ -1,36 +1,63 @@
 pub mod app;
+pub mod burn_down;
 pub mod code_action;
 pub mod command;
+pub mod commit_message;
 pub mod completion;
 pub mod config;
 pub mod db;
 pub mod debug;
 pub mod doc;
+pub mod edit_session;
 pub mod editor;
 pub mod editor_tab;
 pub mod file_explorer;
 pub mod find;
 pub mod focus_text;
+pub mod frecency;
+pub mod fuzzy;
 pub mod global_search;
+#[cfg(test)]
+mod golden;
 pub mod history;
 pub mod id;
+pub mod inline_suggestion;
 pub mod keypress;
 pub mod listener;
 pub mod main_split;
+pub mod markdown;
+pub mod notebook;
+pub mod overlay;
 pub mod palette;
 pub mod panel;
 pub mod plugin;
+pub mod problem;
+pub mod profile;
 pub mod proxy;
+pub mod rebase_todo;
+pub mod regex_tester;
+pub mod registers;
 pub mod rename;
+pub mod replay;
+pub mod safe_mode;
+pub mod scratchpad;
+pub mod search_history;
+pub mod search_results;
 pub mod settings;
+pub mod shortcuts;
+pub mod signature;
 pub mod snippet;
 pub mod source_control;
+pub mod status_template;
+pub mod tabstops;
 pub mod terminal;
 pub mod text_area;
 pub mod text_input;
+pub mod timer;
 pub mod title;
 pub mod update;
 pub mod wave;
+pub mod welcome;
 pub mod window;
 pub mod window_tab;
 pub mod workspace;
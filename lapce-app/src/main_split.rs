@@ -1,32 +1,55 @@
 use std::{
-    collections::HashMap,
+    cell::{Cell, RefCell},
+    collections::{HashMap, HashSet},
     path::{Path, PathBuf},
+    rc::Rc,
+    sync::Arc,
+    time::Duration,
 };
 
+use encoding_rs::{Encoding, UTF_8};
 use floem::{
     ext_event::create_ext_action,
     glazier::KeyEvent,
     peniko::kurbo::{Point, Rect, Vec2},
     reactive::{
-        create_effect, create_memo, create_rw_signal, Memo, RwSignal, Scope,
-        SignalGet, SignalGetUntracked, SignalSet, SignalUpdate, SignalWith,
+        create_effect, create_memo, create_rw_signal, use_context, Memo, RwSignal,
+        Scope, SignalGet, SignalGetUntracked, SignalSet, SignalUpdate, SignalWith,
         SignalWithUntracked,
     },
 };
 use itertools::Itertools;
 use lapce_core::{
-    buffer::rope_text::RopeText, cursor::Cursor, selection::Selection,
+    buffer::{
+        diff::{diff_hunks, DiffHunk},
+        rope_text::RopeText,
+    },
+    cursor::Cursor,
+    editor::EditType,
+    language::plugin_grammars_rev,
+    selection::Selection,
+};
+use lapce_rpc::{
+    buffer::BufferId,
+    file::{archive_member, FileChange},
+    plugin::{PluginId, VoltID},
+    proxy::ProxyResponse,
 };
-use lapce_rpc::{plugin::PluginId, proxy::ProxyResponse};
 use lapce_xi_rope::Rope;
 use lsp_types::{
-    CodeAction, CodeActionOrCommand, DiagnosticSeverity, DocumentChangeOperation,
-    DocumentChanges, OneOf, Position, TextEdit, Url, WorkspaceEdit,
+    CodeAction, CodeActionOrCommand, Diagnostic, DiagnosticSeverity,
+    DocumentChangeOperation, DocumentChanges, OneOf, Position, TextEdit, Url,
+    WorkspaceEdit,
 };
 use serde::{Deserialize, Serialize};
+use tracing::error;
 
 use crate::{
-    doc::{DiagnosticData, DocContent, DocHistory, Document, EditorDiagnostic},
+    db::LapceDb,
+    doc::{
+        DiagnosticData, DocContent, DocHistory, Document, EditorDiagnostic, FileGone,
+    },
+    edit_session::{EditSession, EditSessionError},
     editor::{
         diff::DiffEditorData,
         location::{EditorLocation, EditorPosition},
@@ -35,11 +58,30 @@
     editor_tab::{
         EditorTabChild, EditorTabChildSource, EditorTabData, EditorTabInfo,
     },
+    frecency,
     id::{DiffEditorId, EditorId, EditorTabId, SettingsId, SplitId},
     keypress::KeyPressData,
+    notebook::{self, Notebook},
+    timer::exec_after,
     window_tab::{CommonData, Focus, WindowTabData},
+    workspace::LapceWorkspaceType,
 };
 
+/// How long the popup of a diagnostic jumped to stays visible
+const DIAGNOSTIC_POPUP_DURATION: Duration = Duration::from_secs(5);
+
+/// The smallest size the children of a split can be resized to
+const SPLIT_MIN_SIZE: f64 = 50.0;
+
+/// How many closed editors are remembered to be reopened
+const CLOSED_EDITORS_LIMIT: usize = 50;
+
+/// How long an edit session waits for its documents to be loaded
+const EDIT_SESSION_LOAD_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long to wait for the changes of a file jumped to, which are only known
+/// once its text at `HEAD` is retrieved, before leaving it at its start
+const CHANGE_JUMP_TIMEOUT: Duration = Duration::from_secs(5);
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SplitDirection {
     Vertical,
@@ -54,7 +96,7 @@ pub enum SplitMoveDirection {
     Left,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum SplitContent {
     EditorTab(EditorTabId),
     Split(SplitId),
@@ -102,6 +144,9 @@ pub struct SplitData {
     pub parent_split: Option<SplitId>,
     pub split_id: SplitId,
     pub children: Vec<SplitContent>,
+    /// The share of the split the children take relative to each other, for
+    /// the ones that were resized from the default of 1.0
+    pub sizes: im::HashMap<SplitContent, f64>,
     pub direction: SplitDirection,
     pub window_origin: Point,
     pub layout_rect: Rect,
@@ -110,6 +155,9 @@ pub struct SplitData {
 #[derive(Clone, Serialize, Deserialize)]
 pub struct SplitInfo {
     pub children: Vec<SplitContentInfo>,
+    /// The sizes of the children, in the same order
+    #[serde(default)]
+    pub sizes: Vec<f64>,
     pub direction: SplitDirection,
 }
 
@@ -122,16 +170,19 @@ pub fn to_data(
     ) -> RwSignal<SplitData> {
         let split_data = {
             let (cx, _) = data.scope.run_child_scope(|cx| cx);
+            let children: Vec<SplitContent> = self
+                .children
+                .iter()
+                .map(|child| child.to_data(data.clone(), split_id))
+                .collect();
+            let sizes = children.iter().copied().zip(self.sizes.iter().copied());
             let split_data = SplitData {
                 scope: cx,
                 split_id,
                 direction: self.direction,
                 parent_split,
-                children: self
-                    .children
-                    .iter()
-                    .map(|child| child.to_data(data.clone(), split_id))
-                    .collect(),
+                sizes: sizes.collect(),
+                children,
                 window_origin: Point::ZERO,
                 layout_rect: Rect::ZERO,
             };
@@ -181,9 +232,32 @@ pub fn split_info(&self, data: &WindowTabData) -> SplitInfo {
                 .iter()
                 .map(|child| child.content_info(data))
                 .collect(),
+            sizes: self.children.iter().map(|child| self.size(child)).collect(),
         };
         info
     }
+
+    /// The share of the split the child takes relative to the other ones
+    pub fn size(&self, content: &SplitContent) -> f64 {
+        self.sizes.get(content).copied().unwrap_or(1.0)
+    }
+}
+
+#[derive(Clone, PartialEq)]
+pub struct DiagnosticPopup {
+    pub path: PathBuf,
+    pub diagnostic: Diagnostic,
+}
+
+/// Two editors that scroll together, for reading files side by side. The
+/// line at the top of the second is `lines.1 + (line - lines.0) * ratio` when
+/// the first has `line` at its top, the lines being the ones at their tops
+/// when they were locked, and the ratio that of their numbers of lines.
+#[derive(Clone, Copy, PartialEq)]
+pub struct ScrollLock {
+    pub editors: (EditorId, EditorId),
+    pub lines: (f64, f64),
+    pub ratio: f64,
 }
 
 #[derive(Clone)]
@@ -197,11 +271,22 @@ pub struct MainSplitData {
     pub diff_editors: RwSignal<im::HashMap<DiffEditorId, DiffEditorData>>,
     pub docs: RwSignal<im::HashMap<PathBuf, RwSignal<Document>>>,
     pub diagnostics: RwSignal<im::HashMap<PathBuf, DiagnosticData>>,
+    /// The diagnostic just jumped to, shown in a popup at the cursor
+    pub diagnostic_popup: RwSignal<Option<DiagnosticPopup>>,
     pub active_editor: Memo<Option<RwSignal<EditorData>>>,
     pub find_editor: EditorData,
     pub replace_editor: EditorData,
     pub locations: RwSignal<im::Vector<EditorLocation>>,
     pub current_location: RwSignal<usize>,
+    /// Where the files of the editors closed recently were left, the last
+    /// closed at the end
+    pub closed_editors: RwSignal<im::Vector<EditorLocation>>,
+    /// The editor tab shown over the whole editor area, the other splits
+    /// being hidden with their sizes kept until it's restored
+    pub maximized_editor_tab: RwSignal<Option<EditorTabId>>,
+    pub scroll_lock: RwSignal<Option<ScrollLock>>,
+    /// The revision of the plugin grammars the documents' syntax was made with
+    grammars_rev: RwSignal<u64>,
     pub common: CommonData,
 }
 
@@ -218,7 +303,12 @@ pub fn new(cx: Scope, common: CommonData) -> Self {
         let docs = create_rw_signal(cx, im::HashMap::new());
         let locations = create_rw_signal(cx, im::Vector::new());
         let current_location = create_rw_signal(cx, 0);
+        let closed_editors = create_rw_signal(cx, im::Vector::new());
+        let maximized_editor_tab = create_rw_signal(cx, None);
+        let scroll_lock = create_rw_signal(cx, None);
+        let grammars_rev = create_rw_signal(cx, plugin_grammars_rev());
         let diagnostics = create_rw_signal(cx, im::HashMap::new());
+        let diagnostic_popup = create_rw_signal(cx, None);
         let find_editor =
             EditorData::new_local(cx, EditorId::next(), common.clone());
         let replace_editor =
@@ -254,6 +344,66 @@ pub fn new(cx: Scope, common: CommonData) -> Self {
                 Some(editor)
             });
 
+        // Restore the splits once another editor tab gets the focus, such as
+        // when the maximized one is closed
+        create_effect(cx, move |_| {
+            let active = active_editor_tab.get();
+            let maximized = maximized_editor_tab.get_untracked();
+            if maximized.is_some() && maximized != active {
+                maximized_editor_tab.set(None);
+            }
+        });
+
+        {
+            // The editor that was last scrolled to follow the other, whose
+            // scrolling doesn't move the other back
+            let following: Rc<Cell<Option<EditorId>>> = Rc::new(Cell::new(None));
+            let config = common.config;
+            create_effect(cx, move |last: Option<Option<(f64, f64)>>| {
+                let lock: ScrollLock = scroll_lock.get()?;
+                let (first, second) = editors.with(|editors| {
+                    (
+                        editors.get(&lock.editors.0).copied(),
+                        editors.get(&lock.editors.1).copied(),
+                    )
+                });
+                let (Some(first), Some(second)) = (first, second) else {
+                    // One of them was closed
+                    scroll_lock.set(None);
+                    return None;
+                };
+                let first =
+                    first.with(|e| (e.editor_id, e.viewport.get(), e.scroll_to));
+                let second =
+                    second.with(|e| (e.editor_id, e.viewport.get(), e.scroll_to));
+                let tops = (first.1.y0, second.1.y0);
+                let Some(Some(last)) = last else {
+                    return Some(tops);
+                };
+
+                // The editor that was scrolled moves the other to its line
+                let line_height = config.get_untracked().editor.line_height() as f64;
+                let followed = following.take();
+                let target = if tops.0 != last.0 && followed != Some(first.0) {
+                    let line = tops.0 / line_height - lock.lines.0;
+                    Some((second, lock.lines.1 + line * lock.ratio))
+                } else if tops.1 != last.1 && followed != Some(second.0) {
+                    let line = tops.1 / line_height - lock.lines.1;
+                    Some((first, lock.lines.0 + line / lock.ratio))
+                } else {
+                    None
+                };
+                if let Some(((editor_id, viewport, scroll_to), line)) = target {
+                    let top = (line * line_height).max(0.0);
+                    if (viewport.y0 - top).abs() > 1.0 {
+                        following.set(Some(editor_id));
+                        scroll_to.set(Some(Vec2::new(viewport.x0, top)));
+                    }
+                }
+                Some(tops)
+            });
+        }
+
         {
             let find_editor_doc = find_editor.view.doc;
             let find = common.find.clone();
@@ -263,6 +413,16 @@ pub fn new(cx: Scope, common: CommonData) -> Self {
             });
         }
 
+        {
+            let replace_editor_doc = replace_editor.view.doc;
+            let replace_string = common.find.replace_string;
+            create_effect(cx, move |_| {
+                let content =
+                    replace_editor_doc.with(|doc| doc.buffer().to_string());
+                replace_string.set(content);
+            });
+        }
+
         Self {
             scope: cx,
             root_split: SplitId::next(),
@@ -276,12 +436,89 @@ pub fn new(cx: Scope, common: CommonData) -> Self {
             find_editor,
             replace_editor,
             diagnostics,
+            diagnostic_popup,
             locations,
             current_location,
+            closed_editors,
+            maximized_editor_tab,
+            scroll_lock,
+            grammars_rev,
             common,
         }
     }
 
+    /// Lock the active editor to scroll together with the editor of the
+    /// nearest other editor tab, or unlock the editors that are locked
+    pub fn toggle_scroll_lock(&self) {
+        if self.scroll_lock.get_untracked().is_some() {
+            self.scroll_lock.set(None);
+            return;
+        }
+
+        let Some(active_tab) = self.active_editor_tab.get_untracked() else {
+            return;
+        };
+        let tabs: Vec<(EditorTabId, Point, Option<EditorId>)> =
+            self.editor_tabs.with_untracked(|editor_tabs| {
+                editor_tabs
+                    .iter()
+                    .map(|(id, editor_tab)| {
+                        editor_tab.with_untracked(|editor_tab| {
+                            let editor_id = match editor_tab
+                                .children
+                                .get(editor_tab.active)
+                                .map(|(_, child)| child)
+                            {
+                                Some(EditorTabChild::Editor(editor_id)) => {
+                                    Some(*editor_id)
+                                }
+                                _ => None,
+                            };
+                            (*id, editor_tab.window_origin, editor_id)
+                        })
+                    })
+                    .collect()
+            });
+        let Some((_, origin, Some(active))) =
+            tabs.iter().find(|(id, _, _)| *id == active_tab).copied()
+        else {
+            return;
+        };
+        let Some(other) = tabs
+            .iter()
+            .filter(|(id, _, _)| *id != active_tab)
+            .filter_map(|(_, o, editor_id)| {
+                Some((o.distance(origin), (*editor_id)?))
+            })
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|(_, editor_id)| editor_id)
+        else {
+            return;
+        };
+
+        let line_height =
+            self.common.config.get_untracked().editor.line_height() as f64;
+        let top_and_lines = |editor_id: EditorId| {
+            let editor = self
+                .editors
+                .with_untracked(|editors| editors.get(&editor_id).copied())?;
+            let (viewport, doc) =
+                editor.with_untracked(|editor| (editor.viewport, editor.view.doc));
+            let lines = doc.with_untracked(|doc| doc.buffer().num_lines());
+            Some((viewport.get_untracked().y0 / line_height, lines as f64))
+        };
+        let (Some((active_top, active_lines)), Some((other_top, other_lines))) =
+            (top_and_lines(active), top_and_lines(other))
+        else {
+            return;
+        };
+        self.scroll_lock.set(Some(ScrollLock {
+            editors: (active, other),
+            lines: (active_top, other_top),
+            ratio: other_lines.max(1.0) / active_lines.max(1.0),
+        }));
+    }
+
     pub fn key_down(
         &self,
         key_event: &KeyEvent,
@@ -394,8 +631,30 @@ pub fn jump_to_location(
         self.go_to_location(location, edits);
     }
 
+    /// The canonical path of the file, if the workspace is local, along with
+    /// the path it's opened through if that goes through a symlink
+    fn canonical_path(&self, path: &Path) -> (PathBuf, Option<PathBuf>) {
+        if self.common.workspace.kind != LapceWorkspaceType::Local {
+            return (path.to_path_buf(), None);
+        }
+        canonical_path(path)
+    }
+
+    /// The open document of the file, however the path reaches it
+    pub fn doc_of_path(&self, path: &Path) -> Option<RwSignal<Document>> {
+        let doc = self.docs.with_untracked(|docs| docs.get(path).copied());
+        if doc.is_some() {
+            return doc;
+        }
+        let (path, _) = self.canonical_path(path);
+        self.docs.with_untracked(|docs| docs.get(&path).copied())
+    }
+
     pub fn get_doc(&self, path: PathBuf) -> (RwSignal<Document>, bool) {
         let cx = self.scope;
+        // The same file is only ever opened in one document, however it's
+        // reached
+        let (path, _) = self.canonical_path(&path);
         let doc = self.docs.with_untracked(|docs| docs.get(&path).cloned());
         if let Some(doc) = doc {
             (doc, false)
@@ -433,19 +692,25 @@ pub fn get_doc(&self, path: PathBuf) -> (RwSignal<Document>, bool) {
             {
                 let buffer_id = doc.with_untracked(|doc| doc.buffer_id);
                 let set_doc = doc.write_only();
-                let send = create_ext_action(self.scope, move |content| {
-                    set_doc.update(move |doc| {
-                        doc.init_content(content);
+                let send =
+                    create_ext_action(self.scope, move |(content, encoding)| {
+                        set_doc.update(move |doc| {
+                            doc.encoding = encoding;
+                            doc.init_content(content);
+                        });
                     });
-                });
 
                 self.common
                     .proxy
                     .new_buffer(buffer_id, path, move |result| {
-                        if let Ok(ProxyResponse::NewBufferResponse { content }) =
-                            result
+                        if let Ok(ProxyResponse::NewBufferResponse {
+                            content,
+                            encoding,
+                        }) = result
                         {
-                            send(Rope::from(content))
+                            let encoding = Encoding::for_label(encoding.as_bytes())
+                                .unwrap_or(UTF_8);
+                            send((Rope::from(content), encoding))
                         }
                     });
             }
@@ -458,12 +723,45 @@ pub fn go_to_location(
         &self,
         location: EditorLocation,
         edits: Option<Vec<TextEdit>>,
+    ) {
+        if notebook::is_notebook(&location.path) {
+            self.open_notebook(location.path);
+            return;
+        }
+        if archive_member(&location.path).is_some() {
+            self.open_archive_member(location.path);
+            return;
+        }
+        self.open_location(location, edits);
+    }
+
+    /// Open the file at the location in an editor, as text whatever its type.
+    pub fn open_location(
+        &self,
+        location: EditorLocation,
+        edits: Option<Vec<TextEdit>>,
     ) {
         if self.common.focus.get_untracked() != Focus::Workbench {
             self.common.focus.set(Focus::Workbench);
         }
-        let path = location.path.clone();
+        let (path, symlink) = self.canonical_path(&location.path);
+        let location = EditorLocation {
+            path: path.clone(),
+            ..location
+        };
+        self.record_file_visit(&path);
+        // The file isn't one to reopen anymore once it's open again
+        if self
+            .closed_editors
+            .with_untracked(|closed| closed.iter().any(|l| l.path == path))
+        {
+            self.closed_editors
+                .update(|closed| closed.retain(|l| l.path != path));
+        }
         let (doc, new_doc) = self.get_doc(path.clone());
+        if symlink.is_some() && doc.with_untracked(|doc| doc.symlink != symlink) {
+            doc.update(|doc| doc.symlink = symlink);
+        }
 
         let child = self.get_editor_tab_child(
             EditorTabChildSource::Editor { path, doc },
@@ -481,31 +779,250 @@ pub fn go_to_location(
         }
     }
 
-    pub fn open_file_changes(&self, path: PathBuf) {
-        let (right, _) = self.get_doc(path.clone());
-        let left = Document::new_hisotry(
+    /// Show the search results buffer in the active editor tab.
+    pub fn open_search_results(&self, doc: RwSignal<Document>) {
+        if self.common.focus.get_untracked() != Focus::Workbench {
+            self.common.focus.set(Focus::Workbench);
+        }
+        self.get_editor_tab_child(
+            EditorTabChildSource::Content { doc },
+            false,
+            false,
+        );
+    }
+
+    /// Show the text of a report in a new document of the active editor tab
+    pub fn open_report(&self, report: String) {
+        let mut doc = Document::new_local(
             self.scope,
-            DocContent::History(DocHistory {
-                path: path.clone(),
-                version: "head".to_string(),
-            }),
             self.common.find.clone(),
             self.common.proxy.clone(),
             self.common.config,
         );
-        let left = create_rw_signal(left.scope, left);
+        doc.reload(Rope::from(report), true);
+        let doc = create_rw_signal(self.scope, doc);
+        if self.common.focus.get_untracked() != Focus::Workbench {
+            self.common.focus.set(Focus::Workbench);
+        }
+        self.get_editor_tab_child(
+            EditorTabChildSource::Content { doc },
+            false,
+            false,
+        );
+    }
+
+    /// A read-only document with the notebook at `path` rendered as text
+    pub fn notebook_doc(&self, path: PathBuf) -> RwSignal<Document> {
+        let mut doc = Document::new_local(
+            self.scope,
+            self.common.find.clone(),
+            self.common.proxy.clone(),
+            self.common.config,
+        );
+        doc.content = DocContent::Notebook(path.clone());
+        let doc = create_rw_signal(self.scope, doc);
+
+        let send = create_ext_action(self.scope, move |content: String| {
+            let text = match Notebook::parse(&content) {
+                Ok(notebook) => notebook.render(),
+                Err(e) => format!("Failed to read the notebook: {e}"),
+            };
+            doc.update(|doc| doc.reload(Rope::from(text), true));
+        });
+        self.common
+            .proxy
+            .new_buffer(BufferId::next(), path, move |result| {
+                if let Ok(ProxyResponse::NewBufferResponse { content, .. }) = result
+                {
+                    send(content);
+                }
+            });
+        doc
+    }
+
+    /// Show the notebook at `path` rendered read-only in the active editor tab.
+    pub fn open_notebook(&self, path: PathBuf) {
+        if self.common.focus.get_untracked() != Focus::Workbench {
+            self.common.focus.set(Focus::Workbench);
+        }
+        self.record_file_visit(&path);
+        let doc = self.notebook_doc(path);
+        self.get_editor_tab_child(
+            EditorTabChildSource::Content { doc },
+            false,
+            false,
+        );
+    }
+
+    /// A read-only document with the member of an archive, at a path that
+    /// goes through the archive, which the proxy extracts and reads
+    pub fn archive_doc(&self, path: PathBuf) -> RwSignal<Document> {
+        let doc = Document::new_hisotry(
+            self.scope,
+            DocContent::Archive(path.clone()),
+            self.common.find.clone(),
+            self.common.proxy.clone(),
+            self.common.config,
+        );
+        let doc = create_rw_signal(doc.scope, doc);
+
+        let send = create_ext_action(self.scope, move |content: String| {
+            doc.update(|doc| doc.init_content(Rope::from(content)));
+        });
+        self.common
+            .proxy
+            .extract_archive_member(path, move |result| match result {
+                Ok(ProxyResponse::ExtractArchiveMemberResponse { content }) => {
+                    send(content);
+                }
+                Ok(_) => {}
+                Err(e) => send(format!("Failed to extract the file: {}", e.message)),
+            });
+        doc
+    }
+
+    /// Show the member of an archive read-only in the active editor tab
+    pub fn open_archive_member(&self, path: PathBuf) {
+        if self.common.focus.get_untracked() != Focus::Workbench {
+            self.common.focus.set(Focus::Workbench);
+        }
+        let doc = self.archive_doc(path);
+        self.get_editor_tab_child(
+            EditorTabChildSource::Content { doc },
+            false,
+            false,
+        );
+    }
+
+    /// The path of the notebook in the active editor, if it's a rendered one
+    pub fn active_notebook(&self) -> Option<PathBuf> {
+        let editor = self.active_editor.get_untracked()?;
+        let doc = editor.with_untracked(|editor| editor.view.doc);
+        doc.with_untracked(|doc| match &doc.content {
+            DocContent::Notebook(path) => Some(path.clone()),
+            _ => None,
+        })
+    }
+
+    /// Write the code of the notebook at `path` to a python script next to it,
+    /// in which each cell starts with a `# %%` line, and open the script. An
+    /// existing script isn't overwritten.
+    pub fn export_notebook(&self, path: PathBuf) {
+        let script_path = path.with_extension("py");
+        let main_split = self.clone();
+        let location = EditorLocation {
+            path: script_path.clone(),
+            position: None,
+            scroll_offset: None,
+            ignore_unconfirmed: false,
+            same_editor_tab: false,
+        };
+        let send = create_ext_action(self.scope, move |_| {
+            main_split.go_to_location(location, None);
+        });
+
+        let proxy = self.common.proxy.clone();
+        self.common
+            .proxy
+            .new_buffer(BufferId::next(), path, move |result| {
+                let Ok(ProxyResponse::NewBufferResponse { content, .. }) = result
+                else {
+                    return;
+                };
+                let script = match Notebook::parse(&content) {
+                    Ok(notebook) => notebook.to_script(),
+                    Err(e) => {
+                        error!("Failed to read the notebook: {e}");
+                        return;
+                    }
+                };
+                let save_proxy = proxy.clone();
+                proxy.create_file(script_path.clone(), move |result| {
+                    if let Err(e) = result {
+                        error!("Failed to create {script_path:?}: {}", e.message);
+                        return;
+                    }
+                    save_proxy.save_buffer_as(
+                        BufferId::next(),
+                        script_path,
+                        0,
+                        script,
+                        move |result| {
+                            if result.is_ok() {
+                                send(());
+                            }
+                        },
+                    );
+                });
+            });
+    }
+
+    /// Count opening the file at `path` towards its frecency in the file
+    /// palette, unless it's already the active one.
+    fn record_file_visit(&self, path: &Path) {
+        let active = self.active_editor.get_untracked().and_then(|editor| {
+            let doc = editor.with_untracked(|editor| editor.view.doc);
+            doc.with_untracked(|doc| doc.content.path().cloned())
+        });
+        if active.as_deref() == Some(path) {
+            return;
+        }
+        let file_frecency = self.common.file_frecency;
+        file_frecency.update(|f| f.visit(path, frecency::now()));
+        let db: Arc<LapceDb> = use_context(self.scope).unwrap();
+        db.save_file_frecency(
+            self.common.workspace.clone(),
+            file_frecency.get_untracked(),
+        );
+    }
+
+    /// A read-only document of the file at an old version, `head` or a
+    /// commit, loaded from the source control
+    pub fn history_doc(&self, history: DocHistory) -> RwSignal<Document> {
+        let path = history.path.clone();
+        let version = history.version.clone();
+        let doc = Document::new_hisotry(
+            self.scope,
+            DocContent::History(history),
+            self.common.find.clone(),
+            self.common.proxy.clone(),
+            self.common.config,
+        );
+        let doc = create_rw_signal(doc.scope, doc);
 
         let send = create_ext_action(self.scope, move |result| {
-            if let Ok(ProxyResponse::BufferHeadResponse { content, .. }) = result {
-                left.update(|doc| {
+            if let Ok(
+                ProxyResponse::BufferHeadResponse { content, .. }
+                | ProxyResponse::GitFileAtRevisionResponse { content },
+            ) = result
+            {
+                doc.update(|doc| {
                     doc.init_content(Rope::from(content));
                 });
             }
         });
-        self.common.proxy.get_buffer_head(path, move |result| {
-            send(result);
-        });
+        if version == "head" {
+            self.common.proxy.get_buffer_head(path, move |result| {
+                send(result);
+            });
+        } else {
+            self.common
+                .proxy
+                .git_file_at_revision(path, version, move |result| {
+                    send(result);
+                });
+        }
+        doc
+    }
 
+    pub fn open_file_changes(&self, path: PathBuf) {
+        self.open_revision_changes(path, "head".to_string());
+    }
+
+    /// Diff the file as of the revision against the working tree
+    pub fn open_revision_changes(&self, path: PathBuf, version: String) {
+        let (right, _) = self.get_doc(path.clone());
+        let left = self.history_doc(DocHistory { path, version });
         self.get_editor_tab_child(
             EditorTabChildSource::DiffEditor { left, right },
             false,
@@ -513,6 +1030,19 @@ pub fn open_file_changes(&self, path: PathBuf) {
         );
     }
 
+    /// Show the file as of the revision, read-only, in the active editor tab
+    pub fn open_file_revision(&self, path: PathBuf, version: String) {
+        if self.common.focus.get_untracked() != Focus::Workbench {
+            self.common.focus.set(Focus::Workbench);
+        }
+        let doc = self.history_doc(DocHistory { path, version });
+        self.get_editor_tab_child(
+            EditorTabChildSource::Content { doc },
+            false,
+            false,
+        );
+    }
+
     fn new_editor_tab(
         &self,
         editor_tab_id: EditorTabId,
@@ -526,6 +1056,7 @@ fn new_editor_tab(
                 active: 0,
                 editor_tab_id,
                 children: vec![],
+                pinned: 0,
                 window_origin: Point::ZERO,
                 layout_rect: Rect::ZERO,
                 locations: create_rw_signal(cx, im::Vector::new()),
@@ -734,13 +1265,35 @@ fn get_editor_tab_child(
                         })
                     }
                 }
+                EditorTabChildSource::Content { doc } => active_editor_tab
+                    .with_untracked(|editor_tab| {
+                        editor_tab
+                            .get_editor_with_content(
+                                &editors,
+                                &doc.with_untracked(|doc| doc.content.clone()),
+                            )
+                            .map(|(i, _)| i)
+                            .or_else(|| {
+                                if ignore_unconfirmed {
+                                    None
+                                } else {
+                                    editor_tab
+                                        .get_unconfirmed_editor_tab_child(
+                                            &editors,
+                                            &diff_editors,
+                                        )
+                                        .map(|(i, _)| i)
+                                }
+                            })
+                    }),
             }
         };
 
         let new_child_from_source =
             |editor_tab_id: EditorTabId, source: &EditorTabChildSource| match source
             {
-                EditorTabChildSource::Editor { doc, .. } => {
+                EditorTabChildSource::Editor { doc, .. }
+                | EditorTabChildSource::Content { doc } => {
                     let editor_id = EditorId::next();
                     let editor = EditorData::new(
                         self.scope,
@@ -841,6 +1394,33 @@ fn get_editor_tab_child(
                 (EditorTabChild::Settings(_), EditorTabChildSource::Settings) => {
                     true
                 }
+                (
+                    EditorTabChild::Editor(editor_id),
+                    EditorTabChildSource::Content { doc },
+                ) => {
+                    if let Some(editor) = editors.get(editor_id) {
+                        let content = doc.with_untracked(|doc| doc.content.clone());
+                        let same_content = editor.with_untracked(|editor| {
+                            editor
+                                .view
+                                .doc
+                                .with_untracked(|doc| doc.content == content)
+                        });
+                        if !same_content {
+                            editor.update(|editor| {
+                                editor.update_doc(*doc);
+                            });
+                            editor.with_untracked(|editor| {
+                                editor.cursor.set(Cursor::origin(
+                                    self.common
+                                        .config
+                                        .with_untracked(|c| c.core.modal),
+                                ));
+                            });
+                        }
+                    }
+                    true
+                }
                 _ => false,
             };
             if is_same {
@@ -908,6 +1488,12 @@ fn get_editor_tab_child(
                                     matches!(child, EditorTabChild::Settings(_))
                                 })
                             }
+                            EditorTabChildSource::Content { doc } => editor_tab
+                                .get_editor_with_content(
+                                    &editors,
+                                    &doc.with_untracked(|doc| doc.content.clone()),
+                                )
+                                .map(|(index, _)| index),
                         })
                     {
                         self.active_editor_tab.set(Some(*editor_tab_id));
@@ -934,7 +1520,7 @@ fn get_editor_tab_child(
             let new_active = if editor_tab.children.is_empty() {
                 0
             } else {
-                active + 1
+                (active + 1).max(editor_tab.pinned)
             };
             editor_tab.children.insert(
                 new_active,
@@ -1038,42 +1624,55 @@ pub fn split(
     ) -> Option<()> {
         let editor_tabs = self.editor_tabs.get_untracked();
         let editor_tab = editor_tabs.get(&editor_tab_id).copied()?;
-
         let split_id = editor_tab.with_untracked(|editor_tab| editor_tab.split);
-        let splits = self.splits.get_untracked();
-        let split = splits.get(&split_id).copied()?;
-        let split_direction = split.with_untracked(|split| split.direction);
+        let new_editor_tab = editor_tab.with_untracked(|editor_tab| {
+            let (_, child) = editor_tab.children.get(editor_tab.active)?;
+            self.split_editor_tab(self.scope, split_id, editor_tab, child)
+        })?;
+        self.split_insert(direction, editor_tab_id, new_editor_tab, true)
+    }
 
-        let (index, children_len) = split.with_untracked(|split| {
-            split
-                .children
+    /// Put the new editor tab beside the editor tab in the direction, after it
+    /// or before it.
+    fn split_insert(
+        &self,
+        direction: SplitDirection,
+        editor_tab_id: EditorTabId,
+        new_editor_tab: RwSignal<EditorTabData>,
+        after: bool,
+    ) -> Option<()> {
+        let editor_tabs = self.editor_tabs.get_untracked();
+        let editor_tab = editor_tabs.get(&editor_tab_id).copied()?;
+
+        let split_id = editor_tab.with_untracked(|editor_tab| editor_tab.split);
+        let splits = self.splits.get_untracked();
+        let split = splits.get(&split_id).copied()?;
+        let split_direction = split.with_untracked(|split| split.direction);
+
+        let (index, children_len) = split.with_untracked(|split| {
+            split
+                .children
                 .iter()
                 .position(|c| c == &SplitContent::EditorTab(editor_tab_id))
                 .map(|index| (index, split.children.len()))
         })?;
 
+        let new_editor_tab_id =
+            new_editor_tab.with_untracked(|editor_tab| editor_tab.editor_tab_id);
+        let new_content = SplitContent::EditorTab(new_editor_tab_id);
         if split_direction == direction {
-            let new_editor_tab = editor_tab.with_untracked(|editor_tab| {
-                self.split_editor_tab(self.scope, split_id, editor_tab)
-            })?;
-            let new_editor_tab_id =
-                new_editor_tab.with_untracked(|editor_tab| editor_tab.editor_tab_id);
             split.update(|split| {
-                split
-                    .children
-                    .insert(index + 1, SplitContent::EditorTab(new_editor_tab_id));
+                let index = if after { index + 1 } else { index };
+                split.children.insert(index, new_content);
             });
         } else if children_len == 1 {
-            let new_editor_tab = editor_tab.with_untracked(|editor_tab| {
-                self.split_editor_tab(self.scope, split_id, editor_tab)
-            })?;
-            let new_editor_tab_id =
-                new_editor_tab.with_untracked(|editor_tab| editor_tab.editor_tab_id);
             split.update(|split| {
                 split.direction = direction;
-                split
-                    .children
-                    .push(SplitContent::EditorTab(new_editor_tab_id));
+                if after {
+                    split.children.push(new_content);
+                } else {
+                    split.children.insert(0, new_content);
+                }
             });
         } else {
             let new_split_id = SplitId::next();
@@ -1081,22 +1680,23 @@ pub fn split(
             editor_tab.update(|editor_tab| {
                 editor_tab.split = new_split_id;
             });
-            let new_editor_tab = editor_tab.with_untracked(|editor_tab| {
-                self.split_editor_tab(self.scope, new_split_id, editor_tab)
-            })?;
-            let new_editor_tab_id =
-                new_editor_tab.with_untracked(|editor_tab| editor_tab.editor_tab_id);
+            new_editor_tab.update(|editor_tab| {
+                editor_tab.split = new_split_id;
+            });
 
+            let content = SplitContent::EditorTab(editor_tab_id);
             let new_split = {
                 let (cx, _) = self.scope.run_child_scope(|cx| cx);
                 let new_split = SplitData {
                     scope: cx,
                     parent_split: Some(split_id),
                     split_id: new_split_id,
-                    children: vec![
-                        SplitContent::EditorTab(editor_tab_id),
-                        SplitContent::EditorTab(new_editor_tab_id),
-                    ],
+                    children: if after {
+                        vec![content, new_content]
+                    } else {
+                        vec![new_content, content]
+                    },
+                    sizes: im::HashMap::new(),
                     direction,
                     window_origin: Point::ZERO,
                     layout_rect: Rect::ZERO,
@@ -1108,22 +1708,63 @@ pub fn split(
             });
             split.update(|split| {
                 split.children[index] = SplitContent::Split(new_split_id);
+                // The new split keeps the room of the editor tab it replaces
+                if let Some(size) = split.sizes.remove(&content) {
+                    split.sizes.insert(SplitContent::Split(new_split_id), size);
+                }
             });
         }
 
         Some(())
     }
 
+    /// A new editor tab for the split, showing a copy of the child and
+    /// sharing the jump locations of the editor tab
     fn split_editor_tab(
         &self,
         cx: Scope,
         split_id: SplitId,
         editor_tab: &EditorTabData,
+        child: &EditorTabChild,
     ) -> Option<RwSignal<EditorTabData>> {
-        let (_, child) = editor_tab.children.get(editor_tab.active)?;
-
         let editor_tab_id = EditorTabId::next();
+        let new_child = self.copy_editor_tab_child(cx, editor_tab_id, child)?;
+
+        let editor_tab = {
+            let (cx, _) = self.scope.run_child_scope(|cx| cx);
+            let editor_tab = EditorTabData {
+                scope: cx,
+                split: split_id,
+                editor_tab_id,
+                active: 0,
+                children: vec![(create_rw_signal(cx, 0), new_child)],
+                pinned: 0,
+                window_origin: Point::ZERO,
+                layout_rect: Rect::ZERO,
+                locations: create_rw_signal(
+                    cx,
+                    editor_tab.locations.get_untracked(),
+                ),
+                current_location: create_rw_signal(
+                    cx,
+                    editor_tab.current_location.get_untracked(),
+                ),
+            };
+            create_rw_signal(cx, editor_tab)
+        };
+        self.editor_tabs.update(|editor_tabs| {
+            editor_tabs.insert(editor_tab_id, editor_tab);
+        });
+        Some(editor_tab)
+    }
 
+    /// A copy of the child, to show in the editor tab
+    fn copy_editor_tab_child(
+        &self,
+        cx: Scope,
+        editor_tab_id: EditorTabId,
+        child: &EditorTabChild,
+    ) -> Option<EditorTabChild> {
         let new_child = match child {
             EditorTabChild::Editor(editor_id) => {
                 let new_editor_id = EditorId::next();
@@ -1153,32 +1794,7 @@ fn split_editor_tab(
                 EditorTabChild::Settings(SettingsId::next())
             }
         };
-
-        let editor_tab = {
-            let (cx, _) = self.scope.run_child_scope(|cx| cx);
-            let editor_tab = EditorTabData {
-                scope: cx,
-                split: split_id,
-                editor_tab_id,
-                active: 0,
-                children: vec![(create_rw_signal(cx, 0), new_child)],
-                window_origin: Point::ZERO,
-                layout_rect: Rect::ZERO,
-                locations: create_rw_signal(
-                    cx,
-                    editor_tab.locations.get_untracked(),
-                ),
-                current_location: create_rw_signal(
-                    cx,
-                    editor_tab.current_location.get_untracked(),
-                ),
-            };
-            create_rw_signal(cx, editor_tab)
-        };
-        self.editor_tabs.update(|editor_tabs| {
-            editor_tabs.insert(editor_tab_id, editor_tab);
-        });
-        Some(editor_tab)
+        Some(new_child)
     }
 
     pub fn split_move(
@@ -1292,6 +1908,158 @@ pub fn split_exchange(
         Some(())
     }
 
+    /// Switch the split of the editor tab between laying out its children side
+    /// by side and on top of each other.
+    pub fn split_rotate(&self, editor_tab_id: EditorTabId) -> Option<()> {
+        let editor_tabs = self.editor_tabs.get_untracked();
+        let editor_tab = editor_tabs.get(&editor_tab_id).copied()?;
+
+        let split_id = editor_tab.with_untracked(|editor_tab| editor_tab.split);
+        let splits = self.splits.get_untracked();
+        let split = splits.get(&split_id).copied()?;
+        split.update(|split| {
+            split.direction = match split.direction {
+                SplitDirection::Vertical => SplitDirection::Horizontal,
+                SplitDirection::Horizontal => SplitDirection::Vertical,
+            };
+        });
+
+        Some(())
+    }
+
+    /// The size of the child of a split in the direction of the split
+    fn split_content_size(
+        &self,
+        content: &SplitContent,
+        direction: SplitDirection,
+    ) -> Option<f64> {
+        let rect = match content {
+            SplitContent::EditorTab(editor_tab_id) => self
+                .editor_tabs
+                .get_untracked()
+                .get(editor_tab_id)?
+                .with_untracked(|editor_tab| editor_tab.layout_rect),
+            SplitContent::Split(split_id) => self
+                .splits
+                .get_untracked()
+                .get(split_id)?
+                .with_untracked(|split| split.layout_rect),
+        };
+        Some(match direction {
+            SplitDirection::Vertical => rect.width(),
+            SplitDirection::Horizontal => rect.height(),
+        })
+    }
+
+    /// Move the border between the child of the split at `index` and the one
+    /// before it by `delta`, resizing both of them.
+    pub fn split_resize(
+        &self,
+        split_id: SplitId,
+        index: usize,
+        delta: f64,
+    ) -> Option<()> {
+        let splits = self.splits.get_untracked();
+        let split = splits.get(&split_id).copied()?;
+        let (direction, before, after) = split.with_untracked(|split| {
+            let before = *split.children.get(index.checked_sub(1)?)?;
+            let after = *split.children.get(index)?;
+            Some((split.direction, before, after))
+        })?;
+
+        let before_size = self.split_content_size(&before, direction)?;
+        let total = before_size + self.split_content_size(&after, direction)?;
+        if total <= SPLIT_MIN_SIZE * 2.0 {
+            return None;
+        }
+        let new_size =
+            (before_size + delta).clamp(SPLIT_MIN_SIZE, total - SPLIT_MIN_SIZE);
+        split.update(|split| {
+            let share = split.size(&before) + split.size(&after);
+            split.sizes.insert(before, share * new_size / total);
+            split
+                .sizes
+                .insert(after, share * (total - new_size) / total);
+        });
+
+        Some(())
+    }
+
+    /// Grow the editor tab by `delta` in the direction of the closest split it
+    /// shares with other children, taking the room from the child after it, or
+    /// the one before it for the last child.
+    pub fn split_resize_editor_tab(
+        &self,
+        editor_tab_id: EditorTabId,
+        delta: f64,
+    ) -> Option<()> {
+        let editor_tabs = self.editor_tabs.get_untracked();
+        let editor_tab = editor_tabs.get(&editor_tab_id).copied()?;
+
+        let splits = self.splits.get_untracked();
+        let mut content = SplitContent::EditorTab(editor_tab_id);
+        let mut split_id = editor_tab.with_untracked(|editor_tab| editor_tab.split);
+        loop {
+            let split = splits.get(&split_id).copied()?;
+            let (index, len, parent_split) = split.with_untracked(|split| {
+                let index = split.children.iter().position(|c| c == &content)?;
+                Some((index, split.children.len(), split.parent_split))
+            })?;
+            if len > 1 {
+                return if index + 1 < len {
+                    self.split_resize(split_id, index + 1, delta)
+                } else {
+                    self.split_resize(split_id, index, -delta)
+                };
+            }
+            content = SplitContent::Split(split_id);
+            split_id = parent_split?;
+        }
+    }
+
+    /// Maximize the editor tab over the whole editor area, or restore the
+    /// splits if it already is
+    pub fn split_maximize(&self, editor_tab_id: EditorTabId) {
+        self.maximized_editor_tab.update(|maximized| {
+            *maximized = if *maximized == Some(editor_tab_id) {
+                None
+            } else {
+                Some(editor_tab_id)
+            };
+        });
+    }
+
+    /// Whether the content of a split is hidden by another editor tab being
+    /// maximized, being neither that editor tab nor a split containing it
+    pub fn is_split_content_hidden(&self, content: &SplitContent) -> bool {
+        let Some(maximized) = self.maximized_editor_tab.get() else {
+            return false;
+        };
+        match content {
+            SplitContent::EditorTab(editor_tab_id) => *editor_tab_id != maximized,
+            SplitContent::Split(split_id) => {
+                let splits = self.splits.get_untracked();
+                let mut parent = self
+                    .editor_tabs
+                    .with_untracked(|editor_tabs| {
+                        editor_tabs.get(&maximized).copied()
+                    })
+                    .map(|editor_tab| {
+                        editor_tab.with_untracked(|editor_tab| editor_tab.split)
+                    });
+                while let Some(id) = parent {
+                    if id == *split_id {
+                        return false;
+                    }
+                    parent = splits
+                        .get(&id)
+                        .and_then(|split| split.with_untracked(|s| s.parent_split));
+                }
+                true
+            }
+        }
+    }
+
     fn split_content_focus(&self, cx: Scope, content: &SplitContent) {
         match content {
             SplitContent::EditorTab(editor_tab_id) => {
@@ -1332,6 +2100,7 @@ fn split_remove(&self, split_id: SplitId) -> Option<()> {
                 split
                     .children
                     .retain(|c| c != &SplitContent::Split(split_id));
+                split.sizes.remove(&SplitContent::Split(split_id));
                 split.children.len()
             })
             .unwrap();
@@ -1370,6 +2139,7 @@ fn editor_tab_remove(
         })?;
         split.update(|split| {
             split.children.remove(index);
+            split.sizes.remove(&SplitContent::EditorTab(editor_tab_id));
         });
         let split_children = split.with_untracked(|split| split.children.clone());
 
@@ -1423,18 +2193,134 @@ pub fn editor_tab_close(
         let editor_tabs = self.editor_tabs.get_untracked();
         let editor_tab = editor_tabs.get(&editor_tab_id).copied()?;
         let editor_tab = editor_tab.get_untracked();
-        for (_, child) in editor_tab.children {
+        for (_, child) in editor_tab.children.into_iter().skip(editor_tab.pinned) {
             self.editor_tab_child_close(cx, editor_tab_id, child);
         }
 
         Some(())
     }
 
+    /// Close the children of the editor tab but the active one and the pinned
+    /// ones
+    pub fn editor_tab_close_others(
+        &self,
+        cx: Scope,
+        editor_tab_id: EditorTabId,
+    ) -> Option<()> {
+        let editor_tabs = self.editor_tabs.get_untracked();
+        let editor_tab = editor_tabs.get(&editor_tab_id).copied()?;
+        let editor_tab = editor_tab.get_untracked();
+        for (i, (_, child)) in editor_tab
+            .children
+            .into_iter()
+            .enumerate()
+            .skip(editor_tab.pinned)
+        {
+            if i != editor_tab.active {
+                self.editor_tab_child_close(cx, editor_tab_id, child);
+            }
+        }
+
+        Some(())
+    }
+
+    /// Pin the active child of the editor tab, or unpin it if it's pinned
+    pub fn editor_tab_toggle_pin(&self, editor_tab_id: EditorTabId) -> Option<()> {
+        let editor_tabs = self.editor_tabs.get_untracked();
+        let editor_tab = editor_tabs.get(&editor_tab_id).copied()?;
+        let (_, child) = editor_tab.with_untracked(|editor_tab| {
+            editor_tab.children.get(editor_tab.active).cloned()
+        })?;
+        // A pinned editor is kept rather than replaced by the next file opened
+        match child {
+            EditorTabChild::Editor(editor_id) => {
+                if let Some(editor) = self.editors.get_untracked().get(&editor_id) {
+                    editor.with_untracked(|editor| editor.confirmed.set(true));
+                }
+            }
+            EditorTabChild::DiffEditor(diff_editor_id) => {
+                if let Some(diff_editor) =
+                    self.diff_editors.get_untracked().get(&diff_editor_id)
+                {
+                    diff_editor
+                        .right
+                        .with_untracked(|editor| editor.confirmed.set(true));
+                }
+            }
+            EditorTabChild::Settings(_) => {}
+        }
+        editor_tab.update(|editor_tab| {
+            let active = editor_tab.active;
+            editor_tab.toggle_pin(active);
+        });
+
+        Some(())
+    }
+
     pub fn editor_tab_child_close(
         &self,
         cx: Scope,
         editor_tab_id: EditorTabId,
         child: EditorTabChild,
+    ) -> Option<()> {
+        self.save_closed_editor(&child);
+        self.editor_tab_child_remove(cx, editor_tab_id, child)
+    }
+
+    /// Remember where the file of the editor was left, to reopen it later
+    fn save_closed_editor(&self, child: &EditorTabChild) {
+        let EditorTabChild::Editor(editor_id) = child else {
+            return;
+        };
+        let Some(editor) = self
+            .editors
+            .with_untracked(|editors| editors.get(editor_id).copied())
+        else {
+            return;
+        };
+        let (doc, cursor, viewport) = editor.with_untracked(|editor| {
+            (editor.view.doc, editor.cursor, editor.viewport)
+        });
+        let Some(path) = doc.with_untracked(|doc| doc.content.path().cloned())
+        else {
+            return;
+        };
+        let location = EditorLocation {
+            path,
+            position: Some(EditorPosition::Offset(
+                cursor.with_untracked(|c| c.offset()),
+            )),
+            scroll_offset: Some(viewport.get_untracked().origin().to_vec2()),
+            ignore_unconfirmed: false,
+            same_editor_tab: false,
+        };
+        self.closed_editors.update(|closed| {
+            closed.retain(|l| l.path != location.path);
+            closed.push_back(location);
+            if closed.len() > CLOSED_EDITORS_LIMIT {
+                closed.pop_front();
+            }
+        });
+    }
+
+    /// Reopen the editor closed last, where its file was left
+    pub fn reopen_closed_editor(&self) {
+        if let Some(location) = self
+            .closed_editors
+            .try_update(|closed| closed.pop_back())
+            .unwrap()
+        {
+            self.jump_to_location(location, None);
+        }
+    }
+
+    /// Take the child out of the editor tab, and remove the editor tab if it
+    /// was its last child.
+    fn editor_tab_child_remove(
+        &self,
+        cx: Scope,
+        editor_tab_id: EditorTabId,
+        child: EditorTabChild,
     ) -> Option<()> {
         let editor_tabs = self.editor_tabs.get_untracked();
         let editor_tab = editor_tabs.get(&editor_tab_id).copied()?;
@@ -1446,6 +2332,9 @@ pub fn editor_tab_child_close(
         let editor_tab_children_len = editor_tab
             .try_update(|editor_tab| {
                 editor_tab.children.remove(index);
+                if editor_tab.is_pinned(index) {
+                    editor_tab.pinned -= 1;
+                }
                 editor_tab.active =
                     index.min(editor_tab.children.len().saturating_sub(1));
                 editor_tab.children.len()
@@ -1483,6 +2372,94 @@ pub fn editor_tab_child_close(
         Some(())
     }
 
+    /// Move the child of an editor tab to the index of another editor tab, or
+    /// of the same one, at the end if there's no index. The index is where
+    /// the child was dropped, before it's taken out of its editor tab.
+    pub fn editor_tab_child_move(
+        &self,
+        cx: Scope,
+        from_editor_tab_id: EditorTabId,
+        child: EditorTabChild,
+        to_editor_tab_id: EditorTabId,
+        index: Option<usize>,
+    ) -> Option<()> {
+        let editor_tabs = self.editor_tabs.get_untracked();
+        let from_editor_tab = editor_tabs.get(&from_editor_tab_id).copied()?;
+        let to_editor_tab = editor_tabs.get(&to_editor_tab_id).copied()?;
+
+        if from_editor_tab_id == to_editor_tab_id {
+            let from = from_editor_tab.with_untracked(|editor_tab| {
+                editor_tab.children.iter().position(|(_, c)| c == &child)
+            })?;
+            from_editor_tab.update(|editor_tab| {
+                let item = editor_tab.children.remove(from);
+                let to = index.unwrap_or(editor_tab.children.len() + 1);
+                let to = if to > from { to - 1 } else { to };
+                let to = to.min(editor_tab.children.len());
+                // A child stays among the pinned ones or the others
+                let to = if editor_tab.is_pinned(from) {
+                    to.min(editor_tab.pinned - 1)
+                } else {
+                    to.max(editor_tab.pinned)
+                };
+                editor_tab.children.insert(to, item);
+                editor_tab.active = to;
+            });
+            return Some(());
+        }
+
+        let new_child =
+            self.copy_editor_tab_child(self.scope, to_editor_tab_id, &child)?;
+        to_editor_tab.update(|editor_tab| {
+            let index = index
+                .unwrap_or(editor_tab.children.len())
+                .clamp(editor_tab.pinned, editor_tab.children.len());
+            editor_tab
+                .children
+                .insert(index, (create_rw_signal(editor_tab.scope, 0), new_child));
+            editor_tab.active = index;
+        });
+        self.editor_tab_child_remove(cx, from_editor_tab_id, child);
+        self.split_content_focus(cx, &SplitContent::EditorTab(to_editor_tab_id));
+
+        Some(())
+    }
+
+    /// Move the child of an editor tab into a new editor tab split off beside
+    /// an editor tab in the direction, after it or before it.
+    pub fn editor_tab_child_split(
+        &self,
+        cx: Scope,
+        from_editor_tab_id: EditorTabId,
+        child: EditorTabChild,
+        to_editor_tab_id: EditorTabId,
+        direction: SplitDirection,
+        after: bool,
+    ) -> Option<()> {
+        let editor_tabs = self.editor_tabs.get_untracked();
+        let from_editor_tab = editor_tabs.get(&from_editor_tab_id).copied()?;
+        let to_editor_tab = editor_tabs.get(&to_editor_tab_id).copied()?;
+
+        // The only child of an editor tab can't be split off beside itself
+        if from_editor_tab_id == to_editor_tab_id
+            && from_editor_tab.with_untracked(|editor_tab| editor_tab.children.len())
+                == 1
+        {
+            return Some(());
+        }
+
+        let new_editor_tab = to_editor_tab.with_untracked(|editor_tab| {
+            self.split_editor_tab(self.scope, editor_tab.split, editor_tab, &child)
+        })?;
+        let new_editor_tab_id =
+            new_editor_tab.with_untracked(|editor_tab| editor_tab.editor_tab_id);
+        self.split_insert(direction, to_editor_tab_id, new_editor_tab, after)?;
+        self.editor_tab_child_remove(cx, from_editor_tab_id, child);
+        self.split_content_focus(cx, &SplitContent::EditorTab(new_editor_tab_id));
+
+        Some(())
+    }
+
     pub fn editor_tab_update_layout(
         &self,
         editor_tab_id: &EditorTabId,
@@ -1531,74 +2508,297 @@ fn resolve_code_action(&self, plugin_id: PluginId, action: CodeAction) {
 
     /// Perform a workspace edit, which are from the LSP (such as code actions, or symbol renaming)
     pub fn apply_workspace_edit(&self, edit: &WorkspaceEdit) {
-        if let Some(DocumentChanges::Operations(_op)) =
-            edit.document_changes.as_ref()
-        {
-            // TODO
-        }
+        let session = match EditSession::from_workspace_edit(edit) {
+            Ok(session) => session,
+            Err(err) => {
+                error!("Failed to apply the workspace edit: {err}");
+                return;
+            }
+        };
+        let main_split = self.clone();
+        let documents = session.documents().to_vec();
+        self.apply_edit_session(session, move |result| {
+            if let Err(err) = result {
+                error!("Failed to apply the workspace edit: {err}");
+                return;
+            }
+            // The edited files are opened, at their first edit unless it's the
+            // active one
+            for document in documents {
+                let active_path = main_split
+                    .active_editor
+                    .get_untracked()
+                    .map(|editor| editor.with_untracked(|editor| editor.view.doc))
+                    .map(|doc| doc.with_untracked(|doc| doc.content.clone()))
+                    .and_then(|content| content.path().cloned());
+                let position = if active_path.as_ref() == Some(&document.path) {
+                    None
+                } else {
+                    document
+                        .edits
+                        .first()
+                        .map(|edit| EditorPosition::Position(edit.range.start))
+                };
+                let location = EditorLocation {
+                    path: document.path,
+                    position,
+                    scroll_offset: None,
+                    ignore_unconfirmed: true,
+                    same_editor_tab: false,
+                };
+                main_split.jump_to_location(location, None);
+            }
+        });
+    }
 
-        if let Some(edits) = workspace_edits(edit) {
-            for (url, edits) in edits {
-                if let Ok(path) = url.to_file_path() {
-                    let active_path = self
-                        .active_editor
-                        .get_untracked()
-                        .map(|editor| {
-                            editor.with_untracked(|editor| editor.view.doc)
-                        })
-                        .map(|doc| doc.with_untracked(|doc| doc.content.clone()))
-                        .and_then(|content| content.path().cloned());
-                    let position = if active_path.as_ref() == Some(&path) {
-                        None
-                    } else {
-                        edits
-                            .get(0)
-                            .map(|edit| EditorPosition::Position(edit.range.start))
-                    };
-                    let location = EditorLocation {
-                        path,
-                        position,
-                        scroll_offset: None,
-                        ignore_unconfirmed: true,
-                        same_editor_tab: false,
-                    };
-                    self.jump_to_location(location, Some(edits));
+    /// Apply the edits of the session once all of its documents are loaded,
+    /// either all of them or, if the edits to any document don't apply to it
+    /// as it is, none of them. `on_done` is told which.
+    pub fn apply_edit_session(
+        &self,
+        session: EditSession,
+        on_done: impl FnOnce(Result<(), EditSessionError>) + 'static,
+    ) {
+        let docs: Vec<RwSignal<Document>> = session
+            .documents()
+            .iter()
+            .map(|document| self.get_doc(document.path.clone()).0)
+            .collect();
+        #[allow(clippy::type_complexity)]
+        let on_done: Rc<
+            RefCell<Option<Box<dyn FnOnce(Result<(), EditSessionError>)>>>,
+        > = Rc::new(RefCell::new(Some(Box::new(on_done))));
+        let paths: Vec<PathBuf> = session
+            .documents()
+            .iter()
+            .map(|document| document.path.clone())
+            .collect();
+
+        let apply = {
+            let docs = docs.clone();
+            move || -> Result<(), EditSessionError> {
+                // Everything is checked before anything is edited
+                let mut edits = Vec::new();
+                for (document, doc) in session.documents().iter().zip(&docs) {
+                    let regions = doc.with_untracked(|doc| {
+                        if doc.content.read_only() {
+                            return Err(EditSessionError::ReadOnly {
+                                path: document.path.clone(),
+                            });
+                        }
+                        document.resolve(doc.buffer())
+                    })?;
+                    edits.push((*doc, regions));
                 }
+                for (doc, regions) in edits {
+                    let regions: Vec<(&Selection, &str)> = regions
+                        .iter()
+                        .map(|(selection, text)| (selection, text.as_str()))
+                        .collect();
+                    doc.update(|doc| {
+                        doc.do_raw_edit(&regions, EditType::Other);
+                    });
+                }
+                Ok(())
             }
+        };
+
+        // The session's effect and timeout live in their own scope, which is
+        // disposed of once the session is done either way
+        let (cx, _) = self.scope.run_child_scope(|cx| cx);
+        {
+            let docs = docs.clone();
+            let on_done = on_done.clone();
+            let mut apply = Some(apply);
+            create_effect(cx, move |_| {
+                if !docs.iter().all(|doc| doc.with(|doc| doc.loaded())) {
+                    return;
+                }
+                cx.dispose();
+                if let (Some(apply), Some(on_done)) =
+                    (apply.take(), on_done.borrow_mut().take())
+                {
+                    on_done(apply());
+                }
+            });
         }
+
+        exec_after(cx, EDIT_SESSION_LOAD_TIMEOUT, move || {
+            cx.dispose();
+            let Some(on_done) = on_done.borrow_mut().take() else {
+                return;
+            };
+            let path = paths
+                .into_iter()
+                .zip(docs)
+                .find(|(_, doc)| !doc.with_untracked(|doc| doc.loaded()))
+                .map(|(path, _)| path)
+                .unwrap_or_default();
+            on_done(Err(EditSessionError::NotLoaded { path }));
+        });
     }
 
     pub fn next_error(&self) {
-        let file_diagnostics =
-            self.diagnostics_items(DiagnosticSeverity::ERROR, false);
-        if file_diagnostics.is_empty() {
+        self.jump_to_error(false);
+    }
+
+    pub fn previous_error(&self) {
+        self.jump_to_error(true);
+    }
+
+    /// Jump to the next or previous error in the workspace
+    fn jump_to_error(&self, reverse: bool) {
+        let file_diagnostics: Vec<(PathBuf, Vec<EditorDiagnostic>)> = self
+            .diagnostics_items(DiagnosticSeverity::ERROR, false)
+            .into_iter()
+            .map(|(path, _, diagnostics)| (path, diagnostics))
+            .collect();
+        self.jump_to_diagnostic(&file_diagnostics, reverse);
+    }
+
+    /// Jump to the next or previous diagnostic of any severity in the
+    /// active editor's file
+    pub fn jump_to_diagnostic_in_file(&self, reverse: bool) {
+        let Some((path, _)) = self.active_cursor_position() else {
             return;
-        }
-        let active_editor = self.active_editor.get_untracked();
-        let active_path = active_editor
-            .map(|editor| {
-                editor.with_untracked(|editor| (editor.view.doc, editor.cursor))
-            })
-            .and_then(|(doc, cursor)| {
-                let offset = cursor.with_untracked(|c| c.offset());
-                let (path, position) = doc.with_untracked(|doc| {
-                    (
-                        doc.content.path().cloned(),
-                        doc.buffer().offset_to_position(offset),
-                    )
-                });
-                path.map(|path| (path, position))
-            });
-        let (path, position) =
-            next_in_file_errors_offset(active_path, &file_diagnostics);
+        };
+        let diagnostics = self.diagnostics.with_untracked(|diagnostics| {
+            diagnostics
+                .get(&path)
+                .map(|d| d.diagnostics.get_untracked())
+                .unwrap_or_default()
+        });
+        self.jump_to_diagnostic(
+            &[(path, diagnostics.into_iter().collect())],
+            reverse,
+        );
+    }
+
+    /// Jump to the diagnostic after or before the cursor among the given ones,
+    /// returning the one jumped to.
+    pub fn jump_to_diagnostic(
+        &self,
+        file_diagnostics: &[(PathBuf, Vec<EditorDiagnostic>)],
+        reverse: bool,
+    ) -> Option<(PathBuf, Diagnostic)> {
+        let active = self.active_cursor_position();
+        let (path, diagnostic) = next_diagnostic(active, file_diagnostics, reverse)?;
+
+        let popup = DiagnosticPopup {
+            path: path.clone(),
+            diagnostic: diagnostic.clone(),
+        };
+        self.diagnostic_popup.set(Some(popup.clone()));
+        // The popup is transient, so hide it after a while if it is still
+        // the one being shown
+        let diagnostic_popup = self.diagnostic_popup;
+        exec_after(self.scope, DIAGNOSTIC_POPUP_DURATION, move || {
+            if diagnostic_popup.with_untracked(|p| p.as_ref() == Some(&popup)) {
+                diagnostic_popup.set(None);
+            }
+        });
+
         let location = EditorLocation {
-            path,
-            position: Some(EditorPosition::Position(position)),
+            path: path.clone(),
+            position: Some(EditorPosition::Position(diagnostic.range.start)),
             scroll_offset: None,
             ignore_unconfirmed: false,
             same_editor_tab: false,
         };
         self.jump_to_location(location, None);
+
+        Some((path.clone(), diagnostic.clone()))
+    }
+
+    pub fn next_change(&self, changed_files: &[PathBuf]) {
+        self.jump_to_change(changed_files, false);
+    }
+
+    pub fn previous_change(&self, changed_files: &[PathBuf]) {
+        self.jump_to_change(changed_files, true);
+    }
+
+    /// Jump to the change to the active editor's file since `HEAD` after or
+    /// before the cursor, or past its last or first change, to the first or
+    /// last change of the next or previous of the changed files
+    fn jump_to_change(&self, changed_files: &[PathBuf], reverse: bool) {
+        let active = self.active_editor.get_untracked().and_then(|editor| {
+            let (doc, cursor) =
+                editor.with_untracked(|editor| (editor.view.doc, editor.cursor));
+            let offset = cursor.with_untracked(|c| c.offset());
+            doc.with_untracked(|doc| {
+                let path = doc.content.path()?.clone();
+                let line = doc.buffer().line_of_offset(offset);
+                Some((path, line, doc.head_hunks()))
+            })
+        });
+
+        if let Some((path, line, hunks)) = &active {
+            let hunk = if reverse {
+                hunks.iter().rev().find(|hunk| hunk.right.start < *line)
+            } else {
+                hunks.iter().find(|hunk| hunk.right.start > *line)
+            };
+            if let Some(hunk) = hunk {
+                self.jump_to_location(change_location(path.clone(), hunk), None);
+                return;
+            }
+        }
+
+        let active_path = active.map(|(path, _, _)| path);
+        let Some(path) = next_changed_file(active_path, changed_files, reverse)
+        else {
+            return;
+        };
+        let (doc, _) = self.get_doc(path.clone());
+        let hunks = doc.with_untracked(|doc| doc.head_hunks());
+        let hunk = if reverse { hunks.last() } else { hunks.first() };
+        if let Some(hunk) = hunk {
+            self.jump_to_location(change_location(path, hunk), None);
+            return;
+        }
+
+        // The changes of a file that wasn't open are diffed once its text at
+        // `HEAD` is retrieved, so it's opened at its start until then
+        self.jump_to_location(
+            EditorLocation {
+                path: path.clone(),
+                position: None,
+                scroll_offset: None,
+                ignore_unconfirmed: false,
+                same_editor_tab: false,
+            },
+            None,
+        );
+        let (cx, _) = self.scope.run_child_scope(|cx| cx);
+        let main_split = self.clone();
+        let head_changes = doc.with_untracked(|doc| doc.head_changes);
+        create_effect(cx, move |_| {
+            let changes = head_changes.get();
+            let hunks = diff_hunks(&changes.into_iter().collect::<Vec<_>>());
+            let hunk = if reverse { hunks.last() } else { hunks.first() };
+            if let Some(hunk) = hunk {
+                cx.dispose();
+                let location = change_location(path.clone(), hunk);
+                main_split.go_to_location(location, None);
+            }
+        });
+        exec_after(cx, CHANGE_JUMP_TIMEOUT, move || cx.dispose());
+    }
+
+    /// The path of the active editor's file and the position of its cursor
+    fn active_cursor_position(&self) -> Option<(PathBuf, Position)> {
+        let editor = self.active_editor.get_untracked()?;
+        let (doc, cursor) =
+            editor.with_untracked(|editor| (editor.view.doc, editor.cursor));
+        let offset = cursor.with_untracked(|c| c.offset());
+        let (path, position) = doc.with_untracked(|doc| {
+            (
+                doc.content.path().cloned(),
+                doc.buffer().offset_to_position(offset),
+            )
+        });
+        path.map(|path| (path, position))
     }
 
     pub fn diagnostics_items(
@@ -1649,8 +2849,7 @@ pub fn get_diagnostic_data(&self, path: &Path) -> DiagnosticData {
     }
 
     pub fn open_file_changed(&self, path: &Path, content: &str) {
-        let doc = self.docs.with_untracked(|docs| docs.get(path).copied());
-        let doc = match doc {
+        let doc = match self.doc_of_path(path) {
             Some(doc) => doc,
             None => return,
         };
@@ -1660,6 +2859,80 @@ pub fn open_file_changed(&self, path: &Path, content: &str) {
         });
     }
 
+    /// Make the syntax of the open documents again if the grammars the
+    /// plugins provide changed since it was made
+    pub fn update_grammars(&self) {
+        let rev = plugin_grammars_rev();
+        if self.grammars_rev.get_untracked() == rev {
+            return;
+        }
+        self.grammars_rev.set(rev);
+        for doc in self.docs.get_untracked().values() {
+            doc.update(|doc| {
+                let language = doc.syntax().language;
+                doc.set_language(language);
+                doc.trigger_syntax_change(None);
+            });
+        }
+    }
+
+    /// Update the word characters of the open documents to the language
+    /// settings, which may have been reloaded
+    pub fn update_word_chars(&self) {
+        for doc in self.docs.get_untracked().values() {
+            let stale = doc.with_untracked(|doc| {
+                doc.buffer().word_chars() != doc.syntax().language.word_chars()
+            });
+            if stale {
+                doc.update(|doc| doc.update_word_chars());
+            }
+        }
+    }
+
+    /// Drop the decorations of the plugins other than the ones given
+    pub fn retain_decorations(&self, volts: &HashSet<VoltID>) {
+        for doc in self.docs.get_untracked().values() {
+            let stale = doc.with_untracked(|doc| {
+                doc.decorations.volts().any(|volt| !volts.contains(volt))
+            });
+            if stale {
+                doc.update(|doc| {
+                    doc.retain_decorations(|volt| volts.contains(volt))
+                });
+            }
+        }
+    }
+
+    /// Mark the documents whose files were deleted or renamed outside of the
+    /// editor, and unmark them once their files are back
+    pub fn files_changed(&self, changes: &[FileChange]) {
+        let docs = self.docs.get_untracked();
+        let marks = changes.iter().flat_map(|change| match change {
+            FileChange::Created(path) => vec![(path, None)],
+            FileChange::Modified(_) => Vec::new(),
+            FileChange::Removed(path) => vec![(path, Some(FileGone::Deleted))],
+            FileChange::Renamed { from, to } => {
+                vec![(from, Some(FileGone::Renamed(to.clone()))), (to, None)]
+            }
+        });
+        for (path, gone) in marks {
+            for (doc_path, doc) in docs.iter() {
+                let Ok(rest) = doc_path.strip_prefix(path) else {
+                    continue;
+                };
+                let gone = match &gone {
+                    Some(FileGone::Renamed(to)) => {
+                        Some(FileGone::Renamed(to.join(rest)))
+                    }
+                    gone => gone.clone(),
+                };
+                if doc.with_untracked(|doc| doc.gone != gone) {
+                    doc.update(|doc| doc.gone = gone);
+                }
+            }
+        }
+    }
+
     pub fn set_find_pattern(&self, pattern: Option<String>) {
         if let Some(pattern) = pattern {
             self.find_editor
@@ -1677,6 +2950,17 @@ pub fn set_find_pattern(&self, pattern: Option<String>) {
             .update(|cursor| cursor.set_insert(Selection::region(0, pattern_len)));
     }
 
+    pub fn set_replace_pattern(&self, pattern: String) {
+        let pattern_len = pattern.len();
+        self.replace_editor
+            .view
+            .doc
+            .update(|doc| doc.reload(Rope::from(pattern), true));
+        self.replace_editor
+            .cursor
+            .update(|cursor| cursor.set_insert(Selection::caret(pattern_len)));
+    }
+
     pub fn open_settings(&self) {
         self.get_editor_tab_child(EditorTabChildSource::Settings, false, false);
     }
@@ -1702,7 +2986,37 @@ pub fn can_jump_location_forward(&self, tracked: bool) -> bool {
     }
 }
 
-fn workspace_edits(edit: &WorkspaceEdit) -> Option<HashMap<Url, Vec<TextEdit>>> {
+/// The path of the file with the symlinks resolved, and with the case of the
+/// names on disk on case-insensitive file systems, along with the path itself
+/// if it goes through a symlink. Paths that can't be resolved, such as the ones
+/// of files that don't exist yet, are kept as they are.
+pub fn canonical_path(path: &Path) -> (PathBuf, Option<PathBuf>) {
+    let Ok(canonical) = path.canonicalize() else {
+        return (path.to_path_buf(), None);
+    };
+    // Windows gives verbatim paths, which don't compare equal to the usual ones
+    #[cfg(windows)]
+    let canonical = canonical
+        .to_str()
+        .and_then(|p| p.strip_prefix(r"\\?\"))
+        .filter(|p| !p.starts_with(r"UNC\"))
+        .map(PathBuf::from)
+        .unwrap_or(canonical);
+    let is_symlink = path.ancestors().any(|ancestor| {
+        ancestor
+            .symlink_metadata()
+            .map(|metadata| metadata.file_type().is_symlink())
+            .unwrap_or(false)
+    });
+    let symlink = if is_symlink && canonical != path {
+        Some(path.to_path_buf())
+    } else {
+        None
+    };
+    (canonical, symlink)
+}
+
+pub fn workspace_edits(edit: &WorkspaceEdit) -> Option<HashMap<Url, Vec<TextEdit>>> {
     if let Some(changes) = edit.changes.as_ref() {
         return Some(changes.clone());
     }
@@ -1744,37 +3058,126 @@ fn workspace_edits(edit: &WorkspaceEdit) -> Option<HashMap<Url, Vec<TextEdit>>>
     Some(edits)
 }
 
-fn next_in_file_errors_offset(
-    active_path: Option<(PathBuf, Position)>,
-    file_diagnostics: &[(PathBuf, RwSignal<bool>, Vec<EditorDiagnostic>)],
-) -> (PathBuf, Position) {
-    if let Some((active_path, position)) = active_path {
-        for (current_path, _, diagnostics) in file_diagnostics {
-            if &active_path == current_path {
-                for diagnostic in diagnostics {
-                    if diagnostic.diagnostic.range.start.line > position.line
-                        || (diagnostic.diagnostic.range.start.line == position.line
-                            && diagnostic.diagnostic.range.start.character
-                                > position.character)
-                    {
-                        return (
-                            (*current_path).clone(),
-                            diagnostic.diagnostic.range.start,
-                        );
-                    }
-                }
-            }
-            if current_path > &active_path {
-                return (
-                    (*current_path).clone(),
-                    diagnostics[0].diagnostic.range.start,
-                );
-            }
+/// Find the diagnostic after the active position, or before it if `reverse`,
+/// wrapping around at either end of the workspace.
+fn next_diagnostic<'a>(
+    active: Option<(PathBuf, Position)>,
+    file_diagnostics: &'a [(PathBuf, Vec<EditorDiagnostic>)],
+    reverse: bool,
+) -> Option<(&'a PathBuf, &'a Diagnostic)> {
+    let mut diagnostics: Vec<(&PathBuf, &Diagnostic)> = file_diagnostics
+        .iter()
+        .flat_map(|(path, diagnostics)| {
+            diagnostics.iter().map(move |d| (path, &d.diagnostic))
+        })
+        .collect();
+    diagnostics.sort_by_key(|(path, d)| {
+        (*path, d.range.start.line, d.range.start.character)
+    });
+
+    let Some((active_path, position)) = active else {
+        return if reverse {
+            diagnostics.last().copied()
+        } else {
+            diagnostics.first().copied()
+        };
+    };
+    let active = (&active_path, position.line, position.character);
+    let key = |(path, d): &&(&'a PathBuf, &'a Diagnostic)| {
+        (*path, d.range.start.line, d.range.start.character)
+    };
+    if reverse {
+        diagnostics
+            .iter()
+            .rev()
+            .find(|d| key(d) < active)
+            .or_else(|| diagnostics.last())
+            .copied()
+    } else {
+        diagnostics
+            .iter()
+            .find(|d| key(d) > active)
+            .or_else(|| diagnostics.first())
+            .copied()
+    }
+}
+
+/// The location of the start of the change to the file
+fn change_location(path: PathBuf, hunk: &DiffHunk) -> EditorLocation {
+    EditorLocation {
+        path,
+        position: Some(EditorPosition::Line(hunk.right.start)),
+        scroll_offset: None,
+        ignore_unconfirmed: false,
+        same_editor_tab: false,
+    }
+}
+
+/// The changed file after or before the active one, around to the first or
+/// last of them, which is the active file itself if it's the only one
+fn next_changed_file(
+    active: Option<PathBuf>,
+    changed_files: &[PathBuf],
+    reverse: bool,
+) -> Option<PathBuf> {
+    let mut files: Vec<&PathBuf> = changed_files.iter().collect();
+    files.sort();
+    let next = match active {
+        Some(active) if reverse => files.iter().rev().find(|p| ***p < active),
+        Some(active) => files.iter().find(|p| ***p > active),
+        None => None,
+    };
+    let next = next.or(if reverse { files.last() } else { files.first() });
+    next.map(|path| (*path).clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::Range;
+
+    use super::*;
+
+    fn diagnostic(line: u32, character: u32) -> EditorDiagnostic {
+        let start = Position::new(line, character);
+        EditorDiagnostic {
+            range: (0, 0),
+            diagnostic: Diagnostic::new_simple(
+                Range::new(start, Position::new(line, character + 1)),
+                "error".to_string(),
+            ),
         }
     }
 
-    (
-        file_diagnostics[0].0.clone(),
-        file_diagnostics[0].2[0].diagnostic.range.start,
-    )
+    #[test]
+    fn test_next_diagnostic() {
+        let a = PathBuf::from("a.rs");
+        let b = PathBuf::from("b.rs");
+        // the diagnostics are out of order, as they can be when they come
+        let file_diagnostics = vec![
+            (b.clone(), vec![diagnostic(0, 4)]),
+            (a.clone(), vec![diagnostic(5, 2), diagnostic(1, 0)]),
+        ];
+        let next = |active: Option<(&PathBuf, u32, u32)>, reverse: bool| {
+            let active = active
+                .map(|(path, line, col)| (path.clone(), Position::new(line, col)));
+            next_diagnostic(active, &file_diagnostics, reverse).map(|(path, d)| {
+                (path.clone(), d.range.start.line, d.range.start.character)
+            })
+        };
+
+        assert_eq!(next(None, false), Some((a.clone(), 1, 0)));
+        assert_eq!(next(None, true), Some((b.clone(), 0, 4)));
+
+        // the diagnostic at the cursor is the one jumped from
+        assert_eq!(next(Some((&a, 1, 0)), false), Some((a.clone(), 5, 2)));
+        assert_eq!(next(Some((&a, 1, 0)), true), Some((b.clone(), 0, 4)));
+
+        assert_eq!(next(Some((&a, 6, 0)), false), Some((b.clone(), 0, 4)));
+        assert_eq!(next(Some((&a, 6, 0)), true), Some((a.clone(), 5, 2)));
+
+        // it wraps around at the end of the workspace
+        assert_eq!(next(Some((&b, 3, 0)), false), Some((a.clone(), 1, 0)));
+
+        assert_eq!(next_diagnostic(None, &[], false), None);
+    }
 }
@@ -0,0 +1,351 @@
+use std::{ops::Range, str::FromStr};
+
+use floem::cosmic_text::{
+    Attrs, AttrsList, FamilyOwned, LineHeightValue, Style as FontStyle, TextLayout,
+    Weight,
+};
+use lapce_core::{language::LapceLanguage, syntax::Syntax};
+use lapce_xi_rope::Rope;
+use lsp_types::{Documentation, MarkedString, MarkupKind};
+use pulldown_cmark::{CodeBlockKind, CowStr, Event, Options, Parser, Tag};
+use smallvec::SmallVec;
+use tracing::warn;
+
+use crate::config::{color::LapceColor, LapceConfig};
+
+#[derive(Clone)]
+pub enum MarkdownContent {
+    Text(TextLayout),
+    Image { url: String, title: String },
+    Separator,
+}
+
+/// Collects the text of a markdown block and the attributes of its ranges,
+/// so that they can be put into a [`TextLayout`] once the block is done.
+struct MarkdownTextBuilder<'a> {
+    text: String,
+    default_attrs: Attrs<'a>,
+    spans: Vec<(Range<usize>, Attrs<'a>)>,
+}
+
+impl<'a> MarkdownTextBuilder<'a> {
+    fn new(default_attrs: Attrs<'a>) -> Self {
+        Self {
+            text: String::new(),
+            default_attrs,
+            spans: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, text: &str) {
+        self.text.push_str(text);
+    }
+
+    fn push_with_attrs(&mut self, text: &str, attrs: Attrs<'a>) {
+        let start = self.text.len();
+        self.text.push_str(text);
+        self.spans.push((start..self.text.len(), attrs));
+    }
+
+    fn add_span(&mut self, range: Range<usize>, attrs: Attrs<'a>) {
+        self.spans.push((range, attrs));
+    }
+
+    fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// Build the text layout, resetting the builder for the next block
+    fn build(&mut self) -> TextLayout {
+        let mut attrs_list = AttrsList::new(self.default_attrs);
+        // Tags end from the innermost outwards, so the spans are applied in
+        // reverse for the inner styles to take precedence.
+        for (range, attrs) in self.spans.drain(..).rev() {
+            attrs_list.add_span(range, attrs);
+        }
+        let mut text_layout = TextLayout::new();
+        text_layout.set_text(&self.text, attrs_list);
+        self.text.clear();
+        text_layout
+    }
+}
+
+/// Parse the LSP documentation structure
+pub fn parse_documentation(
+    doc: &Documentation,
+    config: &LapceConfig,
+) -> Vec<MarkdownContent> {
+    match doc {
+        // We assume this is plain text
+        Documentation::String(text) => plain_text(text, 1.5, config),
+        Documentation::MarkupContent(content) => match content.kind {
+            MarkupKind::PlainText => plain_text(&content.value, 1.5, config),
+            MarkupKind::Markdown => parse_markdown(&content.value, 1.5, config),
+        },
+    }
+}
+
+pub fn from_marked_string(
+    text: MarkedString,
+    config: &LapceConfig,
+) -> Vec<MarkdownContent> {
+    match text {
+        MarkedString::String(text) => parse_markdown(&text, 1.5, config),
+        // This is a short version of a code block
+        MarkedString::LanguageString(code) => parse_markdown(
+            &format!("```{}\n{}\n```", code.language, code.value),
+            1.5,
+            config,
+        ),
+    }
+}
+
+fn plain_text(
+    text: &str,
+    line_height: f32,
+    config: &LapceConfig,
+) -> Vec<MarkdownContent> {
+    let font_family = config.ui.font_family();
+    let mut builder =
+        MarkdownTextBuilder::new(default_attrs(&font_family, line_height, config));
+    builder.push(text);
+    vec![MarkdownContent::Text(builder.build())]
+}
+
+fn default_attrs<'a>(
+    font_family: &'a [FamilyOwned],
+    line_height: f32,
+    config: &LapceConfig,
+) -> Attrs<'a> {
+    Attrs::new()
+        .color(*config.get_color(LapceColor::EDITOR_FOREGROUND))
+        .family(font_family)
+        .font_size(config.ui.font_size() as f32)
+        .line_height(LineHeightValue::Normal(line_height))
+}
+
+pub fn parse_markdown(
+    text: &str,
+    line_height: f32,
+    config: &LapceConfig,
+) -> Vec<MarkdownContent> {
+    let mut res = Vec::new();
+
+    let font_family = config.ui.font_family();
+    let code_font_family: Vec<FamilyOwned> =
+        FamilyOwned::parse_list(&config.editor.font_family).collect();
+    let attrs = default_attrs(&font_family, line_height, config);
+    let code_attrs = attrs
+        .family(&code_font_family)
+        .font_size(config.editor.font_size() as f32);
+
+    let mut builder = MarkdownTextBuilder::new(attrs);
+
+    let mut tag_stack: SmallVec<[(usize, Tag); 4]> = SmallVec::new();
+
+    let parser = Parser::new_ext(
+        text,
+        Options::ENABLE_TABLES
+            | Options::ENABLE_FOOTNOTES
+            | Options::ENABLE_STRIKETHROUGH
+            | Options::ENABLE_TASKLISTS
+            | Options::ENABLE_HEADING_ATTRIBUTES,
+    );
+    let mut last_text = CowStr::from("");
+    // Whether we should add a newline on the next entry
+    // This is used so that we don't emit newlines at the very end of the generation
+    let mut add_newline = false;
+    for event in parser {
+        // Add the newline since we're going to be outputting more
+        if add_newline {
+            builder.push("\n");
+            add_newline = false;
+        }
+
+        match event {
+            Event::Start(tag) => {
+                tag_stack.push((builder.text.len(), tag));
+            }
+            Event::End(end_tag) => {
+                let Some((start_offset, tag)) = tag_stack.pop() else {
+                    warn!("Unbalanced markdown tag");
+                    continue;
+                };
+                if end_tag != tag {
+                    warn!("Mismatched markdown tag");
+                    continue;
+                }
+
+                // The text before an image is split off into its own block,
+                // which the tag may have started in.
+                let start_offset = start_offset.min(builder.text.len());
+                let range = start_offset..builder.text.len();
+                if let Tag::CodeBlock(kind) = &tag {
+                    let language = if let CodeBlockKind::Fenced(language) = kind {
+                        md_language_to_lapce_language(language)
+                    } else {
+                        None
+                    };
+                    // The highlighting is added before the code block's own
+                    // attributes, so that it is applied after them.
+                    highlight_as_code(
+                        &mut builder,
+                        code_attrs,
+                        config,
+                        language,
+                        &last_text,
+                        start_offset,
+                    );
+                }
+                if let Some(attrs) = attrs_for_tag(&tag, attrs, code_attrs, config) {
+                    builder.add_span(range, attrs);
+                }
+
+                if should_add_newline_after_tag(&tag) {
+                    add_newline = true;
+                }
+
+                if let Tag::Image(_link_type, dest, title) = &tag {
+                    if !builder.is_empty() {
+                        res.push(MarkdownContent::Text(builder.build()));
+                    }
+                    res.push(MarkdownContent::Image {
+                        url: dest.to_string(),
+                        title: title.to_string(),
+                    });
+                }
+            }
+            Event::Text(text) => {
+                if let Some((_, tag)) = tag_stack.last() {
+                    if should_skip_text_in_tag(tag) {
+                        continue;
+                    }
+                }
+                builder.push(&text);
+                last_text = text;
+            }
+            Event::Code(text) => {
+                builder.push_with_attrs(&text, code_attrs);
+            }
+            // TODO: Some minimal 'parsing' of html could be useful here, since some things use
+            // basic html like `<code>text</code>`.
+            Event::Html(text) => {
+                builder.push_with_attrs(
+                    &text,
+                    code_attrs
+                        .color(*config.get_color(LapceColor::MARKDOWN_BLOCKQUOTE)),
+                );
+            }
+            Event::HardBreak => {
+                builder.push("\n");
+            }
+            Event::SoftBreak => {
+                builder.push(" ");
+            }
+            Event::Rule => {
+                if !builder.is_empty() {
+                    res.push(MarkdownContent::Text(builder.build()));
+                }
+                res.push(MarkdownContent::Separator);
+            }
+            Event::FootnoteReference(_text) => {}
+            Event::TaskListMarker(_text) => {}
+        }
+    }
+
+    if !builder.is_empty() {
+        res.push(MarkdownContent::Text(builder.build()));
+    }
+
+    res
+}
+
+/// Highlight the text like it was a markdown codeblock
+fn highlight_as_code<'a>(
+    builder: &mut MarkdownTextBuilder<'a>,
+    code_attrs: Attrs<'a>,
+    config: &LapceConfig,
+    language: Option<LapceLanguage>,
+    text: &str,
+    start_offset: usize,
+) {
+    let syntax = language.map(Syntax::from_language);
+
+    let styles = syntax.and_then(|mut syntax| {
+        syntax.parse(0, Rope::from(text), None);
+        syntax.styles
+    });
+
+    if let Some(styles) = styles {
+        for (range, style) in styles.iter() {
+            if let Some(color) = style
+                .fg_color
+                .as_ref()
+                .and_then(|fg| config.get_style_color(fg))
+            {
+                builder.add_span(
+                    start_offset + range.start..start_offset + range.end,
+                    code_attrs.color(*color),
+                );
+            }
+        }
+    }
+}
+
+fn attrs_for_tag<'a>(
+    tag: &Tag,
+    attrs: Attrs<'a>,
+    code_attrs: Attrs<'a>,
+    config: &LapceConfig,
+) -> Option<Attrs<'a>> {
+    use pulldown_cmark::HeadingLevel;
+    let attrs = match tag {
+        Tag::Heading(level, _, _) => {
+            // The size calculations are based on the em values given at
+            // https://drafts.csswg.org/css2/#html-stylesheet
+            let font_scale = match level {
+                HeadingLevel::H1 => 2.0,
+                HeadingLevel::H2 => 1.5,
+                HeadingLevel::H3 => 1.17,
+                HeadingLevel::H4 => 1.0,
+                HeadingLevel::H5 => 0.83,
+                HeadingLevel::H6 => 0.75,
+            };
+            let font_size = font_scale * config.ui.font_size() as f32;
+            attrs.font_size(font_size).weight(Weight::BOLD)
+        }
+        Tag::BlockQuote => attrs
+            .style(FontStyle::Italic)
+            .color(*config.get_color(LapceColor::MARKDOWN_BLOCKQUOTE)),
+        Tag::CodeBlock(_) => code_attrs,
+        Tag::Emphasis => attrs.style(FontStyle::Italic),
+        Tag::Strong => attrs.weight(Weight::BOLD),
+        // TODO: Strikethrough support
+        Tag::Link(_link_type, _target, _title) => {
+            // TODO: Link support
+            attrs.color(*config.get_color(LapceColor::EDITOR_LINK))
+        }
+        // All other tags are currently ignored
+        _ => return None,
+    };
+    Some(attrs)
+}
+
+/// Decides whether newlines should be added after a specific markdown tag
+fn should_add_newline_after_tag(tag: &Tag) -> bool {
+    !matches!(
+        tag,
+        Tag::Emphasis | Tag::Strong | Tag::Strikethrough | Tag::Link(..)
+    )
+}
+
+/// Whether it should skip the text node after a specific tag
+/// For example, images are skipped because it emits their title as a separate text node.
+fn should_skip_text_in_tag(tag: &Tag) -> bool {
+    matches!(tag, Tag::Image(..))
+}
+
+fn md_language_to_lapce_language(lang: &str) -> Option<LapceLanguage> {
+    // TODO: There are many other names commonly used that should be supported
+    LapceLanguage::from_str(lang).ok()
+}
@@ -0,0 +1,259 @@
+use std::{collections::BTreeMap, path::Path};
+
+use serde::Deserialize;
+
+/// The indentation of the inputs and outputs of code cells in the rendered
+/// notebook
+const INDENT: &str = "    ";
+
+pub fn is_notebook(path: &Path) -> bool {
+    path.extension().and_then(|ext| ext.to_str()) == Some("ipynb")
+}
+
+/// Text that notebooks store either as a string or as a list of lines
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MultilineString {
+    Single(String),
+    Lines(Vec<String>),
+}
+
+impl Default for MultilineString {
+    fn default() -> Self {
+        MultilineString::Single(String::new())
+    }
+}
+
+impl MultilineString {
+    fn text(&self) -> String {
+        match self {
+            MultilineString::Single(text) => text.clone(),
+            MultilineString::Lines(lines) => lines.concat(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "output_type", rename_all = "snake_case")]
+enum Output {
+    Stream {
+        #[serde(default)]
+        text: MultilineString,
+    },
+    ExecuteResult {
+        #[serde(default)]
+        data: BTreeMap<String, serde_json::Value>,
+    },
+    DisplayData {
+        #[serde(default)]
+        data: BTreeMap<String, serde_json::Value>,
+    },
+    Error {
+        ename: String,
+        evalue: String,
+    },
+}
+
+impl Output {
+    /// The output as text, with its images as placeholders naming their type
+    /// and giving their text if they have one, such as the size of a figure.
+    /// The notebook is shown in a text editor, which can't draw the images.
+    fn text(&self) -> String {
+        match self {
+            Output::Stream { text } => text.text(),
+            Output::ExecuteResult { data } | Output::DisplayData { data } => {
+                let text = data
+                    .get("text/plain")
+                    .and_then(|text| MultilineString::deserialize(text.clone()).ok())
+                    .map(|text| text.text());
+                match (data.keys().find(|mime| mime.starts_with("image/")), text) {
+                    (Some(image), Some(text)) => format!("[{image}: {text}]"),
+                    (Some(image), None) => format!("[{image}]"),
+                    (None, text) => text.unwrap_or_default(),
+                }
+            }
+            Output::Error { ename, evalue } => format!("{ename}: {evalue}"),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "cell_type", rename_all = "snake_case")]
+enum Cell {
+    Code {
+        #[serde(default)]
+        source: MultilineString,
+        execution_count: Option<u64>,
+        #[serde(default)]
+        outputs: Vec<Output>,
+    },
+    Markdown {
+        #[serde(default)]
+        source: MultilineString,
+    },
+    Raw {
+        #[serde(default)]
+        source: MultilineString,
+    },
+}
+
+/// A Jupyter notebook, of the `nbformat` 4 format
+#[derive(Deserialize)]
+pub struct Notebook {
+    cells: Vec<Cell>,
+}
+
+fn push_indented(rendered: &mut Vec<String>, text: &str) {
+    rendered.extend(text.lines().map(|line| format!("{INDENT}{line}")));
+}
+
+impl Notebook {
+    pub fn parse(content: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(content)
+    }
+
+    /// The notebook laid out as text, with the inputs of the code cells and
+    /// their outputs under `In [n]:` and `Out [n]:` labels
+    pub fn render(&self) -> String {
+        let mut rendered = Vec::new();
+        for cell in &self.cells {
+            if !rendered.is_empty() {
+                rendered.push(String::new());
+            }
+            match cell {
+                Cell::Code {
+                    source,
+                    execution_count,
+                    outputs,
+                } => {
+                    let count = execution_count
+                        .map(|count| count.to_string())
+                        .unwrap_or_else(|| " ".to_string());
+                    rendered.push(format!("In [{count}]:"));
+                    push_indented(&mut rendered, &source.text());
+                    if !outputs.is_empty() {
+                        rendered.push(format!("Out [{count}]:"));
+                        for output in outputs {
+                            push_indented(&mut rendered, &output.text());
+                        }
+                    }
+                }
+                Cell::Markdown { source } | Cell::Raw { source } => {
+                    rendered.extend(source.text().lines().map(|l| l.to_string()));
+                }
+            }
+        }
+        rendered.join("\n")
+    }
+
+    /// The notebook as a script in the percent format, where each cell starts
+    /// with a `# %%` line and the text of markdown and raw cells is commented
+    pub fn to_script(&self) -> String {
+        let mut script = Vec::new();
+        for cell in &self.cells {
+            if !script.is_empty() {
+                script.push(String::new());
+            }
+            match cell {
+                Cell::Code { source, .. } => {
+                    script.push("# %%".to_string());
+                    script.extend(source.text().lines().map(|l| l.to_string()));
+                }
+                Cell::Markdown { source } | Cell::Raw { source } => {
+                    let kind = match cell {
+                        Cell::Markdown { .. } => "markdown",
+                        _ => "raw",
+                    };
+                    script.push(format!("# %% [{kind}]"));
+                    script.extend(source.text().lines().map(|line| {
+                        if line.is_empty() {
+                            "#".to_string()
+                        } else {
+                            format!("# {line}")
+                        }
+                    }));
+                }
+            }
+        }
+        script.push(String::new());
+        script.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOTEBOOK: &str = r##"{
+        "cells": [
+            {"cell_type": "markdown", "metadata": {},
+             "source": ["# Title\n", "\n", "Text"]},
+            {
+                "cell_type": "code",
+                "execution_count": 1,
+                "metadata": {},
+                "source": ["print('a')\n", "1 + 1"],
+                "outputs": [
+                    {"output_type": "stream", "name": "stdout", "text": ["a\n"]},
+                    {"output_type": "execute_result", "execution_count": 1,
+                     "metadata": {}, "data": {"text/plain": ["2"]}},
+                    {"output_type": "display_data", "metadata": {},
+                     "data": {"image/png": "iVBORw0KGgo=",
+                              "text/plain": "<Figure>"}},
+                    {"output_type": "display_data", "metadata": {},
+                     "data": {"image/svg+xml": "<svg/>"}}
+                ]
+            },
+            {"cell_type": "code", "execution_count": null, "metadata": {},
+             "source": "x", "outputs": []}
+        ],
+        "metadata": {},
+        "nbformat": 4,
+        "nbformat_minor": 5
+    }"##;
+
+    #[test]
+    fn test_render() {
+        let notebook = Notebook::parse(NOTEBOOK).unwrap();
+        let expected = [
+            "# Title",
+            "",
+            "Text",
+            "",
+            "In [1]:",
+            "    print('a')",
+            "    1 + 1",
+            "Out [1]:",
+            "    a",
+            "    2",
+            "    [image/png: <Figure>]",
+            "    [image/svg+xml]",
+            "",
+            "In [ ]:",
+            "    x",
+        ]
+        .join("\n");
+        assert_eq!(notebook.render(), expected);
+    }
+
+    #[test]
+    fn test_to_script() {
+        let notebook = Notebook::parse(NOTEBOOK).unwrap();
+        let expected = [
+            "# %% [markdown]",
+            "# # Title",
+            "#",
+            "# Text",
+            "",
+            "# %%",
+            "print('a')",
+            "1 + 1",
+            "",
+            "# %%",
+            "x",
+            "",
+        ]
+        .join("\n");
+        assert_eq!(notebook.to_script(), expected);
+    }
+}
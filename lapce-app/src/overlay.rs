@@ -0,0 +1,159 @@
+use floem::reactive::{
+    create_rw_signal, RwSignal, Scope, SignalUpdate, SignalWithUntracked,
+};
+
+use crate::{command::InternalCommand, listener::Listener};
+
+/// A popup shown over the editors, at the text it's about
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OverlayKind {
+    Completion,
+    Signature,
+    CodeAction,
+    Rename,
+    /// The lines a change to the file replaced, opened from the gutter
+    Hunk,
+}
+
+/// What happened that makes the popups shown stale
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OverlayEvent {
+    /// The editor was scrolled away from where the popups point at
+    Scroll,
+    /// Something other than the editor or the popups got the focus
+    Blur,
+    /// The editor changed its mode, such as going back to normal mode
+    ModeChange,
+}
+
+impl OverlayKind {
+    /// Whether the popups can be shown at the same time. The signature help
+    /// makes room for the completion, while the others take the keyboard
+    /// and so go alone.
+    pub fn compatible_with(self, other: OverlayKind) -> bool {
+        use OverlayKind::*;
+        matches!(
+            (self, other),
+            (Completion, Signature) | (Signature, Completion)
+        )
+    }
+
+    /// Whether the popup closes on the event
+    pub fn dismissed_by(self, event: OverlayEvent) -> bool {
+        match event {
+            OverlayEvent::Scroll => self != OverlayKind::Rename,
+            OverlayEvent::Blur => true,
+            OverlayEvent::ModeChange => {
+                matches!(self, OverlayKind::Completion | OverlayKind::Signature)
+            }
+        }
+    }
+}
+
+/// Keeps track of the popups shown over the editors, so that only ones that
+/// get along are shown together, and that they are closed when what they
+/// are about goes away.
+///
+/// The popups report when they are shown and hidden, and are closed with
+/// [`InternalCommand::CloseOverlay`] by the window tab, which owns them all.
+#[derive(Clone, Copy)]
+pub struct OverlayManager {
+    /// The popups shown, the one shown last on top
+    shown: RwSignal<Vec<OverlayKind>>,
+    internal_command: Listener<InternalCommand>,
+}
+
+impl OverlayManager {
+    pub fn new(cx: Scope, internal_command: Listener<InternalCommand>) -> Self {
+        Self {
+            shown: create_rw_signal(cx, Vec::new()),
+            internal_command,
+        }
+    }
+
+    pub fn is_shown(&self, kind: OverlayKind) -> bool {
+        self.shown.with_untracked(|shown| shown.contains(&kind))
+    }
+
+    /// Report the popup as shown, closing the ones it can't be shown with
+    pub fn show(&self, kind: OverlayKind) {
+        let incompatible: Vec<OverlayKind> = self.shown.with_untracked(|shown| {
+            shown
+                .iter()
+                .copied()
+                .filter(|other| *other != kind && !kind.compatible_with(*other))
+                .collect()
+        });
+        for other in incompatible {
+            self.close(other);
+        }
+        self.shown.update(|shown| {
+            shown.retain(|other| *other != kind);
+            shown.push(kind);
+        });
+    }
+
+    /// Report the popup as hidden, when it closed itself
+    pub fn hidden(&self, kind: OverlayKind) {
+        if self.is_shown(kind) {
+            self.shown
+                .update(|shown| shown.retain(|other| *other != kind));
+        }
+    }
+
+    /// Close the popups the event makes stale
+    pub fn dismiss(&self, event: OverlayEvent) {
+        let dismissed: Vec<OverlayKind> = self.shown.with_untracked(|shown| {
+            shown
+                .iter()
+                .copied()
+                .filter(|kind| kind.dismissed_by(event))
+                .collect()
+        });
+        for kind in dismissed {
+            self.close(kind);
+        }
+    }
+
+    /// Close the popup on top for Escape, returning whether there was one
+    pub fn escape(&self) -> bool {
+        match self.shown.with_untracked(|shown| shown.last().copied()) {
+            Some(kind) => {
+                self.close(kind);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn close(&self, kind: OverlayKind) {
+        self.hidden(kind);
+        self.internal_command
+            .send(InternalCommand::CloseOverlay { kind });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compatible_overlays() {
+        use OverlayKind::*;
+        assert!(Completion.compatible_with(Signature));
+        assert!(Signature.compatible_with(Completion));
+        assert!(!Completion.compatible_with(CodeAction));
+        assert!(!Signature.compatible_with(Rename));
+        assert!(!CodeAction.compatible_with(Rename));
+    }
+
+    #[test]
+    fn test_dismissed_overlays() {
+        use OverlayKind::*;
+        assert!(Completion.dismissed_by(OverlayEvent::Scroll));
+        assert!(!Rename.dismissed_by(OverlayEvent::Scroll));
+        assert!(Rename.dismissed_by(OverlayEvent::Blur));
+        assert!(Signature.dismissed_by(OverlayEvent::ModeChange));
+        assert!(!CodeAction.dismissed_by(OverlayEvent::ModeChange));
+    }
+}
@@ -4,48 +4,72 @@
     path::PathBuf,
     rc::Rc,
     sync::{
-        atomic::{AtomicU64, Ordering},
+        atomic::{AtomicBool, AtomicU64, Ordering},
         Arc,
     },
     time::Instant,
 };
 
 use anyhow::Result;
+use chrono::TimeZone;
 use crossbeam_channel::{Receiver, Sender, TryRecvError};
 use floem::{
-    ext_event::{create_ext_action, create_signal_from_channel},
+    ext_event::create_signal_from_channel,
     reactive::{
         create_effect, create_rw_signal, create_signal, use_context, ReadSignal,
         RwSignal, Scope, SignalGet, SignalGetUntracked, SignalSet, SignalUpdate,
         SignalWith, SignalWithUntracked,
     },
 };
-use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
 use itertools::Itertools;
 use lapce_core::{
-    buffer::rope_text::RopeText, command::FocusCommand, language::LapceLanguage,
-    mode::Mode, movement::Movement, selection::Selection, syntax::Syntax,
+    buffer::rope_text::RopeText, command::FocusCommand, indent::IndentStyle,
+    language::LapceLanguage, mode::Mode, movement::Movement, selection::Selection,
+    syntax::Syntax,
+};
+use lapce_rpc::{
+    dap_types::RunDebugConfig,
+    plugin::VoltMetadata,
+    protocol::Capability,
+    proxy::{ProxyResponse, ProxyRpcHandler},
+    source_control::FileCommit,
 };
-use lapce_rpc::proxy::ProxyResponse;
 use lapce_xi_rope::Rope;
-use lsp_types::DocumentSymbolResponse;
+use lsp_types::{DocumentSymbolResponse, SymbolInformation};
+use tracing::error;
 
 use self::{
-    item::{PaletteItem, PaletteItemContent},
+    item::{PaletteItem, PaletteItemContent, RevisionView},
     kind::PaletteKind,
+    pipeline::{self, PaletteStage},
+    provider::{PaletteBatch, PaletteItemStream},
 };
 use crate::{
-    command::{CommandExecuted, CommandKind, InternalCommand, WindowCommand},
+    burn_down,
+    command::{
+        CommandExecuted, CommandKind, InternalCommand, LapceCommand,
+        LapceWorkbenchCommand, WindowCommand,
+    },
     db::LapceDb,
-    debug::{run_configs, RunDebugMode},
+    debug::{run_configs, split_args, RunDebugMode},
+    doc::SystemClipboard,
     editor::{
         location::{EditorLocation, EditorPosition},
         EditorData,
     },
+    frecency,
+    fuzzy::FuzzyMatcher,
     id::EditorId,
-    keypress::{condition::Condition, KeyPressData, KeyPressFocus},
+    keypress::{
+        condition::Condition,
+        review::{KeymapChange, KeymapResolution},
+        KeyPressData, KeyPressFocus,
+    },
     main_split::MainSplitData,
+    plugin::PluginData,
+    profile::{Profile, DEFAULT_PROFILE},
     proxy::path_from_url,
+    registers::{register_entries, register_preview},
     source_control::SourceControlData,
     window_tab::{CommonData, Focus},
     workspace::{LapceWorkspace, LapceWorkspaceType, SshHost},
@@ -53,6 +77,21 @@
 
 pub mod item;
 pub mod kind;
+pub mod pipeline;
+pub mod provider;
+
+/// How many commits of the history of a file are listed at once
+const FILE_HISTORY_PAGE: usize = 200;
+
+/// The items to match against the input for a run, with the stages of the
+/// palette kind to present them through
+type FilterRequest = (
+    u64,
+    String,
+    &'static [PaletteStage],
+    im::Vector<PaletteItem>,
+    FuzzyMatcher,
+);
 
 #[derive(Clone, PartialEq, Eq)]
 pub enum PaletteStatus {
@@ -84,6 +123,9 @@ pub struct PaletteData {
     pub index: RwSignal<usize>,
     pub items: RwSignal<im::Vector<PaletteItem>>,
     pub filtered_items: ReadSignal<im::Vector<PaletteItem>>,
+    /// Whether a provider is still streaming items for the current run
+    pub loading: RwSignal<bool>,
+    batch_tx: Sender<PaletteBatch>,
     pub input: RwSignal<PaletteInput>,
     kind: RwSignal<PaletteKind>,
     pub input_editor: EditorData,
@@ -96,7 +138,18 @@ pub struct PaletteData {
     pub executed_run_configs: Rc<RefCell<HashMap<(RunDebugMode, String), Instant>>>,
     pub main_split: MainSplitData,
     pub references: RwSignal<Vec<EditorLocation>>,
+    /// The run config of the file waiting for its arguments to be entered
+    pub run_current_file: RwSignal<Option<RunDebugConfig>>,
+    /// The plugin whose permissions are being reviewed, or `None` to pick one
+    pub plugin_permissions_volt: RwSignal<Option<VoltMetadata>>,
+    /// The change of the default keymaps being reviewed, or `None` to pick
+    /// one
+    pub keymap_change: RwSignal<Option<KeymapChange>>,
+    /// The commit of the file whose history is shown, to open the file as of
+    /// it, or `None` to pick one
+    pub file_commit: RwSignal<Option<(PathBuf, FileCommit)>>,
     pub source_control: SourceControlData,
+    pub plugin: PluginData,
     pub common: CommonData,
 }
 
@@ -107,12 +160,17 @@ pub fn new(
         main_split: MainSplitData,
         keypress: ReadSignal<KeyPressData>,
         source_control: SourceControlData,
+        plugin: PluginData,
         common: CommonData,
     ) -> Self {
         let status = create_rw_signal(cx, PaletteStatus::Inactive);
         let items = create_rw_signal(cx, im::Vector::new());
         let index = create_rw_signal(cx, 0);
         let references = create_rw_signal(cx, Vec::new());
+        let run_current_file = create_rw_signal(cx, None);
+        let plugin_permissions_volt = create_rw_signal(cx, None);
+        let keymap_change = create_rw_signal(cx, None);
+        let file_commit = create_rw_signal(cx, None);
         let input = create_rw_signal(
             cx,
             PaletteInput {
@@ -135,6 +193,7 @@ pub fn new(
             let run_id = run_id.read_only();
             let input = input.read_only();
             let items = items.read_only();
+            let config = common.config;
             let tx = run_tx;
 
             {
@@ -144,7 +203,9 @@ pub fn new(
                     let items = items.get();
                     let input = input.get_untracked();
                     let run_id = run_id.get_untracked();
-                    let _ = tx.send((run_id, input.input, items));
+                    let matcher = FuzzyMatcher::from_config(&config.get_untracked());
+                    let stages = input.kind.pipeline();
+                    let _ = tx.send((run_id, input.input, stages, items, matcher));
                 });
             }
 
@@ -158,7 +219,9 @@ pub fn new(
 
                 let items = items.get_untracked();
                 let run_id = run_id.get_untracked();
-                let _ = tx.send((run_id, input.input, items));
+                let matcher = FuzzyMatcher::from_config(&config.get_untracked());
+                let stages = kind.pipeline();
+                let _ = tx.send((run_id, input.input, stages, items, matcher));
                 kind
             });
         }
@@ -182,13 +245,40 @@ pub fn new(
                     if run_id.get_untracked() == filter_run_id
                         && input.get_untracked().input == filter_input
                     {
+                        // Start at the first item that can be picked
+                        let first = new_items
+                            .iter()
+                            .position(|item| !item.is_header())
+                            .unwrap_or(0);
                         set_filtered_items.set(new_items);
-                        index.set(0);
+                        index.set(first);
                     }
                 }
             });
         }
 
+        let loading = create_rw_signal(cx, false);
+        let (batch_tx, batch_rx) = crossbeam_channel::unbounded();
+        {
+            let batch = create_signal_from_channel(cx, batch_rx);
+            let run_id_counter = run_id_counter.clone();
+            create_effect(cx, move |_| {
+                let Some(batch) = batch.get() else { return };
+                // Drop the items of providers for previous runs
+                if batch.run_id != run_id_counter.load(Ordering::Acquire) {
+                    return;
+                }
+                if batch.replace {
+                    items.set(batch.items);
+                } else if !batch.items.is_empty() {
+                    items.update(|items| items.extend(batch.items));
+                }
+                if batch.done {
+                    loading.set(false);
+                }
+            });
+        }
+
         let clicked_index = create_rw_signal(cx, Option::<usize>::None);
 
         let palette = Self {
@@ -200,6 +290,8 @@ pub fn new(
             index,
             items,
             filtered_items,
+            loading,
+            batch_tx,
             input_editor,
             preview_editor,
             has_preview,
@@ -210,7 +302,12 @@ pub fn new(
             executed_commands: Rc::new(RefCell::new(HashMap::new())),
             executed_run_configs: Rc::new(RefCell::new(HashMap::new())),
             references,
+            run_current_file,
+            plugin_permissions_volt,
+            keymap_change,
+            file_commit,
             source_control,
+            plugin,
             common,
         };
 
@@ -322,6 +419,7 @@ pub fn run(&self, _cx: Scope, kind: PaletteKind) {
     /// focusing the palette input.
     fn run_inner(&self, cx: Scope, kind: PaletteKind) {
         self.has_preview.set(false);
+        self.loading.set(false);
 
         let run_id = self.run_id_counter.fetch_add(1, Ordering::Relaxed) + 1;
         self.run_id.set(run_id);
@@ -354,6 +452,10 @@ fn run_inner(&self, cx: Scope, kind: PaletteKind) {
             PaletteKind::RunAndDebug => {
                 self.get_run_configs(cx);
             }
+            PaletteKind::RunCurrentFile => {
+                // The input is the list of arguments, there is nothing to pick from
+                self.items.update(|items| items.clear());
+            }
             PaletteKind::ColorTheme => {
                 self.get_color_themes(cx);
                 self.preselect_matching(
@@ -375,46 +477,112 @@ fn run_inner(&self, cx: Scope, kind: PaletteKind) {
                     self.preselect_matching(language.as_str());
                 }
             }
+            PaletteKind::TabWidth => {
+                self.get_tab_widths(cx);
+                if let Some(editor) = self.main_split.active_editor.get_untracked() {
+                    let doc = editor.with_untracked(|editor| editor.view.doc);
+                    let tab_width = doc.with_untracked(|doc| doc.tab_width());
+                    self.preselect_matching(&tab_width.to_string());
+                }
+            }
+            PaletteKind::IndentWidth => {
+                self.get_indent_widths(cx);
+                if let Some(editor) = self.main_split.active_editor.get_untracked() {
+                    let doc = editor.with_untracked(|editor| editor.view.doc);
+                    let tab_width = doc.with_untracked(|doc| doc.tab_width());
+                    self.preselect_matching(&tab_width.to_string());
+                }
+            }
+            PaletteKind::BurnDown => {
+                self.get_diagnostic_kinds(cx);
+            }
             PaletteKind::SCMReferences => {
                 self.get_scm_references(cx);
             }
+            PaletteKind::Register => {
+                self.get_registers(cx);
+            }
+            PaletteKind::SearchHistory => {
+                self.get_search_history(cx);
+            }
+            PaletteKind::Profile => {
+                self.get_profiles(cx);
+                let profile = self.common.config.get_untracked().profile.clone();
+                self.preselect_matching(
+                    profile.as_deref().unwrap_or(DEFAULT_PROFILE),
+                );
+            }
+            PaletteKind::PluginPermissions => {
+                self.get_plugin_permissions(cx);
+            }
+            PaletteKind::RecentlyClosed => {
+                self.get_recently_closed(cx);
+            }
+            PaletteKind::DryRunKeys => {
+                // The input is the keys, there is nothing to pick from
+                self.items.update(|items| items.clear());
+            }
+            PaletteKind::KeymapChanges => {
+                self.get_keymap_changes(cx);
+            }
+            PaletteKind::FileHistory => {
+                self.get_file_history(cx);
+            }
         }
     }
 
+    /// Start streaming items for the current run, clearing the old items.
+    fn stream(&self) -> PaletteItemStream {
+        self.items.update(|items| items.clear());
+        self.loading.set(true);
+        PaletteItemStream::new(
+            self.run_id.get_untracked(),
+            self.run_id_counter.clone(),
+            self.batch_tx.clone(),
+        )
+    }
+
     /// Initialize the palette with the files in the current workspace.
     fn get_files(&self, _cx: Scope) {
         let workspace = self.workspace.clone();
-        let set_items = self.items.write_only();
-        let send =
-            create_ext_action(self.common.scope, move |items: Vec<PathBuf>| {
-                let items = items
-                    .into_iter()
-                    .map(|path| {
-                        let full_path = path.clone();
-                        // Strip the workspace prefix off the path, to avoid clutter
-                        let path =
-                            if let Some(workspace_path) = workspace.path.as_ref() {
-                                path.strip_prefix(workspace_path)
-                                    .unwrap_or(&full_path)
-                                    .to_path_buf()
-                            } else {
-                                path
-                            };
-                        let filter_text = path.to_str().unwrap_or("").to_string();
-                        PaletteItem {
-                            content: PaletteItemContent::File { path, full_path },
-                            filter_text,
-                            score: 0,
-                            indices: Vec::new(),
-                        }
-                    })
-                    .collect::<im::Vector<_>>();
-                set_items.set(items);
-            });
-        self.common.proxy.get_files(move |result| {
+        let stream = self.stream();
+        let file_frecency = self.common.file_frecency.get_untracked();
+        let matcher = FuzzyMatcher::from_config(&self.common.config.get_untracked());
+        let now = frecency::now();
+        // A proxy of an older version lists the files that aren't ignored
+        let include_ignored = self
+            .common
+            .config
+            .with_untracked(|config| config.core.show_ignored_files)
+            && self.common.proxy.supports(Capability::IgnoredFiles);
+        self.common.proxy.get_files(include_ignored, move |result| {
             if let Ok(ProxyResponse::GetFilesResponse { items }) = result {
-                send(items);
+                stream.send_batched(items.into_iter().map(|path| {
+                    let full_path = path.clone();
+                    // Strip the workspace prefix off the path, to avoid clutter
+                    let path = if let Some(workspace_path) = workspace.path.as_ref()
+                    {
+                        path.strip_prefix(workspace_path)
+                            .unwrap_or(&full_path)
+                            .to_path_buf()
+                    } else {
+                        path
+                    };
+                    let filter_text = path.to_str().unwrap_or("").to_string();
+                    let frecency = matcher
+                        .frecency_bonus(file_frecency.frecency(&full_path, now));
+                    PaletteItem {
+                        content: PaletteItemContent::File { path, full_path },
+                        filter_text,
+                        score: 0,
+                        frecency,
+                        indices: Vec::new(),
+                        last_used: None,
+                        decoration: Default::default(),
+                    }
+                }));
             }
+            stream.finish();
         });
     }
 
@@ -451,7 +619,10 @@ fn get_lines(&self, _cx: Scope) {
                     },
                     filter_text: text,
                     score: 0,
+                    frecency: 0,
                     indices: vec![],
+                    last_used: None,
+                    decoration: Default::default(),
                 }
             })
             .collect();
@@ -461,7 +632,7 @@ fn get_lines(&self, _cx: Scope) {
     fn get_commands(&self, _cx: Scope) {
         const EXCLUDED_ITEMS: &[&str] = &["palette.command"];
 
-        let items = self.keypress.with_untracked(|keypress| {
+        let mut items = self.keypress.with_untracked(|keypress| {
             // Get all the commands we've executed, and sort them by how recently they were
             // executed. Ignore commands without descriptions.
             let mut items: im::Vector<PaletteItem> = self
@@ -470,13 +641,16 @@ fn get_commands(&self, _cx: Scope) {
                 .iter()
                 .sorted_by_key(|(_, i)| *i)
                 .rev()
-                .filter_map(|(key, _)| {
+                .filter_map(|(key, last_used)| {
                     keypress.commands.get(key).and_then(|c| {
                         c.kind.desc().as_ref().map(|m| PaletteItem {
                             content: PaletteItemContent::Command { cmd: c.clone() },
                             filter_text: m.to_string(),
                             score: 0,
+                            frecency: 0,
                             indices: vec![],
+                            last_used: Some(*last_used),
+                            decoration: Default::default(),
                         })
                     })
                 })
@@ -496,12 +670,34 @@ fn get_commands(&self, _cx: Scope) {
                     content: PaletteItemContent::Command { cmd: c.clone() },
                     filter_text: m.to_string(),
                     score: 0,
+                    frecency: 0,
                     indices: vec![],
+                    last_used: None,
+                    decoration: Default::default(),
                 })
             }));
 
             items
         });
+        // The commands the plugins registered, by their own titles
+        self.common.plugin_commands.with_untracked(|commands| {
+            items.extend(commands.values().map(|(_, _, command)| PaletteItem {
+                content: PaletteItemContent::Command {
+                    cmd: LapceCommand {
+                        kind: CommandKind::Workbench(
+                            LapceWorkbenchCommand::RunPluginCommand,
+                        ),
+                        data: Some(serde_json::json!(command.command)),
+                    },
+                },
+                filter_text: command.title.clone(),
+                score: 0,
+                frecency: 0,
+                indices: vec![],
+                last_used: None,
+                decoration: Default::default(),
+            }));
+        });
 
         self.items.set(items);
     }
@@ -529,7 +725,10 @@ fn get_workspaces(&self, cx: Scope) {
                     content: PaletteItemContent::Workspace { workspace: w },
                     filter_text,
                     score: 0,
+                    frecency: 0,
                     indices: vec![],
+                    last_used: None,
+                    decoration: Default::default(),
                 })
             })
             .collect();
@@ -543,28 +742,49 @@ fn get_references(&self, _cx: Scope) {
             .references
             .get_untracked()
             .into_iter()
-            .map(|l| {
-                let full_path = l.path.clone();
-                let mut path = l.path.clone();
-                if let Some(workspace_path) = self.workspace.path.as_ref() {
-                    path = path
-                        .strip_prefix(workspace_path)
-                        .unwrap_or(&full_path)
-                        .to_path_buf();
-                }
-                let filter_text = path.to_str().unwrap_or("").to_string();
-                PaletteItem {
-                    content: PaletteItemContent::Reference { path, location: l },
-                    filter_text,
-                    score: 0,
-                    indices: vec![],
-                }
-            })
+            .map(|l| self.location_item(l))
             .collect();
 
         self.items.set(items);
     }
 
+    /// Initialize the list of the editors closed recently, the last closed
+    /// first.
+    fn get_recently_closed(&self, _cx: Scope) {
+        let items = self
+            .main_split
+            .closed_editors
+            .get_untracked()
+            .into_iter()
+            .rev()
+            .map(|l| self.location_item(l))
+            .collect();
+
+        self.items.set(items);
+    }
+
+    /// An item to jump to the location, shown by its path in the workspace
+    fn location_item(&self, location: EditorLocation) -> PaletteItem {
+        let full_path = location.path.clone();
+        let mut path = location.path.clone();
+        if let Some(workspace_path) = self.workspace.path.as_ref() {
+            path = path
+                .strip_prefix(workspace_path)
+                .unwrap_or(&full_path)
+                .to_path_buf();
+        }
+        let filter_text = path.to_str().unwrap_or("").to_string();
+        PaletteItem {
+            content: PaletteItemContent::Reference { path, location },
+            filter_text,
+            score: 0,
+            frecency: 0,
+            indices: vec![],
+            last_used: None,
+            decoration: Default::default(),
+        }
+    }
+
     fn get_document_symbols(&self, _cx: Scope) {
         let editor = self.main_split.active_editor.get_untracked();
         let doc = match editor {
@@ -583,10 +803,10 @@ fn get_document_symbols(&self, _cx: Scope) {
             }
         };
 
-        let set_items = self.items.write_only();
-        let send = create_ext_action(self.common.scope, move |result| {
+        let stream = self.stream();
+        self.common.proxy.get_document_symbols(path, move |result| {
             if let Ok(ProxyResponse::GetDocumentSymbols { resp }) = result {
-                let items: im::Vector<PaletteItem> = match resp {
+                let items: Vec<PaletteItem> = match resp {
                     DocumentSymbolResponse::Flat(symbols) => symbols
                         .iter()
                         .map(|s| {
@@ -603,7 +823,10 @@ fn get_document_symbols(&self, _cx: Scope) {
                                 },
                                 filter_text,
                                 score: 0,
+                                frecency: 0,
                                 indices: Vec::new(),
+                                last_used: None,
+                                decoration: Default::default(),
                             }
                         })
                         .collect(),
@@ -618,66 +841,57 @@ fn get_document_symbols(&self, _cx: Scope) {
                             },
                             filter_text: s.name.clone(),
                             score: 0,
+                            frecency: 0,
                             indices: Vec::new(),
+                            last_used: None,
+                            decoration: Default::default(),
                         })
                         .collect(),
                 };
-                set_items.set(items);
-            } else {
-                set_items.update(|items| items.clear());
+                stream.send(items);
             }
-        });
-
-        self.common.proxy.get_document_symbols(path, move |result| {
-            send(result);
+            stream.finish();
         });
     }
 
     fn get_workspace_symbols(&self, _cx: Scope) {
         let input = self.input.get_untracked().input;
 
-        let set_items = self.items.write_only();
-        let send = create_ext_action(self.common.scope, move |result| {
-            if let Ok(ProxyResponse::GetWorkspaceSymbols { symbols }) = result {
-                let items: im::Vector<PaletteItem> = symbols
-                    .iter()
-                    .map(|s| {
-                        // TODO: Should we be using filter text?
-                        let mut filter_text = s.name.clone();
-                        if let Some(container_name) = s.container_name.as_ref() {
-                            filter_text += container_name;
-                        }
-                        PaletteItem {
-                            content: PaletteItemContent::WorkspaceSymbol {
-                                kind: s.kind,
-                                name: s.name.clone(),
-                                location: EditorLocation {
-                                    path: path_from_url(&s.location.uri),
-                                    position: Some(EditorPosition::Position(
-                                        s.location.range.start,
-                                    )),
-                                    scroll_offset: None,
-                                    ignore_unconfirmed: false,
-                                    same_editor_tab: false,
-                                },
-                                container_name: s.container_name.clone(),
-                            },
-                            filter_text,
-                            score: 0,
-                            indices: Vec::new(),
+        let stream = self.stream();
+        // The symbols indexed from the syntax trees are shown first, as the
+        // language servers can take a while to have theirs, and the ones of
+        // the servers take their place once they come
+        let from_servers = Arc::new(AtomicBool::new(false));
+        {
+            let stream = stream.clone();
+            let from_servers = from_servers.clone();
+            self.common.proxy.get_indexed_workspace_symbols(
+                input.clone(),
+                move |result| {
+                    if let Ok(ProxyResponse::GetWorkspaceSymbols { symbols }) =
+                        result
+                    {
+                        if !from_servers.load(Ordering::Acquire) {
+                            stream.send(workspace_symbol_items(&symbols));
                         }
-                    })
-                    .collect();
-                set_items.set(items);
-            } else {
-                set_items.update(|items| items.clear());
-            }
-        });
-
+                    }
+                },
+            );
+        }
         self.common
             .proxy
             .get_workspace_symbols(input, move |result| {
-                send(result);
+                match result {
+                    // Servers that haven't indexed the workspace yet have nothing
+                    Ok(ProxyResponse::GetWorkspaceSymbols { symbols })
+                        if !symbols.is_empty() =>
+                    {
+                        from_servers.store(true, Ordering::Release);
+                        stream.replace(workspace_symbol_items(&symbols));
+                    }
+                    _ => {}
+                }
+                stream.finish();
             });
     }
 
@@ -697,7 +911,10 @@ fn get_ssh_hosts(&self, cx: Scope) {
                 content: PaletteItemContent::SshHost { host: ssh.clone() },
                 filter_text: ssh.to_string(),
                 score: 0,
+                frecency: 0,
                 indices: vec![],
+                last_used: None,
+                decoration: Default::default(),
             })
             .collect();
         self.items.set(items);
@@ -718,9 +935,11 @@ fn get_run_configs(&self, _cx: Scope) {
         let mut items = Vec::new();
         if let Some(configs) = configs.as_ref() {
             for config in &configs.configs {
+                let last_used = executed_run_configs
+                    .get(&(RunDebugMode::Run, config.name.clone()))
+                    .copied();
                 items.push((
-                    executed_run_configs
-                        .get(&(RunDebugMode::Run, config.name.clone())),
+                    last_used,
                     PaletteItem {
                         content: PaletteItemContent::RunAndDebug {
                             mode: RunDebugMode::Run,
@@ -733,12 +952,17 @@ fn get_run_configs(&self, _cx: Scope) {
                             config.args.join(" ")
                         ),
                         score: 0,
+                        frecency: 0,
                         indices: vec![],
+                        last_used,
+                        decoration: Default::default(),
                     },
                 ));
+                let last_used = executed_run_configs
+                    .get(&(RunDebugMode::Debug, config.name.clone()))
+                    .copied();
                 items.push((
-                    executed_run_configs
-                        .get(&(RunDebugMode::Debug, config.name.clone())),
+                    last_used,
                     PaletteItem {
                         content: PaletteItemContent::RunAndDebug {
                             mode: RunDebugMode::Debug,
@@ -751,13 +975,16 @@ fn get_run_configs(&self, _cx: Scope) {
                             config.args.join(" ")
                         ),
                         score: 0,
+                        frecency: 0,
                         indices: vec![],
+                        last_used,
+                        decoration: Default::default(),
                     },
                 ));
             }
         }
 
-        items.sort_by_key(|(executed, _item)| std::cmp::Reverse(executed.copied()));
+        items.sort_by_key(|(executed, _item)| std::cmp::Reverse(*executed));
         self.items
             .set(items.into_iter().map(|(_, item)| item).collect());
     }
@@ -771,12 +998,207 @@ fn get_color_themes(&self, _cx: Scope) {
                 content: PaletteItemContent::ColorTheme { name: name.clone() },
                 filter_text: name.clone(),
                 score: 0,
+                frecency: 0,
+                indices: Vec::new(),
+                last_used: None,
+                decoration: Default::default(),
+            })
+            .collect();
+        self.items.set(items);
+    }
+
+    fn get_profiles(&self, _cx: Scope) {
+        let profiles = Profile::names().into_iter().map(Some);
+        let items = std::iter::once(None)
+            .chain(profiles)
+            .map(|name| PaletteItem {
+                filter_text: name
+                    .clone()
+                    .unwrap_or_else(|| DEFAULT_PROFILE.to_string()),
+                content: PaletteItemContent::Profile { name },
+                score: 0,
+                frecency: 0,
                 indices: Vec::new(),
+                last_used: None,
+                decoration: Default::default(),
             })
             .collect();
         self.items.set(items);
     }
 
+    /// The ways to grant the permissions the plugin under review asks for, or
+    /// the plugins to pick one to review from
+    fn get_plugin_permissions(&self, _cx: Scope) {
+        let items = if let Some(volt) = self.plugin_permissions_volt.get_untracked()
+        {
+            let granted = self.plugin.granted_permissions(&volt);
+            let items: im::Vector<PaletteItem> = volt
+                .requested_permissions()
+                .grants()
+                .into_iter()
+                .map(|permissions| PaletteItem {
+                    filter_text: format!(
+                        "Allow {}: {}",
+                        volt.display_name,
+                        permissions.description()
+                    ),
+                    content: PaletteItemContent::PluginPermissions {
+                        volt: volt.clone(),
+                        permissions: Some(permissions),
+                    },
+                    score: 0,
+                    frecency: 0,
+                    indices: Vec::new(),
+                    last_used: None,
+                    decoration: Default::default(),
+                })
+                .collect();
+            if let Some(index) = items.iter().position(|item| {
+                matches!(
+                    &item.content,
+                    PaletteItemContent::PluginPermissions {
+                        permissions: Some(permissions),
+                        ..
+                    } if *permissions == granted
+                )
+            }) {
+                self.index.set(index);
+            }
+            items
+        } else {
+            self.plugin
+                .installed
+                .get_untracked()
+                .values()
+                .map(|volt| volt.meta.get_untracked())
+                .filter(|volt| volt.wasm.is_some())
+                .map(|volt| PaletteItem {
+                    filter_text: format!(
+                        "{}: {}",
+                        volt.display_name,
+                        self.plugin.granted_permissions(&volt).description()
+                    ),
+                    content: PaletteItemContent::PluginPermissions {
+                        volt,
+                        permissions: None,
+                    },
+                    score: 0,
+                    frecency: 0,
+                    indices: Vec::new(),
+                    last_used: None,
+                    decoration: Default::default(),
+                })
+                .collect()
+        };
+        self.items.set(items);
+    }
+
+    /// The ways to resolve the change of the default keymaps under review,
+    /// or the changes the user hasn't reviewed to pick one from
+    fn get_keymap_changes(&self, cx: Scope) {
+        let keypress = self.keypress.get_untracked();
+        let title = |change: &KeymapChange| {
+            keypress
+                .commands
+                .get(&change.command)
+                .and_then(|cmd| cmd.kind.desc())
+                .unwrap_or(change.command.as_str())
+                .to_string()
+        };
+        let items = if let Some(change) = self.keymap_change.get_untracked() {
+            [
+                (KeymapResolution::Adopt, "Use the New Keys"),
+                (KeymapResolution::KeepMine, "Keep My Keys"),
+            ]
+            .into_iter()
+            .map(|(resolution, action)| PaletteItem {
+                filter_text: format!(
+                    "{action}: {} {}",
+                    title(&change),
+                    change.description()
+                ),
+                content: PaletteItemContent::KeymapChange {
+                    change: change.clone(),
+                    resolution: Some(resolution),
+                },
+                score: 0,
+                frecency: 0,
+                indices: Vec::new(),
+                last_used: None,
+                decoration: Default::default(),
+            })
+            .collect()
+        } else {
+            let db: Arc<LapceDb> = use_context(cx).unwrap();
+            let modal = self.common.config.get_untracked().core.modal;
+            KeyPressData::unreviewed_keymap_changes(&db, modal)
+                .into_iter()
+                .map(|change| PaletteItem {
+                    filter_text: format!(
+                        "{}: {}",
+                        title(&change),
+                        change.description()
+                    ),
+                    content: PaletteItemContent::KeymapChange {
+                        change,
+                        resolution: None,
+                    },
+                    score: 0,
+                    frecency: 0,
+                    indices: Vec::new(),
+                    last_used: None,
+                    decoration: Default::default(),
+                })
+                .collect()
+        };
+        self.items.set(items);
+    }
+
+    /// The ways to open the file as of the picked commit, or the commits
+    /// that changed the file of the active editor to pick one from
+    fn get_file_history(&self, _cx: Scope) {
+        if let Some((path, commit)) = self.file_commit.get_untracked() {
+            let items = [
+                (RevisionView::Diff, "Compare with Working Tree"),
+                (RevisionView::Document, "Open Revision"),
+            ]
+            .into_iter()
+            .map(|(view, action)| PaletteItem {
+                filter_text: format!(
+                    "{action}: {} {}",
+                    commit.short_id(),
+                    commit.summary
+                ),
+                content: PaletteItemContent::FileCommit {
+                    path: path.clone(),
+                    commit: commit.clone(),
+                    view: Some(view),
+                },
+                score: 0,
+                frecency: 0,
+                indices: Vec::new(),
+                last_used: None,
+                decoration: Default::default(),
+            })
+            .collect();
+            self.items.set(items);
+            return;
+        }
+
+        let editor = self.main_split.active_editor.get_untracked();
+        let path = editor.and_then(|editor| {
+            let doc = editor.with_untracked(|editor| editor.view.doc);
+            doc.with_untracked(|doc| doc.content.path().cloned())
+        });
+        let Some(path) = path else {
+            self.items.update(|items| items.clear());
+            return;
+        };
+
+        let stream = self.stream();
+        stream_file_history(self.common.proxy.clone(), path, 0, stream);
+    }
+
     fn get_icon_themes(&self, _cx: Scope) {
         let config = self.common.config.get_untracked();
         let items = config
@@ -786,7 +1208,10 @@ fn get_icon_themes(&self, _cx: Scope) {
                 content: PaletteItemContent::IconTheme { name: name.clone() },
                 filter_text: name.clone(),
                 score: 0,
+                frecency: 0,
                 indices: Vec::new(),
+                last_used: None,
+                decoration: Default::default(),
             })
             .collect();
         self.items.set(items);
@@ -795,14 +1220,74 @@ fn get_icon_themes(&self, _cx: Scope) {
     fn get_languages(&self, _cx: Scope) {
         let langs = LapceLanguage::languages();
         let items = langs
-            .iter()
+            .into_iter()
             .map(|lang| PaletteItem {
-                content: PaletteItemContent::Language {
-                    name: lang.to_owned().to_owned(),
-                },
-                filter_text: lang.to_owned().to_owned(),
+                content: PaletteItemContent::Language { name: lang.clone() },
+                filter_text: lang,
                 score: 0,
+                frecency: 0,
                 indices: Vec::new(),
+                last_used: None,
+                decoration: Default::default(),
+            })
+            .collect();
+        self.items.set(items);
+    }
+
+    fn get_tab_widths(&self, _cx: Scope) {
+        let tab_width = self.common.config.get_untracked().editor.tab_width;
+        let items = std::iter::once(None)
+            .chain((1..=8).map(Some))
+            .map(|width| PaletteItem {
+                filter_text: width
+                    .map(|width| width.to_string())
+                    .unwrap_or_else(|| format!("Use the Settings ({tab_width})")),
+                content: PaletteItemContent::TabWidth { width },
+                score: 0,
+                frecency: 0,
+                indices: Vec::new(),
+                last_used: None,
+                decoration: Default::default(),
+            })
+            .collect();
+        self.items.set(items);
+    }
+
+    fn get_indent_widths(&self, _cx: Scope) {
+        let items = (1..=IndentStyle::LONGEST_INDENT.len())
+            .map(|width| PaletteItem {
+                filter_text: width.to_string(),
+                content: PaletteItemContent::IndentWidth { width },
+                score: 0,
+                frecency: 0,
+                indices: Vec::new(),
+                last_used: None,
+                decoration: Default::default(),
+            })
+            .collect();
+        self.items.set(items);
+    }
+
+    fn get_diagnostic_kinds(&self, _cx: Scope) {
+        let diagnostics: Vec<_> = self
+            .main_split
+            .diagnostics
+            .get_untracked()
+            .values()
+            .flat_map(|d| d.diagnostics.get_untracked())
+            .collect();
+        let kinds =
+            burn_down::kind_counts(diagnostics.iter().map(|d| &d.diagnostic));
+        let items = kinds
+            .into_iter()
+            .map(|(kind, count)| PaletteItem {
+                filter_text: format!("{} ({count})", kind.label()),
+                content: PaletteItemContent::DiagnosticKind { kind },
+                score: 0,
+                frecency: 0,
+                indices: Vec::new(),
+                last_used: None,
+                decoration: Default::default(),
             })
             .collect();
         self.items.set(items);
@@ -816,25 +1301,87 @@ fn get_scm_references(&self, _cx: Scope) {
             items.push_back(PaletteItem {
                 content: PaletteItemContent::SCMReference {
                     name: refs.to_owned(),
+                    tag: false,
                 },
                 filter_text: refs.to_owned(),
                 score: 0,
+                frecency: 0,
                 indices: Vec::new(),
+                last_used: None,
+                decoration: Default::default(),
             });
         }
         for refs in tags.into_iter() {
             items.push_back(PaletteItem {
                 content: PaletteItemContent::SCMReference {
                     name: refs.to_owned(),
+                    tag: true,
                 },
                 filter_text: refs.to_owned(),
                 score: 0,
+                frecency: 0,
                 indices: Vec::new(),
+                last_used: None,
+                decoration: Default::default(),
             });
         }
         self.items.set(items);
     }
 
+    /// Initialize the palette with the registers that have content, along with
+    /// the system clipboard as the `"+` register.
+    fn get_registers(&self, _cx: Scope) {
+        let entries = self.common.register.with_untracked(|register| {
+            register_entries(register, &SystemClipboard {})
+        });
+        let items = entries
+            .into_iter()
+            .map(|(name, content)| {
+                let content = register_preview(&content);
+                PaletteItem {
+                    content: PaletteItemContent::Register { name },
+                    filter_text: format!("\"{name}  {content}"),
+                    score: 0,
+                    frecency: 0,
+                    indices: Vec::new(),
+                    last_used: None,
+                    decoration: Default::default(),
+                }
+            })
+            .collect();
+        self.items.set(items);
+    }
+
+    /// Initialize the palette with the search and replace histories, the most
+    /// recent entries first.
+    fn get_search_history(&self, _cx: Scope) {
+        let history = &self.common.search_history;
+        let entries = [(false, history.search), (true, history.replace)];
+        let items = entries
+            .into_iter()
+            .flat_map(|(replace, entries)| {
+                let prefix = if replace { "Replace" } else { "Search" };
+                entries
+                    .get_untracked()
+                    .into_iter()
+                    .rev()
+                    .map(move |pattern| PaletteItem {
+                        filter_text: format!("{prefix}: {pattern}"),
+                        content: PaletteItemContent::SearchHistory {
+                            pattern,
+                            replace,
+                        },
+                        score: 0,
+                        frecency: 0,
+                        indices: Vec::new(),
+                        last_used: None,
+                        decoration: Default::default(),
+                    })
+            })
+            .collect();
+        self.items.set(items);
+    }
+
     fn preselect_matching(&self, matching: &str) {
         let Some((idx, _)) = self.items.get_untracked().iter().find_position(|item| item.filter_text == matching) else { return };
 
@@ -844,6 +1391,9 @@ fn preselect_matching(&self, matching: &str) {
     fn select(&self) {
         let index = self.index.get_untracked();
         let items = self.filtered_items.get_untracked();
+        if items.get(index).map_or(false, |item| item.is_header()) {
+            return;
+        }
         self.close();
         if let Some(item) = items.get(index) {
             match &item.content {
@@ -990,7 +1540,29 @@ fn select(&self) {
                         doc.trigger_syntax_change(None);
                     });
                 }
-                PaletteItemContent::SCMReference { name } => {
+                PaletteItemContent::TabWidth { width } => {
+                    if let Some(editor) =
+                        self.main_split.active_editor.get_untracked()
+                    {
+                        let doc = editor.with_untracked(|editor| editor.view.doc);
+                        doc.update(|doc| doc.set_tab_width(*width));
+                    }
+                }
+                PaletteItemContent::IndentWidth { width } => {
+                    if let Some(editor) =
+                        self.main_split.active_editor.get_untracked()
+                    {
+                        editor
+                            .get_untracked()
+                            .convert_indentation(IndentStyle::Spaces(*width as u8));
+                    }
+                }
+                PaletteItemContent::DiagnosticKind { kind } => {
+                    self.common
+                        .internal_command
+                        .send(InternalCommand::StartBurnDown { kind: kind.clone() });
+                }
+                PaletteItemContent::SCMReference { name, .. } => {
                     self.common
                         .lapce_command
                         .send(crate::command::LapceCommand {
@@ -1000,6 +1572,101 @@ fn select(&self) {
                         data: Some(serde_json::json!(name.to_owned())),
                     });
                 }
+                PaletteItemContent::Register { name } => {
+                    if let Some(editor) =
+                        self.main_split.active_editor.get_untracked()
+                    {
+                        editor.get_untracked().paste_register(*name);
+                    }
+                }
+                PaletteItemContent::SearchHistory { pattern, replace } => {
+                    let find = &self.common.find;
+                    if *replace {
+                        self.main_split.set_replace_pattern(pattern.clone());
+                        find.replace_active.set(true);
+                    } else {
+                        self.main_split.set_find_pattern(Some(pattern.clone()));
+                    }
+                    find.replace_focus.set(*replace);
+                    find.visual.set(true);
+                    if let Some(editor) =
+                        self.main_split.active_editor.get_untracked()
+                    {
+                        editor.get_untracked().find_focus.set(true);
+                    }
+                }
+                PaletteItemContent::Profile { name } => {
+                    self.common
+                        .internal_command
+                        .send(InternalCommand::SetProfile { name: name.clone() });
+                }
+                PaletteItemContent::PluginPermissions {
+                    volt,
+                    permissions: Some(permissions),
+                } => {
+                    self.plugin
+                        .grant_permissions(volt.clone(), permissions.clone());
+                }
+                PaletteItemContent::PluginPermissions {
+                    volt,
+                    permissions: None,
+                } => {
+                    self.common.internal_command.send(
+                        InternalCommand::ReviewPluginPermissions {
+                            volt: volt.clone(),
+                        },
+                    );
+                }
+                PaletteItemContent::KeymapChange {
+                    change,
+                    resolution: Some(resolution),
+                } => {
+                    let db: Arc<LapceDb> = use_context(self.common.scope).unwrap();
+                    if let Err(err) =
+                        KeyPressData::resolve_keymap_change(&db, change, *resolution)
+                    {
+                        error!("Failed to resolve the keymap change: {err}");
+                    }
+                }
+                PaletteItemContent::KeymapChange {
+                    change,
+                    resolution: None,
+                } => {
+                    self.common.internal_command.send(
+                        InternalCommand::ReviewKeymapChange {
+                            change: change.clone(),
+                        },
+                    );
+                }
+                PaletteItemContent::FileCommit {
+                    path,
+                    commit,
+                    view: Some(view),
+                } => {
+                    let path = path.clone();
+                    let version = commit.id.clone();
+                    match view {
+                        RevisionView::Document => {
+                            self.main_split.open_file_revision(path, version)
+                        }
+                        RevisionView::Diff => {
+                            self.main_split.open_revision_changes(path, version)
+                        }
+                    }
+                }
+                PaletteItemContent::FileCommit {
+                    path,
+                    commit,
+                    view: None,
+                } => {
+                    self.common.internal_command.send(
+                        InternalCommand::OpenFileCommit {
+                            path: path.clone(),
+                            commit: commit.clone(),
+                        },
+                    );
+                }
+                PaletteItemContent::Header { .. } => {}
             }
         } else if self.kind.get_untracked() == PaletteKind::SshHost {
             let input = self.input.with_untracked(|input| input.input.clone());
@@ -1013,6 +1680,22 @@ fn select(&self) {
                         last_open: 0,
                     },
                 });
+        } else if self.kind.get_untracked() == PaletteKind::RunCurrentFile {
+            let input = self.input.with_untracked(|input| input.input.clone());
+            if let Some(mut config) = self.run_current_file.get_untracked() {
+                config.args.extend(split_args(&input));
+                self.common
+                    .internal_command
+                    .send(InternalCommand::RunAndDebug {
+                        mode: RunDebugMode::Run,
+                        config,
+                    });
+            }
+        } else if self.kind.get_untracked() == PaletteKind::DryRunKeys {
+            let keys = self.input.with_untracked(|input| input.input.clone());
+            self.common
+                .internal_command
+                .send(InternalCommand::DryRunKeys { keys });
         }
     }
 
@@ -1063,6 +1746,9 @@ fn preview(&self, _cx: Scope) {
                 PaletteItemContent::RunAndDebug { .. } => {}
                 PaletteItemContent::SshHost { .. } => {}
                 PaletteItemContent::Language { .. } => {}
+                PaletteItemContent::TabWidth { .. } => {}
+                PaletteItemContent::IndentWidth { .. } => {}
+                PaletteItemContent::DiagnosticKind { .. } => {}
                 PaletteItemContent::Reference { location, .. } => {
                     self.has_preview.set(true);
                     let (doc, new_doc) =
@@ -1132,6 +1818,13 @@ fn preview(&self, _cx: Scope) {
                         save: false,
                     }),
                 PaletteItemContent::SCMReference { .. } => {}
+                PaletteItemContent::Register { .. } => {}
+                PaletteItemContent::SearchHistory { .. } => {}
+                PaletteItemContent::Profile { .. } => {}
+                PaletteItemContent::PluginPermissions { .. } => {}
+                PaletteItemContent::KeymapChange { .. } => {}
+                PaletteItemContent::FileCommit { .. } => {}
+                PaletteItemContent::Header { .. } => {}
             }
         }
     }
@@ -1152,6 +1845,9 @@ fn cancel(&self) {
 
     /// Close the palette, reverting focus back to the workbench.  
     fn close(&self) {
+        // Cancel the providers that are still streaming items
+        self.run_id_counter.fetch_add(1, Ordering::Relaxed);
+        self.loading.set(false);
         self.status.set(PaletteStatus::Inactive);
         if self.common.focus.get_untracked() == Focus::Palette {
             self.common.focus.set(Focus::Workbench);
@@ -1169,18 +1865,26 @@ fn close(&self) {
 
     /// Move to the next entry in the palette list, wrapping around if needed.
     fn next(&self) {
-        let index = self.index.get_untracked();
-        let len = self.filtered_items.with_untracked(|i| i.len());
-        let new_index = Movement::Down.update_index(index, len, 1, true);
-        self.index.set(new_index);
+        self.move_index(Movement::Down);
     }
 
     /// Move to the previous entry in the palette list, wrapping around if needed.
     fn previous(&self) {
-        let index = self.index.get_untracked();
-        let len = self.filtered_items.with_untracked(|i| i.len());
-        let new_index = Movement::Up.update_index(index, len, 1, true);
-        self.index.set(new_index);
+        self.move_index(Movement::Up);
+    }
+
+    /// Move the index by one entry, wrapping around, and skipping the headers
+    /// of groups.
+    fn move_index(&self, movement: Movement) {
+        let items = self.filtered_items.get_untracked();
+        let mut index = self.index.get_untracked();
+        for _ in 0..items.len() {
+            index = movement.update_index(index, items.len(), 1, true);
+            if !items.get(index).map_or(false, |item| item.is_header()) {
+                break;
+            }
+        }
+        self.index.set(index);
     }
 
     fn next_page(&self) {
@@ -1216,15 +1920,21 @@ fn run_focus_command(&self, cmd: &FocusCommand) -> CommandExecuted {
         CommandExecuted::Yes
     }
 
-    fn filter_items(
+    pub(crate) fn filter_items(
         run_id: Arc<AtomicU64>,
         current_run_id: u64,
         input: &str,
+        stages: &[PaletteStage],
         items: im::Vector<PaletteItem>,
-        matcher: &SkimMatcherV2,
+        matcher: &FuzzyMatcher,
     ) -> Option<im::Vector<PaletteItem>> {
         if input.is_empty() {
-            return Some(items);
+            if stages.is_empty() && items.iter().all(|i| i.frecency == 0) {
+                return Some(items);
+            }
+            let mut items: Vec<PaletteItem> = items.into_iter().collect();
+            items.sort_by(|a, b| b.frecency.cmp(&a.frecency));
+            return Some(pipeline::process(stages, items).into());
         }
 
         // NOTE: We collect into a Vec to sort as we are hitting a worst-case behavior in
@@ -1241,7 +1951,7 @@ fn filter_items(
                 matcher.fuzzy_indices(&i.filter_text, input)
             {
                 let mut item = i.clone();
-                item.score = score;
+                item.score = score + i.frecency + matcher.recency_bonus(i.last_used);
                 item.indices = indices;
                 filtered_items.push(item);
             }
@@ -1258,39 +1968,44 @@ fn filter_items(
         if run_id.load(std::sync::atomic::Ordering::Acquire) != current_run_id {
             return None;
         }
-        Some(filtered_items.into())
+        Some(pipeline::process(stages, filtered_items).into())
     }
 
     fn update_process(
         run_id: Arc<AtomicU64>,
-        receiver: Receiver<(u64, String, im::Vector<PaletteItem>)>,
+        receiver: Receiver<FilterRequest>,
         resp_tx: Sender<(u64, String, im::Vector<PaletteItem>)>,
     ) {
         fn receive_batch(
-            receiver: &Receiver<(u64, String, im::Vector<PaletteItem>)>,
-        ) -> Result<(u64, String, im::Vector<PaletteItem>)> {
-            let (mut run_id, mut input, mut items) = receiver.recv()?;
+            receiver: &Receiver<FilterRequest>,
+        ) -> Result<FilterRequest> {
+            let (mut run_id, mut input, mut stages, mut items, mut matcher) =
+                receiver.recv()?;
             loop {
                 match receiver.try_recv() {
                     Ok(update) => {
                         run_id = update.0;
                         input = update.1;
-                        items = update.2;
+                        stages = update.2;
+                        items = update.3;
+                        matcher = update.4;
                     }
                     Err(TryRecvError::Empty) => break,
                     Err(TryRecvError::Disconnected) => break,
                 }
             }
-            Ok((run_id, input, items))
+            Ok((run_id, input, stages, items, matcher))
         }
 
-        let matcher = SkimMatcherV2::default().ignore_case();
         loop {
-            if let Ok((current_run_id, input, items)) = receive_batch(&receiver) {
+            if let Ok((current_run_id, input, stages, items, matcher)) =
+                receive_batch(&receiver)
+            {
                 if let Some(filtered_items) = Self::filter_items(
                     run_id.clone(),
                     current_run_id,
                     &input,
+                    stages,
                     items,
                     &matcher,
                 ) {
@@ -1343,3 +2058,101 @@ fn receive_char(&self, c: &str) {
         self.input_editor.receive_char(c);
     }
 }
+
+fn workspace_symbol_items(symbols: &[SymbolInformation]) -> Vec<PaletteItem> {
+    symbols
+        .iter()
+        .map(|s| {
+            // TODO: Should we be using filter text?
+            let mut filter_text = s.name.clone();
+            if let Some(container_name) = s.container_name.as_ref() {
+                filter_text += container_name;
+            }
+            PaletteItem {
+                content: PaletteItemContent::WorkspaceSymbol {
+                    kind: s.kind,
+                    name: s.name.clone(),
+                    location: EditorLocation {
+                        path: path_from_url(&s.location.uri),
+                        position: Some(EditorPosition::Position(
+                            s.location.range.start,
+                        )),
+                        scroll_offset: None,
+                        ignore_unconfirmed: false,
+                        same_editor_tab: false,
+                    },
+                    container_name: s.container_name.clone(),
+                },
+                filter_text,
+                score: 0,
+                frecency: 0,
+                indices: Vec::new(),
+                last_used: None,
+                decoration: Default::default(),
+            }
+        })
+        .collect()
+}
+
+/// Send the commits that changed the file a page at a time, looking from the
+/// commit at `start` in the history on, until the history ends or the palette
+/// moves on
+fn stream_file_history(
+    proxy: ProxyRpcHandler,
+    path: PathBuf,
+    start: usize,
+    stream: PaletteItemStream,
+) {
+    proxy.clone().git_file_history(
+        path.clone(),
+        start,
+        FILE_HISTORY_PAGE,
+        move |result| {
+            let next = match result {
+                Ok(ProxyResponse::GitFileHistoryResponse { commits, next }) => {
+                    let items = commits
+                        .into_iter()
+                        .map(|commit| PaletteItem {
+                            filter_text: format!(
+                                "{} {} ({}, {})",
+                                commit.short_id(),
+                                commit.summary,
+                                commit.author,
+                                commit_date(commit.time)
+                            ),
+                            content: PaletteItemContent::FileCommit {
+                                path: path.clone(),
+                                commit,
+                                view: None,
+                            },
+                            score: 0,
+                            frecency: 0,
+                            indices: Vec::new(),
+                            last_used: None,
+                            decoration: Default::default(),
+                        })
+                        .collect();
+                    if stream.send(items) {
+                        next
+                    } else {
+                        None
+                    }
+                }
+                _ => None,
+            };
+            match next {
+                Some(next) => stream_file_history(proxy, path, next, stream),
+                None => stream.finish(),
+            }
+        },
+    );
+}
+
+/// The local date of a commit time, in seconds since the Unix epoch
+fn commit_date(time: i64) -> String {
+    chrono::Local
+        .timestamp_opt(time, 0)
+        .single()
+        .map(|date| date.format("%Y-%m-%d").to_string())
+        .unwrap_or_default()
+}
@@ -1,12 +1,18 @@
-use std::path::PathBuf;
+use std::{path::PathBuf, time::Instant};
 
-use lapce_rpc::dap_types::RunDebugConfig;
+use lapce_rpc::{
+    dap_types::RunDebugConfig,
+    plugin::{PluginPermissions, VoltMetadata},
+    source_control::FileCommit,
+};
 use lsp_types::{Range, SymbolKind};
 
 use crate::{
+    burn_down::DiagnosticKind,
     command::LapceCommand,
     debug::RunDebugMode,
     editor::location::EditorLocation,
+    keypress::review::{KeymapChange, KeymapResolution},
     workspace::{LapceWorkspace, SshHost},
 };
 
@@ -14,8 +20,42 @@
 pub struct PaletteItem {
     pub content: PaletteItemContent,
     pub filter_text: String,
+    /// How well the item matched the input, with its bonuses
     pub score: i64,
+    /// The bonus of the item regardless of the input, for how often and
+    /// recently it was opened
+    pub frecency: i64,
     pub indices: Vec<usize>,
+    /// When the item was last used, for boosting it in the matching
+    pub last_used: Option<Instant>,
+    /// How the item is presented, as set by the pipeline of the palette kind
+    pub decoration: PaletteDecoration,
+}
+
+impl PaletteItem {
+    /// Whether the item is the header of a group, which can't be picked
+    pub fn is_header(&self) -> bool {
+        matches!(self.content, PaletteItemContent::Header { .. })
+    }
+}
+
+/// How a revision of a file is opened from its history
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RevisionView {
+    /// The file as of the revision, read-only
+    Document,
+    /// The changes of the working tree since the revision
+    Diff,
+}
+
+/// What the stages of a palette kind add to the presentation of an item, see
+/// [`PaletteStage`](super::pipeline::PaletteStage)
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PaletteDecoration {
+    /// The icon shown before the item, one of the `LapceIcons`
+    pub icon: Option<&'static str>,
+    /// The folder of the path of the item, shortened
+    pub folder: Option<String>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -66,7 +106,55 @@ pub enum PaletteItemContent {
     Language {
         name: String,
     },
+    /// A tab width for the current file, `None` for the one of the settings
+    TabWidth {
+        width: Option<usize>,
+    },
+    /// A number of spaces to convert the indentation of the current file to
+    IndentWidth {
+        width: usize,
+    },
+    /// A kind of diagnostics to burn down
+    DiagnosticKind {
+        kind: DiagnosticKind,
+    },
     SCMReference {
         name: String,
+        /// Whether the reference is a tag rather than a branch
+        tag: bool,
+    },
+    Register {
+        name: char,
+    },
+    SearchHistory {
+        pattern: String,
+        replace: bool,
+    },
+    /// A profile to switch to, `None` for the default one
+    Profile {
+        name: Option<String>,
+    },
+    /// Permissions to grant to a plugin, or the plugin whose permissions to
+    /// review if there are none
+    PluginPermissions {
+        volt: VoltMetadata,
+        permissions: Option<PluginPermissions>,
+    },
+    /// A way to resolve a change of the default keymaps, or the change to
+    /// review if there's none
+    KeymapChange {
+        change: KeymapChange,
+        resolution: Option<KeymapResolution>,
+    },
+    /// A commit in the history of the file, with the way to open the file
+    /// as of it, or the commit to pick a way for if there's none
+    FileCommit {
+        path: PathBuf,
+        commit: FileCommit,
+        view: Option<RevisionView>,
+    },
+    /// The header of a group of items, which can't be picked
+    Header {
+        name: String,
     },
 }
@@ -1,3 +1,5 @@
+use super::pipeline::PaletteStage;
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum PaletteKind {
     File,
@@ -9,10 +11,22 @@ pub enum PaletteKind {
     WorkspaceSymbol,
     SshHost,
     RunAndDebug,
+    RunCurrentFile,
     ColorTheme,
     IconTheme,
     Language,
+    TabWidth,
+    IndentWidth,
+    BurnDown,
     SCMReferences,
+    Register,
+    SearchHistory,
+    Profile,
+    PluginPermissions,
+    RecentlyClosed,
+    DryRunKeys,
+    KeymapChanges,
+    FileHistory,
 }
 
 impl PaletteKind {
@@ -29,10 +43,63 @@ pub fn symbol(&self) -> &'static str {
             | PaletteKind::Reference
             | PaletteKind::SshHost
             | PaletteKind::RunAndDebug
+            | PaletteKind::RunCurrentFile
             | PaletteKind::ColorTheme
             | PaletteKind::IconTheme
             | PaletteKind::Language
-            | PaletteKind::SCMReferences => "",
+            | PaletteKind::TabWidth
+            | PaletteKind::IndentWidth
+            | PaletteKind::BurnDown
+            | PaletteKind::SCMReferences
+            | PaletteKind::Register
+            | PaletteKind::SearchHistory
+            | PaletteKind::Profile
+            | PaletteKind::PluginPermissions
+            | PaletteKind::RecentlyClosed
+            | PaletteKind::DryRunKeys
+            | PaletteKind::KeymapChanges
+            | PaletteKind::FileHistory => "",
+        }
+    }
+
+    /// The stages the items of the palette are presented through, after
+    /// they are matched against the input.
+    pub fn pipeline(&self) -> &'static [PaletteStage] {
+        match self {
+            PaletteKind::Reference => {
+                &[PaletteStage::GroupHeaders, PaletteStage::ShortenPaths]
+            }
+            PaletteKind::RecentlyClosed => {
+                &[PaletteStage::Dedupe, PaletteStage::ShortenPaths]
+            }
+            PaletteKind::WorkspaceSymbol => &[PaletteStage::GroupHeaders],
+            PaletteKind::SCMReferences | PaletteKind::RunAndDebug => &[
+                PaletteStage::Dedupe,
+                PaletteStage::GroupHeaders,
+                PaletteStage::Icons,
+            ],
+            PaletteKind::Workspace
+            | PaletteKind::SshHost
+            | PaletteKind::SearchHistory => {
+                &[PaletteStage::Dedupe, PaletteStage::Icons]
+            }
+            PaletteKind::ColorTheme
+            | PaletteKind::IconTheme
+            | PaletteKind::Profile
+            | PaletteKind::PluginPermissions
+            | PaletteKind::KeymapChanges
+            | PaletteKind::FileHistory => &[PaletteStage::Icons],
+            PaletteKind::File
+            | PaletteKind::Line
+            | PaletteKind::Command
+            | PaletteKind::DocumentSymbol
+            | PaletteKind::RunCurrentFile
+            | PaletteKind::Language
+            | PaletteKind::TabWidth
+            | PaletteKind::IndentWidth
+            | PaletteKind::BurnDown
+            | PaletteKind::Register
+            | PaletteKind::DryRunKeys => &[],
         }
     }
 
@@ -65,10 +132,22 @@ pub fn get_input<'a>(&self, input: &'a str) -> &'a str {
             | PaletteKind::Reference
             | PaletteKind::SshHost
             | PaletteKind::RunAndDebug
+            | PaletteKind::RunCurrentFile
             | PaletteKind::ColorTheme
             | PaletteKind::IconTheme
             | PaletteKind::Language
-            | PaletteKind::SCMReferences => input,
+            | PaletteKind::TabWidth
+            | PaletteKind::IndentWidth
+            | PaletteKind::BurnDown
+            | PaletteKind::SCMReferences
+            | PaletteKind::Register
+            | PaletteKind::SearchHistory
+            | PaletteKind::Profile
+            | PaletteKind::PluginPermissions
+            | PaletteKind::RecentlyClosed
+            | PaletteKind::DryRunKeys
+            | PaletteKind::KeymapChanges
+            | PaletteKind::FileHistory => input,
             PaletteKind::Command
             | PaletteKind::Workspace
             | PaletteKind::DocumentSymbol
@@ -0,0 +1,222 @@
+use std::{
+    collections::HashSet,
+    path::{Path, MAIN_SEPARATOR},
+};
+
+use super::item::{PaletteItem, PaletteItemContent};
+use crate::{
+    config::icon::LapceIcons, debug::RunDebugMode, workspace::LapceWorkspaceType,
+};
+
+/// A step of the presentation of the items of a palette kind, applied in order
+/// after they are matched and sorted, so that every provider gets the same
+/// presentation from the list.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PaletteStage {
+    /// Drop the items whose text is the same as one ranked before them
+    Dedupe,
+    /// Put the items under a header of their group, the groups in the order
+    /// of their best item
+    GroupHeaders,
+    /// Elide the middle of the deep folders of paths
+    ShortenPaths,
+    /// Show an icon of what the item is before it
+    Icons,
+}
+
+/// The folders kept at each end of a path when it's shortened
+const SHORTENED_PATH_ENDS: usize = 2;
+
+/// Run the items through the stages of a palette kind
+pub fn process(
+    stages: &[PaletteStage],
+    mut items: Vec<PaletteItem>,
+) -> Vec<PaletteItem> {
+    for stage in stages {
+        items = match stage {
+            PaletteStage::Dedupe => dedupe(items),
+            PaletteStage::GroupHeaders => group_headers(items),
+            PaletteStage::ShortenPaths => {
+                for item in items.iter_mut() {
+                    if let Some(folder) = item_path(&item.content)
+                        .and_then(Path::parent)
+                        .and_then(shorten_folder)
+                    {
+                        item.decoration.folder = Some(folder);
+                    }
+                }
+                items
+            }
+            PaletteStage::Icons => {
+                for item in items.iter_mut() {
+                    item.decoration.icon = item_icon(&item.content);
+                }
+                items
+            }
+        };
+    }
+    items
+}
+
+fn dedupe(items: Vec<PaletteItem>) -> Vec<PaletteItem> {
+    let mut seen = HashSet::new();
+    items
+        .into_iter()
+        .filter(|item| seen.insert(item.filter_text.clone()))
+        .collect()
+}
+
+fn group_headers(items: Vec<PaletteItem>) -> Vec<PaletteItem> {
+    // The groups keep the order their first item was ranked at
+    let mut groups: Vec<(Option<String>, Vec<PaletteItem>)> = Vec::new();
+    for item in items {
+        let group = item_group(&item.content);
+        match groups.iter_mut().find(|(name, _)| *name == group) {
+            Some((_, items)) => items.push(item),
+            None => groups.push((group, vec![item])),
+        }
+    }
+    if groups.iter().all(|(name, _)| name.is_none()) {
+        return groups.into_iter().flat_map(|(_, items)| items).collect();
+    }
+
+    let mut grouped = Vec::new();
+    for (name, items) in groups {
+        if let Some(name) = name {
+            grouped.push(PaletteItem {
+                content: PaletteItemContent::Header { name: name.clone() },
+                filter_text: name,
+                score: 0,
+                frecency: 0,
+                indices: Vec::new(),
+                last_used: None,
+                decoration: Default::default(),
+            });
+        }
+        grouped.extend(items);
+    }
+    grouped
+}
+
+/// The folder with the ones in its middle replaced by an ellipsis, if it's
+/// deep enough for that
+fn shorten_folder(folder: &Path) -> Option<String> {
+    let components: Vec<_> = folder
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+    if components.len() <= SHORTENED_PATH_ENDS * 2 + 1 {
+        return None;
+    }
+    let mut shortened = components[..SHORTENED_PATH_ENDS].to_vec();
+    shortened.push("…".to_string());
+    shortened
+        .extend_from_slice(&components[components.len() - SHORTENED_PATH_ENDS..]);
+    Some(shortened.join(&MAIN_SEPARATOR.to_string()))
+}
+
+fn item_path(content: &PaletteItemContent) -> Option<&Path> {
+    match content {
+        PaletteItemContent::File { path, .. }
+        | PaletteItemContent::Reference { path, .. } => Some(path),
+        _ => None,
+    }
+}
+
+/// The group of the item under [`PaletteStage::GroupHeaders`]
+fn item_group(content: &PaletteItemContent) -> Option<String> {
+    match content {
+        PaletteItemContent::SCMReference { tag, .. } => {
+            Some(if *tag { "Tags" } else { "Branches" }.to_string())
+        }
+        PaletteItemContent::RunAndDebug { mode, .. } => Some(mode.to_string()),
+        PaletteItemContent::Reference { path, .. } => {
+            Some(path.to_string_lossy().to_string())
+        }
+        PaletteItemContent::WorkspaceSymbol { location, .. } => location
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string()),
+        _ => None,
+    }
+}
+
+/// The icon of the item under [`PaletteStage::Icons`], one of the
+/// [`LapceIcons`]
+fn item_icon(content: &PaletteItemContent) -> Option<&'static str> {
+    match content {
+        PaletteItemContent::SCMReference { .. }
+        | PaletteItemContent::FileCommit { .. } => Some(LapceIcons::SCM),
+        PaletteItemContent::Workspace { workspace } => match &workspace.kind {
+            LapceWorkspaceType::Local => Some(LapceIcons::DIRECTORY_CLOSED),
+            _ => Some(LapceIcons::REMOTE),
+        },
+        PaletteItemContent::SshHost { .. } => Some(LapceIcons::REMOTE),
+        PaletteItemContent::RunAndDebug { mode, .. } => Some(match mode {
+            RunDebugMode::Run => LapceIcons::START,
+            RunDebugMode::Debug => LapceIcons::DEBUG,
+        }),
+        PaletteItemContent::SearchHistory { .. } => Some(LapceIcons::SEARCH),
+        PaletteItemContent::ColorTheme { .. }
+        | PaletteItemContent::IconTheme { .. }
+        | PaletteItemContent::Profile { .. }
+        | PaletteItemContent::KeymapChange { .. } => Some(LapceIcons::SETTINGS),
+        PaletteItemContent::PluginPermissions { .. } => Some(LapceIcons::EXTENSIONS),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reference(name: &str, tag: bool) -> PaletteItem {
+        PaletteItem {
+            content: PaletteItemContent::SCMReference {
+                name: name.to_string(),
+                tag,
+            },
+            filter_text: name.to_string(),
+            score: 0,
+            frecency: 0,
+            indices: Vec::new(),
+            last_used: None,
+            decoration: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_dedupe_and_group() {
+        let items = vec![
+            reference("v1.0", true),
+            reference("main", false),
+            reference("main", false),
+            reference("v0.9", true),
+            reference("dev", false),
+        ];
+        let items =
+            process(&[PaletteStage::Dedupe, PaletteStage::GroupHeaders], items);
+        let texts: Vec<_> = items.iter().map(|i| i.filter_text.as_str()).collect();
+        assert_eq!(
+            texts,
+            vec!["Tags", "v1.0", "v0.9", "Branches", "main", "dev"]
+        );
+        assert!(matches!(
+            items[0].content,
+            PaletteItemContent::Header { .. }
+        ));
+    }
+
+    #[test]
+    fn test_shorten_folder() {
+        assert_eq!(shorten_folder(Path::new("src/a/b")), None);
+        assert_eq!(
+            shorten_folder(Path::new("a/b/c/d/e/f")).as_deref(),
+            Some(
+                ["a", "b", "…", "e", "f"]
+                    .join(&MAIN_SEPARATOR.to_string())
+                    .as_str()
+            )
+        );
+    }
+}
@@ -0,0 +1,110 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+use crossbeam_channel::Sender;
+
+use super::item::PaletteItem;
+
+/// The number of items sent at once when a provider has its items all at once,
+/// so that the first ones can be shown while the rest are still being added.
+pub const PALETTE_BATCH_SIZE: usize = 1000;
+
+/// Items streamed by a palette provider for the palette run `run_id`
+#[derive(Clone)]
+pub struct PaletteBatch {
+    pub run_id: u64,
+    pub items: Vec<PaletteItem>,
+    /// Whether the items take the place of the ones sent before, rather than
+    /// being added to them
+    pub replace: bool,
+    /// Whether the provider has no more items to send
+    pub done: bool,
+}
+
+/// The handle an asynchronous palette provider sends its items through.
+///
+/// A stream is tied to the palette run it was created for, and is cancelled
+/// once the palette runs again, such as when the query changes, or is closed.
+/// Items sent after that are dropped.
+#[derive(Clone)]
+pub struct PaletteItemStream {
+    run_id: u64,
+    run_id_counter: Arc<AtomicU64>,
+    tx: Sender<PaletteBatch>,
+}
+
+impl PaletteItemStream {
+    pub fn new(
+        run_id: u64,
+        run_id_counter: Arc<AtomicU64>,
+        tx: Sender<PaletteBatch>,
+    ) -> Self {
+        Self {
+            run_id,
+            run_id_counter,
+            tx,
+        }
+    }
+
+    /// Whether the palette has moved on from the run this stream is for, in
+    /// which case the provider should stop producing items.
+    pub fn is_cancelled(&self) -> bool {
+        self.run_id_counter.load(Ordering::Acquire) != self.run_id
+    }
+
+    /// Add items to the palette, returning false if the stream was cancelled.
+    pub fn send(&self, items: Vec<PaletteItem>) -> bool {
+        if self.is_cancelled() {
+            return false;
+        }
+        self.tx
+            .send(PaletteBatch {
+                run_id: self.run_id,
+                items,
+                replace: false,
+                done: false,
+            })
+            .is_ok()
+    }
+
+    /// Put the items in place of the ones sent so far, for a provider that
+    /// has better items than the ones it sent first. Returns false if the
+    /// stream was cancelled.
+    pub fn replace(&self, items: Vec<PaletteItem>) -> bool {
+        if self.is_cancelled() {
+            return false;
+        }
+        self.tx
+            .send(PaletteBatch {
+                run_id: self.run_id,
+                items,
+                replace: true,
+                done: false,
+            })
+            .is_ok()
+    }
+
+    /// Add the items in batches of [`PALETTE_BATCH_SIZE`], stopping early if
+    /// the stream is cancelled.
+    pub fn send_batched(&self, items: impl IntoIterator<Item = PaletteItem>) {
+        let mut items = items.into_iter().peekable();
+        while items.peek().is_some() {
+            let batch = items.by_ref().take(PALETTE_BATCH_SIZE).collect();
+            if !self.send(batch) {
+                return;
+            }
+        }
+    }
+
+    /// Mark the provider as done, which stops the loading indicator.
+    pub fn finish(self) {
+        let _ = self.tx.send(PaletteBatch {
+            run_id: self.run_id,
+            items: Vec::new(),
+            replace: false,
+            done: true,
+        });
+    }
+}
@@ -2,14 +2,19 @@
 
 use floem::{
     event::EventListener,
-    reactive::{ReadSignal, SignalGet, SignalGetUntracked, SignalSet, SignalUpdate},
+    reactive::{
+        create_memo, Memo, ReadSignal, SignalGet, SignalGetUntracked, SignalSet,
+        SignalUpdate,
+    },
     style::{CursorStyle, Style},
     view::View,
     views::{
         container, label, scroll, stack, svg, virtual_list, Decorators,
         VirtualListDirection, VirtualListItemSize,
     },
+    ViewContext,
 };
+use lapce_rpc::proxy::SearchMatch;
 use lapce_xi_rope::find::CaseMatching;
 
 use super::{kind::PanelKind, position::PanelPosition};
@@ -19,8 +24,9 @@
     config::{color::LapceColor, icon::LapceIcons, LapceConfig},
     editor::location::{EditorLocation, EditorPosition},
     focus_text::focus_text,
-    global_search::{GlobalSearchData, SearchMatchData},
+    global_search::{expand_replacement, GlobalSearchData, SearchMatchData},
     listener::Listener,
+    settings::checkbox,
     text_input::text_input,
     window_tab::{Focus, WindowTabData},
     workspace::LapceWorkspace,
@@ -32,6 +38,8 @@ pub fn global_search_panel(
 ) -> impl View {
     let global_search = window_tab_data.global_search.clone();
     let editor = global_search.editor.clone();
+    let replace_editor = global_search.replace_editor.clone();
+    let replace_focus = global_search.replace_focus;
     let config = global_search.common.config;
     let workspace = global_search.common.workspace.clone();
     let internal_command = global_search.common.internal_command;
@@ -40,7 +48,12 @@ pub fn global_search_panel(
     let is_regex = global_search.common.find.is_regex;
 
     let focus = global_search.common.focus;
-    let is_focused = move || focus.get() == Focus::Panel(PanelKind::Search);
+    let is_focused = move || {
+        focus.get() == Focus::Panel(PanelKind::Search) && !replace_focus.get()
+    };
+    let is_replace_focused = move || {
+        focus.get() == Focus::Panel(PanelKind::Search) && replace_focus.get()
+    };
 
     stack(|| {
         (
@@ -91,10 +104,22 @@ pub fn global_search_panel(
                             config,
                         )
                         .style(|| Style::BASE.padding_left_px(6.0)),
+                        clickable_icon(
+                            || LapceIcons::SEARCH_OPEN_EDITOR,
+                            {
+                                let global_search = global_search.clone();
+                                move || global_search.open_results_buffer()
+                            },
+                            || false,
+                            || false,
+                            config,
+                        )
+                        .style(|| Style::BASE.padding_left_px(6.0)),
                     )
                 })
                 .on_event(EventListener::PointerDown, move |_| {
                     focus.set(Focus::Panel(PanelKind::Search));
+                    replace_focus.set(false);
                     false
                 })
                 .style(move || {
@@ -110,6 +135,47 @@ pub fn global_search_panel(
                 })
             })
             .style(|| Style::BASE.width_pct(100.0).padding_px(10.0)),
+            container(|| {
+                stack(|| {
+                    (
+                        text_input(replace_editor, is_replace_focused)
+                            .style(|| Style::BASE.width_pct(100.0)),
+                        clickable_icon(
+                            || LapceIcons::SEARCH_REPLACE_ALL,
+                            {
+                                let global_search = global_search.clone();
+                                move || global_search.replace_all()
+                            },
+                            || false,
+                            || false,
+                            config,
+                        )
+                        .style(|| Style::BASE.padding_vert_px(4.0)),
+                    )
+                })
+                .on_event(EventListener::PointerDown, move |_| {
+                    focus.set(Focus::Panel(PanelKind::Search));
+                    replace_focus.set(true);
+                    false
+                })
+                .style(move || {
+                    Style::BASE
+                        .width_pct(100.0)
+                        .padding_right_px(6.0)
+                        .items_center()
+                        .border(1.0)
+                        .border_radius(6.0)
+                        .border_color(
+                            *config.get().get_color(LapceColor::LAPCE_BORDER),
+                        )
+                })
+            })
+            .style(|| {
+                Style::BASE
+                    .width_pct(100.0)
+                    .padding_horiz_px(10.0)
+                    .padding_bottom_px(10.0)
+            }),
             search_result(workspace, global_search, internal_command, config),
         )
     })
@@ -123,6 +189,11 @@ fn search_result(
     config: ReadSignal<Arc<LapceConfig>>,
 ) -> impl View {
     let ui_line_height = global_search_data.common.ui_line_height;
+    let cx = ViewContext::get_current();
+    let replace_text = {
+        let global_search_data = global_search_data.clone();
+        create_memo(cx.scope, move |_| global_search_data.replace_text())
+    };
     container(|| {
         scroll(move || {
             virtual_list(
@@ -132,9 +203,13 @@ fn search_result(
                         match_data.height()
                     },
                 )),
-                move || global_search_data.clone(),
+                {
+                    let global_search_data = global_search_data.clone();
+                    move || global_search_data.clone()
+                },
                 move |(path, _)| path.to_owned(),
                 move |(path, match_data)| {
+                    let global_search_data = global_search_data.clone();
                     let full_path = path.clone();
                     let path = if let Some(workspace_path) = workspace.path.as_ref()
                     {
@@ -159,6 +234,7 @@ fn search_result(
                         .to_string();
 
                     let expanded = match_data.expanded;
+                    let row_match_data = match_data.clone();
 
                     stack(|| {
                         (
@@ -263,54 +339,64 @@ fn search_result(
                                 move |m| {
                                     let path = full_path.clone();
                                     let line_number = m.line;
-                                    let start = m.start;
-                                    let end = m.end;
-                                    let line_content = m.line_content.clone();
+                                    let match_data = row_match_data.clone();
+                                    let checked_match = m.clone();
+                                    let toggled_match = m.clone();
+                                    let preview_match = m.clone();
+                                    let global_search_data =
+                                        global_search_data.clone();
 
-                                    focus_text(
-                                        move || {
-                                            let config = config.get();
-                                            let content = if config
-                                                .ui
-                                                .trim_search_results_whitespace
-                                            {
-                                                m.line_content.trim()
-                                            } else {
-                                                &m.line_content
-                                            };
-                                            format!("{}: {content}", m.line,)
-                                        },
-                                        move || {
-                                            let config = config.get();
-                                            let mut offset = if config
-                                                .ui
-                                                .trim_search_results_whitespace
-                                            {
-                                                line_content.trim_start().len()
-                                                    as i32
-                                                    - line_content.len() as i32
-                                            } else {
-                                                0
-                                            };
-                                            offset += line_number.to_string().len()
-                                                as i32
-                                                + 2;
-
-                                            ((start as i32 + offset) as usize
-                                                ..(end as i32 + offset) as usize)
-                                                .collect()
-                                        },
-                                        move || {
-                                            *config
-                                                .get()
-                                                .get_color(LapceColor::EDITOR_FOCUS)
-                                        },
-                                    )
+                                    stack(|| {
+                                        (
+                                            checkbox(
+                                                move || {
+                                                    !match_data
+                                                        .is_excluded(&checked_match)
+                                                },
+                                                config,
+                                            )
+                                            .on_click({
+                                                let match_data = match_data.clone();
+                                                move |_| {
+                                                    match_data.toggle_excluded(
+                                                        &toggled_match,
+                                                    );
+                                                    true
+                                                }
+                                            })
+                                            .style(|| {
+                                                Style::BASE.margin_right_px(6.0)
+                                            }),
+                                            search_match_text(m, config).style(
+                                                move || {
+                                                    Style::BASE.apply_if(
+                                                        !replace_text
+                                                            .get()
+                                                            .is_empty(),
+                                                        |s| s.hide(),
+                                                    )
+                                                },
+                                            ),
+                                            replace_preview(
+                                                global_search_data,
+                                                preview_match,
+                                                replace_text,
+                                                config,
+                                            )
+                                            .style(move || {
+                                                Style::BASE.apply_if(
+                                                    replace_text.get().is_empty(),
+                                                    |s| s.hide(),
+                                                )
+                                            }),
+                                        )
+                                    })
                                     .style(move || {
                                         let config = config.get();
                                         let icon_size = config.ui.icon_size() as f32;
                                         Style::BASE
                                             .margin_left_px(10.0 + icon_size + 6.0)
+                                            .items_center()
                                     })
                                     .on_click(move |_| {
                                         internal_command.send(
@@ -355,3 +441,98 @@ fn search_result(
     })
     .style(|| Style::BASE.size_pct(100.0, 100.0))
 }
+
+/// The line of the match, with the matched text highlighted
+fn search_match_text(
+    m: SearchMatch,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    let line_number = m.line;
+    let range = m.content_range();
+    let (start, end) = (range.start, range.end);
+    let line_content = m.line_content.clone();
+
+    focus_text(
+        move || {
+            let config = config.get();
+            let content = if config.ui.trim_search_results_whitespace {
+                m.line_content.trim()
+            } else {
+                &m.line_content
+            };
+            format!("{}: {content}", m.line,)
+        },
+        move || {
+            let config = config.get();
+            let mut offset = if config.ui.trim_search_results_whitespace {
+                line_content.trim_start().len() as i32 - line_content.len() as i32
+            } else {
+                0
+            };
+            offset += line_number.to_string().len() as i32 + 2;
+
+            ((start as i32 + offset) as usize..(end as i32 + offset) as usize)
+                .collect()
+        },
+        move || *config.get().get_color(LapceColor::EDITOR_FOCUS),
+    )
+}
+
+/// The line of the match as it would be after the replacement, with the
+/// removed text and its replacement next to each other like in a diff
+fn replace_preview(
+    global_search_data: GlobalSearchData,
+    m: SearchMatch,
+    replace_text: Memo<String>,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    let content = m.line_content.trim_end_matches(['\r', '\n']);
+    let range = m.content_range();
+    let start = range.start.min(content.len());
+    let end = range.end.clamp(start, content.len());
+    let (before, matched, after) = match (
+        content.get(..start),
+        content.get(start..end),
+        content.get(end..),
+    ) {
+        (Some(before), Some(matched), Some(after)) => {
+            (before.to_string(), matched.to_string(), after.to_string())
+        }
+        _ => (content.to_string(), String::new(), String::new()),
+    };
+    let replacement_matched = matched.clone();
+
+    stack(|| {
+        (
+            label(move || {
+                let config = config.get();
+                let before = if config.ui.trim_search_results_whitespace {
+                    before.trim_start()
+                } else {
+                    &before
+                };
+                format!("{}: {before}", m.line)
+            }),
+            label(move || matched.clone()).style(move || {
+                let config = config.get();
+                Style::BASE
+                    .color(*config.get_color(LapceColor::SOURCE_CONTROL_REMOVED))
+            }),
+            label(move || {
+                expand_replacement(
+                    global_search_data.replace_regex().as_ref(),
+                    &replacement_matched,
+                    &replace_text.get(),
+                )
+            })
+            .style(move || {
+                let config = config.get();
+                Style::BASE
+                    .color(*config.get_color(LapceColor::SOURCE_CONTROL_ADDED))
+            }),
+            label(move || after.clone())
+                .style(|| Style::BASE.min_width_px(0.0).text_ellipsis()),
+        )
+    })
+    .style(|| Style::BASE.min_width_px(0.0).items_center())
+}
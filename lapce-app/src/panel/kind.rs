@@ -15,6 +15,8 @@ pub enum PanelKind {
     Search,
     Problem,
     Debug,
+    RegexTester,
+    PluginViews,
 }
 
 impl PanelKind {
@@ -27,6 +29,8 @@ pub fn svg_name(&self) -> &'static str {
             PanelKind::Search => LapceIcons::SEARCH,
             PanelKind::Problem => LapceIcons::PROBLEM,
             PanelKind::Debug => LapceIcons::DEBUG,
+            PanelKind::RegexTester => LapceIcons::SEARCH_REGEX,
+            PanelKind::PluginViews => LapceIcons::PLUGIN_VIEWS,
         }
     }
 
@@ -3,8 +3,10 @@
 pub mod global_search_view;
 pub mod kind;
 pub mod plugin_view;
+pub mod plugin_views_view;
 pub mod position;
 pub mod problem_view;
+pub mod regex_tester_view;
 pub mod source_control_view;
 pub mod style;
 pub mod terminal_view;
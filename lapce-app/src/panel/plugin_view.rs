@@ -22,6 +22,7 @@
 use super::{kind::PanelKind, position::PanelPosition, view::panel_header};
 use crate::{
     app::clickable_icon,
+    command::InternalCommand,
     config::{color::LapceColor, icon::LapceIcons},
     plugin::{AvailableVoltData, InstalledVoltData, PluginData},
     text_input::text_input,
@@ -186,6 +187,22 @@ fn installed_view(plugin: PluginData) -> impl View {
                             }),
                     )
                     .separator()
+                    .entry(
+                        MenuItem::new("Permissions")
+                            .enabled(meta.wasm.is_some())
+                            .action({
+                                let plugin = plugin.clone();
+                                let meta = meta.clone();
+                                move || {
+                                    plugin.common.internal_command.send(
+                                        InternalCommand::ReviewPluginPermissions {
+                                            volt: meta.clone(),
+                                        },
+                                    );
+                                }
+                            }),
+                    )
+                    .separator()
                     .entry(MenuItem::new("Uninstall").action({
                         move || {
                             plugin.uninstall_volt(meta.clone());
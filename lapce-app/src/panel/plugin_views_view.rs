@@ -0,0 +1,259 @@
+use std::{collections::HashSet, sync::Arc};
+
+use floem::{
+    peniko::Color,
+    reactive::{
+        create_memo, create_rw_signal, SignalGet, SignalUpdate, SignalWith,
+        SignalWithUntracked,
+    },
+    style::{CursorStyle, Style},
+    view::View,
+    views::{container, label, list, scroll, stack, svg, Decorators},
+    ViewContext,
+};
+use lapce_rpc::plugin::{PluginViewItem, VoltID};
+
+use super::{position::PanelPosition, view::panel_header};
+use crate::{
+    config::{color::LapceColor, icon::LapceIcons},
+    window_tab::WindowTabData,
+};
+
+/// A row of a plugin panel, for an item whose ancestors are all expanded
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct PluginViewRow {
+    /// The indices of the item and its ancestors in the tree
+    path: Vec<usize>,
+    label: String,
+    description: Option<String>,
+    /// Whether the children are shown, `None` for an item without any
+    expanded: Option<bool>,
+}
+
+/// The panels the plugins show, each as a section
+pub fn plugin_views_panel(
+    window_tab_data: Arc<WindowTabData>,
+    _position: PanelPosition,
+) -> impl View {
+    let config = window_tab_data.common.config;
+    let panels = window_tab_data.common.plugin_panels;
+
+    container(|| {
+        scroll(move || {
+            stack(move || {
+                (
+                    label(|| "No plugin shows a panel".to_string()).style(
+                        move || {
+                            Style::BASE
+                                .padding_px(10.0)
+                                .color(
+                                    *config.get().get_color(LapceColor::EDITOR_DIM),
+                                )
+                                .apply_if(
+                                    panels.with(|panels| !panels.is_empty()),
+                                    |s| s.hide(),
+                                )
+                        },
+                    ),
+                    list(
+                        move || {
+                            panels.with(|panels| {
+                                panels
+                                    .iter()
+                                    .map(|((volt, id), panel)| {
+                                        (
+                                            volt.clone(),
+                                            id.clone(),
+                                            panel.title.clone(),
+                                        )
+                                    })
+                                    .collect::<im::Vector<_>>()
+                            })
+                        },
+                        |section| section.clone(),
+                        move |(volt, id, title)| {
+                            panel_section(window_tab_data.clone(), volt, id, title)
+                        },
+                    )
+                    .style(|| Style::BASE.flex_col().width_pct(100.0)),
+                )
+            })
+            .style(|| Style::BASE.flex_col().width_pct(100.0))
+        })
+        .scroll_bar_color(move || {
+            *config.get().get_color(LapceColor::LAPCE_SCROLL_BAR)
+        })
+        .style(|| Style::BASE.absolute().size_pct(100.0, 100.0))
+    })
+    .style(|| Style::BASE.size_pct(100.0, 100.0))
+}
+
+fn panel_section(
+    window_tab_data: Arc<WindowTabData>,
+    volt: VoltID,
+    id: String,
+    title: String,
+) -> impl View {
+    let cx = ViewContext::get_current();
+    let config = window_tab_data.common.config;
+    let panels = window_tab_data.common.plugin_panels;
+    // The items the user expanded or collapsed, from how the plugin set them
+    let toggled = create_rw_signal(cx.scope, HashSet::<Vec<usize>>::new());
+
+    let key = (volt, id);
+    let rows = {
+        let key = key.clone();
+        create_memo(cx.scope, move |_| {
+            panels.with(|panels| {
+                let mut rows = im::Vector::new();
+                if let Some(panel) = panels.get(&key) {
+                    toggled.with(|toggled| {
+                        visible_rows(
+                            &panel.items,
+                            toggled,
+                            &mut Vec::new(),
+                            &mut rows,
+                        )
+                    });
+                }
+                rows
+            })
+        })
+    };
+
+    stack(move || {
+        (
+            panel_header(title, config),
+            list(
+                move || rows.get(),
+                |row| row.clone(),
+                move |row| {
+                    let window_tab_data = window_tab_data.clone();
+                    let key = key.clone();
+                    let depth = row.path.len() - 1;
+                    let expanded = row.expanded;
+                    let path = row.path;
+                    let text = row.label;
+                    let description = row.description.unwrap_or_default();
+                    stack(|| {
+                        (
+                            svg(move || {
+                                config.get().ui_svg(match expanded {
+                                    Some(true) => LapceIcons::ITEM_OPENED,
+                                    _ => LapceIcons::ITEM_CLOSED,
+                                })
+                            })
+                            .style(move || {
+                                let config = config.get();
+                                let size = config.ui.icon_size() as f32;
+                                Style::BASE
+                                    .margin_right_px(6.0)
+                                    .size_px(size, size)
+                                    .min_width_px(size)
+                                    .color(
+                                        *config.get_color(
+                                            LapceColor::LAPCE_ICON_ACTIVE,
+                                        ),
+                                    )
+                                    .apply_if(expanded.is_none(), |s| {
+                                        s.color(Color::TRANSPARENT)
+                                    })
+                            }),
+                            label(move || text.clone()).style(|| {
+                                Style::BASE
+                                    .margin_right_px(6.0)
+                                    .min_width_px(0.0)
+                                    .text_ellipsis()
+                            }),
+                            label(move || description.clone()).style(move || {
+                                Style::BASE.min_width_px(0.0).text_ellipsis().color(
+                                    *config.get().get_color(LapceColor::EDITOR_DIM),
+                                )
+                            }),
+                        )
+                    })
+                    .on_click(move |_| {
+                        if expanded.is_some() {
+                            toggled.update(|toggled| {
+                                if !toggled.remove(&path) {
+                                    toggled.insert(path.clone());
+                                }
+                            });
+                        }
+                        // The command is looked up when clicked, for the one
+                        // the plugin set last
+                        let command = panels.with_untracked(|panels| {
+                            let panel = panels.get(&key)?;
+                            item_at(&panel.items, &path)?.command.clone()
+                        });
+                        if let Some(command) = command {
+                            window_tab_data.run_command_from_plugin(
+                                &command.command,
+                                command.data,
+                            );
+                        }
+                        true
+                    })
+                    .style(move || {
+                        let config = config.get();
+                        Style::BASE
+                            .width_pct(100.0)
+                            .min_width_px(0.0)
+                            .items_center()
+                            .padding_left_px(
+                                10.0 + (config.ui.icon_size() as f32 + 6.0)
+                                    * depth as f32,
+                            )
+                            .padding_right_px(10.0)
+                    })
+                    .hover_style(move || {
+                        Style::BASE.cursor(CursorStyle::Pointer).background(
+                            *config
+                                .get()
+                                .get_color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                        )
+                    })
+                },
+            )
+            .style(|| Style::BASE.flex_col().width_pct(100.0).line_height(1.6)),
+        )
+    })
+    .style(|| Style::BASE.flex_col().width_pct(100.0))
+}
+
+/// The rows of the items, and of the children of the ones that are expanded
+fn visible_rows(
+    items: &[PluginViewItem],
+    toggled: &HashSet<Vec<usize>>,
+    path: &mut Vec<usize>,
+    rows: &mut im::Vector<PluginViewRow>,
+) {
+    for (i, item) in items.iter().enumerate() {
+        path.push(i);
+        let expanded = (!item.children.is_empty())
+            .then(|| item.expanded != toggled.contains(path.as_slice()));
+        rows.push_back(PluginViewRow {
+            path: path.clone(),
+            label: item.label.clone(),
+            description: item.description.clone(),
+            expanded,
+        });
+        if expanded == Some(true) {
+            visible_rows(&item.children, toggled, path, rows);
+        }
+        path.pop();
+    }
+}
+
+fn item_at<'a>(
+    items: &'a [PluginViewItem],
+    path: &[usize],
+) -> Option<&'a PluginViewItem> {
+    let (first, rest) = path.split_first()?;
+    let item = items.get(*first)?;
+    if rest.is_empty() {
+        Some(item)
+    } else {
+        item_at(&item.children, rest)
+    }
+}
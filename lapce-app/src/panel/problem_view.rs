@@ -1,10 +1,11 @@
 use std::{path::PathBuf, sync::Arc};
 
 use floem::{
+    event::EventListener,
     peniko::Color,
     reactive::{
-        create_memo, create_rw_signal, ReadSignal, SignalGet, SignalUpdate,
-        SignalWith,
+        create_memo, create_rw_signal, ReadSignal, RwSignal, SignalGet, SignalSet,
+        SignalUpdate, SignalWith,
     },
     style::{CursorStyle, Style},
     view::View,
@@ -13,84 +14,147 @@
 };
 use lsp_types::{DiagnosticRelatedInformation, DiagnosticSeverity};
 
-use super::{position::PanelPosition, view::panel_header};
+use super::kind::PanelKind;
 use crate::{
     command::InternalCommand,
     config::{color::LapceColor, icon::LapceIcons, LapceConfig},
-    doc::{DiagnosticData, EditorDiagnostic},
+    doc::EditorDiagnostic,
     editor::location::{EditorLocation, EditorPosition},
     listener::Listener,
+    problem::ProblemData,
     proxy::path_from_url,
-    window_tab::WindowTabData,
+    text_input::text_input,
+    window_tab::{Focus, WindowTabData},
     workspace::LapceWorkspace,
 };
 
-pub fn problem_panel(
-    window_tab_data: Arc<WindowTabData>,
-    position: PanelPosition,
-) -> impl View {
-    let config = window_tab_data.common.config;
-    let is_bottom = position.is_bottom();
+pub fn problem_panel(window_tab_data: Arc<WindowTabData>) -> impl View {
+    let problem = window_tab_data.problem.clone();
+    let editor = problem.filter.clone();
+    let config = problem.common.config;
+    let focus = problem.common.focus;
+    let is_focused = move || focus.get() == Focus::Panel(PanelKind::Problem);
+
+    let cx = ViewContext::get_current();
+    let diagnostics = problem.main_split.diagnostics;
+    let counts = create_memo(cx.scope, move |_| {
+        let mut counts = (0, 0, 0);
+        diagnostics.with(|diagnostics| {
+            for (_, data) in diagnostics.iter() {
+                data.diagnostics.with(|diagnostics| {
+                    for d in diagnostics.iter() {
+                        match d.diagnostic.severity {
+                            Some(DiagnosticSeverity::ERROR) => counts.0 += 1,
+                            Some(DiagnosticSeverity::WARNING) => counts.1 += 1,
+                            _ => counts.2 += 1,
+                        }
+                    }
+                });
+            }
+        });
+        counts
+    });
+
     stack(|| {
         (
-            stack(|| {
-                (
-                    panel_header("Errors".to_string(), config),
-                    problem_section(
-                        window_tab_data.clone(),
-                        DiagnosticSeverity::ERROR,
-                    ),
-                )
-            })
-            .style(move || {
-                let config = config.get();
-                Style::BASE
-                    .flex_col()
-                    .flex_basis_px(0.0)
-                    .flex_grow(1.0)
-                    .border_color(*config.get_color(LapceColor::LAPCE_BORDER))
-                    .apply_if(is_bottom, |s| s.border_right(1.0))
-                    .apply_if(!is_bottom, |s| s.border_bottom(1.0))
-            }),
-            stack(|| {
-                (
-                    panel_header("Warnings".to_string(), config),
-                    problem_section(window_tab_data, DiagnosticSeverity::WARNING),
-                )
+            container(|| {
+                stack(|| {
+                    (
+                        container(|| {
+                            text_input(editor, is_focused)
+                                .style(|| Style::BASE.width_pct(100.0))
+                        })
+                        .style(move || {
+                            let config = config.get();
+                            Style::BASE
+                                .flex_basis_px(0.0)
+                                .flex_grow(1.0)
+                                .min_width_px(0.0)
+                                .border(1.0)
+                                .border_radius(6.0)
+                                .border_color(
+                                    *config.get_color(LapceColor::LAPCE_BORDER),
+                                )
+                        }),
+                        severity_toggle(
+                            move || format!("Errors {}", counts.get().0),
+                            problem.show_errors,
+                            config,
+                        ),
+                        severity_toggle(
+                            move || format!("Warnings {}", counts.get().1),
+                            problem.show_warnings,
+                            config,
+                        ),
+                        severity_toggle(
+                            move || format!("Info {}", counts.get().2),
+                            problem.show_infos,
+                            config,
+                        ),
+                    )
+                })
+                .on_event(EventListener::PointerDown, move |_| {
+                    focus.set(Focus::Panel(PanelKind::Problem));
+                    false
+                })
+                .style(|| Style::BASE.width_pct(100.0).items_center())
             })
-            .style(|| Style::BASE.flex_col().flex_basis_px(0.0).flex_grow(1.0)),
+            .style(|| Style::BASE.width_pct(100.0).padding_px(10.0)),
+            problem_list(problem),
         )
     })
-    .style(move || {
-        Style::BASE
-            .size_pct(100.0, 100.0)
-            .apply_if(!is_bottom, |s| s.flex_col())
-    })
+    .style(|| Style::BASE.absolute().size_pct(100.0, 100.0).flex_col())
 }
 
-fn problem_section(
-    window_tab_data: Arc<WindowTabData>,
-    severity: DiagnosticSeverity,
+fn severity_toggle(
+    text: impl Fn() -> String + 'static,
+    shown: RwSignal<bool>,
+    config: ReadSignal<Arc<LapceConfig>>,
 ) -> impl View {
-    let config = window_tab_data.common.config;
-    let main_split = window_tab_data.main_split.clone();
-    let internal_command = window_tab_data.common.internal_command;
+    container(|| label(text))
+        .on_click(move |_| {
+            shown.update(|shown| *shown = !*shown);
+            true
+        })
+        .style(move || {
+            let config = config.get();
+            Style::BASE
+                .margin_left_px(6.0)
+                .padding_horiz_px(6.0)
+                .border_radius(6.0)
+                .apply_if(shown.get(), |s| {
+                    s.background(
+                        *config.get_color(LapceColor::PANEL_CURRENT_BACKGROUND),
+                    )
+                })
+                .apply_if(!shown.get(), |s| {
+                    s.color(*config.get_color(LapceColor::EDITOR_DIM))
+                })
+        })
+        .hover_style(move || {
+            Style::BASE.cursor(CursorStyle::Pointer).background(
+                *config.get().get_color(LapceColor::PANEL_HOVERED_BACKGROUND),
+            )
+        })
+}
+
+fn problem_list(problem: ProblemData) -> impl View {
+    let config = problem.common.config;
+    let items = problem.items;
     container(|| {
         scroll(move || {
-            let workspace = main_split.common.workspace.clone();
+            let workspace = problem.common.workspace.clone();
             list(
-                move || main_split.diagnostics.get(),
-                |(p, _)| p.clone(),
-                move |(path, diagnostic_data)| {
-                    file_view(
-                        workspace.clone(),
-                        path,
-                        diagnostic_data,
-                        severity,
-                        internal_command,
-                        config,
-                    )
+                move || {
+                    items.with(|items| {
+                        items
+                            .iter()
+                            .map(|(path, _)| path.clone())
+                            .collect::<im::Vector<_>>()
+                    })
                 },
+                |path| path.clone(),
+                move |path| file_view(workspace.clone(), path, problem.clone()),
             )
             .style(|| Style::BASE.flex_col().width_pct(100.0).line_height(1.6))
         })
@@ -99,33 +163,45 @@ fn problem_section(
         })
         .style(|| Style::BASE.absolute().size_pct(100.0, 100.0))
     })
-    .style(|| Style::BASE.size_pct(100.0, 100.0))
+    .style(|| {
+        Style::BASE
+            .width_pct(100.0)
+            .flex_basis_px(0.0)
+            .flex_grow(1.0)
+    })
 }
 
 fn file_view(
     workspace: Arc<LapceWorkspace>,
     path: PathBuf,
-    diagnostic_data: DiagnosticData,
-    severity: DiagnosticSeverity,
-    internal_command: Listener<InternalCommand>,
-    config: ReadSignal<Arc<LapceConfig>>,
+    problem: ProblemData,
 ) -> impl View {
     let cx = ViewContext::get_current();
+    let config = problem.common.config;
+    let internal_command = problem.common.internal_command;
     let collpased = create_rw_signal(cx.scope, false);
 
+    let items = problem.items;
+    let file_path = path.clone();
     let diagnostics = create_memo(cx.scope, move |_| {
-        let diagnostics = diagnostic_data.diagnostics.get();
-        let diagnostics: im::Vector<EditorDiagnostic> = diagnostics
-            .into_iter()
-            .filter_map(|d| {
-                if d.diagnostic.severity == Some(severity) {
-                    Some(d)
-                } else {
-                    None
-                }
-            })
-            .collect();
-        diagnostics
+        items.with(|items| {
+            items
+                .iter()
+                .find(|(p, _)| p == &file_path)
+                .map(|(_, diagnostics)| diagnostics.clone())
+                .unwrap_or_default()
+        })
+    });
+    // the index of the first diagnostic of this file in the whole problem list
+    let file_path = path.clone();
+    let start = create_memo(cx.scope, move |_| {
+        items.with(|items| {
+            items
+                .iter()
+                .take_while(|(p, _)| p != &file_path)
+                .map(|(_, diagnostics)| diagnostics.len())
+                .sum::<usize>()
+        })
     });
 
     let full_path = path.clone();
@@ -138,18 +214,6 @@ fn file_view(
     };
     let style_path = path.clone();
 
-    let icon = match severity {
-        DiagnosticSeverity::ERROR => LapceIcons::ERROR,
-        _ => LapceIcons::WARNING,
-    };
-    let icon_color = move || {
-        let config = config.get();
-        match severity {
-            DiagnosticSeverity::ERROR => *config.get_color(LapceColor::LAPCE_ERROR),
-            _ => *config.get_color(LapceColor::LAPCE_WARN),
-        }
-    };
-
     let file_name = path
         .file_name()
         .and_then(|s| s.to_str())
@@ -259,16 +323,21 @@ fn file_view(
                     if collpased.get() {
                         im::Vector::new()
                     } else {
-                        diagnostics.get()
+                        diagnostics
+                            .get()
+                            .into_iter()
+                            .enumerate()
+                            .collect::<im::Vector<_>>()
                     }
                 },
                 |_| 0,
-                move |d| {
+                move |(i, d)| {
+                    let index = move || start.get() + i;
                     item_view(
                         full_path.clone(),
                         d,
-                        icon,
-                        icon_color,
+                        index,
+                        problem.clone(),
                         internal_command,
                         config,
                     )
@@ -289,11 +358,33 @@ fn file_view(
 fn item_view(
     path: PathBuf,
     d: EditorDiagnostic,
-    icon: &'static str,
-    icon_color: impl Fn() -> Color + 'static,
+    index: impl Fn() -> usize + Copy + 'static,
+    problem: ProblemData,
     internal_command: Listener<InternalCommand>,
     config: ReadSignal<Arc<LapceConfig>>,
 ) -> impl View {
+    let severity = d.diagnostic.severity;
+    let icon = match severity {
+        Some(DiagnosticSeverity::ERROR) => LapceIcons::ERROR,
+        _ => LapceIcons::WARNING,
+    };
+    let icon_color = move || -> Color {
+        let config = config.get();
+        match severity {
+            Some(DiagnosticSeverity::ERROR) => {
+                *config.get_color(LapceColor::LAPCE_ERROR)
+            }
+            Some(DiagnosticSeverity::WARNING) => {
+                *config.get_color(LapceColor::LAPCE_WARN)
+            }
+            _ => *config.get_color(LapceColor::EDITOR_DIM),
+        }
+    };
+    let active = problem.active;
+    let focus = problem.common.focus;
+    let is_active = move || {
+        active.get() == index() && focus.get() == Focus::Panel(PanelKind::Problem)
+    };
     let related = d.diagnostic.related_information.unwrap_or_default();
     let location = EditorLocation {
         path,
@@ -342,7 +433,18 @@ fn item_view(
                         }),
                     )
                 })
-                .style(move || Style::BASE.width_pct(100.0).min_width_px(0.0))
+                .style(move || {
+                    Style::BASE.width_pct(100.0).min_width_px(0.0).apply_if(
+                        is_active(),
+                        |s| {
+                            s.background(
+                                *config
+                                    .get()
+                                    .get_color(LapceColor::PANEL_CURRENT_BACKGROUND),
+                            )
+                        },
+                    )
+                })
                 .hover_style(move || {
                     Style::BASE.cursor(CursorStyle::Pointer).background(
                         *config
@@ -352,6 +454,7 @@ fn item_view(
                 })
             })
             .on_click(move |_| {
+                active.set(index());
                 internal_command.send(InternalCommand::JumpToLocation {
                     location: location.clone(),
                 });
@@ -0,0 +1,317 @@
+use std::sync::Arc;
+
+use floem::{
+    event::EventListener,
+    reactive::{
+        ReadSignal, SignalGet, SignalGetUntracked, SignalSet, SignalUpdate,
+        SignalWith,
+    },
+    style::{CursorStyle, Style},
+    view::View,
+    views::{container, label, list, scroll, stack, Decorators},
+};
+
+use super::{kind::PanelKind, position::PanelPosition};
+use crate::{
+    app::clickable_icon,
+    config::{color::LapceColor, icon::LapceIcons, LapceConfig},
+    focus_text::focus_text,
+    regex_tester::{RegexTestMatch, RegexTesterData},
+    text_input::text_input,
+    window_tab::{Focus, WindowTabData},
+};
+
+pub fn regex_tester_panel(
+    window_tab_data: Arc<WindowTabData>,
+    _position: PanelPosition,
+) -> impl View {
+    let regex_tester = window_tab_data.regex_tester.clone();
+    let config = regex_tester.common.config;
+    let focus = regex_tester.common.focus;
+    let use_sample = regex_tester.use_sample;
+    let sample_focus = regex_tester.sample_focus;
+    let case_sensitive = regex_tester.case_sensitive;
+    let result = regex_tester.result;
+    let is_focused = move || {
+        focus.get() == Focus::Panel(PanelKind::RegexTester)
+            && !(use_sample.get() && sample_focus.get())
+    };
+    let is_sample_focused = move || {
+        focus.get() == Focus::Panel(PanelKind::RegexTester)
+            && use_sample.get()
+            && sample_focus.get()
+    };
+
+    stack(|| {
+        (
+            container(|| {
+                stack(|| {
+                    (
+                        container(|| {
+                            stack(|| {
+                                (
+                                    text_input(
+                                        regex_tester.pattern.clone(),
+                                        is_focused,
+                                    )
+                                    .style(|| Style::BASE.width_pct(100.0)),
+                                    clickable_icon(
+                                        || LapceIcons::SEARCH_CASE_SENSITIVE,
+                                        move || {
+                                            case_sensitive.update(|case| {
+                                                *case = !*case;
+                                            });
+                                        },
+                                        move || case_sensitive.get(),
+                                        || false,
+                                        config,
+                                    )
+                                    .style(|| Style::BASE.padding_vert_px(4.0)),
+                                )
+                            })
+                            .on_event(EventListener::PointerDown, move |_| {
+                                focus.set(Focus::Panel(PanelKind::RegexTester));
+                                sample_focus.set(false);
+                                false
+                            })
+                            .style(|| {
+                                Style::BASE
+                                    .width_pct(100.0)
+                                    .padding_right_px(6.0)
+                                    .items_center()
+                            })
+                        })
+                        .style(move || {
+                            let config = config.get();
+                            Style::BASE
+                                .flex_basis_px(0.0)
+                                .flex_grow(1.0)
+                                .min_width_px(0.0)
+                                .border(1.0)
+                                .border_radius(6.0)
+                                .border_color(
+                                    *config.get_color(LapceColor::LAPCE_BORDER),
+                                )
+                        }),
+                        tester_button(
+                            move || {
+                                if use_sample.get() {
+                                    "Sample Text".to_string()
+                                } else {
+                                    "Active Editor".to_string()
+                                }
+                            },
+                            move || {
+                                use_sample.update(|use_sample| {
+                                    *use_sample = !*use_sample;
+                                });
+                                sample_focus.set(use_sample.get_untracked());
+                            },
+                            config,
+                        ),
+                        tester_button(
+                            || "Find".to_string(),
+                            {
+                                let regex_tester = regex_tester.clone();
+                                move || regex_tester.use_in_find(false)
+                            },
+                            config,
+                        ),
+                        tester_button(
+                            || "Replace".to_string(),
+                            {
+                                let regex_tester = regex_tester.clone();
+                                move || regex_tester.use_in_find(true)
+                            },
+                            config,
+                        ),
+                        tester_button(
+                            || "Search Workspace".to_string(),
+                            {
+                                let regex_tester = regex_tester.clone();
+                                move || regex_tester.use_in_global_search()
+                            },
+                            config,
+                        ),
+                    )
+                })
+                .style(|| Style::BASE.width_pct(100.0).items_center())
+            })
+            .style(|| Style::BASE.width_pct(100.0).padding_px(10.0)),
+            container(|| {
+                text_input(regex_tester.sample.clone(), is_sample_focused)
+                    .style(|| Style::BASE.width_pct(100.0))
+            })
+            .on_event(EventListener::PointerDown, move |_| {
+                focus.set(Focus::Panel(PanelKind::RegexTester));
+                sample_focus.set(true);
+                false
+            })
+            .style(move || {
+                let config = config.get();
+                Style::BASE
+                    .margin_horiz_px(10.0)
+                    .margin_bottom_px(10.0)
+                    .border(1.0)
+                    .border_radius(6.0)
+                    .border_color(*config.get_color(LapceColor::LAPCE_BORDER))
+                    .apply_if(!use_sample.get(), |s| s.hide())
+            }),
+            label(move || {
+                result.with(|result| {
+                    if let Some(error) = result.error.as_ref() {
+                        error.clone()
+                    } else if result.truncated {
+                        format!("{} matches (truncated)", result.matches.len())
+                    } else if result.matches.len() == 1 {
+                        "1 match".to_string()
+                    } else {
+                        format!("{} matches", result.matches.len())
+                    }
+                })
+            })
+            .style(move || {
+                let config = config.get();
+                let is_error = result.with(|result| result.error.is_some());
+                Style::BASE
+                    .padding_horiz_px(10.0)
+                    .padding_bottom_px(6.0)
+                    .color(*config.get_color(LapceColor::EDITOR_DIM))
+                    .apply_if(is_error, |s| {
+                        s.color(*config.get_color(LapceColor::LAPCE_ERROR))
+                    })
+            }),
+            match_list(regex_tester),
+        )
+    })
+    .style(|| Style::BASE.absolute().size_pct(100.0, 100.0).flex_col())
+}
+
+fn tester_button(
+    text: impl Fn() -> String + 'static,
+    on_click: impl Fn() + 'static,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    container(|| label(text))
+        .on_click(move |_| {
+            on_click();
+            true
+        })
+        .style(move || {
+            let config = config.get();
+            Style::BASE
+                .margin_left_px(6.0)
+                .padding_horiz_px(6.0)
+                .border_radius(6.0)
+                .background(*config.get_color(LapceColor::PANEL_CURRENT_BACKGROUND))
+        })
+        .hover_style(move || {
+            Style::BASE.cursor(CursorStyle::Pointer).background(
+                *config.get().get_color(LapceColor::PANEL_HOVERED_BACKGROUND),
+            )
+        })
+}
+
+fn match_list(regex_tester: RegexTesterData) -> impl View {
+    let config = regex_tester.common.config;
+    let result = regex_tester.result;
+    container(|| {
+        scroll(move || {
+            list(
+                move || {
+                    result.with(|result| {
+                        result
+                            .matches
+                            .iter()
+                            .cloned()
+                            .enumerate()
+                            .collect::<im::Vector<_>>()
+                    })
+                },
+                |(i, m)| (*i, m.offset),
+                move |(i, m)| {
+                    let regex_tester = regex_tester.clone();
+                    match_view(i, m, regex_tester, config)
+                },
+            )
+            .style(|| Style::BASE.flex_col().min_width_pct(100.0).line_height(1.6))
+        })
+        .scroll_bar_color(move || {
+            *config.get().get_color(LapceColor::LAPCE_SCROLL_BAR)
+        })
+        .style(|| Style::BASE.absolute().size_pct(100.0, 100.0))
+    })
+    .style(|| {
+        Style::BASE
+            .width_pct(100.0)
+            .flex_basis_px(0.0)
+            .flex_grow(1.0)
+    })
+}
+
+/// The line of the match with the matched text highlighted, followed by what
+/// each capture group captured
+fn match_view(
+    index: usize,
+    m: RegexTestMatch,
+    regex_tester: RegexTesterData,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    let active = regex_tester.active;
+    let line = m.line + 1;
+    let line_content = m.line_content.clone();
+    let prefix_len = line.to_string().len() + 2;
+    // The highlighted characters, as the match is a byte range
+    let indices: Vec<usize> = m
+        .line_content
+        .char_indices()
+        .enumerate()
+        .filter(|(_, (offset, _))| *offset >= m.start && *offset < m.end)
+        .map(|(i, _)| i + prefix_len)
+        .collect();
+    let groups = m
+        .groups
+        .iter()
+        .map(|(name, captured)| {
+            format!("{name}: {}", captured.as_deref().unwrap_or("-"))
+        })
+        .collect::<Vec<_>>()
+        .join("  ");
+
+    stack(|| {
+        (
+            focus_text(
+                move || format!("{line}: {line_content}"),
+                move || indices.clone(),
+                move || *config.get().get_color(LapceColor::EDITOR_FOCUS),
+            ),
+            label(move || groups.clone()).style(move || {
+                Style::BASE
+                    .margin_left_px(10.0)
+                    .min_width_px(0.0)
+                    .text_ellipsis()
+                    .color(*config.get().get_color(LapceColor::EDITOR_DIM))
+            }),
+        )
+    })
+    .on_click(move |_| {
+        active.set(index);
+        regex_tester.select_match(&m);
+        true
+    })
+    .style(move || {
+        let config = config.get();
+        Style::BASE
+            .padding_horiz_px(10.0)
+            .min_width_pct(100.0)
+            .items_center()
+            .apply_if(active.get() == index, |s| {
+                s.background(*config.get_color(LapceColor::PANEL_CURRENT_BACKGROUND))
+            })
+    })
+    .hover_style(move || {
+        Style::BASE.cursor(CursorStyle::Pointer).background(
+            *config.get().get_color(LapceColor::PANEL_HOVERED_BACKGROUND),
+        )
+    })
+}
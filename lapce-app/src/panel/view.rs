@@ -17,8 +17,10 @@
     global_search_view::global_search_panel,
     kind::PanelKind,
     plugin_view::plugin_panel,
+    plugin_views_view::plugin_views_panel,
     position::{PanelContainerPosition, PanelPosition},
     problem_view::problem_panel,
+    regex_tester_view::regex_tester_panel,
     source_control_view::source_control_panel,
     terminal_view::terminal_panel,
 };
@@ -179,11 +181,17 @@ fn panel_view(
                     Box::new(global_search_panel(window_tab_data.clone(), position))
                 }),
                 PanelKind::Problem => container_box(|| {
-                    Box::new(problem_panel(window_tab_data.clone(), position))
+                    Box::new(problem_panel(window_tab_data.clone()))
                 }),
                 PanelKind::Debug => container_box(|| {
                     Box::new(debug_panel(window_tab_data.clone(), position))
                 }),
+                PanelKind::RegexTester => container_box(|| {
+                    Box::new(regex_tester_panel(window_tab_data.clone(), position))
+                }),
+                PanelKind::PluginViews => container_box(|| {
+                    Box::new(plugin_views_panel(window_tab_data.clone(), position))
+                }),
             };
             view.style(|| Style::BASE.size_pct(100.0, 100.0))
         },
@@ -237,6 +245,8 @@ fn panel_picker(
                 PanelKind::Search => LapceIcons::SEARCH,
                 PanelKind::Problem => LapceIcons::PROBLEM,
                 PanelKind::Debug => LapceIcons::DEBUG_ALT,
+                PanelKind::RegexTester => LapceIcons::SEARCH_REGEX,
+                PanelKind::PluginViews => LapceIcons::PLUGIN_VIEWS,
             };
             let is_active = {
                 let window_tab_data = window_tab_data.clone();
@@ -1,22 +1,29 @@
-use std::{collections::HashSet, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
 use anyhow::Result;
 use floem::{
     ext_event::create_ext_action,
     reactive::{
-        create_effect, create_rw_signal, use_context, RwSignal, Scope,
+        create_effect, create_rw_signal, use_context, RwSignal, Scope, SignalGet,
         SignalGetUntracked, SignalSet, SignalUpdate, SignalWith,
         SignalWithUntracked,
     },
 };
 use indexmap::IndexMap;
-use lapce_core::mode::Mode;
-use lapce_proxy::plugin::{download_volt, volt_icon, wasi::find_all_volts};
-use lapce_rpc::plugin::{VoltID, VoltInfo, VoltMetadata};
+use lapce_core::{mode::Mode, selection::Selection};
+use lapce_proxy::{
+    network,
+    plugin::{download_volt, volt_icon, wasi::find_all_volts},
+};
+use lapce_rpc::plugin::{PluginPermissions, VoltID, VoltInfo, VoltMetadata};
+use lapce_xi_rope::Rope;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    command::{CommandExecuted, CommandKind},
+    command::{CommandExecuted, CommandKind, InternalCommand, WindowCommand},
     db::LapceDb,
     editor::EditorData,
     id::EditorId,
@@ -58,6 +65,10 @@ pub struct PluginData {
     pub all: AvailableVoltList,
     pub disabled: RwSignal<HashSet<VoltID>>,
     pub workspace_disabled: RwSignal<HashSet<VoltID>>,
+    /// The plugins disabled by the active profile
+    pub profile_disabled: RwSignal<HashSet<VoltID>>,
+    /// The permissions granted to the plugins that were reviewed
+    pub permissions: RwSignal<HashMap<VoltID, PluginPermissions>>,
     pub common: CommonData,
 }
 
@@ -99,6 +110,8 @@ pub fn new(
         cx: Scope,
         disabled: HashSet<VoltID>,
         workspace_disabled: HashSet<VoltID>,
+        profile_disabled: HashSet<VoltID>,
+        permissions: Vec<(VoltID, PluginPermissions)>,
         common: CommonData,
     ) -> Self {
         let installed = create_rw_signal(cx, IndexMap::new());
@@ -115,12 +128,16 @@ pub fn new(
         };
         let disabled = create_rw_signal(cx, disabled);
         let workspace_disabled = create_rw_signal(cx, workspace_disabled);
+        let profile_disabled = create_rw_signal(cx, profile_disabled);
+        let permissions = create_rw_signal(cx, permissions.into_iter().collect());
 
         let plugin = Self {
             installed,
             all,
             disabled,
             workspace_disabled,
+            profile_disabled,
+            permissions,
             common,
         };
 
@@ -208,6 +225,34 @@ pub fn volt_installed(&self, volt: &VoltMetadata, icon: &Option<Vec<u8>>) {
             })
             .unwrap();
 
+        let installing = self.all.volts.with_untracked(|volts| {
+            volts
+                .get(&volt_id)
+                .map(|volt| volt.installing.get_untracked())
+                .unwrap_or(false)
+        });
+        if installing && (volt.wasm.is_some() || volt.grammars.is_some()) {
+            self.all.volts.with_untracked(|volts| {
+                if let Some(volt) = volts.get(&volt_id) {
+                    volt.installing.set(false);
+                }
+            });
+            // A new plugin starts without permissions until they're reviewed,
+            // and an updated one keeps the ones it was granted
+            let granted = self
+                .permissions
+                .try_update(|permissions| {
+                    permissions.entry(volt_id.clone()).or_default().clone()
+                })
+                .unwrap();
+            self.save_permissions();
+            if !granted.covers(&volt.requested_permissions()) {
+                self.common.internal_command.send(
+                    InternalCommand::ReviewPluginPermissions { volt: volt.clone() },
+                );
+            }
+        }
+
         if !is_latest {
             let url = format!(
                 "https://plugins.lapce.dev/api/v1/plugins/{}/{}/latest",
@@ -220,7 +265,7 @@ pub fn volt_installed(&self, volt: &VoltMetadata, icon: &Option<Vec<u8>>) {
             });
             std::thread::spawn(move || {
                 let info: Option<VoltInfo> =
-                    reqwest::blocking::get(url).ok().and_then(|r| r.json().ok());
+                    network::get(url).ok().and_then(|r| r.json().ok());
                 send(info);
             });
         }
@@ -231,6 +276,7 @@ pub fn volt_removed(&self, volt: &VoltInfo) {
         self.installed.update(|installed| {
             installed.remove(&id);
         });
+        self.remove_plugin_registrations(&id);
 
         if self.disabled.with_untracked(|d| d.contains(&id)) {
             self.disabled.update(|d| {
@@ -303,7 +349,7 @@ fn query_volts(query: &str, offset: usize) -> Result<VoltsInfo> {
         let url = format!(
             "https://plugins.lapce.dev/api/v1/plugins?q={query}&offset={offset}"
         );
-        let plugins: VoltsInfo = reqwest::blocking::get(url)?.json()?;
+        let plugins: VoltsInfo = network::get(url)?.json()?;
         Ok(plugins)
     }
 
@@ -326,6 +372,19 @@ pub fn load_more_available(&self) {
         self.load_available_volts(&query, offset);
     }
 
+    /// Replace the query of the available plugins list, which reloads it.
+    pub fn search_available(&self, query: &str) {
+        self.all
+            .query_editor
+            .view
+            .doc
+            .update(|doc| doc.reload(Rope::from(query), true));
+        self.all
+            .query_editor
+            .cursor
+            .update(|cursor| cursor.set_insert(Selection::caret(query.len())));
+    }
+
     pub fn install_volt(&self, info: VoltInfo) {
         self.all.volts.with_untracked(|volts| {
             if let Some(volt) = volts.get(&info.id()) {
@@ -356,6 +415,35 @@ pub fn install_volt(&self, info: VoltInfo) {
     pub fn plugin_disabled(&self, id: &VoltID) -> bool {
         self.disabled.with_untracked(|d| d.contains(id))
             || self.workspace_disabled.with_untracked(|d| d.contains(id))
+            || self.profile_disabled.with_untracked(|d| d.contains(id))
+    }
+
+    /// Switch to the plugins disabled by a newly active profile, enabling and
+    /// disabling the installed plugins whose state changes.
+    pub fn set_profile_disabled(&self, profile_disabled: HashSet<VoltID>) {
+        let old = self.profile_disabled.get_untracked();
+        if old == profile_disabled {
+            return;
+        }
+        let was_disabled: HashSet<VoltID> = old
+            .symmetric_difference(&profile_disabled)
+            .filter(|id| self.plugin_disabled(id))
+            .cloned()
+            .collect();
+        self.profile_disabled.set(profile_disabled.clone());
+
+        let installed = self.installed.get_untracked();
+        for id in old.symmetric_difference(&profile_disabled) {
+            let Some(volt) = installed.get(id) else {
+                continue;
+            };
+            let info = volt.meta.get_untracked().info();
+            match (was_disabled.contains(id), self.plugin_disabled(id)) {
+                (true, false) => self.common.proxy.enable_volt(info),
+                (false, true) => self.common.proxy.disable_volt(info),
+                _ => {}
+            }
+        }
     }
 
     pub fn enable_volt(&self, volt: VoltInfo) {
@@ -368,16 +456,19 @@ pub fn enable_volt(&self, volt: VoltInfo) {
         }
         let db: Arc<LapceDb> = use_context(self.common.scope).unwrap();
         db.save_disabled_volts(self.disabled.get_untracked().into_iter().collect());
+        self.reload_volt_contributions();
     }
 
     pub fn disable_volt(&self, volt: VoltInfo) {
         let id = volt.id();
+        self.remove_plugin_registrations(&id);
         self.disabled.update(|d| {
             d.insert(id);
         });
         self.common.proxy.disable_volt(volt);
         let db: Arc<LapceDb> = use_context(self.common.scope).unwrap();
         db.save_disabled_volts(self.disabled.get_untracked().into_iter().collect());
+        self.reload_volt_contributions();
     }
 
     pub fn enable_volt_for_ws(&self, volt: VoltInfo) {
@@ -391,12 +482,17 @@ pub fn enable_volt_for_ws(&self, volt: VoltInfo) {
         let db: Arc<LapceDb> = use_context(self.common.scope).unwrap();
         db.save_workspace_disabled_volts(
             self.common.workspace.clone(),
-            self.disabled.get_untracked().into_iter().collect(),
+            self.workspace_disabled
+                .get_untracked()
+                .into_iter()
+                .collect(),
         );
+        self.reload_volt_contributions();
     }
 
     pub fn disable_volt_for_ws(&self, volt: VoltInfo) {
         let id = volt.id();
+        self.remove_plugin_registrations(&id);
         self.workspace_disabled.update(|d| {
             d.insert(id);
         });
@@ -404,8 +500,32 @@ pub fn disable_volt_for_ws(&self, volt: VoltInfo) {
         let db: Arc<LapceDb> = use_context(self.common.scope).unwrap();
         db.save_workspace_disabled_volts(
             self.common.workspace.clone(),
-            self.disabled.get_untracked().into_iter().collect(),
+            self.workspace_disabled
+                .get_untracked()
+                .into_iter()
+                .collect(),
         );
+        self.reload_volt_contributions();
+    }
+
+    /// Reload the config in all the windows, for the themes and grammars of
+    /// the plugins that were enabled or disabled to come or go
+    fn reload_volt_contributions(&self) {
+        self.common.window_command.send(WindowCommand::ReloadConfig);
+    }
+
+    /// Drop the panels, status bar items and commands of the plugin, which
+    /// stops before it could remove them itself
+    fn remove_plugin_registrations(&self, id: &VoltID) {
+        self.common.plugin_panels.update(|panels| {
+            panels.retain(|(volt, _), _| volt != id);
+        });
+        self.common.plugin_status_items.update(|items| {
+            items.retain(|(volt, _), _| volt != id);
+        });
+        self.common.plugin_commands.update(|commands| {
+            commands.retain(|_, (volt, _, _)| volt != id);
+        });
     }
 
     pub fn uninstall_volt(&self, volt: VoltMetadata) {
@@ -434,7 +554,55 @@ pub fn uninstall_volt(&self, volt: VoltMetadata) {
         }
     }
 
+    /// The metadata of the installed plugins that are enabled in this
+    /// workspace, which are the ones whose language servers are running
+    pub fn enabled_volts(&self) -> Vec<VoltMetadata> {
+        let disabled = self.disabled.get();
+        let workspace_disabled = self.workspace_disabled.get();
+        let profile_disabled = self.profile_disabled.get();
+        self.installed.with(|installed| {
+            installed
+                .iter()
+                .filter(|(id, _)| {
+                    !disabled.contains(*id)
+                        && !workspace_disabled.contains(*id)
+                        && !profile_disabled.contains(*id)
+                })
+                .map(|(_, volt)| volt.meta.get())
+                .collect()
+        })
+    }
+
     pub fn reload_volt(&self, volt: VoltMetadata) {
         self.common.proxy.reload_volt(volt);
     }
+
+    /// The permissions the plugin runs with
+    pub fn granted_permissions(&self, volt: &VoltMetadata) -> PluginPermissions {
+        self.permissions
+            .with_untracked(|permissions| permissions.get(&volt.id()).cloned())
+            .unwrap_or_else(|| volt.requested_permissions())
+    }
+
+    /// Grant the permissions to the plugin, which restarts it with them
+    pub fn grant_permissions(
+        &self,
+        volt: VoltMetadata,
+        permissions: PluginPermissions,
+    ) {
+        self.permissions.update(|granted| {
+            granted.insert(volt.id(), permissions.clone());
+        });
+        self.save_permissions();
+        self.common.proxy.grant_volt_permissions(volt, permissions);
+        // The grammars are loaded only if the plugin may load native code
+        self.reload_volt_contributions();
+    }
+
+    fn save_permissions(&self) {
+        let db: Arc<LapceDb> = use_context(self.common.scope).unwrap();
+        db.save_plugin_permissions(
+            self.permissions.get_untracked().into_iter().collect(),
+        );
+    }
 }
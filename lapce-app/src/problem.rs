@@ -0,0 +1,206 @@
+use std::path::PathBuf;
+
+use floem::reactive::{
+    create_effect, create_memo, create_rw_signal, Memo, RwSignal, Scope, SignalGet,
+    SignalGetUntracked, SignalSet, SignalWith,
+};
+use lapce_core::{command::FocusCommand, mode::Mode};
+use lsp_types::DiagnosticSeverity;
+
+use crate::{
+    command::{CommandExecuted, CommandKind, InternalCommand},
+    doc::EditorDiagnostic,
+    editor::{
+        location::{EditorLocation, EditorPosition},
+        EditorData,
+    },
+    id::EditorId,
+    keypress::{condition::Condition, KeyPressFocus},
+    main_split::MainSplitData,
+    window_tab::{CommonData, Focus},
+};
+
+#[derive(Clone)]
+pub struct ProblemData {
+    /// The text input used to filter diagnostics by message or file path
+    pub filter: EditorData,
+    pub show_errors: RwSignal<bool>,
+    pub show_warnings: RwSignal<bool>,
+    /// Whether information and hint diagnostics are shown
+    pub show_infos: RwSignal<bool>,
+    /// The diagnostics that pass the filters, grouped by file
+    pub items: Memo<im::Vector<(PathBuf, im::Vector<EditorDiagnostic>)>>,
+    /// The index of the selected diagnostic, counted across all files
+    pub active: RwSignal<usize>,
+    pub main_split: MainSplitData,
+    pub common: CommonData,
+}
+
+impl KeyPressFocus for ProblemData {
+    fn get_mode(&self) -> Mode {
+        Mode::Insert
+    }
+
+    fn check_condition(&self, condition: Condition) -> bool {
+        matches!(condition, Condition::PanelFocus | Condition::ListFocus)
+    }
+
+    fn run_command(
+        &self,
+        command: &crate::command::LapceCommand,
+        count: Option<usize>,
+        mods: floem::glazier::Modifiers,
+    ) -> CommandExecuted {
+        match &command.kind {
+            CommandKind::Workbench(_) => {}
+            CommandKind::Focus(cmd) => match cmd {
+                FocusCommand::ListNext => self.next(),
+                FocusCommand::ListPrevious => self.previous(),
+                FocusCommand::ListSelect => self.select(),
+                _ => {}
+            },
+            CommandKind::Edit(_)
+            | CommandKind::Move(_)
+            | CommandKind::MultiSelection(_) => {
+                self.filter.run_command(command, count, mods);
+            }
+            CommandKind::MotionMode(_) => {}
+        }
+        CommandExecuted::Yes
+    }
+
+    fn receive_char(&self, c: &str) {
+        self.filter.receive_char(c);
+    }
+}
+
+impl ProblemData {
+    pub fn new(cx: Scope, main_split: MainSplitData, common: CommonData) -> Self {
+        let filter = EditorData::new_local(cx, EditorId::next(), common.clone());
+        let show_errors = create_rw_signal(cx, true);
+        let show_warnings = create_rw_signal(cx, true);
+        let show_infos = create_rw_signal(cx, true);
+        let active = create_rw_signal(cx, 0);
+
+        let diagnostics = main_split.diagnostics;
+        let pattern = filter.view.doc;
+        let items = create_memo(cx, move |_| {
+            let pattern =
+                pattern.with(|doc| doc.buffer().to_string().to_lowercase());
+            let show_errors = show_errors.get();
+            let show_warnings = show_warnings.get();
+            let show_infos = show_infos.get();
+
+            let mut items: im::Vector<(PathBuf, im::Vector<EditorDiagnostic>)> =
+                diagnostics
+                    .get()
+                    .into_iter()
+                    .filter_map(|(path, data)| {
+                        let path_matches =
+                            path.to_string_lossy().to_lowercase().contains(&pattern);
+                        let diagnostics: im::Vector<EditorDiagnostic> = data
+                            .diagnostics
+                            .get()
+                            .into_iter()
+                            .filter(|d| {
+                                let shown = match d.diagnostic.severity {
+                                    Some(DiagnosticSeverity::ERROR) => show_errors,
+                                    Some(DiagnosticSeverity::WARNING) => {
+                                        show_warnings
+                                    }
+                                    _ => show_infos,
+                                };
+                                shown
+                                    && (path_matches
+                                        || d.diagnostic
+                                            .message
+                                            .to_lowercase()
+                                            .contains(&pattern))
+                            })
+                            .collect();
+                        (!diagnostics.is_empty()).then_some((path, diagnostics))
+                    })
+                    .collect();
+            items.sort_by(|a, b| a.0.cmp(&b.0));
+            items
+        });
+
+        // The selection stays on the last diagnostic when the list shrinks
+        // past it
+        create_effect(cx, move |_| {
+            let len: usize =
+                items.with(|items| items.iter().map(|(_, d)| d.len()).sum());
+            if active.get_untracked() >= len {
+                active.set(len.saturating_sub(1));
+            }
+        });
+
+        Self {
+            filter,
+            show_errors,
+            show_warnings,
+            show_infos,
+            items,
+            active,
+            main_split,
+            common,
+        }
+    }
+
+    /// The number of diagnostics that pass the filters
+    pub fn len(&self) -> usize {
+        self.items
+            .with_untracked(|items| items.iter().map(|(_, d)| d.len()).sum())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn next(&self) {
+        let len = self.len();
+        if len == 0 {
+            return;
+        }
+        let active = self.active.get_untracked();
+        self.active
+            .set(if active + 1 >= len { 0 } else { active + 1 });
+    }
+
+    pub fn previous(&self) {
+        let len = self.len();
+        if len == 0 {
+            return;
+        }
+        let active = self.active.get_untracked().min(len - 1);
+        self.active
+            .set(if active == 0 { len - 1 } else { active - 1 });
+    }
+
+    /// Jump to the selected diagnostic and give focus back to the editor
+    pub fn select(&self) {
+        let active = self.active.get_untracked();
+        let item = self.items.with_untracked(|items| {
+            items
+                .iter()
+                .flat_map(|(path, diagnostics)| {
+                    diagnostics.iter().map(move |d| (path, d))
+                })
+                .nth(active)
+                .map(|(path, d)| (path.clone(), d.diagnostic.range.start))
+        });
+        let Some((path, position)) = item else { return };
+        self.common.focus.set(Focus::Workbench);
+        self.common
+            .internal_command
+            .send(InternalCommand::JumpToLocation {
+                location: EditorLocation {
+                    path,
+                    position: Some(EditorPosition::Position(position)),
+                    scroll_offset: None,
+                    ignore_unconfirmed: false,
+                    same_editor_tab: false,
+                },
+            });
+    }
+}
@@ -0,0 +1,87 @@
+use std::path::PathBuf;
+
+use lapce_core::directory::Directory;
+use lapce_rpc::plugin::VoltID;
+use serde::Deserialize;
+use tracing::error;
+
+/// The name shown for using no profile, in which only the user's own settings,
+/// keymaps and plugins apply
+pub const DEFAULT_PROFILE: &str = "Default";
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct ProfilePlugins {
+    /// The plugins disabled while the profile is active, as `author.name`
+    #[serde(default)]
+    disabled: Vec<String>,
+}
+
+/// A named bundle of settings, keymaps and disabled plugins, applied on top of
+/// the user's own ones. Each profile is a folder in the profiles directory
+/// which may contain a `settings.toml`, a `keymaps.toml` and a `plugins.toml`.
+pub struct Profile;
+
+impl Profile {
+    /// The names of the existing profiles, sorted
+    pub fn names() -> Vec<String> {
+        let Some(dir) = Directory::profiles_directory() else {
+            return Vec::new();
+        };
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return Vec::new();
+        };
+        let mut names: Vec<String> = entries
+            .flatten()
+            .filter(|entry| entry.path().is_dir())
+            .filter_map(|entry| entry.file_name().to_str().map(|s| s.to_string()))
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// The folder of the profile, if it exists
+    pub fn directory(name: &str) -> Option<PathBuf> {
+        let dir = Directory::profiles_directory()?.join(name);
+        dir.is_dir().then_some(dir)
+    }
+
+    pub fn exists(name: &str) -> bool {
+        Self::directory(name).is_some()
+    }
+
+    pub fn settings_file(name: &str) -> Option<PathBuf> {
+        Some(Self::directory(name)?.join("settings.toml"))
+    }
+
+    pub fn keymaps_file(name: &str) -> Option<PathBuf> {
+        Some(Self::directory(name)?.join("keymaps.toml"))
+    }
+
+    /// The plugins that the profile disables
+    pub fn disabled_volts(name: &str) -> Vec<VoltID> {
+        let Some(dir) = Self::directory(name) else {
+            return Vec::new();
+        };
+        let path = dir.join("plugins.toml");
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            return Vec::new();
+        };
+        let plugins: ProfilePlugins = toml_edit::easy::from_str(&content)
+            .unwrap_or_else(|e| {
+                error!("Failed to load from {path:?}: {e}");
+                ProfilePlugins::default()
+            });
+        plugins
+            .disabled
+            .iter()
+            .filter_map(|id| {
+                let (author, name) = id.split_once('.')?;
+                Some(VoltID {
+                    author: author.to_string(),
+                    name: name.to_string(),
+                })
+            })
+            .collect()
+    }
+}
@@ -7,10 +7,12 @@
 };
 use lapce_proxy::dispatch::Dispatcher;
 use lapce_rpc::{
-    core::{CoreHandler, CoreNotification, CoreRpcHandler},
-    plugin::VoltID,
+    core::{CoreHandler, CoreNotification, CoreRequest, CoreRpcHandler},
+    plugin::{PluginPermissions, VoltID},
+    protocol::{Protocol, ProtocolInfo},
     proxy::ProxyRpcHandler,
     terminal::TermId,
+    RequestId,
 };
 use lsp_types::Url;
 use tracing::error;
@@ -29,6 +31,8 @@
 pub struct Proxy {
     pub tx: Sender<CoreNotification>,
     pub term_tx: Sender<(TermId, TermEvent)>,
+    pub request_tx: Sender<(RequestId, CoreRequest)>,
+    pub proxy_rpc: ProxyRpcHandler,
 }
 
 #[derive(Clone)]
@@ -36,6 +40,8 @@ pub struct ProxyData {
     pub proxy_rpc: ProxyRpcHandler,
     pub core_rpc: CoreRpcHandler,
     pub notification: ReadSignal<Option<CoreNotification>>,
+    /// The requests of the proxy, which are answered through `core_rpc`
+    pub request: ReadSignal<Option<(RequestId, CoreRequest)>>,
 }
 
 impl ProxyData {
@@ -50,6 +56,7 @@ pub fn start_proxy(
     workspace: Arc<LapceWorkspace>,
     disabled_volts: Vec<VoltID>,
     plugin_configurations: HashMap<String, HashMap<String, serde_json::Value>>,
+    plugin_permissions: Vec<(VoltID, PluginPermissions)>,
     term_tx: Sender<(TermId, TermEvent)>,
 ) -> ProxyData {
     let proxy_rpc = ProxyRpcHandler::new();
@@ -63,6 +70,7 @@ pub fn start_proxy(
                 workspace.path.clone(),
                 disabled_volts,
                 plugin_configurations,
+                plugin_permissions,
                 1,
                 1,
             );
@@ -108,20 +116,29 @@ pub fn start_proxy(
     }
 
     let (tx, rx) = crossbeam_channel::unbounded();
+    let (request_tx, request_rx) = crossbeam_channel::unbounded();
     {
         let core_rpc = core_rpc.clone();
+        let proxy_rpc = proxy_rpc.clone();
         std::thread::spawn(move || {
-            let mut proxy = Proxy { tx, term_tx };
+            let mut proxy = Proxy {
+                tx,
+                term_tx,
+                request_tx,
+                proxy_rpc,
+            };
             core_rpc.mainloop(&mut proxy);
         })
     };
 
     let notification = create_signal_from_channel(cx, rx);
+    let request = create_signal_from_channel(cx, request_rx);
 
     ProxyData {
         proxy_rpc,
         core_rpc,
         notification,
+        request,
     }
 }
 
@@ -133,14 +150,22 @@ fn handle_notification(&mut self, rpc: lapce_rpc::core::CoreNotification) {
                 .send((*term_id, TermEvent::UpdateContent(content.to_vec())));
             return;
         }
+        if let CoreNotification::ProxyConnected { protocol } = &rpc {
+            // A remote proxy can be of an older version than the editor, in
+            // which case the requests it can't handle aren't sent to it
+            let protocol = ProtocolInfo::current()
+                .negotiate(protocol)
+                .unwrap_or_else(|err| {
+                    error!("Incompatible proxy: {err}");
+                    Protocol::oldest()
+                });
+            self.proxy_rpc.set_protocol(protocol);
+        }
         let _ = self.tx.send(rpc);
     }
 
-    fn handle_request(
-        &mut self,
-        _id: lapce_rpc::RequestId,
-        _rpc: lapce_rpc::core::CoreRequest,
-    ) {
+    fn handle_request(&mut self, id: RequestId, rpc: CoreRequest) {
+        let _ = self.request_tx.send((id, rpc));
     }
 }
 
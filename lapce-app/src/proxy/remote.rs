@@ -7,6 +7,7 @@
 use anyhow::{anyhow, Result};
 use flate2::read::GzDecoder;
 use lapce_core::{directory::Directory, meta};
+use lapce_proxy::network;
 use lapce_rpc::{
     core::CoreRpcHandler,
     proxy::{ProxyRpc, ProxyRpcHandler},
@@ -112,27 +113,35 @@ pub fn start_remote(
         _ => format!("{remote_proxy_path}/lapce"),
     };
 
-    if !remote
+    let installed_version = remote
         .command_builder()
         .args([&remote_proxy_file, "--version"])
         .output()
-        .map(|output| {
-            if meta::VERSION == "debug" {
-                String::from_utf8_lossy(&output.stdout).starts_with("Lapce-proxy")
-            } else {
-                String::from_utf8_lossy(&output.stdout)
-                    == format!("Lapce-proxy {}", meta::VERSION)
-            }
+        .ok()
+        .map(|output| String::from_utf8_lossy(&output.stdout).to_string())
+        .filter(|version| version.starts_with("Lapce-proxy"));
+    let up_to_date = installed_version
+        .as_ref()
+        .map(|version| {
+            meta::VERSION == "debug"
+                || *version == format!("Lapce-proxy {}", meta::VERSION)
         })
-        .unwrap_or(false)
-    {
-        download_remote(
+        .unwrap_or(false);
+    if !up_to_date {
+        if let Err(err) = download_remote(
             &remote,
             &platform,
             &architecture,
             &remote_proxy_path,
             &remote_proxy_file,
-        )?;
+        ) {
+            // An older proxy still works, with the capabilities it has, when
+            // the one of this version can't be installed
+            let Some(version) = installed_version else {
+                return Err(err);
+            };
+            error!("Failed to update the remote proxy from {version}: {err}");
+        }
     };
 
     debug!("remote proxy path: {remote_proxy_path}");
@@ -326,7 +335,7 @@ fn download_remote(
             let proxy_version = meta::VERSION;
             let url = format!("https://github.com/lapce/lapce/releases/download/{proxy_version}/{proxy_filename}.gz");
             debug!("proxy download URI: {url}");
-            let mut resp = reqwest::blocking::get(url).expect("request failed");
+            let mut resp = network::get(url)?;
             if resp.status().is_success() {
                 let mut out = std::fs::File::create(&local_proxy_file)
                     .expect("failed to create file");
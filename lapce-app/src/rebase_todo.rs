@@ -0,0 +1,189 @@
+use std::path::Path;
+
+use lapce_rpc::style::{LineStyle, Style};
+
+/// The file git asks the editor to edit for an interactive rebase
+const TODO_FILE: &str = "git-rebase-todo";
+
+/// The commands of a todo list that don't act on a commit, with their short
+/// forms, and whether they take an argument
+const OTHER_COMMANDS: &[(&str, &str, bool)] = &[
+    ("exec", "x", true),
+    ("break", "b", false),
+    ("label", "l", true),
+    ("reset", "t", true),
+    ("merge", "m", true),
+    ("update-ref", "u", true),
+];
+
+pub fn is_rebase_todo(path: &Path) -> bool {
+    path.file_name().and_then(|name| name.to_str()) == Some(TODO_FILE)
+}
+
+/// What an interactive rebase does with a commit
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RebaseAction {
+    Pick,
+    Reword,
+    Edit,
+    Squash,
+    Fixup,
+    Drop,
+}
+
+impl RebaseAction {
+    const ALL: [RebaseAction; 6] = [
+        RebaseAction::Pick,
+        RebaseAction::Reword,
+        RebaseAction::Edit,
+        RebaseAction::Squash,
+        RebaseAction::Fixup,
+        RebaseAction::Drop,
+    ];
+
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            RebaseAction::Pick => "pick",
+            RebaseAction::Reword => "reword",
+            RebaseAction::Edit => "edit",
+            RebaseAction::Squash => "squash",
+            RebaseAction::Fixup => "fixup",
+            RebaseAction::Drop => "drop",
+        }
+    }
+
+    /// Parses the command of a todo line, in its full or short form
+    fn parse(command: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| {
+            command == action.keyword() || command == &action.keyword()[..1]
+        })
+    }
+
+    /// Whether the commit is melded into the one before it
+    fn is_melded(&self) -> bool {
+        matches!(self, RebaseAction::Squash | RebaseAction::Fixup)
+    }
+}
+
+/// Splits a todo line into its command and the rest, with the offset of the
+/// rest. Blank lines and comments have no command.
+fn split_command(line: &str) -> Option<(&str, usize, &str)> {
+    let trimmed = line.trim_start();
+    if trimmed.is_empty() || trimmed.starts_with('#') {
+        return None;
+    }
+    let end = trimmed.find(char::is_whitespace).unwrap_or(trimmed.len());
+    let rest = trimmed[end..].trim_start();
+    Some((&trimmed[..end], line.len() - rest.len(), rest.trim_end()))
+}
+
+/// The line with the action of its commit changed, if it's a line acting on a
+/// commit. The flags of `fixup` are dropped when changing it to another action.
+pub fn set_action(line: &str, action: RebaseAction) -> Option<String> {
+    let (command, _, rest) = split_command(line)?;
+    let current = RebaseAction::parse(command)?;
+    let rest = if current == RebaseAction::Fixup {
+        rest.strip_prefix("-C ")
+            .or_else(|| rest.strip_prefix("-c "))
+            .map(|rest| rest.trim_start())
+            .unwrap_or(rest)
+    } else {
+        rest
+    };
+    Some(format!("{} {rest}", action.keyword()))
+}
+
+/// The problems in a todo list that would make git reject it, with the line
+/// they are on.
+pub fn validate(text: &str) -> Vec<(usize, String)> {
+    let mut problems = Vec::new();
+    let mut has_commit = false;
+    for (i, line) in text.lines().enumerate() {
+        let Some((command, _, rest)) = split_command(line) else {
+            continue;
+        };
+        if let Some(action) = RebaseAction::parse(command) {
+            if rest.is_empty() {
+                problems.push((i, format!("`{command}` needs a commit")));
+            } else if action.is_melded() && !has_commit {
+                problems.push((
+                    i,
+                    format!("cannot `{command}` without a previous commit"),
+                ));
+            } else if action != RebaseAction::Drop {
+                has_commit = true;
+            }
+        } else if let Some((name, _, needs_argument)) = OTHER_COMMANDS
+            .iter()
+            .find(|(name, short, _)| command == *name || command == *short)
+        {
+            if *needs_argument && rest.is_empty() {
+                problems.push((i, format!("`{name}` needs an argument")));
+            }
+        } else {
+            problems.push((i, format!("unknown command `{command}`")));
+        }
+    }
+    problems
+}
+
+/// The highlighting of a line of a todo list, being its command, the commit
+/// it acts on and the comments git adds as instructions.
+pub fn line_styles(content: &str) -> Vec<LineStyle> {
+    let content = content.trim_end_matches(['\r', '\n']);
+    let style = |start: usize, end: usize, fg_color: &str| LineStyle {
+        start,
+        end,
+        style: Style {
+            fg_color: Some(fg_color.to_string()),
+        },
+    };
+    if content.trim_start().starts_with('#') {
+        return vec![style(0, content.len(), "comment")];
+    }
+    let Some((command, rest_start, rest)) = split_command(content) else {
+        return Vec::new();
+    };
+    let command_start = content.len() - content.trim_start().len();
+    let mut styles = vec![style(
+        command_start,
+        command_start + command.len(),
+        "keyword",
+    )];
+    if RebaseAction::parse(command).is_some() {
+        let commit = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        styles.push(style(rest_start, rest_start + commit, "constant"));
+    }
+    styles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_action() {
+        let line = "pick 1a2b3c4 Add a feature";
+        assert_eq!(
+            set_action(line, RebaseAction::Fixup).as_deref(),
+            Some("fixup 1a2b3c4 Add a feature")
+        );
+        assert_eq!(
+            set_action("f -C 1a2b3c4 Fix", RebaseAction::Reword).as_deref(),
+            Some("reword 1a2b3c4 Fix")
+        );
+        assert_eq!(set_action("exec make", RebaseAction::Drop), None);
+        assert_eq!(set_action("# pick 1a2b3c4", RebaseAction::Drop), None);
+    }
+
+    #[test]
+    fn test_validate() {
+        let text = "pick 1a2b3c4 One\ns 5d6e7f8 Two\nexec make\n\n# comment";
+        assert!(validate(text).is_empty());
+
+        let text = "drop 1a2b3c4 One\nfixup 5d6e7f8 Two\npock 9a8b7c6\nl";
+        let lines: Vec<usize> =
+            validate(text).into_iter().map(|(line, _)| line).collect();
+        assert_eq!(lines, vec![1, 2, 3]);
+    }
+}
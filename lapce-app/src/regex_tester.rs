@@ -0,0 +1,433 @@
+use std::{cell::Cell, rc::Rc, time::Duration};
+
+use floem::{
+    ext_event::create_ext_action,
+    reactive::{
+        create_effect, create_rw_signal, Memo, RwSignal, Scope, SignalGet,
+        SignalGetUntracked, SignalSet, SignalUpdate, SignalWith,
+        SignalWithUntracked,
+    },
+};
+use lapce_core::{
+    buffer::rope_text::RopeText, command::FocusCommand, mode::Mode,
+    selection::Selection,
+};
+use regex::RegexBuilder;
+
+use crate::{
+    command::{CommandExecuted, CommandKind, InternalCommand},
+    editor::{
+        location::{EditorLocation, EditorPosition},
+        EditorData,
+    },
+    id::EditorId,
+    keypress::{condition::Condition, KeyPressFocus},
+    main_split::MainSplitData,
+    panel::kind::PanelKind,
+    timer::exec_after,
+    window_tab::{CommonData, Focus},
+};
+
+/// How many matches are listed at most
+const MAX_MATCHES: usize = 1000;
+/// How much of the active editor's text the pattern is tried on, so that
+/// typing the pattern stays responsive in a big file
+const MAX_TESTED_LEN: usize = 1024 * 1024;
+/// How long the pattern and the text have to stay the same before the
+/// pattern is tried on the text
+const TEST_DELAY: Duration = Duration::from_millis(150);
+
+/// A match of the tested pattern
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RegexTestMatch {
+    /// The offset of the match in the text it's in
+    pub offset: usize,
+    /// The line the match starts on, from 0
+    pub line: usize,
+    pub line_content: String,
+    /// The byte range of the match in its line, up to the end of the line
+    pub start: usize,
+    pub end: usize,
+    /// The capture groups of the pattern, by name or number, and what each
+    /// captured if it took part in the match
+    pub groups: Vec<(String, Option<String>)>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RegexTestResult {
+    /// Why the pattern isn't a valid regex
+    pub error: Option<String>,
+    pub matches: im::Vector<RegexTestMatch>,
+    /// Whether there were more matches than are listed
+    pub truncated: bool,
+}
+
+/// A playground for trying out a regex on the text of the active editor or
+/// on a sample text, before searching or replacing with it
+#[derive(Clone)]
+pub struct RegexTesterData {
+    pub pattern: EditorData,
+    pub sample: EditorData,
+    /// Whether the pattern is tried on the sample text rather than on the
+    /// active editor's
+    pub use_sample: RwSignal<bool>,
+    pub sample_focus: RwSignal<bool>,
+    pub case_sensitive: RwSignal<bool>,
+    /// The matches of the pattern, worked out in the background while the
+    /// panel is shown
+    pub result: RwSignal<RegexTestResult>,
+    /// The index of the selected match
+    pub active: RwSignal<usize>,
+    pub main_split: MainSplitData,
+    pub common: CommonData,
+}
+
+impl KeyPressFocus for RegexTesterData {
+    fn get_mode(&self) -> Mode {
+        Mode::Insert
+    }
+
+    fn check_condition(&self, condition: Condition) -> bool {
+        matches!(condition, Condition::PanelFocus | Condition::ListFocus)
+    }
+
+    fn run_command(
+        &self,
+        command: &crate::command::LapceCommand,
+        count: Option<usize>,
+        mods: floem::glazier::Modifiers,
+    ) -> CommandExecuted {
+        match &command.kind {
+            CommandKind::Workbench(_) => {}
+            CommandKind::Focus(cmd) => match cmd {
+                FocusCommand::ListNext => self.next(),
+                FocusCommand::ListPrevious => self.previous(),
+                FocusCommand::ListSelect => self.select(),
+                _ => {}
+            },
+            CommandKind::Edit(_)
+            | CommandKind::Move(_)
+            | CommandKind::MultiSelection(_) => {
+                self.focused_input().run_command(command, count, mods);
+            }
+            CommandKind::MotionMode(_) => {}
+        }
+        CommandExecuted::Yes
+    }
+
+    fn receive_char(&self, c: &str) {
+        self.focused_input().receive_char(c);
+    }
+}
+
+impl RegexTesterData {
+    pub fn new(
+        cx: Scope,
+        main_split: MainSplitData,
+        common: CommonData,
+        shown: Memo<bool>,
+    ) -> Self {
+        let pattern = EditorData::new_local(cx, EditorId::next(), common.clone());
+        let sample = EditorData::new_local(cx, EditorId::next(), common.clone());
+        let use_sample = create_rw_signal(cx, false);
+        let sample_focus = create_rw_signal(cx, false);
+        let case_sensitive = create_rw_signal(cx, true);
+        let active = create_rw_signal(cx, 0);
+
+        let pattern_doc = pattern.view.doc;
+        let sample_doc = sample.view.doc;
+        let active_editor = main_split.active_editor;
+        let result = create_rw_signal(cx, RegexTestResult::default());
+
+        // The pattern is only tried once it and the text stop changing, and
+        // the results of an older test that's finished late are dropped
+        let test_id = Rc::new(Cell::new(0u64));
+        create_effect(cx, move |_| {
+            if !shown.get() {
+                return;
+            }
+            pattern_doc.with(|doc| doc.rev());
+            case_sensitive.track();
+            if use_sample.get() {
+                sample_doc.with(|doc| doc.rev());
+            } else if let Some(editor) = active_editor.get() {
+                let doc = editor.with(|editor| editor.view.doc);
+                doc.with(|doc| doc.rev());
+            }
+
+            let id = test_id.get() + 1;
+            test_id.set(id);
+            let test_id = test_id.clone();
+            exec_after(cx, TEST_DELAY, move || {
+                if test_id.get() != id {
+                    return;
+                }
+                let pattern =
+                    pattern_doc.with_untracked(|doc| doc.buffer().to_string());
+                if pattern.is_empty() {
+                    result.set(RegexTestResult::default());
+                    return;
+                }
+                let case_sensitive = case_sensitive.get_untracked();
+                let text = if use_sample.get_untracked() {
+                    sample_doc.with_untracked(|doc| doc.buffer().to_string())
+                } else {
+                    let Some(editor) = active_editor.get_untracked() else {
+                        result.set(RegexTestResult::default());
+                        return;
+                    };
+                    let doc = editor.with_untracked(|editor| editor.view.doc);
+                    doc.with_untracked(|doc| {
+                        let buffer = doc.buffer();
+                        let end = if buffer.len() > MAX_TESTED_LEN {
+                            buffer.offset_of_line(
+                                buffer.line_of_offset(MAX_TESTED_LEN),
+                            )
+                        } else {
+                            buffer.len()
+                        };
+                        buffer.slice_to_cow(0..end).to_string()
+                    })
+                };
+                let send = create_ext_action(cx, move |tested| {
+                    if test_id.get() == id {
+                        result.set(tested);
+                    }
+                });
+                std::thread::spawn(move || {
+                    send(test_regex(&pattern, case_sensitive, &text));
+                });
+            });
+        });
+
+        Self {
+            pattern,
+            sample,
+            use_sample,
+            sample_focus,
+            case_sensitive,
+            result,
+            active,
+            main_split,
+            common,
+        }
+    }
+
+    /// The input the typing goes to
+    fn focused_input(&self) -> &EditorData {
+        if self.use_sample.get_untracked() && self.sample_focus.get_untracked() {
+            &self.sample
+        } else {
+            &self.pattern
+        }
+    }
+
+    pub fn pattern_text(&self) -> String {
+        self.pattern
+            .view
+            .doc
+            .with_untracked(|doc| doc.buffer().to_string())
+    }
+
+    fn len(&self) -> usize {
+        self.result.with_untracked(|result| result.matches.len())
+    }
+
+    pub fn next(&self) {
+        let len = self.len();
+        if len == 0 {
+            return;
+        }
+        let active = self.active.get_untracked();
+        let next = if active + 1 >= len { 0 } else { active + 1 };
+        self.active.set(next);
+    }
+
+    pub fn previous(&self) {
+        let len = self.len();
+        if len == 0 {
+            return;
+        }
+        let active = self.active.get_untracked().min(len - 1);
+        let previous = if active == 0 { len - 1 } else { active - 1 };
+        self.active.set(previous);
+    }
+
+    pub fn select(&self) {
+        let active = self.active.get_untracked();
+        let m = self
+            .result
+            .with_untracked(|result| result.matches.get(active).cloned());
+        if let Some(m) = m {
+            self.select_match(&m);
+        }
+    }
+
+    /// Show the match in the text it's in, selecting it in the sample text,
+    /// or jumping to it in the active editor
+    pub fn select_match(&self, m: &RegexTestMatch) {
+        let len = m.end.saturating_sub(m.start);
+        if self.use_sample.get_untracked() {
+            self.sample.cursor.update(|cursor| {
+                cursor.set_insert(Selection::region(m.offset, m.offset + len))
+            });
+            self.sample_focus.set(true);
+            return;
+        }
+
+        let Some(editor) = self.main_split.active_editor.get_untracked() else {
+            return;
+        };
+        let path = editor.with_untracked(|editor| {
+            editor
+                .view
+                .doc
+                .with_untracked(|doc| doc.content.path().cloned())
+        });
+        let Some(path) = path else { return };
+        self.common
+            .internal_command
+            .send(InternalCommand::JumpToLocation {
+                location: EditorLocation {
+                    path,
+                    position: Some(EditorPosition::Offset(m.offset)),
+                    scroll_offset: None,
+                    ignore_unconfirmed: false,
+                    same_editor_tab: false,
+                },
+            });
+    }
+
+    /// Search the active editor with the pattern as a regex, in the find bar,
+    /// or search and replace with it
+    pub fn use_in_find(&self, replace: bool) {
+        let pattern = self.pattern_text();
+        if pattern.is_empty() {
+            return;
+        }
+        let find = &self.common.find;
+        find.is_regex.set(true);
+        self.main_split.set_find_pattern(Some(pattern));
+        if replace {
+            find.replace_active.set(true);
+        }
+        find.replace_focus.set(replace);
+        find.visual.set(true);
+        self.common.focus.set(Focus::Workbench);
+        if let Some(editor) = self.main_split.active_editor.get_untracked() {
+            editor.get_untracked().find_focus.set(true);
+        }
+    }
+
+    /// Search the workspace with the pattern as a regex
+    pub fn use_in_global_search(&self) {
+        let pattern = self.pattern_text();
+        if pattern.is_empty() {
+            return;
+        }
+        self.common.find.is_regex.set(true);
+        self.common
+            .internal_command
+            .send(InternalCommand::GlobalSearch { pattern });
+    }
+
+    pub fn is_focused(&self) -> bool {
+        self.common.focus.get() == Focus::Panel(PanelKind::RegexTester)
+    }
+}
+
+/// The matches of the pattern in the text, with the groups each captured
+pub fn test_regex(
+    pattern: &str,
+    case_sensitive: bool,
+    text: &str,
+) -> RegexTestResult {
+    let regex = match RegexBuilder::new(pattern)
+        .case_insensitive(!case_sensitive)
+        .multi_line(true)
+        .build()
+    {
+        Ok(regex) => regex,
+        Err(err) => {
+            return RegexTestResult {
+                error: Some(err.to_string()),
+                ..Default::default()
+            };
+        }
+    };
+    let names: Vec<String> = regex
+        .capture_names()
+        .enumerate()
+        .skip(1)
+        .map(|(i, name)| name.map_or_else(|| i.to_string(), str::to_string))
+        .collect();
+
+    let mut result = RegexTestResult::default();
+    // The line of the last match, which the next one is on or after
+    let mut line = 0;
+    let mut line_start = 0;
+    for captures in regex.captures_iter(text) {
+        if result.matches.len() >= MAX_MATCHES {
+            result.truncated = true;
+            break;
+        }
+        let Some(m) = captures.get(0) else { continue };
+        let from = line_start;
+        for (i, _) in text[from..m.start()].match_indices('\n') {
+            line += 1;
+            line_start = from + i + 1;
+        }
+        let line_end = text[line_start..]
+            .find('\n')
+            .map(|i| line_start + i)
+            .unwrap_or(text.len());
+        let groups = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                let captured = captures.get(i + 1).map(|c| c.as_str().to_string());
+                (name.clone(), captured)
+            })
+            .collect();
+        result.matches.push_back(RegexTestMatch {
+            offset: m.start(),
+            line,
+            line_content: text[line_start..line_end].to_string(),
+            start: m.start() - line_start,
+            end: m.end().min(line_end) - line_start,
+            groups,
+        });
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_test_regex() {
+        let text = "let a = 1;\nlet Bc = 22;\n";
+        let result = test_regex(r"let (?P<name>\w+) = (\d+)", true, text);
+        assert_eq!(result.error, None);
+        assert_eq!(result.matches.len(), 2);
+        let m = &result.matches[1];
+        assert_eq!(m.line, 1);
+        assert_eq!(m.line_content, "let Bc = 22;");
+        assert_eq!((m.start, m.end), (0, 11));
+        assert_eq!(m.offset, 11);
+        assert_eq!(
+            m.groups,
+            vec![
+                ("name".to_string(), Some("Bc".to_string())),
+                ("2".to_string(), Some("22".to_string())),
+            ]
+        );
+
+        let result = test_regex("bc", false, text);
+        assert_eq!(result.matches.len(), 1);
+        assert!(test_regex("bc", true, text).matches.is_empty());
+
+        assert!(test_regex("(", true, text).error.is_some());
+    }
+}
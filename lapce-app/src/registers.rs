@@ -0,0 +1,157 @@
+use floem::reactive::{
+    create_rw_signal, RwSignal, Scope, SignalGetUntracked, SignalSet, SignalWith,
+};
+use lapce_core::{
+    command::FocusCommand,
+    mode::Mode,
+    register::{Clipboard, Register},
+};
+
+use crate::{
+    command::{CommandExecuted, CommandKind, LapceCommand},
+    doc::SystemClipboard,
+    keypress::{condition::Condition, KeyPressFocus},
+    window_tab::{CommonData, Focus},
+};
+
+/// How many characters of the content of a register are shown
+const PREVIEW_CHARS: usize = 200;
+
+/// The list of the registers and their content shown over the workbench,
+/// like vim's `:registers`
+#[derive(Clone)]
+pub struct RegistersData {
+    pub active: RwSignal<bool>,
+    pub common: CommonData,
+}
+
+impl KeyPressFocus for RegistersData {
+    fn get_mode(&self) -> Mode {
+        Mode::Normal
+    }
+
+    fn check_condition(&self, condition: Condition) -> bool {
+        matches!(condition, Condition::ModalFocus)
+    }
+
+    fn run_command(
+        &self,
+        command: &LapceCommand,
+        _count: Option<usize>,
+        _mods: floem::glazier::Modifiers,
+    ) -> CommandExecuted {
+        if let CommandKind::Focus(FocusCommand::ModalClose) = &command.kind {
+            self.close();
+        }
+        CommandExecuted::Yes
+    }
+
+    fn receive_char(&self, _c: &str) {}
+}
+
+impl RegistersData {
+    pub fn new(cx: Scope, common: CommonData) -> Self {
+        Self {
+            active: create_rw_signal(cx, false),
+            common,
+        }
+    }
+
+    pub fn show(&self) {
+        self.active.set(true);
+        self.common.focus.set(Focus::Registers);
+    }
+
+    pub fn close(&self) {
+        self.active.set(false);
+        if let Focus::Registers = self.common.focus.get_untracked() {
+            self.common.focus.set(Focus::Workbench);
+        }
+    }
+
+    /// The registers to list, with a preview of their content
+    pub fn entries(&self) -> Vec<(char, String)> {
+        self.common.register.with(|register| {
+            register_entries(register, &SystemClipboard {})
+                .into_iter()
+                .map(|(name, content)| (name, register_preview(&content)))
+                .collect()
+        })
+    }
+}
+
+/// The registers that have content, along with the system clipboard as the
+/// `"+` register in place of what was last written to it
+pub fn register_entries(
+    register: &Register,
+    clipboard: &impl Clipboard,
+) -> Vec<(char, String)> {
+    let mut entries: Vec<(char, String)> = register
+        .entries()
+        .into_iter()
+        .filter(|(name, _)| *name != '+')
+        .map(|(name, data)| (name, data.content.clone()))
+        .collect();
+    if let Some(content) = clipboard.get_string() {
+        if !content.is_empty() {
+            entries.push(('+', content));
+        }
+    }
+    entries
+}
+
+/// The content of a register on a single line, cut short
+pub fn register_preview(content: &str) -> String {
+    content
+        .replace('\n', "\u{21b5}")
+        .replace('\t', " ")
+        .chars()
+        .take(PREVIEW_CHARS)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use lapce_core::register::{RegisterData, RegisterKind};
+
+    use super::*;
+
+    struct TestClipboard(Option<String>);
+
+    impl Clipboard for TestClipboard {
+        fn get_string(&self) -> Option<String> {
+            self.0.clone()
+        }
+
+        fn put_string(&mut self, s: impl AsRef<str>) {
+            self.0 = Some(s.as_ref().to_string());
+        }
+    }
+
+    #[test]
+    fn test_register_entries() {
+        let mut register = Register::default();
+        register.add(
+            RegisterKind::Yank,
+            RegisterData {
+                content: "fn main() {\n}".to_string(),
+                ..Default::default()
+            },
+        );
+        let clipboard = TestClipboard(Some("copied".to_string()));
+        let entries = register_entries(&register, &clipboard);
+        assert_eq!(
+            entries,
+            vec![
+                ('"', "fn main() {\n}".to_string()),
+                ('0', "fn main() {\n}".to_string()),
+                ('+', "copied".to_string()),
+            ]
+        );
+        assert_eq!(register_preview(&entries[0].1), "fn main() {\u{21b5}}");
+
+        // An empty clipboard isn't listed
+        let entries = register_entries(&register, &TestClipboard(None));
+        assert_eq!(entries.len(), 2);
+    }
+}
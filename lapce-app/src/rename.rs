@@ -18,6 +18,7 @@
     editor::EditorData,
     id::EditorId,
     keypress::{condition::Condition, KeyPressFocus},
+    overlay::OverlayKind,
     window_tab::{CommonData, Focus},
 };
 
@@ -104,6 +105,7 @@ pub fn start(
         self.start.set(start);
         self.position.set(position);
         self.active.set(true);
+        self.common.overlays.show(OverlayKind::Rename);
         self.common.focus.set(Focus::Rename);
     }
 
@@ -120,8 +122,9 @@ fn run_focus_command(&self, cmd: &FocusCommand) -> CommandExecuted {
         CommandExecuted::Yes
     }
 
-    fn cancel(&self) {
+    pub fn cancel(&self) {
         self.active.set(false);
+        self.common.overlays.hidden(OverlayKind::Rename);
         if let Focus::Rename = self.common.focus.get_untracked() {
             self.common.focus.set(Focus::Workbench);
         }
@@ -0,0 +1,318 @@
+use std::{
+    collections::VecDeque,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use floem::reactive::{
+    create_rw_signal, RwSignal, Scope, SignalGetUntracked, SignalSet, SignalUpdate,
+    SignalWith, SignalWithUntracked,
+};
+use lapce_core::{command::FocusCommand, mode::Mode};
+use lapce_xi_rope::{Rope, RopeDelta};
+
+use crate::{
+    command::{CommandExecuted, CommandKind, LapceCommand},
+    doc::Document,
+    editor::EditorData,
+    id::EditorId,
+    keypress::{condition::Condition, KeyPressFocus},
+    timer::exec_after,
+    window_tab::{CommonData, Focus},
+};
+
+/// How many edits a recording keeps, the oldest ones being folded into the
+/// text it starts from
+const MAX_RECORDED_EDITS: usize = 10_000;
+
+/// How often the replay moves on while it plays
+const REPLAY_TICK: Duration = Duration::from_millis(30);
+
+/// How many ticks playing a whole recording takes at most
+const REPLAY_TICKS: usize = 300;
+
+/// The edits of a document, with when they were made, recorded when the
+/// `editor.record-edits` setting is on
+#[derive(Clone)]
+pub struct EditRecording {
+    /// The text before the first of the edits
+    start: Rope,
+    edits: VecDeque<(Instant, RopeDelta)>,
+}
+
+impl EditRecording {
+    pub fn new(start: Rope) -> Self {
+        Self {
+            start,
+            edits: VecDeque::new(),
+        }
+    }
+
+    pub fn record(&mut self, delta: &RopeDelta) {
+        self.edits.push_back((Instant::now(), delta.clone()));
+        if self.edits.len() > MAX_RECORDED_EDITS {
+            if let Some((_, oldest)) = self.edits.pop_front() {
+                self.start = oldest.apply(&self.start);
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.edits.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.edits.is_empty()
+    }
+
+    /// The text once the first `step` edits were made
+    pub fn text_at(&self, step: usize) -> Rope {
+        self.edits
+            .iter()
+            .take(step)
+            .fold(self.start.clone(), |text, (_, delta)| delta.apply(&text))
+    }
+
+    /// When the edit that makes the text of the step was made
+    pub fn time_at(&self, step: usize) -> Option<Instant> {
+        step.checked_sub(1)
+            .and_then(|i| self.edits.get(i))
+            .map(|(time, _)| *time)
+    }
+
+    /// The step of the text as it was at the time
+    pub fn step_at(&self, time: Instant) -> usize {
+        self.edits.partition_point(|(t, _)| *t <= time)
+    }
+
+    fn delta(&self, step: usize) -> Option<&RopeDelta> {
+        self.edits.get(step).map(|(_, delta)| delta)
+    }
+}
+
+/// A replay of the edits recorded of a document, shown over the workbench
+/// with a scrubber to go through them
+#[derive(Clone)]
+pub struct ReplayData {
+    pub active: RwSignal<bool>,
+    /// The editor the text of the current step is shown in
+    pub editor: RwSignal<EditorData>,
+    /// The recording being replayed, as it was when the replay opened
+    pub recording: RwSignal<Option<Rc<EditRecording>>>,
+    /// How many of the recorded edits make the text shown
+    pub step: RwSignal<usize>,
+    pub playing: RwSignal<bool>,
+    /// The scope of the pending tick while it plays, disposed of to stop it
+    tick: RwSignal<Option<Scope>>,
+    pub title: RwSignal<String>,
+    pub common: CommonData,
+}
+
+impl KeyPressFocus for ReplayData {
+    fn get_mode(&self) -> Mode {
+        self.editor.with_untracked(|editor| editor.get_mode())
+    }
+
+    fn check_condition(&self, condition: Condition) -> bool {
+        matches!(condition, Condition::ModalFocus | Condition::EditorFocus)
+    }
+
+    fn run_command(
+        &self,
+        command: &LapceCommand,
+        count: Option<usize>,
+        mods: floem::glazier::Modifiers,
+    ) -> CommandExecuted {
+        match &command.kind {
+            CommandKind::Focus(FocusCommand::ModalClose) => {
+                self.close();
+                CommandExecuted::Yes
+            }
+            // The text is only there to be looked at
+            CommandKind::Edit(_) => CommandExecuted::Yes,
+            _ => self
+                .editor
+                .with_untracked(|editor| editor.run_command(command, count, mods)),
+        }
+    }
+
+    fn receive_char(&self, _c: &str) {}
+}
+
+impl ReplayData {
+    pub fn new(cx: Scope, common: CommonData) -> Self {
+        let editor = EditorData::new_local(cx, EditorId::next(), common.clone());
+        Self {
+            active: create_rw_signal(cx, false),
+            editor: create_rw_signal(cx, editor),
+            recording: create_rw_signal(cx, None),
+            step: create_rw_signal(cx, 0),
+            playing: create_rw_signal(cx, false),
+            tick: create_rw_signal(cx, None),
+            title: create_rw_signal(cx, String::new()),
+            common,
+        }
+    }
+
+    /// Open the replay of the document at its last step
+    pub fn open(&self, doc: RwSignal<Document>, title: String) {
+        let (recording, language) =
+            doc.with_untracked(|doc| (doc.recording(), doc.syntax().language));
+        let (text, last) = recording
+            .as_ref()
+            .map(|r| (r.text_at(r.len()), r.len()))
+            .unwrap_or_default();
+        let editor_doc = self.editor.with_untracked(|editor| editor.view.doc);
+        editor_doc.update(|doc| {
+            doc.set_language(language);
+            doc.reload(text, true);
+        });
+        self.playing.set(false);
+        self.title.set(title);
+        self.recording.set(recording.map(Rc::new));
+        self.step.set(last);
+        self.active.set(true);
+        self.common.focus.set(Focus::Replay);
+    }
+
+    pub fn close(&self) {
+        self.stop();
+        self.active.set(false);
+        if let Focus::Replay = self.common.focus.get_untracked() {
+            self.common.focus.set(Focus::Workbench);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.recording
+            .with(|r| r.as_ref().map(|r| r.len()).unwrap_or(0))
+    }
+
+    /// Show the text of the step, going on from the text shown when it's
+    /// after it
+    pub fn set_step(&self, step: usize) {
+        let Some(recording) = self.recording.get_untracked() else {
+            return;
+        };
+        let step = step.min(recording.len());
+        let current = self.step.get_untracked();
+        let doc = self.editor.with_untracked(|editor| editor.view.doc);
+        let text = if step > current {
+            let text = doc.with_untracked(|doc| doc.buffer().text().clone());
+            (current..step)
+                .filter_map(|i| recording.delta(i))
+                .fold(text, |text, delta| delta.apply(&text))
+        } else {
+            recording.text_at(step)
+        };
+        doc.update(|doc| doc.reload(text, true));
+        self.step.set(step);
+    }
+
+    /// Go back to how the text was an hour ago, or to the start of the
+    /// recording if it's younger than that
+    pub fn step_to_hour_ago(&self) {
+        let Some(recording) = self.recording.get_untracked() else {
+            return;
+        };
+        let step = Instant::now()
+            .checked_sub(Duration::from_secs(60 * 60))
+            .map(|time| recording.step_at(time))
+            .unwrap_or(0);
+        self.set_step(step);
+    }
+
+    /// Play the edits from the current step, or from the start if it's at
+    /// the end
+    pub fn toggle_play(&self) {
+        if self.playing.get_untracked() {
+            self.stop();
+            return;
+        }
+        let len = self.len();
+        if len == 0 {
+            return;
+        }
+        if self.step.get_untracked() >= len {
+            self.set_step(0);
+        }
+        self.playing.set(true);
+        self.schedule_tick();
+    }
+
+    /// Stop playing, cancelling the pending tick
+    fn stop(&self) {
+        if let Some(cx) = self.tick.get_untracked() {
+            cx.dispose();
+            self.tick.set(None);
+        }
+        self.playing.set(false);
+    }
+
+    fn schedule_tick(&self) {
+        if let Some(cx) = self.tick.get_untracked() {
+            cx.dispose();
+        }
+        let (cx, _) = self.common.scope.run_child_scope(|cx| cx);
+        self.tick.set(Some(cx));
+        let replay = self.clone();
+        exec_after(cx, REPLAY_TICK, move || {
+            let len = replay.len();
+            let step = replay.step.get_untracked() + (len / REPLAY_TICKS).max(1);
+            replay.set_step(step);
+            if step >= len {
+                replay.stop();
+            } else {
+                replay.schedule_tick();
+            }
+        });
+    }
+}
+
+/// How long ago the time was, roughly
+pub fn time_ago(time: Instant) -> String {
+    let secs = time.elapsed().as_secs();
+    if secs < 60 {
+        format!("{secs}s ago")
+    } else if secs < 60 * 60 {
+        format!("{}m {}s ago", secs / 60, secs % 60)
+    } else {
+        format!("{}h {}m ago", secs / 3600, secs / 60 % 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lapce_xi_rope::{DeltaBuilder, Interval};
+
+    use super::*;
+
+    fn insert(text: &Rope, offset: usize, s: &str) -> RopeDelta {
+        let mut builder = DeltaBuilder::new(text.len());
+        builder.replace(Interval::new(offset, offset), Rope::from(s));
+        builder.build()
+    }
+
+    #[test]
+    fn test_edit_recording() {
+        let start = Rope::from("fn main() {}");
+        let mut recording = EditRecording::new(start.clone());
+        let first = insert(&start, 11, "\n");
+        let text = first.apply(&start);
+        recording.record(&first);
+        let second = insert(&text, 12, "    todo!();\n");
+        recording.record(&second);
+
+        assert_eq!(recording.len(), 2);
+        assert_eq!(recording.text_at(0).to_string(), "fn main() {}");
+        assert_eq!(recording.text_at(1).to_string(), "fn main() {\n}");
+        assert_eq!(
+            recording.text_at(2).to_string(),
+            "fn main() {\n    todo!();\n}"
+        );
+        assert_eq!(recording.time_at(0), None);
+        assert_eq!(recording.step_at(Instant::now()), 2);
+        let first_time = recording.time_at(1).unwrap();
+        assert!(recording.step_at(first_time) >= 1);
+    }
+}
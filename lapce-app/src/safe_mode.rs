@@ -0,0 +1,136 @@
+//! Safe mode, turned on with `--safe-mode`, starts Lapce without the user's
+//! settings, keymaps and plugins and without restoring the last session, to
+//! tell a broken config or plugin apart from a bug in Lapce. The pieces can
+//! be turned back on one at a time while Lapce runs. Until the next normal
+//! start, the session, the recent workspaces, the file frecency, the
+//! positions in files and the order of the panels aren't saved over.
+
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+use strum_macros::{Display, EnumIter};
+
+/// The pieces of the user's setup safe mode leaves out
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Display, EnumIter)]
+pub enum SafeModePart {
+    Settings,
+    Keymaps,
+    Plugins,
+}
+
+impl SafeModePart {
+    fn flag(self) -> u8 {
+        match self {
+            SafeModePart::Settings => 1,
+            SafeModePart::Keymaps => 2,
+            SafeModePart::Plugins => 4,
+        }
+    }
+}
+
+/// Whether safe mode was started, and the parts it still leaves out
+struct SafeMode {
+    active: AtomicBool,
+    /// The flags of the parts still left out
+    left_out: AtomicU8,
+}
+
+impl SafeMode {
+    const fn new() -> Self {
+        Self {
+            active: AtomicBool::new(false),
+            left_out: AtomicU8::new(0),
+        }
+    }
+
+    fn start(&self) {
+        self.active.store(true, Ordering::SeqCst);
+        self.left_out.store(
+            SafeModePart::Settings.flag()
+                | SafeModePart::Keymaps.flag()
+                | SafeModePart::Plugins.flag(),
+            Ordering::SeqCst,
+        );
+    }
+
+    fn is_active(&self) -> bool {
+        self.active.load(Ordering::SeqCst)
+    }
+
+    fn is_left_out(&self, part: SafeModePart) -> bool {
+        self.left_out.load(Ordering::SeqCst) & part.flag() != 0
+    }
+
+    fn left_out(&self) -> Vec<SafeModePart> {
+        use strum::IntoEnumIterator;
+        SafeModePart::iter()
+            .filter(|part| self.is_left_out(*part))
+            .collect()
+    }
+
+    fn turn_on(&self, part: SafeModePart) {
+        self.left_out.fetch_and(!part.flag(), Ordering::SeqCst);
+    }
+}
+
+static SAFE_MODE: SafeMode = SafeMode::new();
+
+/// Start in safe mode, with all the parts left out. This needs to happen
+/// before the config is first loaded.
+pub fn start() {
+    SAFE_MODE.start();
+}
+
+/// Whether Lapce was started in safe mode, even if all the parts have been
+/// turned back on since
+pub fn is_active() -> bool {
+    SAFE_MODE.is_active()
+}
+
+pub fn is_left_out(part: SafeModePart) -> bool {
+    SAFE_MODE.is_left_out(part)
+}
+
+/// The parts still left out
+pub fn left_out() -> Vec<SafeModePart> {
+    SAFE_MODE.left_out()
+}
+
+/// Turn the part back on. The config has to be reloaded for it to apply.
+pub fn turn_on(part: SafeModePart) {
+    SAFE_MODE.turn_on(part);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_turn_on_parts() {
+        let safe_mode = SafeMode::new();
+        assert!(!safe_mode.is_active());
+        assert!(safe_mode.left_out().is_empty());
+
+        safe_mode.start();
+        assert!(safe_mode.is_active());
+        assert_eq!(
+            safe_mode.left_out(),
+            vec![
+                SafeModePart::Settings,
+                SafeModePart::Keymaps,
+                SafeModePart::Plugins
+            ]
+        );
+
+        safe_mode.turn_on(SafeModePart::Keymaps);
+        assert!(!safe_mode.is_left_out(SafeModePart::Keymaps));
+        assert!(safe_mode.is_left_out(SafeModePart::Settings));
+        assert!(safe_mode.is_left_out(SafeModePart::Plugins));
+
+        // It stays active once all the parts are turned back on, so that the
+        // session still isn't saved over
+        safe_mode.turn_on(SafeModePart::Settings);
+        safe_mode.turn_on(SafeModePart::Plugins);
+        assert!(safe_mode.left_out().is_empty());
+        assert!(safe_mode.is_active());
+    }
+}
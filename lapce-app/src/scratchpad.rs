@@ -0,0 +1,122 @@
+use std::sync::Arc;
+
+use floem::reactive::{
+    create_effect, create_rw_signal, use_context, RwSignal, Scope,
+    SignalGetUntracked, SignalSet, SignalUpdate, SignalWith, SignalWithUntracked,
+};
+use lapce_core::{command::FocusCommand, language::LapceLanguage, mode::Mode};
+use lapce_xi_rope::Rope;
+
+use crate::{
+    command::{CommandExecuted, CommandKind, LapceCommand},
+    db::LapceDb,
+    editor::EditorData,
+    id::EditorId,
+    keypress::{condition::Condition, KeyPressFocus},
+    window_tab::{CommonData, Focus},
+};
+
+/// A notepad of the workspace for quick notes, shown over the workbench. Its
+/// text is kept in the database rather than in the workspace, and restored
+/// when the workspace is opened again.
+#[derive(Clone)]
+pub struct ScratchpadData {
+    pub active: RwSignal<bool>,
+    pub editor: RwSignal<EditorData>,
+    pub common: CommonData,
+}
+
+impl KeyPressFocus for ScratchpadData {
+    fn get_mode(&self) -> Mode {
+        self.editor.with_untracked(|editor| editor.get_mode())
+    }
+
+    fn check_condition(&self, condition: Condition) -> bool {
+        match condition {
+            // Escape only closes the scratchpad once it's back to normal mode
+            Condition::ModalFocus => {
+                !self.common.config.get_untracked().core.modal
+                    || self.get_mode() == Mode::Normal
+            }
+            Condition::EditorFocus => true,
+            _ => self
+                .editor
+                .with_untracked(|editor| editor.check_condition(condition)),
+        }
+    }
+
+    fn run_command(
+        &self,
+        command: &LapceCommand,
+        count: Option<usize>,
+        mods: floem::glazier::Modifiers,
+    ) -> CommandExecuted {
+        if let CommandKind::Focus(FocusCommand::ModalClose) = &command.kind {
+            self.close();
+            return CommandExecuted::Yes;
+        }
+        self.editor
+            .with_untracked(|editor| editor.run_command(command, count, mods))
+    }
+
+    fn expect_char(&self) -> bool {
+        self.editor.with_untracked(|editor| editor.expect_char())
+    }
+
+    fn receive_char(&self, c: &str) {
+        self.editor.with_untracked(|editor| editor.receive_char(c));
+    }
+}
+
+impl ScratchpadData {
+    pub fn new(cx: Scope, common: CommonData) -> Self {
+        let active = create_rw_signal(cx, false);
+        let editor = EditorData::new_local(cx, EditorId::next(), common.clone());
+        let doc = editor.view.doc;
+        let editor = create_rw_signal(cx, editor);
+
+        let db: Arc<LapceDb> = use_context(cx).unwrap();
+        let text = db.get_scratchpad(&common.workspace).unwrap_or_default();
+        let language = common.config.with_untracked(|config| {
+            LapceLanguage::from_name(&config.editor.scratchpad_language)
+        });
+        doc.update(|doc| {
+            if let Some(language) = language {
+                doc.set_language(language);
+            }
+            doc.reload(Rope::from(text), true);
+        });
+
+        let workspace = common.workspace.clone();
+        create_effect(cx, move |last_rev| {
+            let rev = doc.with(|doc| doc.rev());
+            if last_rev.is_some() && last_rev != Some(rev) {
+                let text = doc.with_untracked(|doc| doc.buffer().to_string());
+                db.save_scratchpad(workspace.clone(), text);
+            }
+            rev
+        });
+
+        Self {
+            active,
+            editor,
+            common,
+        }
+    }
+
+    pub fn toggle(&self) {
+        if self.active.get_untracked() {
+            self.close();
+        } else {
+            self.active.set(true);
+            self.common.focus.set(Focus::Scratchpad);
+        }
+    }
+
+    pub fn close(&self) {
+        self.active.set(false);
+        if let Focus::Scratchpad = self.common.focus.get_untracked() {
+            self.common.focus.set(Focus::Workbench);
+        }
+    }
+}
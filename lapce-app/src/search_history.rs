@@ -0,0 +1,197 @@
+use std::sync::Arc;
+
+use floem::reactive::{
+    create_rw_signal, use_context, RwSignal, Scope, SignalGetUntracked, SignalSet,
+    SignalUpdate, SignalWithUntracked,
+};
+use lapce_core::selection::Selection;
+use lapce_xi_rope::Rope;
+use serde::{Deserialize, Serialize};
+
+use crate::{db::LapceDb, editor::EditorData, workspace::LapceWorkspace};
+
+/// The number of entries kept in each of the histories
+const MAX_HISTORY_LEN: usize = 100;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SearchHistoryKind {
+    Search,
+    Replace,
+}
+
+/// The search and replace histories of a workspace as they are persisted, with
+/// the most recent entries last
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SearchHistoryInfo {
+    pub search: Vec<String>,
+    pub replace: Vec<String>,
+}
+
+/// The history of the patterns searched for and replaced with, shared by the
+/// find bar and the global search.
+#[derive(Clone)]
+pub struct SearchHistory {
+    pub search: RwSignal<im::Vector<String>>,
+    pub replace: RwSignal<im::Vector<String>>,
+    /// The entry currently shown while navigating the history, counted back
+    /// from the most recent one
+    index: RwSignal<Option<(SearchHistoryKind, usize)>>,
+    /// What was typed before navigating the history, restored when navigating
+    /// past the most recent entry
+    draft: RwSignal<String>,
+    workspace: Arc<LapceWorkspace>,
+    scope: Scope,
+}
+
+impl SearchHistory {
+    pub fn new(
+        cx: Scope,
+        workspace: Arc<LapceWorkspace>,
+        info: SearchHistoryInfo,
+    ) -> Self {
+        Self {
+            search: create_rw_signal(cx, info.search.into()),
+            replace: create_rw_signal(cx, info.replace.into()),
+            index: create_rw_signal(cx, None),
+            draft: create_rw_signal(cx, String::new()),
+            workspace,
+            scope: cx,
+        }
+    }
+
+    fn entries(&self, kind: SearchHistoryKind) -> RwSignal<im::Vector<String>> {
+        match kind {
+            SearchHistoryKind::Search => self.search,
+            SearchHistoryKind::Replace => self.replace,
+        }
+    }
+
+    /// Add an entry as the most recent one, moving it there if it's already
+    /// in the history.
+    pub fn record(&self, kind: SearchHistoryKind, entry: &str) {
+        self.index.set(None);
+        let mut entries = self.entries(kind).get_untracked();
+        if add_entry(&mut entries, entry) {
+            self.entries(kind).set(entries);
+            self.save();
+        }
+    }
+
+    /// Replace the content of `editor` with the previous or next entry of the
+    /// history.
+    pub fn navigate(
+        &self,
+        kind: SearchHistoryKind,
+        editor: &EditorData,
+        previous: bool,
+    ) {
+        let entries = self.entries(kind).get_untracked();
+        if entries.is_empty() {
+            return;
+        }
+        let index = match self.index.get_untracked() {
+            Some((k, index)) if k == kind => Some(index),
+            _ => None,
+        };
+        let Some(new_index) = navigate_index(index, previous, entries.len()) else {
+            return;
+        };
+        if index.is_none() {
+            let draft = editor
+                .view
+                .doc
+                .with_untracked(|doc| doc.buffer().to_string());
+            self.draft.set(draft);
+        }
+        self.index.set(new_index.map(|index| (kind, index)));
+
+        let content = match new_index {
+            Some(index) => entries[entries.len() - 1 - index].clone(),
+            None => self.draft.get_untracked(),
+        };
+        let len = content.len();
+        editor
+            .view
+            .doc
+            .update(|doc| doc.reload(Rope::from(content), true));
+        editor
+            .cursor
+            .update(|cursor| cursor.set_insert(Selection::caret(len)));
+    }
+
+    fn save(&self) {
+        let info = SearchHistoryInfo {
+            search: self.search.get_untracked().into_iter().collect(),
+            replace: self.replace.get_untracked().into_iter().collect(),
+        };
+        let db: Arc<LapceDb> = use_context(self.scope).unwrap();
+        db.save_search_history(self.workspace.clone(), info);
+    }
+}
+
+/// Add the entry as the most recent one, moving it there if it's already in
+/// the history. Returns whether the history changed.
+fn add_entry(entries: &mut im::Vector<String>, entry: &str) -> bool {
+    if entry.is_empty() || entries.last().map(|e| e.as_str()) == Some(entry) {
+        return false;
+    }
+    entries.retain(|e| e != entry);
+    entries.push_back(entry.to_string());
+    while entries.len() > MAX_HISTORY_LEN {
+        entries.pop_front();
+    }
+    true
+}
+
+/// The entry to show after navigating from `index` through a history of `len`
+/// entries, counted back from the most recent one, or `Some(None)` for the
+/// draft. `None` if there's nowhere to go.
+fn navigate_index(
+    index: Option<usize>,
+    previous: bool,
+    len: usize,
+) -> Option<Option<usize>> {
+    match (index, previous) {
+        (None, false) => None,
+        (None, true) => Some(Some(0)),
+        (Some(index), true) => Some(Some((index + 1).min(len - 1))),
+        (Some(0), false) => Some(None),
+        (Some(index), false) => Some(Some(index - 1)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_entry() {
+        let mut entries = im::Vector::new();
+        assert!(add_entry(&mut entries, "foo"));
+        assert!(add_entry(&mut entries, "bar"));
+        assert!(!add_entry(&mut entries, "bar"));
+        assert!(!add_entry(&mut entries, ""));
+        // An entry searched for again moves to the end
+        assert!(add_entry(&mut entries, "foo"));
+        assert_eq!(entries, im::vector!["bar".to_string(), "foo".to_string()]);
+
+        for i in 0..MAX_HISTORY_LEN {
+            add_entry(&mut entries, &i.to_string());
+        }
+        assert_eq!(entries.len(), MAX_HISTORY_LEN);
+        assert_eq!(entries.front().unwrap(), "0");
+    }
+
+    #[test]
+    fn test_navigate_index() {
+        // Nothing comes after the draft
+        assert_eq!(navigate_index(None, false, 3), None);
+        assert_eq!(navigate_index(None, true, 3), Some(Some(0)));
+        assert_eq!(navigate_index(Some(0), true, 3), Some(Some(1)));
+        // It stops at the oldest entry
+        assert_eq!(navigate_index(Some(2), true, 3), Some(Some(2)));
+        assert_eq!(navigate_index(Some(2), false, 3), Some(Some(1)));
+        // Past the most recent entry is the draft again
+        assert_eq!(navigate_index(Some(0), false, 3), Some(None));
+    }
+}
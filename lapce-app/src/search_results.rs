@@ -0,0 +1,312 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+};
+
+use indexmap::IndexMap;
+use lapce_rpc::proxy::{LineEdit, SearchMatch};
+
+/// The separator between the line number and the content of a result line
+const SEPARATOR: &str = ": ";
+/// The separator of a result line whose edit wasn't applied, because the line
+/// changed in the file in the meantime
+pub const CONFLICT_SEPARATOR: &str = "! ";
+
+/// The results of a global search laid out as an editable buffer. Each file
+/// has a header with its path, followed by its matching lines indented and
+/// prefixed by their line number:
+///
+/// ```text
+/// src/main.rs
+///   3: fn main() {
+///  12:     main_loop();
+/// ```
+///
+/// Editing the content of a result line and saving writes the line back to the
+/// file. The results of long lines only have the part around their matches,
+/// which is all that's written back.
+#[derive(Clone, Default)]
+pub struct SearchResultsBuffer {
+    workspace: Option<PathBuf>,
+    /// The part of the lines of each file the result lines show, by line
+    /// number, as the file had them when last read or written
+    files: IndexMap<PathBuf, BTreeMap<usize, LinePart>>,
+}
+
+/// The part of a line of a file a result line shows
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct LinePart {
+    /// The column of the line the content starts at
+    start: usize,
+    content: String,
+}
+
+impl LinePart {
+    fn end(&self) -> usize {
+        self.start + self.content.len()
+    }
+
+    /// Take in the part of the line around another match of it, if the parts
+    /// overlap or touch. The result line only shows the first part otherwise.
+    fn merge(&mut self, other: LinePart) {
+        if other.start < self.start || other.start > self.end() {
+            return;
+        }
+        if let Some(rest) = other.content.get(self.end() - other.start..) {
+            self.content.push_str(rest);
+        }
+    }
+}
+
+/// A result line of the buffer, identified by its file and line number
+struct ResultLine<'a> {
+    path: &'a Path,
+    line: usize,
+    /// The offset of the separator in the line of the buffer
+    separator: usize,
+    content: &'a str,
+}
+
+impl SearchResultsBuffer {
+    pub fn new(
+        workspace: Option<PathBuf>,
+        results: impl Iterator<Item = (PathBuf, Vec<SearchMatch>)>,
+    ) -> Self {
+        let files = results
+            .map(|(path, matches)| {
+                let mut lines: BTreeMap<usize, LinePart> = BTreeMap::new();
+                for m in matches {
+                    let content = m.line_content.trim_end_matches(['\r', '\n']);
+                    let part = LinePart {
+                        start: m.content_start,
+                        content: content.to_string(),
+                    };
+                    match lines.get_mut(&m.line) {
+                        Some(line) => line.merge(part),
+                        None => {
+                            lines.insert(m.line, part);
+                        }
+                    }
+                }
+                (path, lines)
+            })
+            .collect();
+        Self { workspace, files }
+    }
+
+    fn header(&self, path: &Path) -> String {
+        self.workspace
+            .as_ref()
+            .and_then(|workspace| path.strip_prefix(workspace).ok())
+            .unwrap_or(path)
+            .to_string_lossy()
+            .to_string()
+    }
+
+    /// The text of the buffer
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        for (path, lines) in &self.files {
+            if !text.is_empty() {
+                text.push('\n');
+            }
+            text.push_str(&self.header(path));
+            text.push('\n');
+            let width = lines
+                .keys()
+                .last()
+                .map(|line| line.to_string().len())
+                .unwrap_or(0);
+            for (line, part) in lines {
+                let content = &part.content;
+                text.push_str(&format!("  {line:>width$}{SEPARATOR}{content}\n"));
+            }
+        }
+        text
+    }
+
+    /// The result lines in the text of the buffer, with the index of the line
+    /// they are on
+    fn result_lines<'a>(
+        &'a self,
+        text: &'a str,
+    ) -> impl Iterator<Item = (usize, ResultLine<'a>)> + 'a {
+        let paths: HashMap<String, &Path> = self
+            .files
+            .keys()
+            .map(|path| (self.header(path), path.as_path()))
+            .collect();
+        let mut path = None;
+        text.lines().enumerate().filter_map(move |(i, text)| {
+            if text.trim().is_empty() {
+                return None;
+            }
+            if !text.starts_with(char::is_whitespace) {
+                path = paths.get(text).copied();
+                return None;
+            }
+            let indent = text.len() - text.trim_start().len();
+            let separator = indent + text[indent..].find([':', '!'])?;
+            let line = text[indent..separator].parse().ok()?;
+            let content = text[separator..]
+                .strip_prefix(SEPARATOR)
+                .or_else(|| text[separator..].strip_prefix(CONFLICT_SEPARATOR))?;
+            Some((
+                i,
+                ResultLine {
+                    path: path?,
+                    line,
+                    separator,
+                    content,
+                },
+            ))
+        })
+    }
+
+    /// The edits of the result lines whose content changed in the text of the
+    /// buffer, by file
+    pub fn edits(&self, text: &str) -> IndexMap<PathBuf, Vec<LineEdit>> {
+        let mut edits: IndexMap<PathBuf, Vec<LineEdit>> = IndexMap::new();
+        for (_, result) in self.result_lines(text) {
+            let Some(original) = self
+                .files
+                .get(result.path)
+                .and_then(|lines| lines.get(&result.line))
+            else {
+                continue;
+            };
+            if original.content != result.content {
+                edits
+                    .entry(result.path.to_path_buf())
+                    .or_default()
+                    .push(LineEdit {
+                        line: result.line,
+                        start: original.start,
+                        original: original.content.clone(),
+                        replacement: result.content.to_string(),
+                    });
+            }
+        }
+        edits
+    }
+
+    /// Record the content the part of a line of a file shown has now.
+    pub fn update_line(&mut self, path: &Path, line: usize, content: String) {
+        if let Some(original) = self
+            .files
+            .get_mut(path)
+            .and_then(|lines| lines.get_mut(&line))
+        {
+            original.content = content;
+        }
+    }
+
+    /// The offsets in the text of the buffer of the separators of the result
+    /// lines, to be replaced with the conflict separator
+    pub fn conflict_separators(
+        &self,
+        text: &str,
+        path: &Path,
+        lines: &[usize],
+    ) -> Vec<usize> {
+        let line_offsets: Vec<usize> = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        self.result_lines(text)
+            .filter(|(_, result)| {
+                result.path == path && lines.contains(&result.line)
+            })
+            .filter(|(i, result)| {
+                let line_start = line_offsets[*i];
+                text[line_start + result.separator..].starts_with(SEPARATOR)
+            })
+            .map(|(i, result)| line_offsets[i] + result.separator)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn search_match(line: usize, content: &str) -> SearchMatch {
+        SearchMatch {
+            line,
+            start: 0,
+            end: 1,
+            content_start: 0,
+            line_content: format!("{content}\n"),
+        }
+    }
+
+    #[test]
+    fn test_edits() {
+        let buffer = SearchResultsBuffer::new(
+            Some(PathBuf::from("/ws")),
+            [
+                (
+                    PathBuf::from("/ws/a.rs"),
+                    vec![search_match(3, "let a = 1;"), search_match(12, "a();")],
+                ),
+                (PathBuf::from("/ws/b.rs"), vec![search_match(1, "use a;")]),
+            ]
+            .into_iter(),
+        );
+        let text = buffer.text();
+        assert_eq!(
+            text,
+            "a.rs\n   3: let a = 1;\n  12: a();\n\nb.rs\n  1: use a;\n"
+        );
+        assert!(buffer.edits(&text).is_empty());
+
+        let text = text.replace("a();", "b();").replace("1: use", "1! use b;");
+        let edits = buffer.edits(&text);
+        assert_eq!(edits.len(), 2);
+        let a = &edits[&PathBuf::from("/ws/a.rs")];
+        assert_eq!((a[0].line, a[0].replacement.as_str()), (12, "b();"));
+        let b = &edits[&PathBuf::from("/ws/b.rs")];
+        assert_eq!((b[0].line, b[0].replacement.as_str()), (1, "use b; a;"));
+
+        let separators =
+            buffer.conflict_separators(&text, Path::new("/ws/a.rs"), &[12]);
+        assert_eq!(separators, vec![text.find("12: b();").unwrap() + 2]);
+    }
+
+    #[test]
+    fn test_edits_of_long_lines() {
+        let part = |line: usize, content_start: usize, content: &str| SearchMatch {
+            line,
+            start: content_start + 1,
+            end: content_start + 2,
+            content_start,
+            line_content: content.to_string(),
+        };
+        let buffer = SearchResultsBuffer::new(
+            None,
+            [(
+                PathBuf::from("/a.rs"),
+                vec![
+                    part(1, 300, "abc"),
+                    // Another match of the line, around the first one
+                    part(1, 302, "cde"),
+                    // and one too far from them to be shown
+                    part(1, 900, "xyz"),
+                ],
+            )]
+            .into_iter(),
+        );
+        let text = buffer.text();
+        assert_eq!(text, "/a.rs\n  1: abcde\n");
+
+        let edits = buffer.edits(&text.replace("abcde", "ab"));
+        let edit = &edits[&PathBuf::from("/a.rs")][0];
+        assert_eq!(
+            (
+                edit.start,
+                edit.original.as_str(),
+                edit.replacement.as_str()
+            ),
+            (300, "abcde", "ab")
+        );
+    }
+}
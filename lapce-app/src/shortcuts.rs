@@ -0,0 +1,266 @@
+use floem::reactive::{
+    create_rw_signal, RwSignal, Scope, SignalGet, SignalGetUntracked, SignalSet,
+    SignalUpdate, SignalWith,
+};
+use lapce_core::{
+    command::FocusCommand,
+    mode::{Mode, Modes},
+    selection::Selection,
+};
+use lapce_xi_rope::Rope;
+
+use crate::{
+    command::{CommandExecuted, CommandKind, LapceCommand},
+    editor::EditorData,
+    fuzzy::FuzzyMatcher,
+    id::EditorId,
+    keypress::{condition::Condition, KeyPressData, KeyPressFocus},
+    window_tab::{CommonData, Focus},
+};
+
+/// A keybinding listed in the cheat sheet
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct ShortcutItem {
+    pub desc: String,
+    pub keys: String,
+    /// The modes the keybinding applies in, empty if it applies in all of them
+    pub modes: String,
+    pub when: Option<String>,
+}
+
+/// The keyboard shortcut cheat sheet, which lists the keybindings of the active
+/// keymap grouped by the category of their commands.
+#[derive(Clone)]
+pub struct ShortcutsData {
+    pub active: RwSignal<bool>,
+    /// The editor of the query the keybindings are filtered with
+    pub editor: EditorData,
+    pub common: CommonData,
+}
+
+impl KeyPressFocus for ShortcutsData {
+    fn get_mode(&self) -> Mode {
+        Mode::Insert
+    }
+
+    fn check_condition(&self, condition: Condition) -> bool {
+        matches!(condition, Condition::ModalFocus)
+    }
+
+    fn run_command(
+        &self,
+        command: &LapceCommand,
+        count: Option<usize>,
+        mods: floem::glazier::Modifiers,
+    ) -> CommandExecuted {
+        match &command.kind {
+            CommandKind::Workbench(_) => {}
+            CommandKind::Focus(cmd) => {
+                self.run_focus_command(cmd);
+            }
+            CommandKind::Edit(_)
+            | CommandKind::Move(_)
+            | CommandKind::MultiSelection(_) => {
+                self.editor.run_command(command, count, mods);
+            }
+            CommandKind::MotionMode(_) => {}
+        }
+        CommandExecuted::Yes
+    }
+
+    fn receive_char(&self, c: &str) {
+        self.editor.receive_char(c);
+    }
+}
+
+impl ShortcutsData {
+    pub fn new(cx: Scope, common: CommonData) -> Self {
+        let active = create_rw_signal(cx, false);
+        let editor = EditorData::new_local(cx, EditorId::next(), common.clone());
+        Self {
+            active,
+            editor,
+            common,
+        }
+    }
+
+    pub fn show(&self) {
+        self.editor
+            .view
+            .doc
+            .update(|doc| doc.reload(Rope::from(""), true));
+        self.editor
+            .cursor
+            .update(|cursor| cursor.set_insert(Selection::caret(0)));
+        self.active.set(true);
+        self.common.focus.set(Focus::Shortcuts);
+    }
+
+    pub fn close(&self) {
+        self.active.set(false);
+        if let Focus::Shortcuts = self.common.focus.get_untracked() {
+            self.common.focus.set(Focus::Workbench);
+        }
+    }
+
+    fn run_focus_command(&self, cmd: &FocusCommand) -> CommandExecuted {
+        match cmd {
+            FocusCommand::ModalClose => {
+                self.close();
+            }
+            _ => return CommandExecuted::No,
+        }
+        CommandExecuted::Yes
+    }
+
+    /// The keybindings matching the query, grouped by category in the order
+    /// the categories are first seen.
+    pub fn groups(&self) -> Vec<(&'static str, Vec<ShortcutItem>)> {
+        let query = self.editor.view.doc.with(|doc| doc.buffer().to_string());
+        let query = query.trim();
+        let config = self.common.config.get();
+        let matcher = FuzzyMatcher::from_config(&config);
+        self.common.keypress.with(|keypress| {
+            shortcut_groups(keypress, query, &matcher, config.core.modal)
+        })
+    }
+}
+
+fn shortcut_groups(
+    keypress: &KeyPressData,
+    query: &str,
+    matcher: &FuzzyMatcher,
+    modal: bool,
+) -> Vec<(&'static str, Vec<ShortcutItem>)> {
+    let mut groups: Vec<(&'static str, Vec<ShortcutItem>)> = Vec::new();
+    for keymap in keypress.commands_with_keymap() {
+        // Without modal editing, the editor only ever is in insert mode
+        if !modal
+            && !keymap.modes.is_empty()
+            && !keymap.modes.contains(Modes::INSERT)
+        {
+            continue;
+        }
+        let Some(cmd) = keypress.command(&keymap.command) else {
+            continue;
+        };
+        let desc = cmd
+            .kind
+            .desc()
+            .map(|desc| desc.to_string())
+            .unwrap_or_else(|| keymap.command.clone());
+        // Such as the name of the task a keymap runs
+        let desc = match cmd.data.as_ref().and_then(|data| data.as_str()) {
+            Some(arg) => format!("{desc}: {arg}"),
+            None => desc,
+        };
+        let keys = keymap
+            .key
+            .iter()
+            .map(|key| key.label())
+            .collect::<Vec<_>>()
+            .join(" ");
+        if !query.is_empty()
+            && matcher.fuzzy_match(&desc, query).is_none()
+            && matcher.fuzzy_match(&keys, query).is_none()
+        {
+            continue;
+        }
+
+        let item = ShortcutItem {
+            desc,
+            keys,
+            modes: keymap.modes.to_string(),
+            when: keymap.when.clone(),
+        };
+        let category = cmd.kind.category();
+        match groups.iter_mut().find(|(name, _)| *name == category) {
+            Some((_, items)) => items.push(item),
+            None => groups.push((category, vec![item])),
+        }
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use floem::reactive::{create_runtime, create_scope};
+
+    use super::*;
+    use crate::listener::Listener;
+
+    const KEYMAPS: &str = r#"
+[[keymaps]]
+key = "ctrl+s"
+command = "save"
+
+[[keymaps]]
+key = "ctrl+o"
+command = "open_file"
+
+[[keymaps]]
+key = "f5"
+command = "run_task:Build"
+
+[[keymaps]]
+key = "d d"
+command = "delete_line"
+mode = "n"
+
+[[keymaps]]
+key = "ctrl+u"
+command = "not_a_command"
+"#;
+
+    fn descs(
+        groups: Vec<(&'static str, Vec<ShortcutItem>)>,
+    ) -> Vec<(&'static str, Vec<String>)> {
+        groups
+            .into_iter()
+            .map(|(category, items)| {
+                (category, items.into_iter().map(|item| item.desc).collect())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_shortcut_groups() {
+        let runtime = create_runtime();
+        create_scope(runtime, |cx| {
+            let keypress =
+                KeyPressData::with_keymaps(KEYMAPS, true, Listener::new_empty(cx));
+            let matcher = FuzzyMatcher::default();
+
+            assert_eq!(
+                descs(shortcut_groups(&keypress, "", &matcher, true)),
+                vec![
+                    ("Focus", vec!["Save".to_string()]),
+                    (
+                        "Workbench",
+                        vec!["Open File".to_string(), "Run Task: Build".to_string()]
+                    ),
+                    ("Editing", vec!["delete_line".to_string()]),
+                ]
+            );
+
+            // The keymaps of normal mode only apply with modal editing
+            assert_eq!(
+                descs(shortcut_groups(&keypress, "", &matcher, false)),
+                vec![
+                    ("Focus", vec!["Save".to_string()]),
+                    (
+                        "Workbench",
+                        vec!["Open File".to_string(), "Run Task: Build".to_string()]
+                    ),
+                ]
+            );
+
+            assert_eq!(
+                descs(shortcut_groups(&keypress, "task", &matcher, true)),
+                vec![("Workbench", vec!["Run Task: Build".to_string()])]
+            );
+        })
+        .dispose();
+        runtime.dispose();
+    }
+}
@@ -0,0 +1,230 @@
+use std::path::PathBuf;
+
+use floem::peniko::kurbo::Rect;
+use lapce_rpc::{plugin::PluginId, proxy::ProxyRpcHandler};
+use lsp_types::{Position, SignatureHelp, SignatureInformation};
+
+use crate::id::EditorId;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SignatureStatus {
+    Inactive,
+    Started,
+}
+
+/// Data for the LSP Signature Help, which displays information about the
+/// signature of the function that the user is calling.
+#[derive(Clone)]
+pub struct SignatureData {
+    pub status: SignatureStatus,
+    /// The current request id. This is used to discard old requests.
+    pub request_id: usize,
+    pub path: PathBuf,
+    /// The offset of the opening parenthesis of the call, which is used for
+    /// positioning the signature element
+    pub offset: usize,
+    pub signatures: Vec<SignatureInformation>,
+    pub current_signature: usize,
+    /// The parameter the user is currently editing
+    pub active_parameter: Option<usize>,
+    /// Whether the documentation of the signature and its active parameter is
+    /// shown
+    pub show_documentation: bool,
+    /// The size of the signature element, used for positioning it and the
+    /// completion around each other
+    pub layout_rect: Rect,
+    /// The editor id that was most recently used to trigger the signature help.
+    pub latest_editor_id: Option<EditorId>,
+}
+
+impl SignatureData {
+    pub fn new() -> Self {
+        Self {
+            status: SignatureStatus::Inactive,
+            request_id: 0,
+            path: PathBuf::new(),
+            offset: 0,
+            signatures: Vec::new(),
+            current_signature: 0,
+            active_parameter: None,
+            show_documentation: false,
+            layout_rect: Rect::ZERO,
+            latest_editor_id: None,
+        }
+    }
+
+    /// Check if there are any signatures available
+    pub fn is_empty(&self) -> bool {
+        self.signatures.is_empty()
+    }
+
+    /// Whether the signature element is displayed
+    pub fn is_active(&self) -> bool {
+        self.status != SignatureStatus::Inactive && !self.is_empty()
+    }
+
+    /// Get the currently active signature entry, if one exists
+    pub fn current(&self) -> Option<&SignatureInformation> {
+        self.signatures.get(self.current_signature)
+    }
+
+    /// The parameter being edited, which can be specific to the signature
+    pub fn current_parameter(&self) -> Option<usize> {
+        self.current()
+            .and_then(|signature| signature.active_parameter)
+            .map(|idx| idx as usize)
+            .or(self.active_parameter)
+    }
+
+    /// Request the signature help with a new request id.
+    pub fn request(
+        &mut self,
+        editor_id: EditorId,
+        proxy_rpc: &ProxyRpcHandler,
+        path: PathBuf,
+        offset: usize,
+        position: Position,
+    ) {
+        self.status = SignatureStatus::Started;
+        self.request_id += 1;
+        self.path = path.clone();
+        self.offset = offset;
+        self.latest_editor_id = Some(editor_id);
+        proxy_rpc.signature_help(self.request_id, path, position);
+    }
+
+    /// Close the signature help, clearing all the data.
+    pub fn cancel(&mut self) {
+        if self.status == SignatureStatus::Inactive {
+            return;
+        }
+        self.status = SignatureStatus::Inactive;
+        self.latest_editor_id = None;
+        self.signatures.clear();
+        self.current_signature = 0;
+        self.active_parameter = None;
+    }
+
+    /// Handle the response to a signature help request.
+    pub fn receive(
+        &mut self,
+        request_id: usize,
+        resp: &SignatureHelp,
+        _plugin_id: PluginId,
+    ) {
+        // If we've been canceled or the request id is old, ignore the response.
+        if self.status == SignatureStatus::Inactive || self.request_id != request_id
+        {
+            return;
+        }
+
+        let active_signature = resp.active_signature.unwrap_or(0) as usize;
+        // Keep the signature the user cycled to if the server still offers it,
+        // as servers tend to answer with the first one each time.
+        let same_signatures = self.signatures.len() == resp.signatures.len()
+            && self
+                .signatures
+                .iter()
+                .zip(resp.signatures.iter())
+                .all(|(a, b)| a.label == b.label);
+        let current_signature = if same_signatures {
+            self.current_signature
+        } else {
+            active_signature
+        };
+
+        self.signatures = resp.signatures.clone();
+        self.current_signature = if current_signature < self.signatures.len() {
+            current_signature
+        } else {
+            0
+        };
+        self.active_parameter = resp.active_parameter.map(|idx| idx as usize);
+    }
+
+    /// Cycle to the next overloaded signature.
+    pub fn next(&mut self) {
+        if self.signatures.is_empty() {
+            return;
+        }
+        self.current_signature =
+            (self.current_signature + 1) % self.signatures.len();
+    }
+
+    /// Cycle to the previous overloaded signature.
+    pub fn previous(&mut self) {
+        if self.signatures.is_empty() {
+            return;
+        }
+        self.current_signature = if self.current_signature == 0 {
+            self.signatures.len() - 1
+        } else {
+            self.current_signature - 1
+        };
+    }
+
+    pub fn toggle_documentation(&mut self) {
+        self.show_documentation = !self.show_documentation;
+    }
+}
+
+impl Default for SignatureData {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn help(labels: &[&str], active_signature: u32) -> SignatureHelp {
+        SignatureHelp {
+            signatures: labels
+                .iter()
+                .map(|label| SignatureInformation {
+                    label: label.to_string(),
+                    documentation: None,
+                    parameters: None,
+                    active_parameter: None,
+                })
+                .collect(),
+            active_signature: Some(active_signature),
+            active_parameter: Some(1),
+        }
+    }
+
+    #[test]
+    fn test_cycle_signatures() {
+        let mut signature = SignatureData::new();
+        signature.status = SignatureStatus::Started;
+        signature.request_id = 1;
+        signature.receive(1, &help(&["f(a)", "f(a, b)", "f()"], 1), PluginId(0));
+        assert!(signature.is_active());
+        assert_eq!(signature.current_signature, 1);
+        assert_eq!(signature.current_parameter(), Some(1));
+
+        signature.next();
+        assert_eq!(signature.current().unwrap().label, "f()");
+        signature.next();
+        assert_eq!(signature.current().unwrap().label, "f(a)");
+        signature.previous();
+        assert_eq!(signature.current().unwrap().label, "f()");
+
+        // The signature cycled to is kept when the server answers with the
+        // same ones again
+        signature.receive(1, &help(&["f(a)", "f(a, b)", "f()"], 0), PluginId(0));
+        assert_eq!(signature.current().unwrap().label, "f()");
+        signature.receive(1, &help(&["g(a)", "g()"], 1), PluginId(0));
+        assert_eq!(signature.current().unwrap().label, "g()");
+
+        // An old response is ignored
+        signature.receive(0, &help(&["h()"], 0), PluginId(0));
+        assert_eq!(signature.signatures.len(), 2);
+
+        signature.cancel();
+        assert!(!signature.is_active());
+        signature.receive(1, &help(&["f(a)"], 0), PluginId(0));
+        assert!(signature.is_empty());
+    }
+}
@@ -0,0 +1,157 @@
+use std::path::Path;
+
+/// A piece of a template
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Segment {
+    Text(String),
+    /// `${name}` or `${name:arg}`
+    Variable {
+        name: String,
+        arg: Option<String>,
+    },
+}
+
+/// A format of the status bar or of the editor tab titles, made of text and
+/// `${variable}` placeholders such as `${mode} ${file} ${diag:errors}`.
+/// It's parsed once, so rendering it only looks up the variables it uses.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StatusTemplate {
+    segments: Vec<Segment>,
+}
+
+impl StatusTemplate {
+    /// Parse the template. A `${` that isn't closed is kept as text.
+    pub fn parse(template: &str) -> Self {
+        let mut segments = Vec::new();
+        let mut text = String::new();
+        let mut rest = template;
+        while let Some(start) = rest.find("${") {
+            let Some(len) = rest[start + 2..].find('}') else {
+                break;
+            };
+            text.push_str(&rest[..start]);
+            if !text.is_empty() {
+                segments.push(Segment::Text(std::mem::take(&mut text)));
+            }
+            let variable = rest[start + 2..start + 2 + len].trim();
+            let (name, arg) = match variable.split_once(':') {
+                Some((name, arg)) => (name.trim(), Some(arg.trim().to_string())),
+                None => (variable, None),
+            };
+            segments.push(Segment::Variable {
+                name: name.to_string(),
+                arg,
+            });
+            rest = &rest[start + 2 + len + 1..];
+        }
+        text.push_str(rest);
+        if !text.is_empty() {
+            segments.push(Segment::Text(text));
+        }
+        Self { segments }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Render the template, with `value` giving the value of a variable from
+    /// its name and argument. Unknown variables are kept as they were
+    /// written, so that a typo shows up.
+    pub fn render(
+        &self,
+        mut value: impl FnMut(&str, Option<&str>) -> Option<String>,
+    ) -> String {
+        let mut rendered = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Text(text) => rendered.push_str(text),
+                Segment::Variable { name, arg } => {
+                    match value(name, arg.as_deref()) {
+                        Some(value) => rendered.push_str(&value),
+                        None => match arg {
+                            Some(arg) => {
+                                rendered.push_str(&format!("${{{name}:{arg}}}"))
+                            }
+                            None => rendered.push_str(&format!("${{{name}}}")),
+                        },
+                    }
+                }
+            }
+        }
+        rendered.trim().to_string()
+    }
+}
+
+/// The value of the variables describing a file: `${file}` its name,
+/// `${dir}` the name of its folder, `${path}` its path relative to the
+/// workspace and `${dirty}` a marker if it has unsaved changes. They're empty
+/// without a file.
+pub fn file_variable(
+    name: &str,
+    path: Option<&Path>,
+    workspace: Option<&Path>,
+    is_pristine: bool,
+) -> Option<String> {
+    if !matches!(name, "file" | "dir" | "path" | "dirty") {
+        return None;
+    }
+    let Some(path) = path else {
+        return Some(String::new());
+    };
+    let file_name = |path: &Path| {
+        path.file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default()
+            .to_string()
+    };
+    let value = match name {
+        "file" => file_name(path),
+        "dir" => path.parent().map(file_name).unwrap_or_default(),
+        "path" => workspace
+            .and_then(|workspace| path.strip_prefix(workspace).ok())
+            .unwrap_or(path)
+            .to_string_lossy()
+            .to_string(),
+        _ => if is_pristine { "" } else { "●" }.to_string(),
+    };
+    Some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let template = StatusTemplate::parse("${mode} | ${diag:errors}E ${file");
+        let rendered = template.render(|name, arg| match (name, arg) {
+            ("mode", None) => Some("Normal".to_string()),
+            ("diag", Some("errors")) => Some("2".to_string()),
+            _ => None,
+        });
+        assert_eq!(rendered, "Normal | 2E ${file");
+        assert!(StatusTemplate::parse("").is_empty());
+    }
+
+    #[test]
+    fn test_unknown_variable() {
+        let template = StatusTemplate::parse(" ${branch} ${foo:bar} ");
+        let rendered = template
+            .render(|name, _| (name == "branch").then(|| "main".to_string()));
+        assert_eq!(rendered, "main ${foo:bar}");
+    }
+
+    #[test]
+    fn test_file_variable() {
+        let path = Path::new("/project/src/main.rs");
+        let workspace = Some(Path::new("/project"));
+        let value = |name| file_variable(name, Some(path), workspace, false);
+        assert_eq!(value("file").as_deref(), Some("main.rs"));
+        assert_eq!(value("dir").as_deref(), Some("src"));
+        assert_eq!(value("path").as_deref(), Some("src/main.rs"));
+        assert_eq!(value("dirty").as_deref(), Some("●"));
+        assert_eq!(value("mode"), None);
+        assert_eq!(file_variable("file", None, None, true).as_deref(), Some(""));
+    }
+}
@@ -0,0 +1,78 @@
+/// The number of columns each tab of the line spans, with a tab stop every
+/// `tab_width` columns
+pub fn fixed_tab_widths(line: &str, tab_width: usize) -> Vec<usize> {
+    let tab_width = tab_width.max(1);
+    let mut widths = Vec::new();
+    let mut col = 0;
+    for c in line.chars() {
+        if c == '\t' {
+            let width = tab_width - col % tab_width;
+            widths.push(width);
+            col += width;
+        } else {
+            col += 1;
+        }
+    }
+    widths
+}
+
+/// The number of columns each tab of the line at `index` spans with elastic
+/// tabstops, where the text between tabs forms cells and a column of cells is
+/// as wide as its widest cell across the adjacent lines that have the column.
+/// `lines` holds the line with the lines around it.
+pub fn elastic_tab_widths(
+    lines: &[&str],
+    index: usize,
+    tab_width: usize,
+) -> Vec<usize> {
+    let cells: Vec<Vec<usize>> = lines
+        .iter()
+        .map(|line| line.split('\t').map(|cell| cell.chars().count()).collect())
+        .collect();
+    // A cell ends with a tab unless it's the last one of its line
+    let has_column = |line: usize, column: usize| cells[line].len() > column + 1;
+
+    let line_cells = &cells[index];
+    (0..line_cells.len().saturating_sub(1))
+        .map(|column| {
+            let mut start = index;
+            while start > 0 && has_column(start - 1, column) {
+                start -= 1;
+            }
+            let mut end = index;
+            while end + 1 < cells.len() && has_column(end + 1, column) {
+                end += 1;
+            }
+            let widest = (start..=end)
+                .map(|line| cells[line][column])
+                .max()
+                .unwrap_or(0);
+            (widest + 1).max(tab_width) - line_cells[column]
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixed_tab_widths() {
+        assert_eq!(fixed_tab_widths("\t\tx", 4), vec![4, 4]);
+        assert_eq!(fixed_tab_widths("ab\tc\t", 4), vec![2, 3]);
+        assert_eq!(fixed_tab_widths("abcd\t", 4), vec![4]);
+        assert!(fixed_tab_widths("abc", 4).is_empty());
+    }
+
+    #[test]
+    fn test_elastic_tab_widths() {
+        let lines = ["a\tbb\tc", "aaaaaa\tb", "x", "\tlonger cell\ty"];
+        // The first column spans the first two lines, the second one only
+        // the first line
+        assert_eq!(elastic_tab_widths(&lines, 0, 4), vec![6, 2]);
+        assert_eq!(elastic_tab_widths(&lines, 1, 4), vec![1]);
+        assert!(elastic_tab_widths(&lines, 2, 4).is_empty());
+        // Leading tabs keep the width of a tab
+        assert_eq!(elastic_tab_widths(&lines, 3, 4), vec![4, 1]);
+    }
+}
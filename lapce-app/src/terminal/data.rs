@@ -26,7 +26,7 @@
 };
 use crate::{
     command::{CommandExecuted, CommandKind, InternalCommand},
-    debug::RunDebugProcess,
+    debug::{run_command, RunDebugProcess},
     doc::SystemClipboard,
     keypress::{condition::Condition, KeyPressFocus},
     window_tab::CommonData,
@@ -345,6 +345,7 @@ pub fn new_raw_terminal(
 
         let mut cwd = workspace.path.as_ref().cloned();
         let mut env = None;
+        let mut command = None;
         let shell = if let Some(run_debug) = run_debug {
             if let Some(path) = run_debug.cwd.as_ref() {
                 cwd = Some(PathBuf::from(path));
@@ -366,6 +367,8 @@ pub fn new_raw_terminal(
             if let Some(debug_command) = run_debug.debug_command.as_ref() {
                 debug_command.clone()
             } else {
+                command = Some(run_command(run_debug));
+                // What a proxy that doesn't take the command runs instead
                 format!("{} {}", run_debug.program, run_debug.args.join(" "))
             }
         } else {
@@ -375,7 +378,7 @@ pub fn new_raw_terminal(
         {
             let raw = raw.clone();
             let _ = common.term_tx.send((term_id, TermEvent::NewTerminal(raw)));
-            common.proxy.new_terminal(term_id, cwd, env, shell);
+            common.proxy.new_terminal(term_id, cwd, env, shell, command);
         }
         raw
     }
@@ -1,4 +1,4 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, path::PathBuf, sync::Arc};
 
 use floem::{
     glazier::KeyEvent,
@@ -106,7 +106,22 @@ pub fn new_tab(&self, run_debug: Option<RunDebugProcess>) -> TerminalTabData {
             run_debug,
             self.common.clone(),
         );
+        self.insert_tab(terminal_tab)
+    }
+
+    /// Open a new terminal tab in the directory rather than the workspace
+    pub fn new_tab_in(&self, dir: PathBuf) -> TerminalTabData {
+        let workspace = Arc::new(LapceWorkspace {
+            path: Some(dir),
+            ..(*self.workspace).clone()
+        });
+        let terminal_tab =
+            TerminalTabData::new(workspace, None, self.common.clone());
+        self.insert_tab(terminal_tab)
+    }
 
+    /// Add the tab after the active one and make it active
+    fn insert_tab(&self, terminal_tab: TerminalTabData) -> TerminalTabData {
         self.tab_info.update(|info| {
             info.tabs.insert(
                 if info.tabs.is_empty() {
@@ -331,6 +346,23 @@ pub fn get_stopped_run_debug_terminal(
         })
     }
 
+    /// The tasks run or debugged in the terminals, with the terminal each is
+    /// in, the one started last at the end
+    pub fn run_debug_processes(&self) -> Vec<(TermId, RunDebugProcess)> {
+        let mut processes = self.tab_info.with(|info| {
+            info.tabs
+                .iter()
+                .flat_map(|(_, tab)| tab.terminals.get())
+                .filter_map(|(_, terminal)| {
+                    let process = terminal.run_debug.get()?;
+                    Some((terminal.term_id, process))
+                })
+                .collect::<Vec<_>>()
+        });
+        processes.sort_by_key(|(_, process)| process.created);
+        processes
+    }
+
     pub fn restart_run_debug(&self, term_id: TermId) -> Option<()> {
         let (_, terminal_tab, index, terminal) =
             self.get_terminal_in_tab(&term_id)?;
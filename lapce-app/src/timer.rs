@@ -0,0 +1,11 @@
+use std::time::{Duration, Instant};
+
+use floem::{ext_event::create_ext_action, reactive::Scope};
+use lapce_rpc::timer::schedule;
+
+/// Run the action in the scope once the time passes. The timers share one
+/// thread that waits for their deadlines, rather than each sleeping on one.
+pub fn exec_after(cx: Scope, duration: Duration, action: impl FnOnce() + 'static) {
+    let send = create_ext_action(cx, move |_: ()| action());
+    schedule(Instant::now() + duration, move || send(()));
+}
@@ -3,15 +3,12 @@
 use floem::{
     menu::{Menu, MenuItem},
     peniko::kurbo::Point,
-    reactive::{
-        create_memo, ReadSignal, RwSignal, SignalGet, SignalGetUntracked, SignalWith,
-    },
+    reactive::{create_memo, ReadSignal, SignalGet, SignalGetUntracked, SignalWith},
     style::{AlignItems, CursorStyle, Dimension, Display, JustifyContent, Style},
     view::View,
     views::{container, label, stack, svg, Decorators},
     ViewContext,
 };
-use lapce_core::meta;
 
 use crate::{
     app::clickable_icon,
@@ -20,7 +17,7 @@
     listener::Listener,
     main_split::MainSplitData,
     source_control::SourceControlData,
-    update::ReleaseInfo,
+    update::{ReleaseInfo, UpdateStatus},
     workspace::LapceWorkspace,
 };
 
@@ -287,21 +284,17 @@ fn middle(
 fn right(
     workbench_command: Listener<LapceWorkbenchCommand>,
     latest_release: ReadSignal<Arc<Option<ReleaseInfo>>>,
-    update_in_progress: RwSignal<bool>,
+    update_status: ReadSignal<UpdateStatus>,
     config: ReadSignal<Arc<LapceConfig>>,
 ) -> impl View {
     let cx = ViewContext::get_current();
     let latest_version = create_memo(cx.scope, move |_| {
         let latest_release = latest_release.get();
-        let latest_version =
-            latest_release.as_ref().as_ref().map(|r| r.version.clone());
-        if latest_version.is_some()
-            && latest_version.as_deref() != Some(meta::VERSION)
-        {
-            latest_version
-        } else {
-            None
-        }
+        latest_release
+            .as_ref()
+            .as_ref()
+            .filter(|r| r.is_newer())
+            .map(|r| r.version.clone())
     });
 
     let has_update = move || latest_version.with(|v| v.is_some());
@@ -332,7 +325,9 @@ fn right(
                                 .separator()
                                 .entry(
                                     if let Some(v) = latest_version.get_untracked() {
-                                        if update_in_progress.get_untracked() {
+                                        if update_status.get_untracked()
+                                            == UpdateStatus::Downloading
+                                        {
                                             MenuItem::new(format!(
                                                 "Update in progress ({v})"
                                             ))
@@ -346,8 +341,11 @@ fn right(
                                             })
                                         }
                                     } else {
-                                        MenuItem::new("No update available")
-                                            .enabled(false)
+                                        MenuItem::new("Check for Updates").action(
+                                            move || {
+                                                workbench_command.send(LapceWorkbenchCommand::CheckForUpdates)
+                                            },
+                                        )
                                     },
                                 ),
                             Point::ZERO,
@@ -396,19 +394,14 @@ pub fn title(
     source_control: SourceControlData,
     workbench_command: Listener<LapceWorkbenchCommand>,
     latest_release: ReadSignal<Arc<Option<ReleaseInfo>>>,
-    update_in_progress: RwSignal<bool>,
+    update_status: ReadSignal<UpdateStatus>,
     config: ReadSignal<Arc<LapceConfig>>,
 ) -> impl View {
     stack(move || {
         (
             left(source_control, workbench_command, config),
             middle(workspace, main_split, workbench_command, config),
-            right(
-                workbench_command,
-                latest_release,
-                update_in_progress,
-                config,
-            ),
+            right(workbench_command, latest_release, update_status, config),
         )
     })
     .style(move || {
@@ -2,23 +2,58 @@
 
 use anyhow::{anyhow, Result};
 use lapce_core::{directory::Directory, meta};
+use lapce_proxy::network;
 use serde::Deserialize;
 
 #[derive(Clone, Deserialize)]
 pub struct ReleaseInfo {
     pub tag_name: String,
     pub target_commitish: String,
+    /// The release notes, in markdown
+    #[serde(default)]
+    pub body: Option<String>,
     pub assets: Vec<ReleaseAsset>,
     #[serde(skip)]
     pub version: String,
 }
 
+impl ReleaseInfo {
+    /// Whether the release is newer than the running version of Lapce
+    pub fn is_newer(&self) -> bool {
+        is_newer_version(&self.version, meta::VERSION)
+    }
+}
+
+/// Whether the version is newer than the current one. Releases are compared
+/// by their semantic versions, so that a newer build isn't offered an older
+/// release, while a nightly is newer whenever it's of another commit.
+fn is_newer_version(version: &str, current: &str) -> bool {
+    let parse = |version: &str| {
+        semver::Version::parse(version.strip_prefix('v').unwrap_or(version)).ok()
+    };
+    match (parse(version), parse(current)) {
+        (Some(version), Some(current)) => version > current,
+        _ => version != current,
+    }
+}
+
 #[derive(Clone, Deserialize)]
 pub struct ReleaseAsset {
     pub name: String,
     pub browser_download_url: String,
 }
 
+/// The progress of updating to the latest release
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum UpdateStatus {
+    Idle,
+    Downloading,
+    /// The release was downloaded and extracted, and Lapce can be restarted
+    /// into the contained path
+    Ready(PathBuf),
+    Failed,
+}
+
 pub fn get_latest_release() -> Result<ReleaseInfo> {
     let url = match meta::RELEASE {
         meta::ReleaseType::Debug => {
@@ -30,11 +65,7 @@ pub fn get_latest_release() -> Result<ReleaseInfo> {
         _ => "https://api.github.com/repos/lapce/lapce/releases/latest",
     };
 
-    let resp = reqwest::blocking::ClientBuilder::new()
-        .user_agent("Lapce")
-        .build()?
-        .get(url)
-        .send()?;
+    let resp = network::get(url)?;
     if !resp.status().is_success() {
         return Err(anyhow!("get release info failed {}", resp.text()?));
     }
@@ -64,7 +95,7 @@ pub fn download_release(release: &ReleaseInfo) -> Result<PathBuf> {
 
     for asset in &release.assets {
         if asset.name == name {
-            let mut resp = reqwest::blocking::get(&asset.browser_download_url)?;
+            let mut resp = network::get(&asset.browser_download_url)?;
             if !resp.status().is_success() {
                 return Err(anyhow!("download file error {}", resp.text()?));
             }
@@ -77,6 +108,16 @@ pub fn download_release(release: &ReleaseInfo) -> Result<PathBuf> {
     Err(anyhow!("can't download release"))
 }
 
+/// Download the release and extract it over the running executable, returning
+/// the path to restart into.
+pub fn download_and_extract(
+    release: &ReleaseInfo,
+    process_path: &Path,
+) -> Result<PathBuf> {
+    let src = download_release(release)?;
+    extract(&src, process_path)
+}
+
 #[cfg(target_os = "macos")]
 pub fn extract(src: &Path, process_path: &Path) -> Result<PathBuf> {
     let info = dmg::Attach::new(src).with()?;
@@ -221,3 +262,20 @@ pub fn cleanup() {
 pub fn cleanup() {
     // Nothing to do yet
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_newer_version() {
+        assert!(is_newer_version("v0.2.9", "0.2.8"));
+        assert!(is_newer_version("v0.3.0", "0.2.10"));
+        assert!(!is_newer_version("v0.2.8", "0.2.8"));
+        // A build newer than the latest release isn't offered it
+        assert!(!is_newer_version("v0.2.8", "0.2.9"));
+        assert!(!is_newer_version("v0.2.8", "0.2.9-rc.1"));
+        assert!(is_newer_version("nightly-1234567", "nightly-abcdefg"));
+        assert!(!is_newer_version("nightly-1234567", "nightly-1234567"));
+    }
+}
@@ -0,0 +1,375 @@
+use std::sync::Arc;
+
+use floem::{
+    reactive::{use_context, ReadSignal, SignalGet, SignalGetUntracked},
+    style::{CursorStyle, Style},
+    view::View,
+    views::{empty, label, list, scroll, stack, svg, Decorators},
+    ViewContext,
+};
+
+use crate::{
+    command::{InternalCommand, LapceWorkbenchCommand, WindowCommand},
+    config::{color::LapceColor, LapceConfig},
+    db::LapceDb,
+    panel::kind::PanelKind,
+    window_tab::WindowTabData,
+    workspace::LapceWorkspace,
+};
+
+/// The number of recent workspaces listed on the welcome page
+const RECENT_WORKSPACES: usize = 10;
+/// The number of color theme previews in each row of the theme grid
+const THEMES_PER_ROW: usize = 4;
+/// The languages offered for plugin installation, with the query used to find
+/// their plugins
+const LANGUAGE_PLUGINS: &[(&str, &str)] = &[
+    ("Rust", "rust"),
+    ("Python", "python"),
+    ("Go", "go"),
+    ("TypeScript", "typescript"),
+    ("C/C++", "clangd"),
+    ("Java", "java"),
+];
+
+/// The start page shown in place of the editors when the window has none
+/// open, with recent workspaces and a guided setup for first runs.
+pub fn welcome_page(window_tab_data: Arc<WindowTabData>) -> impl View {
+    let config = window_tab_data.common.config;
+
+    scroll(move || {
+        stack(move || {
+            (
+                stack(move || {
+                    (
+                        svg(move || config.get().logo_svg()).style(move || {
+                            Style::BASE.size_px(48.0, 48.0).color(
+                                *config.get().get_color(LapceColor::EDITOR_DIM),
+                            )
+                        }),
+                        label(|| "Welcome to Lapce".to_string()).style(move || {
+                            Style::BASE
+                                .margin_left_px(16.0)
+                                .font_size((config.get().ui.font_size() * 2) as f32)
+                        }),
+                    )
+                })
+                .style(|| Style::BASE.items_center().margin_bottom_px(30.0)),
+                stack(|| {
+                    (
+                        start_section(window_tab_data.clone()),
+                        setup_section(window_tab_data.clone()),
+                    )
+                })
+                .style(|| Style::BASE.width_pct(100.0)),
+            )
+        })
+        .style(|| {
+            Style::BASE
+                .flex_col()
+                .width_pct(100.0)
+                .max_width_px(900.0)
+                .padding_px(40.0)
+        })
+    })
+    .scroll_bar_color(move || *config.get().get_color(LapceColor::LAPCE_SCROLL_BAR))
+    .style(move || {
+        Style::BASE
+            .size_pct(100.0, 100.0)
+            .justify_center()
+            .color(*config.get().get_color(LapceColor::EDITOR_FOREGROUND))
+    })
+}
+
+fn section_title(
+    title: &'static str,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    label(move || title.to_string()).style(move || {
+        Style::BASE
+            .font_bold()
+            .margin_top_px(20.0)
+            .margin_bottom_px(8.0)
+            .color(*config.get().get_color(LapceColor::EDITOR_DIM))
+    })
+}
+
+/// A clickable line of text, styled like a link
+fn link(
+    text: impl Fn() -> String + 'static,
+    on_click: impl Fn() + 'static,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    label(text)
+        .on_click(move |_| {
+            on_click();
+            true
+        })
+        .style(move || {
+            Style::BASE
+                .text_ellipsis()
+                .padding_vert_px(2.0)
+                .color(*config.get().get_color(LapceColor::EDITOR_LINK))
+        })
+        .hover_style(|| Style::BASE.cursor(CursorStyle::Pointer))
+}
+
+fn start_section(window_tab_data: Arc<WindowTabData>) -> impl View {
+    let config = window_tab_data.common.config;
+    let window_command = window_tab_data.common.window_command;
+    let cx = ViewContext::get_current();
+    let db: Arc<LapceDb> = use_context(cx.scope).unwrap();
+    let current = window_tab_data.workspace.clone();
+    let recent: Vec<LapceWorkspace> = db
+        .recent_workspaces()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|workspace| workspace.path.is_some() && *workspace != *current)
+        .take(RECENT_WORKSPACES)
+        .collect();
+    let is_empty = recent.is_empty();
+
+    stack(move || {
+        (
+            section_title("Start", config),
+            link(
+                || "Open Folder...".to_string(),
+                move || {
+                    window_tab_data.run_workbench_command(
+                        LapceWorkbenchCommand::OpenFolder,
+                        None,
+                    );
+                },
+                config,
+            ),
+            section_title("Recent", config),
+            label(|| "No recent workspaces".to_string()).style(move || {
+                Style::BASE
+                    .color(*config.get().get_color(LapceColor::EDITOR_DIM))
+                    .apply_if(!is_empty, |s| s.hide())
+            }),
+            list(
+                move || recent.clone(),
+                |workspace| workspace.path.clone(),
+                move |workspace| {
+                    let name = workspace.display().unwrap_or_default();
+                    let path = workspace
+                        .path
+                        .as_ref()
+                        .map(|p| p.to_string_lossy().to_string())
+                        .unwrap_or_default();
+                    stack(move || {
+                        (
+                            link(
+                                move || name.clone(),
+                                move || {
+                                    window_command.send(
+                                        WindowCommand::SetWorkspace {
+                                            workspace: workspace.clone(),
+                                        },
+                                    );
+                                },
+                                config,
+                            ),
+                            label(move || path.clone()).style(move || {
+                                Style::BASE
+                                    .text_ellipsis()
+                                    .min_width_px(0.0)
+                                    .margin_left_px(10.0)
+                                    .color(
+                                        *config
+                                            .get()
+                                            .get_color(LapceColor::EDITOR_DIM),
+                                    )
+                            }),
+                        )
+                    })
+                    .style(|| Style::BASE.items_center().width_pct(100.0))
+                },
+            )
+            .style(|| Style::BASE.flex_col().width_pct(100.0)),
+        )
+    })
+    .style(|| {
+        Style::BASE
+            .flex_col()
+            .flex_grow(1.0)
+            .flex_basis_px(0.0)
+            .min_width_px(0.0)
+            .padding_right_px(20.0)
+    })
+}
+
+fn setup_section(window_tab_data: Arc<WindowTabData>) -> impl View {
+    let config = window_tab_data.common.config;
+
+    stack(move || {
+        (
+            section_title("Keymap", config),
+            stack(move || {
+                (
+                    keymap_preset("Default", false, config),
+                    keymap_preset("Vim", true, config),
+                )
+            }),
+            section_title("Color Theme", config),
+            theme_grid(window_tab_data.clone()),
+            section_title("Language Support", config),
+            list(
+                || LANGUAGE_PLUGINS.iter(),
+                |(language, _)| *language,
+                move |(language, query)| {
+                    let window_tab_data = window_tab_data.clone();
+                    link(
+                        move || format!("Install {language} plugins..."),
+                        move || {
+                            window_tab_data.plugin.search_available(query);
+                            window_tab_data.show_panel(PanelKind::Plugin);
+                        },
+                        config,
+                    )
+                },
+            )
+            .style(|| Style::BASE.flex_col()),
+        )
+    })
+    .style(|| {
+        Style::BASE
+            .flex_col()
+            .flex_grow(1.0)
+            .flex_basis_px(0.0)
+            .min_width_px(0.0)
+    })
+}
+
+/// A button choosing between the default and the modal (vim-like) keymaps
+fn keymap_preset(
+    name: &'static str,
+    modal: bool,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    label(move || name.to_string())
+        .on_click(move |_| {
+            if config.get_untracked().core.modal != modal {
+                // The config file is watched
+                LapceConfig::update_file(
+                    "core",
+                    "modal",
+                    toml_edit::Value::from(modal),
+                );
+            }
+            true
+        })
+        .style(move || {
+            let config = config.get();
+            let active = config.core.modal == modal;
+            Style::BASE
+                .padding_horiz_px(12.0)
+                .padding_vert_px(4.0)
+                .margin_right_px(8.0)
+                .border(1.0)
+                .border_radius(6.0)
+                .border_color(*config.get_color(LapceColor::LAPCE_BORDER))
+                .apply_if(active, |s| {
+                    s.border_color(*config.get_color(LapceColor::EDITOR_CARET))
+                })
+        })
+        .hover_style(move || {
+            Style::BASE.cursor(CursorStyle::Pointer).background(
+                *config.get().get_color(LapceColor::PANEL_HOVERED_BACKGROUND),
+            )
+        })
+}
+
+/// The available color themes laid out in rows, each previewed in its own
+/// editor colors.
+fn theme_grid(window_tab_data: Arc<WindowTabData>) -> impl View {
+    let config = window_tab_data.common.config;
+    let internal_command = window_tab_data.common.internal_command;
+    let rows = move || {
+        let config = config.get();
+        let themes: Vec<_> = config
+            .color_theme_list()
+            .into_iter()
+            .filter_map(|name| {
+                let preview = config.color_theme_preview(&name)?;
+                Some((name, preview))
+            })
+            .collect();
+        themes
+            .chunks(THEMES_PER_ROW)
+            .map(|row| row.to_vec())
+            .enumerate()
+            .collect::<Vec<_>>()
+    };
+
+    list(
+        rows,
+        |(i, row)| {
+            let names: Vec<String> =
+                row.iter().map(|(name, _)| name.clone()).collect();
+            (*i, names)
+        },
+        move |(_, row)| {
+            list(
+                move || row.clone(),
+                |(name, _)| name.clone(),
+                move |(name, [background, foreground, caret])| {
+                    let local_name = name.clone();
+                    let active_name = name.clone();
+                    stack(move || {
+                        (
+                            empty().style(move || {
+                                Style::BASE
+                                    .size_px(4.0, 14.0)
+                                    .margin_right_px(6.0)
+                                    .background(caret)
+                            }),
+                            label(move || local_name.clone()).style(move || {
+                                Style::BASE
+                                    .text_ellipsis()
+                                    .min_width_px(0.0)
+                                    .color(foreground)
+                            }),
+                        )
+                    })
+                    .on_click(move |_| {
+                        internal_command.send(InternalCommand::SetColorTheme {
+                            name: name.clone(),
+                            save: true,
+                        });
+                        true
+                    })
+                    .style(move || {
+                        let config = config.get();
+                        let active = config
+                            .core
+                            .color_theme
+                            .eq_ignore_ascii_case(&active_name);
+                        Style::BASE
+                            .items_center()
+                            .flex_grow(1.0)
+                            .flex_basis_px(0.0)
+                            .min_width_px(0.0)
+                            .padding_px(8.0)
+                            .margin_right_px(6.0)
+                            .border(1.0)
+                            .border_radius(6.0)
+                            .background(background)
+                            .border_color(
+                                *config.get_color(LapceColor::LAPCE_BORDER),
+                            )
+                            .apply_if(active, |s| {
+                                s.border(2.0).border_color(
+                                    *config.get_color(LapceColor::EDITOR_CARET),
+                                )
+                            })
+                    })
+                    .hover_style(|| Style::BASE.cursor(CursorStyle::Pointer))
+                },
+            )
+            .style(|| Style::BASE.width_pct(100.0).margin_bottom_px(6.0))
+        },
+    )
+    .style(|| Style::BASE.flex_col().width_pct(100.0))
+}
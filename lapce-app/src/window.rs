@@ -53,6 +53,8 @@ pub struct WindowData {
     pub position: RwSignal<Point>,
     pub root_view_id: RwSignal<floem::id::Id>,
     pub window_scale: RwSignal<f64>,
+    /// How many points the editor font of the window is zoomed by
+    pub editor_font_zoom: RwSignal<isize>,
     pub latest_release: ReadSignal<Arc<Option<ReleaseInfo>>>,
     pub config: RwSignal<Arc<LapceConfig>>,
 }
@@ -65,9 +67,10 @@ pub fn new(
         latest_release: ReadSignal<Arc<Option<ReleaseInfo>>>,
         app_command: Listener<AppCommand>,
     ) -> Self {
-        let config = LapceConfig::load(&LapceWorkspace::default(), &[]);
+        let config = LapceConfig::load(&LapceWorkspace::default(), None, &[], &[]);
         let config = create_rw_signal(cx, Arc::new(config));
         let root_view_id = create_rw_signal(cx, floem::id::Id::next());
+        let editor_font_zoom = create_rw_signal(cx, 0);
 
         let mut window_tabs = im::Vector::new();
         let active = info.tabs.active_tab;
@@ -80,6 +83,7 @@ pub fn new(
                 Arc::new(w),
                 window_command,
                 window_scale,
+                editor_font_zoom,
                 latest_release,
             ));
             window_tabs.push_back((create_rw_signal(cx, 0), window_tab));
@@ -91,6 +95,7 @@ pub fn new(
                 Arc::new(LapceWorkspace::default()),
                 window_command,
                 window_scale,
+                editor_font_zoom,
                 latest_release,
             ));
             window_tabs.push_back((create_rw_signal(cx, 0), window_tab));
@@ -110,6 +115,7 @@ pub fn new(
             position,
             root_view_id,
             window_scale,
+            editor_font_zoom,
             latest_release,
             app_command,
             config,
@@ -126,7 +132,7 @@ pub fn new(
     }
 
     pub fn reload_config(&self) {
-        let config = LapceConfig::load(&LapceWorkspace::default(), &[]);
+        let config = LapceConfig::load(&LapceWorkspace::default(), None, &[], &[]);
         self.config.set(Arc::new(config));
         let window_tabs = self.window_tabs.get_untracked();
         for (_, window_tab) in window_tabs {
@@ -153,6 +159,7 @@ pub fn run_window_command(&self, cmd: WindowCommand) {
                     Arc::new(workspace),
                     self.window_command,
                     self.window_scale,
+                    self.editor_font_zoom,
                     self.latest_release,
                 ));
                 self.window_tabs.update(|window_tabs| {
@@ -180,6 +187,7 @@ pub fn run_window_command(&self, cmd: WindowCommand) {
                     Arc::new(workspace),
                     self.window_command,
                     self.window_scale,
+                    self.editor_font_zoom,
                     self.latest_release,
                 ));
                 let active = self.active.get_untracked();
@@ -252,6 +260,12 @@ pub fn run_window_command(&self, cmd: WindowCommand) {
                     self.active.set(active);
                 }
             }
+            WindowCommand::CheckForUpdates => {
+                self.app_command.send(AppCommand::CheckForUpdates);
+            }
+            WindowCommand::ReloadConfig => {
+                self.app_command.send(AppCommand::ReloadConfig);
+            }
         }
         self.app_command.send(AppCommand::SaveApp);
     }
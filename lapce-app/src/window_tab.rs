@@ -1,4 +1,13 @@
-use std::{collections::HashSet, env, path::Path, sync::Arc, time::Instant};
+use std::{
+    collections::HashSet,
+    env,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
 
 use crossbeam_channel::Sender;
 use floem::{
@@ -12,16 +21,42 @@
         SignalUpdate, SignalWith, SignalWithUntracked, WriteSignal,
     },
 };
+use indexmap::IndexMap;
 use itertools::Itertools;
-use lapce_core::{directory::Directory, meta, mode::Mode, register::Register};
+use lapce_core::{
+    buffer::rope_text::RopeText,
+    cursor::CursorMode,
+    directory::Directory,
+    mode::Mode,
+    register::{Clipboard, Register},
+    selection::Selection,
+};
+use lapce_proxy::{buffer::language_id_from_path, plugin::wasi::find_all_volts};
 use lapce_rpc::{
-    core::CoreNotification, dap_types::RunDebugConfig, file::PathObject,
-    proxy::ProxyRpcHandler, source_control::FileDiff, terminal::TermId,
+    core::{CoreNotification, CoreRequest, CoreResponse},
+    dap_types::RunDebugConfig,
+    file::{is_archive, PathObject},
+    plugin::{
+        PluginCommand, PluginCommandContext, PluginCommandResult, PluginId,
+        PluginPanel, PluginProviders, PluginStatusItem, ProviderKind, VoltID,
+        VoltMetadata,
+    },
+    protocol::Capability,
+    proxy::{ProxyResponse, ProxyRpcHandler},
+    source_control::FileDiff,
+    terminal::TermId,
+    RequestId,
+};
+use lapce_xi_rope::Rope;
+use lsp_types::{
+    ProgressParamsValue, ProgressToken, Range, Url,
+    WorkDoneProgress as LspWorkDoneProgress,
 };
 use serde_json::Value;
 use tracing::{debug, error};
 
 use crate::{
+    burn_down::BurnDown,
     code_action::{CodeActionData, CodeActionStatus},
     command::{
         CommandExecuted, CommandKind, InternalCommand, LapceCommand,
@@ -30,17 +65,26 @@
     completion::{CompletionData, CompletionStatus},
     config::LapceConfig,
     db::LapceDb,
-    debug::{DapData, RunDebugMode, RunDebugProcess},
-    doc::{DocContent, EditorDiagnostic},
-    editor::location::{EditorLocation, EditorPosition},
+    debug::{
+        current_file_run_config, run_configs, DapData, RunDebugMode, RunDebugProcess,
+    },
+    doc::{DocContent, EditorDiagnostic, SystemClipboard},
+    edit_session::{EditSession, EditSessionError},
+    editor::{
+        location::{EditorLocation, EditorPosition},
+        EncodingCheck,
+    },
     editor_tab::EditorTabChild,
     file_explorer::data::FileExplorerData,
     find::Find,
+    frecency::FileFrecency,
     global_search::GlobalSearchData,
-    id::WindowTabId,
+    id::{EditorId, EditorTabId, WindowTabId},
+    inline_suggestion::InlineSuggestionProviders,
     keypress::{condition::Condition, KeyPressData, KeyPressFocus},
     listener::Listener,
     main_split::{MainSplitData, SplitData, SplitDirection},
+    overlay::{OverlayEvent, OverlayKind, OverlayManager},
     palette::{kind::PaletteKind, PaletteData, PaletteStatus},
     panel::{
         data::{default_panel_order, PanelData},
@@ -48,36 +92,85 @@
         position::PanelContainerPosition,
     },
     plugin::PluginData,
+    problem::ProblemData,
+    profile::Profile,
     proxy::{path_from_url, start_proxy, ProxyData},
+    regex_tester::RegexTesterData,
+    registers::RegistersData,
     rename::RenameData,
+    replay::ReplayData,
+    safe_mode::{self, SafeModePart},
+    scratchpad::ScratchpadData,
+    search_history::{SearchHistory, SearchHistoryKind},
+    shortcuts::ShortcutsData,
+    signature::SignatureData,
     source_control::SourceControlData,
     terminal::{
         event::{terminal_update_process, TermEvent, TermNotification},
         panel::TerminalPanelData,
     },
-    update::ReleaseInfo,
+    timer::exec_after,
+    update::{ReleaseInfo, UpdateStatus},
     workspace::{LapceWorkspace, LapceWorkspaceType, WorkspaceInfo},
 };
 
+/// How long after the session last changed it's saved
+const SESSION_SAVE_DELAY: Duration = Duration::from_secs(2);
+
+/// The view of an editor before the selection was fitted in it, to go back
+/// to
+#[derive(Clone, Copy)]
+struct FittedView {
+    editor_id: EditorId,
+    scroll_offset: Vec2,
+    /// How many points the editor font was zoomed by to fit the selection
+    zoom: isize,
+}
+
+/// Whether the default keymaps were checked for changes the user hasn't
+/// reviewed, which the first workspace tab to open does
+static KEYMAP_CHANGES_CHECKED: AtomicBool = AtomicBool::new(false);
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Focus {
     Workbench,
     Palette,
     CodeAction,
     Rename,
+    Shortcuts,
+    Registers,
+    Scratchpad,
+    Replay,
     Panel(PanelKind),
 }
 
 #[derive(Clone)]
 pub enum DragContent {
     Panel(PanelKind),
-    EditorTab(EditorTabChild),
+    /// A child of the editor tab, dragged by its tab
+    EditorTab(EditorTabId, EditorTabChild),
+    /// Files and folders dragged in the file explorer
+    FileExplorer(Vec<PathBuf>),
 }
 
 impl DragContent {
     pub fn is_panel(&self) -> bool {
         matches!(self, DragContent::Panel(_))
     }
+
+    pub fn is_editor_tab(&self) -> bool {
+        matches!(self, DragContent::EditorTab(..))
+    }
+}
+
+/// The state of a long running task reported by a language server, such as
+/// indexing the workspace
+#[derive(Clone, PartialEq)]
+pub struct WorkProgress {
+    pub server_name: String,
+    pub title: String,
+    pub message: Option<String>,
+    pub percentage: Option<u32>,
 }
 
 #[derive(Clone)]
@@ -87,8 +180,12 @@ pub struct CommonData {
     pub focus: RwSignal<Focus>,
     pub keypress: RwSignal<KeyPressData>,
     pub completion: RwSignal<CompletionData>,
+    pub signature: RwSignal<SignatureData>,
+    pub overlays: OverlayManager,
     pub register: RwSignal<Register>,
     pub find: Find,
+    pub search_history: SearchHistory,
+    pub file_frecency: RwSignal<FileFrecency>,
     pub window_command: Listener<WindowCommand>,
     pub internal_command: Listener<InternalCommand>,
     pub lapce_command: Listener<LapceCommand>,
@@ -96,9 +193,26 @@ pub struct CommonData {
     pub term_tx: Sender<(TermId, TermEvent)>,
     pub term_notification_tx: Sender<TermNotification>,
     pub proxy: ProxyRpcHandler,
+    /// The language feature providers the plugins registered
+    pub plugin_providers: RwSignal<PluginProviders>,
+    /// The commands the plugins registered, by their names, with the volts
+    /// of the plugins
+    pub plugin_commands:
+        RwSignal<IndexMap<String, (VoltID, PluginId, PluginCommand)>>,
+    /// The panels the plugins show, by their volts and ids
+    pub plugin_panels: RwSignal<IndexMap<(VoltID, String), PluginPanel>>,
+    /// The status bar items the plugins show, by their volts and ids
+    pub plugin_status_items: RwSignal<IndexMap<(VoltID, String), PluginStatusItem>>,
+    pub inline_suggestions: InlineSuggestionProviders,
     pub view_id: RwSignal<floem::id::Id>,
     pub ui_line_height: Memo<f64>,
     pub dragging: RwSignal<Option<DragContent>>,
+    /// The scope of focus mode, which dims the code outside the scope of the
+    /// cursor, if it is on. Its timer to turn it off again lives in it.
+    pub focus_mode: RwSignal<Option<Scope>>,
+    /// A save waiting on what to do with the characters the encoding of the
+    /// file can't represent
+    pub encoding_check: RwSignal<Option<EncodingCheck>>,
     pub config: ReadSignal<Arc<LapceConfig>>,
 }
 
@@ -116,14 +230,38 @@ pub struct WindowTabData {
     pub code_action: RwSignal<CodeActionData>,
     pub source_control: SourceControlData,
     pub rename: RenameData,
+    pub shortcuts: ShortcutsData,
+    pub registers: RegistersData,
+    pub scratchpad: ScratchpadData,
+    pub replay: ReplayData,
+    pub burn_down: BurnDown,
     pub global_search: GlobalSearchData,
+    pub problem: ProblemData,
+    pub regex_tester: RegexTesterData,
     pub window_origin: RwSignal<Point>,
     pub layout_rect: RwSignal<Rect>,
     pub proxy: ProxyData,
     pub window_scale: RwSignal<f64>,
+    /// How many points the editor font of the window is zoomed by
+    pub editor_font_zoom: RwSignal<isize>,
     pub set_config: WriteSignal<Arc<LapceConfig>>,
-    pub update_in_progress: RwSignal<bool>,
+    pub update_status: RwSignal<UpdateStatus>,
+    /// The version whose update notification was dismissed
+    pub update_dismissed: RwSignal<Option<String>>,
+    /// The work in progress reported by the language servers, in the order
+    /// it was started
+    pub progresses: RwSignal<IndexMap<ProgressToken, WorkProgress>>,
+    pub server_status_visible: RwSignal<bool>,
     pub latest_release: ReadSignal<Arc<Option<ReleaseInfo>>>,
+    /// The scope of the pending session save, disposed of to cancel it
+    session_save: RwSignal<Option<Scope>>,
+    /// The view to restore after the selection was fitted in an editor
+    fitted_view: RwSignal<Option<FittedView>>,
+    /// The parts of the user's setup safe mode still leaves out, as of the
+    /// last time the config was loaded
+    pub safe_mode: RwSignal<Vec<SafeModePart>>,
+    /// Whether the safe mode notification was dismissed
+    pub safe_mode_dismissed: RwSignal<bool>,
     pub common: CommonData,
 }
 
@@ -159,6 +297,7 @@ pub fn new(
         workspace: Arc<LapceWorkspace>,
         window_command: Listener<WindowCommand>,
         window_scale: RwSignal<f64>,
+        editor_font_zoom: RwSignal<isize>,
         latest_release: ReadSignal<Arc<Option<ReleaseInfo>>>,
     ) -> Self {
         let (cx, _) = cx.run_child_scope(|cx| cx);
@@ -168,10 +307,28 @@ pub fn new(
         let workspace_disabled_volts = db
             .get_workspace_disabled_volts(&workspace)
             .unwrap_or_default();
+        let plugin_permissions = db.get_plugin_permissions().unwrap_or_default();
+        // The workspace's own profile takes precedence over the last used one
+        let profile = db
+            .get_workspace_profile(&workspace)
+            .or_else(|_| db.get_active_profile())
+            .ok()
+            .flatten()
+            .filter(|profile| Profile::exists(profile));
+        let profile_disabled_volts = profile
+            .as_deref()
+            .map(Profile::disabled_volts)
+            .unwrap_or_default();
         let mut all_disabled_volts = disabled_volts.clone();
         all_disabled_volts.extend(workspace_disabled_volts.clone());
+        all_disabled_volts.extend(profile_disabled_volts.clone());
+        if safe_mode::is_left_out(SafeModePart::Plugins) {
+            all_disabled_volts.extend(find_all_volts().iter().map(|meta| meta.id()));
+        }
 
-        let workspace_info = if workspace.path.is_some() {
+        let workspace_info = if safe_mode::is_active() {
+            None
+        } else if workspace.path.is_some() {
             db.get_workspace_info(&workspace).ok()
         } else {
             let mut info = db.get_workspace_info(&workspace).ok();
@@ -181,12 +338,20 @@ pub fn new(
             info
         };
 
-        let config = LapceConfig::load(&workspace, &all_disabled_volts);
+        let mut config = LapceConfig::load(
+            &workspace,
+            profile.as_deref(),
+            &all_disabled_volts,
+            &plugin_permissions,
+        );
+        config.zoom_editor_font(editor_font_zoom.get_untracked());
+        let network_settings = config.core.network_settings();
+        lapce_proxy::network::set_settings(network_settings.clone());
         let lapce_command = Listener::new_empty(cx);
         let workbench_command = Listener::new_empty(cx);
         let internal_command = Listener::new_empty(cx);
         let keypress =
-            create_rw_signal(cx, KeyPressData::new(&config, workbench_command));
+            create_rw_signal(cx, KeyPressData::new(&config, lapce_command));
 
         let (term_tx, term_rx) = crossbeam_channel::unbounded();
         let (term_notification_tx, term_notification_rx) =
@@ -203,16 +368,28 @@ pub fn new(
             workspace.clone(),
             all_disabled_volts,
             config.plugins.clone(),
+            plugin_permissions.clone(),
             term_tx.clone(),
         );
+        proxy.proxy_rpc.update_network_settings(network_settings);
         let (config, set_config) = create_signal(cx, Arc::new(config));
 
         let focus = create_rw_signal(cx, Focus::Workbench);
         let completion = create_rw_signal(cx, CompletionData::new(cx, config));
+        let signature = create_rw_signal(cx, SignatureData::new());
 
         let register = create_rw_signal(cx, Register::default());
         let view_id = create_rw_signal(cx, floem::id::Id::next());
         let find = Find::new(cx);
+        let search_history = SearchHistory::new(
+            cx,
+            workspace.clone(),
+            db.get_search_history(&workspace).unwrap_or_default(),
+        );
+        let file_frecency = create_rw_signal(
+            cx,
+            db.get_file_frecency(&workspace).unwrap_or_default(),
+        );
 
         let ui_line_height = create_memo(cx, move |_| {
             let config = config.get();
@@ -229,14 +406,24 @@ pub fn new(
             text_layout.size().height
         });
 
+        let plugin_providers = create_rw_signal(cx, PluginProviders::default());
+        let inline_suggestions = InlineSuggestionProviders::new(
+            cx,
+            proxy.proxy_rpc.clone(),
+            plugin_providers,
+        );
         let common = CommonData {
             workspace: workspace.clone(),
             scope: cx,
             keypress,
             focus,
             completion,
+            signature,
+            overlays: OverlayManager::new(cx, internal_command),
             register,
             find,
+            search_history,
+            file_frecency,
             window_command,
             internal_command,
             lapce_command,
@@ -244,9 +431,16 @@ pub fn new(
             term_tx,
             term_notification_tx,
             proxy: proxy.proxy_rpc.clone(),
+            plugin_providers,
+            plugin_commands: create_rw_signal(cx, IndexMap::new()),
+            plugin_panels: create_rw_signal(cx, IndexMap::new()),
+            plugin_status_items: create_rw_signal(cx, IndexMap::new()),
+            inline_suggestions,
             view_id,
             ui_line_height,
             dragging: create_rw_signal(cx, None),
+            focus_mode: create_rw_signal(cx, None),
+            encoding_check: create_rw_signal(cx, None),
             config,
         };
 
@@ -268,6 +462,7 @@ pub fn new(
                     parent_split: None,
                     split_id: root_split,
                     children: Vec::new(),
+                    sizes: im::HashMap::new(),
                     direction: SplitDirection::Horizontal,
                     window_origin: Point::ZERO,
                     layout_rect: Rect::ZERO,
@@ -279,12 +474,22 @@ pub fn new(
             });
         }
 
+        let plugin = PluginData::new(
+            cx,
+            HashSet::from_iter(disabled_volts),
+            HashSet::from_iter(workspace_disabled_volts),
+            HashSet::from_iter(profile_disabled_volts),
+            plugin_permissions,
+            common.clone(),
+        );
+
         let palette = PaletteData::new(
             cx,
             workspace.clone(),
             main_split.clone(),
             keypress.read_only(),
             source_control.clone(),
+            plugin.clone(),
             common.clone(),
         );
 
@@ -312,14 +517,31 @@ pub fn new(
             TerminalPanelData::new(workspace.clone(), None, common.clone());
 
         let rename = RenameData::new(cx, common.clone());
+        let shortcuts = ShortcutsData::new(cx, common.clone());
+        let registers = RegistersData::new(cx, common.clone());
+        let scratchpad = ScratchpadData::new(cx, common.clone());
+        let replay = ReplayData::new(cx, common.clone());
+        let burn_down = BurnDown::new(
+            cx,
+            workspace.clone(),
+            db.get_burn_down(&workspace).unwrap_or_default(),
+        );
         let global_search =
             GlobalSearchData::new(cx, main_split.clone(), common.clone());
-
-        let plugin = PluginData::new(
+        let problem = ProblemData::new(cx, main_split.clone(), common.clone());
+        let regex_tester_shown = {
+            let panel = panel.clone();
+            create_memo(cx, move |_| {
+                panel.panels.track();
+                panel.styles.track();
+                panel.is_panel_visible(&PanelKind::RegexTester)
+            })
+        };
+        let regex_tester = RegexTesterData::new(
             cx,
-            HashSet::from_iter(disabled_volts),
-            HashSet::from_iter(workspace_disabled_volts),
+            main_split.clone(),
             common.clone(),
+            regex_tester_shown,
         );
 
         {
@@ -354,14 +576,29 @@ pub fn new(
             source_control,
             plugin,
             rename,
+            shortcuts,
+            registers,
+            scratchpad,
+            replay,
+            burn_down,
             global_search,
+            problem,
+            regex_tester,
             window_origin: create_rw_signal(cx, Point::ZERO),
             layout_rect: create_rw_signal(cx, Rect::ZERO),
             proxy,
             window_scale,
+            editor_font_zoom,
             set_config,
-            update_in_progress: create_rw_signal(cx, false),
+            update_status: create_rw_signal(cx, UpdateStatus::Idle),
+            update_dismissed: create_rw_signal(cx, None),
+            progresses: create_rw_signal(cx, IndexMap::new()),
+            server_status_visible: create_rw_signal(cx, false),
             latest_release,
+            session_save: create_rw_signal(cx, None),
+            fitted_view: create_rw_signal(cx, None),
+            safe_mode: create_rw_signal(cx, safe_mode::left_out()),
+            safe_mode_dismissed: create_rw_signal(cx, false),
             common,
         };
 
@@ -379,6 +616,20 @@ pub fn new(
             });
         }
 
+        {
+            let config = window_tab_data.common.config;
+            let set_config = window_tab_data.set_config;
+            create_effect(cx, move |last_zoom| {
+                let zoom = editor_font_zoom.get();
+                if let Some(last_zoom) = last_zoom.filter(|z| *z != zoom) {
+                    let mut new_config = config.get_untracked().as_ref().clone();
+                    new_config.zoom_editor_font(zoom - last_zoom);
+                    set_config.set(Arc::new(new_config));
+                }
+                zoom
+            });
+        }
+
         {
             let window_tab_data = window_tab_data.clone();
             let internal_command = window_tab_data.common.internal_command;
@@ -399,23 +650,212 @@ pub fn new(
             });
         }
 
+        {
+            let window_tab_data = window_tab_data.clone();
+            let request = window_tab_data.proxy.request;
+            create_effect(cx, move |_| {
+                request.with(|request| {
+                    if let Some((id, rpc)) = request.as_ref() {
+                        window_tab_data.handle_core_request(*id, rpc);
+                    }
+                });
+            });
+        }
+
+        {
+            // The popups over the editors close when something else takes the
+            // focus, or another editor becomes the active one
+            let focus = window_tab_data.common.focus;
+            let active_editor = window_tab_data.main_split.active_editor;
+            let overlays = window_tab_data.common.overlays;
+            create_effect(cx, move |last: Option<Option<EditorId>>| {
+                let blurred = !matches!(
+                    focus.get(),
+                    Focus::Workbench | Focus::CodeAction | Focus::Rename
+                );
+                let editor_id = active_editor
+                    .get()
+                    .map(|editor| editor.with_untracked(|e| e.editor_id));
+                if last.is_some() && (blurred || last != Some(editor_id)) {
+                    overlays.dismiss(OverlayEvent::Blur);
+                }
+                editor_id
+            });
+        }
+
+        {
+            // The plugins are told about the file in the active editor
+            let active_editor = window_tab_data.main_split.active_editor;
+            let proxy = window_tab_data.common.proxy.clone();
+            create_effect(cx, move |last: Option<Option<PathBuf>>| {
+                let path = active_editor.get().and_then(|editor| {
+                    let doc = editor.with_untracked(|e| e.view.doc);
+                    doc.with(|doc| doc.content.path().cloned())
+                });
+                if last.as_ref() != Some(&path)
+                    && proxy.supports(Capability::PluginApi)
+                {
+                    proxy.active_editor_changed(path.clone());
+                }
+                path
+            });
+        }
+
+        {
+            let window_tab_data = window_tab_data.clone();
+            create_effect(cx, move |last: Option<()>| {
+                window_tab_data.track_session();
+                if last.is_some() {
+                    window_tab_data.session_change();
+                }
+            });
+        }
+
+        // An update that changed the default keymaps shouldn't go unnoticed by
+        // the keys the user is used to
+        if !KEYMAP_CHANGES_CHECKED.swap(true, Ordering::Relaxed) {
+            let modal = window_tab_data.common.config.get_untracked().core.modal;
+            if !KeyPressData::unreviewed_keymap_changes(&db, modal).is_empty() {
+                window_tab_data
+                    .common
+                    .workbench_command
+                    .send(LapceWorkbenchCommand::ReviewKeymapChanges);
+            }
+        }
+
         window_tab_data
     }
 
+    /// Subscribe to what's saved in the session: the layout of the splits,
+    /// the tabs in them, the cursors and scroll positions of the editors and
+    /// the panels.
+    fn track_session(&self) {
+        let main_split = &self.main_split;
+        main_split.active_editor_tab.with(|_| {});
+        main_split.splits.with(|splits| {
+            for split in splits.values() {
+                split.with(|_| {});
+            }
+        });
+        main_split.editor_tabs.with(|editor_tabs| {
+            for editor_tab in editor_tabs.values() {
+                editor_tab.with(|_| {});
+            }
+        });
+        main_split.editors.with(|editors| {
+            for editor in editors.values() {
+                editor.with(|editor| {
+                    editor.cursor.with(|_| {});
+                    editor.viewport.with(|_| {});
+                });
+            }
+        });
+        main_split.diff_editors.with(|diff_editors| {
+            for diff_editor in diff_editors.values() {
+                diff_editor.right.with(|editor| {
+                    editor.cursor.with(|_| {});
+                    editor.viewport.with(|_| {});
+                });
+            }
+        });
+        self.panel.styles.with(|_| {});
+        self.panel.size.with(|_| {});
+    }
+
+    /// Save the session a moment after it stops changing, so that it's
+    /// restored even if Lapce doesn't get to save it when quitting.
+    fn session_change(&self) {
+        if let Some(cx) = self.session_save.get_untracked() {
+            cx.dispose();
+        }
+        let (cx, _) = self.scope.run_child_scope(|cx| cx);
+        self.session_save.set(Some(cx));
+        let window_tab_data = self.clone();
+        exec_after(cx, SESSION_SAVE_DELAY, move || {
+            window_tab_data.session_save.set(None);
+            let db: Arc<LapceDb> = use_context(window_tab_data.scope).unwrap();
+            let _ = db.save_window_tab(Arc::new(window_tab_data.clone()));
+            cx.dispose();
+        });
+    }
+
     pub fn reload_config(&self) {
+        let profile = self.common.config.get_untracked().profile.clone();
+        self.load_config(profile.as_deref());
+    }
+
+    /// Load the config, keymaps and plugins with the profile applied, `None`
+    /// for the default one.
+    fn load_config(&self, profile: Option<&str>) {
         let db: Arc<LapceDb> = use_context(self.scope).unwrap();
 
         let disabled_volts = db.get_disabled_volts().unwrap_or_default();
         let workspace_disabled_volts = db
             .get_workspace_disabled_volts(&self.workspace)
             .unwrap_or_default();
+        let profile_disabled_volts =
+            profile.map(Profile::disabled_volts).unwrap_or_default();
         let mut all_disabled_volts = disabled_volts;
         all_disabled_volts.extend(workspace_disabled_volts);
+        all_disabled_volts.extend(profile_disabled_volts.clone());
+        let left_out = safe_mode::left_out();
+        if left_out.contains(&SafeModePart::Plugins) {
+            all_disabled_volts.extend(find_all_volts().iter().map(|meta| meta.id()));
+        }
 
-        let config = LapceConfig::load(&self.workspace, &all_disabled_volts);
+        let plugin_permissions = db.get_plugin_permissions().unwrap_or_default();
+        let mut config = LapceConfig::load(
+            &self.workspace,
+            profile,
+            &all_disabled_volts,
+            &plugin_permissions,
+        );
+        self.main_split.update_grammars();
+        self.main_split.update_word_chars();
+        // The plugins that were disabled or uninstalled can't take their
+        // decorations away anymore
+        let enabled_volts: HashSet<VoltID> = find_all_volts()
+            .iter()
+            .map(|meta| meta.id())
+            .filter(|id| !all_disabled_volts.contains(id))
+            .collect();
+        self.main_split.retain_decorations(&enabled_volts);
+        config.zoom_editor_font(self.editor_font_zoom.get_untracked());
+        let network_settings = config.core.network_settings();
+        lapce_proxy::network::set_settings(network_settings.clone());
+        self.common.proxy.update_network_settings(network_settings);
+        self.common
+            .proxy
+            .update_plugin_configs(config.plugins.clone());
+        self.common
+            .keypress
+            .set(KeyPressData::new(&config, self.common.lapce_command));
+        self.plugin
+            .set_profile_disabled(HashSet::from_iter(profile_disabled_volts));
+        // The plugins safe mode left out until now are loaded
+        if self
+            .safe_mode
+            .with_untracked(|parts| parts.contains(&SafeModePart::Plugins))
+            && !left_out.contains(&SafeModePart::Plugins)
+        {
+            for volt in self.plugin.installed.get_untracked().values() {
+                let info = volt.meta.get_untracked().info();
+                if !self.plugin.plugin_disabled(&info.id()) {
+                    self.common.proxy.enable_volt(info);
+                }
+            }
+        }
+        self.safe_mode.set(left_out);
         self.set_config.set(Arc::new(config));
     }
 
+    /// Turn a part of the user's setup that safe mode left out back on, in all
+    /// the windows
+    pub fn turn_on_safe_mode_part(&self, part: SafeModePart) {
+        safe_mode::turn_on(part);
+        self.common.window_command.send(WindowCommand::ReloadConfig);
+    }
+
     pub fn run_lapce_command(&self, cmd: LapceCommand) {
         match cmd.kind {
             CommandKind::Workbench(command) => {
@@ -505,20 +945,40 @@ pub fn run_workbench_command(
                 // TODO: needs scratch files
             }
             RevealActiveFileInFileExplorer => {
-                if let Some(editor_data) = self.main_split.active_editor.get() {
-                    editor_data.with_untracked(|editor_data| {
-                        let path = editor_data.view.doc.with_untracked(|doc| {
-                            if let DocContent::File(path) = &doc.content {
-                                Some(path.clone())
-                            } else {
-                                None
-                            }
-                        });
-                        let Some(path) = path else { return };
-                        let path = path.parent().unwrap_or(&path);
-
-                        open_uri(path);
-                    });
+                // The file is revealed where it was opened, if that was
+                // through a symlink
+                let editor = self.main_split.active_editor.get_untracked();
+                let symlink = editor.and_then(|editor| {
+                    let doc = editor.with_untracked(|editor| editor.view.doc);
+                    doc.with_untracked(|doc| doc.symlink.clone())
+                });
+                if let Some(path) = symlink.or_else(|| self.active_file_path()) {
+                    self.reveal_in_file_explorer(path);
+                }
+            }
+            RevealActiveFileInFileManager => {
+                if let Some(path) = self.active_file_path() {
+                    reveal_in_file_manager(&path);
+                }
+            }
+            OpenActiveFileFolderInTerminal => {
+                if let Some(path) = self.active_file_path() {
+                    self.open_in_terminal(&path);
+                }
+            }
+            CopyActiveFilePath => {
+                if let Some(path) = self.active_file_path() {
+                    self.copy_path(&path, false);
+                }
+            }
+            CopyActiveFileRelativePath => {
+                if let Some(path) = self.active_file_path() {
+                    self.copy_path(&path, true);
+                }
+            }
+            CopyActiveFileName => {
+                if let Some(path) = self.active_file_path() {
+                    copy_file_name(&path);
                 }
             }
 
@@ -573,6 +1033,88 @@ pub fn run_workbench_command(
             OpenKeyboardShortcuts => {
                 // TODO: open keyboard shortcuts
             }
+            ShowShortcutCheatSheet => {
+                self.shortcuts.show();
+            }
+            ShowRegisters => {
+                self.registers.show();
+            }
+            DryRunKeys => {
+                self.palette.run(cx, PaletteKind::DryRunKeys);
+            }
+            ToggleScratchpad => {
+                self.scratchpad.toggle();
+            }
+            ReplayEdits => {
+                let Some(editor) = self.main_split.active_editor.get_untracked()
+                else {
+                    return;
+                };
+                let doc = editor.with_untracked(|editor| editor.view.doc);
+                let title = doc.with_untracked(|doc| {
+                    doc.content
+                        .path()
+                        .and_then(|path| path.file_name())
+                        .map(|name| name.to_string_lossy().to_string())
+                        .unwrap_or_default()
+                });
+                self.replay.open(doc, title);
+            }
+            SummarizeFileChanges => {
+                if let Some(summary) = self.file_changes_summary() {
+                    self.main_split.open_report(summary);
+                }
+            }
+            InsertFileChangesInCommitMessage => {
+                let Some(summary) = self.file_changes_summary() else {
+                    return;
+                };
+                let doc = self.source_control.editor.view.doc;
+                let message = doc.with_untracked(|doc| doc.buffer().to_string());
+                let message = if message.trim().is_empty() {
+                    summary
+                } else {
+                    format!("{}\n\n{summary}", message.trim_end())
+                };
+                doc.update(|doc| doc.reload(Rope::from(message), true));
+                self.show_panel(PanelKind::SourceControl);
+            }
+            ToggleFocusMode => {
+                self.toggle_focus_mode();
+            }
+            ToggleFitSelection => {
+                self.toggle_fit_selection();
+            }
+            ToggleScrollLock => {
+                self.main_split.toggle_scroll_lock();
+            }
+            ToggleIgnoredFiles => {
+                let show = self
+                    .common
+                    .config
+                    .with_untracked(|config| config.core.show_ignored_files);
+                // The config file is watched
+                LapceConfig::update_file(
+                    "core",
+                    "show-ignored-files",
+                    toml_edit::Value::from(!show),
+                );
+            }
+            BurnDownDiagnostics => {
+                self.palette.run(cx, PaletteKind::BurnDown);
+            }
+            BurnDownNext => {
+                self.burn_down.next(&self.main_split);
+            }
+            BurnDownSkip => {
+                self.burn_down.skip(&self.main_split);
+            }
+            BurnDownQuickFix => {
+                self.burn_down.quick_fix(&self.main_split);
+            }
+            BurnDownStop => {
+                self.burn_down.stop();
+            }
             OpenKeyboardShortcutsFile => {
                 if let Some(path) = LapceConfig::keymaps_file() {
                     self.main_split.jump_to_location(
@@ -618,6 +1160,55 @@ pub fn run_workbench_command(
                     open_uri(&dir);
                 }
             }
+            ReviewKeymapChanges => {
+                self.palette.keymap_change.set(None);
+                self.palette.run(cx, PaletteKind::KeymapChanges);
+            }
+            ManagePluginPermissions => {
+                self.palette.plugin_permissions_volt.set(None);
+                self.palette.run(cx, PaletteKind::PluginPermissions);
+            }
+            RunPluginCommand => {
+                let Some(command) = data
+                    .and_then(|data| serde_json::from_value::<String>(data).ok())
+                else {
+                    return;
+                };
+                let plugin_id =
+                    self.common.plugin_commands.with_untracked(|commands| {
+                        commands.get(&command).map(|(_, plugin_id, _)| *plugin_id)
+                    });
+                let Some(plugin_id) = plugin_id else {
+                    error!("No plugin registered the command {command}");
+                    return;
+                };
+                let context = self.plugin_command_context(command);
+                let window_tab_data = self.clone();
+                let send = create_ext_action(cx, move |result| match result {
+                    Ok(ProxyResponse::ExecutePluginCommandResponse { result }) => {
+                        window_tab_data.apply_plugin_command_result(result);
+                    }
+                    Ok(_) => {}
+                    Err(err) => error!("{}", err.message),
+                });
+                self.common.proxy.execute_plugin_command(
+                    plugin_id,
+                    context,
+                    move |result| {
+                        send(result);
+                    },
+                );
+            }
+            OpenProfilesDirectory => {
+                if let Some(dir) = Directory::profiles_directory() {
+                    open_uri(&dir);
+                }
+            }
+            SetWorkspaceProfile => {
+                let profile = self.common.config.get_untracked().profile.clone();
+                let db: Arc<LapceDb> = use_context(self.scope).unwrap();
+                db.save_workspace_profile(self.workspace.clone(), profile);
+            }
 
             InstallTheme => {}
             ExportCurrentThemeSettings => {}
@@ -714,6 +1305,30 @@ pub fn run_workbench_command(
                     });
                 }
             }
+            TogglePinEditorTab => {
+                if let Some(editor_tab_id) =
+                    self.main_split.active_editor_tab.get_untracked()
+                {
+                    self.main_split.editor_tab_toggle_pin(editor_tab_id);
+                }
+            }
+            CloseOtherEditorTabs => {
+                if let Some(editor_tab_id) =
+                    self.main_split.active_editor_tab.get_untracked()
+                {
+                    self.main_split.editor_tab_close_others(cx, editor_tab_id);
+                }
+            }
+            CloseAllEditorTabs => {
+                if let Some(editor_tab_id) =
+                    self.main_split.active_editor_tab.get_untracked()
+                {
+                    self.main_split.editor_tab_close(cx, editor_tab_id);
+                }
+            }
+            ReopenClosedEditor => {
+                self.main_split.reopen_closed_editor();
+            }
 
             // ==== Terminal ====
             NewTerminalTab => {
@@ -798,6 +1413,40 @@ pub fn run_workbench_command(
             PaletteSCMReferences => {
                 self.palette.run(cx, PaletteKind::SCMReferences);
             }
+            PaletteRecentlyClosed => {
+                self.palette.run(cx, PaletteKind::RecentlyClosed);
+            }
+            PaletteRegisters => {
+                self.palette.run(cx, PaletteKind::Register);
+            }
+            PaletteSearchHistory => {
+                self.palette.run(cx, PaletteKind::SearchHistory);
+            }
+            PaletteProfile => {
+                self.palette.run(cx, PaletteKind::Profile);
+            }
+            OpenSearchResultsInEditor => {
+                self.global_search.open_results_buffer();
+            }
+            OpenNotebookRawJson => {
+                if let Some(path) = self.main_split.active_notebook() {
+                    self.main_split.open_location(
+                        EditorLocation {
+                            path,
+                            position: None,
+                            scroll_offset: None,
+                            ignore_unconfirmed: false,
+                            same_editor_tab: false,
+                        },
+                        None,
+                    );
+                }
+            }
+            ExportNotebookToPython => {
+                if let Some(path) = self.main_split.active_notebook() {
+                    self.main_split.export_notebook(path);
+                }
+            }
             ChangeColorTheme => {
                 self.palette.run(cx, PaletteKind::ColorTheme);
             }
@@ -807,6 +1456,12 @@ pub fn run_workbench_command(
             ChangeFileLanguage => {
                 self.palette.run(cx, PaletteKind::Language);
             }
+            SetTabWidth => {
+                self.palette.run(cx, PaletteKind::TabWidth);
+            }
+            ConvertIndentationToSpaces => {
+                self.palette.run(cx, PaletteKind::IndentWidth);
+            }
 
             // ==== Running / Debugging ====
             RunAndDebugRestart => {
@@ -824,6 +1479,74 @@ pub fn run_workbench_command(
                     self.terminal.stop_run_debug(term_id);
                 }
             }
+            RunCurrentFile => {
+                let Some(editor) = self.main_split.active_editor.get_untracked()
+                else {
+                    return;
+                };
+                let doc = editor.with_untracked(|editor| editor.view.doc);
+                let config = doc.with_untracked(|doc| {
+                    let DocContent::File(path) = &doc.content else {
+                        return None;
+                    };
+                    let first_line = doc.buffer().line_content(0).to_string();
+                    let language = doc.syntax().language.to_string();
+                    self.common.config.with_untracked(|config| {
+                        current_file_run_config(
+                            path,
+                            &first_line,
+                            &language,
+                            &config.terminal.runners,
+                        )
+                    })
+                });
+                if let Some(config) = config {
+                    self.palette.run_current_file.set(Some(config));
+                    self.palette.run(cx, PaletteKind::RunCurrentFile);
+                } else {
+                    error!("No shebang or runner found for the current file");
+                }
+            }
+            RunTask => {
+                let Some(name) = data
+                    .and_then(|data| serde_json::from_value::<String>(data).ok())
+                else {
+                    self.palette.run(cx, PaletteKind::RunAndDebug);
+                    return;
+                };
+                let config = run_configs(self.workspace.path.as_deref()).and_then(
+                    |configs| {
+                        configs
+                            .configs
+                            .into_iter()
+                            .find(|config| config.name == name)
+                    },
+                );
+                match config {
+                    Some(config) => {
+                        self.run_and_debug(cx, &RunDebugMode::Run, &config)
+                    }
+                    None => error!("No task named {name} in .lapce/run.toml"),
+                }
+            }
+            RerunLastTask => match self.terminal.debug.last_run.get_untracked() {
+                Some((mode, config)) => self.run_and_debug(cx, &mode, &config),
+                None => self.palette.run(cx, PaletteKind::RunAndDebug),
+            },
+            RestartTask => {
+                let running = self
+                    .terminal
+                    .run_debug_processes()
+                    .into_iter()
+                    .rev()
+                    .find(|(_, process)| !process.stopped);
+                match running {
+                    Some((term_id, _)) => {
+                        self.terminal.restart_run_debug(term_id);
+                    }
+                    None => self.run_workbench_command(RerunLastTask, None),
+                }
+            }
 
             // ==== UI ====
             ZoomIn => {
@@ -845,6 +1568,15 @@ pub fn run_workbench_command(
             ZoomReset => {
                 self.window_scale.set(1.0);
             }
+            EditorFontZoomIn => {
+                self.zoom_editor_font(1);
+            }
+            EditorFontZoomOut => {
+                self.zoom_editor_font(-1);
+            }
+            EditorFontZoomReset => {
+                self.editor_font_zoom.set(0);
+            }
 
             ToggleMaximizedPanel => {
                 if let Some(data) = data {
@@ -910,6 +1642,12 @@ pub fn run_workbench_command(
             ToggleSearchFocus => {
                 self.toggle_panel_focus(PanelKind::Search);
             }
+            ToggleRegexTesterFocus => {
+                self.toggle_panel_focus(PanelKind::RegexTester);
+            }
+            TogglePluginViewsFocus => {
+                self.toggle_panel_focus(PanelKind::PluginViews);
+            }
             ToggleTerminalVisual => {
                 self.toggle_panel_visual(PanelKind::Terminal);
             }
@@ -931,6 +1669,12 @@ pub fn run_workbench_command(
             ToggleSearchVisual => {
                 self.toggle_panel_visual(PanelKind::Search);
             }
+            ToggleRegexTesterVisual => {
+                self.toggle_panel_visual(PanelKind::RegexTester);
+            }
+            TogglePluginViewsVisual => {
+                self.toggle_panel_visual(PanelKind::PluginViews);
+            }
             FocusEditor => {
                 self.common.focus.set(Focus::Workbench);
             }
@@ -982,43 +1726,36 @@ pub fn run_workbench_command(
             SourceControlDiscardWorkspaceChanges => {
                 // TODO:
             }
+            SourceControlShowFileHistory => {
+                self.palette.file_commit.set(None);
+                self.palette.run(cx, PaletteKind::FileHistory);
+            }
 
             // ==== UI ====
             ShowAbout => {}
+            ToggleServerStatus => {
+                self.server_status_visible
+                    .update(|visible| *visible = !*visible);
+            }
 
             // ==== Updating ====
+            CheckForUpdates => {
+                self.update_dismissed.set(None);
+                self.common
+                    .window_command
+                    .send(WindowCommand::CheckForUpdates);
+            }
+            DownloadUpdate => {
+                self.download_update(false);
+            }
             RestartToUpdate => {
-                if let Some(release) = self.latest_release.get_untracked().as_ref() {
-                    let release = release.clone();
-                    let update_in_progress = self.update_in_progress;
-                    if release.version != *meta::VERSION {
-                        if let Ok(process_path) = env::current_exe() {
-                            update_in_progress.set(true);
-                            let send =
-                                create_ext_action(self.scope, move |_started| {
-                                    update_in_progress.set(false);
-                                });
-                            std::thread::spawn(move || {
-                                let do_update = || -> anyhow::Result<()> {
-                                    let src =
-                                        crate::update::download_release(&release)?;
-
-                                    let path =
-                                        crate::update::extract(&src, &process_path)?;
-
-                                    crate::update::restart(&path)?;
-
-                                    Ok(())
-                                };
-
-                                if let Err(err) = do_update() {
-                                    error!("Failed to update: {err}");
-                                }
-
-                                send(false);
-                            });
-                        }
+                if let UpdateStatus::Ready(path) = self.update_status.get_untracked()
+                {
+                    if let Err(err) = crate::update::restart(&path) {
+                        error!("Failed to restart to update: {err}");
                     }
+                } else {
+                    self.download_update(true);
                 }
             }
 
@@ -1042,7 +1779,21 @@ pub fn run_workbench_command(
             NextError => {
                 self.main_split.next_error();
             }
-            PreviousError => {}
+            PreviousError => {
+                self.main_split.previous_error();
+            }
+            NextDiagnosticInFile => {
+                self.main_split.jump_to_diagnostic_in_file(false);
+            }
+            PreviousDiagnosticInFile => {
+                self.main_split.jump_to_diagnostic_in_file(true);
+            }
+            NextChange => {
+                self.main_split.next_change(&self.changed_files());
+            }
+            PreviousChange => {
+                self.main_split.previous_change(&self.changed_files());
+            }
             Quit => {}
         }
     }
@@ -1058,6 +1809,13 @@ pub fn run_internal_command(&self, cmd: InternalCommand) {
                 debug!("{level}");
             }
             InternalCommand::OpenFile { path } => {
+                // An archive is browsed in the explorer rather than opened
+                if is_archive(&path)
+                    && self.common.proxy.supports(Capability::Archives)
+                {
+                    self.reveal_in_file_explorer(path);
+                    return;
+                }
                 self.main_split.jump_to_location(
                     EditorLocation {
                         path,
@@ -1097,6 +1855,19 @@ pub fn run_internal_command(&self, cmd: InternalCommand) {
             InternalCommand::SplitExchange { editor_tab_id } => {
                 self.main_split.split_exchange(cx, editor_tab_id);
             }
+            InternalCommand::SplitRotate { editor_tab_id } => {
+                self.main_split.split_rotate(editor_tab_id);
+            }
+            InternalCommand::SplitResize {
+                editor_tab_id,
+                delta,
+            } => {
+                self.main_split
+                    .split_resize_editor_tab(editor_tab_id, delta);
+            }
+            InternalCommand::SplitMaximize { editor_tab_id } => {
+                self.main_split.split_maximize(editor_tab_id);
+            }
             InternalCommand::EditorTabClose { editor_tab_id } => {
                 self.main_split.editor_tab_close(cx, editor_tab_id);
             }
@@ -1107,6 +1878,36 @@ pub fn run_internal_command(&self, cmd: InternalCommand) {
                 self.main_split
                     .editor_tab_child_close(cx, editor_tab_id, child);
             }
+            InternalCommand::EditorTabChildMove {
+                from_editor_tab_id,
+                child,
+                to_editor_tab_id,
+                index,
+            } => {
+                self.main_split.editor_tab_child_move(
+                    cx,
+                    from_editor_tab_id,
+                    child,
+                    to_editor_tab_id,
+                    index,
+                );
+            }
+            InternalCommand::EditorTabChildSplit {
+                from_editor_tab_id,
+                child,
+                to_editor_tab_id,
+                direction,
+                after,
+            } => {
+                self.main_split.editor_tab_child_split(
+                    cx,
+                    from_editor_tab_id,
+                    child,
+                    to_editor_tab_id,
+                    direction,
+                    after,
+                );
+            }
             InternalCommand::ShowCodeActions {
                 offset,
                 mouse_click,
@@ -1153,9 +1954,50 @@ pub fn run_internal_command(&self, cmd: InternalCommand) {
             } => {
                 self.rename.start(path, placeholder, start, position);
             }
+            InternalCommand::CloseOverlay { kind } => match kind {
+                OverlayKind::Completion => {
+                    let editor = self.common.completion.with_untracked(|c| {
+                        c.latest_editor_id.and_then(|id| {
+                            self.main_split
+                                .editors
+                                .with_untracked(|editors| editors.get(&id).cloned())
+                        })
+                    });
+                    match editor {
+                        Some(editor) => editor.get_untracked().cancel_completion(),
+                        None => self.common.completion.update(|c| c.cancel()),
+                    }
+                }
+                OverlayKind::Signature => {
+                    self.common.signature.update(|signature| signature.cancel());
+                }
+                OverlayKind::CodeAction => {
+                    self.code_action.get_untracked().cancel();
+                }
+                OverlayKind::Rename => {
+                    self.rename.cancel();
+                }
+                OverlayKind::Hunk => {
+                    self.main_split.editors.with_untracked(|editors| {
+                        for editor in editors.values() {
+                            editor.with_untracked(|editor| {
+                                editor.hunk_popup.set(None)
+                            });
+                        }
+                    });
+                }
+            },
             InternalCommand::Search { pattern } => {
                 self.main_split.set_find_pattern(pattern);
             }
+            InternalCommand::NavigateFindHistory { replace, previous } => {
+                let (kind, editor) = if replace {
+                    (SearchHistoryKind::Replace, &self.main_split.replace_editor)
+                } else {
+                    (SearchHistoryKind::Search, &self.main_split.find_editor)
+                };
+                self.common.search_history.navigate(kind, editor, previous);
+            }
             InternalCommand::FindEditorReceiveChar { s } => {
                 self.main_split.find_editor.receive_char(&s);
             }
@@ -1183,6 +2025,21 @@ pub fn run_internal_command(&self, cmd: InternalCommand) {
             InternalCommand::FocusEditorTab { editor_tab_id } => {
                 self.main_split.active_editor_tab.set(Some(editor_tab_id));
             }
+            InternalCommand::CopyPath { path, relative } => {
+                self.copy_path(&path, relative);
+            }
+            InternalCommand::CopyFileName { path } => {
+                copy_file_name(&path);
+            }
+            InternalCommand::RevealInFileExplorer { path } => {
+                self.reveal_in_file_explorer(path);
+            }
+            InternalCommand::RevealInFileManager { path } => {
+                reveal_in_file_manager(&path);
+            }
+            InternalCommand::OpenInTerminal { path } => {
+                self.open_in_terminal(&path);
+            }
             InternalCommand::SetColorTheme { name, save } => {
                 if save {
                     // The config file is watched
@@ -1211,6 +2068,133 @@ pub fn run_internal_command(&self, cmd: InternalCommand) {
                     self.set_config.set(Arc::new(new_config));
                 }
             }
+            InternalCommand::ApplySearchResults => {
+                self.global_search.apply_results_buffer();
+            }
+            InternalCommand::SetProfile { name } => {
+                let db: Arc<LapceDb> = use_context(self.scope).unwrap();
+                db.save_active_profile(name.clone());
+                self.load_config(name.as_deref());
+            }
+            InternalCommand::ReviewPluginPermissions { volt } => {
+                self.palette.plugin_permissions_volt.set(Some(volt));
+                self.palette.run(cx, PaletteKind::PluginPermissions);
+            }
+            InternalCommand::StartBurnDown { kind } => {
+                self.burn_down.start(&self.main_split, kind);
+            }
+            InternalCommand::OpenFileCommit { path, commit } => {
+                self.palette.file_commit.set(Some((path, commit)));
+                self.palette.run(cx, PaletteKind::FileHistory);
+            }
+            InternalCommand::ReviewKeymapChange { change } => {
+                self.palette.keymap_change.set(Some(change));
+                self.palette.run(cx, PaletteKind::KeymapChanges);
+            }
+            InternalCommand::DryRunKeys { keys } => {
+                let Some(editor) = self.main_split.active_editor.get_untracked()
+                else {
+                    return;
+                };
+                let report = self.common.keypress.with_untracked(|keypress| {
+                    editor.with_untracked(|editor| keypress.dry_run(&keys, editor))
+                });
+                self.main_split.open_report(report);
+            }
+            InternalCommand::GlobalSearch { pattern } => {
+                self.show_panel(PanelKind::Search);
+                self.global_search.set_pattern(pattern);
+            }
+        }
+    }
+
+    /// Run the command of the editor a plugin asked for, by its name in the
+    /// keymaps
+    pub fn run_command_from_plugin(&self, command: &str, data: Option<Value>) {
+        let cmd = self
+            .common
+            .keypress
+            .with_untracked(|keypress| keypress.command(command));
+        match cmd {
+            Some(mut cmd) => {
+                if data.is_some() {
+                    cmd.data = data;
+                }
+                self.common.lapce_command.send(cmd);
+            }
+            None => error!("a plugin ran the unknown command {command}"),
+        }
+    }
+
+    /// Where the active editor is, for a plugin command to run on
+    fn plugin_command_context(&self, command: String) -> PluginCommandContext {
+        let mut context = PluginCommandContext {
+            command,
+            uri: None,
+            selections: Vec::new(),
+            cursor: None,
+        };
+        let Some(editor) = self.main_split.active_editor.get_untracked() else {
+            return context;
+        };
+        let (doc, cursor) = editor.with_untracked(|editor| {
+            (editor.view.doc, editor.cursor.get_untracked())
+        });
+        doc.with_untracked(|doc| {
+            let buffer = doc.buffer();
+            context.uri = doc
+                .content
+                .path()
+                .and_then(|path| Url::from_file_path(path).ok());
+            // The block cursor of normal mode is where the cursor is, not a
+            // selection of the character under it
+            let selection = match cursor.mode {
+                CursorMode::Normal(offset) => Selection::caret(offset),
+                _ => cursor.edit_selection(buffer),
+            };
+            context.selections = selection
+                .regions()
+                .iter()
+                .map(|region| Range {
+                    start: buffer.offset_to_position(region.min()),
+                    end: buffer.offset_to_position(region.max()),
+                })
+                .collect();
+            context.cursor = Some(buffer.offset_to_position(cursor.offset()));
+        });
+        context
+    }
+
+    /// Apply the edits a plugin command returned, then run its commands
+    fn apply_plugin_command_result(&self, result: PluginCommandResult) {
+        if let Some(edit) = result.edit.as_ref() {
+            self.main_split.apply_workspace_edit(edit);
+        }
+        for cmd in result.commands {
+            self.run_command_from_plugin(&cmd.command, cmd.data);
+        }
+    }
+
+    fn handle_core_request(&self, id: RequestId, rpc: &CoreRequest) {
+        match rpc {
+            CoreRequest::ApplyWorkspaceEdit { edit } => {
+                let core_rpc = self.proxy.core_rpc.clone();
+                let respond = move |result: Result<(), EditSessionError>| {
+                    core_rpc.handle_response(
+                        id,
+                        Ok(CoreResponse::ApplyWorkspaceEditResponse {
+                            applied: result.is_ok(),
+                            failure_reason: result.err().map(|e| e.to_string()),
+                        }),
+                    );
+                };
+                match EditSession::from_workspace_edit(edit) {
+                    Ok(session) => {
+                        self.main_split.apply_edit_session(session, respond)
+                    }
+                    Err(err) => respond(Err(err)),
+                }
+            }
         }
     }
 
@@ -1251,7 +2235,22 @@ fn handle_core_notification(&self, rpc: &CoreNotification) {
                 plugin_id,
             } => {
                 self.common.completion.update(|completion| {
-                    completion.receive(*request_id, input, resp, *plugin_id);
+                    let language_id = language_id_from_path(&completion.path);
+                    let priority =
+                        self.common.plugin_providers.with_untracked(|providers| {
+                            providers.priority(
+                                plugin_id,
+                                ProviderKind::Completion,
+                                language_id,
+                            )
+                        });
+                    completion.receive(
+                        *request_id,
+                        input,
+                        resp,
+                        *plugin_id,
+                        priority,
+                    );
 
                     let editor_data = completion.latest_editor_id.and_then(|id| {
                         self.main_split
@@ -1270,6 +2269,39 @@ fn handle_core_notification(&self, rpc: &CoreNotification) {
                         });
                     }
                 });
+                let auto_insert_editor =
+                    self.common.completion.with_untracked(|completion| {
+                        if !completion.should_auto_insert() {
+                            return None;
+                        }
+                        completion.latest_editor_id.and_then(|id| {
+                            self.main_split
+                                .editors
+                                .with_untracked(|tabs| tabs.get(&id).cloned())
+                        })
+                    });
+                if let Some(editor_data) = auto_insert_editor {
+                    editor_data.with_untracked(|editor_data| {
+                        editor_data.select_completion()
+                    });
+                } else if self.common.completion.with_untracked(|completion| {
+                    completion.status != CompletionStatus::Inactive
+                        && !completion.filtered_items.is_empty()
+                }) {
+                    self.common.overlays.show(OverlayKind::Completion);
+                }
+            }
+            CoreNotification::SignatureHelpResponse {
+                request_id,
+                resp,
+                plugin_id,
+            } => {
+                self.common.signature.update(|signature| {
+                    signature.receive(*request_id, resp, *plugin_id);
+                });
+                if self.common.signature.with_untracked(|s| s.is_active()) {
+                    self.common.overlays.show(OverlayKind::Signature);
+                }
             }
             CoreNotification::PublishDiagnostics { diagnostics } => {
                 let path = path_from_url(&diagnostics.uri);
@@ -1289,11 +2321,7 @@ fn handle_core_notification(&self, rpc: &CoreNotification) {
                     .set(diagnostics);
 
                 // inform the document about the diagnostics
-                if let Some(doc) = self
-                    .main_split
-                    .docs
-                    .with_untracked(|docs| docs.get(&path).cloned())
-                {
+                if let Some(doc) = self.main_split.doc_of_path(&path) {
                     doc.update(|doc| doc.init_diagnostics());
                 }
             }
@@ -1339,16 +2367,135 @@ fn handle_core_notification(&self, rpc: &CoreNotification) {
             CoreNotification::OpenFileChanged { path, content } => {
                 self.main_split.open_file_changed(path, content);
             }
+            CoreNotification::WorkspaceFileChange { changes } => {
+                self.file_explorer.files_changed(changes);
+                self.main_split.files_changed(changes);
+            }
+            CoreNotification::WorkspaceConfigChange {} => {
+                self.reload_config();
+            }
             CoreNotification::VoltInstalled { volt, icon } => {
                 self.plugin.volt_installed(volt, icon);
             }
             CoreNotification::VoltRemoved { volt, .. } => {
                 self.plugin.volt_removed(volt);
             }
+            CoreNotification::WorkDoneProgress {
+                progress,
+                server_name,
+            } => {
+                self.update_progress(progress, server_name);
+            }
+            CoreNotification::ProviderRegistered {
+                plugin_id,
+                registration,
+            } => {
+                self.common.plugin_providers.update(|providers| {
+                    providers.register(*plugin_id, registration.clone());
+                });
+            }
+            CoreNotification::PluginCommandRegistered {
+                volt,
+                plugin_id,
+                command,
+            } => {
+                self.common.plugin_commands.update(|commands| {
+                    commands.insert(
+                        command.command.clone(),
+                        (volt.clone(), *plugin_id, command.clone()),
+                    );
+                });
+            }
+            CoreNotification::RunCommand { command, data } => {
+                self.run_command_from_plugin(command, data.clone());
+            }
+            CoreNotification::PluginPanelChanged { volt, id, panel } => {
+                let key = (volt.clone(), id.clone());
+                self.common.plugin_panels.update(|panels| match panel {
+                    Some(panel) => {
+                        panels.insert(key, panel.clone());
+                    }
+                    None => {
+                        panels.shift_remove(&key);
+                    }
+                });
+            }
+            CoreNotification::PluginDecorationsChanged {
+                volt,
+                path,
+                key,
+                version,
+                decorations,
+            } => {
+                let doc = self.main_split.doc_of_path(path);
+                if let Some(doc) = doc {
+                    doc.update(|doc| {
+                        doc.set_decorations(
+                            volt.clone(),
+                            key.clone(),
+                            *version,
+                            decorations.clone(),
+                        )
+                    });
+                }
+            }
+            CoreNotification::PluginStatusItemChanged { volt, id, item } => {
+                let key = (volt.clone(), id.clone());
+                self.common.plugin_status_items.update(|items| match item {
+                    Some(item) => {
+                        items.insert(key, item.clone());
+                    }
+                    None => {
+                        items.shift_remove(&key);
+                    }
+                });
+            }
             _ => {}
         }
     }
 
+    fn update_progress(
+        &self,
+        progress: &lsp_types::ProgressParams,
+        server_name: &str,
+    ) {
+        let token = progress.token.clone();
+        let ProgressParamsValue::WorkDone(value) = &progress.value;
+        self.progresses.update(|progresses| match value {
+            LspWorkDoneProgress::Begin(begin) => {
+                progresses.insert(
+                    token,
+                    WorkProgress {
+                        server_name: server_name.to_string(),
+                        title: begin.title.clone(),
+                        message: begin.message.clone(),
+                        percentage: begin.percentage,
+                    },
+                );
+            }
+            LspWorkDoneProgress::Report(report) => {
+                if let Some(work) = progresses.get_mut(&token) {
+                    if report.message.is_some() {
+                        work.message = report.message.clone();
+                    }
+                    work.percentage = report.percentage.or(work.percentage);
+                }
+            }
+            LspWorkDoneProgress::End(_) => {
+                progresses.shift_remove(&token);
+            }
+        });
+    }
+
+    /// Restart the language servers of a plugin by reloading it, dropping the
+    /// work its servers had in progress
+    pub fn restart_server(&self, meta: VoltMetadata) {
+        self.progresses.update(|progresses| {
+            progresses.retain(|_, work| work.server_name != meta.display_name);
+        });
+        self.plugin.reload_volt(meta);
+    }
+
     pub fn key_down(&self, key_event: &KeyEvent) {
         let focus = self.common.focus.get_untracked();
         let mut keypress = self.common.keypress.get_untracked();
@@ -1369,6 +2516,22 @@ pub fn key_down(&self, key_event: &KeyEvent) {
                 keypress.key_down(key_event, &self.rename);
                 true
             }
+            Focus::Shortcuts => {
+                keypress.key_down(key_event, &self.shortcuts);
+                true
+            }
+            Focus::Registers => {
+                keypress.key_down(key_event, &self.registers);
+                true
+            }
+            Focus::Scratchpad => {
+                keypress.key_down(key_event, &self.scratchpad);
+                true
+            }
+            Focus::Replay => {
+                keypress.key_down(key_event, &self.replay);
+                true
+            }
             Focus::Panel(PanelKind::Terminal) => {
                 self.terminal.key_down(key_event, &mut keypress);
                 true
@@ -1377,6 +2540,14 @@ pub fn key_down(&self, key_event: &KeyEvent) {
                 keypress.key_down(key_event, &self.global_search);
                 true
             }
+            Focus::Panel(PanelKind::Problem) => {
+                keypress.key_down(key_event, &self.problem);
+                true
+            }
+            Focus::Panel(PanelKind::RegexTester) => {
+                keypress.key_down(key_event, &self.regex_tester);
+                true
+            }
             Focus::Panel(PanelKind::Plugin) => {
                 keypress.key_down(key_event, &self.plugin);
                 true
@@ -1385,6 +2556,9 @@ pub fn key_down(&self, key_event: &KeyEvent) {
                 keypress.key_down(key_event, &self.source_control);
                 true
             }
+            Focus::Panel(PanelKind::FileExplorer) => {
+                keypress.key_down(key_event, &self.file_explorer)
+            }
             _ => false,
         };
 
@@ -1455,6 +2629,69 @@ pub fn completion_origin(&self) -> Point {
         origin
     }
 
+    /// The signature help goes above the cursor line. It goes below the line
+    /// instead when there's no room above or the completion is shown above,
+    /// and then below the completion if that is also below the line, so that
+    /// the two never overlap.
+    pub fn signature_origin(&self) -> Point {
+        let signature = self.common.signature.get();
+        if !signature.is_active() {
+            return Point::ZERO;
+        }
+
+        let editor =
+            if let Some(editor) = self.main_split.active_editor.get_untracked() {
+                editor
+            } else {
+                return Point::ZERO;
+            };
+
+        let (window_origin, viewport, view, cursor) = editor.with_untracked(|e| {
+            (e.window_origin, e.viewport, e.view.clone(), e.cursor)
+        });
+
+        let offset = cursor.with(|c| c.offset());
+        let (point_above, point_below) = view.points_of_offset(offset);
+        let (call_point, _) = view.points_of_offset(signature.offset);
+
+        let window_origin = window_origin.get() - self.window_origin.get().to_vec2();
+        let viewport = viewport.get();
+        let signature_size = signature.layout_rect.size();
+        let tab_size = self.layout_rect.get().size();
+
+        let line_top = window_origin.y + point_above.y - viewport.y0;
+        let line_bottom = window_origin.y + point_below.y - viewport.y0;
+        let mut origin = Point::new(
+            window_origin.x + call_point.x - viewport.x0,
+            line_top - signature_size.height,
+        );
+
+        let (completion_active, completion_height) =
+            self.common.completion.with(|completion| {
+                (
+                    completion.status != CompletionStatus::Inactive
+                        && !completion.filtered_items.is_empty(),
+                    completion.layout_rect.height(),
+                )
+            });
+        let completion_above =
+            completion_active && self.completion_origin().y < line_top;
+        if origin.y < 0.0 || completion_above {
+            origin.y = line_bottom;
+            if completion_active && !completion_above {
+                origin.y += completion_height;
+            }
+        }
+        if origin.x + signature_size.width + 1.0 > tab_size.width {
+            origin.x = tab_size.width - signature_size.width - 1.0;
+        }
+        if origin.x <= 0.0 {
+            origin.x = 0.0;
+        }
+
+        origin
+    }
+
     pub fn code_action_origin(&self) -> Point {
         let code_action = self.code_action.get();
         let config = self.common.config.get();
@@ -1547,6 +2784,42 @@ pub fn rename_origin(&self) -> Point {
         origin
     }
 
+    /// The origin of the popup for the diagnostic jumped to, or `None` if it
+    /// shouldn't be shown because the cursor has moved away from it.
+    pub fn diagnostic_popup_origin(&self, popup_width: f64) -> Option<Point> {
+        let popup = self.main_split.diagnostic_popup.get()?;
+        let editor = self.main_split.active_editor.get()?;
+        let (window_origin, viewport, view, cursor) = editor.with_untracked(|e| {
+            (e.window_origin, e.viewport, e.view.clone(), e.cursor)
+        });
+
+        let offset = view.doc.with(|doc| {
+            (doc.content.path() == Some(&popup.path)).then(|| {
+                doc.buffer()
+                    .offset_of_position(&popup.diagnostic.range.start)
+            })
+        })?;
+        if cursor.with(|cursor| cursor.offset()) != offset {
+            return None;
+        }
+
+        let (_point_above, point_below) = view.points_of_offset(offset);
+        let window_origin = window_origin.get() - self.window_origin.get().to_vec2();
+        let viewport = viewport.get();
+        let tab_size = self.layout_rect.get().size();
+
+        let mut origin = window_origin
+            + Vec2::new(point_below.x - viewport.x0, point_below.y - viewport.y0);
+        if origin.x + popup_width + 1.0 > tab_size.width {
+            origin.x = tab_size.width - popup_width - 1.0;
+        }
+        if origin.x <= 0.0 {
+            origin.x = 0.0;
+        }
+
+        Some(origin)
+    }
+
     /// Get the mode for the current editor or terminal
     pub fn mode(&self) -> Mode {
         if self.common.config.get().core.modal {
@@ -1575,17 +2848,19 @@ pub fn toggle_panel_visual(&self, kind: PanelKind) {
     /// Toggle a specific kind of panel.
     fn toggle_panel_focus(&self, kind: PanelKind) {
         let should_hide = match kind {
-            PanelKind::FileExplorer
-            | PanelKind::Plugin
+            PanelKind::Plugin
             | PanelKind::Problem
-            | PanelKind::Debug => {
+            | PanelKind::Debug
+            | PanelKind::PluginViews => {
                 // Some panels don't accept focus (yet). Fall back to visibility check
                 // in those cases.
                 self.panel.is_panel_visible(&kind)
             }
-            PanelKind::Terminal | PanelKind::SourceControl | PanelKind::Search => {
-                self.is_panel_focused(kind)
-            }
+            PanelKind::Terminal
+            | PanelKind::SourceControl
+            | PanelKind::Search
+            | PanelKind::FileExplorer
+            | PanelKind::RegexTester => self.is_panel_focused(kind),
         };
         if should_hide {
             self.hide_panel(kind);
@@ -1631,6 +2906,42 @@ fn toggle_container_visual(&self, position: &PanelContainerPosition) {
         }
     }
 
+    /// The path of the file in the active editor, if it's editing one
+    fn active_file_path(&self) -> Option<PathBuf> {
+        let editor = self.main_split.active_editor.get_untracked()?;
+        let doc = editor.with_untracked(|editor| editor.view.doc);
+        doc.with_untracked(|doc| match &doc.content {
+            DocContent::File(path) => Some(path.clone()),
+            _ => None,
+        })
+    }
+
+    fn reveal_in_file_explorer(&self, path: PathBuf) {
+        self.show_panel(PanelKind::FileExplorer);
+        self.file_explorer.reveal(path);
+    }
+
+    fn open_in_terminal(&self, path: &Path) {
+        let dir = if path.is_dir() {
+            path
+        } else {
+            path.parent().unwrap_or(path)
+        };
+        self.terminal.new_tab_in(dir.to_path_buf());
+        self.show_panel(PanelKind::Terminal);
+    }
+
+    fn copy_path(&self, path: &Path, relative: bool) {
+        let path = self
+            .workspace
+            .path
+            .as_deref()
+            .filter(|_| relative)
+            .and_then(|workspace| path.strip_prefix(workspace).ok())
+            .unwrap_or(path);
+        SystemClipboard {}.put_string(path.to_string_lossy());
+    }
+
     fn is_panel_focused(&self, kind: PanelKind) -> bool {
         // Moving between e.g. Search and Problems doesn't affect focus, so we need to also check
         // visibility.
@@ -1675,6 +2986,10 @@ fn run_and_debug(
         mode: &RunDebugMode,
         config: &RunDebugConfig,
     ) {
+        self.terminal
+            .debug
+            .last_run
+            .set(Some((*mode, config.clone())));
         match mode {
             RunDebugMode::Run => {
                 self.run_in_terminal(cx, mode, config);
@@ -1727,6 +3042,167 @@ fn run_in_terminal(
         }
     }
 
+    /// Download and extract the latest release in the background, restarting
+    /// into it once done if `restart` is set.
+    fn download_update(&self, restart: bool) {
+        let release = self.latest_release.get_untracked();
+        let Some(release) = release.as_ref().clone() else {
+            return;
+        };
+        if !release.is_newer()
+            || self.update_status.get_untracked() == UpdateStatus::Downloading
+        {
+            return;
+        }
+        let Ok(process_path) = env::current_exe() else {
+            return;
+        };
+
+        let update_status = self.update_status;
+        update_status.set(UpdateStatus::Downloading);
+        let send = create_ext_action(self.scope, move |path: Option<PathBuf>| {
+            update_status.set(match path {
+                Some(path) => UpdateStatus::Ready(path),
+                None => UpdateStatus::Failed,
+            });
+        });
+        std::thread::spawn(move || {
+            let path =
+                match crate::update::download_and_extract(&release, &process_path) {
+                    Ok(path) => path,
+                    Err(err) => {
+                        error!("Failed to update: {err}");
+                        send(None);
+                        return;
+                    }
+                };
+            if restart {
+                if let Err(err) = crate::update::restart(&path) {
+                    error!("Failed to restart to update: {err}");
+                }
+            }
+            send(Some(path));
+        });
+    }
+
+    /// Zoom the editor font of the window by the given number of points, unless
+    /// the font is already at the bound of its size
+    fn zoom_editor_font(&self, delta: isize) {
+        let config = self.common.config.get_untracked();
+        let mut zoomed = config.editor.clone();
+        zoomed.zoom_font(delta);
+        if zoomed.font_size() != config.editor.font_size() {
+            self.editor_font_zoom.update(|zoom| *zoom += delta);
+        }
+    }
+
+    /// Turn focus mode on or off. Focus mode turns itself off after the
+    /// configured number of minutes, unless it was toggled again meanwhile.
+    fn toggle_focus_mode(&self) {
+        let focus_mode = self.common.focus_mode;
+        if let Some(cx) = focus_mode.get_untracked() {
+            cx.dispose();
+            focus_mode.set(None);
+            return;
+        }
+
+        let (cx, _) = self.scope.run_child_scope(|cx| cx);
+        focus_mode.set(Some(cx));
+        let minutes = self.common.config.get_untracked().editor.focus_mode_minutes;
+        if minutes == 0 {
+            return;
+        }
+        exec_after(cx, Duration::from_secs(minutes * 60), move || {
+            cx.dispose();
+            focus_mode.set(None);
+        });
+    }
+
+    /// The files with changes since `HEAD` that are still there to jump to
+    fn changed_files(&self) -> Vec<PathBuf> {
+        self.source_control.file_diffs.with_untracked(|file_diffs| {
+            file_diffs
+                .values()
+                .filter(|(diff, _)| !matches!(diff, FileDiff::Deleted(_)))
+                .map(|(diff, _)| diff.path().clone())
+                .collect()
+        })
+    }
+
+    /// The changes of the active file since `HEAD` as a list under its path,
+    /// for a commit message or the description of a pull request
+    fn file_changes_summary(&self) -> Option<String> {
+        let editor = self.main_split.active_editor.get_untracked()?;
+        let doc = editor.with_untracked(|editor| editor.view.doc);
+        let (path, items) = doc.with_untracked(|doc| {
+            (doc.content.path().cloned(), doc.head_changes_summary())
+        });
+        let path = path?;
+        if items.is_empty() {
+            return None;
+        }
+        let path = self
+            .workspace
+            .path
+            .as_ref()
+            .and_then(|workspace| path.strip_prefix(workspace).ok())
+            .unwrap_or(&path);
+        Some(format!("{}:\n{}\n", path.display(), items.join("\n")))
+    }
+
+    /// Scroll the active editor so that its selection, or the function around
+    /// the cursor, fits in the view, zooming the font out if it's too long.
+    /// Toggling again restores the view as it was.
+    fn toggle_fit_selection(&self) {
+        if let Some(fitted) = self.fitted_view.get_untracked() {
+            self.fitted_view.set(None);
+            if fitted.zoom != 0 {
+                self.editor_font_zoom.update(|zoom| *zoom -= fitted.zoom);
+            }
+            let editor = self.main_split.editors.with_untracked(|editors| {
+                editors.get(&fitted.editor_id).map(|e| e.get_untracked())
+            });
+            if let Some(editor) = editor {
+                editor.scroll_to.set(Some(fitted.scroll_offset));
+            }
+            return;
+        }
+
+        let Some(editor) = self.main_split.active_editor.get_untracked() else {
+            return;
+        };
+        let editor = editor.get_untracked();
+        let (start_line, end_line) = editor.fit_lines();
+        let lines = end_line - start_line + 1;
+        let viewport = editor.viewport.get_untracked();
+
+        let config = self.common.config.get_untracked();
+        let zoom = if config.editor.fit_selection_zoom {
+            config.editor.fit_zoom(lines, viewport.height())
+        } else {
+            0
+        };
+        let mut zoomed = config.editor.clone();
+        zoomed.zoom_font(zoom);
+
+        self.fitted_view.set(Some(FittedView {
+            editor_id: editor.editor_id,
+            scroll_offset: viewport.origin().to_vec2(),
+            zoom,
+        }));
+        if zoom != 0 {
+            self.editor_font_zoom.update(|z| *z += zoom);
+        }
+
+        // The lines are centered if there's room around them, or start at
+        // the top of the view if there isn't
+        let line_height = zoomed.line_height() as f64;
+        let margin =
+            ((viewport.height() - lines as f64 * line_height) / 2.0).max(0.0);
+        let top = (start_line as f64 * line_height - margin).max(0.0);
+        editor.scroll_to.set(Some(Vec2::new(viewport.x0, top)));
+    }
+
     pub fn open_paths(&self, paths: &[PathObject]) {
         let (folders, files): (Vec<&PathObject>, Vec<&PathObject>) =
             paths.iter().partition(|p| p.is_dir);
@@ -1769,6 +3245,31 @@ pub fn open_paths(&self, paths: &[PathObject]) {
     }
 }
 
+fn copy_file_name(path: &Path) {
+    if let Some(name) = path.file_name() {
+        SystemClipboard {}.put_string(name.to_string_lossy());
+    }
+}
+
+/// Show the path in the file manager of the system, selected where it can be
+fn reveal_in_file_manager(path: &Path) {
+    #[cfg(target_os = "macos")]
+    let result = std::process::Command::new("open")
+        .arg("-R")
+        .arg(path)
+        .spawn();
+    #[cfg(windows)]
+    let result = std::process::Command::new("explorer")
+        .arg(format!("/select,{}", path.display()))
+        .spawn();
+    #[cfg(not(any(target_os = "macos", windows)))]
+    let result = open::that(path.parent().unwrap_or(path));
+
+    if let Err(e) = result {
+        error!("failed to reveal {path:?} in the file manager: {e}");
+    }
+}
+
 /// Open path with the default application without blocking.
 fn open_uri(path: &Path) {
     match open::that(path) {
@@ -0,0 +1,274 @@
+//! Latency of typing, scrolling and pasting in the editing core: the time
+//! from an input to the styled lines of the viewport being ready to lay out,
+//! on generated Rust files the size of real ones. The text layout and the
+//! painting of the app aren't part of it, the `document_editing` benchmark of
+//! `lapce-app` covers the documents and their layout.
+//!
+//! Run with `cargo bench -p lapce-core --features lang-rust --bench core_editing`,
+//! optionally with a name to only run the scenarios containing it. It fails
+//! when a scenario takes longer than its budget, so that a regression is
+//! caught before it is released.
+
+use std::{
+    path::Path,
+    process::ExitCode,
+    time::{Duration, Instant},
+};
+
+use lapce_core::{
+    buffer::{rope_text::RopeText, Buffer},
+    cursor::{Cursor, CursorMode},
+    editor::Editor,
+    mode::VisualMode,
+    register::RegisterData,
+    selection::Selection,
+    style::line_styles,
+    syntax::{edit::SyntaxEdit, Syntax},
+};
+
+/// How many lines the viewport shows
+const VIEWPORT_LINES: usize = 60;
+
+/// The sizes of the files, in lines
+const FILE_SIZES: [usize; 2] = [2_000, 20_000];
+
+/// What typing inserts, a character at a time
+const TYPED: &str = "let value = compute(&argument, 42); // a comment\n";
+
+/// How many lines are pasted at once
+const PASTED_LINES: usize = 5_000;
+
+/// The 95th percentile a scenario must stay under. A keystroke and a scroll
+/// have to fit in a frame.
+const TYPING_BUDGET: Duration = Duration::from_millis(16);
+const SCROLLING_BUDGET: Duration = Duration::from_millis(16);
+const PASTE_BUDGET: Duration = Duration::from_millis(250);
+
+/// A Rust file with the mix of items, comments and strings of real code
+fn rust_source(lines: usize) -> String {
+    let mut source = String::new();
+    let mut source_lines = 0;
+    let mut i = 0;
+    while source_lines < lines {
+        let item = format!(
+            r#"/// The item number {i}, documented like public items are
+#[derive(Clone, Debug, PartialEq)]
+pub struct Item{i} {{
+    pub name: String,
+    pub values: Vec<usize>,
+}}
+
+impl Item{i} {{
+    pub fn new(name: &str) -> Self {{
+        // Start empty, values are pushed later
+        Self {{
+            name: format!("item {{}} {i}", name),
+            values: Vec::with_capacity({i}),
+        }}
+    }}
+
+    pub fn total(&self) -> usize {{
+        self.values.iter().filter(|v| **v > {i}).sum()
+    }}
+}}
+
+"#
+        );
+        source_lines += item.lines().count();
+        source.push_str(&item);
+        i += 1;
+    }
+    source
+}
+
+/// A document as the editor holds it, with its syntax kept up to date
+struct Doc {
+    buffer: Buffer,
+    syntax: Syntax,
+    cursor: Cursor,
+}
+
+impl Doc {
+    /// The document with the cursor in the middle of it
+    fn new(text: &str) -> Self {
+        let buffer = Buffer::new(text);
+        let mut syntax = Syntax::init(Path::new("core_editing.rs"));
+        syntax.parse(buffer.rev(), buffer.text().clone(), None);
+        let offset = buffer.offset_of_line(buffer.num_lines() / 2);
+        let cursor =
+            Cursor::new(CursorMode::Insert(Selection::caret(offset)), None, None);
+        Self {
+            buffer,
+            syntax,
+            cursor,
+        }
+    }
+
+    /// Bring the syntax up to date with the edits, like the document does
+    fn update_syntax(&mut self, edits: Vec<SyntaxEdit>) {
+        self.syntax.parse(
+            self.buffer.rev(),
+            self.buffer.text().clone(),
+            Some(&edits),
+        );
+    }
+
+    /// Style the lines of the viewport starting at the line, like rendering
+    /// it does, giving how many bytes of text got a style
+    fn style_viewport(&self, start: usize) -> usize {
+        let end = (start + VIEWPORT_LINES).min(self.buffer.num_lines());
+        let mut styled = 0;
+        for line in start..end {
+            let content = self.buffer.line_content(line);
+            if let Some(spans) = self.syntax.styles.as_ref() {
+                styled += line_styles(self.buffer.text(), line, spans)
+                    .iter()
+                    .map(|style| {
+                        style.end.min(content.len()).saturating_sub(style.start)
+                    })
+                    .sum::<usize>();
+            }
+        }
+        styled
+    }
+
+    /// The first line of the viewport that keeps the cursor in the middle
+    fn cursor_viewport(&self) -> usize {
+        let line = self.buffer.line_of_offset(self.cursor.offset());
+        line.saturating_sub(VIEWPORT_LINES / 2)
+    }
+}
+
+/// The time each run of a scenario took
+struct Timings {
+    name: String,
+    budget: Duration,
+    samples: Vec<Duration>,
+    /// How many bytes of text got a style, to show the work was done
+    styled: usize,
+}
+
+impl Timings {
+    fn new(name: String, budget: Duration) -> Self {
+        Self {
+            name,
+            budget,
+            samples: Vec::new(),
+            styled: 0,
+        }
+    }
+
+    fn time(&mut self, f: impl FnOnce() -> usize) {
+        let start = Instant::now();
+        let styled = f();
+        self.samples.push(start.elapsed());
+        self.styled += styled;
+    }
+
+    fn percentile(&self, percentile: usize) -> Duration {
+        let mut samples = self.samples.clone();
+        samples.sort();
+        let index = (samples.len() * percentile / 100).min(samples.len() - 1);
+        samples[index]
+    }
+
+    /// Print the timings, giving whether they're within the budget
+    fn report(&self) -> bool {
+        let p95 = self.percentile(95);
+        let within_budget = p95 <= self.budget;
+        println!(
+            "{:<20} median {:>10.3?}  p95 {:>10.3?}  max {:>10.3?}  ({} runs, {} bytes styled){}",
+            self.name,
+            self.percentile(50),
+            p95,
+            self.samples.iter().max().copied().unwrap_or_default(),
+            self.samples.len(),
+            self.styled,
+            if within_budget {
+                String::new()
+            } else {
+                format!("  OVER BUDGET of {:?}", self.budget)
+            },
+        );
+        within_budget
+    }
+}
+
+/// Type a line character by character in the middle of the file
+fn typing(lines: usize) -> Timings {
+    let mut timings = Timings::new(format!("typing/{lines}"), TYPING_BUDGET);
+    let mut doc = Doc::new(&rust_source(lines));
+    for c in TYPED.chars() {
+        timings.time(|| {
+            let deltas = Editor::insert(
+                &mut doc.cursor,
+                &mut doc.buffer,
+                &c.to_string(),
+                &doc.syntax,
+                true,
+            );
+            doc.update_syntax(deltas.into_iter().map(|(_, _, edit)| edit).collect());
+            doc.style_viewport(doc.cursor_viewport())
+        });
+    }
+    timings
+}
+
+/// Scroll through the file a page at a time
+fn scrolling(lines: usize) -> Timings {
+    let mut timings = Timings::new(format!("scrolling/{lines}"), SCROLLING_BUDGET);
+    let doc = Doc::new(&rust_source(lines));
+    for start in (0..doc.buffer.num_lines()).step_by(VIEWPORT_LINES) {
+        timings.time(|| doc.style_viewport(start));
+    }
+    timings
+}
+
+/// Paste a large piece of code in the middle of the file
+fn paste(lines: usize) -> Timings {
+    let mut timings = Timings::new(format!("paste/{lines}"), PASTE_BUDGET);
+    let source = rust_source(lines);
+    let data = RegisterData {
+        content: rust_source(PASTED_LINES),
+        mode: VisualMode::Normal,
+        segments: Vec::new(),
+    };
+    for _ in 0..10 {
+        let mut doc = Doc::new(&source);
+        timings.time(|| {
+            let deltas = Editor::do_paste(&mut doc.cursor, &mut doc.buffer, &data);
+            doc.update_syntax(deltas.into_iter().map(|(_, _, edit)| edit).collect());
+            doc.style_viewport(doc.cursor_viewport())
+        });
+    }
+    timings
+}
+
+fn main() -> ExitCode {
+    // Cargo passes `--bench`, anything else is a filter on the names
+    let filter = std::env::args().skip(1).find(|arg| !arg.starts_with("--"));
+    let scenarios: [(&str, fn(usize) -> Timings); 3] = [
+        ("typing", typing),
+        ("scrolling", scrolling),
+        ("paste", paste),
+    ];
+
+    let mut within_budget = true;
+    for (name, scenario) in scenarios {
+        for lines in FILE_SIZES {
+            let full_name = format!("{name}/{lines}");
+            if let Some(filter) = filter.as_ref() {
+                if !full_name.contains(filter.as_str()) {
+                    continue;
+                }
+            }
+            within_budget &= scenario(lines).report();
+        }
+    }
+
+    if within_budget {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
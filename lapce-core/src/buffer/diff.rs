@@ -1,5 +1,6 @@
 use std::{
     borrow::Cow,
+    collections::HashMap,
     ops::Range,
     sync::{
         atomic::{self, AtomicU64},
@@ -8,6 +9,60 @@
 };
 
 use lapce_xi_rope::Rope;
+use serde::{Deserialize, Serialize};
+
+/// Lines that occur more often than this on the left side aren't taken as
+/// the anchor of a histogram diff, which falls back to the shortest diff
+/// instead
+const MAX_HISTOGRAM_OCCURRENCES: usize = 64;
+
+/// How the lines that changed are found
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffAlgorithm {
+    /// The fewest lines added and removed
+    #[default]
+    Myers,
+    /// Lines that occur once on each side are matched first, and the lines
+    /// between them are diffed on their own, which keeps moved and
+    /// reindented blocks together
+    Patience,
+    /// Like patience, but anchored on the lines that occur the least rather
+    /// than only once, like the histogram diff of git
+    Histogram,
+}
+
+/// How two texts are compared
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DiffOptions {
+    pub algorithm: DiffAlgorithm,
+    /// Lines that only differ in their whitespace are the same
+    pub ignore_whitespace: bool,
+    /// Lines that only differ in the case of their letters are the same
+    pub ignore_case: bool,
+}
+
+impl DiffOptions {
+    /// The line as it's compared
+    fn key<'a>(&self, line: &'a str) -> Cow<'a, str> {
+        let mut key = Cow::Borrowed(line);
+        if self.ignore_whitespace {
+            key = Cow::Owned(key.split_whitespace().collect());
+        }
+        if self.ignore_case {
+            key = Cow::Owned(key.to_lowercase());
+        }
+        key
+    }
+}
+
+/// One line of a diff, in order
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum DiffOp {
+    Left,
+    Both,
+    Right,
+}
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum DiffResult<T> {
@@ -30,6 +85,126 @@ pub enum DiffLines {
     Right(Range<usize>),
 }
 
+/// A run of changed lines between unchanged ones: the lines of the left side
+/// that the lines of the right side replace
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiffHunk {
+    pub left: Range<usize>,
+    pub right: Range<usize>,
+}
+
+/// The hunks of the changes, in order
+pub fn diff_hunks(changes: &[DiffLines]) -> Vec<DiffHunk> {
+    let mut hunks = Vec::new();
+    let mut current: Option<DiffHunk> = None;
+    let mut left_line = 0;
+    let mut right_line = 0;
+    for change in changes {
+        match change {
+            DiffLines::Both(info) => {
+                hunks.extend(current.take());
+                left_line = info.left.end;
+                right_line = info.right.end;
+            }
+            DiffLines::Left(range) => {
+                let hunk = current.get_or_insert(DiffHunk {
+                    left: range.start..range.start,
+                    right: right_line..right_line,
+                });
+                hunk.left.end = range.end;
+                left_line = range.end;
+            }
+            DiffLines::Right(range) => {
+                let hunk = current.get_or_insert(DiffHunk {
+                    left: left_line..left_line,
+                    right: range.start..range.start,
+                });
+                hunk.right.end = range.end;
+                right_line = range.end;
+            }
+        }
+    }
+    hunks.extend(current);
+    hunks
+}
+
+/// What the hunk did to the right side, in words, with its lines counted
+/// from 1, like "changed lines 3-4"
+pub fn hunk_summary(hunk: &DiffHunk) -> String {
+    let lines = |range: &Range<usize>| {
+        if range.len() == 1 {
+            format!("line {}", range.start + 1)
+        } else {
+            format!("lines {}-{}", range.start + 1, range.end)
+        }
+    };
+    if hunk.left.is_empty() {
+        format!("added {}", lines(&hunk.right))
+    } else if hunk.right.is_empty() {
+        let count = hunk.left.len();
+        let plural = if count == 1 { "" } else { "s" };
+        if hunk.right.start == 0 {
+            format!("removed {count} line{plural} at the start")
+        } else {
+            format!(
+                "removed {count} line{plural} after line {}",
+                hunk.right.start
+            )
+        }
+    } else {
+        format!("changed {}", lines(&hunk.right))
+    }
+}
+
+/// The hunk as it's written in a unified diff patch, from its `@@` header,
+/// with up to `context` lines of the left side around it. The patch applies
+/// the hunk alone to the left side, as when staging it.
+pub fn hunk_patch(
+    left_rope: &Rope,
+    right_rope: &Rope,
+    hunk: &DiffHunk,
+    context: usize,
+) -> String {
+    let left_lines = left_rope.lines_raw(..).collect::<Vec<Cow<str>>>();
+    let right_lines = right_rope.lines_raw(..).collect::<Vec<Cow<str>>>();
+    let before = hunk.left.start.min(context);
+    let after = left_lines.len().saturating_sub(hunk.left.end).min(context);
+
+    let left_start = hunk.left.start - before;
+    let left_len = hunk.left.len() + before + after;
+    let right_len = hunk.right.len() + before + after;
+    // An empty side starts at the line before it
+    let start = |start: usize, len: usize| if len == 0 { start } else { start + 1 };
+    let mut patch = format!(
+        "@@ -{},{} +{},{} @@\n",
+        start(left_start, left_len),
+        left_len,
+        start(left_start, right_len),
+        right_len
+    );
+
+    let mut push_line = |origin: char, line: &str| {
+        patch.push(origin);
+        patch.push_str(line);
+        if !line.ends_with('\n') {
+            patch.push_str("\n\\ No newline at end of file\n");
+        }
+    };
+    for line in &left_lines[left_start..hunk.left.start] {
+        push_line(' ', line);
+    }
+    for line in &left_lines[hunk.left.clone()] {
+        push_line('-', line);
+    }
+    for line in &right_lines[hunk.right.clone()] {
+        push_line('+', line);
+    }
+    for line in &left_lines[hunk.left.end..hunk.left.end + after] {
+        push_line(' ', line);
+    }
+    patch
+}
+
 pub enum DiffExpand {
     Up(usize),
     Down(usize),
@@ -82,82 +257,49 @@ pub fn rope_diff(
     rev: u64,
     atomic_rev: Arc<AtomicU64>,
     context_lines: Option<usize>,
+    options: DiffOptions,
 ) -> Option<Vec<DiffLines>> {
     let left_lines = left_rope.lines(..).collect::<Vec<Cow<str>>>();
     let right_lines = right_rope.lines(..).collect::<Vec<Cow<str>>>();
+    let left_keys = left_lines
+        .iter()
+        .map(|line| options.key(line))
+        .collect::<Vec<Cow<str>>>();
+    let right_keys = right_lines
+        .iter()
+        .map(|line| options.key(line))
+        .collect::<Vec<Cow<str>>>();
 
     let left_count = left_lines.len();
     let right_count = right_lines.len();
     let min_count = std::cmp::min(left_count, right_count);
 
-    let leading_equals = left_lines
+    let leading_equals = left_keys
         .iter()
-        .zip(right_lines.iter())
+        .zip(right_keys.iter())
         .take_while(|p| p.0 == p.1)
         .count();
-    let trailing_equals = left_lines
+    let trailing_equals = left_keys
         .iter()
         .rev()
-        .zip(right_lines.iter().rev())
+        .zip(right_keys.iter().rev())
         .take(min_count - leading_equals)
         .take_while(|p| p.0 == p.1)
         .count();
 
-    let left_diff_size = left_count - leading_equals - trailing_equals;
-    let right_diff_size = right_count - leading_equals - trailing_equals;
-
-    let table: Vec<Vec<u32>> = {
-        let mut table = vec![vec![0; right_diff_size + 1]; left_diff_size + 1];
-        let left_skip = left_lines.iter().skip(leading_equals).take(left_diff_size);
-        let right_skip = right_lines
-            .iter()
-            .skip(leading_equals)
-            .take(right_diff_size);
-
-        for (i, l) in left_skip.enumerate() {
-            for (j, r) in right_skip.clone().enumerate() {
-                if atomic_rev.load(atomic::Ordering::Acquire) != rev {
-                    return None;
-                }
-                table[i + 1][j + 1] = if l == r {
-                    table[i][j] + 1
-                } else {
-                    std::cmp::max(table[i][j + 1], table[i + 1][j])
-                };
-            }
+    let cancelled = || atomic_rev.load(atomic::Ordering::Acquire) != rev;
+    let left = &left_keys[leading_equals..left_count - trailing_equals];
+    let right = &right_keys[leading_equals..right_count - trailing_equals];
+    let mut diff = Vec::with_capacity(left.len() + right.len());
+    match options.algorithm {
+        DiffAlgorithm::Myers => lcs_diff(left, right, &cancelled, &mut diff)?,
+        DiffAlgorithm::Patience => {
+            patience_diff(left, right, &cancelled, &mut diff)?
         }
-
-        table
-    };
-
-    let diff = {
-        let mut diff = Vec::with_capacity(left_diff_size + right_diff_size);
-        let mut i = left_diff_size;
-        let mut j = right_diff_size;
-        let mut li = left_lines.iter().rev().skip(trailing_equals);
-        let mut ri = right_lines.iter().skip(trailing_equals);
-
-        loop {
-            if atomic_rev.load(atomic::Ordering::Acquire) != rev {
-                return None;
-            }
-            if j > 0 && (i == 0 || table[i][j] == table[i][j - 1]) {
-                j -= 1;
-                diff.push(DiffResult::Right(ri.next().unwrap()));
-            } else if i > 0 && (j == 0 || table[i][j] == table[i - 1][j]) {
-                i -= 1;
-                diff.push(DiffResult::Left(li.next().unwrap()));
-            } else if i > 0 && j > 0 {
-                i -= 1;
-                j -= 1;
-                diff.push(DiffResult::Both(li.next().unwrap(), ri.next().unwrap()));
-            } else {
-                break;
-            }
+        DiffAlgorithm::Histogram => {
+            histogram_diff(left, right, &cancelled, &mut diff)?
         }
-
-        diff
-    };
+    }
 
     let mut changes = Vec::new();
     let mut left_line = 0;
@@ -172,19 +314,19 @@ pub fn rope_diff(
     left_line += leading_equals;
     right_line += leading_equals;
 
-    for diff in diff.iter().rev() {
-        if atomic_rev.load(atomic::Ordering::Acquire) != rev {
+    for op in diff {
+        if cancelled() {
             return None;
         }
-        match diff {
-            DiffResult::Left(_) => {
+        match op {
+            DiffOp::Left => {
                 match changes.last_mut() {
                     Some(DiffLines::Left(r)) => r.end = left_line + 1,
                     _ => changes.push(DiffLines::Left(left_line..left_line + 1)),
                 }
                 left_line += 1;
             }
-            DiffResult::Both(_, _) => {
+            DiffOp::Both => {
                 match changes.last_mut() {
                     Some(DiffLines::Both(info)) => {
                         info.left.end = left_line + 1;
@@ -199,7 +341,7 @@ pub fn rope_diff(
                 left_line += 1;
                 right_line += 1;
             }
-            DiffResult::Right(_) => {
+            DiffOp::Right => {
                 match changes.last_mut() {
                     Some(DiffLines::Right(r)) => r.end = right_line + 1,
                     _ => changes.push(DiffLines::Right(right_line..right_line + 1)),
@@ -220,7 +362,7 @@ pub fn rope_diff(
         if !changes.is_empty() {
             let changes_last = changes.len() - 1;
             for (i, change) in changes.iter_mut().enumerate() {
-                if atomic_rev.load(atomic::Ordering::Acquire) != rev {
+                if cancelled() {
                     return None;
                 }
                 if let DiffLines::Both(info) = change {
@@ -244,3 +386,363 @@ pub fn rope_diff(
 
     Some(changes)
 }
+
+/// The shortest diff of the lines, from a table of the longest common
+/// subsequences of their starts. It finds as few changes as the Myers
+/// algorithm does, in time and space growing with the product of the sizes
+/// of the sides.
+fn lcs_diff(
+    left: &[Cow<str>],
+    right: &[Cow<str>],
+    cancelled: &dyn Fn() -> bool,
+    diff: &mut Vec<DiffOp>,
+) -> Option<()> {
+    let mut table = vec![vec![0u32; right.len() + 1]; left.len() + 1];
+    for (i, l) in left.iter().enumerate() {
+        if cancelled() {
+            return None;
+        }
+        for (j, r) in right.iter().enumerate() {
+            table[i + 1][j + 1] = if l == r {
+                table[i][j] + 1
+            } else {
+                std::cmp::max(table[i][j + 1], table[i + 1][j])
+            };
+        }
+    }
+
+    let start = diff.len();
+    let mut i = left.len();
+    let mut j = right.len();
+    loop {
+        if j > 0 && (i == 0 || table[i][j] == table[i][j - 1]) {
+            j -= 1;
+            diff.push(DiffOp::Right);
+        } else if i > 0 && (j == 0 || table[i][j] == table[i - 1][j]) {
+            i -= 1;
+            diff.push(DiffOp::Left);
+        } else if i > 0 && j > 0 {
+            i -= 1;
+            j -= 1;
+            diff.push(DiffOp::Both);
+        } else {
+            break;
+        }
+    }
+    diff[start..].reverse();
+    Some(())
+}
+
+/// Push the lines the sides start and end with in common around the diff of
+/// the lines between them, or return false if there aren't any
+fn diff_common_ends(
+    left: &[Cow<str>],
+    right: &[Cow<str>],
+    cancelled: &dyn Fn() -> bool,
+    diff: &mut Vec<DiffOp>,
+    inner: fn(
+        &[Cow<str>],
+        &[Cow<str>],
+        &dyn Fn() -> bool,
+        &mut Vec<DiffOp>,
+    ) -> Option<()>,
+) -> Option<bool> {
+    let prefix = left.iter().zip(right).take_while(|(l, r)| l == r).count();
+    let suffix = left[prefix..]
+        .iter()
+        .rev()
+        .zip(right[prefix..].iter().rev())
+        .take_while(|(l, r)| l == r)
+        .count();
+    if prefix == 0 && suffix == 0 {
+        return Some(false);
+    }
+    diff.extend(std::iter::repeat(DiffOp::Both).take(prefix));
+    inner(
+        &left[prefix..left.len() - suffix],
+        &right[prefix..right.len() - suffix],
+        cancelled,
+        diff,
+    )?;
+    diff.extend(std::iter::repeat(DiffOp::Both).take(suffix));
+    Some(true)
+}
+
+/// The lines of one side against none of the other
+fn diff_one_sided(left: &[Cow<str>], right: &[Cow<str>], diff: &mut Vec<DiffOp>) {
+    diff.extend(std::iter::repeat(DiffOp::Left).take(left.len()));
+    diff.extend(std::iter::repeat(DiffOp::Right).take(right.len()));
+}
+
+fn patience_diff(
+    left: &[Cow<str>],
+    right: &[Cow<str>],
+    cancelled: &dyn Fn() -> bool,
+    diff: &mut Vec<DiffOp>,
+) -> Option<()> {
+    if cancelled() {
+        return None;
+    }
+    if left.is_empty() || right.is_empty() {
+        diff_one_sided(left, right, diff);
+        return Some(());
+    }
+    if diff_common_ends(left, right, cancelled, diff, patience_diff)? {
+        return Some(());
+    }
+
+    // The lines that are unique on both sides, matched in the longest run
+    // that's in the same order on both
+    let mut occurrences: HashMap<&str, (usize, usize, usize, usize)> =
+        HashMap::new();
+    for (i, line) in left.iter().enumerate() {
+        let entry = occurrences.entry(line).or_default();
+        entry.0 += 1;
+        entry.1 = i;
+    }
+    for (j, line) in right.iter().enumerate() {
+        if let Some(entry) = occurrences.get_mut(line.as_ref()) {
+            entry.2 += 1;
+            entry.3 = j;
+        }
+    }
+    let mut unique: Vec<(usize, usize)> = occurrences
+        .into_values()
+        .filter(|(left, _, right, _)| *left == 1 && *right == 1)
+        .map(|(_, i, _, j)| (i, j))
+        .collect();
+    if unique.is_empty() {
+        return lcs_diff(left, right, cancelled, diff);
+    }
+    unique.sort_unstable();
+
+    let mut i = 0;
+    let mut j = 0;
+    for (anchor_i, anchor_j) in longest_increasing(&unique) {
+        patience_diff(&left[i..anchor_i], &right[j..anchor_j], cancelled, diff)?;
+        diff.push(DiffOp::Both);
+        i = anchor_i + 1;
+        j = anchor_j + 1;
+    }
+    patience_diff(&left[i..], &right[j..], cancelled, diff)
+}
+
+/// The longest run of the pairs, sorted by their first item, that's also
+/// increasing in their second item
+fn longest_increasing(pairs: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    // The index of the pair that ends the runs of each length, and the pair
+    // before each one in its run
+    let mut tails: Vec<usize> = Vec::new();
+    let mut previous = vec![None; pairs.len()];
+    for (k, (_, j)) in pairs.iter().enumerate() {
+        let len = tails.partition_point(|tail| pairs[*tail].1 < *j);
+        if len > 0 {
+            previous[k] = Some(tails[len - 1]);
+        }
+        if len == tails.len() {
+            tails.push(k);
+        } else {
+            tails[len] = k;
+        }
+    }
+
+    let mut run = Vec::with_capacity(tails.len());
+    let mut k = tails.last().copied();
+    while let Some(i) = k {
+        run.push(pairs[i]);
+        k = previous[i];
+    }
+    run.reverse();
+    run
+}
+
+fn histogram_diff(
+    mut left: &[Cow<str>],
+    mut right: &[Cow<str>],
+    cancelled: &dyn Fn() -> bool,
+    diff: &mut Vec<DiffOp>,
+) -> Option<()> {
+    // The lines after each anchor are diffed in the loop rather than
+    // recursively, as there can be many of them
+    loop {
+        if cancelled() {
+            return None;
+        }
+        if left.is_empty() || right.is_empty() {
+            diff_one_sided(left, right, diff);
+            return Some(());
+        }
+        if diff_common_ends(left, right, cancelled, diff, histogram_diff)? {
+            return Some(());
+        }
+
+        let mut occurrences: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (i, line) in left.iter().enumerate() {
+            occurrences.entry(line).or_default().push(i);
+        }
+
+        // The longest run of common lines around the line that occurs the
+        // least on the left side
+        let mut anchor: Option<(usize, usize, usize, usize)> = None;
+        for (j, line) in right.iter().enumerate() {
+            let Some(lefts) = occurrences.get(line.as_ref()) else {
+                continue;
+            };
+            if lefts.len() > MAX_HISTOGRAM_OCCURRENCES
+                || anchor.map_or(false, |(count, ..)| count < lefts.len())
+            {
+                continue;
+            }
+            for &i in lefts {
+                let before = left[..i]
+                    .iter()
+                    .rev()
+                    .zip(right[..j].iter().rev())
+                    .take_while(|(l, r)| l == r)
+                    .count();
+                let after = left[i..]
+                    .iter()
+                    .zip(&right[j..])
+                    .take_while(|(l, r)| l == r)
+                    .count();
+                let len = before + after;
+                if anchor.map_or(true, |(count, _, _, anchor_len)| {
+                    lefts.len() < count || len > anchor_len
+                }) {
+                    anchor = Some((lefts.len(), i - before, j - before, len));
+                }
+            }
+        }
+        let Some((_, i, j, len)) = anchor else {
+            return lcs_diff(left, right, cancelled, diff);
+        };
+
+        histogram_diff(&left[..i], &right[..j], cancelled, diff)?;
+        diff.extend(std::iter::repeat(DiffOp::Both).take(len));
+        left = &left[i + len..];
+        right = &right[j + len..];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diff(left: &str, right: &str) -> (Rope, Rope, Vec<DiffLines>) {
+        diff_with(left, right, DiffOptions::default())
+    }
+
+    fn diff_with(
+        left: &str,
+        right: &str,
+        options: DiffOptions,
+    ) -> (Rope, Rope, Vec<DiffLines>) {
+        let left = Rope::from(left);
+        let right = Rope::from(right);
+        let changes = rope_diff(
+            left.clone(),
+            right.clone(),
+            0,
+            Arc::new(AtomicU64::new(0)),
+            None,
+            options,
+        )
+        .unwrap();
+        (left, right, changes)
+    }
+
+    #[test]
+    fn test_diff_hunks() {
+        let (_, _, changes) = diff("a\nb\nc\nd\ne\n", "a\nB\nc\ne\nf\n");
+        assert_eq!(
+            diff_hunks(&changes),
+            vec![
+                DiffHunk {
+                    left: 1..2,
+                    right: 1..2
+                },
+                DiffHunk {
+                    left: 3..4,
+                    right: 3..3
+                },
+                DiffHunk {
+                    left: 5..5,
+                    right: 4..5
+                },
+            ]
+        );
+
+        let summaries: Vec<String> =
+            diff_hunks(&changes).iter().map(hunk_summary).collect();
+        assert_eq!(
+            summaries,
+            vec![
+                "changed line 2",
+                "removed 1 line after line 3",
+                "added line 5"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_options() {
+        let left = "fn a() {\n    x\n}\nfn b() {\n    y\n}\n";
+        let right = "fn b() {\n    y\n}\nfn a() {\n    x\n}\n";
+        for algorithm in [
+            DiffAlgorithm::Myers,
+            DiffAlgorithm::Patience,
+            DiffAlgorithm::Histogram,
+        ] {
+            let options = DiffOptions {
+                algorithm,
+                ..Default::default()
+            };
+            let (_, _, changes) = diff_with(left, right, options);
+            let hunks = diff_hunks(&changes);
+            let removed: usize = hunks.iter().map(|h| h.left.len()).sum();
+            let added: usize = hunks.iter().map(|h| h.right.len()).sum();
+            assert_eq!((removed, added), (3, 3), "{algorithm:?}");
+        }
+
+        let (_, _, changes) = diff_with(
+            "if a {\nb\n}\n",
+            "if A {\n    b\n}\n",
+            DiffOptions {
+                algorithm: DiffAlgorithm::Patience,
+                ignore_whitespace: true,
+                ignore_case: true,
+            },
+        );
+        assert!(diff_hunks(&changes).is_empty());
+
+        let (_, _, changes) = diff_with(
+            "if a {\nb\n}\n",
+            "if a {\n    b\n}\n",
+            DiffOptions::default(),
+        );
+        assert_eq!(
+            diff_hunks(&changes),
+            vec![DiffHunk {
+                left: 1..2,
+                right: 1..2
+            }]
+        );
+    }
+
+    #[test]
+    fn test_hunk_patch() {
+        let (left, right, changes) = diff("a\nb\nc\nd\ne\n", "a\nB\nc\nd\ne\n");
+        let hunks = diff_hunks(&changes);
+        assert_eq!(
+            hunk_patch(&left, &right, &hunks[0], 1),
+            "@@ -1,3 +1,3 @@\n a\n-b\n+B\n c\n"
+        );
+
+        let (left, right, changes) = diff("a\nb", "a\n");
+        let hunks = diff_hunks(&changes);
+        assert_eq!(
+            hunk_patch(&left, &right, &hunks[0], 3),
+            "@@ -1,2 +1,1 @@\n a\n-b\n\\ No newline at end of file\n"
+        );
+    }
+}
@@ -90,6 +90,8 @@ pub struct Buffer {
     last_edit_type: EditType,
 
     indent_style: IndentStyle,
+    /// Extra characters of the language of the text that are part of a word
+    word_chars: String,
 
     max_len: usize,
     max_len_line: usize,
@@ -131,6 +133,7 @@ pub fn new(text: &str) -> Self {
             this_edit_type: EditType::Other,
             last_edit_type: EditType::Other,
             indent_style: IndentStyle::DEFAULT_INDENT,
+            word_chars: String::new(),
 
             max_len: 0,
             max_len_line: 0,
@@ -276,6 +279,18 @@ pub fn indent_unit(&self) -> &'static str {
         self.indent_style.as_str()
     }
 
+    pub fn indent_style(&self) -> IndentStyle {
+        self.indent_style
+    }
+
+    pub fn set_indent_style(&mut self, indent_style: IndentStyle) {
+        self.indent_style = indent_style;
+    }
+
+    pub fn set_word_chars(&mut self, word_chars: String) {
+        self.word_chars = word_chars;
+    }
+
     pub fn reset_edit_type(&mut self) {
         self.last_edit_type = EditType::Other;
     }
@@ -745,6 +760,10 @@ impl RopeText for Buffer {
     fn text(&self) -> &Rope {
         &self.text
     }
+
+    fn word_chars(&self) -> &str {
+        &self.word_chars
+    }
 }
 
 fn shuffle_tombstones(
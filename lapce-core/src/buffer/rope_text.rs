@@ -13,6 +13,12 @@
 pub trait RopeText {
     fn text(&self) -> &Rope;
 
+    /// Extra characters, besides letters and digits, that the word movements
+    /// and selections treat as part of a word
+    fn word_chars(&self) -> &str {
+        ""
+    }
+
     fn len(&self) -> usize {
         self.text().len()
     }
@@ -209,17 +215,36 @@ fn next_grapheme_offset(
         new_offset
     }
 
+    /// Returns the start of the grapheme cluster containing the offset, such as
+    /// an emoji sequence joined by ZWJ or a character with combining marks, so
+    /// that the offset can be used as a cursor position.
+    fn grapheme_start_offset(&self, offset: usize) -> usize {
+        let offset = offset.min(self.len());
+        let prev = self.prev_grapheme_offset(offset, 1, 0);
+        if prev < offset && self.next_grapheme_offset(prev, 1, self.len()) > offset {
+            prev
+        } else {
+            offset
+        }
+    }
+
     fn prev_code_boundary(&self, offset: usize) -> usize {
-        WordCursor::new(self.text(), offset).prev_code_boundary()
+        WordCursor::new(self.text(), offset)
+            .with_word_chars(self.word_chars())
+            .prev_code_boundary()
     }
 
     fn next_code_boundary(&self, offset: usize) -> usize {
-        WordCursor::new(self.text(), offset).next_code_boundary()
+        WordCursor::new(self.text(), offset)
+            .with_word_chars(self.word_chars())
+            .next_code_boundary()
     }
 
     /// Return the previous and end boundaries of the word under cursor.
     fn select_word(&self, offset: usize) -> (usize, usize) {
-        WordCursor::new(self.text(), offset).select_word()
+        WordCursor::new(self.text(), offset)
+            .with_word_chars(self.word_chars())
+            .select_word()
     }
 
     /// Returns the offset of the first non-blank character on the given line.  
@@ -350,7 +375,8 @@ fn find_nth_word<F>(
     where
         F: FnMut(&mut WordCursor) -> Option<usize>,
     {
-        let mut cursor = WordCursor::new(self.text(), offset);
+        let mut cursor =
+            WordCursor::new(self.text(), offset).with_word_chars(self.word_chars());
         let mut new_offset = offset;
         while count != 0 {
             // FIXME: wait for if-let-chain
@@ -539,6 +565,23 @@ fn test_line_content() {
         assert_eq!(text.line_content(5), "");
     }
 
+    #[test]
+    fn test_grapheme_start_offset() {
+        // The family emoji is three emojis joined by two ZWJ, 18 bytes long
+        let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+        let text = Rope::from(format!("a{family}b\ne\u{301}"));
+        let text = RopeTextVal::new(text);
+
+        assert_eq!(text.grapheme_start_offset(0), 0);
+        assert_eq!(text.grapheme_start_offset(1), 1);
+        assert_eq!(text.grapheme_start_offset(5), 1);
+        assert_eq!(text.grapheme_start_offset(12), 1);
+        assert_eq!(text.grapheme_start_offset(19), 19);
+        assert_eq!(text.grapheme_start_offset(21), 21);
+        assert_eq!(text.grapheme_start_offset(22), 21);
+        assert_eq!(text.grapheme_start_offset(100), text.len());
+    }
+
     #[test]
     fn test_offset_of_line() {
         let text = Rope::from("");
@@ -86,6 +86,20 @@ fn on_word_boundary_in_either_direction() {
         }
     }
 
+    #[test]
+    fn word_chars_are_part_of_words() {
+        let mut buffer = Buffer::new("foo-bar baz");
+        //                          ->01234567890<-
+        assert_eq!(buffer.move_word_forward(0), 3);
+        assert_eq!(buffer.select_word(1), (0, 3));
+
+        buffer.set_word_chars("-".to_string());
+        assert_eq!(buffer.move_word_forward(0), 8);
+        assert_eq!(buffer.move_word_backward(7, Mode::Insert), 0);
+        assert_eq!(buffer.select_word(1), (0, 7));
+        assert_eq!(buffer.prev_code_boundary(5), 0);
+    }
+
     mod on_word_end_forward {
         use super::*;
 
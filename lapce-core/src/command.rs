@@ -52,6 +52,12 @@ pub enum EditCommand {
     #[strum(message = "Join Lines")]
     #[strum(serialize = "join_lines")]
     JoinLines,
+    #[strum(message = "Split Arguments onto Their Own Lines")]
+    #[strum(serialize = "split_arguments")]
+    SplitArguments,
+    #[strum(message = "Join Arguments onto One Line")]
+    #[strum(serialize = "join_arguments")]
+    JoinArguments,
     #[strum(message = "Indent Line")]
     #[strum(serialize = "indent_line")]
     IndentLine,
@@ -244,6 +250,18 @@ pub enum FocusCommand {
     SplitUp,
     #[strum(serialize = "split_down")]
     SplitDown,
+    #[strum(serialize = "split_rotate")]
+    #[strum(message = "Rotate Split")]
+    SplitRotate,
+    #[strum(serialize = "split_grow")]
+    #[strum(message = "Grow Split")]
+    SplitGrow,
+    #[strum(serialize = "split_shrink")]
+    #[strum(message = "Shrink Split")]
+    SplitShrink,
+    #[strum(serialize = "split_maximize")]
+    #[strum(message = "Maximize or Restore Split")]
+    SplitMaximize,
     #[strum(serialize = "search_whole_word_forward")]
     SearchWholeWordForward,
     #[strum(serialize = "search_forward")]
@@ -252,6 +270,9 @@ pub enum FocusCommand {
     SearchBackward,
     #[strum(serialize = "toggle_case_sensitive_search")]
     ToggleCaseSensitive,
+    #[strum(message = "Toggle Search in Selection")]
+    #[strum(serialize = "toggle_search_in_selection")]
+    ToggleSearchInSelection,
     #[strum(serialize = "global_search_refresh")]
     GlobalSearchRefresh,
     #[strum(serialize = "clear_search")]
@@ -292,8 +313,23 @@ pub enum FocusCommand {
     ShowCodeActions,
     #[strum(serialize = "get_completion")]
     GetCompletion,
+    #[strum(message = "Accept Inline Suggestion")]
+    #[strum(serialize = "accept_inline_suggestion")]
+    AcceptInlineSuggestion,
+    #[strum(message = "Accept Next Word of Inline Suggestion")]
+    #[strum(serialize = "accept_inline_suggestion_word")]
+    AcceptInlineSuggestionWord,
     #[strum(serialize = "get_signature")]
     GetSignature,
+    #[strum(message = "Next Signature")]
+    #[strum(serialize = "next_signature")]
+    NextSignature,
+    #[strum(message = "Previous Signature")]
+    #[strum(serialize = "previous_signature")]
+    PreviousSignature,
+    #[strum(message = "Toggle Signature Documentation")]
+    #[strum(serialize = "toggle_signature_documentation")]
+    ToggleSignatureDocumentation,
     /// This will close a modal, such as the settings window or completion
     #[strum(message = "Close Modal")]
     #[strum(serialize = "modal.close")]
@@ -322,12 +358,78 @@ pub enum FocusCommand {
     #[strum(serialize = "format_document")]
     #[strum(message = "Format Document")]
     FormatDocument,
+    #[strum(serialize = "format_selection")]
+    #[strum(message = "Format Selection")]
+    FormatSelection,
+    #[strum(serialize = "organize_imports")]
+    #[strum(message = "Organize Imports")]
+    OrganizeImports,
+    #[strum(serialize = "convert_indentation_to_tabs")]
+    #[strum(message = "Convert Indentation to Tabs")]
+    ConvertIndentationToTabs,
+    #[strum(serialize = "reflow")]
+    #[strum(message = "Reflow Lines")]
+    Reflow,
+    #[strum(serialize = "rebase_pick")]
+    #[strum(message = "Rebase: Pick Commit")]
+    RebasePick,
+    #[strum(serialize = "rebase_reword")]
+    #[strum(message = "Rebase: Reword Commit")]
+    RebaseReword,
+    #[strum(serialize = "rebase_edit")]
+    #[strum(message = "Rebase: Edit Commit")]
+    RebaseEdit,
+    #[strum(serialize = "rebase_squash")]
+    #[strum(message = "Rebase: Squash Commit")]
+    RebaseSquash,
+    #[strum(serialize = "rebase_fixup")]
+    #[strum(message = "Rebase: Fixup Commit")]
+    RebaseFixup,
+    #[strum(serialize = "rebase_drop")]
+    #[strum(message = "Rebase: Drop Commit")]
+    RebaseDrop,
+    #[strum(serialize = "file_explorer_new_file")]
+    #[strum(message = "File Explorer: New File")]
+    FileExplorerNewFile,
+    #[strum(serialize = "file_explorer_new_folder")]
+    #[strum(message = "File Explorer: New Folder")]
+    FileExplorerNewFolder,
+    #[strum(serialize = "file_explorer_rename")]
+    #[strum(message = "File Explorer: Rename")]
+    FileExplorerRename,
+    #[strum(serialize = "file_explorer_delete")]
+    #[strum(message = "File Explorer: Delete")]
+    FileExplorerDelete,
+    #[strum(serialize = "file_explorer_delete_permanently")]
+    #[strum(message = "File Explorer: Delete Permanently")]
+    FileExplorerDeletePermanently,
+    #[strum(serialize = "file_explorer_cut")]
+    #[strum(message = "File Explorer: Cut")]
+    FileExplorerCut,
+    #[strum(serialize = "file_explorer_copy")]
+    #[strum(message = "File Explorer: Copy")]
+    FileExplorerCopy,
+    #[strum(serialize = "file_explorer_paste")]
+    #[strum(message = "File Explorer: Paste")]
+    FileExplorerPaste,
     #[strum(serialize = "search")]
     Search,
     #[strum(serialize = "focus_replace_editor")]
     FocusReplaceEditor,
     #[strum(serialize = "focus_find_editor")]
     FocusFindEditor,
+    #[strum(message = "Replace Next Match")]
+    #[strum(serialize = "replace_next")]
+    ReplaceNext,
+    #[strum(message = "Replace All Matches")]
+    #[strum(serialize = "replace_all")]
+    ReplaceAll,
+    #[strum(message = "Previous Search History Entry")]
+    #[strum(serialize = "search_history_previous")]
+    SearchHistoryPrevious,
+    #[strum(message = "Next Search History Entry")]
+    #[strum(serialize = "search_history_next")]
+    SearchHistoryNext,
     #[strum(serialize = "inline_find_right")]
     InlineFindRight,
     #[strum(serialize = "inline_find_left")]
@@ -212,6 +212,7 @@ pub fn apply_delta(&mut self, delta: &RopeDelta) {
     }
 
     pub fn yank(&self, buffer: &Buffer) -> RegisterData {
+        let mut segments = Vec::new();
         let (content, mode) = match &self.mode {
             CursorMode::Insert(selection) => {
                 let mut mode = VisualMode::Normal;
@@ -224,6 +225,9 @@ pub fn yank(&self, buffer: &Buffer) -> RegisterData {
                     } else {
                         buffer.slice_to_cow(region.min()..region.max())
                     };
+                    if selection.len() > 1 {
+                        segments.push(region_content.to_string());
+                    }
                     if content.is_empty() {
                         content = region_content.to_string();
                     } else if content.ends_with('\n') {
@@ -299,7 +303,11 @@ pub fn yank(&self, buffer: &Buffer) -> RegisterData {
                 }
             },
         };
-        RegisterData { content, mode }
+        RegisterData {
+            content,
+            mode,
+            segments,
+        }
     }
 
     /// Return the current selection start and end position for a
@@ -319,34 +327,65 @@ pub fn get_selection(&self) -> Option<(usize, usize)> {
         }
     }
 
+    /// Returns the line, the column and the offset of the primary cursor.  
+    /// The line and column are zero based, and the column is counted in
+    /// characters.
     pub fn get_line_col_char(
         &self,
         buffer: &Buffer,
     ) -> Option<(usize, usize, usize)> {
-        match &self.mode {
-            CursorMode::Normal(offset) => {
-                let ln_col = buffer.offset_to_line_col(*offset);
-                Some((ln_col.0, ln_col.1, *offset))
-            }
-            CursorMode::Visual {
-                start,
-                end,
-                mode: _,
-            } => {
-                let v = buffer.offset_to_line_col(*start.min(end));
-                Some((v.0, v.1, *start))
-            }
-            CursorMode::Insert(selection) => {
-                if selection.regions().len() > 1 {
-                    return None;
-                }
-
-                let x = selection.regions().get(0).unwrap();
-                let v = buffer.offset_to_line_col(x.start);
+        let offset = match &self.mode {
+            CursorMode::Normal(offset) => *offset,
+            CursorMode::Visual { end, .. } => *end,
+            CursorMode::Insert(selection) => selection.last_inserted()?.end,
+        };
+        let line = buffer.line_of_offset(offset);
+        let line_start = buffer.offset_of_line(line);
+        let col = buffer.slice_to_cow(line_start..offset).chars().count();
+        Some((line, col, offset))
+    }
 
-                Some((v.0, v.1, x.start))
+    /// Returns the number of selected characters and the number of lines
+    /// covered by the selections, summed over all the selection regions.
+    pub fn get_selection_stats(&self, buffer: &Buffer) -> (usize, usize) {
+        let ranges = match &self.mode {
+            CursorMode::Normal(_) => Vec::new(),
+            CursorMode::Visual { start, end, mode } => {
+                let min = *start.min(end);
+                let max = *start.max(end);
+                let range = match mode {
+                    VisualMode::Linewise => (
+                        buffer.offset_of_line(buffer.line_of_offset(min)),
+                        buffer.offset_of_line(buffer.line_of_offset(max) + 1),
+                    ),
+                    // the character under the cursor is included in the
+                    // visual selection
+                    _ => (min, buffer.next_grapheme_offset(max, 1, buffer.len())),
+                };
+                vec![range]
             }
-        }
+            CursorMode::Insert(selection) => selection
+                .regions()
+                .iter()
+                .filter(|region| !region.is_caret())
+                .map(|region| (region.min(), region.max()))
+                .collect(),
+        };
+
+        ranges
+            .into_iter()
+            .fold((0, 0), |(chars, lines), (start, end)| {
+                let start_line = buffer.line_of_offset(start);
+                let mut end_line = buffer.line_of_offset(end);
+                // a selection ending at the start of a line doesn't cover it
+                if end_line > start_line && buffer.offset_of_line(end_line) == end {
+                    end_line -= 1;
+                }
+                (
+                    chars + buffer.slice_to_cow(start..end).chars().count(),
+                    lines + end_line - start_line + 1,
+                )
+            })
     }
 
     pub fn get_selection_count(&self) -> usize {
@@ -540,3 +579,51 @@ pub fn get_first_selection_after(
             Cursor::new(cursor_mode, None, None)
         })
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_selection_stats() {
+        let buffer = Buffer::new("ab\u{e9}\ndef\nghi\n");
+
+        let cursor = Cursor::new(CursorMode::Normal(4), None, None);
+        assert_eq!(cursor.get_selection_stats(&buffer), (0, 0));
+        // the column counts characters rather than bytes
+        assert_eq!(cursor.get_line_col_char(&buffer), Some((0, 3, 4)));
+
+        // carets aren't counted, and a selection ending at the start of a
+        // line doesn't cover it
+        let mut selection = Selection::new();
+        selection.add_region(SelRegion::new(1, 7, None));
+        selection.add_region(SelRegion::caret(8));
+        selection.add_region(SelRegion::new(9, 13, None));
+        let cursor = Cursor::new(CursorMode::Insert(selection), None, None);
+        assert_eq!(cursor.get_selection_stats(&buffer), (9, 3));
+
+        let cursor = Cursor::new(
+            CursorMode::Visual {
+                start: 6,
+                end: 10,
+                mode: VisualMode::Linewise,
+            },
+            None,
+            None,
+        );
+        assert_eq!(cursor.get_selection_stats(&buffer), (8, 2));
+
+        // the character under the cursor is part of the visual selection
+        let cursor = Cursor::new(
+            CursorMode::Visual {
+                start: 10,
+                end: 6,
+                mode: VisualMode::Normal,
+            },
+            None,
+            None,
+        );
+        assert_eq!(cursor.get_selection_stats(&buffer), (5, 2));
+        assert_eq!(cursor.get_line_col_char(&buffer), Some((1, 1, 6)));
+    }
+}
@@ -6,26 +6,48 @@
 
 pub struct Directory {}
 
+/// The environment variable turning on portable mode, which is inherited by the
+/// processes Lapce spawns, such as the proxy
+const PORTABLE_ENV: &str = "LAPCE_PORTABLE";
+/// The file next to the executable whose existence turns on portable mode
+const PORTABLE_MARKER: &str = "lapce-portable";
+/// The directory next to the executable holding all the data in portable mode
+const PORTABLE_DATA: &str = "lapce-data";
+
 impl Directory {
     pub fn home_dir() -> Option<PathBuf> {
         BaseDirs::new().map(|d| PathBuf::from(d.home_dir()))
     }
 
-    #[cfg(not(feature = "portable"))]
-    fn project_dirs() -> Option<ProjectDirs> {
-        ProjectDirs::from("dev", "lapce", NAME)
+    /// Turn on portable mode for this process and the ones it spawns. This
+    /// needs to happen before any of the directories are used.
+    pub fn set_portable() {
+        std::env::set_var(PORTABLE_ENV, "1");
+    }
+
+    /// Whether the config, plugins and caches are stored next to the
+    /// executable instead of in the user profile, because Lapce was built or
+    /// launched as portable, or a marker file is next to the executable
+    pub fn is_portable() -> bool {
+        cfg!(feature = "portable")
+            || std::env::var_os(PORTABLE_ENV).is_some()
+            || Self::executable_directory()
+                .map(|dir| dir.join(PORTABLE_MARKER).exists())
+                .unwrap_or(false)
     }
 
-    /// Return path adjacent to lapce executable when built as portable
-    #[cfg(feature = "portable")]
+    fn executable_directory() -> Option<PathBuf> {
+        let current_exe = std::env::current_exe().ok()?;
+        current_exe.parent().map(|dir| dir.to_path_buf())
+    }
+
+    /// Return path adjacent to lapce executable when portable
     fn project_dirs() -> Option<ProjectDirs> {
-        if let Ok(current_exe) = std::env::current_exe() {
-            if let Some(parent) = current_exe.parent() {
-                return ProjectDirs::from_path(parent.join("lapce-data"));
-            }
-            unreachable!("Couldn't obtain current process parent path");
+        if Self::is_portable() {
+            let dir = Self::executable_directory()?;
+            return ProjectDirs::from_path(dir.join(PORTABLE_DATA));
         }
-        unreachable!("Couldn't obtain current process path");
+        ProjectDirs::from("dev", "lapce", NAME)
     }
 
     // Get path of local data directory
@@ -128,6 +150,21 @@ pub fn config_directory() -> Option<PathBuf> {
         }
     }
 
+    /// Get the path to the profiles folder
+    /// Each profile has its own folder, named after it, which contains the
+    /// settings, keymaps and plugins overridden by the profile
+    pub fn profiles_directory() -> Option<PathBuf> {
+        if let Some(dir) = Self::config_directory() {
+            let dir = dir.join("profiles");
+            if !dir.exists() {
+                let _ = std::fs::create_dir(&dir);
+            }
+            Some(dir)
+        } else {
+            None
+        }
+    }
+
     pub fn local_socket() -> Option<PathBuf> {
         Self::data_local_directory().map(|dir| dir.join("local.sock"))
     }
@@ -7,6 +7,7 @@
     buffer::{rope_text::RopeText, Buffer, InvalLines},
     command::EditCommand,
     cursor::{get_first_selection_after, Cursor, CursorMode},
+    language::LapceLanguage,
     mode::{Mode, MotionMode, VisualMode},
     register::{Clipboard, Register, RegisterData, RegisterKind},
     selection::{InsertDrift, SelRegion, Selection},
@@ -97,6 +98,9 @@ pub fn insert(
             } else {
                 let c = s.chars().next().unwrap();
                 let matching_pair_type = matching_pair_direction(c);
+                let auto_pair_close = syntax.language.auto_pair_close(c);
+                // Pairs like quotes, which open and close with the same char
+                let is_symmetric_pair = auto_pair_close == Some(c);
 
                 // The main edit operations
                 let mut edits = vec![];
@@ -115,30 +119,21 @@ pub fn insert(
                         None
                     };
 
-                    // when text is selected, and [,{,(,'," is inserted
-                    // wrap the text with that char and its corresponding closing pair
-                    if region.start != region.end
-                        && (matching_pair_type == Some(true)
-                            || c == '"'
-                            || c == '\'')
-                    {
-                        edits.push((
-                            Selection::region(region.min(), region.min()),
-                            c.to_string(),
-                        ));
-                        edits_after.push((
-                            idx,
-                            match c {
-                                '"' => '"',
-                                '\'' => '\'',
-                                _ => matching_char(c).unwrap(),
-                            },
-                        ));
-                        continue;
+                    // when text is selected, and the opening char of an auto pair
+                    // is inserted, wrap the text with that char and its closing pair
+                    if region.start != region.end {
+                        if let Some(close) = auto_pair_close {
+                            edits.push((
+                                Selection::region(region.min(), region.min()),
+                                c.to_string(),
+                            ));
+                            edits_after.push((idx, close));
+                            continue;
+                        }
                     }
 
                     if auto_closing_matching_pairs {
-                        if (c == '"' || c == '\'') && cursor_char == Some(c) {
+                        if is_symmetric_pair && cursor_char == Some(c) {
                             // Skip the closing character
                             let new_offset =
                                 buffer.next_grapheme_offset(offset, 1, buffer.len());
@@ -189,8 +184,7 @@ pub fn insert(
                             }
                         }
 
-                        if matching_pair_type == Some(true) || c == '"' || c == '\''
-                        {
+                        if let Some(close) = auto_pair_close {
                             // Create a late edit to insert the closing pair, if allowed.
                             let is_whitespace_or_punct = cursor_char
                                 .map(|c| {
@@ -201,29 +195,23 @@ pub fn insert(
                                 })
                                 .unwrap_or(true);
 
-                            let should_insert_pair = match c {
-                                '"' | '\'' => {
-                                    is_whitespace_or_punct
-                                        && prev_cursor_char
-                                            .map(|c| {
-                                                let prop = get_char_property(c);
-                                                prop == CharClassification::Lf
-                                                    || prop == CharClassification::Space
-                                                    || prop
-                                                        == CharClassification::Punctuation
-                                            })
-                                            .unwrap_or(true)
-                                }
-                                _ => is_whitespace_or_punct,
+                            let should_insert_pair = if is_symmetric_pair {
+                                is_whitespace_or_punct
+                                    && prev_cursor_char
+                                        .map(|c| {
+                                            let prop = get_char_property(c);
+                                            prop == CharClassification::Lf
+                                                || prop == CharClassification::Space
+                                                || prop
+                                                    == CharClassification::Punctuation
+                                        })
+                                        .unwrap_or(true)
+                            } else {
+                                is_whitespace_or_punct
                             };
 
                             if should_insert_pair {
-                                let insert_after = match c {
-                                    '"' => '"',
-                                    '\'' => '\'',
-                                    _ => matching_char(c).unwrap(),
-                                };
-                                edits_after.push((idx, insert_after));
+                                edits_after.push((idx, close));
                             }
                         };
                     }
@@ -341,6 +329,7 @@ fn insert_new_line(
         buffer: &mut Buffer,
         cursor: &mut Cursor,
         selection: Selection,
+        syntax: &Syntax,
     ) -> Vec<(RopeDelta, InvalLines, SyntaxEdit)> {
         let mut edits = Vec::with_capacity(selection.regions().len());
         let mut extra_edits = Vec::new();
@@ -357,7 +346,12 @@ fn insert_new_line(
 
             let new_line_content = {
                 let indent_storage;
-                let indent = if has_unmatched_pair(&first_half) {
+                // The indents query of the language, if it has one, says
+                // better than the brackets whether the new line is indented
+                let indented = syntax
+                    .indent_after(line_start, offset)
+                    .unwrap_or_else(|| has_unmatched_pair(&first_half));
+                let indent = if indented {
                     indent_storage =
                         format!("{}{}", line_indent, buffer.indent_unit());
                     &indent_storage
@@ -446,6 +440,7 @@ pub fn execute_motion_mode(
                         } else {
                             VisualMode::Normal
                         },
+                        segments: Vec::new(),
                     },
                 );
                 let selection = Selection::region(start, end);
@@ -466,6 +461,7 @@ pub fn execute_motion_mode(
                         } else {
                             VisualMode::Normal
                         },
+                        segments: Vec::new(),
                     },
                 );
             }
@@ -489,6 +485,15 @@ pub fn do_paste(
         buffer: &mut Buffer,
         data: &RegisterData,
     ) -> Vec<(RopeDelta, InvalLines, SyntaxEdit)> {
+        if let CursorMode::Insert(selection) = &cursor.mode {
+            // Each cursor gets its own piece when there are as many cursors as
+            // there were when it was copied, and the whole of it otherwise
+            if data.segments.len() > 1 && data.segments.len() == selection.len() {
+                let selection = selection.clone();
+                return Self::do_paste_segments(cursor, buffer, selection, data);
+            }
+        }
+
         let mut deltas = Vec::new();
         match data.mode {
             VisualMode::Normal => {
@@ -583,6 +588,45 @@ pub fn do_paste(
         deltas
     }
 
+    fn do_paste_segments(
+        cursor: &mut Cursor,
+        buffer: &mut Buffer,
+        selection: Selection,
+        data: &RegisterData,
+    ) -> Vec<(RopeDelta, InvalLines, SyntaxEdit)> {
+        let linewise = data.mode != VisualMode::Normal;
+        let edits: Vec<(Selection, String)> = selection
+            .regions()
+            .iter()
+            .zip(data.segments.iter())
+            .map(|(region, segment)| {
+                if linewise && region.is_caret() {
+                    // A line goes above the line of the cursor
+                    let line = buffer.line_of_offset(region.start);
+                    let offset = buffer.offset_of_line(line);
+                    let mut segment = segment.clone();
+                    if !segment.ends_with('\n') {
+                        segment.push('\n');
+                    }
+                    (Selection::caret(offset), segment)
+                } else {
+                    (
+                        Selection::region(region.min(), region.max()),
+                        segment.clone(),
+                    )
+                }
+            })
+            .collect();
+        let edits: Vec<(&Selection, &str)> = edits
+            .iter()
+            .map(|(selection, segment)| (selection, segment.as_str()))
+            .collect();
+        let (delta, inval_lines, edits) = buffer.edit(&edits, EditType::Paste);
+        let selection = selection.apply_delta(&delta, true, InsertDrift::Default);
+        cursor.mode = CursorMode::Insert(selection);
+        vec![(delta, inval_lines, edits)]
+    }
+
     fn do_indent(
         buffer: &mut Buffer,
         selection: Selection,
@@ -698,6 +742,65 @@ fn duplicate_line(
         vec![(delta, inval_lines, edits)]
     }
 
+    /// The range to replace, and what to replace it with, to join the line
+    /// with the next one. The comment leader of the next line goes when both
+    /// lines are comments, and so does a trailing comma before a closing
+    /// bracket.
+    fn join_lines_edit(
+        buffer: &Buffer,
+        syntax: &Syntax,
+        line: usize,
+    ) -> (usize, usize, &'static str) {
+        let line_start = buffer.offset_of_line(line);
+        let current =
+            buffer.slice_to_cow(line_start..buffer.line_end_offset(line, true));
+        let current = current.trim_end();
+        let mut start = line_start + current.len();
+        let mut end = buffer.first_non_blank_character_on_line(line + 1);
+        let next = buffer.slice_to_cow(end..buffer.line_end_offset(line + 1, true));
+
+        let comment_token = syntax.language.comment_token();
+        if !comment_token.is_empty()
+            && current.trim_start().starts_with(comment_token.as_str())
+            && next.starts_with(comment_token.as_str())
+        {
+            // The leader the lines share is taken off as a whole, so that
+            // the `///` of a doc comment doesn't leave a `/` behind
+            let leader = |line: &str| {
+                let extra = line[comment_token.len()..]
+                    .find(|c: char| c != '!' && !comment_token.contains(c))
+                    .unwrap_or(line.len() - comment_token.len());
+                comment_token.len() + extra
+            };
+            let current = current.trim_start();
+            let shared = current[..leader(current)]
+                .chars()
+                .zip(next[..leader(&next)].chars())
+                .take_while(|(a, b)| a == b)
+                .count();
+            let rest = &next[shared..];
+            end += shared + rest.len() - rest.trim_start().len();
+            return (start, end, " ");
+        }
+
+        let separator = match next.chars().next() {
+            None => "",
+            Some(c @ (')' | ']' | '}')) => {
+                if current.ends_with(',') {
+                    start -= 1;
+                }
+                if c == '}' {
+                    " "
+                } else {
+                    ""
+                }
+            }
+            Some(_) if current.is_empty() || current.ends_with(['(', '[']) => "",
+            Some(_) => " ",
+        };
+        (start, end, separator)
+    }
+
     pub fn do_edit<T: Clipboard>(
         cursor: &mut Cursor,
         buffer: &mut Buffer,
@@ -781,11 +884,14 @@ pub fn do_edit<T: Clipboard>(
                 deltas
             }
             InsertNewLine => match cursor.mode.clone() {
-                CursorMode::Normal(offset) => {
-                    Self::insert_new_line(buffer, cursor, Selection::caret(offset))
-                }
+                CursorMode::Normal(offset) => Self::insert_new_line(
+                    buffer,
+                    cursor,
+                    Selection::caret(offset),
+                    syntax,
+                ),
                 CursorMode::Insert(selection) => {
-                    Self::insert_new_line(buffer, cursor, selection)
+                    Self::insert_new_line(buffer, cursor, selection, syntax)
                 }
                 CursorMode::Visual {
                     start: _,
@@ -840,16 +946,51 @@ pub fn do_edit<T: Clipboard>(
                 let offset = cursor.offset();
                 let (line, _col) = buffer.offset_to_line_col(offset);
                 if line < buffer.last_line() {
-                    let start = buffer.line_end_offset(line, true);
-                    let end = buffer.first_non_blank_character_on_line(line + 1);
-                    vec![buffer.edit(
-                        &[(&Selection::region(start, end), " ")],
+                    let (start, end, separator) =
+                        Self::join_lines_edit(buffer, syntax, line);
+                    let (delta, inval_lines, edits) = buffer.edit(
+                        &[(&Selection::region(start, end), separator)],
                         EditType::Other,
-                    )]
+                    );
+                    cursor.apply_delta(&delta);
+                    vec![(delta, inval_lines, edits)]
                 } else {
                     vec![]
                 }
             }
+            SplitArguments | JoinArguments => {
+                let Some(list) = syntax.bracket_list(cursor.offset()) else {
+                    return vec![];
+                };
+                let items = list
+                    .items
+                    .iter()
+                    .map(|item| buffer.slice_to_cow(item.clone()).to_string())
+                    .collect::<Vec<_>>();
+                let text = if matches!(cmd, SplitArguments) {
+                    let indent =
+                        buffer.indent_on_line(buffer.line_of_offset(list.open));
+                    let trailing_comma = list.trailing_comma
+                        || matches!(
+                            syntax.language,
+                            LapceLanguage::Rust | LapceLanguage::Go
+                        );
+                    split_list(&items, &indent, buffer.indent_unit(), trailing_comma)
+                } else {
+                    let spaced =
+                        buffer.slice_to_cow(list.open..list.open + 1) == "{";
+                    match join_list(&items, spaced) {
+                        Some(text) => text,
+                        None => return vec![],
+                    }
+                };
+                let (delta, inval_lines, edits) = buffer.edit(
+                    &[(&Selection::region(list.open + 1, list.close), text)],
+                    EditType::Other,
+                );
+                cursor.apply_delta(&delta);
+                vec![(delta, inval_lines, edits)]
+            }
             OutdentLine => {
                 let selection = cursor.edit_selection(buffer);
                 let (delta, inval_lines, edits) =
@@ -883,7 +1024,7 @@ pub fn do_edit<T: Clipboard>(
                         if indent < smallest_indent {
                             smallest_indent = indent;
                         }
-                        if !trimmed_content.starts_with(comment_token) {
+                        if !trimmed_content.starts_with(comment_token.as_str()) {
                             had_comment = false;
                             lines.insert((line, indent, 0));
                         } else {
@@ -980,7 +1121,7 @@ pub fn do_edit<T: Clipboard>(
             }
             ClipboardCopy => {
                 let data = cursor.yank(buffer);
-                clipboard.put_string(data.content);
+                register.copy_to_clipboard(data, clipboard);
 
                 match &cursor.mode {
                     CursorMode::Visual {
@@ -999,7 +1140,7 @@ pub fn do_edit<T: Clipboard>(
             }
             ClipboardCut => {
                 let data = cursor.yank(buffer);
-                clipboard.put_string(data.content);
+                register.copy_to_clipboard(data, clipboard);
 
                 let selection =
                     if let CursorMode::Insert(mut selection) = cursor.mode.clone() {
@@ -1026,12 +1167,7 @@ pub fn do_edit<T: Clipboard>(
             }
             ClipboardPaste => {
                 if let Some(s) = clipboard.get_string() {
-                    let mode = if s.ends_with('\n') {
-                        VisualMode::Linewise
-                    } else {
-                        VisualMode::Normal
-                    };
-                    let data = RegisterData { content: s, mode };
+                    let data = register.clipboard_data(s);
                     Self::do_paste(cursor, buffer, &data)
                 } else {
                     vec![]
@@ -1054,12 +1190,12 @@ pub fn do_edit<T: Clipboard>(
                 vec![]
             }
             Paste => {
-                let data = register.unnamed.clone();
+                let data = register.take_paste_data();
                 Self::do_paste(cursor, buffer, &data)
             }
             PasteBefore => {
                 let offset = cursor.offset();
-                let data = register.unnamed.clone();
+                let data = register.take_paste_data();
                 let mut local_cursor =
                     Cursor::new(CursorMode::Insert(Selection::new()), None, None);
                 local_cursor.set_offset(offset, false, false);
@@ -1073,8 +1209,12 @@ pub fn do_edit<T: Clipboard>(
                 } else {
                     buffer.first_non_blank_character_on_line(line)
                 };
-                let delta =
-                    Self::insert_new_line(buffer, cursor, Selection::caret(offset));
+                let delta = Self::insert_new_line(
+                    buffer,
+                    cursor,
+                    Selection::caret(offset),
+                    syntax,
+                );
                 if line == 0 {
                     cursor.mode = CursorMode::Insert(Selection::caret(offset));
                 }
@@ -1083,7 +1223,12 @@ pub fn do_edit<T: Clipboard>(
             NewLineBelow => {
                 let offset = cursor.offset();
                 let offset = buffer.offset_line_end(offset, true);
-                Self::insert_new_line(buffer, cursor, Selection::caret(offset))
+                Self::insert_new_line(
+                    buffer,
+                    cursor,
+                    Selection::caret(offset),
+                    syntax,
+                )
             }
             DeleteBackward => {
                 let (selection, edit_type) = match cursor.mode {
@@ -1521,12 +1666,78 @@ enum DuplicateDirection {
     Down,
 }
 
+/// The items of a list in brackets each on their own line, indented one
+/// level more than the line of the opening bracket. The later lines of an item
+/// keep their indentation relative to each other.
+fn split_list(
+    items: &[String],
+    indent: &str,
+    indent_unit: &str,
+    trailing_comma: bool,
+) -> String {
+    let item_indent = format!("{indent}{indent_unit}");
+    let mut text = String::from("\n");
+    for (i, item) in items.iter().enumerate() {
+        let mut lines = item.lines();
+        let first = lines.next().unwrap_or("");
+        let rest = lines.collect::<Vec<_>>();
+        let min_indent = rest
+            .iter()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| line.len() - line.trim_start().len())
+            .min()
+            .unwrap_or(0);
+        text.push_str(&item_indent);
+        text.push_str(first.trim_end());
+        for line in rest {
+            text.push('\n');
+            if !line.trim().is_empty() {
+                text.push_str(&item_indent);
+                text.push_str(line[min_indent..].trim_end());
+            }
+        }
+        if i + 1 < items.len() || trailing_comma {
+            text.push(',');
+        }
+        text.push('\n');
+    }
+    text.push_str(indent);
+    text
+}
+
+/// The items of a list in brackets on one line, with spaces inside braces.
+/// There's nothing to join if any of the items spans lines.
+fn join_list(items: &[String], spaced: bool) -> Option<String> {
+    if items.iter().any(|item| item.contains('\n')) {
+        return None;
+    }
+    let text = items.join(", ");
+    Some(if spaced { format!(" {text} ") } else { text })
+}
+
+/// The height an editor can scroll beyond its last line in a viewport of
+/// `viewport_height`, as a percent of the viewport less the last line
+pub fn scroll_beyond_last_line(
+    viewport_height: f64,
+    line_height: f64,
+    percent: usize,
+) -> f64 {
+    let percent = percent.min(100) as f64;
+    (viewport_height - line_height).max(0.0) * percent / 100.0
+}
+
 #[cfg(test)]
 mod test {
     use crate::{
         buffer::{rope_text::RopeText, Buffer},
         cursor::{Cursor, CursorMode},
-        editor::{DuplicateDirection, Editor},
+        editor::{
+            join_list, scroll_beyond_last_line, split_list, DuplicateDirection,
+            EditType, Editor,
+        },
+        language::LapceLanguage,
+        mode::VisualMode,
+        register::RegisterData,
         selection::{SelRegion, Selection},
         syntax::Syntax,
     };
@@ -1763,5 +1974,90 @@ fn check_multiple_cursor_match_insertion() {
         assert_eq!(cursor.mode, CursorMode::Insert(end_selection));
     }
 
+    #[test]
+    fn test_paste_segments() {
+        let mut buffer = Buffer::new("foo bar\nbaz qux\n");
+        let mut selection = Selection::new();
+        selection.add_region(SelRegion::new(0, 3, None));
+        selection.add_region(SelRegion::new(8, 11, None));
+        let cursor = Cursor::new(CursorMode::Insert(selection), None, None);
+        let data = cursor.yank(&buffer);
+        assert_eq!(data.segments, vec!["foo", "baz"]);
+
+        // As many cursors as pieces, so each cursor gets its own
+        let mut selection = Selection::new();
+        selection.add_region(SelRegion::caret(7));
+        selection.add_region(SelRegion::caret(15));
+        let mut cursor = Cursor::new(CursorMode::Insert(selection), None, None);
+        Editor::do_paste(&mut cursor, &mut buffer, &data);
+        assert_eq!(
+            "foo barfoo\nbaz quxbaz\n",
+            buffer.slice_to_cow(0..buffer.len())
+        );
+
+        // A single cursor gets the whole of it
+        let mut buffer = Buffer::new("");
+        let mut cursor =
+            Cursor::new(CursorMode::Insert(Selection::caret(0)), None, None);
+        let data = RegisterData {
+            content: "foo\nbaz".to_string(),
+            mode: VisualMode::Normal,
+            segments: vec!["foo".to_string(), "baz".to_string()],
+        };
+        Editor::do_paste(&mut cursor, &mut buffer, &data);
+        assert_eq!("foo\nbaz", buffer.slice_to_cow(0..buffer.len()));
+    }
+
+    #[test]
+    fn test_join_lines() {
+        fn join(text: &str, syntax: &Syntax) -> String {
+            let mut buffer = Buffer::new(text);
+            let (start, end, separator) =
+                Editor::join_lines_edit(&buffer, syntax, 0);
+            buffer.edit(
+                &[(&Selection::region(start, end), separator)],
+                EditType::Other,
+            );
+            buffer.slice_to_cow(0..buffer.len()).to_string()
+        }
+
+        let plaintext = Syntax::plaintext();
+        assert_eq!(join("foo  \n    bar", &plaintext), "foo bar");
+        assert_eq!(join("foo(\n    a,\n)", &plaintext), "foo(a,\n)");
+        assert_eq!(join("foo(a,\n)", &plaintext), "foo(a)");
+        assert_eq!(join("S { a: 1,\n}", &plaintext), "S { a: 1 }");
+        assert_eq!(join("foo\n\nbar", &plaintext), "foo\nbar");
+
+        let rust = Syntax::from_language(LapceLanguage::Rust);
+        assert_eq!(join("    // foo\n    //   bar", &rust), "    // foo bar");
+        assert_eq!(join("foo\n// bar", &rust), "foo // bar");
+        assert_eq!(join("/// foo\n/// bar", &rust), "/// foo bar");
+        assert_eq!(join("//! foo\n//! bar", &rust), "//! foo bar");
+        assert_eq!(join("// foo\n/// bar", &rust), "// foo / bar");
+    }
+
+    #[test]
+    fn test_split_and_join_list() {
+        let items = vec!["a".to_string(), "b(\n        c,\n    )".to_string()];
+        assert_eq!(
+            split_list(&items, "    ", "    ", true),
+            "\n        a,\n        b(\n            c,\n        ),\n    "
+        );
+        assert_eq!(join_list(&items, false), None);
+
+        let items = vec!["a: 1".to_string(), "b".to_string()];
+        assert_eq!(split_list(&items, "", "  ", false), "\n  a: 1,\n  b\n");
+        assert_eq!(join_list(&items, true), Some(" a: 1, b ".to_string()));
+        assert_eq!(join_list(&items, false), Some("a: 1, b".to_string()));
+    }
+
     // TODO(dbuga): add tests duplicating selections (multiple line blocks)
+
+    #[test]
+    fn test_scroll_beyond_last_line() {
+        assert_eq!(scroll_beyond_last_line(500.0, 20.0, 100), 480.0);
+        assert_eq!(scroll_beyond_last_line(500.0, 20.0, 50), 240.0);
+        assert_eq!(scroll_beyond_last_line(500.0, 20.0, 150), 480.0);
+        assert_eq!(scroll_beyond_last_line(10.0, 20.0, 100), 0.0);
+    }
 }
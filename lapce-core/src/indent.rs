@@ -206,3 +206,151 @@ pub fn auto_detect_indent_style(document_text: &Rope) -> Option<IndentStyle> {
         None
     }
 }
+
+/// The maximum number of lines looked at when checking for indentation issues.
+const INDENT_ISSUE_MAX_LINES: usize = 10000;
+
+/// An inconsistency in the indentation of a document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndentIssue {
+    /// Some lines are indented with tabs and others with spaces.
+    MixedTabsAndSpaces,
+    /// Lines indented with spaces don't use a consistent indent width.
+    InconsistentWidth,
+}
+
+/// Checks the leading whitespace of the document for mixed tabs and spaces,
+/// or for space indentation which isn't a multiple of `indent_width`.
+pub fn detect_indent_issue(
+    document_text: &Rope,
+    indent_width: usize,
+) -> Option<IndentIssue> {
+    let indent_width = indent_width.max(1);
+    let mut tab_lines = 0;
+    let mut space_lines = 0;
+    let mut uneven_lines = 0;
+    let mut mixed_lines = 0;
+
+    for line in document_text.lines(..).take(INDENT_ISSUE_MAX_LINES) {
+        let leading = line
+            .find(|c: char| c != ' ' && c != '\t')
+            .unwrap_or(line.len());
+        // Ignore blank lines.
+        if leading == line.len() {
+            continue;
+        }
+        let leading = &line[..leading];
+        if leading.is_empty() {
+            continue;
+        }
+
+        if leading.starts_with('\t') {
+            tab_lines += 1;
+            // Spaces after tabs are fine for alignment, tabs after spaces aren't.
+            if leading.trim_start_matches('\t').contains('\t') {
+                mixed_lines += 1;
+            }
+        } else if leading.contains('\t') {
+            mixed_lines += 1;
+        } else if leading.len() >= indent_width {
+            // Shorter space runs are usually alignment, e.g. ` * ` in block
+            // comments, rather than indentation.
+            space_lines += 1;
+            if leading.len() % indent_width != 0 {
+                uneven_lines += 1;
+            }
+        }
+    }
+
+    if mixed_lines > 0 || (tab_lines > 0 && space_lines > 0) {
+        Some(IndentIssue::MixedTabsAndSpaces)
+    } else if uneven_lines * 4 > space_lines {
+        Some(IndentIssue::InconsistentWidth)
+    } else {
+        None
+    }
+}
+
+/// Creates the edits that re-indent every line of the document with the
+/// given style, treating a tab as `tab_width` columns. Lines whose
+/// indentation is already correct are left alone.
+pub fn convert_indent_edits(
+    document_text: &Rope,
+    tab_width: usize,
+    style: IndentStyle,
+) -> Vec<(Selection, String)> {
+    let tab_width = tab_width.max(1);
+    let mut edits = Vec::new();
+    let mut offset = 0;
+    for line in document_text.lines_raw(..) {
+        let leading = line
+            .find(|c: char| c != ' ' && c != '\t')
+            .unwrap_or(line.len());
+        let current = &line[..leading];
+
+        let mut width = 0;
+        for c in current.chars() {
+            if c == '\t' {
+                width += tab_width - width % tab_width;
+            } else {
+                width += 1;
+            }
+        }
+
+        let new = match style {
+            IndentStyle::Tabs => format!(
+                "{}{}",
+                "\t".repeat(width / tab_width),
+                " ".repeat(width % tab_width)
+            ),
+            IndentStyle::Spaces(_) => " ".repeat(width),
+        };
+        if new != current {
+            edits.push((Selection::region(offset, offset + leading), new));
+        }
+
+        offset += line.len();
+    }
+    edits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_indent_issue() {
+        let text = Rope::from("fn a() {\n    b();\n}\n");
+        assert_eq!(detect_indent_issue(&text, 4), None);
+
+        let text = Rope::from("fn a() {\n    b();\n\tc();\n}\n");
+        assert_eq!(
+            detect_indent_issue(&text, 4),
+            Some(IndentIssue::MixedTabsAndSpaces)
+        );
+
+        let text = Rope::from("a:\n    b:\n      c: 1\n      d: 2\n");
+        assert_eq!(
+            detect_indent_issue(&text, 4),
+            Some(IndentIssue::InconsistentWidth)
+        );
+    }
+
+    #[test]
+    fn test_convert_indent_edits() {
+        let text = Rope::from("a\n\tb\n        c\n  \td\n");
+        let edits = convert_indent_edits(&text, 4, IndentStyle::Spaces(4));
+        let edits: Vec<_> = edits
+            .iter()
+            .map(|(s, t)| (s.min_offset(), s.max_offset(), t.as_str()))
+            .collect();
+        assert_eq!(edits, vec![(2, 3, "    "), (15, 18, "    ")]);
+
+        let edits = convert_indent_edits(&text, 4, IndentStyle::Tabs);
+        let edits: Vec<_> = edits
+            .iter()
+            .map(|(s, t)| (s.min_offset(), s.max_offset(), t.as_str()))
+            .collect();
+        assert_eq!(edits, vec![(5, 13, "\t\t"), (15, 18, "\t")]);
+    }
+}
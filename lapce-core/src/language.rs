@@ -1,17 +1,47 @@
-use std::{collections::HashSet, path::Path, str::FromStr};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
 
+use arc_swap::ArcSwap;
 use once_cell::sync::Lazy;
-use strum_macros::{AsRefStr, Display, EnumMessage, EnumString};
+use serde::{Deserialize, Serialize};
+use strum_macros::{AsRefStr, EnumDiscriminants, EnumMessage, EnumString};
 use tracing::{debug, error};
-use tree_sitter::TreeCursor;
+use tree_sitter::{Query, TreeCursor};
 
 use crate::{
     directory::Directory,
-    syntax::highlight::{HighlightConfiguration, HighlightIssue},
+    indent::IndentStyle,
+    syntax::highlight::{
+        clear_plugin_highlight_configs, HighlightConfiguration, HighlightIssue,
+    },
 };
 
 pub static RUNTIME_LANGUAGES: Lazy<Vec<SyntaxProperties>> = Lazy::new(Vec::new);
 
+/// User provided language settings, loaded from `languages.toml`.
+/// These take precedence over the built-in `LANGUAGES` table.
+static LANGUAGE_CONFIGS: Lazy<ArcSwap<HashMap<LapceLanguage, LanguageConfig>>> =
+    Lazy::new(|| ArcSwap::from_pointee(HashMap::new()));
+
+/// The grammars, queries and language settings provided by plugins. The user
+/// provided settings and queries take precedence over these.
+static PLUGIN_GRAMMARS: Lazy<ArcSwap<HashMap<LapceLanguage, PluginGrammar>>> =
+    Lazy::new(|| ArcSwap::from_pointee(HashMap::new()));
+/// Bumped each time the grammars provided by plugins change
+static PLUGIN_GRAMMARS_REV: AtomicU64 = AtomicU64::new(0);
+
+/// Pairs that are auto closed when no override is configured for a language.
+const DEFAULT_AUTO_PAIRS: &[(char, char)] =
+    &[('(', ')'), ('[', ']'), ('{', '}'), ('"', '"'), ('\'', '\'')];
+
 #[allow(dead_code)]
 const DEFAULT_CODE_LENS_LIST: &[&str] = &["source_file"];
 #[allow(dead_code)]
@@ -122,8 +152,131 @@ struct CommentProperties {
     multi_line_prefix: Option<&'static str>,
 }
 
-/// NOTE: Keep the enum variants "fieldless" so they can cast to usize as array
-/// indices into the LANGUAGES array.  See method `LapceLanguage::properties`.
+/// Per-language editing behaviour that can be overridden by the user in
+/// `languages.toml`, with one table per language:
+///
+/// ```toml
+/// [rust]
+/// comment = "//"
+/// indent = "    "
+/// auto-pairs = ["()", "[]", "{}", "\"\""]
+/// word-chars = "_"
+/// line-width = 100
+/// ```
+///
+/// Any field left out falls back to the built-in default for the language.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct LanguageConfig {
+    /// Single line comment token.
+    pub comment: Option<String>,
+    /// The indent unit, either a tab or up to 8 spaces.
+    pub indent: Option<String>,
+    /// Pairs of opening and closing characters which are auto closed,
+    /// written as two character strings such as `"()"`.
+    pub auto_pairs: Option<Vec<String>>,
+    /// Extra characters, besides letters and digits, that are part of a word.
+    pub word_chars: Option<String>,
+    /// The preferred maximum line width.
+    pub line_width: Option<usize>,
+}
+
+/// Replace the user provided language settings. The keys are language names,
+/// matched case insensitively. Unknown languages are logged and ignored.
+pub fn set_language_configs(configs: HashMap<String, LanguageConfig>) {
+    let configs = configs
+        .into_iter()
+        .filter_map(
+            |(name, config)| match LapceLanguage::from_config_name(&name) {
+                Some(language) => Some((language, config)),
+                None => {
+                    error!("unknown language '{name}' in languages.toml");
+                    None
+                }
+            },
+        )
+        .collect();
+    LANGUAGE_CONFIGS.store(Arc::new(configs));
+}
+
+/// The support for a language that a plugin provides: a tree-sitter grammar,
+/// its queries, and settings like the ones of `languages.toml`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PluginGrammar {
+    /// The name shown for a language that isn't built in.
+    pub display_name: Option<String>,
+    /// The file extensions of a language that isn't built in.
+    pub extensions: Vec<String>,
+    /// The file names of a language that isn't built in.
+    pub files: Vec<String>,
+    /// The folder with the `tree-sitter-<language>` library, only set when
+    /// the plugin is allowed to load native code.
+    pub grammar_dir: Option<PathBuf>,
+    /// The folder with the `highlights.scm`, `injections.scm`, `indents.scm`
+    /// and `folds.scm` queries.
+    pub queries_dir: Option<PathBuf>,
+    /// The settings of the language, under the ones of `languages.toml`.
+    pub config: LanguageConfig,
+}
+
+/// Replace the grammars provided by plugins. The keys are language names,
+/// matched case insensitively, and the ones that aren't built in are added as
+/// new languages.
+pub fn set_plugin_grammars(grammars: HashMap<String, PluginGrammar>) {
+    let grammars = grammars
+        .into_iter()
+        .map(|(name, grammar)| {
+            let language =
+                LapceLanguage::from_builtin_name(&name).unwrap_or_else(|| {
+                    LapceLanguage::Plugin(PluginLanguage::new(&name))
+                });
+            (language, grammar)
+        })
+        .collect::<HashMap<_, _>>();
+    if **PLUGIN_GRAMMARS.load() != grammars {
+        PLUGIN_GRAMMARS.store(Arc::new(grammars));
+        clear_plugin_highlight_configs();
+        PLUGIN_GRAMMARS_REV.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+/// The revision of the grammars provided by plugins. When it changes, the
+/// open documents need their syntax made again to pick up the new grammars.
+pub fn plugin_grammars_rev() -> u64 {
+    PLUGIN_GRAMMARS_REV.load(Ordering::SeqCst)
+}
+
+/// A language that isn't built in, but added by a plugin, by its lowercase
+/// name.
+#[derive(Eq, PartialEq, Ord, PartialOrd, Hash, Clone, Copy, Debug, Default)]
+pub struct PluginLanguage(&'static str);
+
+impl PluginLanguage {
+    /// The language of the name. The names are kept for as long as the editor
+    /// runs, for the language to be copied around like the built in ones.
+    fn new(name: &str) -> Self {
+        static NAMES: Lazy<Mutex<HashSet<&'static str>>> =
+            Lazy::new(|| Mutex::new(HashSet::new()));
+        let name = name.to_lowercase();
+        let mut names = NAMES.lock().unwrap();
+        match names.get(name.as_str()) {
+            Some(name) => PluginLanguage(name),
+            None => {
+                let name: &'static str = Box::leak(name.into_boxed_str());
+                names.insert(name);
+                PluginLanguage(name)
+            }
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.0
+    }
+}
+
+/// NOTE: Keep the enum variants "fieldless", but for `Plugin` which comes
+/// last, so that their discriminants are indices into the LANGUAGES array.
+/// See method `LapceLanguage::properties`.
 ///
 /// Do not assign values to the variants because the number of variants and
 /// number of elements in the LANGUAGES array change as different features
@@ -137,10 +290,10 @@ struct CommentProperties {
     Clone,
     Copy,
     Debug,
-    Display,
     AsRefStr,
     EnumString,
     EnumMessage,
+    EnumDiscriminants,
     Default,
 )]
 #[strum(ascii_case_insensitive)]
@@ -262,6 +415,20 @@ pub enum LapceLanguage {
     Yaml,
     #[strum(message = "Zig")]
     Zig,
+
+    /// A language added by a plugin, which has none of the built in
+    /// properties
+    #[strum(serialize = "plugin")]
+    Plugin(PluginLanguage),
+}
+
+impl fmt::Display for LapceLanguage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LapceLanguage::Plugin(language) => f.write_str(language.name()),
+            _ => f.write_str(self.as_ref()),
+        }
+    }
 }
 
 /// NOTE: Elements in the array must be in the same order as the enum variants of
@@ -1537,13 +1704,18 @@ pub enum LapceLanguage {
 
 impl LapceLanguage {
     const HIGHLIGHTS_QUERIES_FILE_NAME: &str = "highlights.scm";
+    const INJECTIONS_QUERIES_FILE_NAME: &str = "injections.scm";
+    const INDENTS_QUERIES_FILE_NAME: &str = "indents.scm";
+    const FOLDS_QUERIES_FILE_NAME: &str = "folds.scm";
     #[cfg(unix)]
     const SYSTEM_GRAMMARS_DIRECTORY: &str = "/usr/lib";
     #[cfg(unix)]
     const SYSTEM_QUERIES_DIRECTORY: &str = "/usr/share/tree-sitter/grammars";
 
     pub fn from_path(path: &Path) -> LapceLanguage {
-        Self::from_path_raw(path).unwrap_or(LapceLanguage::Plaintext)
+        Self::from_path_raw(path)
+            .or_else(|| Self::from_plugin_path(path))
+            .unwrap_or(LapceLanguage::Plaintext)
     }
 
     fn from_path_raw(path: &Path) -> Option<LapceLanguage> {
@@ -1562,41 +1734,114 @@ fn from_path_raw(path: &Path) -> Option<LapceLanguage> {
         None
     }
 
+    /// The language a plugin adds for the file
+    fn from_plugin_path(path: &Path) -> Option<LapceLanguage> {
+        let filename = path.file_name()?.to_str()?;
+        let extension = path.extension().and_then(|extension| extension.to_str());
+        PLUGIN_GRAMMARS
+            .load()
+            .iter()
+            .find(|(_, grammar)| {
+                grammar.files.iter().any(|file| file == filename)
+                    || extension.map_or(false, |extension| {
+                        grammar
+                            .extensions
+                            .iter()
+                            .any(|e| e.eq_ignore_ascii_case(extension))
+                    })
+            })
+            .map(|(language, _)| *language)
+    }
+
     pub fn from_name(name: &str) -> Option<LapceLanguage> {
-        match LapceLanguage::from_str(name.to_lowercase().as_str()) {
-            Ok(v) => Some(v),
-            Err(e) => {
-                eprintln!("failed parsing {name} LapceLanguage: {e}");
-                None
-            }
+        let language = Self::from_config_name(name).or_else(|| {
+            PLUGIN_GRAMMARS
+                .load()
+                .iter()
+                .find_map(|(language, grammar)| {
+                    grammar
+                        .display_name
+                        .as_ref()
+                        .filter(|display_name| {
+                            display_name.eq_ignore_ascii_case(name)
+                        })
+                        .map(|_| *language)
+                })
+        });
+        if language.is_none() {
+            eprintln!("failed parsing {name} LapceLanguage");
         }
+        language
+    }
+
+    /// The built in language of the name
+    fn from_builtin_name(name: &str) -> Option<LapceLanguage> {
+        LapceLanguage::from_str(name.to_lowercase().as_str())
+            .ok()
+            .filter(|language| !matches!(language, LapceLanguage::Plugin(_)))
     }
 
-    pub fn languages() -> Vec<&'static str> {
+    /// The language of the name in `languages.toml` or `volt.toml`, built in
+    /// or added by a plugin
+    fn from_config_name(name: &str) -> Option<LapceLanguage> {
+        Self::from_builtin_name(name).or_else(|| {
+            let language = LapceLanguage::Plugin(PluginLanguage::new(name));
+            PLUGIN_GRAMMARS
+                .load()
+                .contains_key(&language)
+                .then_some(language)
+        })
+    }
+
+    pub fn languages() -> Vec<String> {
         let mut langs = vec![];
         for l in LANGUAGES {
             if let Some(lang) = strum::EnumMessage::get_message(&l.id) {
-                langs.push(lang)
+                langs.push(lang.to_string())
+            }
+        }
+        for (language, grammar) in PLUGIN_GRAMMARS.load().iter() {
+            if let LapceLanguage::Plugin(language) = language {
+                langs.push(
+                    grammar
+                        .display_name
+                        .clone()
+                        .unwrap_or_else(|| language.name().to_string()),
+                );
             }
         }
         langs
     }
 
-    // NOTE: Instead of using `&LANGUAGES[*self as usize]` directly, the
+    // NOTE: Instead of using `&LANGUAGES[index]` directly, the
     // `debug_assertion` gives better feedback should something has gone wrong
     // badly.
     fn properties(&self) -> &SyntaxProperties {
-        let i = *self as usize;
+        // The languages of plugins have the properties of plain text, their
+        // settings coming from the plugin
+        let language = match self {
+            LapceLanguage::Plugin(_) => LapceLanguage::Plaintext,
+            language => *language,
+        };
+        let i = LapceLanguageDiscriminants::from(language) as usize;
         let l = &LANGUAGES[i];
         debug_assert!(
-            l.id == *self,
+            l.id == language,
             "LANGUAGES[{i}]: Setting::id mismatch: {:?} != {:?}",
             l.id,
-            self
+            language
         );
         l
     }
 
+    /// The name of the grammar library and of the folder of the queries
+    fn grammar_name(&self) -> String {
+        match self {
+            LapceLanguage::Plugin(language) => language.name().to_string(),
+            _ => self.properties().id.as_ref().to_string(),
+        }
+    }
+
     fn tree_sitter(&self) -> Option<TreeSitterProperties> {
         self.properties().tree_sitter
     }
@@ -1609,31 +1854,102 @@ pub fn sticky_header_tags(&self) -> &[&'static str] {
         }
     }
 
-    pub fn comment_token(&self) -> &str {
-        self.properties()
-            .comment
-            .single_line_start
+    /// Run `f` with the user provided config for this language, and then with
+    /// the one of the plugin providing the language if `f` gives nothing.
+    fn with_config<T>(&self, f: impl Fn(&LanguageConfig) -> Option<T>) -> Option<T> {
+        LANGUAGE_CONFIGS.load().get(self).and_then(&f).or_else(|| {
+            PLUGIN_GRAMMARS
+                .load()
+                .get(self)
+                .and_then(|grammar| f(&grammar.config))
+        })
+    }
+
+    /// Whether a plugin provides a grammar or queries for this language.
+    pub(crate) fn has_plugin_grammar(&self) -> bool {
+        PLUGIN_GRAMMARS.load().get(self).map_or(false, |grammar| {
+            grammar.grammar_dir.is_some() || grammar.queries_dir.is_some()
+        })
+    }
+
+    pub fn comment_token(&self) -> String {
+        self.with_config(|c| c.comment.clone()).unwrap_or_else(|| {
+            self.properties()
+                .comment
+                .single_line_start
+                .unwrap_or_default()
+                .to_string()
+        })
+    }
+
+    pub fn indent_unit(&self) -> &'static str {
+        self.with_config(|c| {
+            c.indent
+                .as_deref()
+                .filter(|indent| {
+                    !indent.is_empty()
+                        && indent.len() <= IndentStyle::LONGEST_INDENT.len()
+                })
+                .map(|indent| IndentStyle::from_str(indent).as_str())
+        })
+        .unwrap_or(self.properties().indent)
+    }
+
+    /// The character that should be inserted to close `c`, if `c` opens an
+    /// auto closed pair in this language.
+    pub fn auto_pair_close(&self, c: char) -> Option<char> {
+        self.with_config(|config| {
+            config.auto_pairs.as_ref().map(|pairs| {
+                pairs.iter().find_map(|pair| {
+                    let mut chars = pair.chars();
+                    match (chars.next(), chars.next(), chars.next()) {
+                        (Some(open), Some(close), None) if open == c => Some(close),
+                        _ => None,
+                    }
+                })
+            })
+        })
+        .unwrap_or_else(|| {
+            DEFAULT_AUTO_PAIRS
+                .iter()
+                .find(|(open, _)| *open == c)
+                .map(|(_, close)| *close)
+        })
+    }
+
+    /// Extra characters that should be considered part of a word.
+    pub fn word_chars(&self) -> String {
+        self.with_config(|c| c.word_chars.clone())
             .unwrap_or_default()
     }
 
-    pub fn indent_unit(&self) -> &str {
-        self.properties().indent
+    /// The preferred maximum line width, if one is configured.
+    pub fn line_width(&self) -> Option<usize> {
+        self.with_config(|c| c.line_width)
     }
 
     pub(crate) fn new_highlight_config(
         &self,
     ) -> Result<HighlightConfiguration, HighlightIssue> {
         let props = self.properties();
+        let grammar_name = self.grammar_name();
+        let plugin_grammar = PLUGIN_GRAMMARS.load().get(self).cloned();
 
-        let mut language = match props.tree_sitter {
-            Some(v) => (v.language)(),
-            None => return Err(HighlightIssue::NotAvailable),
-        };
+        let mut language = props.tree_sitter.map(|v| (v.language)());
+
+        if let Some(grammar_dir) = plugin_grammar
+            .as_ref()
+            .and_then(|grammar| grammar.grammar_dir.as_ref())
+        {
+            match self::load_plugin_grammar(&grammar_name, grammar_dir) {
+                Ok(grammar) => language = Some(grammar),
+                Err(e) => error!("{e:?}"),
+            }
+        }
 
         if let Some(grammars_dir) = Directory::grammars_directory() {
-            if let Ok(grammar) = self::load_grammar(props.id.as_ref(), &grammars_dir)
-            {
-                language = grammar;
+            if let Ok(grammar) = self::load_grammar(&grammar_name, &grammars_dir) {
+                language = Some(grammar);
             }
         };
 
@@ -1641,22 +1957,26 @@ pub(crate) fn new_highlight_config(
         {
             let grammars_dir = Path::new(Self::SYSTEM_GRAMMARS_DIRECTORY);
             if grammars_dir.exists() {
-                let grammars_dir = grammars_dir.join(props.id.as_ref());
+                let grammars_dir = grammars_dir.join(&grammar_name);
                 if grammars_dir.exists() {
                     if let Ok(grammar) =
-                        self::load_grammar(props.id.as_ref(), &grammars_dir)
+                        self::load_grammar(&grammar_name, &grammars_dir)
                     {
-                        language = grammar;
+                        language = Some(grammar);
                     }
                 }
             }
         };
 
+        let Some(language) = language else {
+            return Err(HighlightIssue::NotAvailable);
+        };
+
         let mut highlight = String::new();
 
         // Try reading highlights from user config dir
         if let Some(queries_dir) = Directory::queries_directory() {
-            let queries_dir = queries_dir.join(props.id.to_string().to_lowercase());
+            let queries_dir = queries_dir.join(grammar_name.to_lowercase());
             if queries_dir.exists() {
                 let highlights_file =
                     queries_dir.join(Self::HIGHLIGHTS_QUERIES_FILE_NAME);
@@ -1671,14 +1991,24 @@ pub(crate) fn new_highlight_config(
             }
         }
 
+        // Then from the plugin providing the language
+        let plugin_queries_dir = plugin_grammar
+            .as_ref()
+            .and_then(|grammar| grammar.queries_dir.as_ref());
+        if highlight.is_empty() {
+            if let Some(queries_dir) = plugin_queries_dir {
+                highlight = std::fs::read_to_string(
+                    queries_dir.join(Self::HIGHLIGHTS_QUERIES_FILE_NAME),
+                )
+                .unwrap_or_default();
+            }
+        }
+
         #[cfg(unix)]
         // Try reading highlights from system dir
         if highlight.is_empty() {
             let queries_dir = Path::new(Self::SYSTEM_QUERIES_DIRECTORY);
-            if queries_dir
-                .join(props.id.to_string().to_lowercase())
-                .exists()
-            {
+            if queries_dir.join(grammar_name.to_lowercase()).exists() {
                 let highlights_file =
                     queries_dir.join(Self::HIGHLIGHTS_QUERIES_FILE_NAME);
                 if highlights_file.exists() {
@@ -1691,20 +2021,63 @@ pub(crate) fn new_highlight_config(
         let query = if !highlight.is_empty() {
             highlight.as_str()
         } else {
-            props.tree_sitter.unwrap().highlight.unwrap_or_default()
+            props
+                .tree_sitter
+                .and_then(|v| v.highlight)
+                .unwrap_or_default()
         };
-        let injection = props.tree_sitter.unwrap().injection.unwrap_or_default();
+        let plugin_injection = plugin_queries_dir.and_then(|queries_dir| {
+            std::fs::read_to_string(
+                queries_dir.join(Self::INJECTIONS_QUERIES_FILE_NAME),
+            )
+            .ok()
+        });
+        let injection = plugin_injection
+            .as_deref()
+            .or_else(|| props.tree_sitter.and_then(|v| v.injection))
+            .unwrap_or_default();
 
         match HighlightConfiguration::new(language, query, injection, "") {
-            Ok(x) => Ok(x),
+            Ok(mut x) => {
+                x.indents_query = self.structure_query(
+                    language,
+                    Self::INDENTS_QUERIES_FILE_NAME,
+                    plugin_queries_dir,
+                );
+                x.folds_query = self.structure_query(
+                    language,
+                    Self::FOLDS_QUERIES_FILE_NAME,
+                    plugin_queries_dir,
+                );
+                Ok(x)
+            }
             Err(x) => {
-                let str = format!("Encountered {x:?} while trying to construct HighlightConfiguration for {}", strum::EnumMessage::get_message(self).unwrap_or(self.as_ref()));
+                let str = format!("Encountered {x:?} while trying to construct HighlightConfiguration for {}", strum::EnumMessage::get_message(self).map(str::to_string).unwrap_or_else(|| self.to_string()));
                 error!("{str}");
                 Err(HighlightIssue::Error(str))
             }
         }
     }
 
+    /// The indents or folds query of the language, from the user's queries
+    /// folder or else from the plugin providing the language
+    fn structure_query(
+        &self,
+        language: tree_sitter::Language,
+        file_name: &str,
+        plugin_queries_dir: Option<&PathBuf>,
+    ) -> Option<Query> {
+        let user_queries_dir = Directory::queries_directory()
+            .map(|dir| dir.join(self.grammar_name().to_lowercase()));
+        let source = [user_queries_dir.as_ref(), plugin_queries_dir]
+            .into_iter()
+            .flatten()
+            .find_map(|dir| std::fs::read_to_string(dir.join(file_name)).ok())?;
+        Query::new(language, &source)
+            .map_err(|e| error!("invalid {file_name} of {self}: {e:?}"))
+            .ok()
+    }
+
     pub(crate) fn walk_tree(
         &self,
         cursor: &mut TreeCursor,
@@ -1718,6 +2091,51 @@ pub(crate) fn walk_tree(
     }
 }
 
+/// The folder of a plugin's grammars with the libraries built for this
+/// platform, like `linux-x86_64`
+fn platform_grammar_dir(grammar_dir: &Path) -> PathBuf {
+    grammar_dir.join(format!(
+        "{}-{}",
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    ))
+}
+
+/// Load the grammar a plugin provides, from the folder for this platform if
+/// there is one, or else from the grammar folder itself. Only libraries are
+/// loaded, a grammar compiled to WASM is reported as unsupported.
+fn load_plugin_grammar(
+    grammar_name: &str,
+    grammar_dir: &Path,
+) -> Result<tree_sitter::Language, HighlightIssue> {
+    let platform_dir = platform_grammar_dir(grammar_dir);
+    let dirs = [platform_dir.as_path(), grammar_dir];
+    for dir in dirs {
+        let mut library_path = dir.join(format!("tree-sitter-{grammar_name}"));
+        library_path.set_extension(std::env::consts::DLL_EXTENSION);
+        if library_path.exists() {
+            return load_grammar(grammar_name, dir);
+        }
+    }
+
+    // The tree-sitter in use can only load grammars built as libraries
+    let wasm = format!("tree-sitter-{grammar_name}.wasm");
+    if let Some(dir) = dirs.iter().find(|dir| dir.join(&wasm).exists()) {
+        return Err(HighlightIssue::Error(format!(
+            "Can't load '{}': WASM grammars aren't supported, the plugin \
+             needs to provide a library built for {}-{}",
+            dir.join(&wasm).display(),
+            std::env::consts::OS,
+            std::env::consts::ARCH
+        )));
+    }
+
+    Err(HighlightIssue::Error(format!(
+        "Couldn't find the {grammar_name} grammar in '{}'",
+        grammar_dir.display()
+    )))
+}
+
 fn load_grammar(
     grammar_name: &str,
     path: &Path,
@@ -1791,3 +2209,147 @@ fn walk_tree(
         cursor.goto_parent();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::MutexGuard;
+
+    use super::*;
+
+    /// Held by the tests changing the global language settings, so that they
+    /// don't see each other's settings. The settings from before are
+    /// restored when it's dropped.
+    struct ConfigsGuard {
+        _lock: MutexGuard<'static, ()>,
+        configs: Arc<HashMap<LapceLanguage, LanguageConfig>>,
+        plugin_grammars: Arc<HashMap<LapceLanguage, PluginGrammar>>,
+    }
+
+    impl ConfigsGuard {
+        fn lock() -> Self {
+            static LOCK: Mutex<()> = Mutex::new(());
+            let lock = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+            Self {
+                _lock: lock,
+                configs: LANGUAGE_CONFIGS.load_full(),
+                plugin_grammars: PLUGIN_GRAMMARS.load_full(),
+            }
+        }
+    }
+
+    impl Drop for ConfigsGuard {
+        fn drop(&mut self) {
+            LANGUAGE_CONFIGS.store(self.configs.clone());
+            PLUGIN_GRAMMARS.store(self.plugin_grammars.clone());
+        }
+    }
+
+    #[test]
+    fn test_plugin_language() {
+        let _guard = ConfigsGuard::lock();
+        set_plugin_grammars(HashMap::from([(
+            "Gleam".to_string(),
+            PluginGrammar {
+                display_name: Some("Gleam".to_string()),
+                extensions: vec!["gleam".to_string()],
+                config: LanguageConfig {
+                    comment: Some("//".to_string()),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        )]));
+
+        let gleam = LapceLanguage::from_path(Path::new("src/app.gleam"));
+        assert_eq!(gleam, LapceLanguage::Plugin(PluginLanguage::new("gleam")));
+        assert_eq!(gleam.to_string(), "gleam");
+        assert_eq!(LapceLanguage::from_name("Gleam"), Some(gleam));
+        assert!(LapceLanguage::languages().contains(&"Gleam".to_string()));
+        assert_eq!(gleam.comment_token(), "//");
+        assert_eq!(gleam.indent_unit(), LapceLanguage::Plaintext.indent_unit());
+
+        assert_eq!(
+            LapceLanguage::from_path(Path::new("src/main.rs")),
+            LapceLanguage::Rust
+        );
+        assert_eq!(LapceLanguage::from_name("plugin"), None);
+    }
+
+    #[test]
+    fn test_language_config_overrides() {
+        let _guard = ConfigsGuard::lock();
+        let lua = LapceLanguage::Lua;
+        assert_eq!(lua.auto_pair_close('('), Some(')'));
+        assert_eq!(lua.line_width(), None);
+
+        set_language_configs(HashMap::from([(
+            "LUA".to_string(),
+            LanguageConfig {
+                comment: Some("#".to_string()),
+                indent: Some("\t".to_string()),
+                auto_pairs: Some(vec!["<>".to_string(), "bad".to_string()]),
+                word_chars: Some("-".to_string()),
+                line_width: Some(72),
+            },
+        )]));
+        assert_eq!(lua.comment_token(), "#");
+        assert_eq!(lua.indent_unit(), "\t");
+        // The configured pairs replace the default ones
+        assert_eq!(lua.auto_pair_close('<'), Some('>'));
+        assert_eq!(lua.auto_pair_close('('), None);
+        assert_eq!(lua.auto_pair_close('b'), None);
+        assert_eq!(lua.word_chars(), "-");
+        assert_eq!(lua.line_width(), Some(72));
+
+        // What's left out falls back to the built-in defaults
+        set_language_configs(HashMap::from([(
+            "lua".to_string(),
+            LanguageConfig {
+                line_width: Some(100),
+                ..Default::default()
+            },
+        )]));
+        assert_eq!(lua.comment_token(), "--");
+        assert_eq!(lua.auto_pair_close('('), Some(')'));
+        assert_eq!(lua.word_chars(), "");
+
+        set_language_configs(HashMap::new());
+        assert_eq!(lua.line_width(), None);
+    }
+
+    #[test]
+    fn test_platform_grammar_dir() {
+        let dir = platform_grammar_dir(Path::new("grammars"));
+        assert_eq!(dir.parent(), Some(Path::new("grammars")));
+        let name = dir.file_name().unwrap().to_str().unwrap();
+        assert_eq!(
+            name,
+            format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH)
+        );
+    }
+
+    #[test]
+    fn test_load_plugin_grammar() {
+        let grammar_dir = std::env::temp_dir().join("lapce-plugin-grammar-test");
+        let _ = std::fs::remove_dir_all(&grammar_dir);
+        let platform_dir = platform_grammar_dir(&grammar_dir);
+        std::fs::create_dir_all(&platform_dir).unwrap();
+
+        let missing = load_plugin_grammar("gleam", &grammar_dir).unwrap_err();
+        assert!(matches!(missing, HighlightIssue::Error(e) if e.contains("find")));
+
+        std::fs::write(platform_dir.join("tree-sitter-gleam.wasm"), "").unwrap();
+        let wasm = load_plugin_grammar("gleam", &grammar_dir).unwrap_err();
+        assert!(matches!(wasm, HighlightIssue::Error(e) if e.contains("WASM")));
+
+        // A library in the platform folder is the one loaded, so loading
+        // this invalid one fails instead of reporting the WASM grammar
+        let mut library = platform_dir.join("tree-sitter-gleam");
+        library.set_extension(std::env::consts::DLL_EXTENSION);
+        std::fs::write(&library, "").unwrap();
+        let invalid = load_plugin_grammar("gleam", &grammar_dir).unwrap_err();
+        assert!(matches!(invalid, HighlightIssue::Error(e) if !e.contains("WASM")));
+
+        std::fs::remove_dir_all(&grammar_dir).unwrap();
+    }
+}
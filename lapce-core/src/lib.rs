@@ -15,6 +15,7 @@
 pub mod mode;
 pub mod movement;
 pub mod paragraph;
+pub mod reflow;
 pub mod register;
 pub mod selection;
 pub mod soft_tab;
@@ -0,0 +1,164 @@
+/// A paragraph being reflowed, whose words are laid out again after the prefix
+/// of its first line and then after the prefix of its continuation lines.
+struct Paragraph<'a> {
+    first_prefix: String,
+    continuation_prefix: String,
+    words: Vec<&'a str>,
+}
+
+impl<'a> Paragraph<'a> {
+    fn wrap(&self, width: usize, lines: &mut Vec<String>) {
+        let mut line = self.first_prefix.clone();
+        let mut line_width = line.chars().count();
+        let mut has_words = false;
+        for word in &self.words {
+            let word_width = word.chars().count();
+            if has_words && line_width + 1 + word_width > width {
+                lines.push(line);
+                line = self.continuation_prefix.clone();
+                line_width = line.chars().count();
+                has_words = false;
+            }
+            if has_words {
+                line.push(' ');
+                line_width += 1;
+            }
+            line.push_str(word);
+            line_width += word_width;
+            has_words = true;
+        }
+        lines.push(line);
+    }
+}
+
+/// Splits a line into its prefix, being the indentation and the comment token
+/// with the spaces after it, and its content. Doc comments such as `///` and
+/// `//!` count as the comment token too.
+fn split_prefix<'a>(line: &'a str, comment_token: &str) -> (&'a str, &'a str) {
+    let mut end = line.len() - line.trim_start().len();
+    if !comment_token.is_empty() && line[end..].starts_with(comment_token) {
+        end += comment_token.len();
+        if let Some(c) = line[end..].chars().next() {
+            if c == '!' || Some(c) == comment_token.chars().last() {
+                end += c.len_utf8();
+            }
+        }
+        end += line[end..].len() - line[end..].trim_start_matches(' ').len();
+    }
+    line.split_at(end)
+}
+
+/// The length of the list marker the content starts with, including the space
+/// after it, such as `- `, `* ` or `1. `
+fn list_marker(content: &str) -> Option<usize> {
+    if ["- ", "* ", "+ "]
+        .iter()
+        .any(|marker| content.starts_with(marker))
+    {
+        return Some(2);
+    }
+    let digits = content.len()
+        - content
+            .trim_start_matches(|c: char| c.is_ascii_digit())
+            .len();
+    if digits > 0 {
+        let rest = &content[digits..];
+        if rest.starts_with(". ") || rest.starts_with(") ") {
+            return Some(digits + 2);
+        }
+    }
+    None
+}
+
+/// Whether the line is blank apart from its prefix, which separates
+/// paragraphs
+pub fn is_blank(line: &str, comment_token: &str) -> bool {
+    split_prefix(line, comment_token).1.trim().is_empty()
+}
+
+/// Hard-wraps the lines of `text` so that they fit in `width` columns where
+/// possible, joining the lines of each paragraph. The indentation, comment
+/// prefixes and list markers of the paragraphs are kept, and the continuation
+/// lines of a list item are aligned after its marker. Blank lines and lines
+/// starting a list item separate paragraphs.
+pub fn reflow(text: &str, width: usize, comment_token: &str) -> String {
+    let line_ending = if text.contains("\r\n") { "\r\n" } else { "\n" };
+    let mut lines = Vec::new();
+    let mut paragraph: Option<Paragraph> = None;
+    for line in text.lines() {
+        let (prefix, content) = split_prefix(line, comment_token);
+        if is_blank(line, comment_token) {
+            if let Some(paragraph) = paragraph.take() {
+                paragraph.wrap(width, &mut lines);
+            }
+            lines.push(line.trim_end().to_string());
+            continue;
+        }
+
+        let marker = list_marker(content);
+        if let Some(current) = paragraph.as_mut() {
+            if marker.is_none()
+                && (prefix == current.continuation_prefix
+                    || prefix == current.first_prefix)
+            {
+                current.words.extend(content.split_whitespace());
+                continue;
+            }
+        }
+
+        if let Some(paragraph) = paragraph.take() {
+            paragraph.wrap(width, &mut lines);
+        }
+        let marker = marker.unwrap_or(0);
+        paragraph = Some(Paragraph {
+            first_prefix: format!("{prefix}{}", &content[..marker]),
+            continuation_prefix: format!("{prefix}{}", " ".repeat(marker)),
+            words: content[marker..].split_whitespace().collect(),
+        });
+    }
+    if let Some(paragraph) = paragraph {
+        paragraph.wrap(width, &mut lines);
+    }
+    lines.join(line_ending)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reflow_paragraphs() {
+        let text = "one two three\nfour five six seven\n\neight nine";
+        assert_eq!(
+            reflow(text, 14, "//"),
+            "one two three\nfour five six\nseven\n\neight nine"
+        );
+        assert_eq!(reflow("a\nb\nc", 80, "//"), "a b c");
+    }
+
+    #[test]
+    fn test_reflow_comments_and_lists() {
+        let text = [
+            "    /// Some documentation that",
+            "    /// wraps.",
+            "    ///",
+            "    /// - a list item that is long",
+            "    /// - another",
+        ]
+        .join("\n");
+        let expected = [
+            "    /// Some documentation",
+            "    /// that wraps.",
+            "    ///",
+            "    /// - a list item that",
+            "    ///   is long",
+            "    /// - another",
+        ]
+        .join("\n");
+        assert_eq!(reflow(&text, 28, "//"), expected);
+        assert_eq!(
+            reflow("# 1. first\n#    second", 80, "#"),
+            "# 1. first second"
+        );
+    }
+}
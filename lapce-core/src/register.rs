@@ -1,5 +1,10 @@
+use std::collections::{HashMap, VecDeque};
+
 use crate::mode::VisualMode;
 
+/// The number of previous deletes kept in the registers `"1` to `"9`
+const DELETE_HISTORY_LEN: usize = 9;
+
 pub trait Clipboard {
     fn get_string(&self) -> Option<String>;
     fn put_string(&mut self, s: impl AsRef<str>);
@@ -9,12 +14,45 @@ pub trait Clipboard {
 pub struct RegisterData {
     pub content: String,
     pub mode: VisualMode,
+    /// The pieces yanked by each cursor, in the order of the cursors, when
+    /// there were several, so that a paste with as many cursors gives each
+    /// cursor its own piece
+    pub segments: Vec<String>,
+}
+
+impl RegisterData {
+    /// The data of text from the system clipboard, linewise if it ends a line
+    pub fn from_clipboard(content: String) -> Self {
+        let mode = if content.ends_with('\n') {
+            VisualMode::Linewise
+        } else {
+            VisualMode::Normal
+        };
+        Self {
+            content,
+            mode,
+            segments: Vec::new(),
+        }
+    }
 }
 
 #[derive(Clone, Default)]
 pub struct Register {
     pub unnamed: RegisterData,
     last_yank: RegisterData,
+    /// The previous deletes, the most recent first
+    deletes: VecDeque<RegisterData>,
+    /// The registers `"a` to `"z`, and the clipboard register `"+`
+    named: HashMap<char, RegisterData>,
+    /// The register chosen with `"x` for the next yank, delete or paste
+    pub selected: Option<char>,
+    /// Whether the clipboard register was written to since the content was
+    /// last put into the system clipboard
+    clipboard_changed: bool,
+    /// The data last put into the system clipboard, which keeps the segments
+    /// of a copy with several cursors for as long as the clipboard holds its
+    /// content
+    copied: Option<RegisterData>,
 }
 
 pub enum RegisterKind {
@@ -23,6 +61,15 @@ pub enum RegisterKind {
 }
 
 impl Register {
+    /// Whether `name` is a register that can be chosen with `"x`
+    pub fn is_valid_name(name: char) -> bool {
+        name == '"'
+            || name == '+'
+            || name == '*'
+            || name.is_ascii_digit()
+            || name.is_ascii_alphabetic()
+    }
+
     pub fn add(&mut self, kind: RegisterKind, data: RegisterData) {
         match kind {
             RegisterKind::Delete => self.add_delete(data),
@@ -31,11 +78,212 @@ pub fn add(&mut self, kind: RegisterKind, data: RegisterData) {
     }
 
     pub fn add_delete(&mut self, data: RegisterData) {
+        self.write_selected(&data);
+        self.deletes.push_front(data.clone());
+        self.deletes.truncate(DELETE_HISTORY_LEN);
         self.unnamed = data;
     }
 
     pub fn add_yank(&mut self, data: RegisterData) {
+        self.write_selected(&data);
         self.unnamed = data.clone();
         self.last_yank = data;
     }
+
+    /// Get the content of the register `name`
+    pub fn get(&self, name: char) -> Option<&RegisterData> {
+        match name {
+            '"' => Some(&self.unnamed),
+            '0' => Some(&self.last_yank),
+            '1'..='9' => self.deletes.get(name as usize - '1' as usize),
+            _ => self.named.get(&Self::storage_name(name)),
+        }
+    }
+
+    /// Set the named register `name`, an uppercase name appends to the
+    /// register of its lowercase name, as in vim.
+    pub fn set(&mut self, name: char, data: RegisterData) {
+        if !name.is_ascii_alphabetic() && name != '+' && name != '*' {
+            return;
+        }
+        let storage_name = Self::storage_name(name);
+        if storage_name == '+' {
+            self.clipboard_changed = true;
+        }
+        if name.is_ascii_uppercase() {
+            let register = self.named.entry(storage_name).or_default();
+            register.content.push_str(&data.content);
+            // The pieces of the cursors don't line up with the appended ones
+            register.segments.clear();
+            if data.mode == VisualMode::Linewise {
+                register.mode = VisualMode::Linewise;
+            }
+        } else {
+            self.named.insert(storage_name, data);
+        }
+    }
+
+    /// Take the data to paste, which is the selected register's if one was
+    /// chosen, or else the unnamed register's.
+    pub fn take_paste_data(&mut self) -> RegisterData {
+        match self.selected.take() {
+            Some(name) => self.get(name).cloned().unwrap_or_default(),
+            None => self.unnamed.clone(),
+        }
+    }
+
+    /// All the registers that have content, in the order vim lists them
+    pub fn entries(&self) -> Vec<(char, &RegisterData)> {
+        let mut entries = vec![('"', &self.unnamed), ('0', &self.last_yank)];
+        entries.extend(
+            self.deletes
+                .iter()
+                .enumerate()
+                .map(|(i, data)| ((b'1' + i as u8) as char, data)),
+        );
+        let mut named: Vec<(char, &RegisterData)> = self
+            .named
+            .iter()
+            .map(|(name, data)| (*name, data))
+            .collect();
+        named.sort_by_key(|(name, _)| *name);
+        entries.extend(named);
+        entries.retain(|(_, data)| !data.content.is_empty());
+        entries
+    }
+
+    /// Fill the clipboard register from the system clipboard if it is the
+    /// selected register, so that it can be pasted.
+    pub fn load_clipboard(&mut self, clipboard: &impl Clipboard) {
+        if !matches!(self.selected, Some('+' | '*')) {
+            return;
+        }
+        if let Some(content) = clipboard.get_string() {
+            let data = self.clipboard_data(content);
+            self.named.insert('+', data);
+        }
+    }
+
+    /// Put the data copied or cut into the system clipboard
+    pub fn copy_to_clipboard(
+        &mut self,
+        data: RegisterData,
+        clipboard: &mut impl Clipboard,
+    ) {
+        clipboard.put_string(&data.content);
+        self.copied = Some(data);
+    }
+
+    /// The data to paste from the system clipboard, with the segments of the
+    /// copy it came from if the clipboard still holds what was copied
+    pub fn clipboard_data(&self, content: String) -> RegisterData {
+        match &self.copied {
+            Some(data) if data.content == content => data.clone(),
+            _ => RegisterData::from_clipboard(content),
+        }
+    }
+
+    /// Put the content written to the clipboard register into the system
+    /// clipboard.
+    pub fn store_clipboard(&mut self, clipboard: &mut impl Clipboard) {
+        if !self.clipboard_changed {
+            return;
+        }
+        self.clipboard_changed = false;
+        if let Some(data) = self.named.get(&'+') {
+            clipboard.put_string(&data.content);
+            self.copied = Some(data.clone());
+        }
+    }
+
+    fn write_selected(&mut self, data: &RegisterData) {
+        if let Some(name) = self.selected.take() {
+            self.set(name, data.clone());
+        }
+    }
+
+    /// `"*` and `"+` are the same register, as there is only one clipboard
+    fn storage_name(name: char) -> char {
+        match name {
+            '*' => '+',
+            _ => name.to_ascii_lowercase(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data(content: &str) -> RegisterData {
+        RegisterData {
+            content: content.to_string(),
+            mode: VisualMode::Normal,
+            segments: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_selected_register() {
+        let mut register = Register::default();
+        register.selected = Some('a');
+        register.add_yank(data("foo"));
+        assert_eq!(register.get('a').unwrap().content, "foo");
+        assert_eq!(register.unnamed.content, "foo");
+        assert!(register.selected.is_none());
+
+        register.selected = Some('A');
+        register.add_delete(data("bar"));
+        assert_eq!(register.get('a').unwrap().content, "foobar");
+        assert_eq!(register.get('1').unwrap().content, "bar");
+
+        register.selected = Some('a');
+        assert_eq!(register.take_paste_data().content, "foobar");
+        assert_eq!(register.take_paste_data().content, "bar");
+    }
+
+    #[test]
+    fn test_delete_history() {
+        let mut register = Register::default();
+        for i in 0..12 {
+            register.add_delete(data(&i.to_string()));
+        }
+        assert_eq!(register.get('1').unwrap().content, "11");
+        assert_eq!(register.get('9').unwrap().content, "3");
+        let names: String =
+            register.entries().iter().map(|(name, _)| *name).collect();
+        assert_eq!(names, "\"123456789");
+    }
+
+    struct TestClipboard(Option<String>);
+
+    impl Clipboard for TestClipboard {
+        fn get_string(&self) -> Option<String> {
+            self.0.clone()
+        }
+
+        fn put_string(&mut self, s: impl AsRef<str>) {
+            self.0 = Some(s.as_ref().to_string());
+        }
+    }
+
+    #[test]
+    fn test_clipboard_segments() {
+        let mut register = Register::default();
+        let mut clipboard = TestClipboard(None);
+        let copied = RegisterData {
+            content: "foo\nbar".to_string(),
+            mode: VisualMode::Normal,
+            segments: vec!["foo".to_string(), "bar".to_string()],
+        };
+        register.copy_to_clipboard(copied, &mut clipboard);
+        let data = register.clipboard_data(clipboard.get_string().unwrap());
+        assert_eq!(data.segments, vec!["foo", "bar"]);
+
+        // Something else was copied since, by another application
+        clipboard.put_string("foo\n");
+        let data = register.clipboard_data(clipboard.get_string().unwrap());
+        assert!(data.segments.is_empty());
+        assert!(data.mode == VisualMode::Linewise);
+    }
 }
@@ -8,14 +8,16 @@
 
 use std::{
     borrow::Cow,
+    collections::HashMap,
     sync::{
         atomic::{AtomicUsize, Ordering},
-        Arc,
+        Arc, Mutex,
     },
 };
 
 use arc_swap::ArcSwap;
 use lapce_xi_rope::Rope;
+use once_cell::sync::Lazy;
 use tree_sitter::{
     Language, Point, Query, QueryCaptures, QueryCursor, QueryMatch, Tree,
 };
@@ -44,6 +46,9 @@ mod highlights {
         }
 
         pub(crate) fn get_highlight_config(lang: LapceLanguage) -> Result<Arc<HighlightConfiguration>, HighlightIssue> {
+            if lang.has_plugin_grammar() {
+                return plugin_highlight_config(lang);
+            }
             match lang {
                 $(
                     #[cfg(feature = $feature_name)]
@@ -114,6 +119,32 @@ pub(crate) fn get_highlight_config(lang: LapceLanguage) -> Result<Arc<HighlightC
     Zig: "lang-zig",
 );
 
+/// The highlight configurations of the languages that plugins provide a
+/// grammar or queries for, made the first time they're needed
+#[allow(clippy::type_complexity)]
+static PLUGIN_HIGHLIGHTS: Lazy<
+    Mutex<
+        HashMap<LapceLanguage, Result<Arc<HighlightConfiguration>, HighlightIssue>>,
+    >,
+> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn plugin_highlight_config(
+    lang: LapceLanguage,
+) -> Result<Arc<HighlightConfiguration>, HighlightIssue> {
+    PLUGIN_HIGHLIGHTS
+        .lock()
+        .unwrap()
+        .entry(lang)
+        .or_insert_with(|| lang.new_highlight_config().map(Arc::new))
+        .clone()
+}
+
+/// Forget the highlight configurations made from the grammars of plugins,
+/// for when the plugins change
+pub(crate) fn clear_plugin_highlight_configs() {
+    PLUGIN_HIGHLIGHTS.lock().unwrap().clear();
+}
+
 /// Indicates which highlight should be applied to a region of source code.
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct Highlight(pub usize);
@@ -166,6 +197,12 @@ pub struct HighlightConfiguration {
     pub local_def_capture_index: Option<u32>,
     pub local_def_value_capture_index: Option<u32>,
     pub local_ref_capture_index: Option<u32>,
+    /// The query whose `@indent` captures are the nodes whose lines are
+    /// indented one more level than their first line
+    pub indents_query: Option<Query>,
+    /// The query whose `@fold` captures are the nodes whose lines are folded
+    /// in the code lens but for the first and the last
+    pub folds_query: Option<Query>,
 }
 
 impl HighlightConfiguration {
@@ -280,6 +317,8 @@ pub fn new(
             local_def_capture_index,
             local_def_value_capture_index,
             local_ref_capture_index,
+            indents_query: None,
+            folds_query: None,
         };
         conf.configure(SCOPES);
         Ok(conf)
@@ -10,6 +10,7 @@
     cell::RefCell,
     collections::{HashSet, VecDeque},
     mem,
+    ops::Range,
     path::Path,
     sync::{atomic::AtomicUsize, Arc},
 };
@@ -22,7 +23,7 @@
 };
 use slotmap::{DefaultKey as LayerId, HopSlotMap};
 use thiserror::Error;
-use tree_sitter::{Node, Parser, Point, QueryCursor, Tree};
+use tree_sitter::{Node, Parser, Point, Query, QueryCursor, Tree};
 
 use self::{
     edit::SyntaxEdit,
@@ -41,6 +42,7 @@
 
 pub mod edit;
 pub mod highlight;
+pub mod symbols;
 pub mod util;
 
 // Uses significant portions Helix's implementation, and on tree-sitter's highlighter implementation
@@ -434,6 +436,12 @@ pub fn try_tree(&self) -> Option<&Tree> {
         self.layers[self.root].try_tree()
     }
 
+    /// The highlight configuration of the language of the document, rather
+    /// than of the ones injected in it
+    pub fn root_config(&self) -> &HighlightConfiguration {
+        &self.layers[self.root].config
+    }
+
     /// Iterate over the highlighted regions for a given slice of source code.
     pub fn highlight_iter<'a>(
         &'a self,
@@ -524,6 +532,20 @@ pub fn highlight_iter<'a>(
     // TODO: Folding
 }
 
+/// A list in brackets, such as the arguments of a call or the elements of a
+/// collection
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BracketList {
+    /// The offset of the opening bracket
+    pub open: usize,
+    /// The offset of the closing bracket
+    pub close: usize,
+    /// The ranges of the items, without the commas between them
+    pub items: Vec<Range<usize>>,
+    /// Whether there's a comma after the last item
+    pub trailing_comma: bool,
+}
+
 #[derive(Clone)]
 pub struct Syntax {
     pub rev: u64,
@@ -625,13 +647,16 @@ pub fn parse(
             None
         };
 
-        let normal_lines = if let Some(tree) = tree {
-            let mut cursor = tree.walk();
-            let mut normal_lines = HashSet::new();
-            self.language.walk_tree(&mut cursor, &mut normal_lines);
-            normal_lines.into_iter().sorted().collect::<Vec<usize>>()
-        } else {
-            Vec::new()
+        let folds_query = layers.root_config().folds_query.as_ref();
+        let normal_lines = match (tree, folds_query) {
+            (Some(tree), Some(query)) => fold_normal_lines(&new_text, tree, query),
+            (Some(tree), None) => {
+                let mut cursor = tree.walk();
+                let mut normal_lines = HashSet::new();
+                self.language.walk_tree(&mut cursor, &mut normal_lines);
+                normal_lines.into_iter().sorted().collect::<Vec<usize>>()
+            }
+            (None, _) => Vec::new(),
         };
 
         let lens = Self::lens_from_normal_lines(
@@ -787,6 +812,148 @@ pub fn sticky_headers(&self, offset: usize) -> Option<Vec<usize>> {
         Some(offsets)
     }
 
+    /// Whether a new line inserted at the offset is indented one more level
+    /// than the line of the offset, which starts at `line_start`, because a
+    /// node captured as `@indent` starts on that line and goes on past the
+    /// offset. `None` when the language has no indents query.
+    pub fn indent_after(&self, line_start: usize, offset: usize) -> Option<bool> {
+        let layers = self.layers.as_ref()?;
+        let query = layers.root_config().indents_query.as_ref()?;
+        let tree = layers.try_tree()?;
+        let Some(indent) = query.capture_index_for_name("indent") else {
+            return Some(false);
+        };
+        let mut cursor = QueryCursor::new();
+        cursor.set_byte_range(line_start..offset);
+        let indented = cursor
+            .matches(query, tree.root_node(), RopeProvider(&self.text))
+            .any(|m| {
+                m.captures.iter().any(|capture| {
+                    capture.index == indent
+                        && capture.node.start_byte() >= line_start
+                        && capture.node.start_byte() < offset
+                        && capture.node.end_byte() > offset
+                })
+            });
+        Some(indented)
+    }
+
+    /// The byte range of the innermost function, class or other scope that
+    /// the sticky headers show, which contains the offset
+    pub fn focus_scope(&self, offset: usize) -> Option<(usize, usize)> {
+        let tree = self.layers.as_ref()?.try_tree()?;
+        let mut node = tree.root_node().descendant_for_byte_range(offset, offset)?;
+        let sticky_header_tags = self.language.sticky_header_tags();
+        loop {
+            if sticky_header_tags.iter().any(|t| *t == node.kind()) {
+                return Some((node.start_byte(), node.end_byte()));
+            }
+            node = node.parent()?;
+        }
+    }
+
+    /// The names of the functions, classes and other scopes that the sticky
+    /// headers show, which contain the offset, the outermost first. An
+    /// `impl` is named after the type it's for.
+    pub fn scope_names(&self, offset: usize) -> Vec<String> {
+        let Some(tree) = self.layers.as_ref().and_then(|layers| layers.try_tree())
+        else {
+            return Vec::new();
+        };
+        let Some(mut node) =
+            tree.root_node().descendant_for_byte_range(offset, offset)
+        else {
+            return Vec::new();
+        };
+        let sticky_header_tags = self.language.sticky_header_tags();
+        let mut names = Vec::new();
+        loop {
+            if sticky_header_tags.iter().any(|t| *t == node.kind()) {
+                let name = node
+                    .child_by_field_name("name")
+                    .or_else(|| node.child_by_field_name("type"))
+                    .map(|name| {
+                        self.text
+                            .slice_to_cow(name.start_byte()..name.end_byte())
+                            .to_string()
+                    })
+                    .filter(|name| !name.contains('\n'));
+                names.extend(name);
+            }
+            match node.parent() {
+                Some(parent) => node = parent,
+                None => break,
+            }
+        }
+        names.reverse();
+        names
+    }
+
+    /// The innermost list in brackets that the offset is in, from the nodes
+    /// of the tree. Braces only make a list when there are commas in them, so
+    /// that blocks aren't taken for one, and lists with comments in them are
+    /// left alone.
+    pub fn bracket_list(&self, offset: usize) -> Option<BracketList> {
+        let tree = self.layers.as_ref()?.try_tree()?;
+        let mut node = tree.root_node().descendant_for_byte_range(offset, offset)?;
+        loop {
+            if let Some(list) = Self::node_bracket_list(node) {
+                return Some(list);
+            }
+            node = node.parent()?;
+        }
+    }
+
+    fn node_bracket_list(node: Node) -> Option<BracketList> {
+        let count = node.child_count();
+        if count < 2 {
+            return None;
+        }
+        let first = node.child(0)?;
+        let last = node.child(count - 1)?;
+        let close = match first.kind() {
+            "(" => ")",
+            "[" => "]",
+            "{" => "}",
+            _ => return None,
+        };
+        if last.kind() != close {
+            return None;
+        }
+
+        let mut items = Vec::new();
+        let mut item: Option<Range<usize>> = None;
+        let mut has_comma = false;
+        let mut trailing_comma = false;
+        for i in 1..count - 1 {
+            let child = node.child(i)?;
+            if child.kind().contains("comment") {
+                return None;
+            }
+            if child.kind() == "," {
+                has_comma = true;
+                trailing_comma = item.is_some();
+                items.extend(item.take());
+            } else {
+                trailing_comma = false;
+                let start =
+                    item.map(|item| item.start).unwrap_or(child.start_byte());
+                item = Some(start..child.end_byte());
+            }
+        }
+        items.extend(item);
+        if items.is_empty() || (first.kind() == "{" && !has_comma) {
+            return None;
+        }
+
+        Some(BracketList {
+            open: first.start_byte(),
+            close: last.start_byte(),
+            items,
+            trailing_comma,
+        })
+    }
+
     pub fn find_enclosing_parentheses(
         &self,
         offset: usize,
@@ -859,10 +1026,73 @@ pub fn find_enclosing_pair(&self, offset: usize) -> Option<(usize, usize)> {
     }
 }
 
+/// The lines the code lens shows of a language with a folds query: the ones
+/// outside of the nodes captured as `@fold`, and the first and last lines of
+/// them
+fn fold_normal_lines(text: &Rope, tree: &Tree, query: &Query) -> Vec<usize> {
+    let Some(fold) = query.capture_index_for_name("fold") else {
+        return Vec::new();
+    };
+    let lines = text.line_of_offset(text.len()) + 1;
+    let mut folds = Vec::new();
+    let mut cursor = QueryCursor::new();
+    for m in cursor.matches(query, tree.root_node(), RopeProvider(text)) {
+        for capture in m.captures.iter().filter(|c| c.index == fold) {
+            let start = capture.node.start_position().row;
+            let end = capture.node.end_position().row;
+            if end > start {
+                folds.push((start, end.min(lines - 1)));
+            }
+        }
+    }
+
+    let mut normal = vec![true; lines];
+    for (start, end) in &folds {
+        for line in normal.iter_mut().take(*end).skip(*start + 1) {
+            *line = false;
+        }
+    }
+    for (start, end) in folds {
+        normal[start] = true;
+        normal[end] = true;
+    }
+    (0..lines).filter(|line| normal[*line]).collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// A rust syntax with only the indents and folds queries
+    #[cfg(feature = "lang-rust")]
+    fn rust_syntax(text: &str, indents: &str, folds: &str) -> Syntax {
+        let language = tree_sitter_rust::language();
+        let mut config = HighlightConfiguration::new(language, "", "", "").unwrap();
+        config.indents_query = Some(Query::new(language, indents).unwrap());
+        config.folds_query = Some(Query::new(language, folds).unwrap());
+        let mut syntax = Syntax::plaintext();
+        syntax.layers = Some(SyntaxLayers::new_empty(Arc::new(config)));
+        syntax.parse(1, Rope::from(text), None);
+        syntax
+    }
+
+    #[test]
+    #[cfg(feature = "lang-rust")]
+    fn test_indents_query() {
+        let syntax = rust_syntax("fn main() {\n}\n", "(block) @indent", "");
+        assert_eq!(syntax.indent_after(0, 11), Some(true));
+        assert_eq!(syntax.indent_after(0, 3), Some(false));
+        assert_eq!(Syntax::plaintext().indent_after(0, 0), None);
+    }
+
+    #[test]
+    #[cfg(feature = "lang-rust")]
+    fn test_folds_query() {
+        let text = "fn a() {\n    let x = 1;\n    if x {\n        x;\n    }\n}\n";
+        let syntax = rust_syntax(text, "", "(block) @fold");
+        assert_eq!(syntax.normal_lines, vec![0, 2, 4, 5, 6]);
+    }
+
     #[test]
     fn test_lens() {
         let lens = Syntax::lens_from_normal_lines(5, 25, 2, &[4]);
@@ -0,0 +1,184 @@
+use lsp_types::{Position, Range, SymbolKind};
+use serde::{Deserialize, Serialize};
+use tree_sitter::{Node, Parser, TreeCursor};
+
+use super::highlight::get_highlight_config;
+use crate::{encoding::offset_utf8_to_utf16, language::LapceLanguage};
+
+/// A symbol defined in a file, found from its syntax tree rather than by a
+/// language server
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TreeSymbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    /// The name of the symbol it's defined in, like the type of a method
+    pub container_name: Option<String>,
+    /// The range of its name, in the UTF-16 columns of the LSP
+    pub range: Range,
+}
+
+/// The symbols defined in the text, in a language with a tree-sitter
+/// grammar. They're the nodes named after what they define, like functions
+/// and types, which the grammars share the shape of. The bodies of functions
+/// aren't looked into.
+pub fn tree_symbols(language: LapceLanguage, text: &str) -> Vec<TreeSymbol> {
+    let Ok(config) = get_highlight_config(language) else {
+        return Vec::new();
+    };
+    let mut parser = Parser::new();
+    if parser.set_language(config.language).is_err() {
+        return Vec::new();
+    }
+    let Some(tree) = parser.parse(text, None) else {
+        return Vec::new();
+    };
+
+    let mut symbols = Vec::new();
+    let mut cursor = tree.walk();
+    walk_symbols(&mut cursor, text, None, &mut symbols);
+    symbols
+}
+
+fn walk_symbols(
+    cursor: &mut TreeCursor,
+    text: &str,
+    container: Option<&str>,
+    symbols: &mut Vec<TreeSymbol>,
+) {
+    if !cursor.goto_first_child() {
+        return;
+    }
+    loop {
+        let node = cursor.node();
+        let symbol = symbol_kind(node.kind()).and_then(|kind| {
+            let name = node.child_by_field_name("name")?;
+            let name_text =
+                node_text(name, text).filter(|name| !name.contains('\n'))?;
+            Some((kind, name, name_text))
+        });
+        match symbol {
+            Some((kind, name, name_text)) => {
+                symbols.push(TreeSymbol {
+                    name: name_text.to_string(),
+                    kind,
+                    container_name: container.map(|c| c.to_string()),
+                    range: node_range(name, text),
+                });
+                if kind != SymbolKind::FUNCTION && kind != SymbolKind::METHOD {
+                    walk_symbols(cursor, text, Some(name_text), symbols);
+                }
+            }
+            None => {
+                // The methods of an impl are in the type it's for
+                let container = if node.kind() == "impl_item" {
+                    node.child_by_field_name("type")
+                        .and_then(|ty| node_text(ty, text))
+                        .or(container)
+                } else {
+                    container
+                };
+                walk_symbols(cursor, text, container, symbols);
+            }
+        }
+        if !cursor.goto_next_sibling() {
+            break;
+        }
+    }
+    cursor.goto_parent();
+}
+
+/// The kind of symbol a node of the kind defines, if it's a definition
+fn symbol_kind(kind: &str) -> Option<SymbolKind> {
+    let is_definition = ["_item", "_definition", "_declaration", "_spec"]
+        .iter()
+        .any(|suffix| kind.ends_with(suffix))
+        || ["class", "module", "method"].contains(&kind);
+    if !is_definition
+        || ["parameter", "field", "import", "use", "let", "local"]
+            .iter()
+            .any(|skipped| kind.contains(skipped))
+    {
+        return None;
+    }
+    let kinds = [
+        ("method", SymbolKind::METHOD),
+        ("function", SymbolKind::FUNCTION),
+        ("fn", SymbolKind::FUNCTION),
+        ("macro", SymbolKind::FUNCTION),
+        ("class", SymbolKind::CLASS),
+        ("struct", SymbolKind::STRUCT),
+        ("union", SymbolKind::STRUCT),
+        ("enum", SymbolKind::ENUM),
+        ("trait", SymbolKind::INTERFACE),
+        ("interface", SymbolKind::INTERFACE),
+        ("protocol", SymbolKind::INTERFACE),
+        ("mod", SymbolKind::MODULE),
+        ("namespace", SymbolKind::NAMESPACE),
+        ("const", SymbolKind::CONSTANT),
+        ("static", SymbolKind::VARIABLE),
+        ("type", SymbolKind::TYPE_PARAMETER),
+    ];
+    kinds
+        .iter()
+        .find(|(name, _)| kind.contains(name))
+        .map(|(_, kind)| *kind)
+}
+
+fn node_text<'a>(node: Node, text: &'a str) -> Option<&'a str> {
+    text.get(node.byte_range())
+}
+
+fn node_range(node: Node, text: &str) -> Range {
+    Range::new(
+        position(text, node.start_position().row, node.start_byte()),
+        position(text, node.end_position().row, node.end_byte()),
+    )
+}
+
+fn position(text: &str, line: usize, offset: usize) -> Position {
+    let line_start = text[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let line_text = &text[line_start..];
+    Position::new(
+        line as u32,
+        offset_utf8_to_utf16(line_text.char_indices(), offset - line_start) as u32,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_symbol_kind() {
+        assert_eq!(symbol_kind("function_item"), Some(SymbolKind::FUNCTION));
+        assert_eq!(symbol_kind("struct_item"), Some(SymbolKind::STRUCT));
+        assert_eq!(symbol_kind("class_definition"), Some(SymbolKind::CLASS));
+        assert_eq!(symbol_kind("method_declaration"), Some(SymbolKind::METHOD));
+        assert_eq!(symbol_kind("type_spec"), Some(SymbolKind::TYPE_PARAMETER));
+        assert_eq!(symbol_kind("field_declaration"), None);
+        assert_eq!(symbol_kind("use_declaration"), None);
+        assert_eq!(symbol_kind("call_expression"), None);
+    }
+
+    #[cfg(feature = "lang-rust")]
+    #[test]
+    fn test_tree_symbols() {
+        let text = "struct Point { x: f64 }\n\nimpl Point {\n    fn norm(&self) -> f64 {\n        let y = 1.0;\n        self.x\n    }\n}\n";
+        let symbols: Vec<(String, SymbolKind, Option<String>)> =
+            tree_symbols(LapceLanguage::Rust, text)
+                .into_iter()
+                .map(|s| (s.name, s.kind, s.container_name))
+                .collect();
+        assert_eq!(
+            symbols,
+            vec![
+                ("Point".to_string(), SymbolKind::STRUCT, None),
+                (
+                    "norm".to_string(),
+                    SymbolKind::FUNCTION,
+                    Some("Point".to_string())
+                ),
+            ]
+        );
+    }
+}
@@ -53,12 +53,32 @@ fn is_boundary(&self) -> bool {
 /// Boundaries can be the start of a word, its end, punctuation etc.
 pub struct WordCursor<'a> {
     pub(crate) inner: Cursor<'a, RopeInfo>,
+    /// Extra characters which are treated as part of a word
+    word_chars: &'a str,
 }
 
 impl<'a> WordCursor<'a> {
     pub fn new(text: &'a Rope, pos: usize) -> WordCursor<'a> {
         let inner = Cursor::new(text, pos);
-        WordCursor { inner }
+        WordCursor {
+            inner,
+            word_chars: "",
+        }
+    }
+
+    /// Treat the given characters as part of a word, in addition to letters
+    /// and digits. This is used for language specific word characters.
+    pub fn with_word_chars(mut self, word_chars: &'a str) -> WordCursor<'a> {
+        self.word_chars = word_chars;
+        self
+    }
+
+    fn char_property(&self, codepoint: char) -> CharClassification {
+        if self.word_chars.contains(codepoint) {
+            CharClassification::Other
+        } else {
+            get_char_property(codepoint)
+        }
     }
 
     /// Get the previous start boundary of a word, and set the cursor position to the boundary found.
@@ -78,10 +98,10 @@ pub fn new(text: &'a Rope, pos: usize) -> WordCursor<'a> {
     ///```
     pub fn prev_boundary(&mut self, mode: Mode) -> Option<usize> {
         if let Some(ch) = self.inner.prev_codepoint() {
-            let mut prop = get_char_property(ch);
+            let mut prop = self.char_property(ch);
             let mut candidate = self.inner.pos();
             while let Some(prev) = self.inner.prev_codepoint() {
-                let prop_prev = get_char_property(prev);
+                let prop_prev = self.char_property(prev);
                 if classify_boundary(prop_prev, prop).is_start() {
                     break;
                 }
@@ -121,14 +141,14 @@ pub fn prev_boundary(&mut self, mode: Mode) -> Option<usize> {
     ///```
     pub fn prev_deletion_boundary(&mut self) -> Option<usize> {
         if let Some(ch) = self.inner.prev_codepoint() {
-            let mut prop = get_char_property(ch);
+            let mut prop = self.char_property(ch);
             let mut candidate = self.inner.pos();
 
             // Flag, determines if the word should be deleted or not
             // If not, erase only whitespace characters.
             let mut keep_word = false;
             while let Some(prev) = self.inner.prev_codepoint() {
-                let prop_prev = get_char_property(prev);
+                let prop_prev = self.char_property(prev);
 
                 // Stop if line beginning reached, without any non-whitespace characters
                 if prop_prev == CharClassification::Lf
@@ -185,7 +205,7 @@ pub fn prev_deletion_boundary(&mut self) -> Option<usize> {
     pub fn next_non_blank_char(&mut self) -> usize {
         let mut candidate = self.inner.pos();
         while let Some(next) = self.inner.next_codepoint() {
-            let prop = get_char_property(next);
+            let prop = self.char_property(next);
             if prop != CharClassification::Space {
                 break;
             }
@@ -208,10 +228,10 @@ pub fn next_non_blank_char(&mut self) -> usize {
     ///```
     pub fn next_boundary(&mut self) -> Option<usize> {
         if let Some(ch) = self.inner.next_codepoint() {
-            let mut prop = get_char_property(ch);
+            let mut prop = self.char_property(ch);
             let mut candidate = self.inner.pos();
             while let Some(next) = self.inner.next_codepoint() {
-                let prop_next = get_char_property(next);
+                let prop_next = self.char_property(next);
                 if classify_boundary(prop, prop_next).is_start() {
                     break;
                 }
@@ -238,10 +258,10 @@ pub fn next_boundary(&mut self) -> Option<usize> {
     pub fn end_boundary(&mut self) -> Option<usize> {
         self.inner.next_codepoint();
         if let Some(ch) = self.inner.next_codepoint() {
-            let mut prop = get_char_property(ch);
+            let mut prop = self.char_property(ch);
             let mut candidate = self.inner.pos();
             while let Some(next) = self.inner.next_codepoint() {
-                let prop_next = get_char_property(next);
+                let prop_next = self.char_property(next);
                 if classify_boundary(prop, prop_next).is_end() {
                     break;
                 }
@@ -269,7 +289,7 @@ pub fn end_boundary(&mut self) -> Option<usize> {
     pub fn prev_code_boundary(&mut self) -> usize {
         let mut candidate = self.inner.pos();
         while let Some(prev) = self.inner.prev_codepoint() {
-            let prop_prev = get_char_property(prev);
+            let prop_prev = self.char_property(prev);
             if prop_prev != CharClassification::Other {
                 break;
             }
@@ -293,7 +313,7 @@ pub fn prev_code_boundary(&mut self) -> usize {
     pub fn next_code_boundary(&mut self) -> usize {
         let mut candidate = self.inner.pos();
         while let Some(prev) = self.inner.next_codepoint() {
-            let prop_prev = get_char_property(prev);
+            let prop_prev = self.char_property(prev);
             if prop_prev != CharClassification::Other {
                 break;
             }
@@ -706,4 +726,15 @@ fn find_pair_should_return_none() {
         let positions = cursor.find_enclosing_pair();
         assert_eq!(positions, None);
     }
+
+    #[test]
+    fn select_word_should_include_word_chars() {
+        let text = "margin-top: 0;";
+        let rope = Rope::from(text);
+        let mut cursor = WordCursor::new(&rope, 2);
+        assert_eq!(cursor.select_word(), (0, 6));
+
+        let mut cursor = WordCursor::new(&rope, 2).with_word_chars("-");
+        assert_eq!(cursor.select_word(), (0, 10));
+    }
 }
@@ -9,7 +9,7 @@
     Color, ExtEventSink, FontFamily, Size, Target,
 };
 use indexmap::IndexMap;
-use lapce_core::directory::Directory;
+use lapce_core::{directory::Directory, editor::scroll_beyond_last_line};
 use lapce_proxy::plugin::wasi::find_all_volts;
 use lapce_rpc::plugin::VoltID;
 use lsp_types::{CompletionItemKind, SymbolKind};
@@ -381,10 +381,18 @@ pub struct EditorConfig {
     pub show_bread_crumbs: bool,
     #[field_names(desc = "If the editor can scroll beyond the last line")]
     pub scroll_beyond_last_line: bool,
+    #[field_names(
+        desc = "Set how far the editor scrolls beyond the last line, in percent of the viewport"
+    )]
+    pub scroll_beyond_last_line_percent: usize,
     #[field_names(
         desc = "Set the minimum number of visible lines above and below the cursor"
     )]
     pub cursor_surrounding_lines: usize,
+    #[field_names(
+        desc = "Set the minimum number of visible columns left and right of the cursor"
+    )]
+    pub cursor_surrounding_columns: usize,
     #[field_names(
         desc = "Show code context like functions and classes at the top of editor when scroll"
     )]
@@ -524,6 +532,19 @@ pub fn line_height(&self) -> usize {
         (line_height.round() as usize).max(self.font_size)
     }
 
+    /// The height the editor can scroll beyond the last line in a viewport of
+    /// `viewport_height`
+    pub fn scroll_beyond_last_line_height(&self, viewport_height: f64) -> f64 {
+        if !self.scroll_beyond_last_line {
+            return 0.0;
+        }
+        scroll_beyond_last_line(
+            viewport_height,
+            self.line_height() as f64,
+            self.scroll_beyond_last_line_percent,
+        )
+    }
+
     pub fn font_family(&self) -> FontFamily {
         if self.font_family.is_empty() {
             FontFamily::SYSTEM_UI
@@ -622,6 +643,11 @@ pub struct UIConfig {
     #[field_names(desc = "Set the width for scroll bar")]
     scroll_width: usize,
 
+    #[field_names(
+        desc = "Clicking the track of a scroll bar jumps to the clicked position, instead of scrolling by a page"
+    )]
+    scroll_bar_click_jumps: bool,
+
     #[field_names(desc = "Controls the width of drop shadow in the UI")]
     drop_shadow_width: usize,
 
@@ -681,6 +707,10 @@ pub fn scroll_width(&self) -> usize {
         self.scroll_width
     }
 
+    pub fn scroll_bar_click_jumps(&self) -> bool {
+        self.scroll_bar_click_jumps
+    }
+
     pub fn drop_shadow_width(&self) -> usize {
         self.drop_shadow_width
     }
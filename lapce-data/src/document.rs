@@ -676,7 +676,8 @@ pub fn retrieve_file<P: EditorPosition + Send + 'static>(
             let proxy = self.proxy.clone();
             std::thread::spawn(move || {
                 proxy.proxy_rpc.new_buffer(id, path.clone(), move |result| {
-                    if let Ok(ProxyResponse::NewBufferResponse { content }) = result
+                    if let Ok(ProxyResponse::NewBufferResponse { content, .. }) =
+                        result
                     {
                         let _ = event_sink.submit_command(
                             LAPCE_UI_COMMAND,
@@ -2392,7 +2393,10 @@ pub fn line_horiz_col(
                 let text_layout =
                     self.get_text_layout(text, line, font_size, config);
                 let n = text_layout.text.hit_test_point(Point::new(x, 0.0)).idx;
-                n.min(self.buffer.line_end_col(line, caret))
+                let n = n.min(self.buffer.line_end_col(line, caret));
+                // The hit can land within a grapheme cluster
+                let line_start = self.buffer.offset_of_line(line);
+                self.buffer.grapheme_start_offset(line_start + n) - line_start
             }
             ColPosition::End => self.buffer.line_end_col(line, caret),
             ColPosition::Start => 0,
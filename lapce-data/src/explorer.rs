@@ -94,6 +94,7 @@ pub fn new(
                 open: false,
                 children: HashMap::new(),
                 children_open_count: 0,
+                is_ignored: false,
             });
             let path = path.clone();
             Self::read_dir(&path, true, tab_id, &proxy, event_sink.clone());
@@ -108,6 +109,7 @@ pub fn new(
                 open: false,
                 children: HashMap::new(),
                 children_open_count: 0,
+                is_ignored: false,
             }),
             active_selected: None,
             naming: None,
@@ -11,7 +11,7 @@
 };
 use lapce_core::{
     buffer::{
-        diff::{rope_diff, DiffLines},
+        diff::{rope_diff, DiffLines, DiffOptions},
         rope_text::RopeText,
         Buffer,
     },
@@ -391,6 +391,7 @@ pub fn trigger_update_change(&self, doc: &Document) {
                     rev,
                     atomic_rev.clone(),
                     context_lines,
+                    DiffOptions::default(),
                 );
                 if changes.is_none() {
                     return;
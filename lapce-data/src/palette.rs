@@ -889,41 +889,45 @@ fn get_files(&self, ctx: &mut EventCtx) {
         let workspace = self.workspace.clone();
         let event_sink = ctx.get_external_handle();
         let file_diffs = self.source_control.file_diffs.clone();
-        self.palette.proxy.proxy_rpc.get_files(move |result| {
-            if let Ok(ProxyResponse::GetFilesResponse { items }) = result {
-                let items: im::Vector<PaletteItem> = items
-                    .iter()
-                    .enumerate()
-                    .map(|(_index, path)| {
-                        let full_path = path.clone();
-                        let mut path = path.clone();
-                        if let Some(workspace_path) = workspace.path.as_ref() {
-                            path = path
-                                .strip_prefix(workspace_path)
-                                .unwrap_or(&full_path)
-                                .to_path_buf();
-                        }
-                        let file_diff =
-                            file_diffs.get(&full_path).cloned().map(|t| t.0);
-                        let filter_text = path.to_str().unwrap_or("").to_string();
-                        PaletteItem {
-                            content: PaletteItemContent::File(
-                                path, full_path, file_diff,
-                            ),
-                            filter_text,
-                            score: 0,
-                            indices: Vec::new(),
-                        }
-                    })
-                    .collect();
-
-                let _ = event_sink.submit_command(
-                    LAPCE_UI_COMMAND,
-                    LapceUICommand::UpdatePaletteItems { run_id, items },
-                    Target::Widget(widget_id),
-                );
-            }
-        });
+        self.palette
+            .proxy
+            .proxy_rpc
+            .get_files(false, move |result| {
+                if let Ok(ProxyResponse::GetFilesResponse { items }) = result {
+                    let items: im::Vector<PaletteItem> = items
+                        .iter()
+                        .enumerate()
+                        .map(|(_index, path)| {
+                            let full_path = path.clone();
+                            let mut path = path.clone();
+                            if let Some(workspace_path) = workspace.path.as_ref() {
+                                path = path
+                                    .strip_prefix(workspace_path)
+                                    .unwrap_or(&full_path)
+                                    .to_path_buf();
+                            }
+                            let file_diff =
+                                file_diffs.get(&full_path).cloned().map(|t| t.0);
+                            let filter_text =
+                                path.to_str().unwrap_or("").to_string();
+                            PaletteItem {
+                                content: PaletteItemContent::File(
+                                    path, full_path, file_diff,
+                                ),
+                                filter_text,
+                                score: 0,
+                                indices: Vec::new(),
+                            }
+                        })
+                        .collect();
+
+                    let _ = event_sink.submit_command(
+                        LAPCE_UI_COMMAND,
+                        LapceUICommand::UpdatePaletteItems { run_id, items },
+                        Target::Widget(widget_id),
+                    );
+                }
+            });
     }
 
     fn get_run_configs(&mut self, ctx: &mut EventCtx) {
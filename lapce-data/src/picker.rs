@@ -28,6 +28,7 @@ pub fn new() -> Self {
             open: false,
             children: HashMap::new(),
             children_open_count: 0,
+            is_ignored: false,
         };
         let home = PathBuf::from("/");
         let pwd = PathBuf::from("/");
@@ -51,6 +52,7 @@ pub fn init_home(&mut self, home: &Path) {
             open: false,
             children: HashMap::new(),
             children_open_count: 0,
+            is_ignored: false,
         };
         let mut current_path = home.to_path_buf();
 
@@ -65,6 +67,7 @@ pub fn init_home(&mut self, home: &Path) {
                 open: true,
                 children: HashMap::new(),
                 children_open_count: 0,
+                is_ignored: false,
             };
             file_node
                 .children
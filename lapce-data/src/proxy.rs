@@ -17,7 +17,9 @@
 use lapce_core::{directory::Directory, meta};
 use lapce_proxy::dispatch::Dispatcher;
 use lapce_rpc::{
-    core::{CoreHandler, CoreNotification, CoreRequest, CoreRpcHandler},
+    core::{
+        CoreHandler, CoreNotification, CoreRequest, CoreResponse, CoreRpcHandler,
+    },
     plugin::VoltID,
     proxy::{ProxyRpc, ProxyRpcHandler},
     stdio::stdio_transport,
@@ -107,7 +109,7 @@ fn handle_notification(&mut self, rpc: CoreNotification) {
         use CoreNotification::*;
         match rpc {
             OpenPaths { .. } => {}
-            ProxyConnected {} => {
+            ProxyConnected { .. } => {
                 let _ = self.event_sink.submit_command(
                     LAPCE_UI_COMMAND,
                     LapceUICommand::ProxyUpdateStatus(ProxyStatus::Connected),
@@ -124,13 +126,14 @@ fn handle_notification(&mut self, rpc: CoreNotification) {
                     Target::Widget(self.tab_id),
                 );
             }
-            WorkspaceFileChange {} => {
+            WorkspaceFileChange { .. } => {
                 let _ = self.event_sink.submit_command(
                     LAPCE_UI_COMMAND,
                     LapceUICommand::WorkspaceFileChange,
                     Target::Widget(self.tab_id),
                 );
             }
+            WorkspaceConfigChange {} => {}
             PublishDiagnostics { diagnostics } => {
                 let _ = self.event_sink.submit_command(
                     LAPCE_UI_COMMAND,
@@ -138,7 +141,7 @@ fn handle_notification(&mut self, rpc: CoreNotification) {
                     Target::Widget(self.tab_id),
                 );
             }
-            WorkDoneProgress { progress } => {
+            WorkDoneProgress { progress, .. } => {
                 let _ = self.event_sink.submit_command(
                     LAPCE_UI_COMMAND,
                     LapceUICommand::WorkDoneProgress(progress),
@@ -173,6 +176,7 @@ fn handle_notification(&mut self, rpc: CoreNotification) {
                     Target::Widget(self.tab_id),
                 );
             }
+            ProviderRegistered { .. } => {}
             HomeDir { path } => {
                 let _ = self.event_sink.submit_command(
                     LAPCE_UI_COMMAND,
@@ -328,7 +332,21 @@ fn handle_notification(&mut self, rpc: CoreNotification) {
         }
     }
 
-    fn handle_request(&mut self, _id: RequestId, _rpc: CoreRequest) {}
+    fn handle_request(&mut self, id: RequestId, rpc: CoreRequest) {
+        match rpc {
+            CoreRequest::ApplyWorkspaceEdit { .. } => {
+                self.core_rpc.handle_response(
+                    id,
+                    Ok(CoreResponse::ApplyWorkspaceEditResponse {
+                        applied: false,
+                        failure_reason: Some(
+                            "workspace edits aren't supported".to_string(),
+                        ),
+                    }),
+                );
+            }
+        }
+    }
 }
 
 impl LapceProxy {
@@ -388,6 +406,7 @@ fn start(
             workspace.path.clone(),
             disabled_volts,
             plugin_configurations,
+            Vec::new(),
             window_id,
             tab_id,
         );
@@ -796,7 +815,7 @@ pub fn new_terminal(
         raw: Arc<Mutex<RawTerminal>>,
     ) {
         let _ = self.term_tx.send((term_id, TermEvent::NewTerminal(raw)));
-        self.proxy_rpc.new_terminal(term_id, cwd, env, shell);
+        self.proxy_rpc.new_terminal(term_id, cwd, env, shell, None);
     }
 
     pub fn stop(&self) {
@@ -0,0 +1,300 @@
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeMap, HashMap},
+    fs::{self, File},
+    hash::{Hash, Hasher},
+    io::{self, Read},
+    path::{Component, Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::SystemTime,
+};
+
+use anyhow::{anyhow, Result};
+use flate2::read::GzDecoder;
+use lapce_rpc::file::FileNodeItem;
+use parking_lot::Mutex;
+
+/// The members of the archives that were browsed, kept until an archive
+/// changes so that expanding a folder in it doesn't read all of it again
+#[derive(Default)]
+pub struct ArchiveMembers {
+    archives: HashMap<PathBuf, (SystemTime, Arc<Vec<(PathBuf, bool)>>)>,
+}
+
+/// The entries of the folder in the archive, as the items of a folder that
+/// the archive is, at the paths that go through it. A folder of the archive
+/// is there even without an entry of its own, if there are members in it.
+pub fn read_archive_dir(
+    cache: &Mutex<ArchiveMembers>,
+    archive: &Path,
+    dir: &Path,
+) -> Result<Vec<FileNodeItem>> {
+    let mut entries: BTreeMap<PathBuf, bool> = BTreeMap::new();
+    for (member, is_dir) in cached_members(cache, archive)?.iter() {
+        let Ok(rest) = member.strip_prefix(dir) else {
+            continue;
+        };
+        let mut components = rest.components();
+        let Some(name) = components.next() else {
+            continue;
+        };
+        let is_dir = *is_dir || components.next().is_some();
+        *entries.entry(dir.join(name)).or_default() |= is_dir;
+    }
+
+    let mut items = entries
+        .into_iter()
+        .map(|(member, is_dir)| FileNodeItem {
+            path_buf: archive.join(member),
+            is_dir,
+            read: false,
+            open: false,
+            children: HashMap::new(),
+            children_open_count: 0,
+            is_ignored: false,
+        })
+        .collect::<Vec<_>>();
+    items.sort();
+    Ok(items)
+}
+
+/// The members of the archive, read again only if it changed since they
+/// last were
+fn cached_members(
+    cache: &Mutex<ArchiveMembers>,
+    archive: &Path,
+) -> Result<Arc<Vec<(PathBuf, bool)>>> {
+    let modified = fs::metadata(archive)?.modified()?;
+    if let Some((read_at, members)) = cache.lock().archives.get(archive) {
+        if *read_at == modified {
+            return Ok(members.clone());
+        }
+    }
+
+    // The archive is read without holding the lock, so that browsing other
+    // archives doesn't wait for it
+    let members = Arc::new(members(archive)?);
+    cache
+        .lock()
+        .archives
+        .insert(archive.to_path_buf(), (modified, members.clone()));
+    Ok(members)
+}
+
+/// Extract the member of the archive to a temporary file, unless it already
+/// was since the archive last changed, and give the path of the file
+pub fn extract_archive_member(archive: &Path, member: &Path) -> Result<PathBuf> {
+    if !member
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+    {
+        return Err(anyhow!("{} isn't a member path", member.display()));
+    }
+
+    let modified = fs::metadata(archive)?.modified()?;
+    let mut hasher = DefaultHasher::new();
+    archive.hash(&mut hasher);
+    modified.hash(&mut hasher);
+    let target = std::env::temp_dir()
+        .join("lapce-archives")
+        .join(format!("{:x}", hasher.finish()))
+        .join(member);
+    if target.is_file() {
+        return Ok(target);
+    }
+
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    // The member is extracted to a name of its own and then renamed into
+    // place, so that a partly written file is never taken as extracted
+    static PARTIAL: AtomicUsize = AtomicUsize::new(0);
+    let partial = target.with_file_name(format!(
+        ".{}.{}-{}.partial",
+        target
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default(),
+        std::process::id(),
+        PARTIAL.fetch_add(1, Ordering::Relaxed)
+    ));
+    let mut file = File::create(&partial)?;
+    let found = copy_member(archive, member, &mut file);
+    drop(file);
+    match found {
+        Ok(true) => {}
+        Ok(false) => {
+            let _ = fs::remove_file(&partial);
+            return Err(anyhow!(
+                "{} isn't in {}",
+                member.display(),
+                archive.display()
+            ));
+        }
+        Err(e) => {
+            let _ = fs::remove_file(&partial);
+            return Err(e);
+        }
+    }
+    fs::rename(&partial, &target)?;
+    Ok(target)
+}
+
+/// Write the file member of the archive to the file, if there is one
+fn copy_member(archive: &Path, member: &Path, file: &mut File) -> Result<bool> {
+    if is_zip(archive) {
+        let mut zip = zip::ZipArchive::new(File::open(archive)?)?;
+        let index = (0..zip.len()).find(|i| {
+            zip.by_index(*i)
+                .map(|entry| {
+                    entry.is_file() && entry.enclosed_name() == Some(member)
+                })
+                .unwrap_or(false)
+        });
+        match index {
+            Some(index) => {
+                io::copy(&mut zip.by_index(index)?, file)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    } else {
+        let mut tar = tar::Archive::new(tar_reader(archive)?);
+        for entry in tar.entries()? {
+            let mut entry = entry?;
+            if entry.header().entry_type().is_file()
+                && entry.path()?.as_ref() == member
+            {
+                io::copy(&mut entry, file)?;
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
+
+/// The paths of the members of the archive, and whether each is a folder
+fn members(archive: &Path) -> Result<Vec<(PathBuf, bool)>> {
+    let mut members = Vec::new();
+    if is_zip(archive) {
+        let mut zip = zip::ZipArchive::new(File::open(archive)?)?;
+        for i in 0..zip.len() {
+            let entry = zip.by_index(i)?;
+            // Members with paths out of the archive are left out
+            if let Some(path) = entry.enclosed_name() {
+                members.push((path.to_path_buf(), entry.is_dir()));
+            }
+        }
+    } else {
+        let mut tar = tar::Archive::new(tar_reader(archive)?);
+        for entry in tar.entries()? {
+            let entry = entry?;
+            let path = entry.path()?.into_owned();
+            if path
+                .components()
+                .all(|component| matches!(component, Component::Normal(_)))
+            {
+                members.push((path, entry.header().entry_type().is_dir()));
+            }
+        }
+    }
+    Ok(members)
+}
+
+fn is_zip(archive: &Path) -> bool {
+    archive
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| {
+            ext.eq_ignore_ascii_case("zip") || ext.eq_ignore_ascii_case("jar")
+        })
+        .unwrap_or(false)
+}
+
+/// The tar archive, decompressed if it's gzipped
+fn tar_reader(archive: &Path) -> Result<Box<dyn Read>> {
+    let file = File::open(archive)?;
+    let name = archive
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+    if name.ends_with(".gz") || name.ends_with(".tgz") {
+        Ok(Box::new(GzDecoder::new(file)))
+    } else {
+        Ok(Box::new(file))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use lapce_rpc::file::{archive_member, is_archive};
+
+    use super::*;
+
+    #[test]
+    fn test_zip_archive() {
+        let dir = std::env::temp_dir().join("lapce-archive-test");
+        fs::create_dir_all(&dir).unwrap();
+        let archive = dir.join("deps.zip");
+        {
+            let mut zip = zip::ZipWriter::new(File::create(&archive).unwrap());
+            let options = zip::write::FileOptions::default();
+            zip.start_file("src/lib.rs", options).unwrap();
+            zip.write_all(b"pub fn f() {}\n").unwrap();
+            zip.start_file("README.md", options).unwrap();
+            zip.write_all(b"# deps\n").unwrap();
+            zip.finish().unwrap();
+        }
+
+        let cache = Mutex::new(ArchiveMembers::default());
+        let items = read_archive_dir(&cache, &archive, Path::new("")).unwrap();
+        let entries: Vec<(PathBuf, bool)> = items
+            .into_iter()
+            .map(|item| (item.path_buf, item.is_dir))
+            .collect();
+        assert_eq!(
+            entries,
+            vec![
+                (archive.join("src"), true),
+                (archive.join("README.md"), false)
+            ]
+        );
+
+        // The members are read once, for every folder of the archive
+        let members = cached_members(&cache, &archive).unwrap();
+        assert!(Arc::ptr_eq(
+            &members,
+            &cached_members(&cache, &archive).unwrap()
+        ));
+        let items = read_archive_dir(&cache, &archive, Path::new("src")).unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].path_buf, archive.join("src").join("lib.rs"));
+
+        let path = archive.join("src").join("lib.rs");
+        let (in_archive, member) = archive_member(&path).unwrap();
+        assert_eq!(in_archive, archive);
+        let extracted = extract_archive_member(in_archive, member).unwrap();
+        assert_eq!(fs::read_to_string(&extracted).unwrap(), "pub fn f() {}\n");
+        // Nothing is left besides the extracted member
+        let extracted_dir = fs::read_dir(extracted.parent().unwrap())
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect::<Vec<_>>();
+        assert_eq!(extracted_dir, vec![extracted.clone()]);
+        assert!(extract_archive_member(&archive, Path::new("missing.rs")).is_err());
+        assert!(extract_archive_member(&archive, Path::new("../lib.rs")).is_err());
+
+        // A folder named like an archive is browsed as the folder it is
+        let folder = dir.join("folder.zip");
+        fs::create_dir_all(&folder).unwrap();
+        assert!(!is_archive(&folder));
+        assert!(archive_member(&folder.join("lib.rs")).is_none());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
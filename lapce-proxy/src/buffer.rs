@@ -9,10 +9,13 @@
 };
 
 use anyhow::{anyhow, Result};
+use encoding_rs::{
+    EncoderResult, Encoding, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252,
+};
 use lapce_core::{
     buffer::rope_text::CharIndicesJoin, encoding::offset_utf8_to_utf16,
 };
-use lapce_rpc::buffer::BufferId;
+use lapce_rpc::buffer::{BufferId, EncodingFallback};
 use lapce_xi_rope::{interval::IntervalBounds, rope::Rope, RopeDelta};
 use lsp_types::*;
 
@@ -24,11 +27,14 @@ pub struct Buffer {
     pub path: PathBuf,
     pub rev: u64,
     pub mod_time: Option<SystemTime>,
+    /// The encoding the file is read and saved in
+    pub encoding: &'static Encoding,
 }
 
 impl Buffer {
     pub fn new(id: BufferId, path: PathBuf) -> Buffer {
-        let rope = Rope::from(load_file(&path).unwrap_or_default());
+        let (content, encoding) = load_file(&path).unwrap_or((String::new(), UTF_8));
+        let rope = Rope::from(content);
         let rev = u64::from(!rope.is_empty());
         let language_id = language_id_from_path(&path).unwrap_or("");
         let mod_time = get_mod_time(&path);
@@ -39,10 +45,11 @@ pub fn new(id: BufferId, path: PathBuf) -> Buffer {
             language_id,
             rev,
             mod_time,
+            encoding,
         }
     }
 
-    pub fn save(&mut self, rev: u64) -> Result<()> {
+    pub fn save(&mut self, rev: u64, fallback: EncodingFallback) -> Result<()> {
         if self.rev != rev {
             return Err(anyhow!("not the right rev"));
         }
@@ -62,8 +69,12 @@ pub fn save(&mut self, rev: u64) -> Result<()> {
         let tmp_path = &path.with_extension(tmp_extension);
 
         let mut f = File::create(tmp_path)?;
-        for chunk in self.rope.iter_chunks(..self.rope.len()) {
-            f.write_all(chunk.as_bytes())?;
+        if self.encoding == UTF_8 {
+            for chunk in self.rope.iter_chunks(..self.rope.len()) {
+                f.write_all(chunk.as_bytes())?;
+            }
+        } else {
+            f.write_all(&encode(&self.rope, self.encoding, fallback))?;
         }
 
         if let Ok(metadata) = fs::metadata(&path) {
@@ -151,8 +162,82 @@ pub fn is_empty(&self) -> bool {
     }
 }
 
-pub fn load_file(path: &Path) -> Result<String> {
-    Ok(read_path_to_string_lossy(path)?)
+/// Read the file, and the encoding it's in
+pub fn load_file(path: &Path) -> Result<(String, &'static Encoding)> {
+    Ok(decode(&fs::read(path)?))
+}
+
+/// Decode the content of a file: as UTF-16 if it starts with a UTF-16 byte
+/// order mark, as UTF-8 if it's valid UTF-8, and otherwise as Windows-1252,
+/// which maps every byte to a character, so that saving the file again leaves
+/// the bytes the edits didn't touch as they were
+fn decode(bytes: &[u8]) -> (String, &'static Encoding) {
+    match Encoding::for_bom(bytes) {
+        Some((encoding, bom_len)) if encoding != UTF_8 => {
+            let (content, _) =
+                encoding.decode_without_bom_handling(&bytes[bom_len..]);
+            (content.into_owned(), encoding)
+        }
+        // A UTF-8 byte order mark is kept in the text, and so in the file
+        _ => match std::str::from_utf8(bytes) {
+            Ok(content) => (content.to_string(), UTF_8),
+            Err(_) => {
+                let (content, _) = WINDOWS_1252.decode_without_bom_handling(bytes);
+                (content.into_owned(), WINDOWS_1252)
+            }
+        },
+    }
+}
+
+/// Encode the text in the encoding, writing what `fallback` says in place of
+/// the characters it can't represent
+pub fn encode(
+    text: &Rope,
+    encoding: &'static Encoding,
+    fallback: EncodingFallback,
+) -> Vec<u8> {
+    // The encoders of encoding_rs write UTF-8 for UTF-16, since the web never
+    // encodes in it
+    if encoding == UTF_16LE || encoding == UTF_16BE {
+        let mut bytes = Vec::with_capacity(text.len() * 2 + 2);
+        for unit in std::iter::once('\u{FEFF}' as u16).chain(
+            text.iter_chunks(..text.len())
+                .flat_map(|chunk| chunk.encode_utf16()),
+        ) {
+            if encoding == UTF_16LE {
+                bytes.extend_from_slice(&unit.to_le_bytes());
+            } else {
+                bytes.extend_from_slice(&unit.to_be_bytes());
+            }
+        }
+        return bytes;
+    }
+
+    let mut encoder = encoding.new_encoder();
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut encode_chunk = |mut chunk: &str, last: bool| loop {
+        if let Some(needed) =
+            encoder.max_buffer_length_from_utf8_without_replacement(chunk.len())
+        {
+            bytes.reserve(needed);
+        }
+        let (result, read) = encoder
+            .encode_from_utf8_to_vec_without_replacement(chunk, &mut bytes, last);
+        chunk = &chunk[read..];
+        match result {
+            EncoderResult::InputEmpty => break,
+            EncoderResult::OutputFull => {}
+            EncoderResult::Unmappable(c) => {
+                bytes.extend_from_slice(fallback.replacement(c).as_bytes());
+            }
+        }
+    };
+    for chunk in text.iter_chunks(..text.len()) {
+        encode_chunk(chunk, false);
+    }
+    // Let the encoder finish off, as the stateful ones have to
+    encode_chunk("", true);
+    bytes
 }
 
 pub fn read_path_to_string_lossy<P: AsRef<Path>>(
@@ -308,3 +393,41 @@ pub fn get_mod_time<P: AsRef<Path>>(path: P) -> Option<SystemTime> {
         .and_then(|meta| meta.modified())
         .ok()
 }
+
+#[cfg(test)]
+mod tests {
+    use encoding_rs::SHIFT_JIS;
+
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(decode("café".as_bytes()), ("café".to_string(), UTF_8));
+        assert_eq!(decode(b"caf\xe9"), ("café".to_string(), WINDOWS_1252));
+        assert_eq!(
+            decode(b"\xff\xfec\0a\0f\0\xe9\0"),
+            ("café".to_string(), UTF_16LE)
+        );
+    }
+
+    #[test]
+    fn test_encode() {
+        let text = Rope::from("café ☕");
+        assert_eq!(
+            encode(&text, WINDOWS_1252, EncodingFallback::Substitute),
+            b"caf\xe9 ?"
+        );
+        assert_eq!(
+            encode(&text, WINDOWS_1252, EncodingFallback::Escape),
+            b"caf\xe9 \\u{2615}"
+        );
+        assert_eq!(
+            encode(&Rope::from("日本"), SHIFT_JIS, EncodingFallback::Substitute),
+            b"\x93\xfa\x96\x7b"
+        );
+        assert_eq!(
+            encode(&Rope::from("é"), UTF_16BE, EncodingFallback::Substitute),
+            b"\xfe\xff\0\xe9"
+        );
+    }
+}
@@ -3,7 +3,7 @@
     fs,
     path::{Path, PathBuf},
     sync::{
-        atomic::{AtomicU64, Ordering},
+        atomic::{AtomicBool, AtomicU64, Ordering},
         Arc,
     },
     thread,
@@ -13,19 +13,29 @@
 use alacritty_terminal::{event::WindowSize, event_loop::Msg};
 use anyhow::{anyhow, Context, Result};
 use crossbeam_channel::Sender;
-use git2::{build::CheckoutBuilder, DiffOptions, Repository};
+use encoding_rs::Encoding;
+use git2::{
+    build::CheckoutBuilder, ApplyLocation, Diff, DiffFormat, DiffOptions, Repository,
+};
 use grep_matcher::Matcher;
 use grep_regex::RegexMatcherBuilder;
 use grep_searcher::{sinks::UTF8, SearcherBuilder};
 use indexmap::IndexMap;
+use lapce_core::buffer::diff::{
+    diff_hunks, hunk_patch, rope_diff, DiffHunk as LineDiffHunk,
+    DiffOptions as LineDiffOptions,
+};
 use lapce_rpc::{
+    buffer::EncodingFallback,
     core::{CoreNotification, CoreRpcHandler},
-    file::FileNodeItem,
+    file::{archive_member, is_archive, FileChange, FileNodeItem},
+    plugin::PluginEvent,
+    protocol::{Protocol, ProtocolInfo},
     proxy::{
-        ProxyHandler, ProxyNotification, ProxyRequest, ProxyResponse,
-        ProxyRpcHandler, SearchMatch,
+        LineEdit, ProxyHandler, ProxyNotification, ProxyRequest, ProxyResponse,
+        ProxyRpcHandler, SearchMatch, SearchReplacement,
     },
-    source_control::{DiffInfo, FileDiff},
+    source_control::{DiffInfo, FileCommit, FileDiff},
     style::{LineStyle, SemanticStyles},
     terminal::TermId,
     RequestId, RpcError,
@@ -35,8 +45,13 @@
 use parking_lot::Mutex;
 
 use crate::{
+    archive::{extract_archive_member, read_archive_dir, ArchiveMembers},
     buffer::{get_mod_time, load_file, Buffer},
+    file_index::FileIndex,
+    ignore_matcher::{unignored_entries, walker},
+    network,
     plugin::{catalog::PluginCatalog, remove_volt, PluginCatalogRpcHandler},
+    symbol_index::SymbolIndex,
     terminal::Terminal,
     watcher::{FileWatcher, Notify, WatchToken},
 };
@@ -53,6 +68,9 @@ pub struct Dispatcher {
     #[allow(deprecated)]
     terminals: HashMap<TermId, mio::channel::Sender<Msg>>,
     file_watcher: FileWatcher,
+    file_index: Arc<Mutex<FileIndex>>,
+    symbol_index: Arc<Mutex<SymbolIndex>>,
+    archive_members: Arc<Mutex<ArchiveMembers>>,
     window_id: usize,
     tab_id: usize,
 }
@@ -65,9 +83,17 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                 workspace,
                 disabled_volts,
                 plugin_configurations,
+                plugin_permissions,
                 window_id,
                 tab_id,
+                protocol,
             } => {
+                // The editor logs it when the protocol can't be agreed on,
+                // and both sides stick to the messages of the oldest one
+                let protocol = ProtocolInfo::current()
+                    .negotiate(&protocol)
+                    .unwrap_or_else(|_| Protocol::oldest());
+                self.core_rpc.set_protocol(protocol);
                 self.window_id = window_id;
                 self.tab_id = tab_id;
                 self.workspace = workspace;
@@ -75,12 +101,33 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                     self.workspace.clone(),
                     self.core_rpc.clone(),
                     self.proxy_rpc.clone(),
+                    self.catalog_rpc.clone(),
+                    self.file_index.clone(),
+                    self.symbol_index.clone(),
                 ));
                 if let Some(workspace) = self.workspace.as_ref() {
                     self.file_watcher
                         .watch(workspace, true, WORKSPACE_EVENT_TOKEN);
                 }
 
+                if let Some(workspace) = self.workspace.clone() {
+                    let file_index = self.file_index.clone();
+                    let symbol_index = self.symbol_index.clone();
+                    thread::spawn(move || {
+                        // The index of the last session answers right away,
+                        // and the files changed since are parsed one at a
+                        // time so that searches aren't held up
+                        let loaded = SymbolIndex::load(&workspace);
+                        symbol_index.lock().merge(loaded);
+                        let files = file_index.lock().files(&workspace, false);
+                        symbol_index.lock().retain(&files);
+                        for path in &files {
+                            SymbolIndex::index_file(&symbol_index, path);
+                        }
+                        let _ = symbol_index.lock().save(&workspace);
+                    });
+                }
+
                 let plugin_rpc = self.catalog_rpc.clone();
                 let workspace = self.workspace.clone();
                 thread::spawn(move || {
@@ -88,6 +135,7 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                         workspace,
                         disabled_volts,
                         plugin_configurations,
+                        plugin_permissions,
                         plugin_rpc.clone(),
                     );
                     plugin_rpc.mainloop(&mut plugin);
@@ -110,7 +158,7 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                     if get_mod_time(&buffer.path) == buffer.mod_time {
                         return;
                     }
-                    if let Ok(content) = load_file(&buffer.path) {
+                    if let Ok((content, _)) = load_file(&buffer.path) {
                         self.core_rpc.open_file_changed(path, content);
                     }
                 }
@@ -132,6 +180,9 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                 self.catalog_rpc.signature_help(request_id, &path, position);
             }
             Shutdown {} => {
+                if let Some(workspace) = self.workspace.as_ref() {
+                    let _ = self.symbol_index.lock().save(workspace);
+                }
                 self.catalog_rpc.shutdown();
                 for (_, sender) in self.terminals.iter() {
                     #[allow(deprecated)]
@@ -150,17 +201,26 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                     old_text,
                     buffer.rope.clone(),
                 );
+                self.document_event(&path, |uri| PluginEvent::DocumentChanged {
+                    uri,
+                    version: rev,
+                });
             }
             UpdatePluginConfigs { configs } => {
                 let _ = self.catalog_rpc.update_plugin_configs(configs);
             }
+            UpdateNetworkSettings { settings } => {
+                network::set_settings(settings);
+            }
             NewTerminal {
                 term_id,
                 cwd,
                 env,
                 shell,
+                command,
             } => {
-                let mut terminal = Terminal::new(term_id, cwd, env, shell, 50, 10);
+                let mut terminal =
+                    Terminal::new(term_id, cwd, env, shell, command, 50, 10);
 
                 #[allow(unused)]
                 let mut child_id = None;
@@ -271,6 +331,15 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
             EnableVolt { volt } => {
                 let _ = self.catalog_rpc.enable_volt(volt);
             }
+            GrantVoltPermissions { volt, permissions } => {
+                let _ = self.catalog_rpc.grant_volt_permissions(volt, permissions);
+            }
+            ActiveEditorChanged { path } => {
+                let uri = path.and_then(|path| Url::from_file_path(path).ok());
+                let _ = self
+                    .catalog_rpc
+                    .plugin_event(PluginEvent::ActiveEditorChanged { uri });
+            }
             GitCommit { message, diffs } => {
                 if let Some(workspace) = self.workspace.as_ref() {
                     match git_commit(workspace, &message, diffs) {
@@ -306,6 +375,18 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                     }
                 }
             }
+            GitStageHunk {
+                path,
+                head_lines,
+                lines,
+            } => {
+                if let Some(workspace) = self.workspace.as_ref() {
+                    match git_stage_hunk(workspace, &path, head_lines, &lines) {
+                        Ok(()) => (),
+                        Err(e) => eprintln!("{e:?}"),
+                    }
+                }
+            }
             GitInit {} => {
                 if let Some(workspace) = self.workspace.as_ref() {
                     match git_init(workspace) {
@@ -323,6 +404,7 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
             NewBuffer { buffer_id, path } => {
                 let buffer = Buffer::new(buffer_id, path.clone());
                 let content = buffer.rope.to_string();
+                let encoding = buffer.encoding.name().to_string();
                 self.catalog_rpc.did_open_document(
                     &path,
                     buffer.language_id.to_string(),
@@ -330,10 +412,13 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                     content.clone(),
                 );
                 self.file_watcher.watch(&path, false, OPEN_FILE_EVENT_TOKEN);
+                self.document_event(&path, |uri| PluginEvent::DocumentOpened {
+                    uri,
+                });
                 self.buffers.insert(path, buffer);
                 self.respond_rpc(
                     id,
-                    Ok(ProxyResponse::NewBufferResponse { content }),
+                    Ok(ProxyResponse::NewBufferResponse { content, encoding }),
                 );
             }
             BufferHead { path } => {
@@ -385,12 +470,8 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                             &WORKER_ID,
                             workspace
                                 .iter()
-                                .flat_map(|w| ignore::Walk::new(w).flatten())
-                                .chain(
-                                    buffers.iter().flat_map(|p| {
-                                        ignore::Walk::new(p).flatten()
-                                    }),
-                                )
+                                .chain(buffers.iter())
+                                .flat_map(|p| walker(p, false).build().flatten())
                                 .map(|p| p.into_path()),
                             &pattern,
                             case_sensitive,
@@ -400,6 +481,35 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                     );
                 });
             }
+            ReplaceInFile { path, replacements } => {
+                let result = replace_in_file(&path, replacements)
+                    .map(|skipped| ProxyResponse::ReplaceInFileResponse { skipped })
+                    .map_err(|e| RpcError {
+                        code: 0,
+                        message: e.to_string(),
+                    });
+                self.respond_rpc(id, result);
+            }
+            GitStagedDiff { path } => {
+                let result = git_staged_diff(&path)
+                    .map(|diff| ProxyResponse::GitStagedDiffResponse { diff })
+                    .map_err(|e| RpcError {
+                        code: 0,
+                        message: e.to_string(),
+                    });
+                self.respond_rpc(id, result);
+            }
+            EditLinesInFile { path, edits } => {
+                let result = edit_lines_in_file(&path, edits)
+                    .map(|conflicts| ProxyResponse::EditLinesInFileResponse {
+                        conflicts,
+                    })
+                    .map_err(|e| RpcError {
+                        code: 0,
+                        message: e.to_string(),
+                    });
+                self.respond_rpc(id, result);
+            }
             CompletionResolve {
                 plugin_id,
                 completion_item,
@@ -424,7 +534,7 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                 position,
             } => {
                 let proxy_rpc = self.proxy_rpc.clone();
-                self.catalog_rpc.hover(&path, position, move |_, result| {
+                self.catalog_rpc.hover(&path, position, move |result| {
                     let result = result.map(|hover| ProxyResponse::HoverResponse {
                         request_id,
                         hover,
@@ -433,6 +543,16 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                 });
             }
             GetSignature { .. } => {}
+            GetInlineSuggestion { path, position } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc
+                    .inline_suggestion(&path, position, move |result| {
+                        let result = result.map(|text| {
+                            ProxyResponse::InlineSuggestionResponse { text }
+                        });
+                        proxy_rpc.handle_response(id, result);
+                    });
+            }
             GetReferences { path, position } => {
                 let proxy_rpc = self.proxy_rpc.clone();
                 self.catalog_rpc.get_references(
@@ -446,6 +566,34 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                     },
                 );
             }
+            GitFileHistory { path, start, limit } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                thread::spawn(move || {
+                    let result = git_file_history(&path, start, limit)
+                        .map(|(commits, next)| {
+                            ProxyResponse::GitFileHistoryResponse { commits, next }
+                        })
+                        .map_err(|e| RpcError {
+                            code: 0,
+                            message: e.to_string(),
+                        });
+                    proxy_rpc.handle_response(id, result);
+                });
+            }
+            GitFileAtRevision { path, revision } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                thread::spawn(move || {
+                    let result = git_file_at_revision(&path, &revision)
+                        .map(|content| ProxyResponse::GitFileAtRevisionResponse {
+                            content,
+                        })
+                        .map_err(|e| RpcError {
+                            code: 0,
+                            message: e.to_string(),
+                        });
+                    proxy_rpc.handle_response(id, result);
+                });
+            }
             GitGetRemoteFileUrl { file } => {
                 if let Some(workspace) = self.workspace.as_ref() {
                     match git_get_remote_file_url(workspace, &file) {
@@ -576,6 +724,19 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                     },
                 );
             }
+            GetOrganizeImports { path, range } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.get_organize_imports(
+                    &path,
+                    range,
+                    move |plugin_id, result| {
+                        let result = result.map(|resp| {
+                            ProxyResponse::GetCodeActionsResponse { plugin_id, resp }
+                        });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
             GetDocumentSymbols { path } => {
                 let proxy_rpc = self.proxy_rpc.clone();
                 self.catalog_rpc
@@ -595,15 +756,47 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                         proxy_rpc.handle_response(id, result);
                     });
             }
-            GetDocumentFormatting { path } => {
+            GetIndexedWorkspaceSymbols { query } => {
                 let proxy_rpc = self.proxy_rpc.clone();
-                self.catalog_rpc
-                    .get_document_formatting(&path, move |_, result| {
+                let symbol_index = self.symbol_index.clone();
+                thread::spawn(move || {
+                    let symbols = symbol_index.lock().search(&query);
+                    proxy_rpc.handle_response(
+                        id,
+                        Ok(ProxyResponse::GetWorkspaceSymbols { symbols }),
+                    );
+                });
+            }
+            GetDocumentFormatting { path, options } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.get_document_formatting(
+                    &path,
+                    options,
+                    move |_, result| {
                         let result = result.map(|edits| {
                             ProxyResponse::GetDocumentFormatting { edits }
                         });
                         proxy_rpc.handle_response(id, result);
-                    });
+                    },
+                );
+            }
+            GetDocumentRangeFormatting {
+                path,
+                range,
+                options,
+            } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                self.catalog_rpc.get_document_range_formatting(
+                    &path,
+                    range,
+                    options,
+                    move |_, result| {
+                        let result = result.map(|edits| {
+                            ProxyResponse::GetDocumentRangeFormatting { edits }
+                        });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
             }
             PrepareRename { path, position } => {
                 let proxy_rpc = self.proxy_rpc.clone();
@@ -634,39 +827,16 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                     },
                 );
             }
-            GetFiles { .. } => {
+            GetFiles {
+                include_ignored, ..
+            } => {
                 let workspace = self.workspace.clone();
                 let proxy_rpc = self.proxy_rpc.clone();
+                let file_index = self.file_index.clone();
                 thread::spawn(move || {
                     let result = if let Some(workspace) = workspace {
-                        let git_folder =
-                            ignore::overrides::OverrideBuilder::new(&workspace)
-                                .add("!.git/")
-                                .map(|git_folder| git_folder.build());
-
-                        let walker = match git_folder {
-                            Ok(Ok(git_folder)) => {
-                                ignore::WalkBuilder::new(&workspace)
-                                    .hidden(false)
-                                    .parents(false)
-                                    .require_git(false)
-                                    .overrides(git_folder)
-                                    .build()
-                            }
-                            _ => ignore::WalkBuilder::new(&workspace)
-                                .parents(false)
-                                .require_git(false)
-                                .build(),
-                        };
-
-                        let mut items = Vec::new();
-                        for path in walker.flatten() {
-                            if let Some(file_type) = path.file_type() {
-                                if file_type.is_file() {
-                                    items.push(path.into_path());
-                                }
-                            }
-                        }
+                        let items =
+                            file_index.lock().files(&workspace, include_ignored);
                         Ok(ProxyResponse::GetFilesResponse { items })
                     } else {
                         Ok(ProxyResponse::GetFilesResponse { items: Vec::new() })
@@ -688,9 +858,45 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                 let resp = ProxyResponse::GetOpenFilesContentResponse { items };
                 self.proxy_rpc.handle_response(id, Ok(resp));
             }
+            GetOpenFileContent { path } => {
+                let result = self
+                    .buffers
+                    .get(&path)
+                    .map(|buffer| ProxyResponse::GetOpenFileContentResponse {
+                        item: TextDocumentItem {
+                            uri: Url::from_file_path(&path).unwrap(),
+                            language_id: buffer.language_id.to_string(),
+                            version: buffer.rev as i32,
+                            text: buffer.get_document(),
+                        },
+                    })
+                    .ok_or_else(|| RpcError {
+                        code: 0,
+                        message: format!("{} isn't open", path.display()),
+                    });
+                self.proxy_rpc.handle_response(id, result);
+            }
+            ReadDir { path }
+                if is_archive(&path) || archive_member(&path).is_some() =>
+            {
+                let proxy_rpc = self.proxy_rpc.clone();
+                let archive_members = self.archive_members.clone();
+                thread::spawn(move || {
+                    let (archive, dir) = archive_member(&path)
+                        .unwrap_or((path.as_path(), Path::new("")));
+                    let result = read_archive_dir(&archive_members, archive, dir)
+                        .map(|items| ProxyResponse::ReadDirResponse { items })
+                        .map_err(|e| RpcError {
+                            code: 0,
+                            message: e.to_string(),
+                        });
+                    proxy_rpc.handle_response(id, result);
+                });
+            }
             ReadDir { path } => {
                 let proxy_rpc = self.proxy_rpc.clone();
                 thread::spawn(move || {
+                    let unignored = unignored_entries(&path);
                     let result = fs::read_dir(path)
                         .map(|entries| {
                             let mut items = entries
@@ -704,6 +910,8 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                                             read: false,
                                             children: HashMap::new(),
                                             children_open_count: 0,
+                                            is_ignored: !unignored
+                                                .contains(&e.path()),
                                         })
                                         .ok()
                                 })
@@ -720,19 +928,74 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                     proxy_rpc.handle_response(id, result);
                 });
             }
-            Save { rev, path } => {
+            ExtractArchiveMember { path } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                thread::spawn(move || {
+                    let result = archive_member(&path)
+                        .ok_or_else(|| {
+                            anyhow!("{} isn't in an archive", path.display())
+                        })
+                        .and_then(|(archive, member)| {
+                            let path = extract_archive_member(archive, member)?;
+                            Ok(String::from_utf8_lossy(&fs::read(path)?)
+                                .into_owned())
+                        })
+                        .map(|content| ProxyResponse::ExtractArchiveMemberResponse {
+                            content,
+                        })
+                        .map_err(|e| RpcError {
+                            code: 0,
+                            message: e.to_string(),
+                        });
+                    proxy_rpc.handle_response(id, result);
+                });
+            }
+            ExecutePluginCommand { plugin_id, context } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                let _ = self.catalog_rpc.execute_plugin_command(
+                    plugin_id,
+                    context,
+                    move |result| {
+                        let result = result.map(|result| {
+                            ProxyResponse::ExecutePluginCommandResponse { result }
+                        });
+                        proxy_rpc.handle_response(id, result);
+                    },
+                );
+            }
+            Save {
+                rev,
+                path,
+                encoding,
+                fallback,
+            } => {
                 let buffer = self.buffers.get_mut(&path).unwrap();
+                if let Some(encoding) = encoding
+                    .and_then(|encoding| Encoding::for_label(encoding.as_bytes()))
+                {
+                    buffer.encoding = encoding;
+                }
                 let result = buffer
-                    .save(rev)
+                    .save(rev, fallback)
                     .map(|_r| {
                         self.catalog_rpc
                             .did_save_text_document(&path, buffer.rope.clone());
+                        let symbol_index = self.symbol_index.clone();
+                        let path = path.clone();
+                        thread::spawn(move || {
+                            SymbolIndex::index_file(&symbol_index, &path);
+                        });
                         ProxyResponse::SaveResponse {}
                     })
                     .map_err(|e| RpcError {
                         code: 0,
                         message: e.to_string(),
                     });
+                if result.is_ok() {
+                    self.document_event(&path, |uri| PluginEvent::DocumentSaved {
+                        uri,
+                    });
+                }
                 self.respond_rpc(id, result);
             }
             SaveBufferAs {
@@ -745,7 +1008,7 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                 buffer.rope = Rope::from(content);
                 buffer.rev = rev;
                 let result = buffer
-                    .save(rev)
+                    .save(rev, EncodingFallback::default())
                     .map(|_| ProxyResponse::Success {})
                     .map_err(|e| RpcError {
                         code: 0,
@@ -789,6 +1052,28 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                     });
                 self.respond_rpc(id, result);
             }
+            DeletePath { path } => {
+                let result = if path.is_dir() {
+                    std::fs::remove_dir_all(path)
+                } else {
+                    std::fs::remove_file(path)
+                }
+                .map(|_| ProxyResponse::Success {})
+                .map_err(|e| RpcError {
+                    code: 0,
+                    message: e.to_string(),
+                });
+                self.respond_rpc(id, result);
+            }
+            RestoreTrashedPaths { paths } => {
+                let result = restore_trashed_paths(&paths)
+                    .map(|_| ProxyResponse::Success {})
+                    .map_err(|e| RpcError {
+                        code: 0,
+                        message: e.to_string(),
+                    });
+                self.respond_rpc(id, result);
+            }
             DuplicatePath {
                 existing_path,
                 new_path,
@@ -811,7 +1096,7 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                             return;
                         }
                     }
-                    std::fs::copy(existing_path, new_path)
+                    copy_path(&existing_path, &new_path)
                         .map(|_| ProxyResponse::Success {})
                         .map_err(|e| RpcError {
                             code: 0,
@@ -888,11 +1173,21 @@ pub fn new(core_rpc: CoreRpcHandler, proxy_rpc: ProxyRpcHandler) -> Self {
             buffers: HashMap::new(),
             terminals: HashMap::new(),
             file_watcher,
+            file_index: Arc::new(Mutex::new(FileIndex::default())),
+            symbol_index: Arc::new(Mutex::new(SymbolIndex::default())),
+            archive_members: Arc::new(Mutex::new(ArchiveMembers::default())),
             window_id: 1,
             tab_id: 1,
         }
     }
 
+    /// Tell the plugins subscribed to the events of the documents about one
+    fn document_event(&self, path: &Path, event: impl FnOnce(Url) -> PluginEvent) {
+        if let Ok(uri) = Url::from_file_path(path) {
+            let _ = self.catalog_rpc.plugin_event(event(uri));
+        }
+    }
+
     fn respond_rpc(&self, id: RequestId, result: Result<ProxyResponse, RpcError>) {
         self.proxy_rpc.handle_response(id, result);
     }
@@ -901,9 +1196,15 @@ fn respond_rpc(&self, id: RequestId, result: Result<ProxyResponse, RpcError>) {
 struct FileWatchNotifier {
     core_rpc: CoreRpcHandler,
     proxy_rpc: ProxyRpcHandler,
+    catalog_rpc: PluginCatalogRpcHandler,
     workspace: Option<PathBuf>,
-    workspace_fs_change_handler: Arc<Mutex<Option<Sender<bool>>>>,
+    workspace_fs_change_handler: Arc<Mutex<Option<Sender<Vec<FileChange>>>>>,
+    /// Whether a change to the workspace settings is waiting to be sent, so
+    /// that the events of one save only reload them once
+    workspace_config_change: Arc<AtomicBool>,
     last_diff: Arc<Mutex<DiffInfo>>,
+    file_index: Arc<Mutex<FileIndex>>,
+    symbol_index: Arc<Mutex<SymbolIndex>>,
 }
 
 impl Notify for FileWatchNotifier {
@@ -917,13 +1218,20 @@ fn new(
         workspace: Option<PathBuf>,
         core_rpc: CoreRpcHandler,
         proxy_rpc: ProxyRpcHandler,
+        catalog_rpc: PluginCatalogRpcHandler,
+        file_index: Arc<Mutex<FileIndex>>,
+        symbol_index: Arc<Mutex<SymbolIndex>>,
     ) -> Self {
         let notifier = Self {
             workspace,
             core_rpc,
             proxy_rpc,
+            catalog_rpc,
             workspace_fs_change_handler: Arc::new(Mutex::new(None)),
+            workspace_config_change: Arc::new(AtomicBool::new(false)),
             last_diff: Arc::new(Mutex::new(DiffInfo::default())),
+            file_index,
+            symbol_index,
         };
 
         if let Some(workspace) = notifier.workspace.clone() {
@@ -940,6 +1248,40 @@ fn new(
         notifier
     }
 
+    /// Tell the core to reload the settings when the ones of the workspace
+    /// change
+    fn handle_workspace_config_event(&self, event: &notify::Event) {
+        if event.kind.is_access() {
+            return;
+        }
+        let Some(workspace) = self.workspace.as_ref() else {
+            return;
+        };
+        let config_dir = workspace.join(".lapce");
+        let is_config = event.paths.iter().any(|path| {
+            path.parent() == Some(config_dir.as_path())
+                && matches!(
+                    path.file_name().and_then(|name| name.to_str()),
+                    Some("settings.toml" | "languages.toml")
+                )
+        });
+        if !is_config
+            || self
+                .workspace_config_change
+                .compare_exchange(false, true, Ordering::Relaxed, Ordering::Relaxed)
+                .is_err()
+        {
+            return;
+        }
+        let core_rpc = self.core_rpc.clone();
+        let pending = self.workspace_config_change.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(500));
+            pending.store(false, Ordering::Relaxed);
+            core_rpc.workspace_config_change();
+        });
+    }
+
     fn handle_fs_events(&self, events: Vec<(WatchToken, notify::Event)>) {
         for (token, event) in events {
             match token {
@@ -960,32 +1302,37 @@ fn handle_open_file_fs_event(&self, event: notify::Event) {
     }
 
     fn handle_workspace_fs_event(&self, event: notify::Event) {
-        let explorer_change = match &event.kind {
-            notify::EventKind::Create(_)
-            | notify::EventKind::Remove(_)
-            | notify::EventKind::Modify(notify::event::ModifyKind::Name(_)) => true,
-            notify::EventKind::Modify(_) => false,
-            _ => return,
-        };
+        self.handle_workspace_config_event(&event);
+
+        if !event.kind.is_create()
+            && !event.kind.is_remove()
+            && !event.kind.is_modify()
+        {
+            return;
+        }
+        let changes = file_changes(&event);
 
         let mut handler = self.workspace_fs_change_handler.lock();
         if let Some(sender) = handler.as_mut() {
-            if explorer_change {
-                // only send the value if we need to update file explorer as well
-                let _ = sender.send(explorer_change);
+            if !changes.is_empty() {
+                // only send the changes if we need to update file explorer as well
+                let _ = sender.send(changes);
             }
             return;
         }
         let (sender, receiver) = crossbeam_channel::unbounded();
-        if explorer_change {
-            // only send the value if we need to update file explorer as well
-            let _ = sender.send(explorer_change);
+        if !changes.is_empty() {
+            // only send the changes if we need to update file explorer as well
+            let _ = sender.send(changes);
         }
 
         let local_handler = self.workspace_fs_change_handler.clone();
         let core_rpc = self.core_rpc.clone();
+        let catalog_rpc = self.catalog_rpc.clone();
         let workspace = self.workspace.clone().unwrap();
         let last_diff = self.last_diff.clone();
+        let file_index = self.file_index.clone();
+        let symbol_index = self.symbol_index.clone();
         thread::spawn(move || {
             thread::sleep(Duration::from_millis(500));
 
@@ -993,15 +1340,37 @@ fn handle_workspace_fs_event(&self, event: notify::Event) {
                 local_handler.lock().take();
             }
 
-            let mut explorer_change = false;
-            for e in receiver {
-                if e {
-                    explorer_change = true;
-                    break;
-                }
-            }
-            if explorer_change {
-                core_rpc.workspace_file_change();
+            // A path can be created and removed again within the wait, so only
+            // the changes that still hold are kept
+            let changes: Vec<FileChange> = receiver
+                .try_iter()
+                .flatten()
+                .filter(|change| match change {
+                    FileChange::Created(path) | FileChange::Modified(path) => {
+                        path.exists()
+                    }
+                    FileChange::Removed(path) => !path.exists(),
+                    FileChange::Renamed { to, .. } => to.exists(),
+                })
+                .collect();
+            if !changes.is_empty() {
+                file_index.lock().apply(&changes);
+                SymbolIndex::apply(&symbol_index, &changes);
+                // Saved along the way, so that a session that doesn't shut
+                // down cleanly still leaves most of the index behind
+                let _ = symbol_index.lock().save(&workspace);
+            }
+            // The changes to the content of files are only for the symbol
+            // index
+            let changes: Vec<FileChange> = changes
+                .into_iter()
+                .filter(|change| !matches!(change, FileChange::Modified(_)))
+                .collect();
+            if !changes.is_empty() {
+                let _ = catalog_rpc.plugin_event(PluginEvent::FilesChanged {
+                    changes: changes.clone(),
+                });
+                core_rpc.workspace_file_change(changes);
             }
             if let Some(diff) = git_diff_new(&workspace) {
                 let mut last_diff = last_diff.lock();
@@ -1015,6 +1384,49 @@ fn handle_workspace_fs_event(&self, event: notify::Event) {
     }
 }
 
+/// The changes to the files of the workspace that the event reports
+fn file_changes(event: &notify::Event) -> Vec<FileChange> {
+    use notify::{
+        event::{ModifyKind, RenameMode},
+        EventKind,
+    };
+
+    let paths = event.paths.iter().cloned();
+    match event.kind {
+        EventKind::Create(_) => paths.map(FileChange::Created).collect(),
+        EventKind::Remove(_) => paths.map(FileChange::Removed).collect(),
+        EventKind::Modify(ModifyKind::Data(_)) => {
+            paths.map(FileChange::Modified).collect()
+        }
+        EventKind::Modify(ModifyKind::Name(RenameMode::Both))
+            if event.paths.len() == 2 =>
+        {
+            vec![FileChange::Renamed {
+                from: event.paths[0].clone(),
+                to: event.paths[1].clone(),
+            }]
+        }
+        EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+            paths.map(FileChange::Removed).collect()
+        }
+        EventKind::Modify(ModifyKind::Name(RenameMode::To)) => {
+            paths.map(FileChange::Created).collect()
+        }
+        // Without knowing which side of the rename the path is, whether it
+        // still exists tells it
+        EventKind::Modify(ModifyKind::Name(_)) => paths
+            .map(|path| {
+                if path.exists() {
+                    FileChange::Created(path)
+                } else {
+                    FileChange::Removed(path)
+                }
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct DiffHunk {
     pub old_start: u32,
@@ -1024,6 +1436,44 @@ pub struct DiffHunk {
     pub header: String,
 }
 
+/// Put the paths back from the trash, each from the last time it was moved
+/// there
+#[cfg(any(
+    target_os = "windows",
+    all(unix, not(target_os = "macos"), not(target_os = "ios")),
+))]
+fn restore_trashed_paths(paths: &[PathBuf]) -> Result<()> {
+    let mut items: HashMap<PathBuf, trash::TrashItem> = HashMap::new();
+    for item in trash::os_limited::list()? {
+        let path = item.original_path();
+        if !paths.contains(&path) {
+            continue;
+        }
+        let is_latest = items
+            .get(&path)
+            .map(|latest| item.time_deleted > latest.time_deleted)
+            .unwrap_or(true);
+        if is_latest {
+            items.insert(path, item);
+        }
+    }
+    if let Some(path) = paths.iter().find(|path| !items.contains_key(*path)) {
+        return Err(anyhow!("{path:?} isn't in the trash"));
+    }
+    trash::os_limited::restore_all(items.into_values())?;
+    Ok(())
+}
+
+#[cfg(not(any(
+    target_os = "windows",
+    all(unix, not(target_os = "macos"), not(target_os = "ios")),
+)))]
+fn restore_trashed_paths(_paths: &[PathBuf]) -> Result<()> {
+    Err(anyhow!(
+        "Putting files back from the trash isn't supported on this system"
+    ))
+}
+
 fn git_init(workspace_path: &Path) -> Result<()> {
     if Repository::discover(workspace_path).is_err() {
         Repository::init(workspace_path)?;
@@ -1117,6 +1567,85 @@ fn git_discard_workspace_changes(workspace_path: &Path) -> Result<()> {
     Ok(())
 }
 
+/// Apply the hunk of a unified diff of the file to the index
+/// Stage the lines replacing the given ones of the file at `HEAD`, leaving
+/// the other changes to the file. The patch is made against the file in the
+/// index, so that it applies along with what's already staged of it.
+fn git_stage_hunk(
+    workspace_path: &Path,
+    path: &Path,
+    head_lines: std::ops::Range<usize>,
+    lines: &str,
+) -> Result<()> {
+    let repo = Repository::discover(workspace_path)?;
+    let workdir = repo.workdir().ok_or_else(|| anyhow!("bare repository"))?;
+    let path = path.strip_prefix(workdir)?;
+    let blob_text = |id: git2::Oid| -> Result<Rope> {
+        let blob = repo.find_blob(id)?;
+        Ok(Rope::from(String::from_utf8_lossy(blob.content())))
+    };
+    let head = match repo
+        .head()
+        .and_then(|head| head.peel_to_tree())
+        .and_then(|tree| tree.get_path(path))
+    {
+        Ok(entry) => blob_text(entry.id())?,
+        Err(_) => Rope::from(""),
+    };
+    let index = match repo.index()?.get_path(path, 0) {
+        Some(entry) => blob_text(entry.id)?,
+        None => Rope::from(""),
+    };
+
+    let staged = rope_diff(
+        head,
+        index.clone(),
+        0,
+        Arc::new(AtomicU64::new(0)),
+        None,
+        LineDiffOptions::default(),
+    )
+    .map(|changes| diff_hunks(&changes))
+    .unwrap_or_default();
+    let left = index_lines(&staged, head_lines)
+        .ok_or_else(|| anyhow!("the hunk overlaps changes already staged"))?;
+    let lines = Rope::from(lines);
+    let right = 0..lines.lines_raw(..).count();
+    let hunk = hunk_patch(&index, &lines, &LineDiffHunk { left, right }, 3);
+
+    let path = path.to_string_lossy().replace('\\', "/");
+    let patch =
+        format!("diff --git a/{path} b/{path}\n--- a/{path}\n+++ b/{path}\n{hunk}");
+    let diff = Diff::from_buffer(patch.as_bytes())?;
+    repo.apply(&diff, ApplyLocation::Index, None)?;
+    Ok(())
+}
+
+/// The lines of the file in the index that the lines of the file at `HEAD`
+/// are at, given the hunks that are staged, or `None` if they're changed by
+/// a staged hunk
+fn index_lines(
+    staged: &[LineDiffHunk],
+    head_lines: std::ops::Range<usize>,
+) -> Option<std::ops::Range<usize>> {
+    let mut shift = 0isize;
+    for hunk in staged {
+        let before = hunk.left.end < head_lines.start
+            || (hunk.left.end == head_lines.start
+                && hunk.left.start < head_lines.start);
+        let after = hunk.left.start > head_lines.end
+            || (hunk.left.start == head_lines.end
+                && head_lines.start < head_lines.end);
+        if before {
+            shift += hunk.right.len() as isize - hunk.left.len() as isize;
+        } else if !after {
+            return None;
+        }
+    }
+    let start = (head_lines.start as isize + shift) as usize;
+    Some(start..start + head_lines.len())
+}
+
 fn git_delta_format(
     workspace_path: &Path,
     delta: &git2::DiffDelta,
@@ -1251,6 +1780,86 @@ fn file_get_head(workspace_path: &Path, path: &Path) -> Result<(String, String)>
     Ok((id, content))
 }
 
+/// The changes staged in the repository containing `path`, as a patch
+fn git_staged_diff(path: &Path) -> Result<String> {
+    let repo = Repository::discover(path)?;
+    let head = repo.head().ok().and_then(|head| head.peel_to_tree().ok());
+    let diff = repo.diff_tree_to_index(head.as_ref(), None, None)?;
+    let mut patch = String::new();
+    diff.print(DiffFormat::Patch, |_, _, line| {
+        if matches!(line.origin(), '+' | '-' | ' ') {
+            patch.push(line.origin());
+        }
+        patch.push_str(&String::from_utf8_lossy(line.content()));
+        true
+    })?;
+    Ok(patch)
+}
+
+/// Up to `limit` of the commits reachable from HEAD whose tree has a
+/// different version of the file than their first parent's, the latest
+/// first, looking from the commit at `start` of the walk on. Also gives where
+/// the next ones are looked for from, if the walk didn't end. Renames aren't
+/// followed.
+fn git_file_history(
+    path: &Path,
+    start: usize,
+    limit: usize,
+) -> Result<(Vec<FileCommit>, Option<usize>)> {
+    let repo = Repository::discover(path)?;
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| anyhow!("the repository has no working tree"))?;
+    let relative = path.strip_prefix(workdir)?;
+    let blob_id = |commit: &git2::Commit| {
+        commit
+            .tree()
+            .ok()
+            .and_then(|tree| tree.get_path(relative).ok())
+            .map(|entry| entry.id())
+    };
+
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push_head()?;
+    revwalk.set_sorting(git2::Sort::TIME)?;
+    let mut commits = Vec::new();
+    for (walked, id) in revwalk.enumerate().skip(start) {
+        if commits.len() == limit {
+            return Ok((commits, Some(walked)));
+        }
+        let commit = repo.find_commit(id?)?;
+        let Some(blob) = blob_id(&commit) else {
+            continue;
+        };
+        let parent_blob = commit.parent(0).ok().and_then(|p| blob_id(&p));
+        if parent_blob == Some(blob) {
+            continue;
+        }
+        commits.push(FileCommit {
+            id: commit.id().to_string(),
+            summary: commit.summary().unwrap_or_default().to_string(),
+            author: commit.author().name().unwrap_or_default().to_string(),
+            time: commit.time().seconds(),
+        });
+    }
+    Ok((commits, None))
+}
+
+/// The content of the file in the tree of the revision
+fn git_file_at_revision(path: &Path, revision: &str) -> Result<String> {
+    let repo = Repository::discover(path)?;
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| anyhow!("the repository has no working tree"))?;
+    let tree = repo.revparse_single(revision)?.peel_to_tree()?;
+    let entry = tree.get_path(path.strip_prefix(workdir)?)?;
+    let blob = repo.find_blob(entry.id())?;
+    let content = std::str::from_utf8(blob.content())
+        .with_context(|| "content bytes to string")?
+        .to_string();
+    Ok(content)
+}
+
 fn git_get_remote_file_url(workspace_path: &Path, file: &Path) -> Result<String> {
     let repo = Repository::discover(workspace_path)?;
     let head = repo.head()?;
@@ -1337,35 +1946,18 @@ fn search_in_path(
                         return Ok(false);
                     }
 
-                    let mymatch = matcher.find(line.as_bytes())?.unwrap();
-                    let line = if line.len() > 200 {
-                        // Shorten the line to avoid sending over absurdly long-lines
-                        // (such as in minified javascript)
-                        // Note that the start/end are column based, not absolute from the
-                        // start of the file.
-                        let left_keep = line[..mymatch.start()]
-                            .chars()
-                            .rev()
-                            .take(100)
-                            .map(|c| c.len_utf8())
-                            .sum::<usize>();
-                        let right_keep = line[mymatch.end()..]
-                            .chars()
-                            .take(100)
-                            .map(|c| c.len_utf8())
-                            .sum::<usize>();
-                        let display_range =
-                            mymatch.start() - left_keep..mymatch.end() + right_keep;
-                        line[display_range].to_string()
-                    } else {
-                        line.to_string()
-                    };
-                    line_matches.push(SearchMatch {
-                        line: lnum as usize,
-                        start: mymatch.start(),
-                        end: mymatch.end(),
-                        line_content: line,
-                    });
+                    matcher.find_iter(line.as_bytes(), |mymatch| {
+                        let (content_start, line_content) =
+                            match_context(line, mymatch.start(), mymatch.end());
+                        line_matches.push(SearchMatch {
+                            line: lnum as usize,
+                            start: mymatch.start(),
+                            end: mymatch.end(),
+                            content_start,
+                            line_content,
+                        });
+                        true
+                    })?;
                     Ok(true)
                 }),
             );
@@ -1377,3 +1969,387 @@ fn search_in_path(
 
     Ok(ProxyResponse::GlobalSearchResponse { matches })
 }
+
+/// The part of the line shown for a match, with the column it starts at.
+/// Long lines (such as in minified javascript) are shortened to 100
+/// characters on each side of the match, to avoid sending them over whole.
+fn match_context(line: &str, start: usize, end: usize) -> (usize, String) {
+    if line.len() <= 200 {
+        return (0, line.to_string());
+    }
+    let left_keep = line[..start]
+        .chars()
+        .rev()
+        .take(100)
+        .map(|c| c.len_utf8())
+        .sum::<usize>();
+    let right_keep = line[end..]
+        .chars()
+        .take(100)
+        .map(|c| c.len_utf8())
+        .sum::<usize>();
+    (
+        start - left_keep,
+        line[start - left_keep..end + right_keep].to_string(),
+    )
+}
+
+/// Apply the replacements of search matches to the file on disk, returning
+/// the line and start column of the ones skipped because the text they
+/// replace is no longer there.
+fn replace_in_file(
+    path: &Path,
+    mut replacements: Vec<SearchReplacement>,
+) -> Result<Vec<(usize, usize)>> {
+    let content = fs::read_to_string(path)?;
+    // Replace from the end of each line, so that the columns of the other
+    // replacements on it stay valid.
+    replacements.sort_by(|a, b| a.line.cmp(&b.line).then(b.start.cmp(&a.start)));
+    let mut replacements = replacements.into_iter().peekable();
+
+    let mut skipped = Vec::new();
+    let mut new_content = String::with_capacity(content.len());
+    for (i, line) in content.split_inclusive('\n').enumerate() {
+        let line_number = i + 1;
+        let mut line = line.to_string();
+        while let Some(replacement) = replacements.next_if(|r| r.line <= line_number)
+        {
+            let range = replacement.start..replacement.end;
+            if replacement.line == line_number
+                && line.get(range.clone()) == Some(replacement.original.as_str())
+            {
+                line.replace_range(range, &replacement.replacement);
+            } else {
+                skipped.push((replacement.line, replacement.start));
+            }
+        }
+        new_content.push_str(&line);
+    }
+    // The replacements past the end of the file
+    skipped.extend(replacements.map(|r| (r.line, r.start)));
+    skipped.sort();
+
+    if new_content != content {
+        fs::write(path, new_content)?;
+    }
+    Ok(skipped)
+}
+
+/// Apply the line edits to the file on disk, returning the lines that were
+/// skipped because their content is no longer the one the edit expects, with
+/// their current content.
+fn edit_lines_in_file(
+    path: &Path,
+    edits: Vec<LineEdit>,
+) -> Result<Vec<(usize, String)>> {
+    let content = fs::read_to_string(path)?;
+    let mut edits: HashMap<usize, LineEdit> =
+        edits.into_iter().map(|edit| (edit.line, edit)).collect();
+
+    let mut conflicts = Vec::new();
+    let mut new_content = String::with_capacity(content.len());
+    for (i, line) in content.split_inclusive('\n').enumerate() {
+        let text = line.trim_end_matches(['\r', '\n']);
+        let Some(edit) = edits.remove(&(i + 1)) else {
+            new_content.push_str(line);
+            continue;
+        };
+        match edit.range(text) {
+            Some(range) => {
+                new_content.push_str(&line[..range.start]);
+                new_content.push_str(&edit.replacement);
+                new_content.push_str(&line[range.end..]);
+            }
+            None => {
+                conflicts.push((edit.line, edit.current(text).to_string()));
+                new_content.push_str(line);
+            }
+        }
+    }
+    // The lines past the end of the file
+    conflicts.extend(edits.into_keys().map(|line| (line, String::new())));
+    conflicts.sort();
+
+    if new_content != content {
+        fs::write(path, new_content)?;
+    }
+    Ok(conflicts)
+}
+
+/// Copy the file, or the directory with everything in it, to the new path.
+/// Symbolic links are copied as links to what they point to, rather than
+/// followed.
+fn copy_path(from: &Path, to: &Path) -> std::io::Result<()> {
+    let metadata = fs::symlink_metadata(from)?;
+    if metadata.file_type().is_symlink() {
+        copy_symlink(from, to)
+    } else if metadata.is_dir() {
+        fs::create_dir(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copy_path(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        Ok(())
+    } else {
+        fs::copy(from, to).map(|_| ())
+    }
+}
+
+#[cfg(unix)]
+fn copy_symlink(from: &Path, to: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(fs::read_link(from)?, to)
+}
+
+#[cfg(windows)]
+fn copy_symlink(from: &Path, to: &Path) -> std::io::Result<()> {
+    let target = fs::read_link(from)?;
+    // Windows tells links to directories apart from links to files
+    if from.is_dir() {
+        std::os::windows::fs::symlink_dir(target, to)
+    } else {
+        std::os::windows::fs::symlink_file(target, to)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_index_lines() {
+        let hunk = |left: std::ops::Range<usize>, right: std::ops::Range<usize>| {
+            LineDiffHunk { left, right }
+        };
+        assert_eq!(index_lines(&[], 3..5), Some(3..5));
+        // Two lines were staged in place of one before the hunk
+        assert_eq!(index_lines(&[hunk(0..1, 0..2)], 3..5), Some(4..6));
+        // and a line was staged removed after it
+        assert_eq!(
+            index_lines(&[hunk(0..1, 0..2), hunk(6..7, 7..7)], 3..5),
+            Some(4..6)
+        );
+        // The lines of the hunk were changed by what's staged
+        assert_eq!(index_lines(&[hunk(4..5, 4..5)], 3..5), None);
+        assert_eq!(index_lines(&[hunk(3..3, 3..4)], 3..3), None);
+    }
+
+    #[test]
+    fn test_copy_path() {
+        let dir = std::env::temp_dir().join("lapce-copy-path-test");
+        let _ = fs::remove_dir_all(&dir);
+        let from = dir.join("from");
+        fs::create_dir_all(from.join("sub")).unwrap();
+        fs::write(from.join("a.txt"), "a").unwrap();
+        fs::write(from.join("sub").join("b.txt"), "b").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink("a.txt", from.join("link")).unwrap();
+
+        let to = dir.join("to");
+        copy_path(&from, &to).unwrap();
+        assert_eq!(fs::read_to_string(to.join("a.txt")).unwrap(), "a");
+        assert_eq!(
+            fs::read_to_string(to.join("sub").join("b.txt")).unwrap(),
+            "b"
+        );
+        // The link is copied as a link, still pointing where it did
+        #[cfg(unix)]
+        {
+            let link = to.join("link");
+            assert!(fs::symlink_metadata(&link)
+                .unwrap()
+                .file_type()
+                .is_symlink());
+            assert_eq!(fs::read_link(&link).unwrap(), Path::new("a.txt"));
+        }
+
+        // A directory isn't copied over one that's there
+        assert!(copy_path(&from.join("sub"), &to.join("sub")).is_err());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_stage_hunk_on_index() {
+        let dir = std::env::temp_dir().join("lapce-stage-hunk-test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let repo = Repository::init(&dir).unwrap();
+        let file = dir.join("a.txt");
+        let stage = |text: &str| {
+            fs::write(&file, text).unwrap();
+            let mut index = repo.index().unwrap();
+            index.add_path(Path::new("a.txt")).unwrap();
+            index.write().unwrap();
+            index.write_tree().unwrap()
+        };
+        let tree = repo.find_tree(stage("a\nb\nc\nd\n")).unwrap();
+        let signature = git2::Signature::now("lapce", "lapce@lapce.dev").unwrap();
+        repo.commit(Some("HEAD"), &signature, &signature, "a", &tree, &[])
+            .unwrap();
+        // A line is staged at the start, which the hunk of `HEAD` is after
+        stage("z\na\nb\nc\nd\n");
+        fs::write(&file, "z\na\nb\nC\nd\n").unwrap();
+
+        git_stage_hunk(&dir, &file, 2..3, "C\n").unwrap();
+        let index = repo.index().unwrap();
+        let entry = index.get_path(Path::new("a.txt"), 0).unwrap();
+        let blob = repo.find_blob(entry.id).unwrap();
+        assert_eq!(blob.content(), b"z\na\nb\nC\nd\n");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_git_file_history_pages() {
+        let dir = std::env::temp_dir().join("lapce-file-history-test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let repo = Repository::init(&dir).unwrap();
+        let file = dir.join("a.txt");
+        let mut parent: Option<git2::Oid> = None;
+        let mut commit = |name: &str, text: &str, time: i64| {
+            fs::write(dir.join(name), text).unwrap();
+            let mut index = repo.index().unwrap();
+            index.add_path(Path::new(name)).unwrap();
+            index.write().unwrap();
+            let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+            let signature = git2::Signature::new(
+                "lapce",
+                "lapce@lapce.dev",
+                &git2::Time::new(time, 0),
+            )
+            .unwrap();
+            let parents = parent
+                .map(|id| repo.find_commit(id).unwrap())
+                .into_iter()
+                .collect::<Vec<_>>();
+            let parents = parents.iter().collect::<Vec<_>>();
+            let id = repo
+                .commit(Some("HEAD"), &signature, &signature, text, &tree, &parents)
+                .unwrap();
+            parent = Some(id);
+        };
+        commit("a.txt", "first", 1_000);
+        commit("b.txt", "other file", 2_000);
+        commit("a.txt", "second", 3_000);
+
+        let (commits, next) = git_file_history(&file, 0, 1).unwrap();
+        assert_eq!(commits.len(), 1);
+        assert_eq!(commits[0].summary, "second");
+        assert_eq!(next, Some(1));
+
+        // The commit that didn't change the file is passed over
+        let (commits, next) = git_file_history(&file, 1, 1).unwrap();
+        assert_eq!(commits.len(), 1);
+        assert_eq!(commits[0].summary, "first");
+        assert_eq!(next, None);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    fn replacement(line: usize, start: usize, original: &str) -> SearchReplacement {
+        SearchReplacement {
+            line,
+            start,
+            end: start + original.len(),
+            original: original.to_string(),
+            replacement: "bar".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_search_every_match_in_line() {
+        let dir = std::env::temp_dir().join("lapce-search-test");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("matches.txt");
+        let long_line = format!("{}foo", "x".repeat(300));
+        fs::write(&path, format!("foo foo\nnone\n{long_line}\n")).unwrap();
+
+        let current_id = AtomicU64::new(1);
+        let response = search_in_path(
+            1,
+            &current_id,
+            [path.clone()].into_iter(),
+            "foo",
+            true,
+            false,
+            false,
+        )
+        .unwrap();
+        let ProxyResponse::GlobalSearchResponse { matches } = response else {
+            panic!("not a search response");
+        };
+        let matches: Vec<(usize, usize, usize, &str)> = matches[&path]
+            .iter()
+            .map(|m| (m.line, m.start, m.content_start, m.matched()))
+            .collect();
+        assert_eq!(
+            matches,
+            vec![(1, 0, 0, "foo"), (1, 4, 0, "foo"), (3, 300, 200, "foo")]
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_replace_skips_stale_matches() {
+        let dir = std::env::temp_dir().join("lapce-replace-test");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("stale.txt");
+        // The file changed since "foo foo" and "foo" were matched on the first
+        // and third lines
+        fs::write(&path, "foo baz\nfoo\n").unwrap();
+
+        let skipped = replace_in_file(
+            &path,
+            vec![
+                replacement(1, 0, "foo"),
+                replacement(1, 4, "foo"),
+                replacement(2, 0, "foo"),
+                replacement(3, 0, "foo"),
+            ],
+        )
+        .unwrap();
+        assert_eq!(skipped, vec![(1, 4), (3, 0)]);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "bar baz\nbar\n");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_edit_part_of_lines() {
+        let dir = std::env::temp_dir().join("lapce-edit-lines-test");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("long.txt");
+        let long = format!("{}needle{}", "a".repeat(300), "b".repeat(300));
+        fs::write(&path, format!("{long}\nfoo bar\r\nbaz\n")).unwrap();
+
+        let edit = |line: usize, start: usize, original: &str| LineEdit {
+            line,
+            start,
+            original: original.to_string(),
+            replacement: "pin".to_string(),
+        };
+        let conflicts = edit_lines_in_file(
+            &path,
+            vec![
+                // Only the part of the long line around its match is edited
+                edit(1, 300, "needle"),
+                edit(2, 4, "bar"),
+                // The line changed since it was matched
+                edit(3, 0, "qux"),
+            ],
+        )
+        .unwrap();
+        assert_eq!(conflicts, vec![(3, "baz".to_string())]);
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            format!(
+                "{}pin{}\nfoo pin\r\nbaz\n",
+                "a".repeat(300),
+                "b".repeat(300)
+            )
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,152 @@
+use std::{
+    collections::BTreeSet,
+    path::{Component, Path, PathBuf},
+};
+
+use lapce_rpc::file::FileChange;
+
+use crate::ignore_matcher::{unignored_entries, walker};
+
+/// The files of the workspace for the file finder. The workspace is walked the
+/// first time they're asked for, and after that the changes the file watcher
+/// reports are applied to them, so opening the finder doesn't walk it again.
+#[derive(Default)]
+pub struct FileIndex {
+    /// The files, and whether the ignored ones are in them
+    files: Option<(bool, BTreeSet<PathBuf>)>,
+}
+
+impl FileIndex {
+    pub fn files(
+        &mut self,
+        workspace: &Path,
+        include_ignored: bool,
+    ) -> Vec<PathBuf> {
+        match &self.files {
+            Some((included, files)) if *included == include_ignored => {
+                files.iter().cloned().collect()
+            }
+            _ => {
+                let files = walk_files(workspace, include_ignored);
+                let items = files.iter().cloned().collect();
+                self.files = Some((include_ignored, files));
+                items
+            }
+        }
+    }
+
+    /// Apply the changes to the files, if they've been walked already
+    pub fn apply(&mut self, changes: &[FileChange]) {
+        let Some((include_ignored, files)) = self.files.as_mut() else {
+            return;
+        };
+        for change in changes {
+            match change {
+                FileChange::Created(path) => {
+                    add_path(files, path, *include_ignored);
+                }
+                FileChange::Modified(_) => {}
+                FileChange::Removed(path) => {
+                    remove_path(files, path);
+                }
+                FileChange::Renamed { from, to } => {
+                    remove_path(files, from);
+                    add_path(files, to, *include_ignored);
+                }
+            }
+        }
+    }
+}
+
+fn walk_files(path: &Path, include_ignored: bool) -> BTreeSet<PathBuf> {
+    walker(path, include_ignored)
+        .build()
+        .flatten()
+        .filter(|entry| entry.file_type().map(|t| t.is_file()).unwrap_or(false))
+        .map(|entry| entry.into_path())
+        .collect()
+}
+
+/// Add the file, or the files under the directory, unless they're ignored
+fn add_path(files: &mut BTreeSet<PathBuf>, path: &Path, include_ignored: bool) {
+    if path
+        .components()
+        .any(|c| c == Component::Normal(".git".as_ref()))
+    {
+        return;
+    }
+    if !include_ignored {
+        // The walker always yields the path it starts from, so whether the
+        // path itself is ignored has to be checked from its parent
+        let Some(parent) = path.parent() else {
+            return;
+        };
+        if !unignored_entries(parent).contains(path) {
+            return;
+        }
+    }
+    if path.is_dir() {
+        files.extend(walk_files(path, include_ignored));
+    } else if path.is_file() {
+        files.insert(path.to_path_buf());
+    }
+}
+
+/// Remove the file, or the files under the directory
+fn remove_path(files: &mut BTreeSet<PathBuf>, path: &Path) {
+    let removed: Vec<PathBuf> = files
+        .range(path.to_path_buf()..)
+        .take_while(|file| file.starts_with(path))
+        .cloned()
+        .collect();
+    for file in removed {
+        files.remove(&file);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn test_file_index() {
+        let root = &std::env::temp_dir()
+            .join(format!("lapce-file-index-{}", std::process::id()));
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join(".gitignore"), "*.log\n").unwrap();
+        fs::write(root.join("src/main.rs"), "").unwrap();
+
+        let mut index = FileIndex::default();
+        let files = index.files(root, false);
+        assert!(files.contains(&root.join("src/main.rs")));
+
+        fs::create_dir_all(root.join("tests")).unwrap();
+        fs::write(root.join("tests/test.rs"), "").unwrap();
+        fs::write(root.join("tests/test.log"), "").unwrap();
+        fs::write(root.join("debug.log"), "").unwrap();
+        fs::rename(root.join("src"), root.join("lib")).unwrap();
+        index.apply(&[
+            FileChange::Created(root.join("tests")),
+            FileChange::Created(root.join("debug.log")),
+            FileChange::Renamed {
+                from: root.join("src"),
+                to: root.join("lib"),
+            },
+        ]);
+        let files = index.files(root, false);
+        assert!(files.contains(&root.join("tests/test.rs")));
+        assert!(files.contains(&root.join("lib/main.rs")));
+        assert!(!files.contains(&root.join("src/main.rs")));
+        assert!(!files.contains(&root.join("tests/test.log")));
+        assert!(!files.contains(&root.join("debug.log")));
+
+        fs::remove_dir_all(root.join("tests")).unwrap();
+        index.apply(&[FileChange::Removed(root.join("tests"))]);
+        let files = index.files(root, false);
+        assert!(!files.contains(&root.join("tests/test.rs")));
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
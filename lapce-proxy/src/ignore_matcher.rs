@@ -0,0 +1,85 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use ignore::{overrides::OverrideBuilder, WalkBuilder};
+
+/// A walker of the files under the path, leaving out the ones the workspace
+/// ignores unless `include_ignored`: what the `.gitignore` files, the global
+/// git ignore file and the `.ignore` files match. The `.git` folder is always
+/// left out.
+///
+/// The file finder, the file explorer and the workspace search all go through
+/// it, so that they agree on what's ignored.
+pub fn walker(path: &Path, include_ignored: bool) -> WalkBuilder {
+    let mut builder = WalkBuilder::new(path);
+    builder.hidden(false).require_git(false);
+    if include_ignored {
+        builder
+            .ignore(false)
+            .git_ignore(false)
+            .git_global(false)
+            .git_exclude(false);
+    }
+    let git_folder = OverrideBuilder::new(path)
+        .add("!.git/")
+        .map(|git_folder| git_folder.build());
+    if let Ok(Ok(git_folder)) = git_folder {
+        builder.overrides(git_folder);
+    }
+    builder
+}
+
+/// The entries of the directory that the workspace doesn't ignore, by the
+/// ignore files in it and in the directories above it
+pub fn unignored_entries(dir: &Path) -> HashSet<PathBuf> {
+    walker(dir, false)
+        .max_depth(Some(1))
+        .build()
+        .flatten()
+        .filter(|entry| entry.depth() == 1)
+        .map(|entry| entry.into_path())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn test_unignored_entries() {
+        let root = &std::env::temp_dir()
+            .join(format!("lapce-ignore-matcher-{}", std::process::id()));
+        fs::create_dir_all(root.join("target")).unwrap();
+        fs::write(root.join(".gitignore"), "target/\n*.log\n").unwrap();
+        fs::create_dir_all(root.join("src/nested")).unwrap();
+        fs::create_dir_all(root.join(".git")).unwrap();
+        fs::write(root.join("main.rs"), "").unwrap();
+        fs::write(root.join("debug.log"), "").unwrap();
+        fs::write(root.join("src/nested/trace.log"), "").unwrap();
+
+        let entries = unignored_entries(root);
+        assert!(entries.contains(&root.join("main.rs")));
+        assert!(entries.contains(&root.join(".gitignore")));
+        assert!(entries.contains(&root.join("src")));
+        assert!(!entries.contains(&root.join("target")));
+        assert!(!entries.contains(&root.join("debug.log")));
+        assert!(!entries.contains(&root.join(".git")));
+
+        // The rules of the directories above apply too
+        let nested = unignored_entries(&root.join("src/nested"));
+        assert!(nested.is_empty());
+        let files: Vec<_> = walker(root, true)
+            .build()
+            .flatten()
+            .map(|entry| entry.into_path())
+            .collect();
+        assert!(files.contains(&root.join("src/nested/trace.log")));
+        assert!(!files.contains(&root.join(".git")));
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
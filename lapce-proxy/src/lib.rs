@@ -1,9 +1,15 @@
 #![allow(clippy::manual_clamp)]
 
+pub mod archive;
 pub mod buffer;
 pub mod cli;
 pub mod dispatch;
+pub mod file_index;
+pub mod ignore_matcher;
+pub mod network;
 pub mod plugin;
+pub mod secrets;
+pub mod symbol_index;
 pub mod terminal;
 pub mod watcher;
 
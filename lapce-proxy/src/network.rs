@@ -0,0 +1,55 @@
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use lapce_rpc::proxy::NetworkSettings;
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use reqwest::{
+    blocking::{Client, Response},
+    Certificate, IntoUrl, Proxy,
+};
+
+/// The network settings of this process, shared by everything making requests
+static SETTINGS: Lazy<RwLock<NetworkSettings>> = Lazy::new(Default::default);
+
+const PEM_END: &str = "-----END CERTIFICATE-----";
+
+pub fn set_settings(settings: NetworkSettings) {
+    *SETTINGS.write() = settings;
+}
+
+/// A client making requests through the configured proxy and trusting the
+/// configured certificates, which fails in offline mode.
+pub fn client() -> Result<Client> {
+    let settings = SETTINGS.read().clone();
+    if settings.offline {
+        return Err(anyhow!("network requests are disabled in offline mode"));
+    }
+
+    let mut builder = Client::builder().user_agent("Lapce");
+    if let Some(proxy) = settings.proxy.as_ref() {
+        builder = builder.proxy(Proxy::all(proxy)?);
+    }
+    for path in &settings.ca_certificates {
+        for certificate in read_certificates(path)? {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+    Ok(builder.build()?)
+}
+
+/// Make a GET request with the configured client.
+pub fn get(url: impl IntoUrl) -> Result<Response> {
+    Ok(client()?.get(url).send()?)
+}
+
+/// Read all the certificates of a PEM file, which may be a bundle of several.
+fn read_certificates(path: &Path) -> Result<Vec<Certificate>> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| anyhow!("can't read certificates from {path:?}: {e}"))?;
+    content
+        .split_inclusive(PEM_END)
+        .filter(|pem| pem.contains(PEM_END))
+        .map(|pem| Ok(Certificate::from_pem(pem.trim().as_bytes())?))
+        .collect()
+}
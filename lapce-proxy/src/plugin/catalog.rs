@@ -10,7 +10,7 @@
 
 use lapce_rpc::{
     dap_types::{DapId, DapServer, SetBreakpointsResponse},
-    plugin::{PluginId, VoltID, VoltMetadata},
+    plugin::{PluginId, PluginPermissions, VoltID, VoltMetadata},
     proxy::ProxyResponse,
     style::LineStyle,
     RpcError,
@@ -26,7 +26,10 @@
 
 use super::{
     dap::{DapClient, DapRpcHandler},
-    psp::{ClonableCallback, PluginServerRpc, PluginServerRpcHandler, RpcCallback},
+    psp::{
+        ClonableCallback, PluginHandlerNotification, PluginServerRpc,
+        PluginServerRpcHandler, RpcCallback,
+    },
     wasi::{load_all_volts, start_volt},
     PluginCatalogNotification, PluginCatalogRpcHandler,
 };
@@ -38,6 +41,8 @@ pub struct PluginCatalog {
     plugins: HashMap<PluginId, PluginServerRpcHandler>,
     daps: HashMap<DapId, DapRpcHandler>,
     plugin_configurations: HashMap<String, HashMap<String, serde_json::Value>>,
+    /// The permissions granted to the plugins that were reviewed
+    plugin_permissions: HashMap<VoltID, PluginPermissions>,
     unactivated_volts: HashMap<VoltID, VoltMetadata>,
     open_files: HashMap<PathBuf, String>,
 }
@@ -47,6 +52,7 @@ pub fn new(
         workspace: Option<PathBuf>,
         disabled_volts: Vec<VoltID>,
         plugin_configurations: HashMap<String, HashMap<String, serde_json::Value>>,
+        plugin_permissions: Vec<(VoltID, PluginPermissions)>,
         plugin_rpc: PluginCatalogRpcHandler,
     ) -> Self {
         {
@@ -82,6 +88,7 @@ pub fn new(
             workspace,
             plugin_rpc: plugin_rpc.clone(),
             plugin_configurations,
+            plugin_permissions: plugin_permissions.into_iter().collect(),
             plugins: HashMap::new(),
             daps: HashMap::new(),
             unactivated_volts: HashMap::new(),
@@ -190,9 +197,22 @@ fn start_unactivated_volts(&mut self, to_be_activated: Vec<VoltID>) {
             if let Some(meta) = self.unactivated_volts.remove(id) {
                 let configurations =
                     self.plugin_configurations.get(&meta.name).cloned();
+                // The plugins installed before permissions existed keep what
+                // they could always do until they're reviewed
+                let permissions = self
+                    .plugin_permissions
+                    .get(id)
+                    .cloned()
+                    .unwrap_or_else(|| meta.requested_permissions());
                 let plugin_rpc = self.plugin_rpc.clone();
                 thread::spawn(move || {
-                    let _ = start_volt(workspace, configurations, plugin_rpc, meta);
+                    let _ = start_volt(
+                        workspace,
+                        configurations,
+                        permissions,
+                        plugin_rpc,
+                        meta,
+                    );
                 });
             }
         }
@@ -357,6 +377,13 @@ pub fn handle_notification(&mut self, notification: PluginCatalogNotification) {
                 self.check_unactivated_volts();
             }
             UpdatePluginConfigs(configs) => {
+                for (_, plugin) in self.plugins.iter() {
+                    plugin.handle_rpc(PluginServerRpc::Handler(
+                        PluginHandlerNotification::UpdateConfigurations(
+                            configs.get(&plugin.volt_id.name).cloned(),
+                        ),
+                    ));
+                }
                 self.plugin_configurations = configs;
             }
             PluginServerLoaded(plugin) => {
@@ -384,11 +411,22 @@ pub fn handle_notification(&mut self, notification: PluginCatalogNotification) {
                 let workspace = self.workspace.clone();
                 let configurations =
                     self.plugin_configurations.get(&volt.name).cloned();
+                // A new plugin gets no permissions until it's reviewed
+                let permissions = self
+                    .plugin_permissions
+                    .get(&volt.id())
+                    .cloned()
+                    .unwrap_or_default();
                 let catalog_rpc = self.plugin_rpc.clone();
                 let _ = catalog_rpc.stop_volt(volt.clone());
                 thread::spawn(move || {
-                    let _ =
-                        install_volt(catalog_rpc, workspace, configurations, volt);
+                    let _ = install_volt(
+                        catalog_rpc,
+                        workspace,
+                        configurations,
+                        permissions,
+                        volt,
+                    );
                 });
             }
             ReloadVolt(volt) => {
@@ -402,6 +440,38 @@ pub fn handle_notification(&mut self, notification: PluginCatalogNotification) {
                 }
                 let _ = self.plugin_rpc.unactivated_volts(vec![volt]);
             }
+            GrantVoltPermissions(volt, permissions) => {
+                let volt_id = volt.id();
+                self.plugin_permissions.insert(volt_id.clone(), permissions);
+                // A disabled plugin gets the permissions when it's enabled
+                if self.unactivated_volts.contains_key(&volt_id)
+                    || self.plugins.values().any(|p| p.volt_id == volt_id)
+                {
+                    self.handle_notification(ReloadVolt(volt));
+                }
+            }
+            PluginEvent(event) => {
+                for (_, plugin) in self.plugins.iter() {
+                    plugin.handle_rpc(PluginServerRpc::Handler(
+                        PluginHandlerNotification::Event(event.clone()),
+                    ));
+                }
+            }
+            ExecutePluginCommand {
+                plugin_id,
+                context,
+                f,
+            } => match self.plugins.get(&plugin_id) {
+                Some(plugin) => {
+                    plugin.handle_rpc(PluginServerRpc::Handler(
+                        PluginHandlerNotification::ExecuteCommand(context, f),
+                    ));
+                }
+                None => f.call(Err(RpcError {
+                    code: 0,
+                    message: "plugin doesn't exist".to_string(),
+                })),
+            },
             StopVolt(volt) => {
                 let volt_id = volt.id();
                 let ids: Vec<PluginId> = self.plugins.keys().cloned().collect();
@@ -1,6 +1,7 @@
 #[cfg(target_os = "windows")]
 use std::os::windows::process::CommandExt;
 use std::{
+    collections::HashMap,
     io::{BufRead, BufReader, BufWriter, Write},
     path::{Path, PathBuf},
     process::{self, Child, Command, Stdio},
@@ -12,10 +13,14 @@
 use crossbeam_channel::Sender;
 use jsonrpc_lite::{Id, Params};
 use lapce_core::meta;
-use lapce_rpc::{plugin::VoltID, style::LineStyle, RpcError};
+use lapce_rpc::{
+    plugin::{PluginPermissions, VoltID},
+    style::LineStyle,
+    RpcError,
+};
 use lapce_xi_rope::Rope;
 use lsp_types::{
-    notification::{Initialized, Notification},
+    notification::{DidChangeConfiguration, Initialized, Notification},
     request::{Initialize, Request},
     *,
 };
@@ -28,6 +33,7 @@
         handle_plugin_server_message, PluginHandlerNotification, PluginHostHandler,
         PluginServerHandler, PluginServerRpcHandler, RpcCallback,
     },
+    wasi::unflatten_map,
 };
 use crate::{buffer::Buffer, plugin::PluginCatalogRpcHandler};
 
@@ -54,6 +60,67 @@ pub enum LspRpc {
     },
 }
 
+/// The settings of a language server from the configurations of its plugin,
+/// under `lsp.initialization-options` and `lsp.settings`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LspSettings {
+    /// Merged into the options the plugin starts the server with
+    pub initialization_options: Option<Value>,
+    /// Sent with `workspace/didChangeConfiguration`, and what
+    /// `workspace/configuration` is answered from
+    pub settings: Option<Value>,
+}
+
+impl LspSettings {
+    pub fn from_configurations(
+        configurations: Option<&HashMap<String, Value>>,
+    ) -> Self {
+        let lsp = configurations
+            .map(unflatten_map)
+            .and_then(|configurations| configurations.get("lsp").cloned());
+        let section =
+            |name: &str| lsp.as_ref().and_then(|lsp| lsp.get(name)).cloned();
+        Self {
+            initialization_options: section("initialization-options"),
+            settings: section("settings"),
+        }
+    }
+
+    /// The value of a dotted section of the settings, or all of them without
+    /// one, as the server asks for it with `workspace/configuration`
+    pub fn section(&self, section: Option<&str>) -> Value {
+        let Some(settings) = self.settings.as_ref() else {
+            return Value::Null;
+        };
+        match section {
+            Some(section) => section
+                .split('.')
+                .try_fold(settings, |value, part| value.get(part))
+                .cloned()
+                .unwrap_or(Value::Null),
+            None => settings.clone(),
+        }
+    }
+}
+
+/// Merge the value into the base, objects key by key and anything else by
+/// replacing it
+fn merge_json(base: &mut Value, value: Value) {
+    match (base, value) {
+        (Value::Object(base), Value::Object(value)) => {
+            for (key, value) in value {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_json(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, value) => *base = value,
+    }
+}
+
 pub struct LspClient {
     plugin_rpc: PluginCatalogRpcHandler,
     server_rpc: PluginServerRpcHandler,
@@ -88,6 +155,15 @@ fn handle_handler_notification(
             InitializeResult(result) => {
                 self.host.server_capabilities = result.capabilities;
             }
+            UpdateConfigurations(configurations) => {
+                self.update_configurations(configurations);
+            }
+            Event(event) => {
+                self.host.handle_event(event);
+            }
+            ExecuteCommand(context, f) => {
+                self.host.execute_command(context, f);
+            }
             Shutdown => {
                 self.shutdown();
             }
@@ -169,6 +245,7 @@ fn new(
         server_uri: Url,
         args: Vec<String>,
         options: Option<Value>,
+        lsp_settings: LspSettings,
     ) -> Result<Self> {
         let server = match server_uri.scheme() {
             "file" => {
@@ -250,7 +327,7 @@ fn new(
             }
         });
 
-        let host = PluginHostHandler::new(
+        let mut host = PluginHostHandler::new(
             workspace.clone(),
             pwd,
             volt_id,
@@ -258,7 +335,11 @@ fn new(
             document_selector,
             server_rpc.clone(),
             plugin_rpc.clone(),
+            // A language server already runs outside of the sandbox, it has
+            // nothing to ask the host for
+            PluginPermissions::default(),
         );
+        host.lsp_settings = lsp_settings;
 
         Ok(Self {
             plugin_rpc,
@@ -281,6 +362,7 @@ pub fn start(
         server_uri: Url,
         args: Vec<String>,
         options: Option<Value>,
+        lsp_settings: LspSettings,
     ) -> Result<()> {
         let mut lsp = Self::new(
             plugin_rpc,
@@ -292,6 +374,7 @@ pub fn start(
             server_uri,
             args,
             options,
+            lsp_settings,
         )?;
         let rpc = lsp.server_rpc.clone();
         thread::spawn(move || {
@@ -305,11 +388,20 @@ fn initialize(&mut self) {
             .workspace
             .clone()
             .map(|p| Url::from_directory_path(p).unwrap());
+        let mut options = self.options.clone();
+        if let Some(overrides) =
+            self.host.lsp_settings.initialization_options.clone()
+        {
+            match options.as_mut() {
+                Some(options) => merge_json(options, overrides),
+                None => options = Some(overrides),
+            }
+        }
         #[allow(deprecated)]
         let params = InitializeParams {
             process_id: Some(process::id()),
             root_uri: root_uri.clone(),
-            initialization_options: self.options.clone(),
+            initialization_options: options,
             capabilities: client_capabilities(),
             trace: Some(TraceValue::Verbose),
             workspace_folders: root_uri.map(|uri| {
@@ -341,6 +433,9 @@ fn initialize(&mut self) {
                 None,
                 false,
             );
+            if let Some(settings) = self.host.lsp_settings.settings.clone() {
+                self.did_change_configuration(settings);
+            }
             if self
                 .plugin_rpc
                 .plugin_server_loaded(self.server_rpc.clone())
@@ -362,6 +457,32 @@ fn initialize(&mut self) {
         // );
     }
 
+    /// Take the new configurations of the plugin, and tell the server about
+    /// its settings if they changed. The initialization options only apply
+    /// when the server is started again.
+    fn update_configurations(
+        &mut self,
+        configurations: Option<HashMap<String, Value>>,
+    ) {
+        let lsp_settings = LspSettings::from_configurations(configurations.as_ref());
+        let changed = lsp_settings.settings != self.host.lsp_settings.settings;
+        let settings = lsp_settings.settings.clone();
+        self.host.lsp_settings = lsp_settings;
+        if changed {
+            self.did_change_configuration(settings.unwrap_or(Value::Null));
+        }
+    }
+
+    fn did_change_configuration(&self, settings: Value) {
+        self.server_rpc.server_notification(
+            DidChangeConfiguration::METHOD,
+            DidChangeConfigurationParams { settings },
+            None,
+            None,
+            false,
+        );
+    }
+
     fn shutdown(&mut self) {
         let _ = self.process.kill();
         let _ = self.process.wait();
@@ -485,3 +606,78 @@ pub fn get_change_for_sync_kind(
         _ => None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_lsp_settings() {
+        let configurations = HashMap::from([
+            ("diagnostics".to_string(), json!(true)),
+            (
+                "lsp.initialization-options".to_string(),
+                json!({ "cargo": { "features": ["serde"] } }),
+            ),
+            (
+                "lsp.settings".to_string(),
+                json!({ "python": { "venvPath": ".venv" } }),
+            ),
+        ]);
+        let settings = LspSettings::from_configurations(Some(&configurations));
+        assert_eq!(settings.section(Some("python.venvPath")), json!(".venv"));
+        assert_eq!(settings.section(Some("rust-analyzer")), Value::Null);
+
+        let mut options = json!({ "cargo": { "buildScripts": true } });
+        merge_json(&mut options, settings.initialization_options.unwrap());
+        assert_eq!(
+            options,
+            json!({ "cargo": { "buildScripts": true, "features": ["serde"] } })
+        );
+    }
+
+    #[test]
+    fn test_lsp_settings_from_dotted_keys() {
+        let configurations = HashMap::from([
+            (
+                "lsp.settings.rust-analyzer.checkOnSave.command".to_string(),
+                json!("clippy"),
+            ),
+            (
+                "lsp.settings.rust-analyzer.cargo.features".to_string(),
+                json!(["serde"]),
+            ),
+        ]);
+        let settings = LspSettings::from_configurations(Some(&configurations));
+        assert_eq!(settings.initialization_options, None);
+        assert_eq!(
+            settings.section(Some("rust-analyzer.checkOnSave")),
+            json!({ "command": "clippy" })
+        );
+        // Without a section the server is answered with all of them
+        assert_eq!(
+            settings.section(None),
+            json!({
+                "rust-analyzer": {
+                    "checkOnSave": { "command": "clippy" },
+                    "cargo": { "features": ["serde"] }
+                }
+            })
+        );
+
+        // A change of the other configurations of the plugin leaves the
+        // settings the same, so the server isn't told of it
+        let mut changed = configurations.clone();
+        changed.insert("diagnostics".to_string(), json!(false));
+        assert_eq!(
+            LspSettings::from_configurations(Some(&changed)).settings,
+            settings.settings
+        );
+
+        let settings = LspSettings::from_configurations(None);
+        assert_eq!(settings, LspSettings::default());
+        assert_eq!(settings.section(None), Value::Null);
+    }
+}
@@ -5,6 +5,7 @@
 pub mod wasi;
 
 use std::{
+    cmp::Reverse,
     collections::HashMap,
     fs,
     path::{Path, PathBuf},
@@ -21,9 +22,13 @@
 use flate2::read::GzDecoder;
 use lapce_core::directory::Directory;
 use lapce_rpc::{
-    core::CoreRpcHandler,
+    core::{CoreResponse, CoreRpcHandler},
     dap_types::{DapId, RunDebugConfig, SourceBreakpoint, ThreadId},
-    plugin::{PluginId, VoltInfo, VoltMetadata},
+    plugin::{
+        InlineSuggestionResult, PluginCommandContext, PluginCommandResult,
+        PluginEvent, PluginId, PluginPermissions, PluginProviders, ProviderKind,
+        ProviderRegistration, VoltInfo, VoltMetadata,
+    },
     proxy::ProxyRpcHandler,
     style::LineStyle,
     terminal::TermId,
@@ -35,9 +40,9 @@
         CodeActionRequest, CodeActionResolveRequest, Completion,
         DocumentSymbolRequest, Formatting, GotoDefinition, GotoTypeDefinition,
         GotoTypeDefinitionParams, GotoTypeDefinitionResponse, HoverRequest,
-        InlayHintRequest, PrepareRenameRequest, References, Rename, Request,
-        ResolveCompletionItem, SelectionRangeRequest, SemanticTokensFullRequest,
-        SignatureHelpRequest, WorkspaceSymbol,
+        InlayHintRequest, PrepareRenameRequest, RangeFormatting, References, Rename,
+        Request, ResolveCompletionItem, SelectionRangeRequest,
+        SemanticTokensFullRequest, SignatureHelpRequest, WorkspaceSymbol,
     },
     ClientCapabilities, CodeAction, CodeActionCapabilityResolveSupport,
     CodeActionClientCapabilities, CodeActionContext, CodeActionKind,
@@ -45,14 +50,16 @@
     CodeActionResponse, CompletionClientCapabilities, CompletionItem,
     CompletionItemCapability, CompletionItemCapabilityResolveSupport,
     CompletionParams, CompletionResponse, Diagnostic, DocumentFormattingParams,
-    DocumentSymbolParams, DocumentSymbolResponse, FormattingOptions, GotoCapability,
+    DocumentRangeFormattingParams, DocumentSymbolParams, DocumentSymbolResponse,
+    DynamicRegistrationClientCapabilities, FormattingOptions, GotoCapability,
     GotoDefinitionParams, GotoDefinitionResponse, Hover, HoverClientCapabilities,
-    HoverParams, InlayHint, InlayHintClientCapabilities, InlayHintParams, Location,
-    MarkupKind, MessageActionItemCapabilities, ParameterInformationSettings,
-    PartialResultParams, Position, PrepareRenameResponse, Range, ReferenceContext,
-    ReferenceParams, RenameParams, SelectionRange, SelectionRangeParams,
-    SemanticTokens, SemanticTokensClientCapabilities, SemanticTokensParams,
-    ShowMessageRequestClientCapabilities, SignatureHelp,
+    HoverContents, HoverParams, InlayHint, InlayHintClientCapabilities,
+    InlayHintParams, Location, MarkedString, MarkupContent, MarkupKind,
+    MessageActionItemCapabilities, ParameterInformationSettings,
+    PartialResultParams, Position, PrepareRenameResponse, PublishDiagnosticsParams,
+    Range, ReferenceContext, ReferenceParams, RenameParams, SelectionRange,
+    SelectionRangeParams, SemanticTokens, SemanticTokensClientCapabilities,
+    SemanticTokensParams, ShowMessageRequestClientCapabilities, SignatureHelp,
     SignatureHelpClientCapabilities, SignatureHelpParams,
     SignatureInformationSettings, SymbolInformation, TextDocumentClientCapabilities,
     TextDocumentIdentifier, TextDocumentItem, TextDocumentPositionParams,
@@ -69,10 +76,12 @@
 use self::{
     catalog::PluginCatalog,
     dap::DapRpcHandler,
-    psp::{ClonableCallback, PluginServerRpcHandler, RpcCallback},
+    psp::{
+        ClonableCallback, PluginServerRpcHandler, RpcCallback, INLINE_SUGGESTION,
+    },
     wasi::{load_volt, start_volt},
 };
-use crate::buffer::language_id_from_path;
+use crate::{buffer::language_id_from_path, network};
 
 pub type PluginName = String;
 
@@ -128,6 +137,15 @@ pub enum PluginCatalogNotification {
     StopVolt(VoltInfo),
     EnableVolt(VoltInfo),
     ReloadVolt(VoltMetadata),
+    GrantVoltPermissions(VoltMetadata, PluginPermissions),
+    /// An event of the editor for the plugins subscribed to it
+    PluginEvent(PluginEvent),
+    /// Run a command the plugin registered, in the context of the editor
+    ExecutePluginCommand {
+        plugin_id: PluginId,
+        context: PluginCommandContext,
+        f: Box<dyn RpcCallback<PluginCommandResult, RpcError>>,
+    },
     DapLoaded(DapRpcHandler),
     DapDisconnected(DapId),
     DapStart {
@@ -171,6 +189,12 @@ pub struct PluginCatalogRpcHandler {
     proxy_rpc: ProxyRpcHandler,
     plugin_tx: Sender<PluginCatalogRpc>,
     plugin_rx: Arc<Mutex<Option<Receiver<PluginCatalogRpc>>>>,
+    /// The providers registered by the plugins, to order their results
+    providers: Arc<Mutex<PluginProviders>>,
+    /// The diagnostics each plugin published for the documents, which are
+    /// shown together
+    #[allow(clippy::type_complexity)]
+    diagnostics: Arc<Mutex<HashMap<Url, HashMap<PluginId, Vec<Diagnostic>>>>>,
     #[allow(dead_code)]
     id: Arc<AtomicU64>,
     #[allow(dead_code, clippy::type_complexity)]
@@ -185,6 +209,8 @@ pub fn new(core_rpc: CoreRpcHandler, proxy_rpc: ProxyRpcHandler) -> Self {
             proxy_rpc,
             plugin_tx,
             plugin_rx: Arc::new(Mutex::new(Some(plugin_rx))),
+            providers: Arc::new(Mutex::new(PluginProviders::default())),
+            diagnostics: Arc::new(Mutex::new(HashMap::new())),
             id: Arc::new(AtomicU64::new(0)),
             pending: Arc::new(Mutex::new(HashMap::new())),
         }
@@ -297,6 +323,92 @@ fn catalog_notification(
         Ok(())
     }
 
+    /// Apply the edits of a plugin in the editor, all together or not at all
+    pub fn apply_workspace_edit(
+        &self,
+        edit: WorkspaceEdit,
+    ) -> Result<CoreResponse, RpcError> {
+        self.core_rpc.apply_workspace_edit(edit)
+    }
+
+    /// Keep the provider a plugin registered, and let the core know about it
+    pub fn provider_registered(
+        &self,
+        plugin_id: PluginId,
+        registration: ProviderRegistration,
+    ) {
+        self.providers
+            .lock()
+            .register(plugin_id, registration.clone());
+        self.core_rpc.provider_registered(plugin_id, registration);
+    }
+
+    /// Publish the diagnostics of the document from the plugin along with the
+    /// ones the other plugins published for it
+    pub fn publish_diagnostics(
+        &self,
+        plugin_id: PluginId,
+        params: PublishDiagnosticsParams,
+    ) {
+        let diagnostics = {
+            let mut diagnostics = self.diagnostics.lock();
+            let document = diagnostics.entry(params.uri.clone()).or_default();
+            document.insert(plugin_id, params.diagnostics);
+            document.values().flatten().cloned().collect()
+        };
+        self.core_rpc.publish_diagnostics(PublishDiagnosticsParams {
+            uri: params.uri,
+            diagnostics,
+            version: params.version,
+        });
+    }
+
+    /// Send the request to all the plugins, and call back with the responses of
+    /// all the ones which succeeded once every plugin has responded
+    fn send_request_to_all_plugins_collect<P, Resp, F>(
+        &self,
+        method: &'static str,
+        params: P,
+        language_id: Option<String>,
+        path: Option<PathBuf>,
+        cb: F,
+    ) where
+        P: Serialize,
+        Resp: DeserializeOwned + Send + 'static,
+        F: FnOnce(Result<Vec<(PluginId, Resp)>, RpcError>) + Clone + Send + 'static,
+    {
+        let request_sent = Arc::new(AtomicUsize::new(0));
+        let received = Arc::new(AtomicUsize::new(0));
+        let responses = Arc::new(Mutex::new(Vec::new()));
+        self.send_request(
+            None,
+            Some(request_sent.clone()),
+            method,
+            params,
+            language_id,
+            path,
+            move |plugin_id, result| {
+                if let Ok(value) = result {
+                    if let Ok(resp) = serde_json::from_value::<Resp>(value) {
+                        responses.lock().push((plugin_id, resp));
+                    }
+                }
+                let rx = received.fetch_add(1, Ordering::Relaxed) + 1;
+                if request_sent.load(Ordering::Acquire) == rx {
+                    let responses = std::mem::take(&mut *responses.lock());
+                    if responses.is_empty() {
+                        cb(Err(RpcError {
+                            code: 0,
+                            message: "no plugin responded".to_string(),
+                        }))
+                    } else {
+                        cb(Ok(responses))
+                    }
+                }
+            },
+        );
+    }
+
     fn send_request_to_all_plugins<P, Resp>(
         &self,
         method: &'static str,
@@ -555,6 +667,38 @@ pub fn get_code_actions(
         );
     }
 
+    pub fn get_organize_imports(
+        &self,
+        path: &Path,
+        range: Range,
+        cb: impl FnOnce(PluginId, Result<CodeActionResponse, RpcError>)
+            + Clone
+            + Send
+            + 'static,
+    ) {
+        let uri = Url::from_file_path(path).unwrap();
+        let method = CodeActionRequest::METHOD;
+        let params = CodeActionParams {
+            text_document: TextDocumentIdentifier { uri },
+            range,
+            context: CodeActionContext {
+                diagnostics: Vec::new(),
+                only: Some(vec![CodeActionKind::SOURCE_ORGANIZE_IMPORTS]),
+            },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+        let language_id =
+            Some(language_id_from_path(path).unwrap_or("").to_string());
+        self.send_request_to_all_plugins(
+            method,
+            params,
+            language_id,
+            Some(path.to_path_buf()),
+            cb,
+        );
+    }
+
     pub fn get_inlay_hints(
         &self,
         path: &Path,
@@ -628,6 +772,7 @@ pub fn get_workspace_symbols(
     pub fn get_document_formatting(
         &self,
         path: &Path,
+        options: FormattingOptions,
         cb: impl FnOnce(PluginId, Result<Vec<TextEdit>, RpcError>)
             + Clone
             + Send
@@ -637,11 +782,36 @@ pub fn get_document_formatting(
         let method = Formatting::METHOD;
         let params = DocumentFormattingParams {
             text_document: TextDocumentIdentifier { uri },
-            options: FormattingOptions {
-                tab_size: 4,
-                insert_spaces: true,
-                ..Default::default()
-            },
+            options,
+            work_done_progress_params: WorkDoneProgressParams::default(),
+        };
+        let language_id =
+            Some(language_id_from_path(path).unwrap_or("").to_string());
+        self.send_request_to_all_plugins(
+            method,
+            params,
+            language_id,
+            Some(path.to_path_buf()),
+            cb,
+        );
+    }
+
+    pub fn get_document_range_formatting(
+        &self,
+        path: &Path,
+        range: Range,
+        options: FormattingOptions,
+        cb: impl FnOnce(PluginId, Result<Vec<TextEdit>, RpcError>)
+            + Clone
+            + Send
+            + 'static,
+    ) {
+        let uri = Url::from_file_path(path).unwrap();
+        let method = RangeFormatting::METHOD;
+        let params = DocumentRangeFormattingParams {
+            text_document: TextDocumentIdentifier { uri },
+            range,
+            options,
             work_done_progress_params: WorkDoneProgressParams::default(),
         };
         let language_id =
@@ -770,7 +940,7 @@ pub fn hover(
         &self,
         path: &Path,
         position: Position,
-        cb: impl FnOnce(PluginId, Result<Hover, RpcError>) + Clone + Send + 'static,
+        cb: impl FnOnce(Result<Hover, RpcError>) + Clone + Send + 'static,
     ) {
         let uri = Url::from_file_path(path).unwrap();
         let method = HoverRequest::METHOD;
@@ -784,12 +954,84 @@ pub fn hover(
         let language_id =
             Some(language_id_from_path(path).unwrap_or("").to_string());
 
-        self.send_request_to_all_plugins(
+        // The hovers of all the plugins are shown together, the ones of the
+        // plugins with a higher priority first
+        let providers = self.providers.clone();
+        let hover_language_id = language_id.clone();
+        self.send_request_to_all_plugins_collect(
             method,
             params,
             language_id,
             Some(path.to_path_buf()),
-            cb,
+            move |result| {
+                let result = result.map(|mut hovers: Vec<(PluginId, Hover)>| {
+                    let providers = providers.lock();
+                    hovers.sort_by_key(|(plugin_id, _)| {
+                        Reverse(providers.priority(
+                            plugin_id,
+                            ProviderKind::Hover,
+                            hover_language_id.as_deref(),
+                        ))
+                    });
+                    if hovers.len() == 1 {
+                        hovers.remove(0).1
+                    } else {
+                        merge_hovers(hovers.into_iter().map(|(_, hover)| hover))
+                    }
+                });
+                cb(result);
+            },
+        );
+    }
+
+    /// The text the plugin with the highest priority suggests inserting at
+    /// the position
+    pub fn inline_suggestion(
+        &self,
+        path: &Path,
+        position: Position,
+        cb: impl FnOnce(Result<Option<String>, RpcError>) + Clone + Send + 'static,
+    ) {
+        let uri = Url::from_file_path(path).unwrap();
+        let params = TextDocumentPositionParams {
+            text_document: TextDocumentIdentifier { uri },
+            position,
+        };
+        let language_id =
+            Some(language_id_from_path(path).unwrap_or("").to_string());
+
+        let providers = self.providers.clone();
+        let suggestion_language_id = language_id.clone();
+        self.send_request_to_all_plugins_collect(
+            INLINE_SUGGESTION,
+            params,
+            language_id,
+            Some(path.to_path_buf()),
+            move |result| {
+                let result = result.map(
+                    |suggestions: Vec<(
+                        PluginId,
+                        Option<InlineSuggestionResult>,
+                    )>| {
+                        let providers = providers.lock();
+                        suggestions
+                            .into_iter()
+                            .filter_map(|(plugin_id, suggestion)| {
+                                Some((plugin_id, suggestion?.text))
+                            })
+                            .filter(|(_, text)| !text.is_empty())
+                            .max_by_key(|(plugin_id, _)| {
+                                providers.priority(
+                                    plugin_id,
+                                    ProviderKind::InlineSuggestion,
+                                    suggestion_language_id.as_deref(),
+                                )
+                            })
+                            .map(|(_, text)| text)
+                    },
+                );
+                cb(result);
+            },
         );
     }
 
@@ -1000,6 +1242,34 @@ pub fn enable_volt(&self, volt: VoltInfo) -> Result<()> {
         self.catalog_notification(PluginCatalogNotification::EnableVolt(volt))
     }
 
+    pub fn grant_volt_permissions(
+        &self,
+        volt: VoltMetadata,
+        permissions: PluginPermissions,
+    ) -> Result<()> {
+        self.catalog_notification(PluginCatalogNotification::GrantVoltPermissions(
+            volt,
+            permissions,
+        ))
+    }
+
+    pub fn plugin_event(&self, event: PluginEvent) -> Result<()> {
+        self.catalog_notification(PluginCatalogNotification::PluginEvent(event))
+    }
+
+    pub fn execute_plugin_command(
+        &self,
+        plugin_id: PluginId,
+        context: PluginCommandContext,
+        f: impl FnOnce(Result<PluginCommandResult, RpcError>) + Send + 'static,
+    ) -> Result<()> {
+        self.catalog_notification(PluginCatalogNotification::ExecutePluginCommand {
+            plugin_id,
+            context,
+            f: Box::new(f),
+        })
+    }
+
     pub fn dap_disconnected(&self, dap_id: DapId) -> Result<()> {
         self.catalog_notification(PluginCatalogNotification::DapDisconnected(dap_id))
     }
@@ -1115,7 +1385,7 @@ pub fn download_volt(volt: &VoltInfo) -> Result<VoltMetadata> {
         volt.author, volt.name, volt.version
     );
 
-    let resp = reqwest::blocking::get(url)?;
+    let resp = network::get(url)?;
     if !resp.status().is_success() {
         return Err(anyhow!("can't download plugin"));
     }
@@ -1123,7 +1393,7 @@ pub fn download_volt(volt: &VoltInfo) -> Result<VoltMetadata> {
     // this is the s3 url
     let url = resp.text()?;
 
-    let mut resp = reqwest::blocking::get(url)?;
+    let mut resp = network::get(url)?;
     if !resp.status().is_success() {
         return Err(anyhow!("can't download plugin"));
     }
@@ -1159,6 +1429,7 @@ pub fn install_volt(
     catalog_rpc: PluginCatalogRpcHandler,
     workspace: Option<PathBuf>,
     configurations: Option<HashMap<String, serde_json::Value>>,
+    permissions: PluginPermissions,
     volt: VoltInfo,
 ) -> Result<()> {
     let download_volt_result = download_volt(&volt);
@@ -1171,7 +1442,13 @@ pub fn install_volt(
     let local_catalog_rpc = catalog_rpc.clone();
     let local_meta = meta.clone();
 
-    let _ = start_volt(workspace, configurations, local_catalog_rpc, local_meta);
+    let _ = start_volt(
+        workspace,
+        configurations,
+        permissions,
+        local_catalog_rpc,
+        local_meta,
+    );
     let icon = volt_icon(&meta);
     catalog_rpc.core_rpc.volt_installed(meta, icon);
     Ok(())
@@ -1202,6 +1479,40 @@ pub fn remove_volt(
     Ok(())
 }
 
+/// The hovers of several plugins as one, keeping the range of the first
+fn merge_hovers(hovers: impl Iterator<Item = Hover>) -> Hover {
+    let mut hovers = hovers.peekable();
+    let range = hovers.peek().and_then(|hover| hover.range);
+    let value = hovers
+        .map(|hover| match hover.contents {
+            HoverContents::Scalar(s) => marked_string_markdown(s),
+            HoverContents::Array(a) => a
+                .into_iter()
+                .map(marked_string_markdown)
+                .collect::<Vec<_>>()
+                .join("\n\n"),
+            HoverContents::Markup(m) => m.value,
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n---\n\n");
+    Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value,
+        }),
+        range,
+    }
+}
+
+fn marked_string_markdown(s: MarkedString) -> String {
+    match s {
+        MarkedString::String(s) => s,
+        MarkedString::LanguageString(s) => {
+            format!("```{}\n{}\n```", s.language, s.value)
+        }
+    }
+}
+
 fn client_capabilities() -> ClientCapabilities {
     ClientCapabilities {
         text_document: Some(TextDocumentClientCapabilities {
@@ -1295,7 +1606,11 @@ fn client_capabilities() -> ClientCapabilities {
             symbol: Some(WorkspaceSymbolClientCapabilities {
                 ..Default::default()
             }),
-            configuration: Some(false),
+            configuration: Some(true),
+            apply_edit: Some(true),
+            did_change_configuration: Some(DynamicRegistrationClientCapabilities {
+                dynamic_registration: Some(false),
+            }),
             ..Default::default()
         }),
         ..Default::default()
@@ -1,11 +1,12 @@
 use std::{
-    collections::HashMap,
-    path::{Path, PathBuf},
+    collections::{HashMap, HashSet},
+    path::{Component, Path, PathBuf},
     sync::{
         atomic::{AtomicU64, Ordering},
         Arc,
     },
     thread,
+    time::{Duration, Instant},
 };
 
 use anyhow::{anyhow, Result};
@@ -17,47 +18,131 @@
     encoding::offset_utf16_to_utf8,
 };
 use lapce_rpc::{
-    plugin::{PluginId, VoltID},
+    core::CoreResponse,
+    plugin::{
+        EditorCommand, PluginCommand, PluginCommandContext, PluginCommandResult,
+        PluginDecorations, PluginEvent, PluginEventKind, PluginId, PluginPanel,
+        PluginPermissions, PluginStatusItem, ProviderKind, ProviderRegistration,
+        VoltID, PROCESS_COMMANDS,
+    },
+    proxy::ProxyResponse,
     style::{LineStyle, Style},
-    RpcError,
+    timer, RpcError,
 };
 use lapce_xi_rope::{Rope, RopeDelta};
 use lsp_types::{
     notification::{
-        DidChangeTextDocument, DidOpenTextDocument, DidSaveTextDocument,
+        Cancel, DidChangeTextDocument, DidOpenTextDocument, DidSaveTextDocument,
         Initialized, LogMessage, Notification, Progress, PublishDiagnostics,
         ShowMessage,
     },
     request::{
-        CodeActionRequest, CodeActionResolveRequest, Completion,
+        ApplyWorkspaceEdit, CodeActionRequest, CodeActionResolveRequest, Completion,
         DocumentSymbolRequest, Formatting, GotoDefinition, GotoTypeDefinition,
         HoverRequest, Initialize, InlayHintRequest, PrepareRenameRequest,
-        References, RegisterCapability, Rename, ResolveCompletionItem,
-        SelectionRangeRequest, SemanticTokensFullRequest, SignatureHelpRequest,
-        WorkDoneProgressCreate, WorkspaceSymbol,
+        RangeFormatting, References, RegisterCapability, Rename,
+        ResolveCompletionItem, SelectionRangeRequest, SemanticTokensFullRequest,
+        SignatureHelpRequest, WorkDoneProgressCreate, WorkspaceConfiguration,
+        WorkspaceSymbol,
     },
-    CodeActionProviderCapability, DidChangeTextDocumentParams,
+    ApplyWorkspaceEditParams, ApplyWorkspaceEditResponse,
+    CodeActionProviderCapability, ConfigurationParams, DidChangeTextDocumentParams,
     DidSaveTextDocumentParams, DocumentSelector, HoverProviderCapability,
-    InitializeResult, LogMessageParams, OneOf, ProgressParams,
+    InitializeResult, LogMessageParams, MessageType, OneOf, ProgressParams,
     PublishDiagnosticsParams, Range, Registration, RegistrationParams,
     SemanticTokens, SemanticTokensLegend, SemanticTokensServerCapabilities,
     ServerCapabilities, ShowMessageParams, TextDocumentContentChangeEvent,
     TextDocumentIdentifier, TextDocumentSaveRegistrationOptions,
     TextDocumentSyncCapability, TextDocumentSyncKind, TextDocumentSyncSaveOptions,
-    VersionedTextDocumentIdentifier,
+    Url, VersionedTextDocumentIdentifier,
 };
 use parking_lot::Mutex;
 use psp_types::{
     ExecuteProcess, ExecuteProcessParams, ExecuteProcessResult, Request,
     StartLspServer, StartLspServerParams,
 };
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 use super::{
-    lsp::{DocumentFilter, LspClient},
+    lsp::{DocumentFilter, LspClient, LspSettings},
     PluginCatalogRpcHandler,
 };
+use crate::secrets::{self, SecretKey};
+
+/// The requests a plugin can make to keep its secrets, such as tokens, in the
+/// keychain of the platform instead of its configuration
+const GET_SECRET: &str = "lapce/getSecret";
+const STORE_SECRET: &str = "lapce/storeSecret";
+const DELETE_SECRET: &str = "lapce/deleteSecret";
+
+/// The request of a plugin to provide completions, hovers or diagnostics for
+/// some languages itself, merged with the results of the language servers
+const REGISTER_PROVIDER: &str = "lapce/registerProvider";
+
+/// The request for the text a plugin suggests inserting at the cursor, sent
+/// to the plugins that registered to provide inline suggestions
+pub const INLINE_SUGGESTION: &str = "lapce/inlineSuggestion";
+
+/// The request of a plugin to run a command of the editor, by its name in the
+/// keymaps
+const RUN_COMMAND: &str = "lapce/runCommand";
+/// The request of a plugin for the text of a document open in the editor,
+/// with the changes that aren't saved yet
+const READ_BUFFER: &str = "lapce/readBuffer";
+/// The request of a plugin to be sent the events of some kinds, which come in
+/// `lapce/event` notifications
+const SUBSCRIBE: &str = "lapce/subscribe";
+const EVENT: &str = "lapce/event";
+/// The request of a plugin to add a command to the palette and the keymaps,
+/// which is run with a `lapce/executeCommand` request to the plugin
+const REGISTER_COMMAND: &str = "lapce/registerCommand";
+const EXECUTE_COMMAND: &str = "lapce/executeCommand";
+/// The requests of a plugin to show or replace a panel or a segment of the
+/// status bar, and to remove it, by its id
+const SET_PANEL: &str = "lapce/setPanel";
+const REMOVE_PANEL: &str = "lapce/removePanel";
+const SET_STATUS_ITEM: &str = "lapce/setStatusItem";
+const REMOVE_STATUS_ITEM: &str = "lapce/removeStatusItem";
+/// The request of a plugin to replace its decorations of a key on a document
+const SET_DECORATIONS: &str = "lapce/setDecorations";
+
+/// Whether the command, by its name in the keymaps, runs programs or has the
+/// system open something. The editor only runs its own commands for plugins,
+/// so the other names run nothing.
+fn runs_process(command: &str) -> bool {
+    let name = command.split(':').next().unwrap_or_default();
+    PROCESS_COMMANDS.contains(&name)
+}
+
+#[derive(Deserialize)]
+struct SecretParams {
+    key: String,
+    #[serde(default)]
+    value: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RunCommandParams {
+    command: String,
+    #[serde(default)]
+    data: Option<Value>,
+}
+
+#[derive(Deserialize)]
+struct ReadBufferParams {
+    uri: Url,
+}
+
+#[derive(Deserialize)]
+struct SubscribeParams {
+    events: Vec<PluginEventKind>,
+}
+
+#[derive(Deserialize)]
+struct RemoveViewParams {
+    id: String,
+}
 
 pub enum ResponseHandler<Resp, Error> {
     Chan(Sender<Result<Resp, Error>>),
@@ -100,6 +185,15 @@ fn call(self: Box<F>, result: Result<Resp, Error>) {
 pub enum PluginHandlerNotification {
     Initialize,
     InitializeResult(InitializeResult),
+    /// The configurations of the plugin changed in the settings
+    UpdateConfigurations(Option<HashMap<String, Value>>),
+    /// An event of the editor, sent on if the plugin subscribed to it
+    Event(PluginEvent),
+    /// Run a command the plugin registered
+    ExecuteCommand(
+        PluginCommandContext,
+        Box<dyn RpcCallback<PluginCommandResult, RpcError>>,
+    ),
     Shutdown,
 }
 
@@ -167,6 +261,22 @@ pub struct PluginServerRpcHandler {
     server_pending: Arc<Mutex<HashMap<Id, ResponseHandler<Value, RpcError>>>>,
 }
 
+/// How long a request of the method is waited on before it's cancelled, for
+/// the ones the editor shouldn't be held up by a slow server on. The ones
+/// answering typing get less time than the ones decorating the document.
+fn request_timeout(method: &str) -> Option<Duration> {
+    match method {
+        Completion::METHOD | HoverRequest::METHOD | SignatureHelpRequest::METHOD => {
+            Some(Duration::from_secs(5))
+        }
+        CodeActionRequest::METHOD
+        | InlayHintRequest::METHOD
+        | SemanticTokensFullRequest::METHOD
+        | DocumentSymbolRequest::METHOD => Some(Duration::from_secs(10)),
+        _ => None,
+    }
+}
+
 pub trait PluginServerHandler {
     fn document_supported(
         &mut self,
@@ -174,6 +284,16 @@ fn document_supported(
         path: Option<&Path>,
     ) -> bool;
     fn method_registered(&mut self, method: &'static str) -> bool;
+    /// Whether the request or notification of the method about the document
+    /// is to be sent to the server
+    fn request_supported(
+        &mut self,
+        method: &'static str,
+        language_id: Option<&str>,
+        path: Option<&Path>,
+    ) -> bool {
+        self.document_supported(language_id, path) && self.method_registered(method)
+    }
     fn handle_host_notification(&mut self, method: String, params: Params);
     fn handle_host_request(
         &mut self,
@@ -250,6 +370,31 @@ fn send_server_request(
             let mut pending = self.server_pending.lock();
             pending.insert(id.clone(), rh);
         }
+        if let Some(timeout) = request_timeout(method) {
+            let server_pending = self.server_pending.clone();
+            let io_tx = self.io_tx.clone();
+            let id = id.clone();
+            let method = method.to_string();
+            timer::schedule(Instant::now() + timeout, move || {
+                let handler = { server_pending.lock().remove(&id) };
+                if let Some(handler) = handler {
+                    // The server is still busy with it, so it's cancelled
+                    // rather than left to answer a request nobody waits on
+                    let params = serde_json::json!({ "id": id });
+                    let _ = io_tx.send(JsonRpc::notification_with_params(
+                        Cancel::METHOD,
+                        Params::from(params),
+                    ));
+                    handler.invoke(Err(RpcError {
+                        code: 0,
+                        message: format!(
+                            "{method} timed out after {}s",
+                            timeout.as_secs()
+                        ),
+                    }));
+                }
+            });
+        }
         let msg = JsonRpc::request_with_params(id, method, params);
         self.send_server_rpc(msg);
     }
@@ -391,10 +536,11 @@ pub fn mainloop<H>(&self, handler: &mut H)
                     path,
                     rh,
                 } => {
-                    if handler
-                        .document_supported(language_id.as_deref(), path.as_deref())
-                        && handler.method_registered(method)
-                    {
+                    if handler.request_supported(
+                        method,
+                        language_id.as_deref(),
+                        path.as_deref(),
+                    ) {
                         self.send_server_request(id, method, params, rh);
                     } else {
                         rh.invoke(Err(RpcError {
@@ -409,10 +555,11 @@ pub fn mainloop<H>(&self, handler: &mut H)
                     language_id,
                     path,
                 } => {
-                    if handler
-                        .document_supported(language_id.as_deref(), path.as_deref())
-                        && handler.method_registered(method)
-                    {
+                    if handler.request_supported(
+                        method,
+                        language_id.as_deref(),
+                        path.as_deref(),
+                    ) {
                         self.send_server_notification(method, params);
                     }
                 }
@@ -551,9 +698,19 @@ pub struct PluginHostHandler {
     pub server_rpc: PluginServerRpcHandler,
     pub server_capabilities: ServerCapabilities,
     server_registrations: ServerRegistrations,
+    providers: Vec<ProviderRegistration>,
+    /// What the plugin is allowed to ask the host for
+    permissions: PluginPermissions,
+    /// The language server settings from the configurations of the plugin
+    pub(crate) lsp_settings: LspSettings,
+    /// The kinds of the events the plugin subscribed to
+    subscribed_events: HashSet<PluginEventKind>,
+    /// The names of the commands the plugin registered
+    commands: HashSet<String>,
 }
 
 impl PluginHostHandler {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         workspace: Option<PathBuf>,
         pwd: Option<PathBuf>,
@@ -562,6 +719,7 @@ pub fn new(
         document_selector: DocumentSelector,
         server_rpc: PluginServerRpcHandler,
         catalog_rpc: PluginCatalogRpcHandler,
+        permissions: PluginPermissions,
     ) -> Self {
         let document_selector = document_selector
             .iter()
@@ -577,6 +735,11 @@ pub fn new(
             server_rpc,
             server_capabilities: ServerCapabilities::default(),
             server_registrations: ServerRegistrations::default(),
+            providers: Vec::new(),
+            permissions,
+            lsp_settings: LspSettings::default(),
+            subscribed_events: HashSet::new(),
+            commands: HashSet::new(),
         }
     }
 
@@ -699,6 +862,15 @@ pub fn method_registered(&mut self, method: &'static str) -> bool {
                     OneOf::Right(_) => true,
                 })
                 .unwrap_or(false),
+            RangeFormatting::METHOD => self
+                .server_capabilities
+                .document_range_formatting_provider
+                .as_ref()
+                .map(|f| match f {
+                    OneOf::Left(is_capable) => *is_capable,
+                    OneOf::Right(_) => true,
+                })
+                .unwrap_or(false),
             SemanticTokensFullRequest::METHOD => {
                 self.server_capabilities.semantic_tokens_provider.is_some()
             }
@@ -725,6 +897,36 @@ pub fn method_registered(&mut self, method: &'static str) -> bool {
         }
     }
 
+    /// Whether the plugin registered a provider for the language that handles
+    /// the method, where every provider is sent the changes of the documents
+    /// of its languages
+    fn provider_registered(&self, method: &str, language_id: Option<&str>) -> bool {
+        let kind = match method {
+            Completion::METHOD => Some(ProviderKind::Completion),
+            HoverRequest::METHOD => Some(ProviderKind::Hover),
+            INLINE_SUGGESTION => Some(ProviderKind::InlineSuggestion),
+            DidOpenTextDocument::METHOD
+            | DidChangeTextDocument::METHOD
+            | DidSaveTextDocument::METHOD => None,
+            _ => return false,
+        };
+        self.providers.iter().any(|p| {
+            kind.map(|kind| p.kind == kind).unwrap_or(true)
+                && p.supports(language_id)
+        })
+    }
+
+    pub fn request_supported(
+        &mut self,
+        method: &'static str,
+        language_id: Option<&str>,
+        path: Option<&Path>,
+    ) -> bool {
+        (self.document_supported(language_id, path)
+            && self.method_registered(method))
+            || self.provider_registered(method, language_id)
+    }
+
     fn check_save_capability(&self, language_id: &str, path: &Path) -> (bool, bool) {
         if self.document_supported(Some(language_id), Some(path)) {
             let (should_send, include_text) = self
@@ -760,6 +962,10 @@ fn check_save_capability(&self, language_id: &str, path: &Path) -> (bool, bool)
             }
         }
 
+        if self.provider_registered(DidSaveTextDocument::METHOD, Some(language_id)) {
+            return (true, true);
+        }
+
         (false, false)
     }
 
@@ -821,13 +1027,52 @@ pub fn process_request(
     ) -> Result<Value> {
         match method.as_str() {
             WorkDoneProgressCreate::METHOD => Ok(Value::Null),
+            WorkspaceConfiguration::METHOD => {
+                let params: ConfigurationParams =
+                    serde_json::from_value(serde_json::to_value(params)?)?;
+                Ok(Value::Array(
+                    params
+                        .items
+                        .iter()
+                        .map(|item| {
+                            self.lsp_settings.section(item.section.as_deref())
+                        })
+                        .collect(),
+                ))
+            }
             RegisterCapability::METHOD => {
                 let params: RegistrationParams =
                     serde_json::from_value(serde_json::to_value(params)?)?;
                 self.register_capabilities(params.registrations);
                 Ok(Value::Null)
             }
+            ApplyWorkspaceEdit::METHOD => {
+                // The edits are made to the documents in the editor, where
+                // they can be undone, rather than to the files
+                let params: ApplyWorkspaceEditParams =
+                    serde_json::from_value(serde_json::to_value(params)?)?;
+                let response =
+                    match self.catalog_rpc.apply_workspace_edit(params.edit) {
+                        Ok(CoreResponse::ApplyWorkspaceEditResponse {
+                            applied,
+                            failure_reason,
+                        }) => ApplyWorkspaceEditResponse {
+                            applied,
+                            failure_reason,
+                            failed_change: None,
+                        },
+                        Err(err) => ApplyWorkspaceEditResponse {
+                            applied: false,
+                            failure_reason: Some(err.message),
+                            failed_change: None,
+                        },
+                    };
+                Ok(serde_json::to_value(response)?)
+            }
             ExecuteProcess::METHOD => {
+                if !self.permissions.process {
+                    return Err(anyhow!("plugin isn't allowed to run programs"));
+                }
                 let params: ExecuteProcessParams =
                     serde_json::from_value(serde_json::to_value(params)?)?;
                 let output = std::process::Command::new(params.program)
@@ -839,10 +1084,238 @@ pub fn process_request(
                     stderr: Some(output.stderr),
                 })?)
             }
+            REGISTER_PROVIDER => {
+                let registration: ProviderRegistration =
+                    serde_json::from_value(serde_json::to_value(params)?)?;
+                self.providers.push(registration.clone());
+                self.catalog_rpc
+                    .provider_registered(self.server_rpc.plugin_id, registration);
+                Ok(Value::Null)
+            }
+            GET_SECRET | STORE_SECRET | DELETE_SECRET => {
+                let params: SecretParams =
+                    serde_json::from_value(serde_json::to_value(params)?)?;
+                let key = SecretKey {
+                    volt: self.volt_id.to_string(),
+                    key: params.key,
+                };
+                match method.as_str() {
+                    GET_SECRET => Ok(serde_json::to_value(secrets::get(&key)?)?),
+                    STORE_SECRET => {
+                        let value = params
+                            .value
+                            .ok_or_else(|| anyhow!("no secret to store"))?;
+                        secrets::set(&key, &value)?;
+                        Ok(Value::Null)
+                    }
+                    _ => {
+                        secrets::delete(&key)?;
+                        Ok(Value::Null)
+                    }
+                }
+            }
+            RUN_COMMAND => {
+                let params: RunCommandParams =
+                    serde_json::from_value(serde_json::to_value(params)?)?;
+                if !self.permissions.process && runs_process(&params.command) {
+                    return Err(anyhow!("plugin isn't allowed to run programs"));
+                }
+                self.catalog_rpc
+                    .core_rpc
+                    .run_command(params.command, params.data);
+                Ok(Value::Null)
+            }
+            READ_BUFFER => {
+                let params: ReadBufferParams =
+                    serde_json::from_value(serde_json::to_value(params)?)?;
+                let path = params
+                    .uri
+                    .to_file_path()
+                    .map_err(|_| anyhow!("{} isn't a file", params.uri))?;
+                let item = match self
+                    .catalog_rpc
+                    .proxy_rpc
+                    .get_open_file_content(path.clone())
+                {
+                    Ok(ProxyResponse::GetOpenFileContentResponse { item }) => item,
+                    Ok(_) => return Err(anyhow!("unexpected response")),
+                    Err(err) => return Err(anyhow!(err.message)),
+                };
+                // Only the documents the plugin is sent anyway can be read
+                if !self.document_supported(Some(&item.language_id), Some(&path)) {
+                    return Err(anyhow!("the plugin isn't for {}", params.uri));
+                }
+                Ok(serde_json::to_value(item)?)
+            }
+            SUBSCRIBE => {
+                let params: SubscribeParams =
+                    serde_json::from_value(serde_json::to_value(params)?)?;
+                self.subscribed_events.extend(params.events);
+                Ok(Value::Null)
+            }
+            REGISTER_COMMAND => {
+                let command: PluginCommand =
+                    serde_json::from_value(serde_json::to_value(params)?)?;
+                if command.command.is_empty() {
+                    return Err(anyhow!("the command has no name"));
+                }
+                self.commands.insert(command.command.clone());
+                self.catalog_rpc.core_rpc.plugin_command_registered(
+                    self.volt_id.clone(),
+                    self.server_rpc.plugin_id,
+                    command,
+                );
+                Ok(Value::Null)
+            }
+            SET_PANEL => {
+                let panel: PluginPanel =
+                    serde_json::from_value(serde_json::to_value(params)?)?;
+                let commands = panel
+                    .items
+                    .iter()
+                    .flat_map(|item| item.commands())
+                    .collect::<Vec<_>>();
+                self.check_click_commands(&commands)?;
+                self.catalog_rpc.core_rpc.plugin_panel_changed(
+                    self.volt_id.clone(),
+                    panel.id.clone(),
+                    Some(panel),
+                );
+                Ok(Value::Null)
+            }
+            SET_STATUS_ITEM => {
+                let item: PluginStatusItem =
+                    serde_json::from_value(serde_json::to_value(params)?)?;
+                self.check_click_commands(&item.command.iter().collect::<Vec<_>>())?;
+                self.catalog_rpc.core_rpc.plugin_status_item_changed(
+                    self.volt_id.clone(),
+                    item.id.clone(),
+                    Some(item),
+                );
+                Ok(Value::Null)
+            }
+            REMOVE_PANEL | REMOVE_STATUS_ITEM => {
+                let params: RemoveViewParams =
+                    serde_json::from_value(serde_json::to_value(params)?)?;
+                let core_rpc = &self.catalog_rpc.core_rpc;
+                if method == REMOVE_PANEL {
+                    core_rpc.plugin_panel_changed(
+                        self.volt_id.clone(),
+                        params.id,
+                        None,
+                    );
+                } else {
+                    core_rpc.plugin_status_item_changed(
+                        self.volt_id.clone(),
+                        params.id,
+                        None,
+                    );
+                }
+                Ok(Value::Null)
+            }
+            SET_DECORATIONS => {
+                let PluginDecorations {
+                    uri,
+                    key,
+                    version,
+                    mut decorations,
+                } = serde_json::from_value(serde_json::to_value(params)?)?;
+                let path = uri
+                    .to_file_path()
+                    .map_err(|_| anyhow!("{uri} isn't a file"))?;
+                // The icons can only be read from the plugin's folder
+                for decoration in decorations.iter_mut() {
+                    if let Some(icon) = decoration.gutter_icon.take() {
+                        let in_folder = icon.components().all(|component| {
+                            matches!(component, Component::Normal(_))
+                        });
+                        decoration.gutter_icon = self
+                            .pwd
+                            .as_ref()
+                            .filter(|_| in_folder)
+                            .map(|pwd| pwd.join(icon));
+                    }
+                }
+                self.catalog_rpc.core_rpc.plugin_decorations_changed(
+                    self.volt_id.clone(),
+                    path,
+                    key,
+                    version,
+                    decorations,
+                );
+                Ok(Value::Null)
+            }
             _ => Err(anyhow!("request not supported")),
         }
     }
 
+    /// The commands run by clicking the views of the plugin can't run
+    /// programs if the plugin isn't allowed to
+    fn check_click_commands(&self, commands: &[&EditorCommand]) -> Result<()> {
+        if !self.permissions.process
+            && commands.iter().any(|cmd| runs_process(&cmd.command))
+        {
+            return Err(anyhow!("plugin isn't allowed to run programs"));
+        }
+        Ok(())
+    }
+
+    /// Send the event on to the plugin if it subscribed to its kind
+    pub fn handle_event(&self, event: PluginEvent) {
+        if self.subscribed_events.contains(&event.kind()) {
+            self.server_rpc
+                .server_notification(EVENT, event, None, None, false);
+        }
+    }
+
+    /// Send the command to the plugin to run, and call back with what it
+    /// returns, leaving out the commands that run programs if the plugin
+    /// isn't allowed to
+    pub fn execute_command(
+        &self,
+        context: PluginCommandContext,
+        f: Box<dyn RpcCallback<PluginCommandResult, RpcError>>,
+    ) {
+        if !self.commands.contains(&context.command) {
+            f.call(Err(RpcError {
+                code: 0,
+                message: format!("the plugin has no command {}", context.command),
+            }));
+            return;
+        }
+        let process = self.permissions.process;
+        self.server_rpc.server_request_async(
+            EXECUTE_COMMAND,
+            context,
+            None,
+            None,
+            false,
+            move |result: Result<Value, RpcError>| {
+                let result = result.and_then(|value| {
+                    // A command that only did things itself has nothing to
+                    // return
+                    if value.is_null() {
+                        return Ok(PluginCommandResult::default());
+                    }
+                    serde_json::from_value::<PluginCommandResult>(value).map_err(
+                        |_| RpcError {
+                            code: 0,
+                            message: "plugin command result deserialize error"
+                                .to_string(),
+                        },
+                    )
+                });
+                let result = result.map(|mut result| {
+                    if !process {
+                        result.commands.retain(|cmd| !runs_process(&cmd.command));
+                    }
+                    result
+                });
+                f.call(result);
+            },
+        );
+    }
+
     pub fn handle_notification(
         &mut self,
         method: String,
@@ -850,6 +1323,19 @@ pub fn handle_notification(
     ) -> Result<()> {
         match method.as_str() {
             StartLspServer::METHOD => {
+                if !self.permissions.process {
+                    let title = format!("Plugin: {}", self.volt_display_name);
+                    self.catalog_rpc.core_rpc.show_message(
+                        title,
+                        ShowMessageParams {
+                            typ: MessageType::WARNING,
+                            message: "The plugin isn't allowed to run programs, \
+                                      so its language server wasn't started"
+                                .to_string(),
+                        },
+                    );
+                    return Ok(());
+                }
                 let params: StartLspServerParams =
                     serde_json::from_value(serde_json::to_value(params)?)?;
                 let workspace = self.workspace.clone();
@@ -857,6 +1343,7 @@ pub fn handle_notification(
                 let catalog_rpc = self.catalog_rpc.clone();
                 let volt_id = self.volt_id.clone();
                 let volt_display_name = self.volt_display_name.clone();
+                let lsp_settings = self.lsp_settings.clone();
                 thread::spawn(move || {
                     let _ = LspClient::start(
                         catalog_rpc,
@@ -868,18 +1355,22 @@ pub fn handle_notification(
                         params.server_uri,
                         params.server_args,
                         params.options,
+                        lsp_settings,
                     );
                 });
             }
             PublishDiagnostics::METHOD => {
                 let diagnostics: PublishDiagnosticsParams =
                     serde_json::from_value(serde_json::to_value(params)?)?;
-                self.catalog_rpc.core_rpc.publish_diagnostics(diagnostics);
+                self.catalog_rpc
+                    .publish_diagnostics(self.server_rpc.plugin_id, diagnostics);
             }
             Progress::METHOD => {
                 let progress: ProgressParams =
                     serde_json::from_value(serde_json::to_value(params)?)?;
-                self.catalog_rpc.core_rpc.work_done_progress(progress);
+                self.catalog_rpc
+                    .core_rpc
+                    .work_done_progress(progress, self.volt_display_name.clone());
             }
             ShowMessage::METHOD => {
                 let message: ShowMessageParams =
@@ -949,6 +1440,16 @@ pub fn handle_did_change_text_document(
             }
             None => TextDocumentSyncKind::NONE,
         };
+        // Providers are sent the whole document as the server doesn't sync it
+        let kind = if kind == TextDocumentSyncKind::NONE
+            && self.provider_registered(
+                DidChangeTextDocument::METHOD,
+                Some(&lanaguage_id),
+            ) {
+            TextDocumentSyncKind::FULL
+        } else {
+            kind
+        };
 
         let mut existing = change.lock();
         let change = match kind {
@@ -1121,3 +1622,22 @@ fn semantic_tokens_legend(
         ) => &options.semantic_tokens_options.legend,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::runs_process;
+
+    #[test]
+    fn test_runs_process() {
+        assert!(!runs_process("insert_new_line"));
+        assert!(!runs_process("down"));
+        assert!(!runs_process("palette.command"));
+        assert!(!runs_process("change_file_language:rust"));
+        assert!(runs_process("run_task"));
+        assert!(runs_process("install_to_path"));
+        assert!(runs_process("reveal_active_file_in_file_manager"));
+        assert!(runs_process("run_plugin_command"));
+        assert!(runs_process("run_task:build"));
+        assert!(!runs_process("author.plugin.command"));
+    }
+}
@@ -16,24 +16,30 @@
 use jsonrpc_lite::{Id, Params};
 use lapce_core::directory::Directory;
 use lapce_rpc::{
-    plugin::{PluginId, VoltID, VoltInfo, VoltMetadata},
+    plugin::{
+        FilesystemScope, PluginId, PluginPermissions, VoltID, VoltInfo,
+        VoltMetadata, PLUGIN_API_VERSION,
+    },
     style::LineStyle,
     RpcError,
 };
 use lapce_xi_rope::{Rope, RopeDelta};
 use lsp_types::{
     notification::Initialized, request::Initialize, DocumentFilter,
-    InitializeParams, InitializedParams, TextDocumentContentChangeEvent,
-    TextDocumentIdentifier, Url, VersionedTextDocumentIdentifier,
+    InitializeParams, InitializedParams, MessageType, ShowMessageParams,
+    TextDocumentContentChangeEvent, TextDocumentIdentifier, Url,
+    VersionedTextDocumentIdentifier,
 };
 use parking_lot::Mutex;
 use psp_types::{Notification, Request};
 use toml_edit::easy as toml;
 use wasi_experimental_http_wasmtime::{HttpCtx, HttpState};
-use wasmtime_wasi::WasiCtxBuilder;
+use wasmtime::{StoreLimits, StoreLimitsBuilder};
+use wasmtime_wasi::{WasiCtx, WasiCtxBuilder};
 
 use super::{
     client_capabilities,
+    lsp::LspSettings,
     psp::{
         handle_plugin_server_message, PluginHandlerNotification, PluginHostHandler,
         PluginServerHandler, PluginServerRpc, RpcCallback,
@@ -42,6 +48,12 @@
 };
 use crate::plugin::psp::PluginServerRpcHandler;
 
+/// The most memory a plugin can use
+const PLUGIN_MEMORY_LIMIT: usize = 1024 * 1024 * 1024;
+/// The fuel a plugin has for handling each message, which bounds the time it
+/// can spend on the CPU for it
+const PLUGIN_FUEL: u64 = 100_000_000_000;
+
 #[derive(Default)]
 pub struct WasiPipe {
     buffer: VecDeque<u8>,
@@ -94,6 +106,15 @@ fn method_registered(&mut self, method: &'static str) -> bool {
         self.host.method_registered(method)
     }
 
+    fn request_supported(
+        &mut self,
+        method: &'static str,
+        language_id: Option<&str>,
+        path: Option<&Path>,
+    ) -> bool {
+        self.host.request_supported(method, language_id, path)
+    }
+
     fn document_supported(
         &mut self,
         language_id: Option<&str>,
@@ -114,6 +135,17 @@ fn handle_handler_notification(
             InitializeResult(result) => {
                 self.host.server_capabilities = result.capabilities;
             }
+            UpdateConfigurations(configurations) => {
+                self.host.lsp_settings =
+                    LspSettings::from_configurations(configurations.as_ref());
+                self.configurations = configurations;
+            }
+            Event(event) => {
+                self.host.handle_event(event);
+            }
+            ExecuteCommand(context, f) => {
+                self.host.execute_command(context, f);
+            }
             Shutdown => {
                 self.shutdown();
             }
@@ -305,7 +337,10 @@ pub fn find_all_volts() -> Vec<VoltMetadata> {
 ///         icon_themes: None,
 ///         dir: parent_path.canonicalize().ok(),
 ///         activation: None,
-///         config: None
+///         config: None,
+///         permissions: None,
+///         grammars: None,
+///         api_version: None
 ///     }
 /// );
 /// let _ = std::fs::remove_file(parent_path.join("volt.toml"));
@@ -341,6 +376,19 @@ pub fn load_volt(path: &Path) -> Result<VoltMetadata> {
             })
             .collect()
     });
+    meta.grammars = meta.grammars.take().map(|grammars| {
+        grammars
+            .into_iter()
+            .map(|mut grammar| {
+                let resolve = |dir: Option<String>| {
+                    Some(path.join(dir?).canonicalize().ok()?.to_str()?.to_string())
+                };
+                grammar.grammar = resolve(grammar.grammar);
+                grammar.queries = resolve(grammar.queries);
+                grammar
+            })
+            .collect()
+    });
 
     Ok(meta)
 }
@@ -357,13 +405,38 @@ pub fn enable_volt(
     Ok(())
 }
 
+/// The state of the store of a plugin
+struct PluginStore {
+    wasi: WasiCtx,
+    limits: StoreLimits,
+}
+
+/// Start the plugin in a sandbox where it can only reach what it's granted
 pub fn start_volt(
     workspace: Option<PathBuf>,
     configurations: Option<HashMap<String, serde_json::Value>>,
+    permissions: PluginPermissions,
     plugin_rpc: PluginCatalogRpcHandler,
     meta: VoltMetadata,
 ) -> Result<()> {
-    let engine = wasmtime::Engine::default();
+    if let Some(version) = meta.api_version.filter(|v| *v > PLUGIN_API_VERSION) {
+        plugin_rpc.core_rpc.show_message(
+            format!("Plugin: {}", meta.display_name),
+            ShowMessageParams {
+                typ: MessageType::WARNING,
+                message: format!(
+                    "The plugin needs version {version} of the plugin API, which \
+                     is newer than the {PLUGIN_API_VERSION} of this Lapce, so it \
+                     wasn't started"
+                ),
+            },
+        );
+        return Err(anyhow!("plugin API version {version} isn't supported"));
+    }
+
+    let mut config = wasmtime::Config::new();
+    config.consume_fuel(true);
+    let engine = wasmtime::Engine::new(&config)?;
     let module = wasmtime::Module::from_file(
         &engine,
         meta.wasm
@@ -371,9 +444,14 @@ pub fn start_volt(
             .ok_or_else(|| anyhow!("no wasm in plugin"))?,
     )?;
     let mut linker = wasmtime::Linker::new(&engine);
-    wasmtime_wasi::add_to_linker(&mut linker, |s| s)?;
-    HttpState::new()?.add_to_linker(&mut linker, |_| HttpCtx {
-        allowed_hosts: Some(vec!["insecure:allow-all".to_string()]),
+    wasmtime_wasi::add_to_linker(&mut linker, |s: &mut PluginStore| &mut s.wasi)?;
+    let allowed_hosts = if permissions.network {
+        Some(vec!["insecure:allow-all".to_string()])
+    } else {
+        None
+    };
+    HttpState::new()?.add_to_linker(&mut linker, move |_| HttpCtx {
+        allowed_hosts: allowed_hosts.clone(),
         max_concurrent_requests: Some(100),
     })?;
 
@@ -408,11 +486,26 @@ pub fn start_volt(
     let stdin = Arc::new(RwLock::new(WasiPipe::new()));
     let stdout = Arc::new(RwLock::new(WasiPipe::new()));
     let stderr = Arc::new(RwLock::new(WasiPipe::new()));
-    let wasi = WasiCtxBuilder::new()
-        .inherit_env()?
+    // Only the variables meant for plugins are passed on from the environment,
+    // short of the ones set here
+    let set_env = [
+        "VOLT_OS",
+        "VOLT_ARCH",
+        "VOLT_LIBC",
+        "VOLT_API_VERSION",
+        "VOLT_URI",
+    ];
+    let volt_env: Vec<(String, String)> = std::env::vars()
+        .filter(|(key, _)| {
+            key.starts_with("VOLT_") && !set_env.contains(&key.as_str())
+        })
+        .collect();
+    let mut wasi = WasiCtxBuilder::new()
+        .envs(&volt_env)?
         .env("VOLT_OS", std::env::consts::OS)?
         .env("VOLT_ARCH", std::env::consts::ARCH)?
         .env("VOLT_LIBC", volt_libc)?
+        .env("VOLT_API_VERSION", &PLUGIN_API_VERSION.to_string())?
         .env(
             "VOLT_URI",
             Url::from_directory_path(volt_path)
@@ -434,9 +527,35 @@ pub fn start_volt(
                 wasmtime_wasi::ambient_authority(),
             )?,
             "/",
-        )?
-        .build();
-    let mut store = wasmtime::Store::new(&engine, wasi);
+        )?;
+    // The directories granted are found at their own paths
+    let granted_dir = match permissions.filesystem {
+        FilesystemScope::Plugin => None,
+        FilesystemScope::Workspace => workspace.clone(),
+        FilesystemScope::Home => {
+            directories::UserDirs::new().map(|dirs| dirs.home_dir().to_path_buf())
+        }
+    };
+    if let Some(dir) = granted_dir {
+        wasi = wasi.preopened_dir(
+            wasmtime_wasi::Dir::open_ambient_dir(
+                &dir,
+                wasmtime_wasi::ambient_authority(),
+            )?,
+            &dir,
+        )?;
+    }
+    let wasi = wasi.build();
+    let mut store = wasmtime::Store::new(
+        &engine,
+        PluginStore {
+            wasi,
+            limits: StoreLimitsBuilder::new()
+                .memory_size(PLUGIN_MEMORY_LIMIT)
+                .build(),
+        },
+    );
+    store.limiter(|s| &mut s.limits);
 
     let (io_tx, io_rx) = crossbeam_channel::unbounded();
     let rpc = PluginServerRpcHandler::new(meta.id(), io_tx);
@@ -457,8 +576,11 @@ pub fn start_volt(
             eprintln!("got stderr from plugin: {msg}");
         }
     })?;
+    refuel(&mut store);
     linker.module(&mut store, "", &module)?;
+    let volt_name = meta.display_name.clone();
     thread::spawn(move || {
+        refuel(&mut store);
         let instance = linker.instantiate(&mut store, &module).unwrap();
         let handle_rpc = instance
             .get_func(&mut store, "handle_rpc")
@@ -470,11 +592,15 @@ pub fn start_volt(
             if let Ok(msg) = serde_json::to_string(&msg) {
                 let _ = writeln!(stdin.write().unwrap(), "{msg}");
             }
-            let _ = handle_rpc.call(&mut store, ());
+            refuel(&mut store);
+            if let Err(err) = handle_rpc.call(&mut store, ()) {
+                eprintln!("plugin {volt_name} failed to handle a message: {err}");
+            }
         }
     });
 
     let id = PluginId::next();
+    let lsp_settings = LspSettings::from_configurations(configurations.as_ref());
     let mut plugin = Plugin {
         id,
         host: PluginHostHandler::new(
@@ -505,9 +631,11 @@ pub fn start_volt(
                 .collect(),
             rpc.clone(),
             plugin_rpc.clone(),
+            permissions,
         ),
         configurations,
     };
+    plugin.host.lsp_settings = lsp_settings;
     let local_rpc = rpc.clone();
     thread::spawn(move || {
         local_rpc.mainloop(&mut plugin);
@@ -519,13 +647,22 @@ pub fn start_volt(
     Ok(())
 }
 
+/// Top up the fuel of the plugin for handling a message, so that a plugin
+/// stuck in a loop is stopped instead of spinning forever
+fn refuel(store: &mut wasmtime::Store<PluginStore>) {
+    let remaining = store.consume_fuel(0).unwrap_or(0);
+    let _ = store.add_fuel(PLUGIN_FUEL.saturating_sub(remaining));
+}
+
 fn wasi_read_string(stdout: &Arc<RwLock<WasiPipe>>) -> Result<String> {
     let mut buf = String::new();
     stdout.write().unwrap().read_to_string(&mut buf)?;
     Ok(buf)
 }
 
-fn unflatten_map(map: &HashMap<String, serde_json::Value>) -> serde_json::Value {
+pub(crate) fn unflatten_map(
+    map: &HashMap<String, serde_json::Value>,
+) -> serde_json::Value {
     let mut new = serde_json::json!({});
     for (key, value) in map.iter() {
         let mut current = new.as_object_mut().unwrap();
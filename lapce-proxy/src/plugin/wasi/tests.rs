@@ -145,7 +145,10 @@ fn test_load_volt() {
             icon_themes: Some(icon_themes_pathes),
             dir: parent_path.canonicalize().ok(),
             activation: None,
-            config: None
+            config: None,
+            permissions: None,
+            grammars: None,
+            api_version: None
         }
     );
 
@@ -205,7 +208,10 @@ fn test_load_volt() {
             icon_themes: Some(icon_themes_pathes),
             dir: parent_path.canonicalize().ok(),
             activation: None,
-            config: None
+            config: None,
+            permissions: None,
+            grammars: None,
+            api_version: None
         }
     );
 
@@ -231,7 +237,10 @@ fn test_load_volt() {
             icon_themes: Some(Vec::new()),
             dir: parent_path.canonicalize().ok(),
             activation: None,
-            config: None
+            config: None,
+            permissions: None,
+            grammars: None,
+            api_version: None
         }
     );
 }
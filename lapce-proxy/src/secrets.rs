@@ -0,0 +1,42 @@
+//! Tokens and passwords stored in the keychain of the platform, being the
+//! Secret Service on Linux, the Keychain on macOS and the Credential Manager on
+//! Windows, so that they are never written to the config files.
+
+use anyhow::Result;
+use keyring::Entry;
+use lapce_core::meta::NAME;
+
+/// A secret a plugin stored under its own key, with the plugin identified as
+/// `author.name`, which keeps the secrets of different plugins apart in the
+/// keychain
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SecretKey {
+    pub volt: String,
+    pub key: String,
+}
+
+fn entry(key: &SecretKey) -> Result<Entry> {
+    let account = format!("plugin:{}:{}", key.volt, key.key);
+    Ok(Entry::new(NAME, &account)?)
+}
+
+pub fn get(key: &SecretKey) -> Result<Option<String>> {
+    match entry(key)?.get_password() {
+        Ok(secret) => Ok(Some(secret)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub fn set(key: &SecretKey, secret: &str) -> Result<()> {
+    entry(key)?.set_password(secret)?;
+    Ok(())
+}
+
+/// Remove the secret, which is not an error if there was none
+pub fn delete(key: &SecretKey) -> Result<()> {
+    match entry(key)?.delete_password() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,298 @@
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeMap, HashSet},
+    fs,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use anyhow::{anyhow, Result};
+use lapce_core::{
+    directory::Directory,
+    language::LapceLanguage,
+    syntax::symbols::{tree_symbols, TreeSymbol},
+};
+use lapce_rpc::file::FileChange;
+use lsp_types::{Location, SymbolInformation, Url};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::ignore_matcher::walker;
+
+/// Files bigger than this aren't indexed, they're rarely where a symbol is
+/// looked for and they're slow to parse
+const MAX_INDEXED_FILE_SIZE: u64 = 1024 * 1024;
+/// How many symbols a search gives at most
+const MAX_SEARCH_RESULTS: usize = 1000;
+
+/// The symbols defined in the files of the workspace, from their syntax trees.
+/// They answer the workspace symbol search while the language servers are
+/// still starting, or for the languages that don't have one. The index is
+/// kept on disk between sessions, and only the files changed since are parsed
+/// again.
+#[derive(Default, Serialize, Deserialize)]
+pub struct SymbolIndex {
+    files: BTreeMap<PathBuf, IndexedFile>,
+    /// Whether the index changed since it was loaded or saved
+    #[serde(skip)]
+    changed: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct IndexedFile {
+    modified: SystemTime,
+    symbols: Vec<TreeSymbol>,
+}
+
+impl SymbolIndex {
+    /// The index of the workspace as the last session left it, if there's one
+    pub fn load(workspace: &Path) -> Self {
+        index_path(workspace)
+            .and_then(|path| fs::read(path).ok())
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default()
+    }
+
+    /// Take in the index loaded from disk, keeping the files indexed since
+    /// the session started, which are newer
+    pub fn merge(&mut self, loaded: SymbolIndex) {
+        if !self.files.is_empty() {
+            self.changed = true;
+        }
+        for (path, file) in loaded.files {
+            self.files.entry(path).or_insert(file);
+        }
+        self.changed |= loaded.changed;
+    }
+
+    pub fn save(&mut self, workspace: &Path) -> Result<()> {
+        if !self.changed {
+            return Ok(());
+        }
+        let path = index_path(workspace)
+            .ok_or_else(|| anyhow!("can't get the cache directory"))?;
+        fs::write(path, serde_json::to_vec(self)?)?;
+        self.changed = false;
+        Ok(())
+    }
+
+    /// Forget the files that aren't in the workspace anymore
+    pub fn retain(&mut self, files: &[PathBuf]) {
+        let files: HashSet<&PathBuf> = files.iter().collect();
+        let len = self.files.len();
+        self.files.retain(|path, _| files.contains(path));
+        if self.files.len() != len {
+            self.changed = true;
+        }
+    }
+
+    /// Parse the file for its symbols, unless it's unchanged since it was
+    /// last parsed. The index is only locked to look the file up and to store
+    /// its symbols, so that searches aren't held up by the parsing.
+    pub fn index_file(index: &Mutex<Self>, path: &Path) {
+        let language = LapceLanguage::from_path(path);
+        if language == LapceLanguage::Plaintext {
+            return;
+        }
+        let Ok(metadata) = fs::metadata(path) else {
+            index.lock().remove(path);
+            return;
+        };
+        if !metadata.is_file() || metadata.len() > MAX_INDEXED_FILE_SIZE {
+            return;
+        }
+        let Ok(modified) = metadata.modified() else {
+            return;
+        };
+        if index
+            .lock()
+            .files
+            .get(path)
+            .map(|file| file.modified == modified)
+            .unwrap_or(false)
+        {
+            return;
+        }
+        let Ok(text) = fs::read_to_string(path) else {
+            return;
+        };
+        let symbols = tree_symbols(language, &text);
+        let mut index = index.lock();
+        index
+            .files
+            .insert(path.to_path_buf(), IndexedFile { modified, symbols });
+        index.changed = true;
+    }
+
+    /// Apply the changes the file watcher reported
+    pub fn apply(index: &Mutex<Self>, changes: &[FileChange]) {
+        for change in changes {
+            match change {
+                FileChange::Created(path) => {
+                    Self::add(index, path);
+                }
+                FileChange::Modified(path) => {
+                    Self::index_file(index, path);
+                }
+                FileChange::Removed(path) => {
+                    index.lock().remove(path);
+                }
+                FileChange::Renamed { from, to } => {
+                    index.lock().remove(from);
+                    Self::add(index, to);
+                }
+            }
+        }
+    }
+
+    /// The symbols whose names have the characters of the query in order,
+    /// ignoring case, the ones starting with the query first
+    pub fn search(&self, query: &str) -> Vec<SymbolInformation> {
+        let query = query.to_lowercase();
+        let mut matches: Vec<(bool, &Path, &TreeSymbol)> = self
+            .files
+            .iter()
+            .flat_map(|(path, file)| {
+                file.symbols.iter().map(move |symbol| (path, symbol))
+            })
+            .filter_map(|(path, symbol)| {
+                let name = symbol.name.to_lowercase();
+                let mut chars = name.chars();
+                if !query.chars().all(|c| chars.any(|n| n == c)) {
+                    return None;
+                }
+                Some((!name.starts_with(&query), path.as_path(), symbol))
+            })
+            .collect();
+        matches
+            .sort_by_key(|(not_prefix, _, symbol)| (*not_prefix, symbol.name.len()));
+        matches
+            .into_iter()
+            .take(MAX_SEARCH_RESULTS)
+            .filter_map(|(_, path, symbol)| {
+                #[allow(deprecated)]
+                Some(SymbolInformation {
+                    name: symbol.name.clone(),
+                    kind: symbol.kind,
+                    tags: None,
+                    deprecated: None,
+                    location: Location {
+                        uri: Url::from_file_path(path).ok()?,
+                        range: symbol.range,
+                    },
+                    container_name: symbol.container_name.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// Index the file, or the files under the directory that aren't ignored
+    fn add(index: &Mutex<Self>, path: &Path) {
+        if path.is_dir() {
+            let files = walker(path, false)
+                .build()
+                .flatten()
+                .filter(|entry| {
+                    entry.file_type().map(|t| t.is_file()).unwrap_or(false)
+                })
+                .map(|entry| entry.into_path());
+            for file in files {
+                Self::index_file(index, &file);
+            }
+        } else {
+            Self::index_file(index, path);
+        }
+    }
+
+    /// Forget the file, or the files under the directory
+    fn remove(&mut self, path: &Path) {
+        let removed: Vec<PathBuf> = self
+            .files
+            .range(path.to_path_buf()..)
+            .take_while(|(file, _)| file.starts_with(path))
+            .map(|(file, _)| file.clone())
+            .collect();
+        for file in removed {
+            self.files.remove(&file);
+            self.changed = true;
+        }
+    }
+}
+
+/// Where the index of the workspace is kept, named after a hash of its path.
+/// The hash isn't stable across Rust versions, which only means the workspace
+/// is indexed again from scratch.
+fn index_path(workspace: &Path) -> Option<PathBuf> {
+    let dir = Directory::cache_directory()?.join("symbols");
+    fs::create_dir_all(&dir).ok()?;
+    let mut hasher = DefaultHasher::new();
+    workspace.hash(&mut hasher);
+    Some(dir.join(format!("{:x}.json", hasher.finish())))
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::{Position, Range, SymbolKind};
+
+    use super::*;
+
+    fn symbol(name: &str) -> TreeSymbol {
+        TreeSymbol {
+            name: name.to_string(),
+            kind: SymbolKind::FUNCTION,
+            container_name: None,
+            range: Range::new(Position::new(0, 0), Position::new(0, 0)),
+        }
+    }
+
+    #[test]
+    fn test_search() {
+        let mut index = SymbolIndex::default();
+        let path = std::env::temp_dir().join("lib.rs");
+        index.files.insert(
+            path.clone(),
+            IndexedFile {
+                modified: SystemTime::UNIX_EPOCH,
+                symbols: vec![
+                    symbol("read_file_index"),
+                    symbol("file_index"),
+                    symbol("fetch"),
+                ],
+            },
+        );
+
+        let names: Vec<String> = index
+            .search("FIdx")
+            .into_iter()
+            .map(|symbol| symbol.name)
+            .collect();
+        assert_eq!(names, vec!["file_index", "read_file_index"]);
+
+        index.remove(&path);
+        assert!(index.search("f").is_empty());
+    }
+
+    #[test]
+    fn test_merge() {
+        let file = |modified: u64, name: &str| IndexedFile {
+            modified: SystemTime::UNIX_EPOCH
+                + std::time::Duration::from_secs(modified),
+            symbols: vec![symbol(name)],
+        };
+        let dir = std::env::temp_dir();
+        let mut loaded = SymbolIndex::default();
+        loaded.files.insert(dir.join("a.rs"), file(1, "old_a"));
+        loaded.files.insert(dir.join("b.rs"), file(1, "b"));
+
+        // A file saved while the index was loading is newer than what was on
+        // disk
+        let mut index = SymbolIndex::default();
+        index.files.insert(dir.join("a.rs"), file(2, "new_a"));
+        index.merge(loaded);
+
+        assert!(index.changed);
+        assert_eq!(index.files.len(), 2);
+        assert_eq!(index.files[&dir.join("a.rs")].symbols[0].name, "new_a");
+        assert_eq!(index.files[&dir.join("b.rs")].symbols[0].name, "b");
+    }
+}
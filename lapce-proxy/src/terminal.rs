@@ -44,6 +44,7 @@ pub fn new(
         cwd: Option<PathBuf>,
         env: Option<HashMap<String, String>>,
         shell: String,
+        command: Option<Vec<String>>,
         width: usize,
         height: usize,
     ) -> Terminal {
@@ -58,41 +59,46 @@ pub fn new(
         config.env = env.unwrap_or_default();
 
         let shell = shell.trim();
+        let command = command.filter(|command| !command.is_empty());
         let flatpak_use_host_terminal = flatpak_should_use_host_terminal();
 
-        if !shell.is_empty() || flatpak_use_host_terminal {
-            let mut parts = shell.split(' ');
-
+        if command.is_some() || !shell.is_empty() || flatpak_use_host_terminal {
             if flatpak_use_host_terminal {
                 let flatpak_spawn_path = "/usr/bin/flatpak-spawn".to_string();
-                let host_shell = flatpak_get_default_host_shell();
-
-                let args = if shell.is_empty() {
-                    vec![
-                        "--host".to_string(),
-                        "--env=TERM=alacritty".to_string(),
-                        host_shell,
-                    ]
+                let mut args =
+                    vec!["--host".to_string(), "--env=TERM=alacritty".to_string()];
+                if let Some(command) = command {
+                    args.extend(command);
                 } else {
-                    vec![
-                        "--host".to_string(),
-                        "--env=TERM=alacritty".to_string(),
-                        host_shell,
-                        "-c".to_string(),
-                        shell.to_string(),
-                    ]
-                };
+                    args.push(flatpak_get_default_host_shell());
+                    if !shell.is_empty() {
+                        args.push("-c".to_string());
+                        args.push(shell.to_string());
+                    }
+                }
 
                 config.pty_config.shell = Some(Program::WithArgs {
                     program: flatpak_spawn_path,
                     args,
                 })
             } else {
-                let program = parts.next().unwrap();
+                // A command comes split into its arguments already, while the
+                // shell is split on spaces
+                let (program, args) = match command {
+                    Some(mut command) => {
+                        let program = command.remove(0);
+                        (program, command)
+                    }
+                    None => {
+                        let mut parts = shell.split(' ');
+                        let program = parts.next().unwrap().to_string();
+                        (program, parts.map(|p| p.to_string()).collect())
+                    }
+                };
                 if let Ok(p) = which::which(program) {
                     config.pty_config.shell = Some(Program::WithArgs {
                         program: p.to_str().unwrap().to_string(),
-                        args: parts.map(|p| p.to_string()).collect::<Vec<String>>(),
+                        args,
                     })
                 }
             }
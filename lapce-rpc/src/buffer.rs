@@ -12,6 +12,26 @@ pub fn next() -> Self {
     }
 }
 
+/// What saving a file does with the characters its encoding can't represent
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EncodingFallback {
+    /// Write a `?` in place of the character
+    #[default]
+    Substitute,
+    /// Write the code point of the character as `\u{...}`
+    Escape,
+}
+
+impl EncodingFallback {
+    /// What is written in place of the character
+    pub fn replacement(&self, c: char) -> String {
+        match self {
+            EncodingFallback::Substitute => "?".to_string(),
+            EncodingFallback::Escape => format!("\\u{{{:04X}}}", c as u32),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NewBufferResponse {
     pub content: String,
@@ -10,15 +10,19 @@
 use crossbeam_channel::{Receiver, Sender};
 use lsp_types::{
     CompletionResponse, LogMessageParams, ProgressParams, PublishDiagnosticsParams,
-    ShowMessageParams, SignatureHelp,
+    ShowMessageParams, SignatureHelp, WorkspaceEdit,
 };
 use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
 
 use crate::{
     dap_types::{self, DapId, RunDebugConfig, StackFrame, Stopped, ThreadId},
-    file::PathObject,
-    plugin::{PluginId, VoltInfo, VoltMetadata},
+    file::{FileChange, PathObject},
+    plugin::{
+        PluginCommand, PluginId, PluginPanel, PluginStatusItem,
+        ProviderRegistration, TextDecoration, VoltID, VoltInfo, VoltMetadata,
+    },
+    protocol::{Capability, Protocol, ProtocolInfo, UNSUPPORTED_REQUEST},
     source_control::DiffInfo,
     terminal::TermId,
     RequestId, RpcError, RpcMessage,
@@ -34,7 +38,11 @@ pub enum CoreRpc {
 #[serde(rename_all = "snake_case")]
 #[serde(tag = "method", content = "params")]
 pub enum CoreNotification {
-    ProxyConnected {},
+    ProxyConnected {
+        /// The protocol the proxy speaks
+        #[serde(default)]
+        protocol: ProtocolInfo,
+    },
     OpenFileChanged {
         path: PathBuf,
         content: String,
@@ -53,12 +61,20 @@ pub enum CoreNotification {
     OpenPaths {
         paths: Vec<PathObject>,
     },
-    WorkspaceFileChange {},
+    /// Files or directories of the workspace were created, removed or renamed
+    WorkspaceFileChange {
+        #[serde(default)]
+        changes: Vec<FileChange>,
+    },
+    /// The settings of the workspace, in its `.lapce` folder, changed
+    WorkspaceConfigChange {},
     PublishDiagnostics {
         diagnostics: PublishDiagnosticsParams,
     },
     WorkDoneProgress {
         progress: ProgressParams,
+        /// The display name of the plugin whose server reports the progress
+        server_name: String,
     },
     ShowMessage {
         title: String,
@@ -67,6 +83,42 @@ pub enum CoreNotification {
     LogMessage {
         message: LogMessageParams,
     },
+    /// A plugin registered a provider of a language feature
+    ProviderRegistered {
+        plugin_id: PluginId,
+        registration: ProviderRegistration,
+    },
+    /// A plugin registered a command for the palette and the keymaps
+    PluginCommandRegistered {
+        volt: VoltID,
+        plugin_id: PluginId,
+        command: PluginCommand,
+    },
+    /// A plugin set its panel of the id, or removed it if `None`
+    PluginPanelChanged {
+        volt: VoltID,
+        id: String,
+        panel: Option<PluginPanel>,
+    },
+    /// A plugin set its status bar segment of the id, or removed it if `None`
+    PluginStatusItemChanged {
+        volt: VoltID,
+        id: String,
+        item: Option<PluginStatusItem>,
+    },
+    /// A plugin replaced its decorations of the key on the document
+    PluginDecorationsChanged {
+        volt: VoltID,
+        path: PathBuf,
+        key: String,
+        version: Option<i32>,
+        decorations: Vec<TextDecoration>,
+    },
+    /// A plugin runs a command of the editor, by its name in the keymaps
+    RunCommand {
+        command: String,
+        data: Option<serde_json::Value>,
+    },
     HomeDir {
         path: PathBuf,
     },
@@ -123,12 +175,60 @@ pub enum CoreNotification {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub enum CoreRequest {}
+#[serde(rename_all = "snake_case")]
+#[serde(tag = "method", content = "params")]
+pub enum CoreRequest {
+    /// Apply the edits to the documents all together, or none of them if
+    /// any can't be, such as when its document changed since the edits were
+    /// made
+    ApplyWorkspaceEdit { edit: WorkspaceEdit },
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 #[serde(tag = "method", content = "params")]
-pub enum CoreResponse {}
+pub enum CoreResponse {
+    ApplyWorkspaceEditResponse {
+        applied: bool,
+        /// Why the edits weren't applied
+        failure_reason: Option<String>,
+    },
+}
+
+impl CoreRequest {
+    /// The capability the editor needs to have to handle the request
+    pub fn capability(&self) -> Option<Capability> {
+        match self {
+            CoreRequest::ApplyWorkspaceEdit { .. } => Some(Capability::EditSessions),
+        }
+    }
+}
+
+impl CoreNotification {
+    /// The capability the editor needs to have to read the notification
+    pub fn capability(&self) -> Option<Capability> {
+        match self {
+            CoreNotification::WorkspaceConfigChange {} => {
+                Some(Capability::ConfigChanges)
+            }
+            CoreNotification::ProviderRegistered { .. } => {
+                Some(Capability::ProviderRegistration)
+            }
+            CoreNotification::RunCommand { .. } => Some(Capability::PluginApi),
+            CoreNotification::PluginCommandRegistered { .. } => {
+                Some(Capability::PluginCommands)
+            }
+            CoreNotification::PluginPanelChanged { .. }
+            | CoreNotification::PluginStatusItemChanged { .. } => {
+                Some(Capability::PluginViews)
+            }
+            CoreNotification::PluginDecorationsChanged { .. } => {
+                Some(Capability::PluginDecorations)
+            }
+            _ => None,
+        }
+    }
+}
 
 pub type CoreMessage = RpcMessage<CoreRequest, CoreNotification, CoreResponse>;
 
@@ -144,6 +244,7 @@ pub struct CoreRpcHandler {
     id: Arc<AtomicU64>,
     #[allow(clippy::type_complexity)]
     pending: Arc<Mutex<HashMap<u64, Sender<Result<CoreResponse, RpcError>>>>>,
+    protocol: Arc<Mutex<Protocol>>,
 }
 
 impl CoreRpcHandler {
@@ -154,6 +255,7 @@ pub fn new() -> Self {
             rx,
             id: Arc::new(AtomicU64::new(0)),
             pending: Arc::new(Mutex::new(HashMap::new())),
+            protocol: Arc::new(Mutex::new(Protocol::default())),
         }
     }
 
@@ -192,6 +294,14 @@ pub fn handle_response(
     }
 
     pub fn request(&self, request: CoreRequest) -> Result<CoreResponse, RpcError> {
+        if let Some(capability) = request.capability() {
+            if !self.protocol.lock().supports(capability) {
+                return Err(RpcError {
+                    code: UNSUPPORTED_REQUEST,
+                    message: format!("the editor doesn't support {capability:?}"),
+                });
+            }
+        }
         let (tx, rx) = crossbeam_channel::bounded(1);
         let id = self.id.fetch_add(1, Ordering::Relaxed);
         {
@@ -211,16 +321,41 @@ pub fn shutdown(&self) {
         let _ = self.tx.send(CoreRpc::Shutdown);
     }
 
+    /// Set the protocol negotiated with the editor
+    pub fn set_protocol(&self, protocol: Protocol) {
+        *self.protocol.lock() = protocol;
+    }
+
     pub fn notification(&self, notification: CoreNotification) {
+        // An editor of an older version can't read the notifications it
+        // doesn't have the capability for
+        if let Some(capability) = notification.capability() {
+            if !self.protocol.lock().supports(capability) {
+                return;
+            }
+        }
         let _ = self.tx.send(CoreRpc::Notification(Box::new(notification)));
     }
 
+    pub fn apply_workspace_edit(
+        &self,
+        edit: WorkspaceEdit,
+    ) -> Result<CoreResponse, RpcError> {
+        self.request(CoreRequest::ApplyWorkspaceEdit { edit })
+    }
+
     pub fn proxy_connected(&self) {
-        self.notification(CoreNotification::ProxyConnected {});
+        self.notification(CoreNotification::ProxyConnected {
+            protocol: ProtocolInfo::current(),
+        });
+    }
+
+    pub fn workspace_file_change(&self, changes: Vec<FileChange>) {
+        self.notification(CoreNotification::WorkspaceFileChange { changes });
     }
 
-    pub fn workspace_file_change(&self) {
-        self.notification(CoreNotification::WorkspaceFileChange {});
+    pub fn workspace_config_change(&self) {
+        self.notification(CoreNotification::WorkspaceConfigChange {});
     }
 
     pub fn diff_info(&self, diff: DiffInfo) {
@@ -293,8 +428,11 @@ pub fn publish_diagnostics(&self, diagnostics: PublishDiagnosticsParams) {
         self.notification(CoreNotification::PublishDiagnostics { diagnostics });
     }
 
-    pub fn work_done_progress(&self, progress: ProgressParams) {
-        self.notification(CoreNotification::WorkDoneProgress { progress });
+    pub fn work_done_progress(&self, progress: ProgressParams, server_name: String) {
+        self.notification(CoreNotification::WorkDoneProgress {
+            progress,
+            server_name,
+        });
     }
 
     pub fn show_message(&self, title: String, message: ShowMessageParams) {
@@ -305,6 +443,73 @@ pub fn log_message(&self, message: LogMessageParams) {
         self.notification(CoreNotification::LogMessage { message });
     }
 
+    pub fn provider_registered(
+        &self,
+        plugin_id: PluginId,
+        registration: ProviderRegistration,
+    ) {
+        self.notification(CoreNotification::ProviderRegistered {
+            plugin_id,
+            registration,
+        });
+    }
+
+    pub fn plugin_command_registered(
+        &self,
+        volt: VoltID,
+        plugin_id: PluginId,
+        command: PluginCommand,
+    ) {
+        self.notification(CoreNotification::PluginCommandRegistered {
+            volt,
+            plugin_id,
+            command,
+        });
+    }
+
+    pub fn plugin_panel_changed(
+        &self,
+        volt: VoltID,
+        id: String,
+        panel: Option<PluginPanel>,
+    ) {
+        self.notification(CoreNotification::PluginPanelChanged { volt, id, panel });
+    }
+
+    pub fn plugin_status_item_changed(
+        &self,
+        volt: VoltID,
+        id: String,
+        item: Option<PluginStatusItem>,
+    ) {
+        self.notification(CoreNotification::PluginStatusItemChanged {
+            volt,
+            id,
+            item,
+        });
+    }
+
+    pub fn plugin_decorations_changed(
+        &self,
+        volt: VoltID,
+        path: PathBuf,
+        key: String,
+        version: Option<i32>,
+        decorations: Vec<TextDecoration>,
+    ) {
+        self.notification(CoreNotification::PluginDecorationsChanged {
+            volt,
+            path,
+            key,
+            version,
+            decorations,
+        });
+    }
+
+    pub fn run_command(&self, command: String, data: Option<serde_json::Value>) {
+        self.notification(CoreNotification::RunCommand { command, data });
+    }
+
     pub fn terminal_process_id(&self, term_id: TermId, process_id: Option<u32>) {
         self.notification(CoreNotification::TerminalProcessId {
             term_id,
@@ -47,6 +47,63 @@ pub fn from_path(path: PathBuf, is_dir: bool) -> PathObject {
     }
 }
 
+/// A change to the files of the workspace made outside of the editor, as
+/// reported by the file watcher
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum FileChange {
+    Created(PathBuf),
+    /// The content of the file changed. It's only used within the proxy, as
+    /// older peers don't know of it.
+    Modified(PathBuf),
+    Removed(PathBuf),
+    Renamed {
+        from: PathBuf,
+        to: PathBuf,
+    },
+}
+
+impl FileChange {
+    /// The paths the change touches
+    pub fn paths(&self) -> Vec<&Path> {
+        match self {
+            FileChange::Created(path)
+            | FileChange::Modified(path)
+            | FileChange::Removed(path) => {
+                vec![path.as_path()]
+            }
+            FileChange::Renamed { from, to } => {
+                vec![from.as_path(), to.as_path()]
+            }
+        }
+    }
+}
+
+/// The endings of the names of the archives whose members can be browsed
+/// like the entries of a folder
+const ARCHIVE_EXTENSIONS: &[&str] = &[".zip", ".jar", ".tar", ".tar.gz", ".tgz"];
+
+/// Whether the path is a file that is an archive that can be browsed, by its
+/// name. A folder named like an archive isn't one.
+pub fn is_archive(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .map(|name| {
+            let name = name.to_lowercase();
+            ARCHIVE_EXTENSIONS.iter().any(|ext| name.ends_with(ext))
+        })
+        .unwrap_or(false)
+        && path.is_file()
+}
+
+/// The archive and the path of the member in it, for a path that goes
+/// through an archive file like `/deps/lib.zip/src/lib.rs`. Archives in
+/// archives aren't looked into.
+pub fn archive_member(path: &Path) -> Option<(&Path, &Path)> {
+    let archive = path.ancestors().skip(1).filter(|p| is_archive(p)).last()?;
+    let member = path.strip_prefix(archive).ok()?;
+    Some((archive, member))
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct FileNodeItem {
     pub path_buf: PathBuf,
@@ -55,6 +112,10 @@ pub struct FileNodeItem {
     pub open: bool,
     pub children: HashMap<PathBuf, FileNodeItem>,
     pub children_open_count: usize,
+    /// Whether the ignore files of the workspace, such as `.gitignore`, match
+    /// the path
+    #[serde(default)]
+    pub is_ignored: bool,
 }
 
 impl PartialOrd for FileNodeItem {
@@ -115,6 +176,7 @@ pub fn sorted_children_mut(&mut self) -> Vec<&mut FileNodeItem> {
     /// #    open: false,
     /// #    children: HashMap::new(),
     /// #    children_open_count: 0,
+    /// #    is_ignored: false,
     ///};
     /// let mut iter = node_item.ancestors_rev(Path::new("/pre/fix/foo/bar")).unwrap();
     /// assert_eq!(Some(Path::new("/pre/fix/foo")), iter.next());
@@ -168,6 +230,7 @@ pub fn add_child(&mut self, path: &Path, is_dir: bool) -> Option<()> {
                 open: false,
                 children: HashMap::new(),
                 children_open_count: 0,
+                is_ignored: false,
             },
         );
         for p in path.ancestors() {
@@ -7,11 +7,13 @@
 pub mod file;
 mod parse;
 pub mod plugin;
+pub mod protocol;
 pub mod proxy;
 pub mod source_control;
 pub mod stdio;
 pub mod style;
 pub mod terminal;
+pub mod timer;
 
 pub use parse::{Call, RequestId, RpcObject};
 use serde::{Deserialize, Serialize};
@@ -1,10 +1,19 @@
 use core::fmt;
 use std::{collections::HashMap, path::PathBuf};
 
+use lsp_types::{Position, Range, Url, WorkspaceEdit};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-use crate::counter::Counter;
+use crate::{counter::Counter, file::FileChange};
+
+/// The version of the API Lapce offers plugins on top of the plugin server
+/// protocol, such as `lapce/runCommand`, `lapce/readBuffer`, the events of
+/// `lapce/subscribe`, the commands of `lapce/registerCommand`, the views of
+/// `lapce/setPanel` and `lapce/setStatusItem` and the decorations of
+/// `lapce/setDecorations`. It goes up with each addition, and a plugin
+/// written for a newer version than the editor's isn't started.
+pub const PLUGIN_API_VERSION: u32 = 4;
 
 #[derive(Eq, PartialEq, Hash, Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct PluginId(pub u64);
@@ -49,6 +58,53 @@ pub struct VoltActivation {
     pub workspace_contains: Option<Vec<String>>,
 }
 
+/// The support for a language that a plugin provides, written in `volt.toml`
+/// as
+///
+/// ```toml
+/// [[grammars]]
+/// language = "rust"
+/// grammar = "grammars"
+/// queries = "queries/rust"
+/// config = { comment = "//", indent = "    " }
+/// ```
+///
+/// A language that isn't built in is added, with the files it's for:
+///
+/// ```toml
+/// [[grammars]]
+/// language = "gleam"
+/// name = "Gleam"
+/// extensions = ["gleam"]
+/// grammar = "grammars"
+/// queries = "queries/gleam"
+/// ```
+///
+/// Loading the grammar library runs native code, which needs the
+/// `native-code` permission.
+#[derive(Deserialize, Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct VoltGrammar {
+    /// The name of the language, like in `languages.toml`
+    pub language: String,
+    /// The name shown for a language that isn't built in
+    pub name: Option<String>,
+    /// The file extensions of a language that isn't built in
+    pub extensions: Option<Vec<String>>,
+    /// The file names of a language that isn't built in, like `Makefile`
+    pub files: Option<Vec<String>>,
+    /// The folder with the `tree-sitter-<language>` library. The libraries
+    /// built for each platform the plugin supports go in subfolders named
+    /// after the platform, like `linux-x86_64` or `macos-aarch64`, which are
+    /// looked in first. Grammars compiled to WASM aren't supported.
+    pub grammar: Option<String>,
+    /// The folder with `highlights.scm`, `injections.scm`, and the
+    /// `indents.scm` and `folds.scm` whose `@indent` and `@fold` captures
+    /// give the nodes the lines in are indented and folded in the code lens
+    pub queries: Option<String>,
+    /// The settings of the language, like in `languages.toml`
+    pub config: Option<Value>,
+}
+
 #[derive(Deserialize, Clone, Debug, Serialize, PartialEq, Eq)]
 pub struct VoltConfig {
     pub default: Value,
@@ -71,6 +127,14 @@ pub struct VoltMetadata {
     pub dir: Option<PathBuf>,
     pub activation: Option<VoltActivation>,
     pub config: Option<HashMap<String, VoltConfig>>,
+    /// The permissions the plugin asks for, or `None` for the plugins made
+    /// before permissions existed
+    pub permissions: Option<PluginPermissions>,
+    /// The tree-sitter grammars and queries the plugin provides
+    pub grammars: Option<Vec<VoltGrammar>>,
+    /// The version of the plugin API the plugin is written for, see
+    /// [`PLUGIN_API_VERSION`]
+    pub api_version: Option<u32>,
 }
 
 impl VoltMetadata {
@@ -78,6 +142,17 @@ pub fn id(&self) -> VoltID {
         VoltID::from(self)
     }
 
+    /// The permissions the plugin asks for, which for the plugins that don't
+    /// say are the network and processes they always had
+    pub fn requested_permissions(&self) -> PluginPermissions {
+        self.permissions.clone().unwrap_or(PluginPermissions {
+            filesystem: FilesystemScope::Plugin,
+            network: true,
+            process: true,
+            native_code: false,
+        })
+    }
+
     pub fn info(&self) -> VoltInfo {
         VoltInfo {
             name: self.name.clone(),
@@ -140,9 +215,459 @@ fn from(volt: &VoltInfo) -> Self {
     }
 }
 
+/// How much of the file system a plugin can read and write
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum FilesystemScope {
+    /// Only the plugin's own directory
+    #[default]
+    Plugin,
+    /// The workspace as well
+    Workspace,
+    /// The home directory as well
+    Home,
+}
+
+impl FilesystemScope {
+    pub fn description(&self) -> &'static str {
+        match self {
+            FilesystemScope::Plugin => "its own files",
+            FilesystemScope::Workspace => "the workspace files",
+            FilesystemScope::Home => "the files of the home directory",
+        }
+    }
+}
+
+/// The resources a plugin asks for in its `volt.toml`, or is granted, beyond
+/// running sandboxed in its own directory
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", default)]
+pub struct PluginPermissions {
+    pub filesystem: FilesystemScope,
+    pub network: bool,
+    /// Running programs, which includes starting language servers
+    pub process: bool,
+    /// Loading native libraries, like the tree-sitter grammars it provides
+    pub native_code: bool,
+}
+
+impl PluginPermissions {
+    /// Whether everything the requested permissions ask for is granted
+    pub fn covers(&self, requested: &PluginPermissions) -> bool {
+        self.filesystem >= requested.filesystem
+            && (self.network || !requested.network)
+            && (self.process || !requested.process)
+            && (self.native_code || !requested.native_code)
+    }
+
+    /// The ways the requested permissions can be granted, from granting all of
+    /// them to granting none
+    pub fn grants(&self) -> Vec<PluginPermissions> {
+        let filesystems: Vec<FilesystemScope> =
+            if self.filesystem == FilesystemScope::Plugin {
+                vec![FilesystemScope::Plugin]
+            } else {
+                vec![self.filesystem, FilesystemScope::Plugin]
+            };
+        let mut grants = Vec::new();
+        for filesystem in filesystems {
+            for network in [self.network, false] {
+                for process in [self.process, false] {
+                    for native_code in [self.native_code, false] {
+                        let grant = PluginPermissions {
+                            filesystem,
+                            network,
+                            process,
+                            native_code,
+                        };
+                        if !grants.contains(&grant) {
+                            grants.push(grant);
+                        }
+                    }
+                }
+            }
+        }
+        grants
+    }
+
+    /// What the permissions allow, in words
+    pub fn description(&self) -> String {
+        let mut allowed = Vec::new();
+        if self.filesystem != FilesystemScope::Plugin {
+            allowed.push(self.filesystem.description());
+        }
+        if self.network {
+            allowed.push("the network");
+        }
+        if self.process {
+            allowed.push("running programs");
+        }
+        if self.native_code {
+            allowed.push("loading native code");
+        }
+        if allowed.is_empty() {
+            "Nothing but its own files".to_string()
+        } else {
+            let mut description = allowed.join(", ");
+            description[..1].make_ascii_uppercase();
+            description
+        }
+    }
+}
+
+/// A language feature a plugin can provide itself, without starting a
+/// language server
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ProviderKind {
+    Completion,
+    Hover,
+    /// Diagnostics published by the plugin for the documents it's sent
+    Diagnostics,
+    /// Text to insert at the cursor, shown after it until it's accepted, in
+    /// answer to `lapce/inlineSuggestion` requests
+    InlineSuggestion,
+}
+
+/// The answer of a plugin to a `lapce/inlineSuggestion` request, with the text
+/// to suggest inserting at the position
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InlineSuggestionResult {
+    pub text: String,
+}
+
+/// The registration of a provider by a plugin, with the `lapce/registerProvider`
+/// request
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderRegistration {
+    pub kind: ProviderKind,
+    /// The language ids the provider is for, or all of them if empty
+    #[serde(default)]
+    pub languages: Vec<String>,
+    /// The characters which trigger a completion on top of the ones of words
+    #[serde(default)]
+    pub trigger_characters: Vec<String>,
+    /// The results of the providers with a higher priority come first, with
+    /// language servers having a priority of 0
+    #[serde(default)]
+    pub priority: i32,
+}
+
+impl ProviderRegistration {
+    pub fn supports(&self, language_id: Option<&str>) -> bool {
+        match language_id {
+            Some(language_id) => {
+                self.languages.is_empty()
+                    || self.languages.iter().any(|l| l == language_id)
+            }
+            None => true,
+        }
+    }
+}
+
+/// The kinds of the events of the editor a plugin can subscribe to with the
+/// `lapce/subscribe` request
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PluginEventKind {
+    FilesChanged,
+    ActiveEditorChanged,
+    DocumentOpened,
+    DocumentChanged,
+    DocumentSaved,
+}
+
+/// An event of the editor, sent in a `lapce/event` notification to the
+/// plugins subscribed to its kind
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum PluginEvent {
+    /// Files of the workspace were created, removed or renamed outside of
+    /// the editor
+    FilesChanged { changes: Vec<FileChange> },
+    /// Another document is in the active editor, `None` when no editor is
+    ActiveEditorChanged { uri: Option<Url> },
+    /// A document was opened in the editor
+    DocumentOpened { uri: Url },
+    /// A document open in the editor was edited, its text at the version
+    /// being readable with `lapce/readBuffer`
+    DocumentChanged { uri: Url, version: u64 },
+    /// A document open in the editor was saved
+    DocumentSaved { uri: Url },
+}
+
+impl PluginEvent {
+    pub fn kind(&self) -> PluginEventKind {
+        match self {
+            PluginEvent::FilesChanged { .. } => PluginEventKind::FilesChanged,
+            PluginEvent::ActiveEditorChanged { .. } => {
+                PluginEventKind::ActiveEditorChanged
+            }
+            PluginEvent::DocumentOpened { .. } => PluginEventKind::DocumentOpened,
+            PluginEvent::DocumentChanged { .. } => PluginEventKind::DocumentChanged,
+            PluginEvent::DocumentSaved { .. } => PluginEventKind::DocumentSaved,
+        }
+    }
+}
+
+/// A command a plugin registered with `lapce/registerCommand`, listed in the
+/// palette and bound in the keymaps as `run_plugin_command:<command>`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginCommand {
+    /// The name of the command, like `my-plugin.sort-imports`
+    pub command: String,
+    /// What the palette shows for the command
+    pub title: String,
+}
+
+/// What the editor was at when a plugin command was run, sent to the plugin
+/// in the `lapce/executeCommand` request
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginCommandContext {
+    pub command: String,
+    /// The document of the active editor, if there is one
+    pub uri: Option<Url>,
+    pub selections: Vec<Range>,
+    pub cursor: Option<Position>,
+}
+
+/// The commands of the editor, by their names in the keymaps, which run
+/// programs or have the system open something, like the terminals, the tasks
+/// and the remote connections. Only the plugins allowed to run programs can
+/// run these.
+pub const PROCESS_COMMANDS: &[&str] = &[
+    "open_folder",
+    "reveal_active_file_in_file_manager",
+    "open_active_file_folder_in_terminal",
+    "open_settings_directory",
+    "open_logs_directory",
+    "open_proxy_directory",
+    "open_themes_directory",
+    "open_plugins_directory",
+    "open_profiles_directory",
+    "run_plugin_command",
+    "set_workspace_profile",
+    "new_window_tab",
+    "new_terminal_tab",
+    "reload_window",
+    "new_window",
+    "connect_ssh_host",
+    "connect_wsl",
+    "palette.workspace",
+    "palette.run_and_debug",
+    "palette.profile",
+    "palette.run_and_debug_restart",
+    "run_current_file",
+    "run_task",
+    "rerun_last_task",
+    "restart_task",
+    "toggle_terminal_focus",
+    "toggle_terminal_visual",
+    "focus_terminal",
+    "restart_to_update",
+    "install_to_path",
+    "uninstall_from_path",
+];
+
+/// A command of the editor for it to run, by its name in the keymaps
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EditorCommand {
+    pub command: String,
+    #[serde(default)]
+    pub data: Option<Value>,
+}
+
+/// What a plugin command did, returned for the `lapce/executeCommand`
+/// request: the edits to apply, then the commands of the editor to run
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PluginCommandResult {
+    pub edit: Option<WorkspaceEdit>,
+    pub commands: Vec<EditorCommand>,
+}
+
+/// A panel a plugin shows with `lapce/setPanel`, as a section of the plugin
+/// views panel, which the user can move to the side or the bottom like the
+/// other panels. Setting it again with the same id replaces it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginPanel {
+    pub id: String,
+    pub title: String,
+    /// The items of the panel, a list, or a tree where they have children
+    #[serde(default)]
+    pub items: Vec<PluginViewItem>,
+}
+
+/// A row of a plugin panel
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginViewItem {
+    pub label: String,
+    /// Dimmer text after the label
+    #[serde(default)]
+    pub description: Option<String>,
+    /// The command of the editor run when the item is clicked
+    #[serde(default)]
+    pub command: Option<EditorCommand>,
+    #[serde(default)]
+    pub children: Vec<PluginViewItem>,
+    /// Whether the children are shown to begin with
+    #[serde(default)]
+    pub expanded: bool,
+}
+
+impl PluginViewItem {
+    /// The commands of the item and of all its descendants
+    pub fn commands(&self) -> Vec<&EditorCommand> {
+        let mut commands: Vec<&EditorCommand> = self.command.iter().collect();
+        for child in self.children.iter() {
+            commands.extend(child.commands());
+        }
+        commands
+    }
+}
+
+/// A segment of the status bar a plugin shows with `lapce/setStatusItem`.
+/// Setting it again with the same id replaces it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginStatusItem {
+    pub id: String,
+    pub text: String,
+    /// The command of the editor run when the segment is clicked
+    #[serde(default)]
+    pub command: Option<EditorCommand>,
+}
+
+/// The decorations a plugin sets on a document with `lapce/setDecorations`,
+/// replacing the ones it set before with the same key
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginDecorations {
+    pub uri: Url,
+    /// The set of the plugin's decorations these replace, for a plugin to
+    /// keep several apart, like coverage and TODOs
+    pub key: String,
+    /// The version of the document the ranges are in, as sent in
+    /// `textDocument/didChange`. The decorations for another version than
+    /// the document's are dropped.
+    #[serde(default)]
+    pub version: Option<i32>,
+    #[serde(default)]
+    pub decorations: Vec<TextDecoration>,
+}
+
+/// How a range of a document is decorated. The decoration stays on the text
+/// it's on as the document is edited.
+///
+/// Colors are the names of the colors of the theme, like `"lapce.warn"`, or
+/// hex colors like `"#ff000040"`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextDecoration {
+    pub range: Range,
+    /// The color the text of the range is highlighted with
+    #[serde(default)]
+    pub background: Option<String>,
+    /// The color of a line under the text of the range
+    #[serde(default)]
+    pub underline: Option<String>,
+    /// An SVG file shown in the gutter at the first line of the range, by
+    /// its path in the plugin's folder
+    #[serde(default)]
+    pub gutter_icon: Option<PathBuf>,
+    /// Text shown after the end of the range
+    #[serde(default)]
+    pub virtual_text: Option<String>,
+    /// The color of the virtual text
+    #[serde(default)]
+    pub virtual_text_color: Option<String>,
+}
+
+/// The providers registered by the plugins
+#[derive(Clone, Debug, Default)]
+pub struct PluginProviders {
+    providers: HashMap<PluginId, Vec<ProviderRegistration>>,
+}
+
+impl PluginProviders {
+    pub fn register(
+        &mut self,
+        plugin_id: PluginId,
+        registration: ProviderRegistration,
+    ) {
+        self.providers
+            .entry(plugin_id)
+            .or_default()
+            .push(registration);
+    }
+
+    /// The priority of the results of the plugin for the language, which is
+    /// the one of a language server if it has no provider of the kind for it
+    pub fn priority(
+        &self,
+        plugin_id: &PluginId,
+        kind: ProviderKind,
+        language_id: Option<&str>,
+    ) -> i32 {
+        self.providers
+            .get(plugin_id)
+            .into_iter()
+            .flatten()
+            .filter(|p| p.kind == kind && p.supports(language_id))
+            .map(|p| p.priority)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Whether a plugin registered a provider of the kind for the language
+    pub fn has_provider(
+        &self,
+        kind: ProviderKind,
+        language_id: Option<&str>,
+    ) -> bool {
+        self.providers
+            .values()
+            .flatten()
+            .any(|p| p.kind == kind && p.supports(language_id))
+    }
+
+    /// Whether the character typed before a word triggers a completion from a
+    /// provider for the language
+    pub fn is_trigger_character(&self, language_id: Option<&str>, c: &str) -> bool {
+        self.providers.values().flatten().any(|p| {
+            p.kind == ProviderKind::Completion
+                && p.supports(language_id)
+                && p.trigger_characters.iter().any(|t| t == c)
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{VoltID, VoltInfo, VoltMetadata};
+    use super::{
+        EditorCommand, FilesystemScope, PluginCommandResult, PluginDecorations,
+        PluginEvent, PluginEventKind, PluginId, PluginPanel, PluginPermissions,
+        PluginProviders, ProviderKind, ProviderRegistration, VoltID, VoltInfo,
+        VoltMetadata,
+    };
 
     #[test]
     fn test_volt_metadata_id() {
@@ -160,6 +685,9 @@ fn test_volt_metadata_id() {
             dir: std::env::current_dir().unwrap().canonicalize().ok(),
             activation: None,
             config: None,
+            permissions: None,
+            grammars: None,
+            api_version: None,
         };
         let volt_id = VoltID {
             author: "Author".to_string(),
@@ -198,6 +726,9 @@ fn test_volt_metadata_info() {
             dir: std::env::current_dir().unwrap().canonicalize().ok(),
             activation: None,
             config: None,
+            permissions: None,
+            grammars: None,
+            api_version: None,
         };
         let volt_info = VoltInfo {
             name: "plugin".to_string(),
@@ -234,4 +765,178 @@ fn test_volt_info_id() {
         assert_eq!(<&VoltInfo as Into<VoltID>>::into(&volt_info), volt_id);
         assert_eq!(<VoltInfo as Into<VoltID>>::into(volt_info), volt_id);
     }
+
+    #[test]
+    fn test_plugin_providers() {
+        let mut providers = PluginProviders::default();
+        let plugin_id = PluginId(1);
+        providers.register(
+            plugin_id,
+            ProviderRegistration {
+                kind: ProviderKind::Completion,
+                languages: vec!["rust".to_string()],
+                trigger_characters: vec!["#".to_string()],
+                priority: 5,
+            },
+        );
+
+        let priority = |language_id| {
+            providers.priority(&plugin_id, ProviderKind::Completion, language_id)
+        };
+        assert_eq!(priority(Some("rust")), 5);
+        assert_eq!(priority(Some("python")), 0);
+        assert_eq!(
+            providers.priority(&plugin_id, ProviderKind::Hover, Some("rust")),
+            0
+        );
+        assert_eq!(
+            providers.priority(&PluginId(2), ProviderKind::Completion, None),
+            0
+        );
+
+        assert!(providers.is_trigger_character(Some("rust"), "#"));
+        assert!(!providers.is_trigger_character(Some("python"), "#"));
+        assert!(!providers.is_trigger_character(Some("rust"), "@"));
+
+        assert!(providers.has_provider(ProviderKind::Completion, Some("rust")));
+        assert!(!providers.has_provider(ProviderKind::Completion, Some("python")));
+        assert!(!providers.has_provider(ProviderKind::InlineSuggestion, None));
+    }
+
+    #[test]
+    fn test_plugin_permissions() {
+        let requested = PluginPermissions {
+            filesystem: FilesystemScope::Workspace,
+            network: true,
+            process: false,
+            native_code: true,
+        };
+        let grants = requested.grants();
+        assert_eq!(grants.len(), 8);
+        assert_eq!(grants[0], requested);
+        assert_eq!(grants[7], PluginPermissions::default());
+        assert!(grants[0].covers(&requested));
+        assert!(grants.iter().skip(1).all(|grant| !grant.covers(&requested)));
+
+        let home = PluginPermissions {
+            filesystem: FilesystemScope::Home,
+            ..requested.clone()
+        };
+        assert!(home.covers(&requested));
+        assert!(!requested.covers(&home));
+
+        assert_eq!(
+            requested.description(),
+            "The workspace files, the network, loading native code"
+        );
+        assert_eq!(
+            PluginPermissions::default().description(),
+            "Nothing but its own files"
+        );
+    }
+
+    #[test]
+    fn test_plugin_event() {
+        let event = PluginEvent::ActiveEditorChanged { uri: None };
+        assert_eq!(event.kind(), PluginEventKind::ActiveEditorChanged);
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            serde_json::json!({ "kind": "activeEditorChanged", "uri": null })
+        );
+        let event = PluginEvent::DocumentChanged {
+            uri: "file:///a.rs".parse().unwrap(),
+            version: 2,
+        };
+        assert_eq!(event.kind(), PluginEventKind::DocumentChanged);
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            serde_json::json!({
+                "kind": "documentChanged",
+                "uri": "file:///a.rs",
+                "version": 2,
+            })
+        );
+        let kinds: Vec<PluginEventKind> =
+            serde_json::from_str(r#"["filesChanged", "documentSaved"]"#).unwrap();
+        assert_eq!(
+            kinds,
+            vec![
+                PluginEventKind::FilesChanged,
+                PluginEventKind::DocumentSaved
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plugin_command_result() {
+        let result: PluginCommandResult = serde_json::from_str("{}").unwrap();
+        assert_eq!(result, PluginCommandResult::default());
+        let result: PluginCommandResult = serde_json::from_str(
+            r#"{ "commands": [{ "command": "run_task:Build" }] }"#,
+        )
+        .unwrap();
+        assert!(result.edit.is_none());
+        assert_eq!(
+            result.commands,
+            vec![EditorCommand {
+                command: "run_task:Build".to_string(),
+                data: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_plugin_panel() {
+        let panel: PluginPanel = serde_json::from_str(
+            r#"{
+                "id": "todos",
+                "title": "TODOs",
+                "items": [{
+                    "label": "src",
+                    "children": [
+                        { "label": "a", "command": { "command": "open_file" } },
+                        { "label": "b", "description": "2 items" }
+                    ]
+                }]
+            }"#,
+        )
+        .unwrap();
+        let item = &panel.items[0];
+        assert!(!item.expanded);
+        assert!(item.command.is_none());
+        assert_eq!(item.children[1].description.as_deref(), Some("2 items"));
+        let commands: Vec<&str> = item
+            .commands()
+            .into_iter()
+            .map(|cmd| cmd.command.as_str())
+            .collect();
+        assert_eq!(commands, vec!["open_file"]);
+    }
+
+    #[test]
+    fn test_plugin_decorations() {
+        let decorations: PluginDecorations = serde_json::from_str(
+            r##"{
+                "uri": "file:///src/main.rs",
+                "key": "coverage",
+                "decorations": [{
+                    "range": {
+                        "start": { "line": 1, "character": 0 },
+                        "end": { "line": 1, "character": 4 }
+                    },
+                    "background": "#ff000040",
+                    "gutterIcon": "icons/uncovered.svg"
+                }]
+            }"##,
+        )
+        .unwrap();
+        assert!(decorations.version.is_none());
+        let decoration = &decorations.decorations[0];
+        assert_eq!(decoration.background.as_deref(), Some("#ff000040"));
+        assert_eq!(
+            decoration.gutter_icon.as_deref(),
+            Some(std::path::Path::new("icons/uncovered.svg"))
+        );
+        assert!(decoration.virtual_text.is_none());
+    }
 }
@@ -0,0 +1,246 @@
+use serde::{Deserialize, Serialize};
+
+/// The version of the protocol between the editor and the proxy. It goes up
+/// when messages change in a way that a peer of an older version can't read,
+/// while the messages that are only added are behind a [`Capability`].
+pub const PROTOCOL_VERSION: u32 = 2;
+/// The oldest version of the protocol this build can still talk to
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
+
+/// The error code of a request the peer doesn't support, the same as the
+/// "method not found" of JSON-RPC
+pub const UNSUPPORTED_REQUEST: i64 = -32601;
+
+/// The features of the protocol that came after its first version, which a
+/// peer only uses if the other side has them too. Older proxies, such as the
+/// one left on a remote host by an older editor, don't know their messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Capability {
+    /// The changes to the settings of the workspace are sent as they happen
+    ConfigChanges,
+    /// Files that aren't open in the editor can be edited, for replacing
+    /// search results
+    FileEdits,
+    /// The staged changes of a file can be diffed
+    StagedDiff,
+    /// Documents can be formatted, and their imports organized
+    Formatting,
+    /// The language features the plugins provide are registered with the
+    /// editor
+    ProviderRegistration,
+    /// The workspace symbols can be searched in an index of the syntax trees
+    SymbolIndex,
+    /// Edits to several documents can be applied all together, for the
+    /// plugins
+    EditSessions,
+    /// The commits that changed a file can be listed, and the file read as
+    /// of one of them
+    FileHistory,
+    /// The members of archives can be listed like the entries of a folder,
+    /// and extracted to be read
+    Archives,
+    /// The plugins can run the commands of the editor and be told about the
+    /// file in the active editor and the documents opened, edited and saved
+    PluginApi,
+    /// The plugins can register commands for the palette and the keymaps
+    PluginCommands,
+    /// The plugins can show panels and status bar segments
+    PluginViews,
+    /// The plugins can decorate the text of documents
+    PluginDecorations,
+    /// A hunk of the changes of a file can be staged alone
+    StageHunks,
+    /// Paths can be deleted for good, and the ones moved to the trash put
+    /// back
+    PermanentDelete,
+    /// The files the workspace ignores can be listed along with the others
+    IgnoredFiles,
+    /// A capability of a newer version than this one, which is never used
+    #[serde(other)]
+    Unknown,
+}
+
+impl Capability {
+    pub const ALL: &'static [Capability] = &[
+        Capability::ConfigChanges,
+        Capability::FileEdits,
+        Capability::StagedDiff,
+        Capability::Formatting,
+        Capability::ProviderRegistration,
+        Capability::SymbolIndex,
+        Capability::EditSessions,
+        Capability::FileHistory,
+        Capability::Archives,
+        Capability::PluginApi,
+        Capability::PluginCommands,
+        Capability::PluginViews,
+        Capability::PluginDecorations,
+        Capability::StageHunks,
+        Capability::PermanentDelete,
+        Capability::IgnoredFiles,
+    ];
+}
+
+/// What a peer tells the other about the protocol it speaks when they
+/// connect. A peer that doesn't send one is of the first version, before
+/// there were any capabilities.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProtocolInfo {
+    pub version: u32,
+    pub min_version: u32,
+    pub capabilities: Vec<Capability>,
+}
+
+impl Default for ProtocolInfo {
+    fn default() -> Self {
+        Self {
+            version: 1,
+            min_version: 1,
+            capabilities: Vec::new(),
+        }
+    }
+}
+
+impl ProtocolInfo {
+    /// The protocol of this build
+    pub fn current() -> Self {
+        Self {
+            version: PROTOCOL_VERSION,
+            min_version: MIN_PROTOCOL_VERSION,
+            capabilities: Capability::ALL.to_vec(),
+        }
+    }
+
+    /// The protocol both peers can speak: the older of their versions, if
+    /// both still support it, with the capabilities they share
+    pub fn negotiate(&self, peer: &ProtocolInfo) -> Result<Protocol, String> {
+        let version = self.version.min(peer.version);
+        let min_version = self.min_version.max(peer.min_version);
+        if version < min_version {
+            return Err(format!(
+                "protocol version {} isn't supported, the oldest supported is {}",
+                version, min_version
+            ));
+        }
+        let capabilities = self
+            .capabilities
+            .iter()
+            .filter(|c| **c != Capability::Unknown)
+            .filter(|c| peer.capabilities.contains(c))
+            .copied()
+            .collect();
+        Ok(Protocol {
+            version,
+            capabilities,
+        })
+    }
+}
+
+/// The protocol agreed on with the peer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Protocol {
+    pub version: u32,
+    pub capabilities: Vec<Capability>,
+}
+
+impl Default for Protocol {
+    /// Until the peers have negotiated, they're taken to be of the same
+    /// version, which the local proxy always is
+    fn default() -> Self {
+        Self {
+            version: PROTOCOL_VERSION,
+            capabilities: Capability::ALL.to_vec(),
+        }
+    }
+}
+
+impl Protocol {
+    /// The oldest version this build speaks, without any capabilities, for a
+    /// peer the protocol can't be agreed with
+    pub fn oldest() -> Self {
+        Self {
+            version: MIN_PROTOCOL_VERSION,
+            capabilities: Vec::new(),
+        }
+    }
+
+    pub fn supports(&self, capability: Capability) -> bool {
+        self.capabilities.contains(&capability)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiate() {
+        let current = ProtocolInfo::current();
+
+        let protocol = current.negotiate(&ProtocolInfo::current()).unwrap();
+        assert_eq!(protocol, Protocol::default());
+
+        // A proxy from before the protocol was versioned
+        let protocol = current.negotiate(&ProtocolInfo::default()).unwrap();
+        assert_eq!(protocol.version, 1);
+        assert!(!protocol.supports(Capability::SymbolIndex));
+
+        let newer: ProtocolInfo = serde_json::from_value(serde_json::json!({
+            "version": PROTOCOL_VERSION + 1,
+            "min_version": PROTOCOL_VERSION,
+            "capabilities": ["symbol_index", "something_new"],
+        }))
+        .unwrap();
+        let protocol = current.negotiate(&newer).unwrap();
+        assert_eq!(protocol.version, PROTOCOL_VERSION);
+        assert_eq!(protocol.capabilities, vec![Capability::SymbolIndex]);
+
+        let incompatible = ProtocolInfo {
+            version: PROTOCOL_VERSION + 2,
+            min_version: PROTOCOL_VERSION + 1,
+            capabilities: Vec::new(),
+        };
+        assert!(current.negotiate(&incompatible).is_err());
+    }
+
+    #[test]
+    fn test_older_peers() {
+        use crate::{
+            file::FileNodeItem,
+            proxy::{ProxyNotification, ProxyRequest},
+        };
+
+        // Listing the ignored files is only asked of a proxy that can
+        let request: ProxyRequest = serde_json::from_value(serde_json::json!({
+            "method": "get_files",
+            "params": { "path": "." },
+        }))
+        .unwrap();
+        assert_eq!(request.capability(), None);
+        let request = ProxyRequest::GetFiles {
+            path: ".".to_string(),
+            include_ignored: true,
+        };
+        assert_eq!(request.capability(), Some(Capability::IgnoredFiles));
+        let request = ProxyRequest::RestoreTrashedPaths { paths: Vec::new() };
+        assert_eq!(request.capability(), Some(Capability::PermanentDelete));
+        let notification = ProxyNotification::GitStageHunk {
+            path: "a.rs".into(),
+            head_lines: 0..1,
+            lines: String::new(),
+        };
+        assert_eq!(notification.capability(), Some(Capability::StageHunks));
+
+        let item: FileNodeItem = serde_json::from_value(serde_json::json!({
+            "path_buf": "a.rs",
+            "is_dir": false,
+            "read": false,
+            "open": false,
+            "children": {},
+            "children_open_count": 0,
+        }))
+        .unwrap();
+        assert!(!item.is_ignored);
+    }
+}
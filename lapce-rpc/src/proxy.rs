@@ -12,20 +12,25 @@
 use lapce_xi_rope::RopeDelta;
 use lsp_types::{
     request::GotoTypeDefinitionResponse, CodeAction, CodeActionResponse,
-    CompletionItem, Diagnostic, DocumentSymbolResponse, GotoDefinitionResponse,
-    Hover, InlayHint, Location, Position, PrepareRenameResponse, SelectionRange,
-    SymbolInformation, TextDocumentItem, TextEdit, WorkspaceEdit,
+    CompletionItem, Diagnostic, DocumentSymbolResponse, FormattingOptions,
+    GotoDefinitionResponse, Hover, InlayHint, Location, Position,
+    PrepareRenameResponse, Range, SelectionRange, SymbolInformation,
+    TextDocumentItem, TextEdit, WorkspaceEdit,
 };
 use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
 
 use super::plugin::VoltID;
 use crate::{
-    buffer::BufferId,
+    buffer::{BufferId, EncodingFallback},
     dap_types::{DapId, RunDebugConfig, SourceBreakpoint, ThreadId},
     file::{FileNodeItem, PathObject},
-    plugin::{PluginId, VoltInfo, VoltMetadata},
-    source_control::FileDiff,
+    plugin::{
+        PluginCommandContext, PluginCommandResult, PluginId, PluginPermissions,
+        VoltInfo, VoltMetadata,
+    },
+    protocol::{Capability, Protocol, ProtocolInfo, UNSUPPORTED_REQUEST},
+    source_control::{FileCommit, FileDiff},
     style::SemanticStyles,
     terminal::TermId,
     RequestId, RpcError, RpcMessage,
@@ -43,9 +48,87 @@ pub struct SearchMatch {
     pub line: usize,
     pub start: usize,
     pub end: usize,
+    /// The column of the line that `line_content` starts at, since long lines
+    /// only have the part around the match sent over
+    #[serde(default)]
+    pub content_start: usize,
     pub line_content: String,
 }
 
+impl SearchMatch {
+    /// The range of the match in `line_content`
+    pub fn content_range(&self) -> std::ops::Range<usize> {
+        self.start.saturating_sub(self.content_start)
+            ..self.end.saturating_sub(self.content_start)
+    }
+
+    /// The text that was matched
+    pub fn matched(&self) -> &str {
+        self.line_content.get(self.content_range()).unwrap_or("")
+    }
+}
+
+/// A replacement of a [`SearchMatch`], with the same line and columns, which
+/// only applies if the line still has the `original` text there
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchReplacement {
+    pub line: usize,
+    pub start: usize,
+    pub end: usize,
+    pub original: String,
+    pub replacement: String,
+}
+
+/// A replacement of the content of a line from the column `start`, without
+/// its line ending, which only applies if the line still has the `original`
+/// text there
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LineEdit {
+    pub line: usize,
+    /// The column of the line the original text starts at, since the results
+    /// of long lines only have the part around the matches
+    #[serde(default)]
+    pub start: usize,
+    pub original: String,
+    pub replacement: String,
+}
+
+impl LineEdit {
+    /// The range of the content of the line that the edit replaces, if the
+    /// line still has the original text there
+    pub fn range(&self, content: &str) -> Option<std::ops::Range<usize>> {
+        let range = self.start..self.start + self.original.len();
+        (content.get(range.clone()) == Some(self.original.as_str())).then_some(range)
+    }
+
+    /// The text the line has now where the original text was, as long as it
+    /// was, for a line that changed in the meantime
+    pub fn current<'a>(&self, content: &'a str) -> &'a str {
+        let floor = |mut i: usize| {
+            i = i.min(content.len());
+            while !content.is_char_boundary(i) {
+                i -= 1;
+            }
+            i
+        };
+        &content[floor(self.start)..floor(self.start + self.original.len())]
+    }
+}
+
+/// How the network features, such as installing plugins and checking for
+/// updates, connect to the internet
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetworkSettings {
+    /// The url of the proxy for all requests, instead of the one from the
+    /// environment
+    pub proxy: Option<String>,
+    /// The PEM files of the certificates trusted in addition to the ones of the
+    /// platform
+    pub ca_certificates: Vec<PathBuf>,
+    /// Whether all network requests fail without connecting
+    pub offline: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 #[serde(tag = "method", content = "params")]
@@ -63,6 +146,16 @@ pub enum ProxyRequest {
         whole_word: bool,
         is_regex: bool,
     },
+    /// Replace search matches in a file that isn't open in the editor
+    ReplaceInFile {
+        path: PathBuf,
+        replacements: Vec<SearchReplacement>,
+    },
+    /// Edit lines of a file that isn't open in the editor
+    EditLinesInFile {
+        path: PathBuf,
+        edits: Vec<LineEdit>,
+    },
     CompletionResolve {
         plugin_id: PluginId,
         completion_item: Box<CompletionItem>,
@@ -80,6 +173,11 @@ pub enum ProxyRequest {
         buffer_id: BufferId,
         position: Position,
     },
+    /// The text the plugins suggest inserting at the position
+    GetInlineSuggestion {
+        path: PathBuf,
+        position: Position,
+    },
     GetSelectionRange {
         path: PathBuf,
         positions: Vec<Position>,
@@ -87,6 +185,21 @@ pub enum ProxyRequest {
     GitGetRemoteFileUrl {
         file: PathBuf,
     },
+    GitStagedDiff {
+        path: PathBuf,
+    },
+    /// Up to `limit` of the commits that changed the file, the latest first,
+    /// looking from the commit at `start` in the history of HEAD on
+    GitFileHistory {
+        path: PathBuf,
+        start: usize,
+        limit: usize,
+    },
+    /// The content of the file as of the commit
+    GitFileAtRevision {
+        path: PathBuf,
+        revision: String,
+    },
     GetReferences {
         path: PathBuf,
         position: Position,
@@ -121,6 +234,10 @@ pub enum ProxyRequest {
         position: Position,
         diagnostics: Vec<Diagnostic>,
     },
+    GetOrganizeImports {
+        path: PathBuf,
+        range: Range,
+    },
     GetDocumentSymbols {
         path: PathBuf,
     },
@@ -128,19 +245,52 @@ pub enum ProxyRequest {
         /// The search query
         query: String,
     },
+    /// The workspace symbols from the syntax trees of the files, which are
+    /// there before the language servers have started
+    GetIndexedWorkspaceSymbols {
+        query: String,
+    },
     GetDocumentFormatting {
         path: PathBuf,
+        options: FormattingOptions,
+    },
+    GetDocumentRangeFormatting {
+        path: PathBuf,
+        range: Range,
+        options: FormattingOptions,
     },
     GetOpenFilesContent {},
+    /// The text of a file open in the editor, with the changes that aren't
+    /// saved yet
+    GetOpenFileContent {
+        path: PathBuf,
+    },
     GetFiles {
         path: String,
+        /// Whether the files the workspace ignores are listed too
+        #[serde(default)]
+        include_ignored: bool,
     },
     ReadDir {
         path: PathBuf,
     },
+    /// Extract the member of an archive, at a path that goes through the
+    /// archive, to a temporary file and read it
+    ExtractArchiveMember {
+        path: PathBuf,
+    },
+    /// Run a command the plugin registered, in the context of the editor
+    ExecutePluginCommand {
+        plugin_id: PluginId,
+        context: PluginCommandContext,
+    },
     Save {
         rev: u64,
         path: PathBuf,
+        /// The encoding to save the file in, instead of the one it was read in
+        encoding: Option<String>,
+        /// What to do with the characters the encoding can't represent
+        fallback: EncodingFallback,
     },
     SaveBufferAs {
         buffer_id: BufferId,
@@ -157,6 +307,14 @@ pub enum ProxyRequest {
     TrashPath {
         path: PathBuf,
     },
+    /// Delete the path for good rather than moving it to the trash
+    DeletePath {
+        path: PathBuf,
+    },
+    /// Put the paths moved to the trash back where they were
+    RestoreTrashedPaths {
+        paths: Vec<PathBuf>,
+    },
     DuplicatePath {
         existing_path: PathBuf,
         new_path: PathBuf,
@@ -174,8 +332,14 @@ pub enum ProxyNotification {
         workspace: Option<PathBuf>,
         disabled_volts: Vec<VoltID>,
         plugin_configurations: HashMap<String, HashMap<String, serde_json::Value>>,
+        /// The permissions granted to the plugins that were reviewed
+        #[serde(default)]
+        plugin_permissions: Vec<(VoltID, PluginPermissions)>,
         window_id: usize,
         tab_id: usize,
+        /// The protocol the editor speaks
+        #[serde(default)]
+        protocol: ProtocolInfo,
     },
     OpenFileChanged {
         path: PathBuf,
@@ -203,11 +367,18 @@ pub enum ProxyNotification {
     UpdatePluginConfigs {
         configs: HashMap<String, HashMap<String, serde_json::Value>>,
     },
+    UpdateNetworkSettings {
+        settings: NetworkSettings,
+    },
     NewTerminal {
         term_id: TermId,
         cwd: Option<PathBuf>,
         env: Option<HashMap<String, String>>,
         shell: String,
+        /// The program to run instead of the shell, followed by its
+        /// arguments, which are passed to it as they are
+        #[serde(default)]
+        command: Option<Vec<String>>,
     },
     InstallVolt {
         volt: VoltInfo,
@@ -224,6 +395,16 @@ pub enum ProxyNotification {
     EnableVolt {
         volt: VoltInfo,
     },
+    /// Grant permissions to a plugin, restarting it with them
+    GrantVoltPermissions {
+        volt: VoltMetadata,
+        permissions: PluginPermissions,
+    },
+    /// The file in the active editor changed, `None` when no editor is
+    /// active, for the plugins
+    ActiveEditorChanged {
+        path: Option<PathBuf>,
+    },
     GitCommit {
         message: String,
         diffs: Vec<FileDiff>,
@@ -235,6 +416,13 @@ pub enum ProxyNotification {
         files: Vec<PathBuf>,
     },
     GitDiscardWorkspaceChanges {},
+    /// Stage a hunk of the changes of a file: the lines of the file at `HEAD`
+    /// it replaces, and the lines it replaces them with
+    GitStageHunk {
+        path: PathBuf,
+        head_lines: std::ops::Range<usize>,
+        lines: String,
+    },
     GitInit {},
     TerminalWrite {
         term_id: TermId,
@@ -289,8 +477,30 @@ pub enum ProxyResponse {
     GitGetRemoteFileUrl {
         file_url: String,
     },
+    GitStagedDiffResponse {
+        diff: String,
+    },
+    GitFileHistoryResponse {
+        commits: Vec<FileCommit>,
+        /// Where the next commits are looked for from, if the history goes
+        /// on
+        next: Option<usize>,
+    },
+    GitFileAtRevisionResponse {
+        content: String,
+    },
+    /// The content of the archive member
+    ExtractArchiveMemberResponse {
+        content: String,
+    },
+    ExecutePluginCommandResponse {
+        result: PluginCommandResult,
+    },
     NewBufferResponse {
         content: String,
+        /// The name of the encoding the file was read in
+        #[serde(default)]
+        encoding: String,
     },
     BufferHeadResponse {
         version: String,
@@ -309,6 +519,9 @@ pub enum ProxyResponse {
         request_id: usize,
         hover: Hover,
     },
+    InlineSuggestionResponse {
+        text: Option<String>,
+    },
     GetDefinitionResponse {
         request_id: usize,
         definition: GotoDefinitionResponse,
@@ -330,6 +543,9 @@ pub enum ProxyResponse {
     GetDocumentFormatting {
         edits: Vec<TextEdit>,
     },
+    GetDocumentRangeFormatting {
+        edits: Vec<TextEdit>,
+    },
     GetDocumentSymbols {
         resp: DocumentSymbolResponse,
     },
@@ -354,13 +570,72 @@ pub enum ProxyResponse {
     GetOpenFilesContentResponse {
         items: Vec<TextDocumentItem>,
     },
+    GetOpenFileContentResponse {
+        item: TextDocumentItem,
+    },
     GlobalSearchResponse {
         matches: IndexMap<PathBuf, Vec<SearchMatch>>,
     },
+    /// The line and start column of the replacements that were skipped since
+    /// the text they replace changed in the meantime
+    ReplaceInFileResponse {
+        skipped: Vec<(usize, usize)>,
+    },
+    /// The lines that weren't edited since they changed in the meantime, with
+    /// their current content
+    EditLinesInFileResponse {
+        conflicts: Vec<(usize, String)>,
+    },
     Success {},
     SaveResponse {},
 }
 
+impl ProxyRequest {
+    /// The capability the proxy needs to have to handle the request
+    pub fn capability(&self) -> Option<Capability> {
+        match self {
+            ProxyRequest::ReplaceInFile { .. }
+            | ProxyRequest::EditLinesInFile { .. } => Some(Capability::FileEdits),
+            ProxyRequest::GitStagedDiff { .. } => Some(Capability::StagedDiff),
+            ProxyRequest::GitFileHistory { .. }
+            | ProxyRequest::GitFileAtRevision { .. } => {
+                Some(Capability::FileHistory)
+            }
+            ProxyRequest::GetOrganizeImports { .. }
+            | ProxyRequest::GetDocumentFormatting { .. }
+            | ProxyRequest::GetDocumentRangeFormatting { .. } => {
+                Some(Capability::Formatting)
+            }
+            ProxyRequest::GetIndexedWorkspaceSymbols { .. } => {
+                Some(Capability::SymbolIndex)
+            }
+            ProxyRequest::ExtractArchiveMember { .. } => Some(Capability::Archives),
+            ProxyRequest::ExecutePluginCommand { .. } => {
+                Some(Capability::PluginCommands)
+            }
+            ProxyRequest::DeletePath { .. }
+            | ProxyRequest::RestoreTrashedPaths { .. } => {
+                Some(Capability::PermanentDelete)
+            }
+            ProxyRequest::GetFiles {
+                include_ignored: true,
+                ..
+            } => Some(Capability::IgnoredFiles),
+            _ => None,
+        }
+    }
+}
+
+impl ProxyNotification {
+    /// The capability the proxy needs to have to handle the notification
+    pub fn capability(&self) -> Option<Capability> {
+        match self {
+            ProxyNotification::GitStageHunk { .. } => Some(Capability::StageHunks),
+            _ => None,
+        }
+    }
+}
+
 pub type ProxyMessage = RpcMessage<ProxyRequest, ProxyNotification, ProxyResponse>;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -399,6 +674,7 @@ pub struct ProxyRpcHandler {
     rx: Receiver<ProxyRpc>,
     id: Arc<AtomicU64>,
     pending: Arc<Mutex<HashMap<u64, ResponseHandler>>>,
+    protocol: Arc<Mutex<Protocol>>,
 }
 
 impl ProxyRpcHandler {
@@ -409,6 +685,7 @@ pub fn new() -> Self {
             rx,
             id: Arc::new(AtomicU64::new(0)),
             pending: Arc::new(Mutex::new(HashMap::new())),
+            protocol: Arc::new(Mutex::new(Protocol::default())),
         }
     }
 
@@ -436,7 +713,29 @@ pub fn mainloop<H>(&self, handler: &mut H)
         }
     }
 
+    /// Set the protocol negotiated with the proxy
+    pub fn set_protocol(&self, protocol: Protocol) {
+        *self.protocol.lock() = protocol;
+    }
+
+    /// Whether the proxy has the capability, which it may not if it's of an
+    /// older version than the editor
+    pub fn supports(&self, capability: Capability) -> bool {
+        self.protocol.lock().supports(capability)
+    }
+
     fn request_common(&self, request: ProxyRequest, rh: ResponseHandler) {
+        // The proxy can't read the requests it doesn't have the capability for
+        if let Some(capability) = request.capability() {
+            if !self.supports(capability) {
+                rh.invoke(Err(RpcError {
+                    code: UNSUPPORTED_REQUEST,
+                    message: format!("the proxy doesn't support {capability:?}"),
+                }));
+                return;
+            }
+        }
+
         let id = self.id.fetch_add(1, Ordering::Relaxed);
 
         self.pending.lock().insert(id, rh);
@@ -475,6 +774,13 @@ pub fn handle_response(
     }
 
     pub fn notification(&self, notification: ProxyNotification) {
+        // The proxy can't read the notifications it doesn't have the
+        // capability for
+        if let Some(capability) = notification.capability() {
+            if !self.supports(capability) {
+                return;
+            }
+        }
         let _ = self.tx.send(ProxyRpc::Notification(notification));
     }
 
@@ -510,6 +816,21 @@ pub fn enable_volt(&self, volt: VoltInfo) {
         self.notification(ProxyNotification::EnableVolt { volt });
     }
 
+    pub fn grant_volt_permissions(
+        &self,
+        volt: VoltMetadata,
+        permissions: PluginPermissions,
+    ) {
+        self.notification(ProxyNotification::GrantVoltPermissions {
+            volt,
+            permissions,
+        });
+    }
+
+    pub fn active_editor_changed(&self, path: Option<PathBuf>) {
+        self.notification(ProxyNotification::ActiveEditorChanged { path });
+    }
+
     pub fn shutdown(&self) {
         self.notification(ProxyNotification::Shutdown {});
         let _ = self.tx.send(ProxyRpc::Shutdown);
@@ -520,6 +841,7 @@ pub fn initialize(
         workspace: Option<PathBuf>,
         disabled_volts: Vec<VoltID>,
         plugin_configurations: HashMap<String, HashMap<String, serde_json::Value>>,
+        plugin_permissions: Vec<(VoltID, PluginPermissions)>,
         window_id: usize,
         tab_id: usize,
     ) {
@@ -527,8 +849,10 @@ pub fn initialize(
             workspace,
             disabled_volts,
             plugin_configurations,
+            plugin_permissions,
             window_id,
             tab_id,
+            protocol: ProtocolInfo::current(),
         });
     }
 
@@ -566,12 +890,14 @@ pub fn new_terminal(
         cwd: Option<PathBuf>,
         env: Option<HashMap<String, String>>,
         shell: String,
+        command: Option<Vec<String>>,
     ) {
         self.notification(ProxyNotification::NewTerminal {
             term_id,
             cwd,
             env,
             shell,
+            command,
         })
     }
 
@@ -616,6 +942,18 @@ pub fn trash_path(&self, path: PathBuf, f: impl ProxyCallback + 'static) {
         self.request_async(ProxyRequest::TrashPath { path }, f);
     }
 
+    pub fn delete_path(&self, path: PathBuf, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::DeletePath { path }, f);
+    }
+
+    pub fn restore_trashed_paths(
+        &self,
+        paths: Vec<PathBuf>,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::RestoreTrashedPaths { paths }, f);
+    }
+
     pub fn duplicate_path(
         &self,
         existing_path: PathBuf,
@@ -678,14 +1016,60 @@ pub fn global_search(
         );
     }
 
+    pub fn replace_in_file(
+        &self,
+        path: PathBuf,
+        replacements: Vec<SearchReplacement>,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::ReplaceInFile { path, replacements }, f);
+    }
+
+    pub fn edit_lines_in_file(
+        &self,
+        path: PathBuf,
+        edits: Vec<LineEdit>,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::EditLinesInFile { path, edits }, f);
+    }
+
     pub fn save(&self, rev: u64, path: PathBuf, f: impl ProxyCallback + 'static) {
-        self.request_async(ProxyRequest::Save { rev, path }, f);
+        self.request_async(
+            ProxyRequest::Save {
+                rev,
+                path,
+                encoding: None,
+                fallback: EncodingFallback::default(),
+            },
+            f,
+        );
     }
 
-    pub fn get_files(&self, f: impl ProxyCallback + 'static) {
+    pub fn save_with_encoding(
+        &self,
+        rev: u64,
+        path: PathBuf,
+        encoding: String,
+        fallback: EncodingFallback,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::Save {
+                rev,
+                path,
+                encoding: Some(encoding),
+                fallback,
+            },
+            f,
+        );
+    }
+
+    pub fn get_files(&self, include_ignored: bool, f: impl ProxyCallback + 'static) {
         self.request_async(
             ProxyRequest::GetFiles {
                 path: "path".into(),
+                include_ignored,
             },
             f,
         );
@@ -695,10 +1079,37 @@ pub fn get_open_files_content(&self) -> Result<ProxyResponse, RpcError> {
         self.request(ProxyRequest::GetOpenFilesContent {})
     }
 
+    pub fn get_open_file_content(
+        &self,
+        path: PathBuf,
+    ) -> Result<ProxyResponse, RpcError> {
+        self.request(ProxyRequest::GetOpenFileContent { path })
+    }
+
     pub fn read_dir(&self, path: PathBuf, f: impl ProxyCallback + 'static) {
         self.request_async(ProxyRequest::ReadDir { path }, f);
     }
 
+    pub fn extract_archive_member(
+        &self,
+        path: PathBuf,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::ExtractArchiveMember { path }, f);
+    }
+
+    pub fn execute_plugin_command(
+        &self,
+        plugin_id: PluginId,
+        context: PluginCommandContext,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::ExecutePluginCommand { plugin_id, context },
+            f,
+        );
+    }
+
     pub fn completion_resolve(
         &self,
         plugin_id: PluginId,
@@ -746,6 +1157,15 @@ pub fn get_hover(
         );
     }
 
+    pub fn get_inline_suggestion(
+        &self,
+        path: PathBuf,
+        position: Position,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GetInlineSuggestion { path, position }, f);
+    }
+
     pub fn get_definition(
         &self,
         request_id: usize,
@@ -806,12 +1226,39 @@ pub fn get_code_actions(
         );
     }
 
+    pub fn get_organize_imports(
+        &self,
+        path: PathBuf,
+        range: Range,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GetOrganizeImports { path, range }, f);
+    }
+
     pub fn get_document_formatting(
         &self,
         path: PathBuf,
+        options: FormattingOptions,
         f: impl ProxyCallback + 'static,
     ) {
-        self.request_async(ProxyRequest::GetDocumentFormatting { path }, f);
+        self.request_async(ProxyRequest::GetDocumentFormatting { path, options }, f);
+    }
+
+    pub fn get_document_range_formatting(
+        &self,
+        path: PathBuf,
+        range: Range,
+        options: FormattingOptions,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::GetDocumentRangeFormatting {
+                path,
+                range,
+                options,
+            },
+            f,
+        );
     }
 
     pub fn get_semantic_tokens(
@@ -838,6 +1285,14 @@ pub fn get_workspace_symbols(
         self.request_async(ProxyRequest::GetWorkspaceSymbols { query }, f);
     }
 
+    pub fn get_indexed_workspace_symbols(
+        &self,
+        query: String,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GetIndexedWorkspaceSymbols { query }, f);
+    }
+
     pub fn prepare_rename(
         &self,
         path: PathBuf,
@@ -855,6 +1310,30 @@ pub fn git_get_remote_file_url(
         self.request_async(ProxyRequest::GitGetRemoteFileUrl { file }, f);
     }
 
+    /// Get the staged changes of the repository containing `path` as a patch.
+    pub fn git_staged_diff(&self, path: PathBuf, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::GitStagedDiff { path }, f);
+    }
+
+    pub fn git_file_history(
+        &self,
+        path: PathBuf,
+        start: usize,
+        limit: usize,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GitFileHistory { path, start, limit }, f);
+    }
+
+    pub fn git_file_at_revision(
+        &self,
+        path: PathBuf,
+        revision: String,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GitFileAtRevision { path, revision }, f);
+    }
+
     pub fn rename(
         &self,
         path: PathBuf,
@@ -887,6 +1366,10 @@ pub fn update_plugin_configs(
         self.notification(ProxyNotification::UpdatePluginConfigs { configs });
     }
 
+    pub fn update_network_settings(&self, settings: NetworkSettings) {
+        self.notification(ProxyNotification::UpdateNetworkSettings { settings });
+    }
+
     pub fn git_discard_files_changes(&self, files: Vec<PathBuf>) {
         self.notification(ProxyNotification::GitDiscardFilesChanges { files });
     }
@@ -895,6 +1378,19 @@ pub fn git_discard_workspace_changes(&self) {
         self.notification(ProxyNotification::GitDiscardWorkspaceChanges {});
     }
 
+    pub fn git_stage_hunk(
+        &self,
+        path: PathBuf,
+        head_lines: std::ops::Range<usize>,
+        lines: String,
+    ) {
+        self.notification(ProxyNotification::GitStageHunk {
+            path,
+            head_lines,
+            lines,
+        });
+    }
+
     pub fn get_selection_range(
         &self,
         path: PathBuf,
@@ -10,6 +10,23 @@ pub struct DiffInfo {
     pub diffs: Vec<FileDiff>,
 }
 
+/// A commit that changed a file, in its history
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FileCommit {
+    pub id: String,
+    /// The first line of the message
+    pub summary: String,
+    pub author: String,
+    /// When it was committed, in seconds since the Unix epoch
+    pub time: i64,
+}
+
+impl FileCommit {
+    pub fn short_id(&self) -> &str {
+        self.id.get(..7).unwrap_or(&self.id)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum FileDiff {
     Modified(PathBuf),
@@ -1,14 +1,16 @@
 use std::{
     io::{self, BufRead, Write},
+    sync::Arc,
     thread,
 };
 
 use anyhow::Result;
 use crossbeam_channel::{Receiver, Sender};
+use parking_lot::Mutex;
 use serde::{de::DeserializeOwned, Serialize};
 use serde_json::{json, Value};
 
-use crate::{RpcError, RpcMessage, RpcObject};
+use crate::{protocol::UNSUPPORTED_REQUEST, RpcError, RpcMessage, RpcObject};
 
 pub fn stdio_transport<W, R, Req1, Notif1, Resp1, Req2, Notif2, Resp2>(
     mut writer: W,
@@ -25,16 +27,49 @@ pub fn stdio_transport<W, R, Req1, Notif1, Resp1, Req2, Notif2, Resp2>(
     Notif2: 'static + Serialize + DeserializeOwned + Send + Sync,
     Resp2: 'static + Serialize + DeserializeOwned + Send + Sync,
 {
-    thread::spawn(move || {
-        for value in writer_receiver {
-            if write_msg(&mut writer, value).is_err() {
-                return;
-            };
-        }
-    });
+    let writer = Arc::new(Mutex::new(writer));
+    {
+        let writer = writer.clone();
+        thread::spawn(move || {
+            for value in writer_receiver {
+                if write_msg(&mut *writer.lock(), value).is_err() {
+                    return;
+                };
+            }
+        });
+    }
     thread::spawn(move || -> Result<()> {
         loop {
-            let msg = read_msg(&mut reader)?;
+            let object = read_object(&mut reader)?;
+            let id = object.get_id();
+            let is_response = object.is_response();
+            let msg = match parse_msg(object) {
+                Ok(msg) => msg,
+                // A message this side can't read is from a peer of a newer
+                // version, which is no reason to end the connection. The
+                // request gets an error back, and the response is taken as
+                // one, so that nothing waits on them.
+                Err(err) if err.kind() == io::ErrorKind::InvalidData => {
+                    let error = RpcError {
+                        code: UNSUPPORTED_REQUEST,
+                        message: err.to_string(),
+                    };
+                    match id {
+                        Some(id) if is_response => {
+                            reader_sender.send(RpcMessage::Error(id, error))?;
+                        }
+                        Some(id) => {
+                            write_msg::<_, Req2, Notif2, Resp2>(
+                                &mut *writer.lock(),
+                                RpcMessage::Error(id, error),
+                            )?;
+                        }
+                        None => {}
+                    }
+                    continue;
+                }
+                Err(err) => return Err(err.into()),
+            };
             reader_sender.send(msg)?;
         }
     });
@@ -87,10 +122,24 @@ pub fn read_msg<R, Req, Notif, Resp>(
     Notif: DeserializeOwned,
     Resp: DeserializeOwned,
 {
+    parse_msg(read_object(inp)?)
+}
+
+fn read_object<R: BufRead>(inp: &mut R) -> io::Result<RpcObject> {
     let mut buf = String::new();
     let _s = inp.read_line(&mut buf)?;
     let value: Value = serde_json::from_str(&buf)?;
-    let object = RpcObject(value);
+    Ok(RpcObject(value))
+}
+
+fn parse_msg<Req, Notif, Resp>(
+    object: RpcObject,
+) -> io::Result<RpcMessage<Req, Notif, Resp>>
+where
+    Req: DeserializeOwned,
+    Notif: DeserializeOwned,
+    Resp: DeserializeOwned,
+{
     let is_response = object.is_response();
     let msg = if is_response {
         let id = object.get_id().ok_or(io::ErrorKind::NotFound)?;
@@ -0,0 +1,101 @@
+use std::{cmp::Ordering, collections::BinaryHeap, thread, time::Instant};
+
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender};
+use parking_lot::{const_mutex, Mutex};
+
+/// The thread that waits for the deadlines of all the timers, so that a timer
+/// doesn't take a thread sleeping until it's due. It's started with the first
+/// timer.
+static TIMER: Mutex<Option<Sender<Deadline>>> = const_mutex(None);
+
+/// An action to run once the time comes
+struct Deadline {
+    at: Instant,
+    action: Box<dyn FnOnce() + Send>,
+}
+
+impl PartialEq for Deadline {
+    fn eq(&self, other: &Self) -> bool {
+        self.at == other.at
+    }
+}
+
+impl Eq for Deadline {}
+
+impl PartialOrd for Deadline {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Deadline {
+    /// The earliest deadline is the greatest, to be at the top of the heap
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.at.cmp(&self.at)
+    }
+}
+
+/// Run the action on the timer thread at the time. The action should be
+/// quick, since the ones due after it wait for it.
+pub fn schedule(at: Instant, action: impl FnOnce() + Send + 'static) {
+    let mut timer = TIMER.lock();
+    let tx = timer.get_or_insert_with(|| {
+        let (tx, rx) = crossbeam_channel::unbounded();
+        thread::Builder::new()
+            .name("Timer".to_owned())
+            .spawn(move || run_timer(rx))
+            .unwrap();
+        tx
+    });
+    let _ = tx.send(Deadline {
+        at,
+        action: Box::new(action),
+    });
+}
+
+fn run_timer(rx: Receiver<Deadline>) {
+    let mut deadlines = BinaryHeap::new();
+    loop {
+        let now = Instant::now();
+        while deadlines
+            .peek()
+            .map_or(false, |deadline: &Deadline| deadline.at <= now)
+        {
+            if let Some(deadline) = deadlines.pop() {
+                (deadline.action)();
+            }
+        }
+
+        let next = match deadlines.peek() {
+            Some(deadline) => rx.recv_timeout(deadline.at - now),
+            None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        match next {
+            Ok(deadline) => deadlines.push(deadline),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_deadlines_in_order() {
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let now = Instant::now();
+        for (i, ms) in [30, 10, 20].into_iter().enumerate() {
+            let tx = tx.clone();
+            schedule(now + Duration::from_millis(ms), move || {
+                let _ = tx.send(i);
+            });
+        }
+        let order = rx.iter().take(3).collect::<Vec<_>>();
+        assert_eq!(order, vec![1, 2, 0]);
+        assert!(now.elapsed() >= Duration::from_millis(30));
+    }
+}
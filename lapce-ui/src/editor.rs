@@ -415,6 +415,10 @@ pub fn get_size(
     ) -> Size {
         let line_height = data.config.editor.line_height() as f64;
         let width = data.config.editor_char_width(text);
+        let scroll_beyond = data
+            .config
+            .editor
+            .scroll_beyond_last_line_height(editor_size.height);
         match &data.editor.content {
             BufferContent::File(_)
             | BufferContent::Scratch(..)
@@ -426,12 +430,7 @@ pub fn get_size(
                         Size::new(
                             (width * data.doc.buffer().max_len() as f64)
                                 .max(editor_size.width),
-                            if data.config.editor.scroll_beyond_last_line {
-                                (height as f64 - line_height).max(0.0)
-                                    + editor_size.height
-                            } else {
-                                (height as f64).max(editor_size.height)
-                            },
+                            (height as f64 + scroll_beyond).max(editor_size.height),
                         )
                     } else {
                         let height = data.doc.buffer().num_lines()
@@ -439,12 +438,7 @@ pub fn get_size(
                         Size::new(
                             (width * data.doc.buffer().max_len() as f64)
                                 .max(editor_size.width),
-                            if data.config.editor.scroll_beyond_last_line {
-                                (height as f64 - line_height).max(0.0)
-                                    + editor_size.height
-                            } else {
-                                (height as f64).max(editor_size.height)
-                            },
+                            (height as f64 + scroll_beyond).max(editor_size.height),
                         )
                     }
                 } else if let Some(compare) = data.editor.compare.as_ref() {
@@ -463,27 +457,17 @@ pub fn get_size(
                     Size::new(
                         (width * data.doc.buffer().max_len() as f64)
                             .max(editor_size.width),
-                        if data.config.editor.scroll_beyond_last_line {
-                            (line_height * lines as f64 - line_height).max(0.0)
-                                + editor_size.height
-                        } else {
-                            (line_height * lines as f64).max(editor_size.height)
-                        },
+                        (line_height * lines as f64 + scroll_beyond)
+                            .max(editor_size.height),
                     )
                 } else {
                     Size::new(
                         (width * data.doc.buffer().max_len() as f64)
                             .max(data.doc.text_layouts.borrow().max_width)
                             .max(editor_size.width),
-                        if data.config.editor.scroll_beyond_last_line {
-                            (line_height * data.doc.buffer().num_lines() as f64
-                                - line_height)
-                                .max(0.0)
-                                + editor_size.height
-                        } else {
-                            (line_height * data.doc.buffer().num_lines() as f64)
-                                .max(editor_size.height)
-                        },
+                        (line_height * data.doc.buffer().num_lines() as f64
+                            + scroll_beyond)
+                            .max(editor_size.height),
                     )
                 }
             }
@@ -546,14 +546,19 @@ fn cursor_region(data: &LapceEditorBufferData, text: &mut PietText) -> Rect {
             line as f64 * line_height
         };
 
+        let size = *data.editor.size.borrow();
         let surrounding_lines_height =
             (data.config.editor.cursor_surrounding_lines as f64 * line_height)
-                .min(data.editor.size.borrow().height / 2.);
+                .min(size.height / 2.);
+        let surrounding_columns_width =
+            (data.config.editor.cursor_surrounding_columns as f64 * width)
+                .min(size.width / 2.)
+                .max(width);
 
         Rect::ZERO
             .with_size(Size::new(width, line_height))
             .with_origin(Point::new(cursor_x, y))
-            .inflate(width, surrounding_lines_height)
+            .inflate(surrounding_columns_width, surrounding_lines_height)
     }
 }
 
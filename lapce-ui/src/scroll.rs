@@ -15,6 +15,12 @@
 /// scrollbar's primary axis.
 pub const SCROLLBAR_MIN_SIZE: f64 = 45.0;
 
+/// How long the scrollbars stay visible after scrolling or being hovered.
+const SCROLLBAR_FADE_DELAY: Duration = Duration::from_millis(500);
+
+/// How long the scrollbars take to fade out, in milliseconds.
+const SCROLLBAR_FADE_DURATION: f64 = 300.0;
+
 /// Denotes which scrollbar, if any, is currently being hovered over
 /// by the mouse.
 #[derive(Debug, Copy, Clone)]
@@ -365,6 +371,9 @@ struct ScrollComponent {
     pub held: BarHeldState,
     pub fade_start: Option<Instant>,
     pub vertical_scroll_for_horizontal: bool,
+    /// The fraction of a pixel of the wheel deltas not scrolled yet, so that
+    /// the small deltas of trackpads and their momentum add up
+    pub wheel_remainder: Vec2,
 }
 
 impl Default for ScrollComponent {
@@ -376,6 +385,7 @@ fn default() -> Self {
             held: BarHeldState::None,
             fade_start: None,
             vertical_scroll_for_horizontal: false,
+            wheel_remainder: Vec2::ZERO,
         }
     }
 }
@@ -398,9 +408,7 @@ pub fn reset_scrollbar_fade<F>(&mut self, request_timer: F, env: &Env)
     {
         self.opacity = env.get(theme::SCROLLBAR_MAX_OPACITY);
         self.fade_start = None;
-        let fade_delay = 500;
-        let deadline = Duration::from_millis(fade_delay);
-        self.timer_id = request_timer(deadline);
+        self.timer_id = request_timer(SCROLLBAR_FADE_DELAY);
     }
 
     /// Calculates the paint rect of the vertical scrollbar, or `None` if the vertical scrollbar is
@@ -505,18 +513,12 @@ pub fn draw_bars(
             return;
         }
 
-        let brush = ctx.render_ctx.solid_brush(
-            config
-                .get_color_unchecked(LapceTheme::LAPCE_SCROLL_BAR)
-                .clone()
-                .with_alpha(self.opacity),
-        );
-        let border_brush = ctx.render_ctx.solid_brush(
-            config
-                .get_color_unchecked(LapceTheme::LAPCE_SCROLL_BAR)
-                .clone()
-                .with_alpha(self.opacity),
-        );
+        // The opacity fades the color of the theme, keeping its own alpha
+        let color = config.get_color_unchecked(LapceTheme::LAPCE_SCROLL_BAR);
+        let (_, _, _, alpha) = color.as_rgba();
+        let brush = ctx
+            .render_ctx
+            .solid_brush(color.clone().with_alpha(alpha * self.opacity));
 
         let _radius = env.get(theme::SCROLLBAR_RADIUS);
         let edge_width = env.get(theme::SCROLLBAR_EDGE_WIDTH);
@@ -527,7 +529,7 @@ pub fn draw_bars(
             if let Some(bounds) = self.calc_vertical_bar_bounds(port, config, env) {
                 let rect = (bounds - scroll_offset).inset(-edge_width / 2.0);
                 ctx.render_ctx.fill(rect, &brush);
-                ctx.render_ctx.stroke(rect, &border_brush, edge_width);
+                ctx.render_ctx.stroke(rect, &brush, edge_width);
             }
 
             // Horizontal bar
@@ -535,7 +537,7 @@ pub fn draw_bars(
             {
                 let rect = (bounds - scroll_offset).inset(-edge_width / 2.0);
                 ctx.render_ctx.fill(rect, &brush);
-                ctx.render_ctx.stroke(rect, &border_brush, edge_width);
+                ctx.render_ctx.stroke(rect, &brush, edge_width);
             }
         })
     }
@@ -585,6 +587,112 @@ pub fn point_hits_horizontal_bar(
         }
     }
 
+    /// Tests if the specified point is on the track of the vertical scrollbar,
+    /// which spans the whole height of the viewport
+    ///
+    /// Returns false if the vertical scrollbar is not visible
+    pub fn point_hits_vertical_track(
+        &self,
+        port: &Viewport,
+        pos: Point,
+        config: &LapceConfig,
+        env: &Env,
+    ) -> bool {
+        let viewport_size = port.rect.size();
+        let scroll_offset = port.rect.origin().to_vec2();
+
+        if let Some(mut bounds) = self.calc_vertical_bar_bounds(port, config, env) {
+            bounds.x1 = scroll_offset.x + viewport_size.width;
+            bounds.y0 = scroll_offset.y;
+            bounds.y1 = scroll_offset.y + viewport_size.height;
+            bounds.contains(pos)
+        } else {
+            false
+        }
+    }
+
+    /// Tests if the specified point is on the track of the horizontal
+    /// scrollbar, which spans the whole width of the viewport
+    ///
+    /// Returns false if the horizontal scrollbar is not visible
+    pub fn point_hits_horizontal_track(
+        &self,
+        port: &Viewport,
+        pos: Point,
+        config: &LapceConfig,
+        env: &Env,
+    ) -> bool {
+        let viewport_size = port.rect.size();
+        let scroll_offset = port.rect.origin().to_vec2();
+
+        if let Some(mut bounds) = self.calc_horizontal_bar_bounds(port, config, env)
+        {
+            bounds.y1 = scroll_offset.y + viewport_size.height;
+            bounds.x0 = scroll_offset.x;
+            bounds.x1 = scroll_offset.x + viewport_size.width;
+            bounds.contains(pos)
+        } else {
+            false
+        }
+    }
+
+    /// Scrolls for a click on the track of the vertical scrollbar, outside of
+    /// its thumb, at `y` in the viewport
+    fn click_vertical_track(
+        &self,
+        port: &mut Viewport,
+        y: f64,
+        config: &LapceConfig,
+        env: &Env,
+    ) {
+        let scroll_offset = port.rect.origin().to_vec2();
+        let Some(bounds) = self.calc_vertical_bar_bounds(port, config, env) else {
+            return;
+        };
+        let bounds = bounds - scroll_offset;
+        let y = track_click_offset(
+            config.ui.scroll_bar_click_jumps(),
+            y,
+            (bounds.y0, bounds.y1),
+            port.rect.height(),
+            scroll_offset.y,
+            port.rect.height(),
+            port.content_size.height,
+        );
+        port.pan_to(Point::new(scroll_offset.x, y));
+    }
+
+    /// Scrolls for a click on the track of the horizontal scrollbar, outside of
+    /// its thumb, at `x` in the viewport
+    fn click_horizontal_track(
+        &self,
+        port: &mut Viewport,
+        x: f64,
+        config: &LapceConfig,
+        env: &Env,
+    ) {
+        let scroll_offset = port.rect.origin().to_vec2();
+        let Some(bounds) = self.calc_horizontal_bar_bounds(port, config, env) else {
+            return;
+        };
+        let bounds = bounds - scroll_offset;
+        // The track ends before the vertical scrollbar, if there is one
+        let track = match self.calc_vertical_bar_bounds(port, config, env) {
+            Some(vertical) => vertical.x0 - scroll_offset.x,
+            None => port.rect.width(),
+        };
+        let x = track_click_offset(
+            config.ui.scroll_bar_click_jumps(),
+            x,
+            (bounds.x0, bounds.x1),
+            track,
+            scroll_offset.x,
+            port.rect.width(),
+            port.content_size.width,
+        );
+        port.pan_to(Point::new(x, scroll_offset.y));
+    }
+
     /// Checks if the event applies to the scroll behavior, uses it, and marks it handled
     ///
     /// Make sure to call on every event
@@ -603,8 +711,9 @@ pub fn event(
         let scrollbar_is_hovered = match event {
             Event::MouseMove(e) | Event::MouseUp(e) | Event::MouseDown(e) => {
                 let offset_pos = e.pos + scroll_offset;
-                self.point_hits_vertical_bar(port, offset_pos, config, env)
-                    || self.point_hits_horizontal_bar(port, offset_pos, config, env)
+                self.point_hits_vertical_track(port, offset_pos, config, env)
+                    || self
+                        .point_hits_horizontal_track(port, offset_pos, config, env)
             }
             _ => false,
         };
@@ -659,10 +768,11 @@ pub fn event(
             match event {
                 Event::MouseMove(event) => {
                     let offset_pos = event.pos + scroll_offset;
-                    if self.point_hits_vertical_bar(port, offset_pos, config, env) {
+                    if self.point_hits_vertical_track(port, offset_pos, config, env)
+                    {
                         self.hovered = BarHoveredState::Vertical;
                     } else if self
-                        .point_hits_horizontal_bar(port, offset_pos, config, env)
+                        .point_hits_horizontal_track(port, offset_pos, config, env)
                     {
                         self.hovered = BarHoveredState::Horizontal;
                     } else {
@@ -676,45 +786,64 @@ pub fn event(
                 Event::MouseDown(event) => {
                     let pos = event.pos + scroll_offset;
 
-                    if self.point_hits_vertical_bar(port, pos, config, env) {
+                    // Clicking the track scrolls first, after which the thumb
+                    // can be dragged from where it ended up
+                    if self.point_hits_vertical_track(port, pos, config, env) {
+                        if !self.point_hits_vertical_bar(port, pos, config, env) {
+                            self.click_vertical_track(
+                                port,
+                                event.pos.y,
+                                config,
+                                env,
+                            );
+                        }
                         ctx.set_handled();
                         ctx.set_active(true);
                         self.held = BarHeldState::Vertical(
-                            // The bounds must be non-empty, because the point hits the scrollbar.
                             event.pos.y,
-                            scroll_offset,
+                            port.rect.origin().to_vec2(),
                         );
-                    } else if self.point_hits_horizontal_bar(port, pos, config, env)
+                    } else if self
+                        .point_hits_horizontal_track(port, pos, config, env)
                     {
+                        if !self.point_hits_horizontal_bar(port, pos, config, env) {
+                            self.click_horizontal_track(
+                                port,
+                                event.pos.x,
+                                config,
+                                env,
+                            );
+                        }
                         ctx.set_handled();
                         ctx.set_active(true);
                         self.held = BarHeldState::Horizontal(
-                            // The bounds must be non-empty, because the point hits the scrollbar.
                             event.pos.x,
-                            scroll_offset,
+                            port.rect.origin().to_vec2(),
                         );
-                    } else {
                     }
+                    ctx.request_paint();
                 }
                 // if the mouse was downed elsewhere, moved over a scroll bar and released: noop.
                 Event::MouseUp(_) => (),
                 _ => (),
             }
-        } else if !ctx.is_hot() {
+        } else {
+            // The scrollbars fade out when idle, even with the mouse over the
+            // content, unless it's over one of them
             match event {
-                // Event::MouseMove(_) => {
-                //     // if we have just stopped hovering
-                //     if self.hovered.is_hovered() && !scrollbar_is_hovered {
-                //         self.hovered = BarHoveredState::None;
-                //         self.reset_scrollbar_fade(|d| ctx.request_timer(d), env);
-                //     }
-                // }
+                Event::MouseMove(_)
+                    if !matches!(self.hovered, BarHoveredState::None) =>
+                {
+                    // if we have just stopped hovering
+                    self.hovered = BarHoveredState::None;
+                    self.reset_scrollbar_fade(|d| ctx.request_timer(d, None), env);
+                }
                 Event::AnimFrame(_) => {
                     if let Some(start) = self.fade_start {
-                        let _elapsed = start.elapsed().subsec_millis();
-                        let diff = 0.02;
-                        self.opacity = env.get(theme::SCROLLBAR_MAX_OPACITY)
-                            - diff * (start.elapsed().subsec_millis() as f64 / 20.0);
+                        self.opacity = fade_opacity(
+                            env.get(theme::SCROLLBAR_MAX_OPACITY),
+                            start.elapsed(),
+                        );
                         if self.opacity > 0.0 {
                             ctx.request_anim_frame();
                         } else {
@@ -747,11 +876,19 @@ pub fn handle_scroll(
     ) {
         if !ctx.is_handled() {
             if let Event::Wheel(mouse) = event {
-                let mut delta =
-                    mouse.wheel_delta.round() * config.editor.scroll_speed_modifier;
+                let mut delta = mouse.wheel_delta;
+                // A mouse wheel only scrolls vertically, which shift turns into
+                // horizontal scrolling where the platform doesn't already
+                if mouse.mods.shift() && delta.x == 0.0 {
+                    delta = Vec2::new(delta.y, 0.0);
+                }
                 if self.vertical_scroll_for_horizontal && delta.x == 0.0 {
                     delta.x = delta.y;
                 }
+                let delta = whole_pixels(
+                    delta * config.editor.scroll_speed_modifier,
+                    &mut self.wheel_remainder,
+                );
                 if port.pan_by(delta) {}
                 ctx.request_paint();
                 self.reset_scrollbar_fade(|d| ctx.request_timer(d, None), env);
@@ -783,6 +920,54 @@ pub fn lifecycle(
     }
 }
 
+/// The whole pixels to scroll by for a wheel delta, carrying the fraction left
+/// over in `remainder` to the next delta. Scrolling by whole pixels keeps the
+/// text sharp, while carrying the fractions keeps the precise deltas of
+/// trackpads, as their momentum slows down, from being rounded away.
+fn whole_pixels(delta: Vec2, remainder: &mut Vec2) -> Vec2 {
+    // Scrolling back the other way drops what was left of the old direction
+    if delta.x * remainder.x < 0.0 {
+        remainder.x = 0.0;
+    }
+    if delta.y * remainder.y < 0.0 {
+        remainder.y = 0.0;
+    }
+    let total = delta + *remainder;
+    let whole = Vec2::new(total.x.trunc(), total.y.trunc());
+    *remainder = total - whole;
+    whole
+}
+
+/// The opacity of the scrollbars the time after they started fading out
+fn fade_opacity(max_opacity: f64, elapsed: Duration) -> f64 {
+    let progress = elapsed.as_secs_f64() * 1000.0 / SCROLLBAR_FADE_DURATION;
+    max_opacity * (1.0 - progress).max(0.0)
+}
+
+/// The scroll offset along the axis of a scrollbar for a click at `click` on its
+/// track, outside of the thumb spanning `thumb`. Either scrolls by a page
+/// towards the click, or jumps to center the thumb on it.
+fn track_click_offset(
+    jump: bool,
+    click: f64,
+    thumb: (f64, f64),
+    track: f64,
+    offset: f64,
+    viewport: f64,
+    content: f64,
+) -> f64 {
+    let (thumb_start, thumb_end) = thumb;
+    if jump {
+        let length = thumb_end - thumb_start;
+        let percent = (click - length / 2.0) / (track - length).max(1.0);
+        percent.clamp(0.0, 1.0) * (content - viewport)
+    } else if click < thumb_start {
+        offset - viewport
+    } else {
+        offset + viewport
+    }
+}
+
 pub struct LapceScroll<T, W> {
     clip: ClipBox<T, W>,
     scroll_component: ScrollComponent,
@@ -1106,3 +1291,45 @@ fn id(&self) -> Option<WidgetId> {
         Some(self.id)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fade_opacity() {
+        assert_eq!(fade_opacity(0.8, Duration::ZERO), 0.8);
+        assert_eq!(fade_opacity(0.8, Duration::from_millis(150)), 0.4);
+        assert_eq!(fade_opacity(0.8, Duration::from_millis(300)), 0.0);
+        assert_eq!(fade_opacity(0.8, Duration::from_secs(1)), 0.0);
+    }
+
+    #[test]
+    fn test_track_click_offset() {
+        // A 100 high viewport at 200 into 1000 high content, with its thumb
+        // at 20..30 on the track
+        let thumb = (20.0, 30.0);
+        assert_eq!(
+            track_click_offset(false, 80.0, thumb, 100.0, 200.0, 100.0, 1000.0),
+            300.0
+        );
+        assert_eq!(
+            track_click_offset(false, 10.0, thumb, 100.0, 200.0, 100.0, 1000.0),
+            100.0
+        );
+
+        // Jumping centers the thumb on the click, as far as it can go
+        assert_eq!(
+            track_click_offset(true, 50.0, thumb, 100.0, 200.0, 100.0, 1000.0),
+            450.0
+        );
+        assert_eq!(
+            track_click_offset(true, 2.0, thumb, 100.0, 200.0, 100.0, 1000.0),
+            0.0
+        );
+        assert_eq!(
+            track_click_offset(true, 99.0, thumb, 100.0, 200.0, 100.0, 1000.0),
+            900.0
+        );
+    }
+}